@@ -0,0 +1,219 @@
+/* ability.rs
+ *   The player's special ability, a ground slam that hits everything standing on the tiles around them. Once used it
+ *   has to cool down for a number of turns before it can be used again, the turns left are shown in the ui. Beings
+ *   that are hit are knocked back a tile unless they are large.
+ * */
+
+use std::time::Duration;
+
+use bracket_lib::{color::ColorPair, terminal::Point};
+use serde::{Deserialize, Serialize};
+use specs::{Entity, Join, World, WorldExt};
+
+use crate::{
+    being::BeingID,
+    char_c::CH_STRIKE,
+    colors::TEXASROSE,
+    components::{Companion, DeleteCondition, HealthStats, Position, Size, SufferDamage, Viewshed},
+    cooldowns::{start_cooldown, turns_left, CooldownKind, Cooldowns},
+    debug::CLEAR,
+    game_init::PlayerEntity,
+    map::{MapRes, TileEntity},
+    player::PlayerResponse,
+    screen_shake::{ScreenShake, HEAVY_HIT_SHAKE},
+    tile_animation::{AnimationRequest, TileAnimationBuilder},
+    ui::message_log::MessageLog,
+};
+
+/// How hard the ground slam hits and how long it takes to come back, read from the settings when the game starts
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AbilityConfig {
+    /// Damage dealt to everything around the player
+    pub slam_damage: usize,
+    /// Turns after a slam before the player can slam again
+    pub cooldown_turns: u32,
+}
+
+impl Default for AbilityConfig {
+    fn default() -> Self {
+        Self { slam_damage: 3, cooldown_turns: 8 }
+    }
+}
+
+/// The player's ability, the turns until it can be used again are kept in their `Cooldowns`
+#[derive(Default)]
+pub struct GroundSlam {
+    cfg: AbilityConfig,
+}
+
+impl GroundSlam {
+    pub fn new(cfg: AbilityConfig) -> Self {
+        Self { cfg }
+    }
+
+    pub fn cooldown_turns(&self) -> u32 {
+        self.cfg.cooldown_turns
+    }
+}
+
+/// How long the cracks in the ground are shown around the player
+const SLAM_FLASH_TIME: Duration = Duration::from_millis(250);
+
+/// Slams the ground, everything with health on the tiles around the player is hit. Companions are left out
+pub fn try_ground_slam(ecs: &mut World) -> PlayerResponse {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let turns_left = turns_left(ecs.read_storage::<Cooldowns>().get(player_entity), CooldownKind::GroundSlam);
+    if turns_left > 0 {
+        ecs.fetch_mut::<MessageLog>().log(format!("You can slam again in {} turns.", turns_left));
+        return PlayerResponse::Waiting;
+    }
+    let center = match ecs.read_storage::<Position>().get(player_entity) {
+        Some(pos) => *pos,
+        None => return PlayerResponse::Waiting,
+    };
+
+    let around = slam_area(ecs, center);
+    let victims: Vec<Entity> = (
+        &ecs.entities(),
+        &ecs.read_storage::<Position>(),
+        &ecs.read_storage::<HealthStats>(),
+        !&ecs.read_storage::<Companion>(),
+    )
+        .join()
+        .filter(|(e, pos, ..)| *e != player_entity && around.contains(pos))
+        .map(|(e, ..)| e)
+        .collect();
+    {
+        let damage = ecs.read_resource::<GroundSlam>().cfg.slam_damage as i32;
+        let mut suffer_damage = ecs.write_storage::<SufferDamage>();
+        for victim in victims.iter() {
+            SufferDamage::new_damage(&mut suffer_damage, *victim, Some(player_entity), -damage);
+        }
+    }
+    for victim in victims.iter() {
+        knock_back(ecs, *victim, center);
+    }
+
+    let mut anim_builder = ecs.write_resource::<TileAnimationBuilder>();
+    for pos in around {
+        anim_builder.request(AnimationRequest::StaticTile(
+            CH_STRIKE as usize,
+            pos,
+            ColorPair::new(TEXASROSE, CLEAR),
+            DeleteCondition::Timed(SLAM_FLASH_TIME),
+        ));
+    }
+    ecs.write_resource::<ScreenShake>().shake(HEAVY_HIT_SHAKE);
+    let message = match victims.len() {
+        0 => "You slam the ground, but nothing is close enough to hit.".to_string(),
+        hit => format!("You slam the ground, hitting {} around you!", hit),
+    };
+    ecs.fetch_mut::<MessageLog>().log(message);
+    let cooldown_turns = ecs.read_resource::<GroundSlam>().cooldown_turns();
+    start_cooldown(&mut ecs.write_storage::<Cooldowns>(), player_entity, CooldownKind::GroundSlam, cooldown_turns);
+    PlayerResponse::TurnAdvance
+}
+
+/// Pushes a being one tile straight away from `center` if there is room, large beings hold their ground and
+/// anything that isn't a being, like a breakable rock, never moves
+fn knock_back(ecs: &mut World, victim: Entity, center: Position) {
+    let size = ecs.read_storage::<Size>().get(victim).copied().unwrap_or_default();
+    if !ecs.read_storage::<BeingID>().contains(victim) || size.resists_knockback() {
+        return;
+    }
+    let from = match ecs.read_storage::<Position>().get(victim) {
+        Some(pos) => *pos,
+        None => return,
+    };
+    let away = from.to_point() - center.to_point();
+    let to = from.to_point() + Point::new(away.x.signum(), away.y.signum());
+    let mut map = ecs.write_resource::<MapRes>();
+    if !map.0.in_bounds(to) || map.0.is_blocked(&Position::from(to)) {
+        return;
+    }
+    let to = Position::from(to);
+
+    let old_idx = from.to_idx(map.0.width);
+    map.0.tile_entities[old_idx].retain(|tile| tile != &TileEntity::Blocking(victim));
+    let new_idx = to.to_idx(map.0.width);
+    map.0.tile_entities[new_idx].push(TileEntity::Blocking(victim));
+    let _ = ecs.write_storage::<Position>().insert(victim, to);
+    if let Some(view) = ecs.write_storage::<Viewshed>().get_mut(victim) {
+        view.dirty = true;
+    }
+}
+
+/// The tiles next to `center` that are on the map, diagonals included
+fn slam_area(ecs: &World, center: Position) -> Vec<Position> {
+    let map = ecs.read_resource::<MapRes>();
+    (-1..=1)
+        .flat_map(|dx| (-1..=1).map(move |dy| Point::new(dx, dy)))
+        .filter(|delta| *delta != Point::zero())
+        .map(|delta| center.to_point() + delta)
+        .filter(|point| map.0.in_bounds(*point))
+        .map(Position::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::Builder;
+
+    use super::*;
+    use crate::{cooldowns::TickCooldowns, test_harness::TestWorld};
+
+    #[test]
+    fn slams_hit_everything_around_then_cool_down() {
+        let mut world = TestWorld::new();
+        world.ecs.insert(GroundSlam::new(AbilityConfig { slam_damage: 2, cooldown_turns: 2 }));
+        let player = world.player();
+        world.insert(player, Position::new(5, 5));
+        world.insert(player, HealthStats::new(10, 0));
+        let slime = world.spawn("Slime", Position::new(6, 6)).with(HealthStats::new(5, 0)).build();
+        let far_slime = world.spawn("Slime", Position::new(8, 5)).with(HealthStats::new(5, 0)).build();
+        let dog = world
+            .spawn("Dog", Position::new(4, 5))
+            .with(HealthStats::new(5, 0))
+            .with(Companion { follow_distance: 1 })
+            .build();
+
+        assert!(matches!(try_ground_slam(&mut world.ecs), PlayerResponse::TurnAdvance));
+        let hits = world.ecs.read_storage::<SufferDamage>().get(slime).map(|hurt| hurt.amount.clone());
+        assert_eq!(hits, Some(vec![(Some(player), -2)]));
+        for untouched in [far_slime, dog, player] {
+            assert!(!world.has::<SufferDamage>(untouched));
+        }
+
+        assert!(matches!(try_ground_slam(&mut world.ecs), PlayerResponse::Waiting));
+        assert!(world.logged("slam again in 2 turns"));
+        world.run(TickCooldowns);
+        world.run(TickCooldowns);
+        assert!(matches!(try_ground_slam(&mut world.ecs), PlayerResponse::TurnAdvance));
+    }
+
+    #[test]
+    fn slams_knock_back_all_but_large_beings() {
+        let mut world = TestWorld::new();
+        let player = world.player();
+        world.insert(player, Position::new(5, 5));
+        let rabbit = world
+            .spawn("Rabbit", Position::new(6, 5))
+            .with(HealthStats::new(5, 0))
+            .with(BeingID::new(1))
+            .with(Size::Small)
+            .build();
+        let ogre = world
+            .spawn("Ogre", Position::new(4, 5))
+            .with(HealthStats::new(5, 0))
+            .with(BeingID::new(2))
+            .with(Size::Large)
+            .build();
+
+        try_ground_slam(&mut world.ecs);
+        assert_eq!(world.get::<Position>(rabbit), Some(Position::new(7, 5)));
+        assert_eq!(world.get::<Position>(ogre), Some(Position::new(4, 5)));
+        let map = world.ecs.read_resource::<MapRes>();
+        assert!(map.0.is_blocked(&Position::new(7, 5)));
+    }
+}
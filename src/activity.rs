@@ -0,0 +1,164 @@
+//! Activities are things an entity spends a while doing, ie. fishing. While the player has one the game sits in the
+//! `ActivityBound` state and only the activity systems run.
+//!
+//! The lifecycle of an activity:
+//! 1. A system reacting to an action component starts it by inserting an `Activity` on the entity
+//! 2. The kind's system calls `Activity::advance` every frame and does its work each time a step completes
+//! 3. When it is done the kind's system inserts `FinishedActivity`, other systems react to it that frame, ie. to hand
+//!    out what was made
+//! 4. `EndFinishedActivities` removes the activity at the end of the frame
+//!
+//! To add a new kind give `ActivityKind` a variant holding the state the kind needs, write its system following the
+//! steps above, and add the system to `activity_bound_dispatcher` in dispatch.rs. `check_player_activity` works for
+//! every kind.
+//!
+//! Pressing Escape cancels the player's activity: `CancelActivities` unhooks anything that would be rewarded and
+//! finishes the activity early, the kind's systems then clean up the same way they do after a normal finish.
+use std::time::Duration;
+
+use specs::{
+    Component, Entities, Entity, Join, NullStorage, Read, ReadStorage, System, VecStorage, World, WorldExt, Write,
+    WriteStorage,
+};
+
+use crate::{components::FinishedActivity, game_init::PlayerEntity, ui::message_log::MessageLog};
+
+#[derive(Component)]
+#[storage(VecStorage)]
+pub struct Activity {
+    pub kind: ActivityKind,
+    /// Time spent on the current step
+    pub progress: Duration,
+    /// How long each step takes
+    pub duration: Duration,
+}
+
+pub enum ActivityKind {
+    /// Each step is a chance for a bite, `hooked` is set once a fish takes the bait
+    Fishing { attempts_left: usize, hooked: bool },
+    /// A single step of cooking `ingredient` over `station`
+    Cooking { station: Entity, ingredient: Entity },
+}
+
+impl ActivityKind {
+    pub fn label(&self) -> &str {
+        match self {
+            ActivityKind::Fishing { .. } => "fishing",
+            ActivityKind::Cooking { .. } => "cooking",
+        }
+    }
+}
+
+/// The entity wants to stop their activity before it is done
+#[derive(Component, Default)]
+#[storage(NullStorage)]
+pub struct CancelActivity {}
+
+impl Activity {
+    pub fn new(kind: ActivityKind, duration: Duration) -> Self {
+        Self { kind, progress: Duration::ZERO, duration }
+    }
+
+    /// Adds `dt` to the progress, true when a step completed in which case the next step is started
+    pub fn advance(&mut self, dt: Duration) -> bool {
+        self.progress += dt;
+        if self.progress < self.duration {
+            return false;
+        }
+        self.progress = Duration::ZERO;
+        true
+    }
+
+    /// How far through its current step the activity is from 0 to 1, `None` while there is nothing to count down
+    /// ie. once a fish is hooked and the minigame takes over
+    pub fn step_fraction(&self) -> Option<f32> {
+        if let ActivityKind::Fishing { hooked: true, .. } = self.kind {
+            return None;
+        }
+        Some((self.progress.as_secs_f32() / self.duration.as_secs_f32().max(f32::EPSILON)).min(1.0))
+    }
+}
+
+/// True once the player is done with their activity, or if they never had one to begin with
+pub fn check_player_activity(ecs: &World) -> bool {
+    let player_entity = ecs.read_resource::<PlayerEntity>();
+    ecs.read_storage::<FinishedActivity>().contains(player_entity.0)
+        || !ecs.read_storage::<Activity>().contains(player_entity.0)
+}
+
+/// Finishes canceled activities without anything they would have given, runs before the activity systems
+pub struct CancelActivities;
+
+impl<'a> System<'a> for CancelActivities {
+    type SystemData = (
+        WriteStorage<'a, CancelActivity>,
+        WriteStorage<'a, Activity>,
+        WriteStorage<'a, FinishedActivity>,
+        Read<'a, PlayerEntity>,
+        Write<'a, MessageLog>,
+        Entities<'a>,
+    );
+
+    fn run(
+        &mut self,
+        (mut cancels, mut activities, mut finished_activities, player_entity, mut log, entities): Self::SystemData,
+    ) {
+        for (entity, _, activity) in (&entities, &cancels, &mut activities).join() {
+            if finished_activities.contains(entity) {
+                continue;
+            }
+            // a hooked fish is only landed when the activity finishes with it still on the line
+            if let ActivityKind::Fishing { hooked, .. } = &mut activity.kind {
+                *hooked = false;
+            }
+            let _ = finished_activities.insert(entity, FinishedActivity {});
+            if entity == player_entity.0 {
+                log.log(format!("Canceled {}.", activity.kind.label()));
+            }
+        }
+        cancels.clear();
+    }
+}
+
+/// Removes the activities that were finished this frame
+pub struct EndFinishedActivities;
+
+impl<'a> System<'a> for EndFinishedActivities {
+    type SystemData = (WriteStorage<'a, Activity>, ReadStorage<'a, FinishedActivity>, Entities<'a>);
+
+    fn run(&mut self, (mut activities, finished_activities, entities): Self::SystemData) {
+        let finished: Vec<_> = (&entities, &activities, &finished_activities).join().map(|(e, ..)| e).collect();
+        for entity in finished {
+            activities.remove(entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{components::Position, test_harness::TestWorld};
+
+    #[test]
+    fn canceling_finishes_the_activity_without_the_catch() {
+        let mut world = TestWorld::new();
+        let player = world.player();
+        let fishing = ActivityKind::Fishing { attempts_left: 3, hooked: true };
+        world.insert(player, Activity::new(fishing, Duration::from_secs(1)));
+        world.insert(player, CancelActivity {});
+        let bystander = world.spawn("Fisher", Position::new(1, 1)).build();
+        world
+            .insert(bystander, Activity::new(ActivityKind::Fishing { attempts_left: 3, hooked: true }, Duration::ZERO));
+
+        world.run(CancelActivities);
+        assert!(world.has::<FinishedActivity>(player));
+        assert!(!world.has::<CancelActivity>(player));
+        assert!(world.logged("Canceled fishing."));
+        let activities = world.ecs.read_storage::<Activity>();
+        let kind = activities.get(player).map(|activity| &activity.kind);
+        assert!(matches!(kind, Some(ActivityKind::Fishing { hooked: false, .. })));
+        drop(activities);
+        assert!(!world.has::<FinishedActivity>(bystander));
+        assert!(check_player_activity(&world.ecs));
+    }
+}
@@ -0,0 +1,196 @@
+/* auto_attack.rs
+ *   With auto attack on, bumping into a hostile monster keeps the player swinging at it turn after turn without
+ *   another key press. A turn is only spent waiting while the player's attack is still cooling down, and any key the
+ *   player presses takes back control. The fight stops on its own once the target dies or is no longer next to them.
+ * */
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use specs::{Entity, World, WorldExt};
+
+use crate::{
+    components::{AttackAction, HealthStats, Monster, Name, Position},
+    cooldowns::{turns_left, CooldownKind, Cooldowns},
+    factions::{are_hostile, Faction},
+    game_init::PlayerEntity,
+    map::distance,
+    player::{Player, PlayerResponse},
+    time::RealDeltaTime,
+    ui::message_log::MessageLog,
+};
+
+/// Whether bumping into a monster starts auto attacking and how quickly the swings come, read from the settings when
+/// the game starts
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AutoAttackConfig {
+    pub enabled: bool,
+    /// Time between each automatic turn so the fight can be followed
+    pub turn_delay_ms: u64,
+}
+
+impl Default for AutoAttackConfig {
+    fn default() -> Self {
+        Self { enabled: false, turn_delay_ms: 250 }
+    }
+}
+
+/// The monster the player is auto attacking, if any, and how long until the next automatic turn
+#[derive(Default)]
+pub struct AutoAttack {
+    cfg: AutoAttackConfig,
+    target: Option<Entity>,
+    wait: Duration,
+}
+
+impl AutoAttack {
+    pub fn new(cfg: AutoAttackConfig) -> Self {
+        Self { cfg, target: None, wait: Duration::ZERO }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.cfg.enabled
+    }
+
+    /// Hands control back to the player
+    pub fn stop(&mut self) {
+        self.target = None;
+    }
+}
+
+/// Starts auto attacking `target` after the player bumped into it, only hostile monsters are fought this way
+pub fn start_auto_attack(ecs: &World, target: Entity) {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let mut auto_attack = ecs.write_resource::<AutoAttack>();
+    if !auto_attack.cfg.enabled {
+        return;
+    }
+    let factions = ecs.read_storage::<Faction>();
+    let players = ecs.read_storage::<Player>();
+    let monsters = ecs.read_storage::<Monster>();
+    let hostile = match (
+        Faction::of(player_entity, &factions, &players, &monsters),
+        Faction::of(target, &factions, &players, &monsters),
+    ) {
+        (Some(ours), Some(theirs)) => monsters.contains(target) && are_hostile(ours, theirs),
+        _ => false,
+    };
+    if hostile {
+        auto_attack.target = Some(target);
+        auto_attack.wait = Duration::from_millis(auto_attack.cfg.turn_delay_ms);
+    }
+}
+
+/// Takes the player's next automatic turn once the delay is up, a swing at the target or a wait while their attack
+/// cools down. Stops auto attacking once the target is dead or out of reach
+pub fn auto_attack_turn(ecs: &mut World) -> PlayerResponse {
+    let target = match ecs.read_resource::<AutoAttack>().target {
+        Some(target) => target,
+        None => return PlayerResponse::Waiting,
+    };
+    // its death is already in the log so there is nothing more to say
+    let dead =
+        !ecs.is_alive(target) || ecs.read_storage::<HealthStats>().get(target).map_or(true, |stats| stats.hp == 0);
+    let out_of_reach = if dead { None } else { out_of_reach(ecs, target) };
+    if dead || out_of_reach.is_some() {
+        if let Some(reason) = out_of_reach {
+            ecs.fetch_mut::<MessageLog>().log(reason);
+        }
+        ecs.write_resource::<AutoAttack>().stop();
+        return PlayerResponse::Waiting;
+    }
+
+    {
+        let dt = ecs.read_resource::<RealDeltaTime>().0;
+        let mut auto_attack = ecs.write_resource::<AutoAttack>();
+        auto_attack.wait = auto_attack.wait.saturating_sub(dt);
+        if !auto_attack.wait.is_zero() {
+            return PlayerResponse::Waiting;
+        }
+        auto_attack.wait = Duration::from_millis(auto_attack.cfg.turn_delay_ms);
+    }
+
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    if ready_to_swing(ecs.read_storage::<Cooldowns>().get(player_entity)) {
+        let _ = ecs.write_storage::<AttackAction>().insert(player_entity, AttackAction { target });
+    }
+    PlayerResponse::TurnAdvance
+}
+
+/// The cooldown ticks down at the start of the turn, before the swing, so the last turn of it can already be swung on
+fn ready_to_swing(cooldowns: Option<&Cooldowns>) -> bool {
+    turns_left(cooldowns, CooldownKind::Attack) <= 1
+}
+
+/// What to tell the player when `target` is no longer right next to them
+fn out_of_reach(ecs: &World, target: Entity) -> Option<String> {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let positions = ecs.read_storage::<Position>();
+    let adjacent = match (positions.get(player_entity), positions.get(target)) {
+        (Some(player_pos), Some(target_pos)) => distance(player_pos, target_pos) == 1,
+        _ => false,
+    };
+    if adjacent {
+        return None;
+    }
+    let name = ecs.read_storage::<Name>().get(target).map_or("monster".to_string(), |name| name.0.clone());
+    Some(format!("You stop attacking, the {} is out of reach.", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::Builder;
+
+    use super::*;
+    use crate::{
+        components::{Interactor, InteractorMode},
+        data_read::prelude::FACTION_DB,
+        test_harness::TestWorld,
+    };
+
+    fn duel() -> (TestWorld, Entity, Entity) {
+        FACTION_DB.lock().unwrap().load();
+        let mut world = TestWorld::new();
+        world.ecs.insert(AutoAttack::new(AutoAttackConfig { enabled: true, turn_delay_ms: 0 }));
+        let player = world.player();
+        world.insert(player, Position::new(4, 4));
+        let orc = world
+            .spawn("Orc", Position::new(5, 4))
+            .with(Monster::new(3, 6))
+            .with(Interactor::new(InteractorMode::Agressive))
+            .with(HealthStats::new(10, 0))
+            .build();
+        start_auto_attack(&world.ecs, orc);
+        (world, player, orc)
+    }
+
+    #[test]
+    fn auto_attack_swings_each_turn_and_waits_out_cooldowns() {
+        let (mut world, player, orc) = duel();
+        assert!(matches!(auto_attack_turn(&mut world.ecs), PlayerResponse::TurnAdvance));
+        assert_eq!(world.ecs.read_storage::<AttackAction>().get(player).map(|action| action.target), Some(orc));
+
+        world.ecs.write_storage::<AttackAction>().clear();
+        let mut cooldowns = Cooldowns::default();
+        cooldowns.start(CooldownKind::Attack, 2);
+        world.insert(player, cooldowns);
+        assert!(matches!(auto_attack_turn(&mut world.ecs), PlayerResponse::TurnAdvance));
+        assert!(!world.has::<AttackAction>(player));
+    }
+
+    #[test]
+    fn auto_attack_stops_when_the_target_gets_away_or_dies() {
+        let (mut world, _, orc) = duel();
+        world.insert(orc, Position::new(7, 4));
+        assert!(matches!(auto_attack_turn(&mut world.ecs), PlayerResponse::Waiting));
+        assert!(world.logged("out of reach"));
+        world.insert(orc, Position::new(5, 4));
+        assert!(matches!(auto_attack_turn(&mut world.ecs), PlayerResponse::Waiting));
+
+        start_auto_attack(&world.ecs, orc);
+        world.ecs.delete_entity(orc).unwrap();
+        assert!(matches!(auto_attack_turn(&mut world.ecs), PlayerResponse::Waiting));
+        assert_eq!(world.ecs.read_resource::<AutoAttack>().target, None);
+    }
+}
@@ -0,0 +1,128 @@
+/* autosave.rs
+ *   Saves the world on its own every few turns and when the player leaves the game. Auto-saves rotate through a few
+ *   slots next to the world's own save so one bad state can't overwrite every backup.
+ * */
+
+use std::{fs, time::SystemTime};
+
+use log::info;
+use specs::{World, WorldExt};
+
+use crate::{
+    saveload::{save_game_as, SAVE_EXTENSION, SAVE_PATH},
+    saveload_menu::LoadedWorld,
+    ui::message_log::MessageLog,
+    TurnCounter,
+};
+
+/// When to auto-save and how many slots to rotate through
+#[derive(Default)]
+pub struct AutoSave {
+    /// Turns between auto-saves, zero turns the interval off
+    every_turns: usize,
+    slots: usize,
+    last_turn: usize,
+}
+
+impl AutoSave {
+    pub fn new(every_turns: usize, slots: usize) -> Self {
+        Self { every_turns, slots, last_turn: 0 }
+    }
+
+    /// Whether enough turns have passed since the last auto-save, a due save is counted as done
+    pub fn due(&mut self, turn: usize) -> bool {
+        if self.every_turns == 0 || self.slots == 0 || turn.saturating_sub(self.last_turn) < self.every_turns {
+            return false;
+        }
+        self.last_turn = turn;
+        true
+    }
+
+    /// The turn counter starts over with each world
+    pub fn reset(&mut self) {
+        self.last_turn = 0;
+    }
+}
+
+/// The world's name without the extension or the slot if it was loaded from an auto-save, so loading one keeps
+/// rotating through the same slots
+fn world_stem(file_name: &str) -> &str {
+    let stem = file_name.strip_suffix(&format!(".{SAVE_EXTENSION}")).unwrap_or(file_name);
+    match stem.rsplit_once(".auto") {
+        Some((world, slot)) if !slot.is_empty() && slot.chars().all(|c| c.is_ascii_digit()) => world,
+        _ => stem,
+    }
+}
+
+fn slot_file_name(stem: &str, slot: usize) -> String {
+    format!("{stem}.auto{slot}.{SAVE_EXTENSION}")
+}
+
+/// An empty slot if there is one, otherwise the one written longest ago
+fn next_slot(modified: &[Option<SystemTime>]) -> usize {
+    modified
+        .iter()
+        .position(Option::is_none)
+        .or_else(|| modified.iter().enumerate().min_by_key(|(_, time)| *time).map(|(slot, _)| slot))
+        .unwrap_or(0)
+}
+
+/// Writes an auto-save to the next slot for the loaded world
+pub fn auto_save(ecs: &mut World) {
+    let slots = ecs.read_resource::<AutoSave>().slots;
+    if slots == 0 {
+        return;
+    }
+    let file_name = ecs.read_resource::<LoadedWorld>().file_name.clone();
+    let stem = world_stem(file_name.as_deref().unwrap_or("default")).to_string();
+    let modified: Vec<_> = (0..slots)
+        .map(|slot| {
+            let path = format!("{}{}", SAVE_PATH, slot_file_name(&stem, slot));
+            fs::metadata(path).and_then(|meta| meta.modified()).ok()
+        })
+        .collect();
+    let slot_name = slot_file_name(&stem, next_slot(&modified));
+
+    save_game_as(ecs, &slot_name);
+    info!("Auto-saved to {}", slot_name);
+    ecs.write_resource::<MessageLog>().log("Auto-saved.");
+}
+
+/// Checked once the turn's systems have run so the world is saved between turns
+pub fn auto_save_if_due(ecs: &mut World) {
+    let turn = ecs.read_resource::<TurnCounter>().0;
+    if ecs.write_resource::<AutoSave>().due(turn) {
+        auto_save(ecs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn saves_every_interval_and_not_when_off() {
+        let mut autosave = AutoSave::new(10, 3);
+        assert!(!autosave.due(9));
+        assert!(autosave.due(10));
+        assert!(!autosave.due(15));
+        assert!(autosave.due(21));
+
+        let mut off = AutoSave::new(0, 3);
+        assert!(!off.due(100));
+    }
+
+    #[test]
+    fn slots_fill_then_rotate_oldest_first() {
+        let now = SystemTime::now();
+        let earlier = now - Duration::from_secs(60);
+        assert_eq!(next_slot(&[Some(now), None, None]), 1);
+        assert_eq!(next_slot(&[Some(now), Some(earlier), Some(now)]), 1);
+
+        assert_eq!(world_stem("forest.auto2.edo"), "forest");
+        assert_eq!(world_stem("forest.edo"), "forest");
+        assert_eq!(world_stem("auto.edo"), "auto");
+    }
+}
@@ -1,22 +1,35 @@
+use std::{collections::HashSet, fmt, time::Duration};
+
 use bracket_lib::random::RandomNumberGenerator;
 use bracket_lib::terminal::Point;
 use log::{info, warn};
 use pathfinding::prelude::astar;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 #[allow(deprecated)] // specs's saveload requires this import so it's not our choice
 use specs::error::NoError;
 use specs::{
     saveload::{ConvertSaveload, Marker},
-    shred::PanicHandler,
-    Component, ConvertSaveload, Entities, Entity, Join, ReadExpect, ReadStorage, System, VecStorage, Write,
-    WriteExpect, WriteStorage,
+    Component, ConvertSaveload, Entities, Entity, Join, Read, ReadExpect, ReadStorage, System, VecStorage, WriteExpect,
+    WriteStorage,
 };
 
 use crate::{
-    components::{AttackAction, BreakAction, GoalMoverAI, MoveAction, Name, Position, RandomWalkerAI},
-    data_read::ENTITY_DB,
+    collision::{Collision, CollisionData},
+    components::{
+        ActionPoints, AggroIndicator, Alert, AttackAction, BreakAction, Companion, Flying, GoalMoverAI, IdleAnimation,
+        Interactor, InteractorMode, Monster, MoveAction, Name, Phasing, Position, Quips, RandomWalkerAI, Size,
+        SufferDamage, Tags, Viewshed,
+    },
+    cooldowns::{off_cooldown, start_cooldown, CooldownKind, Cooldowns},
+    data_read::{deserialize_id, ENTITY_DB},
     droptables::Drops,
-    map::{distance, is_goal, successors, MapRes, TileEntity},
+    factions::{are_hostile, Faction},
+    game_init::PlayerEntity,
+    items::ItemID,
+    map::{distance, is_goal, MapRes, TileEntity},
+    player::Player,
+    special_attacks::{SpecialAbility, SpecialAttack},
+    speed::{initiative_order, is_ready},
     stats::Stats,
     ui::message_log::MessageLog,
 };
@@ -26,11 +39,67 @@ pub struct Being {
     pub(crate) name: String,
     pub(crate) ai: Option<AIDefinition>,
     pub(crate) is_blocking: bool,
-    pub(crate) atlas_index: u8,
+    pub(crate) atlas_index: usize,
     pub(crate) fg: (u8, u8, u8),
     pub(crate) quips: Option<Vec<String>>,
+    pub(crate) dialogue: Option<Vec<String>>,
+    pub(crate) examine_text: Option<String>,
+    /// Items the being sells when it is a shopkeeper
+    pub(crate) shop: Option<Vec<ItemID>>,
     pub(crate) stats: Stats,
     pub(crate) loot: Option<Drops>,
+    pub(crate) idle: Option<IdleDefinition>,
+    pub(crate) speed: u32,
+    pub(crate) initiative: u32,
+    pub(crate) attack_cooldown: u32,
+    pub(crate) movement: MovementDefinition,
+    pub(crate) tags: Option<Tags>,
+    pub(crate) faction: Option<Faction>,
+    pub(crate) special: Option<SpecialAbility>,
+    pub(crate) size: Size,
+}
+
+impl Being {
+    /// What the being says when talked to, beings without their own dialogue fall back to their quips
+    pub fn dialogue_lines(&self) -> Option<&Vec<String>> {
+        self.dialogue.as_ref().or(self.quips.as_ref())
+    }
+}
+
+/// A looping set of atlas frames a being cycles through while idle
+#[derive(Deserialize, Clone)]
+pub struct IdleDefinition {
+    pub(crate) frames: Vec<usize>,
+    /// How long each frame is shown in milliseconds
+    pub(crate) frame_ms: u64,
+    #[serde(default)]
+    pub(crate) phase: IdlePhase,
+}
+
+/// Whether beings of the same kind animate in step or each start somewhere random in the loop
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum IdlePhase {
+    #[default]
+    Sync,
+    Random,
+}
+
+impl IdleDefinition {
+    pub fn to_component(&self) -> IdleAnimation {
+        let offset = match self.phase {
+            IdlePhase::Sync => 0,
+            IdlePhase::Random => {
+                let cycle_ms = self.frame_ms * self.frames.len() as u64;
+                RandomNumberGenerator::new().range(0, cycle_ms.max(1))
+            }
+        };
+        IdleAnimation {
+            frames: self.frames.clone(),
+            frame_time: Duration::from_millis(self.frame_ms),
+            offset: Duration::from_millis(offset),
+        }
+    }
 }
 
 #[derive(Deserialize, Clone)]
@@ -38,11 +107,84 @@ pub struct AIDefinition {
     pub(crate) start_mode: String,
     pub(crate) goals: Option<Vec<String>>,
     pub(crate) goal_range: Option<usize>,
+    pub(crate) follow_distance: Option<usize>,
+    pub(crate) aggro_range: Option<u32>,
+    pub(crate) give_up_range: Option<u32>,
 }
 
-#[derive(Debug, Copy, Clone, Deserialize, ConvertSaveload, Component)]
+/// Obstacles the being can get past, see `collision.rs`
+#[derive(Deserialize, Clone, Default)]
+pub struct MovementDefinition {
+    /// Flies over deep water
+    #[serde(default)]
+    pub(crate) flying: bool,
+    /// Drifts through walls and other terrain
+    #[serde(default)]
+    pub(crate) phasing: bool,
+    /// Whether the player is stopped by a phasing being, beings that aren't phasing always block the player
+    #[serde(default = "default_blocks_player")]
+    pub(crate) blocks_player: bool,
+}
+
+fn default_blocks_player() -> bool {
+    true
+}
+
+impl MovementDefinition {
+    pub fn flying(&self) -> Option<Flying> {
+        self.flying.then_some(Flying {})
+    }
+
+    pub fn phasing(&self) -> Option<Phasing> {
+        self.phasing.then_some(Phasing { blocks_player: self.blocks_player })
+    }
+}
+
+/// Monsters notice the player this close when their definition doesn't say otherwise
+pub const DEFAULT_AGGRO_RANGE: u32 = 8;
+/// Monsters stop chasing once the player is this far when their definition doesn't say otherwise
+pub const DEFAULT_GIVE_UP_RANGE: u32 = 14;
+
+impl AIDefinition {
+    /// The monster component for a `hostile` ai
+    pub fn monster(&self) -> Monster {
+        Monster::new(
+            self.aggro_range.unwrap_or(DEFAULT_AGGRO_RANGE),
+            self.give_up_range.unwrap_or(DEFAULT_GIVE_UP_RANGE),
+        )
+    }
+}
+
+/// Unique id of a being's static data in the raws
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, PartialOrd, Ord, ConvertSaveload, Component)]
 #[storage(VecStorage)]
-pub struct BeingID(pub u32);
+pub struct BeingID(u32);
+
+impl BeingID {
+    pub const fn new(id: u32) -> Self {
+        Self(id)
+    }
+
+    pub const fn value(self) -> u32 {
+        self.0
+    }
+}
+
+impl<'de> Deserialize<'de> for BeingID {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_id(deserializer, "being").map(Self)
+    }
+}
+
+impl fmt::Display for BeingID {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let edb = &ENTITY_DB.lock().unwrap();
+        match edb.beings.get_by_id(*self) {
+            Some(info) => write!(f, "id: {} | name: {}", self.0, info.name),
+            None => write!(f, "id: {} | name: Missing name {}", self.0, self.0),
+        }
+    }
+}
 
 /// Mainly used for early testing but it's somewhat useful
 /// Random Cardinal Directional Movement or RCDM for short
@@ -53,10 +195,11 @@ impl<'a> System<'a> for RandomMonsterMovementSystem {
         WriteStorage<'a, Position>,
         WriteStorage<'a, BreakAction>,
         WriteStorage<'a, MoveAction>,
-        ReadStorage<'a, Name>,
         ReadStorage<'a, RandomWalkerAI>,
-        WriteExpect<'a, MessageLog>,
+        ReadStorage<'a, Monster>,
+        ReadStorage<'a, ActionPoints>,
         ReadExpect<'a, MapRes>,
+        CollisionData<'a>,
         Entities<'a>,
     );
 
@@ -66,25 +209,29 @@ impl<'a> System<'a> for RandomMonsterMovementSystem {
             mut positions,
             mut break_actions,
             mut move_actions,
-            names,
             randwalks,
-            mut log,
+            monsters,
+            action_points,
             map,
+            collision_data,
             entities,
         ): Self::SystemData,
     ) {
+        let collision = Collision::new(&collision_data);
         let mut rng = RandomNumberGenerator::new();
-        for (entity, pos, name, _) in (&entities, &mut positions, &names, &randwalks).join() {
+        for (entity, pos, _) in (&entities, &mut positions, &randwalks).join() {
+            if !is_ready(action_points.get(entity))
+                || monsters.get(entity).is_some_and(Monster::chasing)
+                || move_actions.contains(entity)
+            {
+                continue;
+            }
             let delta: Point = match rng.range::<u32>(0, 100) {
                 0..=10 => Point::new(1, 0),
                 11..=20 => Point::new(0, 1),
                 21..=30 => Point::new(0, -1),
                 31..=40 => Point::new(-1, 0),
-                41..=97 => {
-                    continue;
-                }
-                98..=99 => {
-                    say_random_quip(name, &mut log);
+                41..=99 => {
                     continue;
                 }
                 _ => unreachable!("rng.range(0, 100) should have range of "),
@@ -95,7 +242,11 @@ impl<'a> System<'a> for RandomMonsterMovementSystem {
                 return;
             }
 
-            if let Some(tile) = map.0.first_entity_in_pos(&Position::from(target_pos)) {
+            let passes = collision.passes(entity);
+            if collision.too_narrow(&map.0, &Position::from(target_pos), passes) {
+                continue;
+            }
+            if let Some(tile) = collision.first_in_way(&map.0, &Position::from(target_pos), passes) {
                 match tile {
                     TileEntity::Item(_) => {}
                     TileEntity::Breakable(target) => {
@@ -114,11 +265,129 @@ impl<'a> System<'a> for RandomMonsterMovementSystem {
     }
 }
 
-fn say_random_quip(name: &Name, log: &mut Write<MessageLog, PanicHandler>) {
-    let edb = &ENTITY_DB.lock().unwrap();
-    if let Some(monster) = edb.beings.get_by_name(&name.0) {
-        if let Some(quip) = monster.quips.as_ref().and_then(|quips| quips.first()) {
-            log.enhance(quip)
+/// Wandering beings run from anything hostile to them that comes this close
+const FLEE_RANGE: u32 = 4;
+
+/// Has wandering beings with a faction run from the nearest being that is hostile toward them, they go back to
+/// wandering once nothing is close
+pub struct FleeAI;
+
+impl<'a> System<'a> for FleeAI {
+    type SystemData = (
+        WriteStorage<'a, MoveAction>,
+        ReadStorage<'a, RandomWalkerAI>,
+        ReadStorage<'a, Monster>,
+        ReadStorage<'a, Faction>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, ActionPoints>,
+        ReadExpect<'a, MapRes>,
+        CollisionData<'a>,
+        Entities<'a>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut move_actions,
+            randwalks,
+            monsters,
+            factions,
+            players,
+            positions,
+            action_points,
+            map,
+            collision_data,
+            entities,
+        ): Self::SystemData,
+    ) {
+        let collision = Collision::new(&collision_data);
+        let sided: Vec<(Entity, Position, Faction)> = (&entities, &positions)
+            .join()
+            .filter_map(|(e, pos)| Faction::of(e, &factions, &players, &monsters).map(|faction| (e, *pos, faction)))
+            .collect();
+        let mut claimed: HashSet<Position> = move_actions.join().map(|want| want.new_pos).collect();
+
+        for (entity, pos, faction, _) in (&entities, &positions, &factions, &randwalks).join() {
+            if !is_ready(action_points.get(entity)) || monsters.get(entity).is_some_and(Monster::chasing) {
+                continue;
+            }
+            let threat = sided
+                .iter()
+                .filter(|(e, threat_pos, other)| {
+                    *e != entity && are_hostile(*other, *faction) && distance(pos, threat_pos) <= FLEE_RANGE
+                })
+                .min_by_key(|(_, threat_pos, _)| distance(pos, threat_pos));
+            let threat_pos = match threat {
+                Some((_, threat_pos, _)) => threat_pos,
+                None => continue,
+            };
+
+            let passes = collision.passes(entity);
+            let away = collision
+                .successors(&map.0, pos, passes)
+                .into_iter()
+                .map(|(step, _)| step)
+                .filter(|step| distance(step, threat_pos) > distance(pos, threat_pos) && !claimed.contains(step))
+                .max_by_key(|step| distance(step, threat_pos));
+            if let Some(step) = away {
+                claimed.insert(step);
+                let _ = move_actions.insert(entity, MoveAction::new(step));
+            }
+        }
+    }
+}
+
+/// Chance out of 100 a being will say something on a turn they are able to
+const QUIP_CHANCE: u32 = 3;
+/// Chance out of 100 a being will say something after being attacked
+const PROVOKED_QUIP_CHANCE: u32 = 50;
+/// Beings must be this close to the player to be heard without being provoked
+const QUIP_HEARING_RANGE: u32 = 8;
+/// Turns a being must wait after saying something before they can speak again
+const QUIP_COOLDOWN_TURNS: u32 = 20;
+
+/// Lets beings with quips occasionally say something to the message log when they are near the player
+/// or have just been attacked
+pub struct QuipSystem;
+
+impl<'a> System<'a> for QuipSystem {
+    type SystemData = (
+        ReadStorage<'a, Quips>,
+        WriteStorage<'a, Cooldowns>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, SufferDamage>,
+        Read<'a, PlayerEntity>,
+        WriteExpect<'a, MessageLog>,
+        Entities<'a>,
+    );
+
+    fn run(
+        &mut self,
+        (quips, mut cooldowns, positions, names, suffering, player_e, mut log, entities): Self::SystemData,
+    ) {
+        let mut rng = RandomNumberGenerator::new();
+        let player_pos = positions.get(player_e.0).copied();
+        for (entity, quip, pos, name) in (&entities, &quips, &positions, &names).join() {
+            if !off_cooldown(cooldowns.get(entity), CooldownKind::Quip) || quip.lines.is_empty() {
+                continue;
+            }
+
+            let provoked = suffering.contains(entity);
+            let near_player = player_pos.is_some_and(|p_pos| distance(&p_pos, pos) <= QUIP_HEARING_RANGE);
+            let chance = match (provoked, near_player) {
+                (true, _) => PROVOKED_QUIP_CHANCE,
+                (false, true) => QUIP_CHANCE,
+                (false, false) => continue,
+            };
+            if rng.range(0, 100) >= chance {
+                continue;
+            }
+
+            let line = &quip.lines[rng.range(0, quip.lines.len())];
+            log.speak(name, line);
+            start_cooldown(&mut cooldowns, entity, CooldownKind::Quip, QUIP_COOLDOWN_TURNS);
         }
     }
 }
@@ -131,10 +400,11 @@ impl<'a> System<'a> for GoalFindEntities {
         WriteStorage<'a, RandomWalkerAI>,
         ReadStorage<'a, Position>,
         ReadStorage<'a, Name>,
+        ReadStorage<'a, Tags>,
         Entities<'a>,
     );
 
-    fn run(&mut self, (mut goal_movers, mut randwalkers, positions, names, entities): Self::SystemData) {
+    fn run(&mut self, (mut goal_movers, mut randwalkers, positions, names, tags, entities): Self::SystemData) {
         let mut remove_mes: Vec<Entity> = vec![];
         for (goal_entity, goal_mover, mover_pos, mover_name) in (&entities, &mut goal_movers, &positions, &names).join()
         {
@@ -144,7 +414,7 @@ impl<'a> System<'a> for GoalFindEntities {
             let mut closest_goal = (None, 1000000);
             let data: Vec<_> = (&entities, &names, &positions)
                 .join()
-                .filter(|(e, n, _)| goal_mover.desires.contains(n) && e.ne(&goal_entity))
+                .filter(|(e, n, _)| goal_mover.desires(n, tags.get(*e)) && e.ne(&goal_entity))
                 .collect();
             if data.is_empty() {
                 info!("No goals remain for {}, switching to randomwalk", mover_name);
@@ -177,16 +447,29 @@ impl<'a> System<'a> for GoalMoveToEntities {
         WriteStorage<'a, GoalMoverAI>,
         ReadStorage<'a, Position>,
         ReadStorage<'a, Name>,
+        ReadStorage<'a, ActionPoints>,
         ReadExpect<'a, MapRes>,
+        CollisionData<'a>,
         Entities<'a>,
     );
 
     fn run(
         &mut self,
-        (mut move_actions, mut attack_actions, mut goal_movers, positions, names, map, entities): Self::SystemData,
+        (
+            mut move_actions,
+            mut attack_actions,
+            mut goal_movers,
+            positions,
+            names,
+            action_points,
+            map,
+            collision_data,
+            entities,
+        ): Self::SystemData,
     ) {
+        let collision = Collision::new(&collision_data);
         for (entity, goal_mover, mover_pos, mover_name) in (&entities, &mut goal_movers, &positions, &names).join() {
-            if goal_mover.current.is_none() {
+            if goal_mover.current.is_none() || !is_ready(action_points.get(entity)) {
                 continue;
             }
             let goal_pos = match positions.get(goal_mover.current.unwrap()) {
@@ -205,9 +488,10 @@ impl<'a> System<'a> for GoalMoveToEntities {
                 info!("{} tries to attack {}", mover_name, target_name);
                 continue;
             }
+            let passes = collision.passes(entity);
             let path: (Vec<Position>, u32) = match astar(
                 mover_pos,
-                |p| successors(&map.0, p),
+                |p| collision.successors(&map.0, p, passes),
                 |p| distance(p, goal_pos),
                 |p| is_goal(p, goal_pos),
             ) {
@@ -224,14 +508,250 @@ impl<'a> System<'a> for GoalMoveToEntities {
     }
 }
 
+/// Has aggressive monsters chase down the nearest being of a faction they are hostile to once it is in range and
+/// attack it when next to it. A monster that loses its target goes back to wandering
+pub struct MonsterChaseAI;
+
+impl<'a> System<'a> for MonsterChaseAI {
+    type SystemData = (
+        WriteStorage<'a, MoveAction>,
+        WriteStorage<'a, AttackAction>,
+        WriteStorage<'a, Monster>,
+        WriteStorage<'a, AggroIndicator>,
+        ReadStorage<'a, Interactor>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, ActionPoints>,
+        ReadStorage<'a, Faction>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, SpecialAttack>,
+        ReadExpect<'a, MapRes>,
+        CollisionData<'a>,
+        Entities<'a>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut move_actions,
+            mut attack_actions,
+            mut monsters,
+            mut indicators,
+            interactors,
+            positions,
+            names,
+            action_points,
+            factions,
+            players,
+            specials,
+            map,
+            collision_data,
+            entities,
+        ): Self::SystemData,
+    ) {
+        let collision = Collision::new(&collision_data);
+        // Everything that picks a side, beings without a faction are never attacked
+        let sided: Vec<(Entity, Position, Faction)> = (&entities, &positions)
+            .join()
+            .filter_map(|(e, pos)| Faction::of(e, &factions, &players, &monsters).map(|faction| (e, *pos, faction)))
+            .collect();
+        // Tiles other beings are stepping onto this round, taking them would pile monsters onto one tile
+        let mut claimed: HashSet<Position> = move_actions.join().map(|want| want.new_pos).collect();
+
+        for (entity, monster, interactor, pos, name) in
+            (&entities, &mut monsters, &interactors, &positions, &names).join()
+        {
+            if !matches!(interactor.mode, InteractorMode::Agressive) {
+                monster.target = None;
+                continue;
+            }
+            let faction = factions.get(entity).copied().unwrap_or(Faction::Monster);
+
+            let kept = monster.target.and_then(|target| sided.iter().find(|(e, ..)| *e == target));
+            let target = match kept {
+                Some((target, target_pos, _)) if distance(pos, target_pos) <= monster.give_up_range => {
+                    Some((*target, *target_pos))
+                }
+                _ => sided
+                    .iter()
+                    .filter(|(e, target_pos, other)| {
+                        *e != entity && are_hostile(faction, *other) && distance(pos, target_pos) <= monster.aggro_range
+                    })
+                    .min_by_key(|(_, target_pos, _)| distance(pos, target_pos))
+                    .map(|(e, target_pos, _)| (*e, *target_pos)),
+            };
+
+            match (monster.target, target) {
+                (None, Some((target, _))) => {
+                    info!("{} starts chasing {}", name, names.get(target).map_or("something", |n| n.0.as_str()));
+                    let _ = indicators.insert(entity, AggroIndicator::new(Alert::Noticed));
+                }
+                (Some(_), None) => {
+                    info!("{} lost track of its target", name);
+                    let _ = indicators.insert(entity, AggroIndicator::new(Alert::Lost));
+                }
+                _ => {}
+            }
+            monster.target = target.map(|(target, _)| target);
+
+            // A monster winding up or recovering from its special attack holds still
+            let busy = specials.get(entity).is_some_and(SpecialAttack::busy);
+            let (target, target_pos) = match target {
+                Some(target) if is_ready(action_points.get(entity)) && !busy => target,
+                _ => continue,
+            };
+
+            if distance(pos, &target_pos) < 2 {
+                let _ = attack_actions.insert(entity, AttackAction { target });
+                continue;
+            }
+
+            // Path until next to the target since their tile is occupied
+            let passes = collision.passes(entity);
+            let path = astar(
+                pos,
+                |p| collision.successors(&map.0, p, passes),
+                |p| distance(p, &target_pos),
+                |p| distance(p, &target_pos) < 2,
+            );
+            if let Some((steps, _)) = path {
+                if steps.len() > 1 && claimed.insert(steps[1]) {
+                    let _ = move_actions.insert(entity, MoveAction::new(steps[1]));
+                }
+            }
+        }
+    }
+}
+
+/// Companions will go after threats that are this close to the player
+const COMPANION_ENGAGE_RANGE: u32 = 6;
+pub const DEFAULT_FOLLOW_DISTANCE: usize = 2;
+
+/// Moves companions along with the player and has them fight anything targeting the player or themselves
+pub struct CompanionAI;
+
+impl<'a> System<'a> for CompanionAI {
+    type SystemData = (
+        WriteStorage<'a, MoveAction>,
+        WriteStorage<'a, AttackAction>,
+        ReadStorage<'a, Companion>,
+        ReadStorage<'a, GoalMoverAI>,
+        ReadStorage<'a, Monster>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, ActionPoints>,
+        Read<'a, PlayerEntity>,
+        ReadExpect<'a, MapRes>,
+        CollisionData<'a>,
+        Entities<'a>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut move_actions,
+            mut attack_actions,
+            companions,
+            goal_movers,
+            monsters,
+            positions,
+            action_points,
+            player_e,
+            map,
+            collision_data,
+            entities,
+        ): Self::SystemData,
+    ) {
+        let collision = Collision::new(&collision_data);
+        let leader_pos = match positions.get(player_e.0) {
+            Some(pos) => *pos,
+            None => return,
+        };
+
+        for (entity, companion, pos) in (&entities, &companions, &positions).join() {
+            // The companion may already be moving, ie. swapping places with the player
+            if move_actions.contains(entity) || !is_ready(action_points.get(entity)) {
+                continue;
+            }
+
+            let threat = (&entities, &positions)
+                .join()
+                .filter(|(threat_e, _)| {
+                    let goal_threat = goal_movers.get(*threat_e).is_some_and(|goal_mover| {
+                        goal_mover.current == Some(player_e.0) || goal_mover.current == Some(entity)
+                    });
+                    goal_threat
+                        || monsters
+                            .get(*threat_e)
+                            .is_some_and(|monster| monster.target == Some(player_e.0) || monster.target == Some(entity))
+                })
+                .filter(|(_, threat_pos)| distance(threat_pos, &leader_pos) <= COMPANION_ENGAGE_RANGE)
+                .min_by_key(|(_, threat_pos)| distance(threat_pos, pos));
+
+            let goal_pos = match threat {
+                Some((threat_e, threat_pos)) => {
+                    if distance(pos, threat_pos) < 2 {
+                        let _ = attack_actions.insert(entity, AttackAction { target: threat_e });
+                        continue;
+                    }
+                    threat_pos
+                }
+                None if distance(pos, &leader_pos) as usize > companion.follow_distance => &leader_pos,
+                None => continue,
+            };
+
+            // Path until next to the goal since the goal's tile is usually occupied
+            let passes = collision.passes(entity);
+            let path = astar(
+                pos,
+                |p| collision.successors(&map.0, p, passes),
+                |p| distance(p, goal_pos),
+                |p| distance(p, goal_pos) < 2,
+            );
+            if let Some((steps, _)) = path {
+                if steps.len() > 1 && steps[1] != leader_pos {
+                    let _ = move_actions.insert(entity, MoveAction::new(steps[1]));
+                }
+            }
+        }
+    }
+}
+
 // Performs the changes of a move for an entity, this should be called only if the move is valid.
+// Moves are carried out in initiative order and a move onto a tile someone stepped onto or stayed on this round is lost
 pub struct HandleMoveActions;
 
 impl<'a> System<'a> for HandleMoveActions {
-    type SystemData = (WriteStorage<'a, MoveAction>, WriteStorage<'a, Position>, WriteExpect<'a, MapRes>, Entities<'a>);
+    type SystemData = (
+        WriteStorage<'a, MoveAction>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Viewshed>,
+        ReadStorage<'a, ActionPoints>,
+        ReadStorage<'a, Player>,
+        WriteExpect<'a, MapRes>,
+        Entities<'a>,
+    );
+
+    fn run(
+        &mut self,
+        (mut move_actions, mut positions, mut viewsheds, action_points, players, mut map, entities): Self::SystemData,
+    ) {
+        let movers: Vec<Entity> = (&entities, &move_actions, &positions).join().map(|(entity, _, _)| entity).collect();
+        // Where everyone who could move this round is standing, kept up to date as moves are made. The map's index is
+        // from before any of the moves so it can't tell two movers apart stepping onto the same tile
+        let mut occupied: HashSet<Position> =
+            movers.iter().filter_map(|mover| positions.get(*mover)).copied().collect();
+        occupied.extend((&positions, &players).join().map(|(pos, _)| *pos));
+        for entity in initiative_order(movers, &action_points) {
+            let (want, mover_pos) = match (move_actions.get(entity), positions.get_mut(entity)) {
+                (Some(want), Some(pos)) => (want, pos),
+                _ => continue,
+            };
+            if occupied.contains(&want.new_pos) {
+                continue;
+            }
+            occupied.remove(mover_pos);
+            occupied.insert(want.new_pos);
 
-    fn run(&mut self, (mut move_actions, mut positions, mut map, entities): Self::SystemData) {
-        for (entity, want, mover_pos) in (&entities, &move_actions, &mut positions).join() {
             let idx = mover_pos.to_idx(map.0.width);
             match map.0.tile_entities[idx].iter().position(|tile| tile == &TileEntity::Blocking(entity)) {
                 Some(remove_idx) => {
@@ -248,6 +768,9 @@ impl<'a> System<'a> for HandleMoveActions {
             }
 
             *mover_pos = want.new_pos;
+            if let Some(view) = viewsheds.get_mut(entity) {
+                view.dirty = true;
+            }
 
             let idx = mover_pos.to_idx(map.0.width);
             map.0.tile_entities[idx].push(TileEntity::Blocking(entity));
@@ -256,3 +779,145 @@ impl<'a> System<'a> for HandleMoveActions {
         move_actions.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use specs::{Builder, WorldExt};
+
+    use crate::{
+        data_read::prelude::FACTION_DB, speed::NORMAL_SPEED, test_harness::TestWorld,
+        tile_animation::AggroIndicatorFade, time::DeltaTime,
+    };
+
+    use super::*;
+
+    #[test]
+    fn goal_movers_find_entities_by_tag_or_by_name() {
+        let mut world = TestWorld::new().with_map(8, 8);
+        let carrot = world
+            .spawn("Carrot", Position::new(6, 6))
+            .with(Tags::from_raw(&Some(vec!["Food".to_string()])).unwrap())
+            .build();
+        let grass = world.spawn("Grass", Position::new(1, 1)).build();
+        let hungry = world
+            .spawn("Rabbit", Position::new(5, 5))
+            .with(GoalMoverAI::with_desires(&[Name("food".to_string())], 0))
+            .build();
+        let grazer = world
+            .spawn("Goat", Position::new(2, 2))
+            .with(GoalMoverAI::with_desires(&[Name("Grass".to_string())], 0))
+            .build();
+        world.run(GoalFindEntities);
+
+        assert_eq!(world.get::<GoalMoverAI>(hungry).unwrap().current, Some(carrot));
+        assert_eq!(world.get::<GoalMoverAI>(grazer).unwrap().current, Some(grass));
+    }
+
+    #[test]
+    fn only_provoked_monsters_show_an_indicator_that_fades() {
+        FACTION_DB.lock().unwrap().load();
+        let mut world = TestWorld::new().with_map(8, 8);
+        let player = world.player();
+        world.insert(player, Position::new(4, 4));
+        let orc = world
+            .spawn("Orc", Position::new(6, 4))
+            .with(Monster::new(3, 6))
+            .with(Interactor::new(InteractorMode::Agressive))
+            .build();
+        let sheep = world
+            .spawn("Sheep", Position::new(2, 4))
+            .with(Monster::new(3, 6))
+            .with(Interactor::new(InteractorMode::Reactive))
+            .build();
+        world.run(MonsterChaseAI);
+
+        assert_eq!(world.get::<AggroIndicator>(orc).map(|i| i.alert), Some(Alert::Noticed));
+        assert!(!world.has::<AggroIndicator>(sheep));
+
+        let half = world.get::<AggroIndicator>(orc).unwrap().time_left / 2;
+        world.ecs.insert(DeltaTime(half));
+        world.run(AggroIndicatorFade);
+        assert!(world.get::<AggroIndicator>(orc).unwrap().alpha() < 1.0);
+        world.run(AggroIndicatorFade);
+        assert!(!world.has::<AggroIndicator>(orc));
+    }
+
+    #[test]
+    fn aggressive_beings_attack_the_nearest_hostile_faction() {
+        FACTION_DB.lock().unwrap().load();
+        let mut world = TestWorld::new().with_map(10, 10);
+        let player = world.player();
+        world.insert(player, Position::new(9, 9));
+        let wolf = world
+            .spawn("Wolf", Position::new(4, 4))
+            .with(Monster::new(4, 8))
+            .with(Interactor::new(InteractorMode::Agressive))
+            .with(Faction::Predator)
+            .with(ActionPoints { points: 1, ..ActionPoints::new(NORMAL_SPEED) })
+            .build();
+        world.spawn("Wolf", Position::new(4, 5)).with(Faction::Predator).build();
+        world.spawn("Merchant", Position::new(5, 4)).with(Faction::Villager).build();
+        let sheep = world.spawn("Sheep", Position::new(3, 4)).with(Faction::Wildlife).build();
+        world.run(MonsterChaseAI);
+
+        assert_eq!(world.get::<Monster>(wolf).unwrap().target, Some(sheep));
+        let attack = world.ecs.read_storage::<AttackAction>().get(wolf).map(|attack| attack.target);
+        assert_eq!(attack, Some(sheep));
+    }
+
+    #[test]
+    fn wildlife_runs_from_what_hunts_it() {
+        FACTION_DB.lock().unwrap().load();
+        let mut world = TestWorld::new().with_map(10, 10);
+        let ready = ActionPoints { points: 1, ..ActionPoints::new(NORMAL_SPEED) };
+        let sheep = world
+            .spawn("Sheep", Position::new(4, 4))
+            .with(RandomWalkerAI {})
+            .with(Faction::Wildlife)
+            .with(ready.clone())
+            .build();
+        let calm_sheep = world
+            .spawn("Sheep", Position::new(8, 8))
+            .with(RandomWalkerAI {})
+            .with(Faction::Wildlife)
+            .with(ready)
+            .build();
+        let wolf_pos = Position::new(3, 4);
+        world.spawn("Wolf", wolf_pos).with(Faction::Predator).build();
+        world.run(FleeAI);
+
+        let fled_to = world.ecs.read_storage::<MoveAction>().get(sheep).map(|want| want.new_pos).unwrap();
+        assert_eq!(distance(&fled_to, &wolf_pos), 2);
+        assert!(!world.has::<MoveAction>(calm_sheep));
+    }
+
+    #[test]
+    fn only_one_of_two_converging_monsters_moves() {
+        let mut world = TestWorld::new().with_map(6, 6);
+        let meeting = Position::new(2, 2);
+        let first = world.spawn("Orc", Position::new(1, 2)).with(ActionPoints::new(NORMAL_SPEED * 2)).build();
+        let second = world.spawn("Orc", Position::new(3, 2)).with(ActionPoints::new(NORMAL_SPEED)).build();
+        world.insert(first, MoveAction::new(meeting));
+        world.insert(second, MoveAction::new(meeting));
+        world.run(HandleMoveActions);
+
+        assert_eq!(world.get::<Position>(first), Some(meeting));
+        assert_eq!(world.get::<Position>(second), Some(Position::new(3, 2)));
+    }
+
+    #[test]
+    fn moves_onto_a_mover_that_stayed_put_are_lost() {
+        let mut world = TestWorld::new().with_map(6, 6);
+        // the blocked orc goes first and can't move so the one behind can't step onto it
+        let blocked = world.spawn("Orc", Position::new(2, 2)).with(ActionPoints::new(NORMAL_SPEED * 2)).build();
+        let behind = world.spawn("Orc", Position::new(1, 2)).with(ActionPoints::new(NORMAL_SPEED)).build();
+        let player = world.player();
+        world.insert(player, Position::new(3, 2));
+        world.insert(blocked, MoveAction::new(Position::new(3, 2)));
+        world.insert(behind, MoveAction::new(Position::new(2, 2)));
+        world.run(HandleMoveActions);
+
+        assert_eq!(world.get::<Position>(blocked), Some(Position::new(2, 2)));
+        assert_eq!(world.get::<Position>(behind), Some(Position::new(1, 2)));
+    }
+}
@@ -0,0 +1,194 @@
+/* boating.rs
+ *   Water is blocking so beings walk around it, carrying a boat flips that for the player. Out on the water
+ *   only other water can be sailed onto and land is reached by going ashore, which puts the boat away.
+ * */
+
+use std::collections::{HashSet, VecDeque};
+
+use bracket_lib::terminal::Point;
+use log::warn;
+use specs::{Entities, Entity, Join, ReadExpect, ReadStorage, System, Write, WriteStorage};
+
+use crate::{
+    components::{Boat, Boating, InBag, Position, Viewshed, Water},
+    map::{Map, MapRes, TileEntity},
+    player::Player,
+    ui::message_log::MessageLog,
+};
+
+/// What a step onto a tile means for a being that may be out on the water
+#[derive(Debug, PartialEq, Eq)]
+pub enum BoatStep {
+    /// Paddles out from land onto the water
+    Embark,
+    /// Moves from water to water
+    Sail,
+    /// Leaves the water for land
+    GoAshore,
+    /// Boating has nothing to do with the step, it is handled like any other move
+    Walk,
+}
+
+/// Works out how a step onto `target` goes, anything standing in the way is left for the normal move to handle
+pub fn boat_step(map: &Map, waters: &ReadStorage<Water>, target: &Position, boating: bool, has_boat: bool) -> BoatStep {
+    if is_water(map, waters, target) {
+        if blocked_by_more_than_water(map, waters, target) {
+            BoatStep::Walk
+        } else if boating {
+            BoatStep::Sail
+        } else if has_boat {
+            BoatStep::Embark
+        } else {
+            BoatStep::Walk
+        }
+    } else if boating && !map.is_blocked(target) {
+        BoatStep::GoAshore
+    } else {
+        BoatStep::Walk
+    }
+}
+
+pub fn is_water(map: &Map, waters: &ReadStorage<Water>, pos: &Position) -> bool {
+    map.tile_entities[pos.to_idx(map.width)].iter().any(|tile_entity| match tile_entity {
+        TileEntity::Blocking(entity) | TileEntity::Fishable(entity) => waters.contains(*entity),
+        _ => false,
+    })
+}
+
+/// A being or anything else that blocks is on the water tile
+fn blocked_by_more_than_water(map: &Map, waters: &ReadStorage<Water>, pos: &Position) -> bool {
+    map.tile_entities[pos.to_idx(map.width)].iter().any(|tile_entity| match tile_entity {
+        TileEntity::Blocking(entity) => !waters.contains(*entity),
+        _ => false,
+    })
+}
+
+pub fn carries_boat(owner: Entity, boats: &ReadStorage<Boat>, in_bags: &ReadStorage<InBag>) -> bool {
+    (boats, in_bags).join().any(|(_, bag)| bag.owner == owner)
+}
+
+/// The closest land that can be stood on, searching outwards from `from`
+fn nearest_shore(map: &Map, waters: &ReadStorage<Water>, from: Position) -> Option<Position> {
+    let mut seen = HashSet::from([from]);
+    let mut frontier = VecDeque::from([from]);
+    while let Some(pos) = frontier.pop_front() {
+        if !is_water(map, waters, &pos) && !map.is_blocked(&pos) {
+            return Some(pos);
+        }
+        for (dx, dy) in [(0, 1), (1, 0), (0, -1), (-1, 0)] {
+            let next = Point::new(pos.x as i32 + dx, pos.y as i32 + dy);
+            if map.in_bounds(next) && seen.insert(Position::from(next)) {
+                frontier.push_back(Position::from(next));
+            }
+        }
+    }
+    None
+}
+
+/// Keeps the player from being stuck on the water, losing the boat while out on it means swimming back to shore
+pub struct StrandedCheck;
+
+impl<'a> System<'a> for StrandedCheck {
+    type SystemData = (
+        WriteStorage<'a, Boating>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Viewshed>,
+        ReadStorage<'a, Boat>,
+        ReadStorage<'a, InBag>,
+        ReadStorage<'a, Water>,
+        ReadStorage<'a, Player>,
+        ReadExpect<'a, MapRes>,
+        Write<'a, MessageLog>,
+        Entities<'a>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut boatings,
+            mut positions,
+            mut viewsheds,
+            boats,
+            in_bags,
+            waters,
+            players,
+            map,
+            mut log,
+            entities,
+        ): Self::SystemData,
+    ) {
+        for (entity, pos, _) in (&entities, &mut positions, &players).join() {
+            if boatings.contains(entity) && !carries_boat(entity, &boats, &in_bags) {
+                boatings.remove(entity);
+            }
+            if boatings.contains(entity) || !is_water(&map.0, &waters, pos) {
+                continue;
+            }
+
+            match nearest_shore(&map.0, &waters, *pos) {
+                Some(shore) => {
+                    *pos = shore;
+                    if let Some(view) = viewsheds.get_mut(entity) {
+                        view.dirty = true;
+                    }
+                    log.log("Without a boat you swim back to shore.");
+                }
+                None => warn!("No shore to swim to from {}", pos),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::{Builder, WorldExt};
+
+    use crate::{
+        components::{Blocking, Water},
+        indexing::{IndexBlockedTiles, IndexReset},
+        test_harness::TestWorld,
+    };
+
+    use super::*;
+
+    /// Water fills the left half of the map
+    fn lake_world() -> TestWorld {
+        let mut world = TestWorld::new().with_map(6, 6);
+        for x in 0..3 {
+            for y in 0..6 {
+                world.ecs.create_entity().with(Position::new(x, y)).with(Blocking {}).with(Water {}).build();
+            }
+        }
+        world.run(IndexReset);
+        world.run(IndexBlockedTiles);
+        world
+    }
+
+    #[test]
+    fn boats_swap_which_tiles_are_walkable() {
+        let world = lake_world();
+        let map = world.ecs.read_resource::<MapRes>();
+        let waters = world.ecs.read_storage::<Water>();
+        let (water, land) = (Position::new(2, 2), Position::new(3, 2));
+
+        assert_eq!(boat_step(&map.0, &waters, &water, false, false), BoatStep::Walk);
+        assert_eq!(boat_step(&map.0, &waters, &water, false, true), BoatStep::Embark);
+        assert_eq!(boat_step(&map.0, &waters, &water, true, true), BoatStep::Sail);
+        assert_eq!(boat_step(&map.0, &waters, &land, true, true), BoatStep::GoAshore);
+        assert_eq!(boat_step(&map.0, &waters, &land, false, true), BoatStep::Walk);
+    }
+
+    #[test]
+    fn stranded_player_swims_to_shore() {
+        let mut world = lake_world();
+        let player = world.player();
+        world.insert(player, Position::new(0, 3));
+        world.insert(player, Boating {});
+
+        world.run(StrandedCheck);
+
+        assert!(!world.has::<Boating>(player));
+        assert_eq!(world.get::<Position>(player), Some(Position::new(3, 3)));
+        assert!(world.logged("swim back to shore"));
+    }
+}
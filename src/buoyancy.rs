@@ -0,0 +1,80 @@
+/* buoyancy.rs
+ *   Items that end up on water either float or sink, as set by `buoyancy` in the item raws. Floating items stay where
+ *   they landed and drift along any current, see `currents.rs`, so they can be fished back out by boat. Sinking items
+ *   are gone for good, the player is told when something valuable goes under.
+ * */
+
+use specs::{Entities, Join, Read, ReadStorage, System, Write};
+
+use crate::{
+    components::{Buoyancy, InBag, Item, Position, Water},
+    data_read::ENTITY_DB,
+    map::MapRes,
+    ui::message_log::MessageLog,
+};
+
+/// Sinks every item on the ground that landed on water and can't float
+pub struct SinkingItems;
+
+impl<'a> System<'a> for SinkingItems {
+    type SystemData = (
+        ReadStorage<'a, Item>,
+        ReadStorage<'a, Buoyancy>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, InBag>,
+        ReadStorage<'a, Water>,
+        Read<'a, MapRes>,
+        Write<'a, MessageLog>,
+        Entities<'a>,
+    );
+
+    fn run(&mut self, (items, buoyancies, positions, inbags, waters, map, mut log, entities): Self::SystemData) {
+        let edb = &ENTITY_DB.lock().unwrap();
+        for (e, item, buoyancy, pos, _) in (&entities, &items, &buoyancies, &positions, !&inbags).join() {
+            if *buoyancy != Buoyancy::Sinks {
+                continue;
+            }
+            let on_water = match map.0.checked_xy_to_idx(pos.x, pos.y) {
+                Some(idx) => map.0.tile_entities[idx].iter().any(|tile_entity| waters.contains(tile_entity.entity())),
+                None => false,
+            };
+            if !on_water {
+                continue;
+            }
+            if let Some(info) = edb.items.get_by_id(item.id).filter(|info| info.is_valuable()) {
+                log.log(format!("The {} sinks out of reach.", info.name));
+            }
+            let _ = entities.delete(e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::{Builder, WorldExt};
+
+    use super::*;
+    use crate::{
+        components::Blocking,
+        indexing::{IndexBlockedTiles, IndexReset},
+        items::{ItemID, ItemQty},
+        test_harness::TestWorld,
+    };
+
+    #[test]
+    fn only_sinking_items_on_water_are_lost() {
+        let mut world = TestWorld::new().with_map(4, 4);
+        let (lake, shore) = (Position::new(1, 1), Position::new(2, 2));
+        world.spawn("Water", lake).with(Water {}).with(Blocking {}).build();
+        let item = || Item::new(ItemID::new(1), ItemQty(1));
+        let sunk = world.spawn("Stone", lake).with(item()).with(Buoyancy::Sinks).build();
+        let floating = world.spawn("Log", lake).with(item()).with(Buoyancy::Floats).build();
+        let ashore = world.spawn("Pebble", shore).with(item()).with(Buoyancy::Sinks).build();
+        world.run(IndexReset);
+        world.run(IndexBlockedTiles);
+
+        world.run(SinkingItems);
+        assert!(!world.ecs.is_alive(sunk));
+        assert!(world.ecs.is_alive(floating) && world.ecs.is_alive(ashore));
+    }
+}
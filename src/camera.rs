@@ -1,20 +1,114 @@
+use std::time::Duration;
+
 use bracket_lib::terminal::{Point, Rect};
+use serde::{Deserialize, Serialize};
 use specs::{Join, World, WorldExt};
 
-use crate::{components::Position, map::MapRes, player::Player, DISPLAY_HEIGHT, DISPLAY_WIDTH};
+use crate::{components::Position, map::MapRes, player::Player, time::DeltaTime, DISPLAY_HEIGHT, DISPLAY_WIDTH};
 
 const PLAYER_CAMERA_OFFSET_X: i32 = (DISPLAY_WIDTH / 2) as i32;
 const PLAYER_CAMERA_OFFSET_Y: i32 = (DISPLAY_HEIGHT / 2) as i32;
 
-/// Gets the bounds of the camera positioned at the player.
-pub fn get_camera_bounds(ecs: &World) -> Rect {
+/// The map tile under the mouse this frame, `None` when the mouse is off the map. Updated once at the start of
+/// every frame so everything that cares about the mouse agrees on the tile
+#[derive(Default)]
+pub struct HoveredTile(pub Option<Position>);
+
+/// How the camera follows the player, read from the settings when the game starts
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct CameraConfig {
+    /// Roughly how long the camera takes to catch up with the player, 0 snaps it to the player every frame
+    pub glide_ms: u64,
+    /// The camera jumps straight to a target further than this many tiles away instead of gliding across the map
+    pub snap_distance: f32,
+}
+
+impl Default for CameraConfig {
+    fn default() -> Self {
+        Self { glide_ms: 120, snap_distance: 8.0 }
+    }
+}
+
+/// Where the camera is looking, it eases towards the player each frame instead of snapping to them
+#[derive(Default)]
+pub struct Camera {
+    cfg: CameraConfig,
+    /// Top left of the view in map tiles, `None` until the camera is first placed
+    offset: Option<(f32, f32)>,
+}
+
+/// Close enough to the target that the camera stops gliding
+const SETTLED_DISTANCE: f32 = 0.05;
+
+impl Camera {
+    pub fn new(cfg: CameraConfig) -> Self {
+        Self { cfg, offset: None }
+    }
+
+    /// Places the camera on its target next frame, for when the map is swapped out
+    pub fn snap(&mut self) {
+        self.offset = None;
+    }
+
+    /// Moves the camera part of the way to `target` for a frame lasting `dt`
+    fn update(&mut self, target: (f32, f32), dt: Duration) {
+        let glide = Duration::from_millis(self.cfg.glide_ms);
+        let (x, y) = match self.offset {
+            Some(offset) if !glide.is_zero() => offset,
+            _ => {
+                self.offset = Some(target);
+                return;
+            }
+        };
+        let (dx, dy) = (target.0 - x, target.1 - y);
+        let distance = dx.hypot(dy);
+        if distance > self.cfg.snap_distance || distance < SETTLED_DISTANCE {
+            self.offset = Some(target);
+            return;
+        }
+        let t = (dt.as_secs_f32() / glide.as_secs_f32()).min(1.0);
+        self.offset = Some((x + dx * t, y + dy * t));
+    }
+}
+
+/// Ran every frame time passes so the camera glides towards the player
+pub fn update_camera(ecs: &World) {
+    let target = match camera_target(ecs) {
+        Some(target) => target,
+        None => return,
+    };
+    let dt = ecs.read_resource::<DeltaTime>().0;
+    ecs.write_resource::<Camera>().update(target, dt);
+}
+
+/// The top left of a view with the player in the middle. The view is kept on the map, a map smaller than the view is
+/// centered in it instead
+fn camera_target(ecs: &World) -> Option<(f32, f32)> {
     let player = ecs.read_storage::<Player>();
     let positions = ecs.read_storage::<Position>();
+    let map = ecs.read_resource::<MapRes>();
+    (&positions, &player).join().next().map(|(pos, _)| {
+        let x = clamp_to_map(pos.x as i32 - PLAYER_CAMERA_OFFSET_X, map.0.width, DISPLAY_WIDTH);
+        let y = clamp_to_map(pos.y as i32 - PLAYER_CAMERA_OFFSET_Y, map.0.height, DISPLAY_HEIGHT);
+        (x as f32, y as f32)
+    })
+}
+
+fn clamp_to_map(offset: i32, map_len: usize, view_len: usize) -> i32 {
+    let spare = map_len as i32 - view_len as i32;
+    if spare < 0 {
+        spare / 2
+    } else {
+        offset.clamp(0, spare)
+    }
+}
 
-    if let Some((pos, _player)) = (&positions, &player).join().next() {
-        let x_offset = pos.x as i32 - PLAYER_CAMERA_OFFSET_X;
-        let y_offset = pos.y as i32 - PLAYER_CAMERA_OFFSET_Y;
-        Rect::with_size(x_offset, y_offset, DISPLAY_WIDTH as i32, DISPLAY_HEIGHT as i32)
+/// Gets the bounds of the camera, which follows the player.
+pub fn get_camera_bounds(ecs: &World) -> Rect {
+    let offset = ecs.read_resource::<Camera>().offset.or_else(|| camera_target(ecs));
+    if let Some((x, y)) = offset {
+        Rect::with_size(x.round() as i32, y.round() as i32, DISPLAY_WIDTH as i32, DISPLAY_HEIGHT as i32)
     } else {
         Rect::with_size(
             PLAYER_CAMERA_OFFSET_X,
@@ -35,9 +129,47 @@ pub fn mouse_to_map_pos(mouse_pos: &(i32, i32), ecs: &World) -> Option<Position>
     let tile_pos = Point::new(bounds.x1 + mouse_pos.0, bounds.y1 + mouse_pos.1);
 
     if !map.0.in_bounds(tile_pos) {
-        eprintln!("{:?} is outside of the map", mouse_pos);
         return None;
     }
 
     Some(Position::from(tile_pos))
 }
+
+/// `mouse_pos` must be read from the world console
+pub fn update_hovered_tile(ecs: &World, mouse_pos: (i32, i32)) {
+    let hovered = mouse_to_map_pos(&mouse_pos, ecs);
+    ecs.write_resource::<HoveredTile>().0 = hovered;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(16);
+
+    #[test]
+    fn camera_glides_to_near_targets_and_snaps_to_far_ones() {
+        let mut camera = Camera::new(CameraConfig { glide_ms: 100, snap_distance: 5.0 });
+        camera.update((0.0, 0.0), FRAME);
+        assert_eq!(camera.offset, Some((0.0, 0.0)));
+
+        camera.update((1.0, 0.0), FRAME);
+        let (x, _) = camera.offset.unwrap();
+        assert!(x > 0.0 && x < 1.0);
+        for _ in 0..30 {
+            camera.update((1.0, 0.0), FRAME);
+        }
+        assert_eq!(camera.offset, Some((1.0, 0.0)));
+
+        camera.update((20.0, 12.0), FRAME);
+        assert_eq!(camera.offset, Some((20.0, 12.0)));
+    }
+
+    #[test]
+    fn view_is_kept_on_the_map() {
+        assert_eq!(clamp_to_map(-3, 100, 40), 0);
+        assert_eq!(clamp_to_map(70, 100, 40), 60);
+        assert_eq!(clamp_to_map(30, 100, 40), 30);
+        assert_eq!(clamp_to_map(5, 20, 40), -10);
+    }
+}
@@ -0,0 +1,125 @@
+/* catch_release.rs
+ *   Once the player lands a fish they choose to keep it or let it go. Kept fish go into the backpack, released ones
+ *   swim away and the player is paid a little gold for it. Waiting too long keeps the fish, and a fish that won't fit
+ *   in a full backpack is let go no matter what was chosen.
+ * */
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use specs::{Component, Entities, Join, Read, System, VecStorage, WriteStorage};
+
+use crate::{components::FinishedActivity, time::DeltaTime};
+
+/// How long the player has to choose and what letting a fish go is worth, read from the settings when the game starts
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct CatchReleaseConfig {
+    /// Seconds to choose before the fish is kept
+    pub choice_secs: u64,
+    /// Gold given for every fish let go
+    pub release_gold: u32,
+}
+
+impl Default for CatchReleaseConfig {
+    fn default() -> Self {
+        Self { choice_secs: 5, release_gold: 2 }
+    }
+}
+
+#[derive(Default)]
+pub struct CatchRelease {
+    cfg: CatchReleaseConfig,
+}
+
+impl CatchRelease {
+    pub fn new(cfg: CatchReleaseConfig) -> Self {
+        Self { cfg }
+    }
+
+    pub fn choice_time(&self) -> Duration {
+        Duration::from_secs(self.cfg.choice_secs)
+    }
+
+    pub fn release_gold(&self) -> u32 {
+        self.cfg.release_gold
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CatchDecision {
+    Keep,
+    Release,
+}
+
+/// A landed fish waiting on the fisher to keep or release it, their fishing only finishes once it is decided
+#[derive(Component)]
+#[storage(VecStorage)]
+pub struct CatchChoice {
+    pub time_left: Duration,
+    pub decision: Option<CatchDecision>,
+}
+
+impl CatchChoice {
+    pub fn new(time: Duration) -> Self {
+        Self { time_left: time, decision: None }
+    }
+
+    /// What was chosen, a fish nobody decided on is kept
+    pub fn decided(&self) -> CatchDecision {
+        self.decision.unwrap_or(CatchDecision::Keep)
+    }
+}
+
+/// Counts down the time left to choose and finishes the fishing activity once a choice is made or the time runs out,
+/// `CatchFishSystem` then keeps or releases the fish
+pub struct CatchChoiceSystem;
+
+impl<'a> System<'a> for CatchChoiceSystem {
+    type SystemData =
+        (WriteStorage<'a, CatchChoice>, WriteStorage<'a, FinishedActivity>, Read<'a, DeltaTime>, Entities<'a>);
+
+    fn run(&mut self, (mut choices, mut finished_activities, dt, entities): Self::SystemData) {
+        let mut decided = Vec::new();
+        for (fisher, choice, ()) in (&entities, &mut choices, !&finished_activities).join() {
+            if choice.decision.is_none() {
+                choice.time_left = choice.time_left.saturating_sub(dt.0);
+                if !choice.time_left.is_zero() {
+                    continue;
+                }
+            }
+            decided.push(fisher);
+        }
+        for fisher in decided {
+            let _ = finished_activities.insert(fisher, FinishedActivity {});
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::Builder;
+
+    use super::*;
+    use crate::{components::Position, test_harness::TestWorld};
+
+    #[test]
+    fn fish_are_kept_once_the_time_to_choose_runs_out() {
+        let mut world = TestWorld::new().with_delta(Duration::from_secs(3));
+        let waiting = world.spawn("Fisher", Position::new(1, 1)).with(CatchChoice::new(Duration::from_secs(5))).build();
+        let mut released = CatchChoice::new(Duration::from_secs(5));
+        released.decision = Some(CatchDecision::Release);
+        let releasing = world.spawn("Fisher", Position::new(2, 1)).with(released).build();
+
+        world.run(CatchChoiceSystem);
+        assert!(!world.has::<FinishedActivity>(waiting));
+        assert!(world.has::<FinishedActivity>(releasing));
+
+        world.run(CatchChoiceSystem);
+        assert!(world.has::<FinishedActivity>(waiting));
+        assert_eq!(
+            world.ecs.read_storage::<CatchChoice>().get(waiting).map(CatchChoice::decided),
+            Some(CatchDecision::Keep)
+        );
+    }
+}
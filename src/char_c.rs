@@ -12,9 +12,34 @@ pub const CH_GOAL_SINGLE: u8 = 18;
 pub const CH_GOAL_MID: u8 = 23;
 pub const CH_GOAL_LEFT: u8 = 22;
 pub const CH_GOAL_RIGHT: u8 = 24;
+// The line between the rod and the bobber, plain ascii strokes picked by which way the line runs
+pub const CH_LINE_FLAT: u8 = 45;
+pub const CH_LINE_UPRIGHT: u8 = 124;
+pub const CH_LINE_RISING: u8 = 47;
+pub const CH_LINE_FALLING: u8 = 92;
 
 // Mining
 pub const CH_STRIKE: u8 = 2;
 
+// Explosives, the same flame the campfire is drawn with
+pub const CH_EXPLOSION: u8 = 15;
+
+// Monster aggro
+pub const CH_ALERT: u8 = 33;
+pub const CH_LOST: u8 = 63;
+
+// Weather
+pub const CH_RAIN: u8 = 124;
+
+// Targeting
+pub const CH_TARGET: u8 = 254;
+/// Same shading the remembered but unseen tiles are dimmed with
+pub const CH_HIGHLIGHT: u8 = 34;
+/// The small dot embers drift up as
+pub const CH_ARC: u8 = 250;
+
 pub const CH_SOLID: u8 = 4;
 pub const CH_WATER: u8 = 5 * 16;
+/// Same look as gravel
+pub const CH_CAVE_FLOOR: u8 = 16;
+pub const CH_MOUNTAIN: u8 = 17;
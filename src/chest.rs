@@ -0,0 +1,224 @@
+use bracket_lib::terminal::{BTerm, VirtualKeyCode as VKC};
+use specs::{Entity, Join, World, WorldExt};
+
+use crate::{
+    components::{Container, Equipped, InBag, Item, LevelPersistent, Name},
+    game_init::PlayerEntity,
+    items::{ItemID, ItemQty},
+    shop::backpack_has_room,
+    ui::message_log::MessageLog,
+};
+
+#[derive(PartialEq, Clone, Copy, Default)]
+pub enum ChestSide {
+    #[default]
+    Chest,
+    Backpack,
+}
+
+/// Resource holding the chest the player currently has open
+#[derive(Default)]
+pub struct OpenChest {
+    pub chest: Option<Entity>,
+    pub chest_name: String,
+    /// The panel the hovered item is picked from
+    pub side: ChestSide,
+    pub hovering: usize,
+}
+
+pub enum ChestAction {
+    Up,
+    Down,
+    SwitchSide,
+    Transfer,
+    Close,
+    Waiting,
+}
+
+/// Opens the container so the player can move items between it and their backpack
+pub fn try_open_chest(ecs: &World, chest: Entity) -> bool {
+    if !ecs.read_storage::<Container>().contains(chest) {
+        return false;
+    }
+    let chest_name = ecs.read_storage::<Name>().get(chest).map_or("chest".to_string(), |name| name.0.clone());
+    ecs.fetch_mut::<MessageLog>().log(format!("You open the {}.", chest_name));
+
+    let mut open = ecs.write_resource::<OpenChest>();
+    *open = OpenChest { chest: Some(chest), chest_name, side: ChestSide::Chest, hovering: 0 };
+    true
+}
+
+pub fn p_input_chest(ctx: &BTerm) -> ChestAction {
+    match ctx.key {
+        Some(VKC::W | VKC::Up) => ChestAction::Up,
+        Some(VKC::S | VKC::Down) => ChestAction::Down,
+        Some(VKC::Tab | VKC::A | VKC::D | VKC::Left | VKC::Right) => ChestAction::SwitchSide,
+        Some(VKC::Return | VKC::E) => ChestAction::Transfer,
+        Some(VKC::Escape) => ChestAction::Close,
+        _ => ChestAction::Waiting,
+    }
+}
+
+/// Performs the action in the open chest, returns false once the chest is closed
+pub fn handle_chest_action(ecs: &mut World, action: ChestAction) -> bool {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let (chest, side, hovering) = {
+        let open = ecs.read_resource::<OpenChest>();
+        match open.chest {
+            Some(chest) => (chest, open.side, open.hovering),
+            None => return false,
+        }
+    };
+    // the chest could have been destroyed while it was open
+    if !ecs.is_alive(chest) {
+        return false;
+    }
+    let (from, to) = match side {
+        ChestSide::Chest => (chest, player_entity),
+        ChestSide::Backpack => (player_entity, chest),
+    };
+    let listed = stored_items(ecs, from);
+
+    match action {
+        ChestAction::Up => {
+            ecs.write_resource::<OpenChest>().hovering =
+                if hovering == 0 { listed.len().saturating_sub(1) } else { hovering - 1 };
+        }
+        ChestAction::Down => {
+            ecs.write_resource::<OpenChest>().hovering = if hovering + 1 >= listed.len() { 0 } else { hovering + 1 };
+        }
+        ChestAction::SwitchSide => {
+            let mut open = ecs.write_resource::<OpenChest>();
+            open.side = match side {
+                ChestSide::Chest => ChestSide::Backpack,
+                ChestSide::Backpack => ChestSide::Chest,
+            };
+            open.hovering = 0;
+        }
+        ChestAction::Transfer => {
+            if let Some((item_entity, ..)) = listed.get(hovering) {
+                transfer(ecs, *item_entity, to);
+            }
+            // Moving a whole stack or merging it shortens the list
+            let remaining = stored_items(ecs, from).len();
+            let mut open = ecs.write_resource::<OpenChest>();
+            open.hovering = open.hovering.min(remaining.saturating_sub(1));
+        }
+        ChestAction::Close => return false,
+        ChestAction::Waiting => {}
+    }
+    true
+}
+
+/// Items held by the owner that can be moved, equipped items have to be taken off first
+pub fn stored_items(ecs: &World, owner: Entity) -> Vec<(Entity, ItemID, ItemQty)> {
+    let items = ecs.read_storage::<Item>();
+    let in_bags = ecs.read_storage::<InBag>();
+    let equipped = ecs.read_storage::<Equipped>();
+    let entities = ecs.entities();
+
+    let mut stored: Vec<(Entity, ItemID, ItemQty)> = (&entities, &items, &in_bags, !&equipped)
+        .join()
+        .filter(|(_, item, bag, _)| bag.owner == owner && item.qty.0 > 0)
+        .map(|(entity, item, ..)| (entity, item.id, item.qty))
+        .collect();
+    stored.sort_by_key(|(_, id, _)| *id);
+    stored
+}
+
+/// Moves the whole stack into `to`, joining a stack of the same item when there is one. Returns false when it
+/// didn't fit
+pub fn transfer(ecs: &mut World, item_entity: Entity, to: Entity) -> bool {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let item = match ecs.read_storage::<Item>().get(item_entity) {
+        Some(item) => item.clone(),
+        None => return false,
+    };
+    let item_name = ecs.read_storage::<Name>().get(item_entity).cloned().unwrap_or_else(Name::missing_item_name);
+    let to_name = if to == player_entity {
+        "your backpack".to_string()
+    } else {
+        ecs.read_storage::<Name>().get(to).map_or("the chest".to_string(), |name| format!("the {}", name.0))
+    };
+
+    let existing = stored_items(ecs, to).into_iter().find(|(e, id, _)| *id == item.id && *e != item_entity);
+    if let Some((stack, _, qty)) = existing {
+        let total = match qty.try_add(item.qty) {
+            Ok(total) => total,
+            Err(e) => {
+                ecs.fetch_mut::<MessageLog>().log(format!("The {} won't fit in {}, {}", item_name, to_name, e));
+                return false;
+            }
+        };
+        let mut items = ecs.write_storage::<Item>();
+        let _ = items.insert(stack, Item::new(item.id, total));
+        // The zero quantity cleanup deletes the emptied stack
+        let _ = items.insert(item_entity, Item::new(item.id, ItemQty(0)));
+    } else {
+        if !backpack_has_room(ecs, to, item.id) {
+            ecs.fetch_mut::<MessageLog>().log(format!("There is no room in {} for the {}.", to_name, item_name));
+            return false;
+        }
+        let _ = ecs.write_storage::<InBag>().insert(item_entity, InBag { owner: to });
+        // Only what the player carries follows them between levels
+        let mut persistents = ecs.write_storage::<LevelPersistent>();
+        if to == player_entity {
+            let _ = persistents.insert(item_entity, LevelPersistent {});
+        } else {
+            persistents.remove(item_entity);
+        }
+    }
+
+    ecs.fetch_mut::<MessageLog>().log(format!("You move {}x {} into {}.", item.qty, item_name, to_name));
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::Builder;
+
+    use super::*;
+    use crate::test_harness::TestWorld;
+
+    fn give(world: &mut TestWorld, owner: Entity, id: u32, qty: usize) -> Entity {
+        world
+            .ecs
+            .create_entity()
+            .with(Name(format!("Item {}", id)))
+            .with(Item::new(ItemID::new(id), ItemQty(qty)))
+            .with(InBag { owner })
+            .build()
+    }
+
+    #[test]
+    fn moved_items_join_the_stack_already_there() {
+        let mut world = TestWorld::new();
+        let player = world.player();
+        let chest = world.ecs.create_entity().with(Container { slots: 2 }).build();
+        let worms = give(&mut world, player, 4, 3);
+        give(&mut world, chest, 4, 2);
+
+        assert!(transfer(&mut world.ecs, worms, chest));
+        let in_chest = stored_items(&world.ecs, chest);
+        assert_eq!(in_chest.len(), 1);
+        assert_eq!(in_chest[0].2, ItemQty(5));
+        assert!(stored_items(&world.ecs, player).is_empty());
+    }
+
+    #[test]
+    fn full_chests_refuse_new_stacks() {
+        let mut world = TestWorld::new();
+        let player = world.player();
+        let chest = world.ecs.create_entity().with(Container { slots: 1 }).build();
+        give(&mut world, chest, 2, 1);
+        let rod = give(&mut world, player, 100, 1);
+
+        assert!(!transfer(&mut world.ecs, rod, chest));
+        assert_eq!(world.get::<InBag>(rod).map(|bag| bag.owner), Some(player));
+
+        let stick = stored_items(&world.ecs, chest)[0].0;
+        assert!(transfer(&mut world.ecs, stick, player));
+        assert!(world.has::<LevelPersistent>(stick));
+        assert!(transfer(&mut world.ecs, rod, chest));
+    }
+}
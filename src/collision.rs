@@ -0,0 +1,195 @@
+/* collision.rs
+ *   Decides what stops a being from moving. Blocking is absolute for most beings but flying ones pass over deep water
+ *   and phasing ones drift through terrain. Beings and the player always block other beings. Large beings are also
+ *   kept out of narrow gaps, a tile with terrain on both of its opposite sides.
+ * */
+
+use bracket_lib::terminal::Point;
+use specs::{Entity, ReadStorage};
+
+use crate::{
+    being::BeingID,
+    components::{Flying, Phasing, Position, Size, Water},
+    map::{successors, Map, TileEntity},
+    player::Player,
+};
+
+/// What sort of obstacle a blocking entity is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockKind {
+    /// Walls, trees, rocks and anything else that stays put
+    Terrain,
+    /// Deep water
+    Water,
+    /// Other beings and the player
+    Being,
+}
+
+/// The kinds of obstacle a mover goes through
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Passes {
+    flying: bool,
+    phasing: bool,
+    /// Too big to fit through narrow gaps
+    large: bool,
+}
+
+impl Passes {
+    /// Flyers pass over water and phasing beings through terrain, nothing passes through a being
+    pub fn ignores(&self, kind: BlockKind) -> bool {
+        match kind {
+            BlockKind::Water => self.flying,
+            BlockKind::Terrain => self.phasing,
+            BlockKind::Being => false,
+        }
+    }
+}
+
+/// The storages needed to work out collisions, systems take it as one part of their SystemData
+pub type CollisionData<'a> = (
+    ReadStorage<'a, Water>,
+    ReadStorage<'a, BeingID>,
+    ReadStorage<'a, Player>,
+    ReadStorage<'a, Flying>,
+    ReadStorage<'a, Phasing>,
+    ReadStorage<'a, Size>,
+);
+
+pub struct Collision<'s, 'a> {
+    data: &'s CollisionData<'a>,
+}
+
+impl<'s, 'a> Collision<'s, 'a> {
+    pub fn new(data: &'s CollisionData<'a>) -> Self {
+        Self { data }
+    }
+
+    pub fn passes(&self, mover: Entity) -> Passes {
+        let (_, _, _, flying, phasing, sizes) = self.data;
+        Passes {
+            flying: flying.contains(mover),
+            phasing: phasing.contains(mover),
+            large: sizes.get(mover).is_some_and(Size::too_big_for_gaps),
+        }
+    }
+
+    pub fn kind(&self, entity: Entity) -> BlockKind {
+        let (waters, beings, players, ..) = self.data;
+        if beings.contains(entity) || players.contains(entity) {
+            BlockKind::Being
+        } else if waters.contains(entity) {
+            BlockKind::Water
+        } else {
+            BlockKind::Terrain
+        }
+    }
+
+    /// Whether the tile entity is in the way of a mover that `passes` some obstacles, items are never in the way
+    fn in_the_way(&self, tile_entity: &TileEntity, passes: Passes) -> bool {
+        match tile_entity {
+            TileEntity::Item(_) => true,
+            tile_entity => !passes.ignores(self.kind(tile_entity.entity())),
+        }
+    }
+
+    /// Like `Map::is_blocked` but for a particular mover
+    pub fn is_blocked(&self, map: &Map, pos: &Position, passes: Passes) -> bool {
+        map.tile_entities[pos.to_idx(map.width)]
+            .iter()
+            .any(|tile_entity| tile_entity.is_blocker() && self.in_the_way(tile_entity, passes))
+            || self.too_narrow(map, pos, passes)
+    }
+
+    /// Whether `pos` is a gap too tight for the mover, terrain it can't pass on both its left and right or both above
+    /// and below. The map's edge counts as a wall
+    pub fn too_narrow(&self, map: &Map, pos: &Position, passes: Passes) -> bool {
+        if !passes.large {
+            return false;
+        }
+        let walled = |dx: i32, dy: i32| {
+            let point = Point::new(pos.x as i32 + dx, pos.y as i32 + dy);
+            if !map.in_bounds(point) {
+                return true;
+            }
+            map.tile_entities[Position::from(point).to_idx(map.width)].iter().any(|tile_entity| {
+                tile_entity.is_blocker()
+                    && self.kind(tile_entity.entity()) == BlockKind::Terrain
+                    && self.in_the_way(tile_entity, passes)
+            })
+        };
+        (walled(-1, 0) && walled(1, 0)) || (walled(0, -1) && walled(0, 1))
+    }
+
+    /// Like `Map::first_entity_in_pos` but anything the mover passes through is left out
+    pub fn first_in_way<'m>(&self, map: &'m Map, pos: &Position, passes: Passes) -> Option<&'m TileEntity> {
+        map.first_entity_where(pos, |tile_entity| self.in_the_way(tile_entity, passes))
+    }
+
+    /// Neighbouring tiles the mover can step onto, for pathfinding
+    pub fn successors(&self, map: &Map, curr: &Position, passes: Passes) -> Vec<(Position, u32)> {
+        successors(map, curr, |pos| self.is_blocked(map, pos, passes))
+    }
+}
+
+/// Phasing beings can be set to let the player walk through them too
+pub fn blocks_player(phasing: &ReadStorage<Phasing>, entity: Entity) -> bool {
+    phasing.get(entity).map_or(true, |phasing| phasing.blocks_player)
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::{Builder, WorldExt};
+
+    use crate::{
+        components::Blocking,
+        indexing::{IndexBlockedTiles, IndexReset},
+        map::MapRes,
+        test_harness::TestWorld,
+    };
+
+    use super::*;
+
+    #[test]
+    fn flyers_cross_water_and_phasers_cross_terrain_but_not_beings() {
+        let mut world = TestWorld::new().with_map(6, 6);
+        let (lake, wall, sheep) = (Position::new(1, 1), Position::new(2, 2), Position::new(3, 3));
+        world.spawn("Water", lake).with(Blocking {}).with(Water {}).build();
+        world.spawn("Wall", wall).with(Blocking {}).build();
+        world.spawn("Sheep", sheep).with(Blocking {}).with(BeingID::new(0)).build();
+        let bat = world.spawn("Bat", Position::new(0, 0)).with(Flying {}).build();
+        let ghost = world.spawn("Ghost", Position::new(5, 5)).with(Phasing { blocks_player: true }).build();
+        world.run(IndexReset);
+        world.run(IndexBlockedTiles);
+
+        let map = world.ecs.read_resource::<MapRes>();
+        let data: CollisionData = world.ecs.system_data();
+        let collision = Collision::new(&data);
+        let (bat, ghost) = (collision.passes(bat), collision.passes(ghost));
+
+        assert!(!collision.is_blocked(&map.0, &lake, bat));
+        assert!(collision.is_blocked(&map.0, &wall, bat));
+        assert!(collision.is_blocked(&map.0, &lake, ghost));
+        assert!(!collision.is_blocked(&map.0, &wall, ghost));
+        assert!(collision.is_blocked(&map.0, &sheep, bat));
+        assert!(collision.is_blocked(&map.0, &sheep, ghost));
+    }
+
+    #[test]
+    fn large_beings_cannot_squeeze_between_walls() {
+        let mut world = TestWorld::new().with_map(6, 6);
+        world.spawn("Wall", Position::new(1, 2)).with(Blocking {}).build();
+        world.spawn("Wall", Position::new(3, 2)).with(Blocking {}).build();
+        let ogre = world.spawn("Ogre", Position::new(2, 4)).with(Size::Large).build();
+        let rat = world.spawn("Rat", Position::new(2, 5)).with(Size::Small).build();
+        world.run(IndexReset);
+        world.run(IndexBlockedTiles);
+
+        let map = world.ecs.read_resource::<MapRes>();
+        let data: CollisionData = world.ecs.system_data();
+        let collision = Collision::new(&data);
+        let (gap, open) = (Position::new(2, 2), Position::new(2, 3));
+        assert!(collision.is_blocked(&map.0, &gap, collision.passes(ogre)));
+        assert!(!collision.is_blocked(&map.0, &open, collision.passes(ogre)));
+        assert!(!collision.is_blocked(&map.0, &gap, collision.passes(rat)));
+    }
+}
@@ -3,7 +3,12 @@
  * Colors should use their color name
  * Palettes should be named after their use ingame
  */
-use bracket_lib::terminal::{register_palette_color, ColorPair, RGB, WHITESMOKE};
+use bracket_lib::terminal::{
+    register_palette_color, ColorPair, ANTIQUEWHITE, BURLYWOOD, CADETBLUE4, CORNFLOWER_BLUE, DARKGOLDENROD1,
+    DARKSALMON, LIGHTCYAN4, LIGHTGOLDENRODYELLOW, LIGHTPINK, LIGHTSALMON, RGB, RGBA, ROSYBROWN, WHITESMOKE,
+};
+
+use crate::{items::Rarity, theme::Theme};
 
 pub type Color = (u8, u8, u8);
 
@@ -15,9 +20,24 @@ pub fn to_rgb(color: Color) -> RGB {
     RGB::from(color)
 }
 
-/// Adds all the Palettes to BTerm
-pub fn initialize_printer_palette() {
-    register_palette_color(PL_KEYBIND, to_rgb(MAROON));
+/// Composites `over` on top of `base` by the alpha of `over`, the result keeps the alpha of `base`. A fully
+/// transparent `over` leaves `base` as it is
+pub fn blend_over(base: RGBA, over: RGBA) -> RGBA {
+    if over.a <= 0.0 {
+        return base;
+    }
+    let a = over.a.min(1.0);
+    RGBA::from_f32(
+        base.r + (over.r - base.r) * a,
+        base.g + (over.g - base.g) * a,
+        base.b + (over.b - base.b) * a,
+        base.a,
+    )
+}
+
+/// Adds all the Palettes to BTerm, the ones the theme covers take its colors
+pub fn initialize_printer_palette(theme: &Theme) {
+    register_palette_color(PL_KEYBIND, to_rgb(theme.keybind));
     register_palette_color(PL_MAIN_MENU_TEXT, to_rgb(MIDDLERED));
     register_palette_color(PL_MAIN_MENU_TEXT_HIGHLIGHT, to_rgb(SALMON));
 
@@ -25,18 +45,26 @@ pub fn initialize_printer_palette() {
     register_palette_color(PL_SETTINGS_HIGHLIGHT, to_rgb(MIDDLERED));
 
     register_palette_color(PL_ORANGE, to_rgb(SALMON));
-    register_palette_color(PL_MENU_TEXT, to_rgb(DARKERBROWN));
-    register_palette_color(PL_MENU_ACCENT_TEXT, to_rgb(DARKBROWN));
+    register_palette_color(PL_MENU_TEXT, to_rgb(theme.menu_text));
+    register_palette_color(PL_MENU_ACCENT_TEXT, to_rgb(theme.menu_accent_text));
 
-    register_palette_color(PL_MAX_HP, RGB::from_hex("#67fc3a").unwrap());
-    register_palette_color(PL_MED_HP, RGB::from_hex("#48aa2a").unwrap());
-    register_palette_color(PL_LOW_HP, RGB::from_hex("#eab838").unwrap());
-    register_palette_color(PL_CRITICAL_HP, RGB::from_hex("#fc321b").unwrap());
+    register_palette_color(PL_MAX_HP, to_rgb(theme.max_hp));
+    register_palette_color(PL_MED_HP, to_rgb(theme.med_hp));
+    register_palette_color(PL_LOW_HP, to_rgb(theme.low_hp));
+    register_palette_color(PL_CRITICAL_HP, to_rgb(theme.critical_hp));
 
     register_palette_color("red", to_rgb(MIDDLERED));
-    register_palette_color("bright_green", RGB::from_u8(52, 156, 88));
+    register_palette_color("bright_green", to_rgb(HEAL_GREEN));
     register_palette_color("white", to_rgb(WHITE));
     register_palette_color("lightgray", RGB::from_u8(161, 161, 161));
+
+    register_palette_color(PL_RARITY_COMMON, to_rgb(WHITE));
+    register_palette_color(PL_RARITY_UNCOMMON, to_rgb(HEAL_GREEN));
+    register_palette_color(PL_RARITY_RARE, to_rgb(RARE_BLUE));
+
+    for (palette, color) in PL_SPEAKERS.iter().zip(SPEAKER_COLORS) {
+        register_palette_color(palette, to_rgb(color));
+    }
 }
 
 // Palette Definitions always start with PL_*
@@ -56,6 +84,52 @@ pub const PL_MED_HP: &str = "med_hp";
 pub const PL_LOW_HP: &str = "low_hp";
 pub const PL_CRITICAL_HP: &str = "critical_hp";
 
+pub const PL_RARITY_COMMON: &str = "rarity_common";
+pub const PL_RARITY_UNCOMMON: &str = "rarity_uncommon";
+pub const PL_RARITY_RARE: &str = "rarity_rare";
+
+/// Gets the palette an item's name is tinted with for its rarity
+pub fn rarity_palette(rarity: Rarity) -> &'static str {
+    match rarity {
+        Rarity::Common => PL_RARITY_COMMON,
+        Rarity::Uncommon => PL_RARITY_UNCOMMON,
+        Rarity::Rare => PL_RARITY_RARE,
+    }
+}
+
+pub const PL_SPEAKERS: [&str; 11] = [
+    "speaker0",
+    "speaker1",
+    "speaker2",
+    "speaker3",
+    "speaker4",
+    "speaker5",
+    "speaker6",
+    "speaker7",
+    "speaker8",
+    "speaker9",
+    "speaker10",
+];
+const SPEAKER_COLORS: [Color; 11] = [
+    ROSYBROWN,
+    DARKSALMON,
+    BURLYWOOD,
+    CADETBLUE4,
+    ANTIQUEWHITE,
+    DARKGOLDENROD1,
+    CORNFLOWER_BLUE,
+    LIGHTPINK,
+    LIGHTCYAN4,
+    LIGHTGOLDENRODYELLOW,
+    LIGHTSALMON,
+];
+
+/// Picks a speaker palette for a name so the same speaker is always the same color
+pub fn speaker_palette(name: &str) -> &'static str {
+    let hash = name.bytes().fold(0usize, |acc, b| acc.wrapping_mul(31).wrapping_add(b as usize));
+    PL_SPEAKERS[hash % PL_SPEAKERS.len()]
+}
+
 // Color Definitions
 pub const MIDDLERED: Color = (183, 65, 50);
 pub const SALMON: Color = (230, 113, 70);
@@ -69,3 +143,5 @@ pub const TEXASROSE: Color = (235, 184, 91);
 pub const DARKESTBROWN: Color = (64, 46, 43);
 pub const DARKERBROWN: Color = (118, 64, 50);
 pub const DARKBROWN: Color = (161, 92, 52);
+pub const HEAL_GREEN: Color = (52, 156, 88);
+pub const RARE_BLUE: Color = (79, 143, 221);
@@ -1,10 +1,28 @@
-use specs::{Entities, Join, ReadStorage, System, Write, WriteStorage};
+use bracket_lib::terminal::Point;
+use specs::{Entities, Entity, Join, ReadExpect, ReadStorage, System, World, WorldExt, Write, WriteStorage};
 
 use crate::{
-    components::{AttackAction, AttackBonus, EntityStats, Equipped, HealAction, HealthStats, Name, SufferDamage},
+    components::{
+        ActionPoints, AttackAction, AttackCooldown, Durability, EffectiveStats, EntityStats, Equipped, HealAction,
+        HealthStats, Item, Monster, Name, Position, RangedAttackAction, Size, SufferDamage, Viewshed, WeaponCooldown,
+        WeaponReach,
+    },
+    cooldowns::{off_cooldown, start_cooldown, CooldownKind, Cooldowns},
+    durability::wear_equipped_tools,
+    factions::{are_allied, Faction},
+    map::{Map, MapRes, TileEntity},
+    player::Player,
+    rng::GameRng,
+    speed::initiative_order,
     ui::message_log::MessageLog,
 };
 
+/// Turns between attacks for anything that doesn't define its own, it can attack every turn
+pub const DEFAULT_ATTACK_COOLDOWN: u32 = 1;
+
+/// Resolves every attack, weapons with reach hit each damageable entity in a line from the attacker through the
+/// target until something solid is in the way, passing over the attacker's allies. Attackers with more initiative
+/// swing first
 pub struct AttackActionHandler;
 
 impl<'a> System<'a> for AttackActionHandler {
@@ -15,8 +33,22 @@ impl<'a> System<'a> for AttackActionHandler {
         ReadStorage<'a, EntityStats>,
         ReadStorage<'a, HealthStats>,
         ReadStorage<'a, Name>,
-        ReadStorage<'a, AttackBonus>,
+        ReadStorage<'a, AttackCooldown>,
+        WriteStorage<'a, Cooldowns>,
+        ReadStorage<'a, EffectiveStats>,
+        ReadStorage<'a, WeaponCooldown>,
+        ReadStorage<'a, WeaponReach>,
         ReadStorage<'a, Equipped>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Position>,
+        ReadExpect<'a, MapRes>,
+        WriteStorage<'a, Durability>,
+        WriteStorage<'a, Item>,
+        ReadStorage<'a, ActionPoints>,
+        ReadStorage<'a, Faction>,
+        ReadStorage<'a, Monster>,
+        ReadStorage<'a, Size>,
+        Write<'a, GameRng>,
         Entities<'a>,
     );
 
@@ -29,37 +61,249 @@ impl<'a> System<'a> for AttackActionHandler {
             stats,
             health_stats,
             names,
-            attack_bonus,
+            attack_cooldowns,
+            mut cooldowns,
+            effective_stats,
+            weapon_cooldowns,
+            weapon_reaches,
             equipped,
+            players,
+            positions,
+            map,
+            mut durabilities,
+            mut items,
+            action_points,
+            factions,
+            monsters,
+            sizes,
+            mut rng,
             entities,
         ): Self::SystemData,
     ) {
-        for (attacker, stats_set, action, name) in (&entities, &stats, &attack_actions, &names).join() {
-            if let Some(target_stats) = health_stats.get(action.target) {
-                if target_stats.defense > stats_set.set.strength {
-                    log.log("Took no damage because defense is greater");
+        let attackers = (&entities, &stats, &attack_actions).join().map(|(attacker, _, _)| attacker).collect();
+        for attacker in initiative_order(attackers, &action_points) {
+            let (stats_set, action) = match (stats.get(attacker), attack_actions.get(attacker)) {
+                (Some(stats_set), Some(action)) => (stats_set, action),
+                _ => continue,
+            };
+            if !health_stats.contains(action.target) {
+                continue;
+            }
+            let attacker_is_player = players.contains(attacker);
+            if let Some(attack_cooldown) = attack_cooldowns.get(attacker) {
+                if !off_cooldown(cooldowns.get(attacker), CooldownKind::Attack) {
+                    // monsters just lose their swing, only the player is told why
+                    if attacker_is_player {
+                        log.log("You are still recovering from your last attack");
+                    }
                     continue;
                 }
-                let target_name = names.get(action.target).unwrap();
-                let mut damage = stats_set.set.strength - target_stats.defense;
-
-                // collect all attack bonuses
-                for (bonus, _) in (&attack_bonus, &equipped).join().filter(|(_, equip)| equip.on == attacker) {
-                    damage = if bonus.0 >= 0 {
-                        damage + bonus.0 as usize
-                    } else {
-                        damage.saturating_sub(bonus.0.unsigned_abs() as usize)
-                    };
+                let weapon_turns = (&weapon_cooldowns, &equipped)
+                    .join()
+                    .filter(|(_, equip)| equip.on == attacker)
+                    .map(|(weapon, _)| weapon.0)
+                    .max();
+                let turns = weapon_turns.unwrap_or(attack_cooldown.turns);
+                start_cooldown(&mut cooldowns, attacker, CooldownKind::Attack, turns);
+            }
+
+            let reach = (&weapon_reaches, &equipped)
+                .join()
+                .filter(|(_, equip)| equip.on == attacker)
+                .map(|(weapon, _)| weapon.0)
+                .max()
+                .unwrap_or(1);
+            let targets = match (positions.get(attacker), positions.get(action.target)) {
+                (Some(from), Some(to)) if reach > 1 => {
+                    line_targets(&map.0, from, to, reach, action.target, |e| health_stats.contains(e))
+                }
+                _ => vec![action.target],
+            };
+
+            let subject = combatant_name(attacker, &names, &players, true);
+            let verb = if attacker_is_player { "hit" } else { "hits" };
+            let attacker_faction = Faction::of(attacker, &factions, &players, &monsters);
+            for target in targets {
+                let allied = match (attacker_faction, Faction::of(target, &factions, &players, &monsters)) {
+                    (Some(ours), Some(theirs)) => are_allied(ours, theirs),
+                    _ => false,
+                };
+                // only the one swung at is hit on purpose
+                if allied && target != action.target {
+                    continue;
                 }
+                let target_stats = match health_stats.get(target) {
+                    Some(target_stats) => target_stats,
+                    None => continue,
+                };
+                let object = combatant_name(target, &names, &players, false);
 
-                log.log(format!("{} dealt {} damage to {}", name, damage, target_name));
-                SufferDamage::new_damage(&mut suffer_damage, action.target, -(damage as i32));
+                // only rolled for beings that can dodge so everything else hits like it always has
+                let dodge = sizes.get(target).copied().unwrap_or_default().dodge_chance();
+                if dodge > 0 && rng.0.range(0, 100) < dodge as i32 {
+                    let (swing, miss) = if attacker_is_player { ("swing at", "miss") } else { ("swings at", "misses") };
+                    log.log(format!("{} {} {} but {}", subject, swing, object, miss));
+                    continue;
+                }
+
+                let defense = effective_stats.get(target).map_or(target_stats.defense, |stats| stats.defense);
+                // equipped weapons are already counted in the attacker's effective stats
+                let attack_bonus = effective_stats.get(attacker).map_or(0, |stats| stats.attack_bonus)
+                    + sizes.get(attacker).copied().unwrap_or_default().damage_bonus();
+                let damage = match attack_damage(stats_set.set.strength, attack_bonus, defense) {
+                    Some(damage) => damage,
+                    None => {
+                        let does = if attacker_is_player { "do" } else { "does" };
+                        log.log(format!("{} {} {} but {} no damage", subject, verb, object, does));
+                        continue;
+                    }
+                };
+
+                log.log(format!("{} {} {} for {}", subject, verb, object, damage));
+                SufferDamage::new_damage(&mut suffer_damage, target, Some(attacker), -(damage as i32));
+            }
+
+            for weapon in wear_equipped_tools(attacker, &equipped, &mut durabilities, &mut items, &entities) {
+                if attacker_is_player {
+                    if let Some(weapon_name) = names.get(weapon) {
+                        log.log(format!("Your {} breaks!", weapon_name));
+                    }
+                }
             }
         }
         attack_actions.clear();
     }
 }
 
+/// Damage of a hit from an attacker with `strength` and `attack_bonus` on a target with `defense`, `None` when the
+/// defense is higher than the strength and the hit does nothing at all
+pub fn attack_damage(strength: usize, attack_bonus: i32, defense: usize) -> Option<usize> {
+    if defense > strength {
+        return None;
+    }
+    let damage = strength - defense;
+    Some(if attack_bonus >= 0 {
+        damage + attack_bonus as usize
+    } else {
+        damage.saturating_sub(attack_bonus.unsigned_abs() as usize)
+    })
+}
+
+/// What a hit from `attacker` on `target` would do with their stats right now, `None` when either can't fight
+pub fn forecast_damage(ecs: &World, attacker: Entity, target: Entity) -> Option<usize> {
+    let strength = ecs.read_storage::<EntityStats>().get(attacker)?.set.strength;
+    let effective_stats = ecs.read_storage::<EffectiveStats>();
+    let defense = ecs.read_storage::<HealthStats>().get(target)?.defense;
+    let defense = effective_stats.get(target).map_or(defense, |stats| stats.defense);
+    let attack_bonus = effective_stats.get(attacker).map_or(0, |stats| stats.attack_bonus)
+        + ecs.read_storage::<Size>().get(attacker).copied().unwrap_or_default().damage_bonus();
+    Some(attack_damage(strength, attack_bonus, defense).unwrap_or(0))
+}
+
+/// Everything hit by an attack from `from` at the adjacent `to` that carries on for `reach` tiles. The line stops
+/// at the map's edge, a blocked tile or a blocking entity that can't be damaged. Attacks at anything not adjacent
+/// only hit the `target`
+fn line_targets(
+    map: &Map,
+    from: &Position,
+    to: &Position,
+    reach: u32,
+    target: Entity,
+    damageable: impl Fn(Entity) -> bool,
+) -> Vec<Entity> {
+    let dx = to.x as i32 - from.x as i32;
+    let dy = to.y as i32 - from.y as i32;
+    if dx.abs() > 1 || dy.abs() > 1 {
+        return vec![target];
+    }
+
+    let mut targets = vec![target];
+    for step in 1..=reach as i32 {
+        let point = Point::new(from.x as i32 + dx * step, from.y as i32 + dy * step);
+        if !map.in_bounds(point) {
+            break;
+        }
+        let idx = map.xy_to_idx(point.x as usize, point.y as usize);
+        if map.tiles.get(idx).is_some_and(|tile| tile.is_blocked) {
+            break;
+        }
+        let mut solid = false;
+        for tile_entity in map.tile_entities[idx].iter() {
+            match tile_entity {
+                TileEntity::Blocking(entity) if damageable(*entity) => {
+                    if !targets.contains(entity) {
+                        targets.push(*entity);
+                    }
+                }
+                TileEntity::Blocking(_) => solid = true,
+                _ => {}
+            }
+        }
+        if solid {
+            break;
+        }
+    }
+    targets
+}
+
+/// Turns ranged attacks into normal attacks when the attacker can still see their target, ran right before
+/// `AttackActionHandler` so the cooldown and damage are worked out the same way
+pub struct RangedAttackHandler;
+
+impl<'a> System<'a> for RangedAttackHandler {
+    type SystemData = (
+        WriteStorage<'a, RangedAttackAction>,
+        WriteStorage<'a, AttackAction>,
+        ReadStorage<'a, Viewshed>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Player>,
+        Write<'a, MessageLog>,
+        Entities<'a>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut ranged_actions,
+            mut attack_actions,
+            viewsheds,
+            positions,
+            names,
+            players,
+            mut log,
+            entities,
+        ): Self::SystemData,
+    ) {
+        for (attacker, action, view) in (&entities, &ranged_actions, &viewsheds).join() {
+            let in_sight = positions.get(action.target).is_some_and(|pos| view.tiles.contains(&pos.to_point()));
+            if !in_sight {
+                if players.contains(attacker) {
+                    let target = combatant_name(action.target, &names, &players, false);
+                    log.log(format!("You lose sight of {}", target));
+                }
+                continue;
+            }
+            let _ = attack_actions.insert(attacker, AttackAction { target: action.target });
+        }
+        ranged_actions.clear();
+    }
+}
+
+/// Gets the name used for a combatant in the log, the player is always referred to as "you" with
+/// `at_sentence_start` deciding the capitalization
+fn combatant_name(
+    entity: Entity,
+    names: &ReadStorage<Name>,
+    players: &ReadStorage<Player>,
+    at_sentence_start: bool,
+) -> String {
+    if players.contains(entity) {
+        return if at_sentence_start { "You" } else { "you" }.to_string();
+    }
+    names.get(entity).map_or_else(|| Name::missing_being_name().0, |name| name.0.clone())
+}
+
 pub struct HealActionHandler;
 
 impl<'a> System<'a> for HealActionHandler {
@@ -73,3 +317,17 @@ impl<'a> System<'a> for HealActionHandler {
         heal_actions.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attack_damage_is_strength_past_defense_plus_bonus() {
+        assert_eq!(attack_damage(5, 0, 2), Some(3));
+        assert_eq!(attack_damage(5, 2, 2), Some(5));
+        assert_eq!(attack_damage(5, -4, 2), Some(0));
+        assert_eq!(attack_damage(2, 0, 2), Some(0));
+        assert_eq!(attack_damage(2, 10, 3), None);
+    }
+}
@@ -1,4 +1,5 @@
 pub(crate) use crate::{
+    colors::Color,
     debug::CLEAR,
     fishing::{Cursor, GoalBar},
     items::ItemQty,
@@ -7,13 +8,13 @@ pub(crate) use crate::{
 };
 use std::{collections::HashSet, fmt::Display, str::FromStr, time::Duration};
 
-use bracket_lib::terminal::{ColorPair, Degrees, Point, PointF};
+use bracket_lib::terminal::{ColorPair, Degrees, Point, PointF, RGBA};
 use serde::{Deserialize, Serialize};
 #[allow(deprecated)] // deprecated but specs uses it so it's not my choice, maybe this gets changed in new version
 use specs::error::NoError;
 use specs::{
     saveload::{ConvertSaveload, Marker},
-    Component, ConvertSaveload, Entity, NullStorage, VecStorage,
+    Component, ConvertSaveload, Entity, FlaggedStorage, NullStorage, VecStorage,
 };
 
 use crate::{
@@ -21,13 +22,15 @@ use crate::{
     indexing::idx_to_point,
     inventory::UseMenuResult,
     items::ItemID,
+    status::StatusKind,
+    tile_animation::AGGRO_INDICATOR_LIFETIME,
 };
 
 #[derive(Debug, Component, ConvertSaveload, Clone)]
 #[storage(VecStorage)]
 pub struct Renderable {
     pub color_pair: ColorPair,
-    pub atlas_index: u8,
+    pub atlas_index: usize,
     pub z_priority: u32,
 }
 
@@ -37,11 +40,34 @@ pub struct Renderable {
 #[storage(NullStorage)]
 pub struct LevelPersistent {}
 
+/// The entity is in the map's static index so the indexing systems skip it. Only entities that never move are
+/// marked, it is not saved so everything is indexed again after loading
+#[derive(Component, Default, Clone)]
+#[storage(NullStorage)]
+pub struct StaticIndexed {}
+
+/// Holds the spot of an entity on a map layer the player is not on, its `Position` is given back once the
+/// player returns so nothing on other layers is indexed, drawn, or simulated
+#[derive(Component, Clone, ConvertSaveload)]
+#[storage(VecStorage)]
+pub struct LayerStash {
+    pub layer: usize,
+    pub pos: Position,
+    /// The entity was drawn with a `Transform` which is recreated when it comes back
+    pub fancy: bool,
+}
+
 impl Renderable {
     /// Creates a renderable with a clear bg and specified parts
-    pub fn clear_bg(atlas_index: u8, fg: (u8, u8, u8), z_priority: u32) -> Self {
+    pub fn clear_bg(atlas_index: usize, fg: (u8, u8, u8), z_priority: u32) -> Self {
         Self { color_pair: ColorPair::new(fg, CLEAR), atlas_index, z_priority }
     }
+
+    /// Tints the tile under the sprite, the tint is blended over the map by its alpha
+    pub fn with_background(mut self, (r, g, b, a): (u8, u8, u8, u8)) -> Self {
+        self.color_pair.bg = RGBA::from_u8(r, g, b, a);
+        self
+    }
 }
 
 #[derive(Component, Clone, Copy)]
@@ -160,29 +186,28 @@ pub struct FishAction {
     pub target: Position, // mainly just for finding where the fishing rod will be spawned
 }
 
+/// Where the entity has its line cast for as long as it is fishing
 #[derive(Component)]
 #[storage(VecStorage)]
-pub struct WaitingForFish {
-    pub attempts: usize,
-    pub time_since_last_attempt: Duration,
+pub struct FishingSpot {
+    pub pos: Position,
 }
 
-impl WaitingForFish {
-    pub fn new(attempts: usize) -> Self {
-        Self { attempts, time_since_last_attempt: Duration::new(0, 0) }
-    }
+/// Bait used on the fishing line, it makes fish bite sooner and is eaten by the next fish caught.
+/// `potency` comes from the bait's consumable amount
+#[derive(Component, Clone, Serialize, Deserialize)]
+#[storage(VecStorage)]
+pub struct BaitActive {
+    pub potency: usize,
 }
 
-#[derive(Component, Default)]
-#[storage(NullStorage)]
-// TODO: put type of fish on this, ItemID will probably do
-pub struct FishOnTheLine {}
-
+/// Flagged so `NamedEntities` can follow names being added and removed
 #[derive(Component, Clone, PartialEq, Eq, PartialOrd, Ord, ConvertSaveload)]
-#[storage(VecStorage)]
+#[storage(FlaggedStorage)]
 pub struct Name(pub String);
 
 const MISSING_ITEM_NAME: &str = "MISSING_ITEM_NAME";
+const MISSING_BEING_NAME: &str = "MISSING_BEING_NAME";
 
 impl Name {
     pub fn new(name: impl ToString) -> Self {
@@ -192,6 +217,10 @@ impl Name {
     pub fn missing_item_name() -> Self {
         Name::new(MISSING_ITEM_NAME)
     }
+
+    pub fn missing_being_name() -> Self {
+        Name::new(MISSING_BEING_NAME)
+    }
 }
 
 impl Display for Name {
@@ -210,6 +239,7 @@ pub struct RandomWalkerAI {}
 #[storage(VecStorage)]
 pub struct GoalMoverAI {
     pub current: Option<Entity>,
+    /// Tags or names of what the entity goes after
     pub desires: Vec<Name>,
     pub goal_range: usize,
 }
@@ -218,6 +248,84 @@ impl GoalMoverAI {
     pub fn with_desires(desires: &[Name], goal_range: usize) -> Self {
         Self { current: None, desires: desires.to_vec(), goal_range }
     }
+
+    /// A desire is a tag the entity has, goals written before tags existed name the entity instead
+    pub fn desires(&self, name: &Name, tags: Option<&Tags>) -> bool {
+        self.desires.iter().any(|desire| desire == name || tags.is_some_and(|tags| tags.has(&desire.0)))
+    }
+}
+
+/// Labels from the raws like "edible" or "npc" so entities can be looked up by what they are instead of by a
+/// concrete component. They are not saved, loading a game gives them back from the raws
+#[derive(Component, Debug, Clone, Default)]
+#[storage(VecStorage)]
+pub struct Tags(pub HashSet<String>);
+
+impl Tags {
+    /// `None` when there are no tags so untagged entities don't get the component
+    pub fn from_raw(tags: &Option<Vec<String>>) -> Option<Self> {
+        let tags: HashSet<String> = tags.iter().flatten().map(|tag| tag.to_lowercase()).collect();
+        (!tags.is_empty()).then_some(Self(tags))
+    }
+
+    /// Tags are matched ignoring case
+    pub fn has(&self, tag: &str) -> bool {
+        self.0.contains(&tag.to_lowercase())
+    }
+}
+
+/// The time an entity has to act with. Each turn it gains points based on `speed` and every action spends them,
+/// it can act again while it has points left
+#[derive(Debug, Component, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct ActionPoints {
+    pub speed: u32,
+    pub points: i32,
+    /// Entities with more initiative have their moves and attacks carried out first
+    #[serde(default)]
+    pub initiative: u32,
+}
+
+impl ActionPoints {
+    /// Initiative starts out as the speed so faster entities act first
+    pub fn new(speed: u32) -> Self {
+        Self { speed, points: 0, initiative: speed }
+    }
+
+    pub fn with_initiative(mut self, initiative: u32) -> Self {
+        self.initiative = initiative;
+        self
+    }
+}
+
+/// Makes the entity follow the player around, attacking anything that goes after either of them
+#[derive(Component, Clone)]
+#[storage(VecStorage)]
+pub struct Companion {
+    /// The companion will not move closer to the player once within this many tiles
+    pub follow_distance: usize,
+}
+
+/// Makes the entity go after beings of a hostile faction once they come close, it wanders around while it isn't chasing
+#[derive(Component, Clone)]
+#[storage(VecStorage)]
+pub struct Monster {
+    /// The monster starts chasing when a target comes within this many tiles
+    pub aggro_range: u32,
+    /// The monster loses interest once its target gets this many tiles away
+    pub give_up_range: u32,
+    /// The being currently being chased
+    pub target: Option<Entity>,
+}
+
+impl Monster {
+    pub fn new(aggro_range: u32, give_up_range: u32) -> Self {
+        Self { aggro_range, give_up_range, target: None }
+    }
+
+    pub fn chasing(&self) -> bool {
+        self.target.is_some()
+    }
 }
 
 #[derive(Debug, Component, Clone, ConvertSaveload)]
@@ -272,6 +380,37 @@ pub enum ToolType {
     Shovel,
 }
 
+/// Lights up the tiles around the entity, fading out over `radius` tiles
+#[derive(Debug, Component, Clone, Copy, Serialize, Deserialize)]
+#[storage(VecStorage)]
+pub struct LightSource {
+    pub radius: u32,
+    pub color: (u8, u8, u8),
+}
+
+/// Gives off small particles that drift and fade, ie. embers from a fire
+#[derive(Debug, Component, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[storage(VecStorage)]
+pub struct ParticleEmitter {
+    pub atlas_index: usize,
+    pub color: (u8, u8, u8),
+    /// Particles given off each second on average
+    pub per_second: f32,
+    /// How long a particle takes to fade away
+    pub lifetime_ms: u64,
+    /// Tiles a second the particles move, each one strays from it a little
+    pub drift: (f32, f32),
+}
+
+/// A sprite drawn with a `Transform` that moves `velocity` tiles a second and fades out as it ages
+#[derive(Component, Clone)]
+#[storage(VecStorage)]
+pub struct Particle {
+    pub velocity: PointF,
+    pub lifetime: Duration,
+    pub time_left: Duration,
+}
+
 #[derive(Debug, Component)]
 #[storage(VecStorage)]
 pub struct BreakAction {
@@ -284,6 +423,13 @@ pub struct AttackAction {
     pub target: Entity,
 }
 
+/// An attack from a distance, it lands as a normal attack as long as the target can still be seen
+#[derive(Debug, Component)]
+#[storage(VecStorage)]
+pub struct RangedAttackAction {
+    pub target: Entity,
+}
+
 #[derive(Debug, Component)]
 #[storage(VecStorage)]
 pub struct MoveAction {
@@ -296,6 +442,14 @@ impl MoveAction {
     }
 }
 
+/// Cooks `ingredient` from the cook's bag at the `station` next to them
+#[derive(Component)]
+#[storage(VecStorage)]
+pub struct CookAction {
+    pub station: Entity,
+    pub ingredient: Entity,
+}
+
 #[derive(Component)]
 #[storage(VecStorage)]
 pub struct CraftAction {
@@ -318,7 +472,8 @@ pub struct HealAction {
 #[derive(Debug, Component)]
 #[storage(VecStorage)]
 pub struct SufferDamage {
-    pub amount: Vec<i32>,
+    /// Every hit taken this turn and who dealt it, `None` is damage from the environment like poison
+    pub amount: Vec<(Option<Entity>, i32)>,
 }
 
 /// Used to delete an entity when a condition is satisfied
@@ -329,6 +484,29 @@ pub enum DeleteCondition {
     ActivityFinish(Entity), // Condition is based on when the entity finishes their activity
 }
 
+/// Fades the entity out over `total` before deleting it, it is stripped of anything that could be interacted with
+/// when the fade starts
+#[derive(Debug, Component, Clone, Copy)]
+#[storage(VecStorage)]
+pub struct FadingOut {
+    pub remaining: Duration,
+    pub total: Duration,
+}
+
+impl FadingOut {
+    pub fn new(duration: Duration) -> Self {
+        Self { remaining: duration, total: duration }
+    }
+
+    /// Goes from 1.0 when the fade starts down to 0.0 when the entity is deleted
+    pub fn alpha(&self) -> f32 {
+        if self.total.is_zero() {
+            return 0.0;
+        }
+        self.remaining.as_secs_f32() / self.total.as_secs_f32()
+    }
+}
+
 /// Used to signal to other systems that an entity finished their activity
 #[derive(Component, Default)]
 #[storage(NullStorage)]
@@ -357,14 +535,20 @@ pub struct InBag {
 #[storage(VecStorage)]
 pub enum Consumable {
     InstantRegen(usize),
-    // Effect
+    Bait(usize),
+    /// Applies a timed status effect of the kind with the magnitude
+    Status(StatusKind, usize),
+    /// Fills the consumer's hunger back up by the amount
+    Feed(usize),
 }
 
 impl Consumable {
-    pub fn from_str(str: &str, amt: usize) -> Self {
+    pub fn from_str(str: &str, amt: usize) -> Option<Self> {
         match str {
-            "instant_regen" => Self::InstantRegen(amt),
-            _ => panic!("Bad string based to consumable."),
+            "instant_regen" => Some(Self::InstantRegen(amt)),
+            "bait" => Some(Self::Bait(amt)),
+            "feed" => Some(Self::Feed(amt)),
+            other => StatusKind::from_str(other).map(|kind| Self::Status(kind, amt)),
         }
     }
 }
@@ -394,7 +578,7 @@ pub struct Equipable {
 }
 
 impl Equipable {
-    pub fn from_str(str: &str) -> Self {
+    pub fn from_str(str: &str) -> Option<Self> {
         let slot = match str {
             "Hand" => EquipmentSlot::Hand,
             "Torso" => EquipmentSlot::Torso,
@@ -402,12 +586,9 @@ impl Equipable {
             "Legs" => EquipmentSlot::Legs,
             "Feet" => EquipmentSlot::Feet,
             "Tail" => EquipmentSlot::Tail,
-            _ => {
-                eprintln!("{} is not a valid name for an equipment slot, using Head instead", str);
-                EquipmentSlot::Head
-            }
+            _ => return None,
         };
-        Equipable { slot }
+        Some(Equipable { slot })
     }
 }
 
@@ -446,7 +627,14 @@ impl EquipmentSlots {
 #[derive(Component)]
 #[storage(VecStorage)]
 pub struct PickupAction {
-    pub item: Entity,
+    /// Picked up in order, anything that doesn't fit in the backpack stays on the ground
+    pub items: Vec<Entity>,
+}
+
+impl PickupAction {
+    pub fn one(item: Entity) -> Self {
+        Self { items: vec![item] }
+    }
 }
 
 /// Water ripe for swimming in or boating over or building a pier to fish off
@@ -454,11 +642,115 @@ pub struct PickupAction {
 #[storage(NullStorage)]
 pub struct Water {}
 
+/// A tile that hurts whatever ends its turn on it, see `hazards.rs`
+#[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[storage(VecStorage)]
+pub struct Hazard {
+    pub damage: u32,
+    /// Beings tagged with this, or wearing something tagged with it, are not hurt
+    pub immunity: Option<String>,
+}
+
+/// Whether an item drifts off with the current or sinks for good when it ends up on water, see `buoyancy.rs`
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[storage(VecStorage)]
+#[serde(rename_all = "lowercase")]
+pub enum Buoyancy {
+    Floats,
+    #[default]
+    Sinks,
+}
+
+/// An item that lets whoever carries it travel over water, see `boating.rs`
+#[derive(Component, Default, Serialize, Deserialize, Clone)]
+#[storage(NullStorage)]
+pub struct Boat {}
+
+/// Water flowing one way, anything floating on it drifts along every few turns, see `currents.rs`
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+#[storage(VecStorage)]
+pub struct Current(pub Facing);
+
+/// The being is out on the water in a boat, water is walkable for them and land is only reached by going ashore
+#[derive(Component, Default, Serialize, Deserialize, Clone)]
+#[storage(NullStorage)]
+pub struct Boating {}
+
+/// The being flies over deep water, see `collision.rs`
+#[derive(Component, Default, Clone)]
+#[storage(NullStorage)]
+pub struct Flying {}
+
+/// The being drifts through walls and other terrain, see `collision.rs`
+#[derive(Component, Clone)]
+pub struct Phasing {
+    /// Whether the player bumps into the being or walks straight through it
+    pub blocks_player: bool,
+}
+
+/// How big a being is, read from the raws and `Medium` for beings that don't say
+/// - Small beings dodge one in five melee hits and deal a point less damage
+/// - Medium beings are the baseline everything else is measured against
+/// - Large beings deal two more damage, stand their ground against the ground slam and can't squeeze through a gap
+///   walled in on both sides, see `collision.rs`
+///
+/// Small and medium beings are knocked back a tile by the ground slam
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[storage(VecStorage)]
+#[serde(rename_all = "lowercase")]
+pub enum Size {
+    Small,
+    #[default]
+    Medium,
+    Large,
+}
+
+impl Size {
+    /// Added to the damage of every hit the being lands
+    pub fn damage_bonus(&self) -> i32 {
+        match self {
+            Size::Small => -1,
+            Size::Medium => 0,
+            Size::Large => 2,
+        }
+    }
+
+    /// Chance out of 100 that a melee hit on the being misses
+    pub fn dodge_chance(&self) -> u32 {
+        match self {
+            Size::Small => 20,
+            Size::Medium | Size::Large => 0,
+        }
+    }
+
+    pub fn resists_knockback(&self) -> bool {
+        *self == Size::Large
+    }
+
+    /// Whether a gap with walls on either side is too tight for the being
+    pub fn too_big_for_gaps(&self) -> bool {
+        *self == Size::Large
+    }
+}
+
 /// A delicious treat loved by many animals and other beings...
 #[derive(Component, Default, Serialize, Deserialize, Clone)]
 #[storage(NullStorage)]
 pub struct Grass {}
 
+/// Lets items be stored inside the entity, like a chest. Stored items are held the same way as a backpack's
+#[derive(Component, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct Container {
+    /// Distinct stacks it can hold
+    pub slots: usize,
+}
+
+/// A fire or stove that raw food can be cooked at
+#[derive(Component, Default, Serialize, Deserialize, Clone)]
+#[storage(NullStorage)]
+pub struct CookingStation {}
+
 #[derive(Component, ConvertSaveload, Clone)]
 #[storage(VecStorage)]
 pub struct Interactor {
@@ -471,6 +763,40 @@ impl Interactor {
     }
 }
 
+/// The direction an entity is looking, used to target the tile in front of them
+#[derive(Component, Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[storage(VecStorage)]
+pub enum Facing {
+    Up,
+    #[default]
+    Down,
+    Left,
+    Right,
+}
+
+impl Facing {
+    /// Gets the facing of a movement, diagonal or no movement keeps `None` since they have no clear facing
+    pub fn from_delta(delta_x: i32, delta_y: i32) -> Option<Self> {
+        match (delta_x.signum(), delta_y.signum()) {
+            (0, -1) => Some(Facing::Up),
+            (0, 1) => Some(Facing::Down),
+            (-1, 0) => Some(Facing::Left),
+            (1, 0) => Some(Facing::Right),
+            _ => None,
+        }
+    }
+
+    /// The offset to the tile being faced
+    pub fn delta(&self) -> Point {
+        match self {
+            Facing::Up => Point::new(0, -1),
+            Facing::Down => Point::new(0, 1),
+            Facing::Left => Point::new(-1, 0),
+            Facing::Right => Point::new(1, 0),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub enum InteractorMode {
     Reactive,
@@ -498,6 +824,174 @@ pub struct SelectedInventoryItem {
 #[storage(VecStorage)]
 pub struct AttackBonus(pub i32);
 
+/// Stats an item adds to whoever has it equipped besides attack, negative values take away. Read from the raws
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[storage(VecStorage)]
+#[serde(default)]
+pub struct StatModifiers {
+    pub defense: i32,
+    pub speed: i32,
+}
+
+/// An equipper's stats with everything it has equipped counted, see `equipment.rs`. Combat, movement and the ui
+/// read these in place of the base stats whenever they are there
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+#[storage(VecStorage)]
+pub struct EffectiveStats {
+    pub attack_bonus: i32,
+    pub defense: usize,
+    pub speed: u32,
+}
+
+/// Turns a weapon needs between swings, it replaces the wielder's own cooldown while equipped
+#[derive(Component, Clone, ConvertSaveload)]
+#[storage(VecStorage)]
+pub struct WeaponCooldown(pub u32);
+
+/// Uses a tool has left before it breaks, it wears down each time it hits something while equipped
+#[derive(Debug, Component, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[storage(VecStorage)]
+pub struct Durability {
+    pub current: u32,
+    pub max: u32,
+}
+
+impl Durability {
+    pub fn new(max: u32) -> Self {
+        Self { current: max, max }
+    }
+
+    /// Uses the tool once, true when that use broke it. A broken tool starts over at full durability since the
+    /// next one in its stack takes over
+    pub fn wear(&mut self) -> bool {
+        self.current = self.current.saturating_sub(1);
+        if self.current == 0 {
+            self.current = self.max;
+            return true;
+        }
+        false
+    }
+
+    /// Restores up to `amount` uses, returns how many were restored
+    pub fn repair(&mut self, amount: u32) -> u32 {
+        let restored = amount.min(self.max - self.current);
+        self.current += restored;
+        restored
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.current >= self.max
+    }
+}
+
+/// How many tiles in a straight line a weapon hits with each swing, 1 only hits what is being attacked
+#[derive(Component, Clone, ConvertSaveload)]
+#[storage(VecStorage)]
+pub struct WeaponReach(pub u32);
+
+/// Limits how often an entity can land an attack. `turns` is the time from one attack until the next can be made,
+/// 1 lets the entity attack every turn. The time left is kept in the entity's `Cooldowns`, older saves also wrote a
+/// `remaining` count which is ignored when they are loaded
+#[derive(Debug, Component, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct AttackCooldown {
+    pub turns: u32,
+}
+
+impl AttackCooldown {
+    pub fn new(turns: u32) -> Self {
+        Self { turns }
+    }
+}
+
+pub const HOTBAR_SLOTS: usize = 5;
+
+/// Items the player can use without opening the inventory, slot 0 is bound to the 1 key
+#[derive(Component, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+#[storage(VecStorage)]
+pub struct Hotbar {
+    pub slots: [Option<ItemID>; HOTBAR_SLOTS],
+}
+
+impl Hotbar {
+    /// Binds the item to the slot, it is taken out of any other slot it was in so each item shows up once
+    pub fn assign(&mut self, slot: usize, id: ItemID) {
+        for assigned in self.slots.iter_mut().filter(|assigned| **assigned == Some(id)) {
+            *assigned = None;
+        }
+        self.slots[slot] = Some(id);
+    }
+}
+
+/// A planted crop, the growth stages are defined by the crop named by the entity's `Name`
+#[derive(Debug, Component, Serialize, Deserialize, Clone, Default)]
+#[storage(VecStorage)]
+pub struct Crop {
+    pub stage: usize,
+    pub turns_in_stage: usize,
+}
+
+impl Crop {
+    pub fn new() -> Self {
+        Self { stage: 0, turns_in_stage: 0 }
+    }
+}
+
+/// An item that is used on a tile picked by the player instead of on themself
+#[derive(Component, Clone, ConvertSaveload)]
+#[storage(VecStorage)]
+pub struct TargetedUse {
+    pub effect: TargetEffect,
+    /// How many tiles away from the user the target can be
+    pub range: u32,
+}
+
+/// What happens to the targeted tile, the value is the name of what gets made
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum TargetEffect {
+    /// Grows the world object on an open grass tile
+    Grow(String),
+    /// Trades the item for the filled item when used on water
+    Fill(String),
+    /// Plants the crop on tillable soil
+    Plant(String),
+    /// Lands on the tile and goes off after a short fuse
+    Explode(Blast),
+}
+
+/// What a thrown explosive hits when it goes off
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Blast {
+    /// Tiles out from where it lands, anything behind a wall is sheltered from it
+    pub radius: u32,
+    /// Taken by every breakable and being caught in it, less their defense
+    pub damage: u32,
+}
+
+/// A thrown explosive counting down, it goes off when its `DeleteCondition::Timed` runs out
+#[derive(Component, Clone, Copy)]
+#[storage(VecStorage)]
+pub struct LitFuse {
+    pub blast: Blast,
+    pub thrower: Entity,
+}
+
+impl TargetEffect {
+    pub fn from_str(str: &str, result: String) -> Option<Self> {
+        match str {
+            "grow" => Some(Self::Grow(result)),
+            "fill" => Some(Self::Fill(result)),
+            "plant" => Some(Self::Plant(result)),
+            _ => None,
+        }
+    }
+}
+
+/// How many tiles out from the wielder a mining hit reaches, 1 hits all 8 surrounding tiles
+#[derive(Component, Clone, ConvertSaveload)]
+#[storage(VecStorage)]
+pub struct MiningArea(pub u32);
+
 #[derive(Component)]
 #[storage(NullStorage)]
 pub struct GameAction {}
@@ -512,6 +1006,22 @@ pub struct FishingMinigame {
     pub mode: FishingBehavior,
 }
 
+/// The fish that bit, it is landed once the fisher's activity finishes while still hooked
+#[derive(Component, Clone, Copy)]
+#[storage(VecStorage)]
+pub struct FishOnTheLine {
+    pub fish: ItemID,
+}
+
+/// The last stand of a hooked fish, it needs to be reeled `reels_needed` times before the time runs out or it escapes
+#[derive(Component)]
+#[storage(VecStorage)]
+pub struct FishStruggle {
+    pub reels_needed: u32,
+    pub reels: u32,
+    pub time_left: Duration,
+}
+
 #[derive(Component, Clone)]
 #[storage(VecStorage)]
 pub struct SizeFlexor {
@@ -526,6 +1036,39 @@ impl SizeFlexor {
     }
 }
 
+/// Slides the sprite over from where the entity was to its `Position` instead of it jumping there, ie. after a dash
+#[derive(Component, Clone)]
+#[storage(VecStorage)]
+pub struct Slide {
+    /// The `Transform` was only added for the slide and is removed once it ends
+    pub owns_transform: bool,
+}
+
+/// Scales an entity up from nothing when it first appears, only the sprite is scaled so its `Position` is untouched
+#[derive(Component, Clone)]
+#[storage(VecStorage)]
+pub struct SpawnPop {
+    pub elapsed: Duration,
+    pub duration: Duration,
+    /// The `Transform` was only added for the pop and is removed once it ends
+    pub owns_transform: bool,
+}
+
+impl SpawnPop {
+    /// Scale of the sprite at this point in the pop, eases out so it slows as it reaches full size
+    pub fn scale(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        let t = (self.elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.0);
+        1.0 - (1.0 - t) * (1.0 - t)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
 #[derive(Component, Clone)]
 #[storage(VecStorage)]
 pub struct GlyphFlash {
@@ -538,4 +1081,117 @@ pub struct GlyphFlash {
 pub struct Viewshed {
     pub tiles: HashSet<Point>,
     pub range: usize,
+    /// Set when the owner moves so the view is only recalculated when it could have changed. Saves from before it
+    /// existed don't have it and start dirty so their view is worked out on load
+    #[serde(default = "default_dirty")]
+    pub dirty: bool,
+}
+
+fn default_dirty() -> bool {
+    true
+}
+
+impl Viewshed {
+    pub fn new(range: usize) -> Self {
+        Self { tiles: HashSet::new(), range, dirty: true }
+    }
+}
+
+/// Text that rises up from where it was spawned and fades out over its lifetime
+#[derive(Component, Clone)]
+#[storage(VecStorage)]
+pub struct FloatingText {
+    pub text: String,
+    pub color: Color,
+    /// The world position the text was spawned at, used to offset stacking texts
+    pub origin: Position,
+    pub lifetime: Duration,
+}
+
+/// What a monster's aggro indicator says about it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alert {
+    /// Shown as "!" when the monster starts chasing the player
+    Noticed,
+    /// Shown as "?" when the monster loses track of the player
+    Lost,
+}
+
+/// Pops above a monster for a moment when its chase state changes, fading out as `time_left` runs down
+#[derive(Component, Clone)]
+#[storage(VecStorage)]
+pub struct AggroIndicator {
+    pub alert: Alert,
+    pub time_left: Duration,
+}
+
+impl AggroIndicator {
+    pub fn new(alert: Alert) -> Self {
+        Self { alert, time_left: AGGRO_INDICATOR_LIFETIME }
+    }
+
+    /// 1.0 when fresh down to 0.0 when it is about to disappear
+    pub fn alpha(&self) -> f32 {
+        self.time_left.as_secs_f32() / AGGRO_INDICATOR_LIFETIME.as_secs_f32()
+    }
+}
+
+/// Lines a being can say, the time between them is kept in its `Cooldowns`
+#[derive(Component, Clone)]
+#[storage(VecStorage)]
+pub struct Quips {
+    pub lines: Vec<String>,
+}
+
+impl Quips {
+    pub fn new(lines: &[String]) -> Self {
+        Self { lines: lines.to_vec() }
+    }
+}
+
+/// Gold carried by an entity
+#[derive(Component, Serialize, Deserialize, Clone, Default)]
+#[storage(VecStorage)]
+pub struct Currency {
+    pub gold: u32,
+}
+
+/// Beings the entity has landed the killing blow on
+#[derive(Component, Serialize, Deserialize, Clone, Default)]
+#[storage(VecStorage)]
+pub struct KillCount(pub u32);
+
+/// Items a being sells, anything the player carries can be sold back to them
+#[derive(Component, Clone)]
+#[storage(VecStorage)]
+pub struct Shop {
+    pub stock: Vec<ItemID>,
+}
+
+/// Lines a being says when the player talks to them, shown one at a time
+#[derive(Component, Clone)]
+#[storage(VecStorage)]
+pub struct Dialogue {
+    pub lines: Vec<String>,
+}
+
+/// Cycles the sprite through `frames`. Every idle animation reads the same `IdleClock` so ones sharing an
+/// offset stay in step
+#[derive(Component, Clone)]
+#[storage(VecStorage)]
+pub struct IdleAnimation {
+    pub frames: Vec<usize>,
+    pub frame_time: Duration,
+    pub offset: Duration,
+}
+
+impl IdleAnimation {
+    /// The atlas index to draw at `clock`, None when there are no frames
+    pub fn frame(&self, clock: Duration) -> Option<usize> {
+        if self.frames.is_empty() {
+            return None;
+        }
+        let step = (clock + self.offset).as_millis() / self.frame_time.as_millis().max(1);
+        self.frames.get((step % self.frames.len() as u128) as usize).copied()
+    }
 }
@@ -1,6 +1,19 @@
-use std::fmt::Display;
+use std::{cmp::Ordering, fmt::Display};
 
-use crate::settings::SettingsConfig;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use specs::Entity;
+
+use crate::{
+    components::{Item, Name},
+    inventory::BatchDrop,
+    settings::SettingsConfig,
+    ui::modal::Modal,
+    DISPLAY_HEIGHT, DISPLAY_WIDTH,
+};
+
+/// Text is drawn at twice its font size, filling a 1280x960 window
+pub const DEFAULT_DISPLAY_SCALE: usize = 2;
 
 /// Contains all configs for various game things
 /// Note - For now it resides on the `State` variable since we shouldn't have systems modifying it as that
@@ -12,12 +25,57 @@ pub struct ConfigMaster {
 
 impl ConfigMaster {
     pub fn load() -> Self {
-        Self { inventory: InventoryConfig { sort_mode: SortMode::NameABC }, general: SettingsConfig::load() }
+        Self { inventory: InventoryConfig::default(), general: SettingsConfig::load() }
+    }
+}
+
+/// How big the window is made, only read when the game starts
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct DisplayConfig {
+    /// How many screen pixels each pixel of the text font takes up, must be at least 1
+    #[serde(default = "default_display_scale")]
+    pub scale: usize,
+    #[serde(default)]
+    pub fullscreen: bool,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self { scale: default_display_scale(), fullscreen: false }
+    }
+}
+
+fn default_display_scale() -> usize {
+    DEFAULT_DISPLAY_SCALE
+}
+
+impl DisplayConfig {
+    /// The scale from the config, or the default when the config's can't be used
+    pub fn window_scale(&self) -> usize {
+        if self.scale < 1 {
+            warn!("Display scale {} is too small, using {} instead", self.scale, DEFAULT_DISPLAY_SCALE);
+            return DEFAULT_DISPLAY_SCALE;
+        }
+        self.scale
+    }
+
+    /// Window size in 8x8 tiles, the text console is twice the size of the world consoles
+    pub fn window_dimensions(&self) -> (usize, usize) {
+        let scale = self.window_scale();
+        (DISPLAY_WIDTH * 2 * scale, DISPLAY_HEIGHT * 2 * scale)
     }
 }
 
 pub struct InventoryConfig {
     pub sort_mode: SortMode,
+    /// Only items with this in their name are listed, an empty filter lists everything
+    pub filter: String,
+    /// Key presses are typed into the filter instead of selecting items
+    pub typing_filter: bool,
+    /// The order the bag was last sorted into, items picked up since then are listed after it
+    pub order: Vec<Entity>,
+    /// A drop of many items waiting on the player to confirm it in the modal
+    pub pending_drop: Option<(BatchDrop, Modal)>,
 }
 
 pub enum SortMode {
@@ -27,6 +85,17 @@ pub enum SortMode {
     _IDDesc,
     _Category,
 }
+impl SortMode {
+    /// How two items in the bag are ordered
+    pub fn compare(&self, (a_item, a_name): (&Item, &Name), (b_item, b_name): (&Item, &Name)) -> Ordering {
+        match self {
+            SortMode::NameABC => a_name.cmp(b_name),
+            SortMode::IDAsc => a_item.id.cmp(&b_item.id),
+            _ => a_item.id.cmp(&b_item.id),
+        }
+    }
+}
+
 impl Display for SortMode {
     /// Limited to 3 characters for nice formatting
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -39,6 +108,18 @@ impl Display for SortMode {
         write!(f, "{}", sort_mode)
     }
 }
+impl Default for InventoryConfig {
+    fn default() -> Self {
+        Self {
+            sort_mode: SortMode::NameABC,
+            filter: String::new(),
+            typing_filter: false,
+            order: Vec::new(),
+            pending_drop: None,
+        }
+    }
+}
+
 impl InventoryConfig {
     pub fn rotate_sort_mode(&mut self) {
         self.sort_mode = match self.sort_mode {
@@ -47,4 +128,14 @@ impl InventoryConfig {
             _ => SortMode::NameABC,
         };
     }
+
+    /// Case is ignored when matching the filter
+    pub fn matches_filter(&self, name: &str) -> bool {
+        name.to_lowercase().contains(&self.filter.to_lowercase())
+    }
+
+    pub fn clear_filter(&mut self) {
+        self.filter.clear();
+        self.typing_filter = false;
+    }
 }
@@ -0,0 +1,306 @@
+/* console.rs
+ *   A drop-down console for dev builds, opened with the backtick key. Typed commands are parsed and checked here
+ *   before they touch the world, anything wrong with them is printed back in the console instead.
+ * */
+
+use std::fs;
+
+use bracket_lib::terminal::{BTerm, Point, VirtualKeyCode as VKC};
+use log::info;
+use specs::{World, WorldExt};
+
+use crate::{
+    colors::PL_ORANGE,
+    components::{Facing, HealthStats, Position, StaticIndexed},
+    currents::set_currents,
+    data_read::{prelude::build_being, ENTITY_DB},
+    game_init::PlayerEntity,
+    get_text,
+    indexing::NamedEntities,
+    items::{ItemQty, ItemSpawner, SpawnType},
+    map::{Map, MapRes},
+    player::mark_view_dirty,
+    rng::GameRng,
+};
+
+/// Lines of output kept around to be shown above the prompt
+pub const CONSOLE_OUTPUT_LINES: usize = 6;
+
+pub const CONSOLE_USAGE: &str =
+    "give <item> <qty>, spawn <being>, tp <x> <y>, goto <being>, heal, setseed <n>, exportmap <name>, importmap <name>";
+
+/// Where maps are exported to and imported from by the console
+const MAP_EXPORT_PATH: &str = "./maps/";
+
+/// What has been typed so far and what the last commands printed
+#[derive(Default)]
+pub struct DebugConsole {
+    pub input: String,
+    pub output: Vec<String>,
+}
+
+impl DebugConsole {
+    fn print(&mut self, line: impl ToString) {
+        if self.output.len() == CONSOLE_OUTPUT_LINES {
+            self.output.remove(0);
+        }
+        self.output.push(line.to_string());
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// Adds the item to the player's bag
+    Give {
+        item: String,
+        qty: usize,
+    },
+    /// Builds the being on the tile the player is facing
+    Spawn(String),
+    Teleport(Position),
+    /// Puts the player on a free tile next to the being with the name
+    GoTo(String),
+    /// Puts the player back to full health
+    Heal,
+    /// Reseeds the game rng so the rolls from here on can be repeated
+    SetSeed(u64),
+    /// Writes the current map's tiles to a ron file so they can be looked over or edited by hand
+    ExportMap(String),
+    /// Swaps the current map's tiles for an exported one, everything on the map stays where it is
+    ImportMap(String),
+}
+
+pub enum ConsoleAction {
+    Waiting,
+    Close,
+}
+
+/// Reads the typed line into a command, the error explains what was wrong with it
+pub fn parse_command(line: &str) -> Result<Command, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let (name, args) = match words.split_first() {
+        Some((name, args)) => (*name, args),
+        None => return Err(format!("Commands are {}", CONSOLE_USAGE)),
+    };
+
+    match name {
+        "give" => match args.split_last() {
+            Some((qty, item)) if !item.is_empty() => {
+                let qty = parse_number::<usize>(qty, "qty")?;
+                if qty == 0 {
+                    return Err("qty must be at least 1".to_string());
+                }
+                Ok(Command::Give { item: item.join(" "), qty })
+            }
+            _ => Err("usage: give <item> <qty>".to_string()),
+        },
+        "spawn" if !args.is_empty() => Ok(Command::Spawn(args.join(" "))),
+        "spawn" => Err("usage: spawn <being>".to_string()),
+        "tp" => match args {
+            [x, y] => Ok(Command::Teleport(Position::new(parse_number(x, "x")?, parse_number(y, "y")?))),
+            _ => Err("usage: tp <x> <y>".to_string()),
+        },
+        "goto" if !args.is_empty() => Ok(Command::GoTo(args.join(" "))),
+        "goto" => Err("usage: goto <being>".to_string()),
+        "heal" if args.is_empty() => Ok(Command::Heal),
+        "heal" => Err("heal takes no arguments".to_string()),
+        "setseed" => match args {
+            [seed] => Ok(Command::SetSeed(parse_number(seed, "seed")?)),
+            _ => Err("usage: setseed <n>".to_string()),
+        },
+        "exportmap" => match args {
+            [name] => Ok(Command::ExportMap(name.to_string())),
+            _ => Err("usage: exportmap <name>".to_string()),
+        },
+        "importmap" => match args {
+            [name] => Ok(Command::ImportMap(name.to_string())),
+            _ => Err("usage: importmap <name>".to_string()),
+        },
+        _ => Err(format!("Unknown command {}, commands are {}", name, CONSOLE_USAGE)),
+    }
+}
+
+/// The being's name as it is written in the raws, names are typed in lowercase so the raws are searched ignoring case
+fn being_name(typed: &str) -> Result<String, String> {
+    let edb = ENTITY_DB.lock().unwrap();
+    match edb.beings.iter().find(|info| info.name.eq_ignore_ascii_case(typed)) {
+        Some(info) => Ok(info.name.clone()),
+        None => Err(format!("There is no being named {}", typed)),
+    }
+}
+
+fn map_file_path(name: &str) -> String {
+    format!("{}{}.ron", MAP_EXPORT_PATH, name)
+}
+
+fn parse_number<T: std::str::FromStr>(word: &str, arg: &str) -> Result<T, String> {
+    word.parse::<T>().map_err(|_| format!("{} must be a number, got {}", arg, word))
+}
+
+/// Carries out the command, the result is printed back to the console either way
+pub fn run_command(ecs: &mut World, command: Command) -> Result<String, String> {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    match command {
+        Command::Give { item, qty } => {
+            // names are typed in lowercase so the raws are searched ignoring case
+            let (id, name) = {
+                let edb = ENTITY_DB.lock().unwrap();
+                match edb.items.iter().find(|info| info.name.eq_ignore_ascii_case(&item)) {
+                    Some(info) => (info.identifier, info.name.clone()),
+                    None => return Err(format!("There is no item named {}", item)),
+                }
+            };
+            ecs.write_resource::<ItemSpawner>().request_amt(id, SpawnType::InBag(player_entity), ItemQty(qty));
+            Ok(format!("Gave {}x {}", qty, name))
+        }
+        Command::Spawn(being) => {
+            let name = being_name(&being)?;
+            let pos = match (
+                ecs.read_storage::<Position>().get(player_entity),
+                ecs.read_storage::<Facing>().get(player_entity),
+            ) {
+                (Some(pos), Some(facing)) => Position::from(pos.to_point() + facing.delta()),
+                _ => return Err("The player is not facing anywhere".to_string()),
+            };
+            if ecs.read_resource::<MapRes>().0.is_blocked(&pos) {
+                return Err(format!("Can't spawn at {}, it is blocked", pos));
+            }
+            build_being(&name, pos, ecs).map_err(|e| format!("Could not spawn {}, {}", name, e))?;
+            Ok(format!("Spawned {} at {}", name, pos))
+        }
+        Command::Teleport(pos) => {
+            if !ecs.read_resource::<MapRes>().0.in_bounds(pos.to_point()) {
+                return Err(format!("{} is off the map", pos));
+            }
+            if ecs.read_resource::<MapRes>().0.is_blocked(&pos) {
+                return Err(format!("Can't teleport to {}, it is blocked", pos));
+            }
+            let _ = ecs.write_storage::<Position>().insert(player_entity, pos);
+            mark_view_dirty(ecs, player_entity);
+            Ok(format!("Teleported to {}", pos))
+        }
+        Command::GoTo(being) => {
+            let name = being_name(&being)?;
+            let target = match ecs.read_resource::<NamedEntities>().get(&name) {
+                Some(target) => target,
+                None => return Err(format!("There is no {} in the world", name)),
+            };
+            let target_pos = match ecs.read_storage::<Position>().get(target) {
+                Some(pos) => *pos,
+                None => return Err(format!("{} is not on the map", name)),
+            };
+            // the being is standing on its tile so the player goes on the first free one around it
+            let free = {
+                let map = ecs.read_resource::<MapRes>();
+                (-1..=1)
+                    .flat_map(|dx| (-1..=1).map(move |dy| target_pos.to_point() + Point::new(dx, dy)))
+                    .filter(|point| map.0.in_bounds(*point))
+                    .map(Position::from)
+                    .find(|pos| *pos != target_pos && !map.0.is_blocked(pos))
+            };
+            let pos = match free {
+                Some(pos) => pos,
+                None => return Err(format!("There is no room next to {}", name)),
+            };
+            let _ = ecs.write_storage::<Position>().insert(player_entity, pos);
+            mark_view_dirty(ecs, player_entity);
+            Ok(format!("Teleported next to {} at {}", name, pos))
+        }
+        Command::Heal => match ecs.write_storage::<HealthStats>().get_mut(player_entity) {
+            Some(stats) => {
+                stats.hp = stats.max_hp;
+                Ok(format!("Healed to {} hp", stats.max_hp))
+            }
+            None => Err("The player has no health to heal".to_string()),
+        },
+        Command::SetSeed(seed) => {
+            ecs.insert(GameRng::seeded(seed));
+            Ok(format!("The game rng is now seeded with {}", seed))
+        }
+        Command::ExportMap(name) => {
+            let contents = ecs.read_resource::<MapRes>().0.to_ron().map_err(|e| format!("Could not export, {}", e))?;
+            let path = map_file_path(&name);
+            fs::create_dir_all(MAP_EXPORT_PATH)
+                .and_then(|_| fs::write(&path, contents))
+                .map_err(|e| format!("Could not write {}, {}", path, e))?;
+            Ok(format!("Exported the map to {}", path))
+        }
+        Command::ImportMap(name) => {
+            let path = map_file_path(&name);
+            let contents = fs::read_to_string(&path).map_err(|e| format!("Could not read {}, {}", path, e))?;
+            let map = Map::from_ron(&contents).map_err(|e| e.to_string())?;
+            let player_pos = ecs.read_storage::<Position>().get(player_entity).copied();
+            if player_pos.is_some_and(|pos| !map.in_bounds(pos.to_point())) {
+                return Err(format!("The player would be off the map in {}", path));
+            }
+            // the indexing systems fill the new map's entities back in on the next frame, the static ones included
+            ecs.write_storage::<StaticIndexed>().clear();
+            set_currents(&map, ecs);
+            ecs.insert(MapRes(map));
+            mark_view_dirty(ecs, player_entity);
+            Ok(format!("Imported the map from {}", path))
+        }
+    }
+}
+
+pub fn p_input_console(ecs: &mut World, ctx: &BTerm) -> ConsoleAction {
+    match ctx.key {
+        Some(VKC::Escape | VKC::Grave) => return ConsoleAction::Close,
+        Some(VKC::Back) => {
+            ecs.write_resource::<DebugConsole>().input.pop();
+        }
+        Some(VKC::Return) => {
+            let line = std::mem::take(&mut ecs.write_resource::<DebugConsole>().input);
+            info!("Console command: {}", line);
+            let result = parse_command(&line).and_then(|command| run_command(ecs, command));
+            let mut console = ecs.write_resource::<DebugConsole>();
+            console.print(format!("> {}", line));
+            match result {
+                Ok(done) => console.print(format!("#[white]{}#[]", done)),
+                Err(e) => console.print(format!("#[{}]{}#[]", PL_ORANGE, e)),
+            }
+        }
+        Some(key) => {
+            if let Some(letter) = get_text(key) {
+                ecs.write_resource::<DebugConsole>().input.push(letter);
+            }
+        }
+        None => {}
+    }
+    ConsoleAction::Waiting
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::TestWorld;
+
+    #[test]
+    fn commands_are_parsed_and_checked() {
+        assert_eq!(parse_command("give fishing rod 2"), Ok(Command::Give { item: "fishing rod".to_string(), qty: 2 }));
+        assert_eq!(parse_command("tp 4 7"), Ok(Command::Teleport(Position::new(4, 7))));
+        assert_eq!(parse_command("setseed 50"), Ok(Command::SetSeed(50)));
+        assert_eq!(parse_command("heal"), Ok(Command::Heal));
+        assert_eq!(parse_command("goto old man"), Ok(Command::GoTo("old man".to_string())));
+        assert_eq!(parse_command("exportmap cave"), Ok(Command::ExportMap("cave".to_string())));
+        assert!(parse_command("importmap").is_err());
+        assert!(parse_command("give 3").is_err());
+        assert!(parse_command("give worm 0").is_err());
+        assert!(parse_command("tp 4").is_err());
+        assert!(parse_command("tp four 7").is_err());
+        assert!(parse_command("fly").is_err());
+        assert!(parse_command("").is_err());
+    }
+
+    #[test]
+    fn teleporting_stays_on_the_map() {
+        let mut world = TestWorld::new();
+        let player = world.player();
+        world.insert(player, Position::new(1, 1));
+
+        assert!(run_command(&mut world.ecs, Command::Teleport(Position::new(5, 6))).is_ok());
+        assert_eq!(world.get::<Position>(player), Some(Position::new(5, 6)));
+        assert!(run_command(&mut world.ecs, Command::Teleport(Position::new(500, 6))).is_err());
+        assert_eq!(world.get::<Position>(player), Some(Position::new(5, 6)));
+    }
+}
@@ -0,0 +1,150 @@
+use bracket_lib::random::RandomNumberGenerator;
+use specs::{Entities, Entity, Join, Read, ReadStorage, System, World, WorldExt, Write, WriteExpect, WriteStorage};
+
+use crate::{
+    activity::{Activity, ActivityKind},
+    components::{CookAction, CookingStation, FinishedActivity, InBag, Item, Name, Position},
+    data_read::{prelude::RECIPE_DB, ENTITY_DB},
+    items::{ItemQty, ItemSpawner, SpawnType},
+    map::distance,
+    time::DeltaTime,
+    ui::message_log::MessageLog,
+};
+
+pub fn is_cooking_station(ecs: &World, entity: Entity) -> bool {
+    ecs.read_storage::<CookingStation>().contains(entity)
+}
+
+/// Queues up cooking the first thing in the cook's bag that has a recipe, false when there is nothing to cook
+pub fn try_start_cooking(ecs: &World, cook: Entity, station: Entity) -> bool {
+    let ingredient = {
+        let rdb = RECIPE_DB.lock().unwrap();
+        let entities = ecs.entities();
+        let items = ecs.read_storage::<Item>();
+        let bags = ecs.read_storage::<InBag>();
+        (&entities, &items, &bags)
+            .join()
+            .find(|(_, item, bag)| bag.owner == cook && rdb.cooking_recipe_for(item.id).is_some())
+            .map(|(entity, ..)| entity)
+    };
+
+    match ingredient {
+        Some(ingredient) => {
+            let _ = ecs.write_storage::<CookAction>().insert(cook, CookAction { station, ingredient });
+            true
+        }
+        None => {
+            ecs.fetch_mut::<MessageLog>().log("You have nothing that can be cooked.");
+            false
+        }
+    }
+}
+
+/// The station must be right next to the cook, diagonals don't count
+fn next_to_station(positions: &ReadStorage<Position>, cook: Entity, station: Entity) -> bool {
+    match (positions.get(cook), positions.get(station)) {
+        (Some(cook_pos), Some(station_pos)) => distance(cook_pos, station_pos) == 1,
+        _ => false,
+    }
+}
+
+pub struct SetupCookingActions;
+
+impl<'a> System<'a> for SetupCookingActions {
+    type SystemData = (
+        WriteStorage<'a, CookAction>,
+        WriteStorage<'a, Activity>,
+        ReadStorage<'a, Item>,
+        ReadStorage<'a, Position>,
+        Write<'a, MessageLog>,
+        Entities<'a>,
+    );
+
+    fn run(&mut self, (mut cook_actions, mut activities, items, positions, mut log, entities): Self::SystemData) {
+        let rdb = RECIPE_DB.lock().unwrap();
+        for (cook, action) in (&entities, &cook_actions).join() {
+            if !next_to_station(&positions, cook, action.station) {
+                log.log("You need to be next to a fire to cook.");
+                continue;
+            }
+            let recipe = match items.get(action.ingredient).and_then(|item| rdb.cooking_recipe_for(item.id)) {
+                Some(recipe) => recipe,
+                None => continue,
+            };
+
+            let cooking = ActivityKind::Cooking { station: action.station, ingredient: action.ingredient };
+            let _ = activities.insert(cook, Activity::new(cooking, recipe.cook_time));
+            log.log("You start cooking over the fire.");
+        }
+
+        cook_actions.clear();
+    }
+}
+
+pub struct CookingSystem;
+
+impl<'a> System<'a> for CookingSystem {
+    type SystemData = (
+        WriteStorage<'a, Activity>,
+        WriteStorage<'a, FinishedActivity>,
+        WriteStorage<'a, Item>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Name>,
+        WriteExpect<'a, ItemSpawner>,
+        Write<'a, MessageLog>,
+        Read<'a, DeltaTime>,
+        Entities<'a>,
+    );
+
+    fn run(
+        &mut self,
+        (mut activities, mut finished_activities, mut items, positions, names, mut item_spawner, mut log, dt, entities): Self::SystemData,
+    ) {
+        let rdb = RECIPE_DB.lock().unwrap();
+        let mut rng = RandomNumberGenerator::new();
+        let mut finished_cooks = Vec::new();
+
+        for (cook, activity, ()) in (&entities, &mut activities, !&finished_activities).join() {
+            let (station, ingredient) = match activity.kind {
+                ActivityKind::Cooking { station, ingredient } => (station, ingredient),
+                _ => continue,
+            };
+            if !next_to_station(&positions, cook, station) {
+                log.log("The fire is gone, there is nothing to cook on.");
+                finished_cooks.push(cook);
+                continue;
+            }
+            if !activity.advance(dt.0) {
+                continue;
+            }
+            finished_cooks.push(cook);
+
+            let item = match items.get_mut(ingredient) {
+                Some(item) if item.qty.0 > 0 => item,
+                _ => continue,
+            };
+            let recipe = match rdb.cooking_recipe_for(item.id) {
+                Some(recipe) => recipe,
+                None => continue,
+            };
+            item.qty = item.qty - ItemQty(1);
+
+            let burnt = rng.range(0, 100) < recipe.burn_chance;
+            let output = if burnt { recipe.burnt } else { recipe.output };
+            item_spawner.request(output, SpawnType::InBag(cook));
+
+            let cook_name = names.get(cook).map_or("Someone", |name| name.0.as_str());
+            let edb = ENTITY_DB.lock().unwrap();
+            let output_name = edb.items.get_by_id(output).map_or("something", |info| info.name.as_str());
+            if burnt {
+                log.log(format!("#[orange]{} burnt the food into {}.#[]", cook_name, output_name));
+            } else {
+                log.enhance(format!("{} cooked up {}!", cook_name, output_name));
+            }
+        }
+
+        for cook in finished_cooks {
+            let _ = finished_activities.insert(cook, FinishedActivity {});
+        }
+    }
+}
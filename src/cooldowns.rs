@@ -0,0 +1,116 @@
+/* cooldowns.rs
+ *   Timers an entity has to wait out before doing something again. Each entity has one `Cooldowns` holding a timer
+ *   per kind, `TickCooldowns` counts all of them down once at the start of every turn and drops the ones that ran out
+ *   so the map only ever holds what is still cooling down. Everything that cools down is paced by turns rather than
+ *   real time so waiting in place is the same as acting. A new timer just needs a variant in `CooldownKind`.
+ * */
+
+use std::collections::HashMap;
+
+use specs::{Component, Entity, Join, System, VecStorage, WriteStorage};
+
+/// What is cooling down, add a variant for each new thing that needs a timer
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum CooldownKind {
+    /// Time before a being can say another of its quips
+    Quip,
+    /// Time from one attack until the next can be made
+    Attack,
+    /// Time for the player to catch their breath after a dash
+    Dash,
+    /// Time before the player can ground slam again
+    GroundSlam,
+}
+
+/// Turns left on each of the entity's cooldowns, not saved since they are all short lived
+#[derive(Component, Default, Clone, Debug)]
+#[storage(VecStorage)]
+pub struct Cooldowns(HashMap<CooldownKind, u32>);
+
+impl Cooldowns {
+    /// True when the kind isn't cooling down
+    pub fn is_ready(&self, kind: CooldownKind) -> bool {
+        !self.0.contains_key(&kind)
+    }
+
+    /// Turns until the kind is ready, 0 when it already is
+    pub fn turns_left(&self, kind: CooldownKind) -> u32 {
+        self.0.get(&kind).copied().unwrap_or(0)
+    }
+
+    /// Starts the kind's timer, replacing whatever was left on it
+    pub fn start(&mut self, kind: CooldownKind, turns: u32) {
+        if turns == 0 {
+            self.0.remove(&kind);
+        } else {
+            self.0.insert(kind, turns);
+        }
+    }
+
+    /// Counts every timer down by a turn, the ones that ran out are removed
+    fn tick(&mut self) {
+        self.0.retain(|_, left| {
+            *left -= 1;
+            *left > 0
+        });
+    }
+}
+
+/// Whether the entity is free to do `kind`, entities without any cooldowns always are
+pub fn off_cooldown(cooldowns: Option<&Cooldowns>, kind: CooldownKind) -> bool {
+    cooldowns.map_or(true, |cooldowns| cooldowns.is_ready(kind))
+}
+
+/// Turns until the entity can do `kind` again
+pub fn turns_left(cooldowns: Option<&Cooldowns>, kind: CooldownKind) -> u32 {
+    cooldowns.map_or(0, |cooldowns| cooldowns.turns_left(kind))
+}
+
+/// Starts the entity's `kind` cooldown, creating their `Cooldowns` if they have none yet
+pub fn start_cooldown(store: &mut WriteStorage<Cooldowns>, entity: Entity, kind: CooldownKind, turns: u32) {
+    if let Ok(entry) = store.entry(entity) {
+        entry.or_insert_with(Cooldowns::default).start(kind, turns);
+    }
+}
+
+/// Counts every entity's cooldowns down, ran once at the start of each turn
+pub struct TickCooldowns;
+
+impl<'a> System<'a> for TickCooldowns {
+    type SystemData = (WriteStorage<'a, Cooldowns>,);
+
+    fn run(&mut self, (mut cooldowns,): Self::SystemData) {
+        for cooldowns in (&mut cooldowns).join() {
+            cooldowns.tick();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::Builder;
+
+    use super::*;
+    use crate::{components::Position, test_harness::TestWorld};
+
+    #[test]
+    fn cooldowns_count_down_and_are_pruned_once_ready() {
+        let mut world = TestWorld::new();
+        let mut cooldowns = Cooldowns::default();
+        cooldowns.start(CooldownKind::Quip, 2);
+        cooldowns.start(CooldownKind::Attack, 1);
+        let talker = world.spawn("Talker", Position::new(1, 1)).with(cooldowns).build();
+
+        world.run(TickCooldowns);
+        let cooldowns = world.get::<Cooldowns>(talker).unwrap();
+        assert!(!cooldowns.is_ready(CooldownKind::Quip));
+        assert!(cooldowns.is_ready(CooldownKind::Attack));
+        assert_eq!(cooldowns.turns_left(CooldownKind::Quip), 1);
+
+        world.run(TickCooldowns);
+        let cooldowns = world.get::<Cooldowns>(talker).unwrap();
+        assert!(cooldowns.is_ready(CooldownKind::Quip));
+        assert!(cooldowns.0.is_empty());
+        assert!(off_cooldown(None, CooldownKind::Quip));
+    }
+}
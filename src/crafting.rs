@@ -1,9 +1,11 @@
+use std::time::Duration;
+
 use log::warn;
 use specs::{Entities, Entity, Join, ReadStorage, System, Write, WriteStorage};
 
 use crate::{
-    components::{CraftAction, InBag, Item},
-    data_read::prelude::RECIPE_DB,
+    components::{CraftAction, Durability, InBag, Item, Name},
+    data_read::prelude::{RecipeDatabase, RECIPE_DB},
     items::{ItemID, ItemQty, ItemSpawner, SpawnType},
     ui::message_log::MessageLog,
 };
@@ -18,6 +20,23 @@ pub struct Ingredient {
     pub consume: Option<ItemQty>,
 }
 
+/// Turns `input` into `output` at a cooking station, `burn_chance` out of 100 times it becomes `burnt` instead
+pub struct CookingRecipe {
+    pub input: ItemID,
+    pub output: ItemID,
+    pub burnt: ItemID,
+    pub burn_chance: u32,
+    pub cook_time: Duration,
+}
+
+/// Using `consume` of `material` on a worn `tool` restores up to `restores` of its durability
+pub struct RepairRecipe {
+    pub tool: ItemID,
+    pub material: ItemID,
+    pub consume: ItemQty,
+    pub restores: u32,
+}
+
 pub struct HandleCraftingSystem;
 
 impl<'a> System<'a> for HandleCraftingSystem {
@@ -27,16 +46,32 @@ impl<'a> System<'a> for HandleCraftingSystem {
         Write<'a, MessageLog>,
         WriteStorage<'a, Item>,
         ReadStorage<'a, InBag>,
+        WriteStorage<'a, Durability>,
+        ReadStorage<'a, Name>,
         Entities<'a>,
     );
 
     /// TODO: check for item qty in recipes
     fn run(
         &mut self,
-        (mut craft_actions, mut spawn_requests, mut log, mut items, in_bags, entities): Self::SystemData,
+        (
+            mut craft_actions,
+            mut spawn_requests,
+            mut log,
+            mut items,
+            in_bags,
+            mut durabilities,
+            names,
+            entities,
+        ): Self::SystemData,
     ) {
         let rdb = &RECIPE_DB.lock().unwrap();
         'outer: for (crafter, craft_action) in (&entities, &craft_actions).join() {
+            if let Some(message) = try_repair(craft_action, rdb, &mut items, &mut durabilities, &names) {
+                log.log(message);
+                continue;
+            }
+
             let crafting_items: Vec<(Entity, &Item)> = (&entities, &items, &in_bags)
                 .join()
                 .filter(|(bagged_entity, _, bag)| {
@@ -62,12 +97,10 @@ impl<'a> System<'a> for HandleCraftingSystem {
             // check there are enough of a consumable ingredient
             for ingredient in recipe_crafted.ingredients.iter().filter(|ingredient| ingredient.consume.is_some()) {
                 match crafting_items.iter().find(|(_, Item { id, .. })| id.eq(&ingredient.id)) {
-                    Some((e, bag_item)) => {
-                        if bag_item.qty < ingredient.consume.unwrap() {
-                            continue 'outer;
-                        }
-                        item_updates.push((*e, Item::new(bag_item.id, bag_item.qty - ingredient.consume.unwrap())));
-                    }
+                    Some((e, bag_item)) => match bag_item.qty.try_sub(ingredient.consume.unwrap()) {
+                        Ok(left) => item_updates.push((*e, Item::new(bag_item.id, left))),
+                        Err(_) => continue 'outer,
+                    },
                     None => {
                         warn!("Item entity was cleared before proper cleanup was conducted.")
                     }
@@ -84,3 +117,37 @@ impl<'a> System<'a> for HandleCraftingSystem {
         craft_actions.clear();
     }
 }
+
+/// Repairs the tool in `craft_action` when the other item is a material that can repair it, returns what happened
+/// or `None` when the items weren't a repair
+fn try_repair(
+    craft_action: &CraftAction,
+    rdb: &RecipeDatabase,
+    items: &mut WriteStorage<Item>,
+    durabilities: &mut WriteStorage<Durability>,
+    names: &ReadStorage<Name>,
+) -> Option<String> {
+    let (first, second) = (items.get(craft_action.first_item)?, items.get(craft_action.second_item)?);
+    let recipe = rdb.repair_recipe_for(first.id, second.id)?;
+    let (tool, material) = if recipe.tool == first.id {
+        (craft_action.first_item, craft_action.second_item)
+    } else {
+        (craft_action.second_item, craft_action.first_item)
+    };
+    let tool_name = names.get(tool).map_or("tool".to_string(), |name| name.0.clone());
+
+    let durability = durabilities.get_mut(tool)?;
+    if durability.is_full() {
+        return Some(format!("Your {} is already in good shape.", tool_name));
+    }
+    let material_item = items.get_mut(material)?;
+    match material_item.qty.try_sub(recipe.consume) {
+        Ok(left) => material_item.qty = left,
+        Err(_) => {
+            let material_name = names.get(material).map_or("material".to_string(), |name| name.0.clone());
+            return Some(format!("You need {} {} to repair your {}.", recipe.consume.0, material_name, tool_name));
+        }
+    }
+    durability.repair(recipe.restores);
+    Some(format!("You repair your {}.", tool_name))
+}
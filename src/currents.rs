@@ -0,0 +1,254 @@
+/* currents.rs
+ *   Water runs downhill, a water tile whose bed drops off steeply enough into a neighboring water tile carries a
+ *   current that way. Every few turns the current pushes anything floating on it one tile along, a boat can still
+ *   be paddled against it since it only pushes now and then.
+ * */
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use specs::{Entities, Entity, Join, Read, ReadStorage, System, World, WorldExt, Write, WriteStorage};
+
+use crate::{
+    components::{Blocking, Boating, Buoyancy, Current, Facing, Position, Viewshed, Water},
+    map::{Map, MapRes},
+};
+
+/// How strong currents are and where they form, read from the settings when the game starts
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct CurrentConfig {
+    /// Nothing drifts when off, the currents are still there
+    pub enabled: bool,
+    /// Turns between each push of a current
+    pub turns_per_drift: u32,
+    /// How much lower the neighboring water's bed must be for the water to flow into it
+    pub min_drop: u8,
+}
+
+impl Default for CurrentConfig {
+    fn default() -> Self {
+        Self { enabled: true, turns_per_drift: 3, min_drop: 6 }
+    }
+}
+
+/// The current settings and how long until the currents push again
+pub struct Currents {
+    cfg: CurrentConfig,
+    turns_until_drift: u32,
+}
+
+impl Default for Currents {
+    fn default() -> Self {
+        Self::new(CurrentConfig::default())
+    }
+}
+
+impl Currents {
+    pub fn new(cfg: CurrentConfig) -> Self {
+        Self { turns_until_drift: cfg.turns_per_drift, cfg }
+    }
+}
+
+/// The way water at `pos` flows, towards the lowest neighboring water at least `min_drop` below it
+pub fn flow_direction(map: &Map, pos: &Position, min_drop: u8) -> Option<Facing> {
+    let height = map.tiles[pos.to_idx(map.width)].height;
+    [Facing::Up, Facing::Down, Facing::Left, Facing::Right]
+        .into_iter()
+        .filter_map(|facing| {
+            let next = pos.to_point() + facing.delta();
+            if !map.in_bounds(next) {
+                return None;
+            }
+            let tile = &map.tiles[Position::from(next).to_idx(map.width)];
+            (tile.name == "Water" && height.saturating_sub(tile.height) >= min_drop.max(1))
+                .then_some((tile.height, facing))
+        })
+        .min_by_key(|(height, _)| *height)
+        .map(|(_, facing)| facing)
+}
+
+/// Works out the current of every water tile on `map`, ran whenever a map is made or comes back into play since the
+/// currents are not saved with it
+pub fn set_currents(map: &Map, ecs: &World) {
+    let min_drop = ecs.read_resource::<Currents>().cfg.min_drop;
+    let entities = ecs.entities();
+    let waters = ecs.read_storage::<Water>();
+    let positions = ecs.read_storage::<Position>();
+    let mut currents = ecs.write_storage::<Current>();
+    for (e, _, pos) in (&entities, &waters, &positions).join() {
+        match flow_direction(map, pos, min_drop) {
+            Some(facing) => {
+                let _ = currents.insert(e, Current(facing));
+            }
+            None => {
+                currents.remove(e);
+            }
+        }
+    }
+}
+
+/// Pushes everything out boating, and any item floating on the water, along the current under it. Floating things only
+/// drift onto open water that is still on the map, anything else in the way holds them where they are
+pub struct CurrentDrift;
+
+impl<'a> System<'a> for CurrentDrift {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Viewshed>,
+        ReadStorage<'a, Boating>,
+        ReadStorage<'a, Buoyancy>,
+        ReadStorage<'a, Current>,
+        ReadStorage<'a, Water>,
+        ReadStorage<'a, Blocking>,
+        Write<'a, Currents>,
+        Read<'a, MapRes>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut positions,
+            mut viewsheds,
+            boatings,
+            buoyancies,
+            currents,
+            waters,
+            blockings,
+            mut drift,
+            map,
+        ): Self::SystemData,
+    ) {
+        if !drift.cfg.enabled {
+            return;
+        }
+        drift.turns_until_drift = drift.turns_until_drift.saturating_sub(1);
+        if drift.turns_until_drift > 0 {
+            return;
+        }
+        drift.turns_until_drift = drift.cfg.turns_per_drift;
+
+        let flows: HashMap<Position, Facing> = (&currents, &positions).join().map(|(c, pos)| (*pos, c.0)).collect();
+        let open_water: HashSet<Position> = (&waters, &positions).join().map(|(_, pos)| *pos).collect();
+        let mut taken: HashSet<Position> = (&blockings, &positions, !&waters).join().map(|(_, pos, _)| *pos).collect();
+
+        // items only ever have a position while they are on the ground
+        let floating_items = (&entities, &positions, &buoyancies)
+            .join()
+            .filter(|(_, pos, buoyancy)| **buoyancy == Buoyancy::Floats && open_water.contains(pos));
+        let floating: Vec<(Entity, Position)> = (&entities, &positions, &boatings)
+            .join()
+            .map(|(e, pos, _)| (e, *pos))
+            .chain(floating_items.map(|(e, pos, _)| (e, *pos)))
+            .collect();
+        for (e, from) in floating {
+            let facing = match flows.get(&from) {
+                Some(facing) => facing,
+                None => continue,
+            };
+            let next = from.to_point() + facing.delta();
+            if !map.0.in_bounds(next) {
+                continue;
+            }
+            let to = Position::from(next);
+            if !open_water.contains(&to) || taken.contains(&to) {
+                continue;
+            }
+
+            // items don't block so they never hold up anything else drifting
+            if blockings.contains(e) {
+                taken.remove(&from);
+                taken.insert(to);
+            }
+            let _ = positions.insert(e, to);
+            if let Some(view) = viewsheds.get_mut(e) {
+                view.dirty = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::Builder;
+
+    use super::*;
+    use crate::{map::WorldTile, test_harness::TestWorld};
+
+    /// A row of water along the top of the map flowing to the right, the player is out on it in a boat
+    fn river() -> (TestWorld, Entity, Vec<Entity>) {
+        let mut world = TestWorld::new().with_map(6, 3);
+        {
+            let mut map = world.ecs.write_resource::<MapRes>();
+            for x in 0..6 {
+                let idx = map.0.xy_to_idx(x, 0);
+                map.0.tiles[idx] = WorldTile::water(60 - x as u8 * 10);
+            }
+        }
+        let waters = (0..6)
+            .map(|x| world.spawn("Water", Position::new(x, 0)).with(Water {}).with(Blocking {}).build())
+            .collect();
+        set_currents(&world.ecs.fetch::<MapRes>().0, &world.ecs);
+
+        let player = world.player();
+        world.insert(player, Position::new(2, 0));
+        world.insert(player, Boating {});
+        world.insert(player, Blocking {});
+        (world, player, waters)
+    }
+
+    fn drift_turns(world: &mut TestWorld, turns: u32) {
+        for _ in 0..turns {
+            world.run(CurrentDrift);
+        }
+    }
+
+    #[test]
+    fn water_flows_downhill_where_the_drop_is_steep() {
+        let (world, _, _) = river();
+        let map = &world.ecs.fetch::<MapRes>().0;
+        assert_eq!(flow_direction(map, &Position::new(2, 0), 6), Some(Facing::Right));
+        assert_eq!(flow_direction(map, &Position::new(2, 0), 20), None);
+        // the last tile has nowhere lower to go
+        assert_eq!(flow_direction(map, &Position::new(5, 0), 6), None);
+    }
+
+    #[test]
+    fn currents_push_boats_every_few_turns() {
+        let (mut world, player, _) = river();
+        let turns = CurrentConfig::default().turns_per_drift;
+
+        drift_turns(&mut world, turns - 1);
+        assert_eq!(world.get::<Position>(player), Some(Position::new(2, 0)));
+        drift_turns(&mut world, 1);
+        assert_eq!(world.get::<Position>(player), Some(Position::new(3, 0)));
+
+        // something in the way holds the boat in place
+        world.spawn("Log", Position::new(4, 0)).with(Blocking {}).build();
+        drift_turns(&mut world, turns);
+        assert_eq!(world.get::<Position>(player), Some(Position::new(3, 0)));
+    }
+
+    #[test]
+    fn floating_items_drift_and_sinking_ones_stay() {
+        let (mut world, _, _) = river();
+        let log = world.spawn("Log", Position::new(0, 0)).with(Buoyancy::Floats).build();
+        let stone = world.spawn("Stone", Position::new(1, 0)).with(Buoyancy::Sinks).build();
+
+        drift_turns(&mut world, CurrentConfig::default().turns_per_drift);
+        assert_eq!(world.get::<Position>(log), Some(Position::new(1, 0)));
+        assert_eq!(world.get::<Position>(stone), Some(Position::new(1, 0)));
+    }
+
+    #[test]
+    fn currents_stop_at_the_edge_of_the_map() {
+        let (mut world, player, waters) = river();
+        world.insert(player, Position::new(4, 0));
+        world.insert(waters[5], Current(Facing::Right));
+
+        drift_turns(&mut world, CurrentConfig::default().turns_per_drift * 3);
+        assert_eq!(world.get::<Position>(player), Some(Position::new(5, 0)));
+    }
+}
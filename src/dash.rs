@@ -0,0 +1,210 @@
+/* dash.rs
+ *   The player can dash several tiles the way they are facing for the time of a single step. The dash stops short of
+ *   anything solid and a monster in the way is attacked instead. The player is winded for a few turns afterwards.
+ * */
+
+use bracket_lib::terminal::Point;
+use serde::{Deserialize, Serialize};
+use specs::{Entity, World, WorldExt};
+
+use crate::{
+    collision::blocks_player,
+    components::{AttackAction, Boating, Facing, Monster, Phasing, Position, Slide, Transform},
+    cooldowns::{off_cooldown, start_cooldown, CooldownKind, Cooldowns},
+    game_init::PlayerEntity,
+    map::{Map, MapRes, TileEntity},
+    player::{mark_view_dirty, PlayerResponse},
+    ui::message_log::MessageLog,
+};
+
+/// How far the player dashes and how often, read from the settings when the game starts
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct DashConfig {
+    /// Most tiles crossed in one dash, 0 turns dashing off
+    pub distance: usize,
+    /// Turns after a dash before the player can dash again
+    pub cooldown_turns: u32,
+}
+
+impl Default for DashConfig {
+    fn default() -> Self {
+        Self { distance: 4, cooldown_turns: 6 }
+    }
+}
+
+/// The player's dash, the turns until they have caught their breath are kept in their `Cooldowns`
+#[derive(Default)]
+pub struct Dash {
+    cfg: DashConfig,
+}
+
+impl Dash {
+    pub fn new(cfg: DashConfig) -> Self {
+        Self { cfg }
+    }
+}
+
+/// The tiles a dash crosses, the last step is where the dasher ends up
+#[derive(Debug, PartialEq)]
+pub struct DashPath {
+    pub steps: Vec<Position>,
+    /// The monster standing just past the last step
+    pub hit: Option<Entity>,
+}
+
+/// Steps from `from` along `delta` up to `distance` times, stopping before the first tile with something on it that
+/// `in_the_way` keeps. Items are dashed over
+pub fn dash_path(
+    map: &Map,
+    from: Position,
+    delta: Point,
+    distance: usize,
+    in_the_way: impl Fn(&TileEntity) -> bool,
+    is_monster: impl Fn(Entity) -> bool,
+) -> DashPath {
+    let mut path = DashPath { steps: Vec::new(), hit: None };
+    let mut at = from.to_point();
+    for _ in 0..distance {
+        let next = at + delta;
+        if !map.in_bounds(next) {
+            break;
+        }
+        let next_pos = Position::from(next);
+        match map.first_entity_where(&next_pos, &in_the_way) {
+            None | Some(TileEntity::Item(_)) => {}
+            Some(TileEntity::Blocking(blocker)) if is_monster(*blocker) => {
+                path.hit = Some(*blocker);
+                break;
+            }
+            Some(_) => break,
+        }
+        path.steps.push(next_pos);
+        at = next;
+    }
+    path
+}
+
+/// Dashes the player the way they are facing, a monster at the end of the dash is attacked
+pub fn try_dash(ecs: &mut World) -> PlayerResponse {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let distance = ecs.read_resource::<Dash>().cfg.distance;
+    if distance == 0 {
+        return PlayerResponse::Waiting;
+    }
+    if !off_cooldown(ecs.read_storage::<Cooldowns>().get(player_entity), CooldownKind::Dash) {
+        ecs.fetch_mut::<MessageLog>().log("You are still catching your breath.");
+        return PlayerResponse::Waiting;
+    }
+    if ecs.read_storage::<Boating>().contains(player_entity) {
+        ecs.fetch_mut::<MessageLog>().log("There is no room to dash in a boat.");
+        return PlayerResponse::Waiting;
+    }
+
+    let (from, delta) =
+        match (ecs.read_storage::<Position>().get(player_entity), ecs.read_storage::<Facing>().get(player_entity)) {
+            (Some(pos), Some(facing)) => (*pos, facing.delta()),
+            _ => return PlayerResponse::Waiting,
+        };
+    let path = {
+        let phasing = ecs.read_storage::<Phasing>();
+        let monsters = ecs.read_storage::<Monster>();
+        let in_the_way =
+            |tile_entity: &TileEntity| !tile_entity.is_blocker() || blocks_player(&phasing, tile_entity.entity());
+        dash_path(&ecs.fetch::<MapRes>().0, from, delta, distance, in_the_way, |e| monsters.contains(e))
+    };
+    let to = match (path.steps.last(), path.hit) {
+        (Some(to), _) => Some(*to),
+        (None, Some(_)) => None,
+        (None, None) => {
+            ecs.fetch_mut::<MessageLog>().log("Something is in the way.");
+            return PlayerResponse::Waiting;
+        }
+    };
+
+    if let Some(to) = to {
+        let _ = ecs.write_storage::<Position>().insert(player_entity, to);
+        slide_from(ecs, player_entity, from);
+        mark_view_dirty(ecs, player_entity);
+    }
+    if let Some(monster) = path.hit {
+        let _ = ecs.write_storage::<AttackAction>().insert(player_entity, AttackAction { target: monster });
+        ecs.fetch_mut::<MessageLog>().log("You dash forward and strike!");
+    } else {
+        ecs.fetch_mut::<MessageLog>().log("You dash forward.");
+    }
+    let cooldown_turns = ecs.read_resource::<Dash>().cfg.cooldown_turns;
+    start_cooldown(&mut ecs.write_storage::<Cooldowns>(), player_entity, CooldownKind::Dash, cooldown_turns);
+    PlayerResponse::TurnAdvance
+}
+
+/// Draws the entity back at `from` so it slides over to where it is now instead of jumping there
+fn slide_from(ecs: &World, entity: Entity, from: Position) {
+    let mut transforms = ecs.write_storage::<Transform>();
+    let owns_transform = match transforms.get_mut(entity) {
+        Some(_) => false,
+        None => {
+            let _ = transforms.insert(entity, Transform::new(from.x as f32, from.y as f32, 0.0, 1.0, 1.0));
+            true
+        }
+    };
+    let _ = ecs.write_storage::<Slide>().insert(entity, Slide { owns_transform });
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::Builder;
+
+    use super::*;
+    use crate::{
+        components::Blocking,
+        cooldowns::TickCooldowns,
+        indexing::{IndexBlockedTiles, IndexReset},
+        test_harness::TestWorld,
+    };
+
+    fn dash_world() -> (TestWorld, Entity) {
+        let mut world = TestWorld::new();
+        let player = world.player();
+        world.insert(player, Position::new(2, 5));
+        world.insert(player, Facing::Right);
+        (world, player)
+    }
+
+    fn index(world: &mut TestWorld) {
+        world.run(IndexReset);
+        world.run(IndexBlockedTiles);
+    }
+
+    #[test]
+    fn dashes_stop_before_anything_solid() {
+        let (mut world, player) = dash_world();
+        world.spawn("Rock", Position::new(5, 5)).with(Blocking {}).build();
+        index(&mut world);
+
+        assert!(matches!(try_dash(&mut world.ecs), PlayerResponse::TurnAdvance));
+        assert_eq!(world.get::<Position>(player), Some(Position::new(4, 5)));
+        assert!(world.has::<Slide>(player));
+
+        // winded until the cooldown runs out
+        assert!(matches!(try_dash(&mut world.ecs), PlayerResponse::Waiting));
+        for _ in 0..DashConfig::default().cooldown_turns {
+            world.run(TickCooldowns);
+        }
+        world.insert(player, Facing::Left);
+        assert!(matches!(try_dash(&mut world.ecs), PlayerResponse::TurnAdvance));
+        assert_eq!(world.get::<Position>(player), Some(Position::new(0, 5)));
+    }
+
+    #[test]
+    fn dashing_into_a_monster_attacks_it() {
+        let (mut world, player) = dash_world();
+        let slime = world.spawn("Slime", Position::new(4, 5)).with(Blocking {}).with(Monster::new(5, 10)).build();
+        index(&mut world);
+
+        assert!(matches!(try_dash(&mut world.ecs), PlayerResponse::TurnAdvance));
+        assert_eq!(world.get::<Position>(player), Some(Position::new(3, 5)));
+        let attack = world.ecs.read_storage::<AttackAction>().get(player).map(|attack| attack.target);
+        assert_eq!(attack, Some(slime));
+    }
+}
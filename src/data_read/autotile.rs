@@ -0,0 +1,76 @@
+use std::{fs, sync::Mutex};
+
+use json5::from_str;
+use lazy_static::lazy_static;
+use log::error;
+use serde::Deserialize;
+
+lazy_static! {
+    pub static ref AUTOTILE_DB: Mutex<AutotileDatabase> = Mutex::new(AutotileDatabase::new());
+}
+
+const AUTOTILE_FILE: &str = "./raws/autotile.json5";
+
+/// Which neighbours are checked when building a tile's mask
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Neighbours {
+    /// North 1, east 2, south 4, west 8
+    Four,
+    /// The four cardinals plus north-east 16, south-east 32, south-west 64, north-west 128
+    Eight,
+}
+
+/// Maps the neighbour mask of one kind of tile to an atlas index for a single tileset
+#[derive(Deserialize, Clone, Debug)]
+pub struct AutotileRule {
+    /// Font index of the tileset the atlas indices belong to
+    pub tileset: usize,
+    /// Name of the tile this applies to, neighbours with the same name are counted in the mask
+    pub tile: String,
+    pub neighbours: Neighbours,
+    /// Atlas index used when a mask is not in `masks`
+    pub base: usize,
+    /// Pairs of (mask, atlas index)
+    pub masks: Vec<(u8, usize)>,
+}
+
+impl AutotileRule {
+    pub fn atlas_for(&self, mask: u8) -> usize {
+        self.masks.iter().find(|(m, _)| *m == mask).map_or(self.base, |(_, atlas)| *atlas)
+    }
+}
+
+pub struct AutotileDatabase {
+    rules: Vec<AutotileRule>,
+}
+
+impl AutotileDatabase {
+    pub fn new() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    pub fn load(&mut self) {
+        let data = match fs::read_to_string(AUTOTILE_FILE) {
+            Ok(d) => d,
+            Err(e) => {
+                error!("Error while reading autotile file: {}", e);
+                *self = Self::new();
+                return;
+            }
+        };
+
+        self.rules = match from_str(&data) {
+            Ok(rules) => rules,
+            Err(e) => {
+                error!("Error while parsing autotile file: {}", e);
+                Vec::new()
+            }
+        };
+    }
+
+    /// Gets the rules for every tile in the tileset
+    pub fn rules_for(&self, tileset: usize) -> Vec<AutotileRule> {
+        self.rules.iter().filter(|rule| rule.tileset == tileset).cloned().collect()
+    }
+}
@@ -1,25 +1,35 @@
-use std::fs;
-
 use log::warn;
 use serde::Deserialize;
-use serde_json::from_str;
 use specs::{
     saveload::{MarkedBuilder, SimpleMarker},
     Builder, Entity, World, WorldExt,
 };
 
 use crate::{
-    being::{AIDefinition, Being, BeingID},
-    components::{Blocking, GoalMoverAI, Name, Position, RandomWalkerAI, Renderable},
+    being::{AIDefinition, Being, BeingID, IdleDefinition, MovementDefinition, DEFAULT_FOLLOW_DISTANCE},
+    combat::DEFAULT_ATTACK_COOLDOWN,
+    components::{
+        ActionPoints, AttackCooldown, Blocking, Companion, Dialogue, GoalMoverAI, Interactor, InteractorMode, Name,
+        Position, Quips, RandomWalkerAI, Renderable, Shop, Size, Tags,
+    },
     droptables::Drops,
+    factions::Faction,
+    items::ItemID,
     saveload::SerializeMe,
+    special_attacks::{SpecialAbility, SpecialAttack},
+    speed::NORMAL_SPEED,
     stats::{EntityStatsBuilder, Stats},
+    tile_animation::{AnimationRequest, TileAnimationBuilder},
     z_order::BEING_Z,
 };
 
-use super::{EntityBuildError, GameData, OptionalStats, ENTITY_DB};
+use super::{find_collisions, read_json, EntityBuildError, GameData, LookupError, OptionalStats, RawsError, ENTITY_DB};
+
+const BEINGS_PATH: &str = "raws/beings.json";
 
 pub struct BeingDatabase {
+    /// The file the data was loaded from, used when reporting problems
+    path: &'static str,
     data: Vec<Being>,
 }
 
@@ -29,11 +39,32 @@ pub struct RawBeing {
     pub(crate) name: String,
     pub(crate) ai: Option<AIDefinition>,
     pub(crate) is_blocking: bool,
-    pub(crate) atlas_index: u8,
+    pub(crate) atlas_index: usize,
     pub(crate) fg: (u8, u8, u8),
     pub(crate) quips: Option<Vec<String>>,
+    pub(crate) dialogue: Option<Vec<String>>,
+    /// Shown when the being is examined, beings without one are described by their name
+    pub(crate) examine_text: Option<String>,
+    pub(crate) shop: Option<Vec<String>>,
     pub(crate) stats: Option<OptionalStats>,
     pub(crate) loot: Option<RawDrops>,
+    pub(crate) idle: Option<IdleDefinition>,
+    /// Points gained each turn, beings without one move at normal speed
+    pub(crate) speed: Option<u32>,
+    /// Turns between attacks, beings without one can attack every turn
+    pub(crate) attack_cooldown: Option<u32>,
+    /// Who acts first when beings act in the same round, beings without one use their speed
+    pub(crate) initiative: Option<u32>,
+    /// Obstacles the being gets past, beings without one are blocked by everything
+    pub(crate) movement: Option<MovementDefinition>,
+    /// Labels other systems can find the being by, ie. "npc"
+    pub(crate) tags: Option<Vec<String>>,
+    /// Decides who the being fights and who fights it, beings without one are left alone by everyone
+    pub(crate) faction: Option<Faction>,
+    /// A charged attack the being telegraphs before using, see `special_attacks.rs`
+    pub(crate) special: Option<SpecialAbility>,
+    /// How big the being is, see `Size` for what each one does. Beings without one are medium
+    pub(crate) size: Option<Size>,
 }
 
 #[derive(Deserialize)]
@@ -51,39 +82,124 @@ pub struct RawLoot {
 
 impl BeingDatabase {
     pub(crate) fn empty() -> Self {
-        Self { data: Vec::new() }
-    }
-
-    // Uses GameData in order to transform string names into item ids
-    pub fn load(game_db: &GameData) -> Self {
-        let contents: String =
-            fs::read_to_string("raws/beings.json").expect("Unable to find beings.json at `raws/beings.json`");
-        let beings: Vec<RawBeing> = from_str(&contents).expect("Bad JSON in beings.json fix it");
-        BeingDatabase {
-            data: beings
-                .iter()
-                .map(|raw| Being {
-                    identifier: raw.identifier,
-                    name: raw.name.clone(),
-                    ai: raw.ai.clone(),
-                    is_blocking: raw.is_blocking,
-                    atlas_index: raw.atlas_index,
-                    fg: raw.fg,
-                    quips: raw.quips.to_owned(),
-                    stats: raw.stats.as_ref().map_or_else(Stats::zero, Stats::from_optional),
-                    loot: raw.loot.as_ref().map(|raw| Drops::from_raw(raw, game_db)),
-                })
-                .collect(),
+        Self { path: BEINGS_PATH, data: Vec::new() }
+    }
+
+    /// Uses GameData in order to transform string names into item ids, bad entries are skipped and added to `errors`
+    pub fn load(game_db: &GameData, errors: &mut Vec<RawsError>) -> Self {
+        Self::load_from(BEINGS_PATH, game_db, errors)
+    }
+
+    pub(crate) fn load_from(path: &'static str, game_db: &GameData, errors: &mut Vec<RawsError>) -> Self {
+        let beings: Vec<RawBeing> = match read_json(path) {
+            Ok(raw) => raw,
+            Err(e) => {
+                errors.push(e);
+                return Self { path, data: Vec::new() };
+            }
+        };
+
+        let mut data: Vec<Being> = Vec::new();
+        for raw in beings.iter() {
+            match Being::from_raw(raw, game_db) {
+                Ok(being) => data.push(being),
+                Err(reason) => errors.push(RawsError::InvalidEntry { path, entry: raw.name.clone(), reason }),
+            }
         }
+        BeingDatabase { path, data }
+    }
+
+    /// Checks that no two beings share an id or a name since lookups only ever find the first
+    pub(crate) fn validate(&self) -> Result<(), RawsError> {
+        let collisions =
+            find_collisions(self.data.iter().map(|being| (being.identifier.value().to_string(), being.name.as_str())));
+        if collisions.is_empty() {
+            return Ok(());
+        }
+        Err(RawsError::Duplicates { path: self.path, collisions })
     }
 
     pub fn get_by_name(&self, name: &String) -> Option<&Being> {
         self.data.iter().find(|i| i.name.eq(name))
     }
 
-    #[allow(dead_code)]
-    pub fn get_by_id(&self, id: u32) -> Option<&Being> {
-        self.data.iter().find(|i| i.identifier.0 == id)
+    pub fn get_by_id(&self, id: BeingID) -> Option<&Being> {
+        self.data.iter().find(|i| i.identifier == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Being> {
+        self.data.iter()
+    }
+}
+
+impl Being {
+    fn from_raw(raw: &RawBeing, game_db: &GameData) -> Result<Self, String> {
+        if let Some(ai) = &raw.ai {
+            match ai.start_mode.as_str() {
+                "random_walk" | "companion" => {}
+                "goal" if ai.goal_range.is_none() => return Err("goal ai has no goal_range".to_string()),
+                "goal" => {}
+                "hostile" => {
+                    let monster = ai.monster();
+                    if monster.give_up_range < monster.aggro_range {
+                        return Err("hostile ai has a give_up_range smaller than its aggro_range".to_string());
+                    }
+                }
+                other => return Err(format!("{} is not a valid ai start_mode", other)),
+            }
+        }
+        if let Some(idle) = &raw.idle {
+            if idle.frames.is_empty() || idle.frame_ms == 0 {
+                return Err("idle animation needs at least one frame and a frame_ms above 0".to_string());
+            }
+        }
+        if raw.speed == Some(0) {
+            return Err("speed must be above 0".to_string());
+        }
+        if raw.attack_cooldown == Some(0) {
+            return Err("attack_cooldown must be above 0".to_string());
+        }
+        if let Some(special) = &raw.special {
+            special.validate()?;
+        }
+        let loot = match &raw.loot {
+            Some(loot) => Some(Drops::from_raw(loot, game_db)?),
+            None => None,
+        };
+        let shop = match &raw.shop {
+            Some(stock) => Some(
+                stock
+                    .iter()
+                    .map(|name| game_db.items.try_get_by_name(name).map(|item| item.identifier))
+                    .collect::<Result<Vec<ItemID>, LookupError>>()
+                    .map_err(|e| format!("shop stock is not an item, {}", e))?,
+            ),
+            None => None,
+        };
+
+        Ok(Being {
+            identifier: raw.identifier,
+            name: raw.name.clone(),
+            ai: raw.ai.clone(),
+            is_blocking: raw.is_blocking,
+            atlas_index: raw.atlas_index,
+            fg: raw.fg,
+            quips: raw.quips.to_owned(),
+            dialogue: raw.dialogue.to_owned(),
+            examine_text: raw.examine_text.clone(),
+            shop,
+            stats: raw.stats.as_ref().map_or_else(Stats::zero, Stats::from_optional),
+            loot,
+            idle: raw.idle.clone(),
+            speed: raw.speed.unwrap_or(NORMAL_SPEED),
+            initiative: raw.initiative.or(raw.speed).unwrap_or(NORMAL_SPEED),
+            attack_cooldown: raw.attack_cooldown.unwrap_or(DEFAULT_ATTACK_COOLDOWN),
+            movement: raw.movement.clone().unwrap_or_default(),
+            tags: Tags::from_raw(&raw.tags),
+            faction: raw.faction,
+            special: raw.special.clone(),
+            size: raw.size.unwrap_or_default(),
+        })
     }
 }
 
@@ -93,10 +209,7 @@ pub fn build_being(name: impl ToString, pos: Position, world: &mut World) -> Res
 
     let raw = match edb.beings.get_by_name(&name.to_string()) {
         Some(raw) => raw,
-        None => {
-            eprintln!("No being found named: {}", name.to_string());
-            return Err(EntityBuildError);
-        }
+        None => return Err(EntityBuildError::NotFound(name.to_string())),
     };
 
     let mut builder = world
@@ -111,6 +224,51 @@ pub fn build_being(name: impl ToString, pos: Position, world: &mut World) -> Res
         builder = builder.with(Blocking {});
     }
 
+    if let Some(lines) = &raw.quips {
+        builder = builder.with(Quips::new(lines));
+    }
+
+    if let Some(lines) = raw.dialogue_lines() {
+        builder = builder.with(Dialogue { lines: lines.clone() });
+    }
+
+    if let Some(stock) = &raw.shop {
+        builder = builder.with(Shop { stock: stock.clone() });
+    }
+
+    if let Some(idle) = &raw.idle {
+        builder = builder.with(idle.to_component());
+    }
+
+    if let Some(tags) = &raw.tags {
+        builder = builder.with(tags.clone());
+    }
+
+    if let Some(faction) = raw.faction {
+        builder = builder.with(faction);
+    }
+
+    if let Some(flying) = raw.movement.flying() {
+        builder = builder.with(flying);
+    }
+
+    if let Some(phasing) = raw.movement.phasing() {
+        builder = builder.with(phasing);
+    }
+
+    if let Some(special) = &raw.special {
+        builder = builder.with(SpecialAttack::new(special.clone()));
+    }
+
+    if raw.size != Size::Medium {
+        builder = builder.with(raw.size);
+    }
+
+    builder = builder
+        .with(ActionPoints::new(raw.speed).with_initiative(raw.initiative))
+        .with(AttackCooldown::new(raw.attack_cooldown));
+
+    // The ai is checked when the database is loaded so every start mode here is valid
     if let Some(ai) = &raw.ai {
         builder = match ai.start_mode.as_str() {
             "random_walk" => builder.with(RandomWalkerAI {}),
@@ -122,7 +280,13 @@ pub fn build_being(name: impl ToString, pos: Position, world: &mut World) -> Res
                         vec![]
                     }
                 };
-                builder.with(GoalMoverAI::with_desires(&goals, ai.goal_range.unwrap()))
+                builder.with(GoalMoverAI::with_desires(&goals, ai.goal_range.unwrap_or_default()))
+            }
+            "companion" => {
+                builder.with(Companion { follow_distance: ai.follow_distance.unwrap_or(DEFAULT_FOLLOW_DISTANCE) })
+            }
+            "hostile" => {
+                builder.with(RandomWalkerAI {}).with(ai.monster()).with(Interactor::new(InteractorMode::Agressive))
             }
             _ => builder,
         };
@@ -139,5 +303,7 @@ pub fn build_being(name: impl ToString, pos: Position, world: &mut World) -> Res
 
     builder = builder.with(esb.0).with(esb.1);
 
-    Ok(builder.build())
+    let being = builder.build();
+    world.fetch_mut::<TileAnimationBuilder>().request(AnimationRequest::SpawnPop(being));
+    Ok(being)
 }
@@ -0,0 +1,143 @@
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+use log::error;
+use serde::Deserialize;
+use specs::{
+    saveload::{MarkedBuilder, SimpleMarker},
+    Builder, Entity, World, WorldExt,
+};
+
+use crate::{
+    components::{Blocking, Crop, Name, Position, Renderable},
+    droptables::DropQty,
+    items::ItemID,
+    saveload::SerializeMe,
+    z_order::WORLD_OBJECT_Z,
+};
+
+use super::{read_json5, EntityBuildError, GameData};
+
+lazy_static! {
+    pub static ref CROP_DB: Mutex<CropDatabase> = Mutex::new(CropDatabase::empty());
+}
+
+const CROPS_PATH: &str = "raws/crops.json5";
+
+/// A plant that grows through its stages over turns and can be harvested once it reaches the last one
+pub struct CropInfo {
+    pub name: String,
+    pub fg: (u8, u8, u8),
+    pub stages: Vec<CropStage>,
+    pub yields: Vec<(ItemID, DropQty)>,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct CropStage {
+    pub atlas_index: usize,
+    /// Turns spent in the stage before moving to the next, the last stage ignores it
+    #[serde(default)]
+    pub turns: usize,
+    /// Overrides the crop's color while in the stage
+    pub fg: Option<(u8, u8, u8)>,
+}
+
+impl CropInfo {
+    /// The last stage is the only one that can be harvested
+    pub fn is_ripe(&self, stage: usize) -> bool {
+        stage + 1 >= self.stages.len()
+    }
+
+    pub fn stage_fg(&self, stage: &CropStage) -> (u8, u8, u8) {
+        stage.fg.unwrap_or(self.fg)
+    }
+
+    fn from_raw(raw: &RawCrop, game_db: &GameData) -> Result<Self, String> {
+        if raw.stages.is_empty() {
+            return Err("crops need at least one growth stage".to_string());
+        }
+        if raw.stages[..raw.stages.len() - 1].iter().any(|stage| stage.turns == 0) {
+            return Err("every stage before the last needs turns above 0".to_string());
+        }
+        let yields = raw
+            .yields
+            .iter()
+            .map(|y| match game_db.items.get_by_name(&y.item) {
+                Some(item) => Ok((item.identifier, DropQty::from_str(&y.qty))),
+                None => Err(format!("yield item {} has no definition in items", y.item)),
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(Self { name: raw.name.clone(), fg: raw.fg, stages: raw.stages.clone(), yields })
+    }
+}
+
+#[derive(Deserialize)]
+struct RawCrop {
+    name: String,
+    fg: (u8, u8, u8),
+    stages: Vec<CropStage>,
+    yields: Vec<RawYield>,
+}
+
+#[derive(Deserialize)]
+struct RawYield {
+    item: String,
+    /// Same format as loot quantities, a single number or a `min:max` range
+    qty: String,
+}
+
+pub struct CropDatabase {
+    crops: Vec<CropInfo>,
+}
+
+impl CropDatabase {
+    pub fn empty() -> Self {
+        Self { crops: Vec::new() }
+    }
+
+    /// Items must be loaded first since the yields are looked up by name
+    pub fn load(&mut self, game_db: &GameData) {
+        let raws: Vec<RawCrop> = match read_json5(CROPS_PATH) {
+            Ok(raws) => raws,
+            Err(e) => {
+                error!("{}", e);
+                *self = Self::empty();
+                return;
+            }
+        };
+
+        self.crops = raws
+            .iter()
+            .filter_map(|raw| {
+                CropInfo::from_raw(raw, game_db)
+                    .map_err(|reason| error!("{}: {} was skipped, {}", CROPS_PATH, raw.name, reason))
+                    .ok()
+            })
+            .collect();
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<&CropInfo> {
+        self.crops.iter().find(|crop| crop.name == name)
+    }
+}
+
+/// Plants the crop as a fresh seedling
+pub fn build_crop(name: &str, pos: Position, world: &mut World) -> Result<Entity, EntityBuildError> {
+    let cdb = CROP_DB.lock().unwrap();
+    let info = match cdb.get_by_name(name) {
+        Some(info) => info,
+        None => return Err(EntityBuildError::NotFound(name.to_string())),
+    };
+    let first = &info.stages[0];
+
+    Ok(world
+        .create_entity()
+        .with(Name::new(&info.name))
+        .with(pos)
+        .with(Renderable::clear_bg(first.atlas_index, info.stage_fg(first), WORLD_OBJECT_Z))
+        .with(Blocking {})
+        .with(Crop::new())
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build())
+}
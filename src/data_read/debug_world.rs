@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::components::{Position, ToolType};
+
+use super::{new_game::TerrainTile, read_json5, GameData, RawsError};
+
+const DEBUG_WORLD_PATH: &str = "raws/debug_world.json5";
+
+/// A test scene placed around the start of a new game in dev builds, so every interaction can be tried right away
+#[derive(Default)]
+pub struct DebugWorld {
+    pub terrain: Vec<(Position, TerrainTile)>,
+    /// World objects with the tool that breaks them when it should differ from the raws
+    pub world_objs: Vec<(String, Position, Option<ToolType>)>,
+    pub beings: Vec<(String, Position)>,
+}
+
+#[derive(Deserialize)]
+struct RawDebugWorld {
+    #[serde(default)]
+    enabled: bool,
+    /// Where the top left of `rows` is on the map
+    origin: (usize, usize),
+    /// Each character is looked up in `legend`, a space leaves the tile as it was generated
+    rows: Vec<String>,
+    legend: HashMap<char, RawDebugTile>,
+}
+
+/// What one legend character places, any of them can be combined on a tile
+#[derive(Deserialize)]
+struct RawDebugTile {
+    tile: Option<String>,
+    world_obj: Option<String>,
+    /// Overrides the tool the world object is broken with
+    tool: Option<ToolType>,
+    being: Option<String>,
+}
+
+impl DebugWorld {
+    /// Reads the test scene from the raws, it is empty when turned off or when the file can't be read. Characters
+    /// missing from the legend and unknown names are skipped and added to `errors`
+    pub fn load(game_db: &GameData, errors: &mut Vec<RawsError>) -> Self {
+        let path = DEBUG_WORLD_PATH;
+        let raw: RawDebugWorld = match read_json5(path) {
+            Ok(raw) => raw,
+            Err(e) => {
+                errors.push(e);
+                return Self::default();
+            }
+        };
+        let mut world = Self::default();
+        if !raw.enabled {
+            return world;
+        }
+
+        for (y, row) in raw.rows.iter().enumerate() {
+            for (x, symbol) in row.chars().enumerate().filter(|(_, symbol)| *symbol != ' ') {
+                let pos = Position::new(raw.origin.0 + x, raw.origin.1 + y);
+                let placed = match raw.legend.get(&symbol) {
+                    Some(placed) => placed,
+                    None => {
+                        let reason = "the character is not in the legend".to_string();
+                        errors.push(RawsError::InvalidEntry { path, entry: symbol.to_string(), reason });
+                        continue;
+                    }
+                };
+                if let Some(tile) = &placed.tile {
+                    world.terrain.push((pos, TerrainTile::named(tile)));
+                }
+                if let Some(name) = &placed.world_obj {
+                    match game_db.world_objs.get_by_name(name) {
+                        Some(_) => world.world_objs.push((name.clone(), pos, placed.tool.clone())),
+                        None => {
+                            let reason = "no world object has this name".to_string();
+                            errors.push(RawsError::InvalidEntry { path, entry: name.clone(), reason });
+                        }
+                    }
+                }
+                if let Some(name) = &placed.being {
+                    match game_db.beings.get_by_name(name) {
+                        Some(_) => world.beings.push((name.clone(), pos)),
+                        None => {
+                            let reason = "no being has this name".to_string();
+                            errors.push(RawsError::InvalidEntry { path, entry: name.clone(), reason });
+                        }
+                    }
+                }
+            }
+        }
+        world
+    }
+}
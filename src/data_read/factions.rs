@@ -0,0 +1,96 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use lazy_static::lazy_static;
+use log::error;
+use serde::Deserialize;
+
+use crate::factions::{Faction, Relation};
+
+use super::read_json5;
+
+lazy_static! {
+    pub static ref FACTION_DB: Mutex<FactionDatabase> = Mutex::new(FactionDatabase::empty());
+}
+
+const FACTIONS_PATH: &str = "raws/factions.json5";
+
+#[derive(Deserialize)]
+pub(crate) struct RawRelation {
+    faction: Faction,
+    toward: Faction,
+    relation: Relation,
+}
+
+/// How every faction feels toward the others
+pub struct FactionDatabase {
+    relations: HashMap<(Faction, Faction), Relation>,
+}
+
+impl FactionDatabase {
+    pub fn empty() -> Self {
+        Self { relations: HashMap::new() }
+    }
+
+    pub fn load(&mut self) {
+        match read_json5::<Vec<RawRelation>>(FACTIONS_PATH) {
+            Ok(raws) => *self = Self::from_raw(raws),
+            Err(e) => {
+                error!("{}", e);
+                *self = Self::empty();
+            }
+        }
+    }
+
+    pub(crate) fn from_raw(raws: Vec<RawRelation>) -> Self {
+        let mut relations = HashMap::new();
+        for raw in raws {
+            if raw.faction == raw.toward {
+                error!(
+                    "{}: {:?} toward itself was skipped, factions are always allies of themselves",
+                    FACTIONS_PATH, raw.faction
+                );
+                continue;
+            }
+            if relations.insert((raw.faction, raw.toward), raw.relation).is_some() {
+                error!(
+                    "{}: {:?} toward {:?} is listed more than once, the last one is used",
+                    FACTIONS_PATH, raw.faction, raw.toward
+                );
+            }
+        }
+        Self { relations }
+    }
+
+    /// How `faction` feels toward `toward`, pairs that aren't listed are neutral
+    pub fn relation(&self, faction: Faction, toward: Faction) -> Relation {
+        if faction == toward {
+            return Relation::Ally;
+        }
+        self.relations.get(&(faction, toward)).copied().unwrap_or(Relation::Neutral)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unlisted_pairs_are_neutral_and_factions_stick_together() {
+        let raws = vec![
+            RawRelation { faction: Faction::Predator, toward: Faction::Wildlife, relation: Relation::Hostile },
+            RawRelation { faction: Faction::Monster, toward: Faction::Monster, relation: Relation::Hostile },
+        ];
+        let factions = FactionDatabase::from_raw(raws);
+
+        assert_eq!(factions.relation(Faction::Predator, Faction::Wildlife), Relation::Hostile);
+        assert_eq!(factions.relation(Faction::Wildlife, Faction::Predator), Relation::Neutral);
+        assert_eq!(factions.relation(Faction::Monster, Faction::Monster), Relation::Ally);
+    }
+
+    #[test]
+    fn shipped_factions_load() {
+        let mut factions = FactionDatabase::empty();
+        factions.load();
+        assert_eq!(factions.relation(Faction::Monster, Faction::Player), Relation::Hostile);
+    }
+}
@@ -1,14 +1,20 @@
-use std::fs;
-
 use serde::Deserialize;
-use serde_json::from_str;
 
 use crate::{
-    components::{AttackBonus, Consumable, Equipable},
-    items::{ItemID, ItemInfo},
+    components::{
+        AttackBonus, Blast, Boat, Buoyancy, Consumable, Durability, Equipable, MiningArea, StatModifiers, Tags,
+        TargetEffect, TargetedUse, WeaponCooldown, WeaponReach,
+    },
+    items::{ItemID, ItemInfo, Rarity},
 };
 
+use super::{find_collisions, read_json, LookupError, RawsError};
+
+const ITEMS_PATH: &str = "raws/items.json";
+
 pub struct ItemDatabase {
+    /// The file the data was loaded from, used when reporting problems
+    path: &'static str,
     data: Vec<ItemInfo>,
 }
 
@@ -19,22 +25,58 @@ pub struct RawItemDatabase {
 
 impl ItemDatabase {
     pub(crate) fn empty() -> Self {
-        Self { data: Vec::new() }
+        Self { path: ITEMS_PATH, data: Vec::new() }
+    }
+
+    /// Bad entries are skipped and added to `errors`
+    pub fn load(errors: &mut Vec<RawsError>) -> Self {
+        Self::load_from(ITEMS_PATH, errors)
     }
 
-    pub fn load() -> Self {
-        let contents: String =
-            fs::read_to_string("raws/items.json").expect("Unable to find items.json at `raws/items.json`");
-        let raw_info_db: RawItemDatabase = from_str(&contents).expect("Bad JSON in items.json fix it");
-        ItemDatabase { data: raw_info_db.data.iter().map(ItemInfo::from_raw).collect() }
+    pub(crate) fn load_from(path: &'static str, errors: &mut Vec<RawsError>) -> Self {
+        let raw_info_db: RawItemDatabase = match read_json(path) {
+            Ok(raw) => raw,
+            Err(e) => {
+                errors.push(e);
+                return Self { path, data: Vec::new() };
+            }
+        };
+
+        let mut data: Vec<ItemInfo> = Vec::new();
+        for raw in raw_info_db.data.iter() {
+            match ItemInfo::from_raw(raw) {
+                Ok(item) => data.push(item),
+                Err(reason) => errors.push(RawsError::InvalidEntry { path, entry: raw.name.clone(), reason }),
+            }
+        }
+        ItemDatabase { path, data }
+    }
+
+    /// Checks that no two items share an id or a name since lookups only ever find the first
+    pub(crate) fn validate(&self) -> Result<(), RawsError> {
+        let collisions =
+            find_collisions(self.data.iter().map(|item| (item.identifier.value().to_string(), item.name.as_str())));
+        if collisions.is_empty() {
+            return Ok(());
+        }
+        Err(RawsError::Duplicates { path: self.path, collisions })
     }
 
     pub fn get_by_name(&self, name: &str) -> Option<&ItemInfo> {
         self.data.iter().find(|i| i.name.eq(name))
     }
 
-    /// Gets the entity by name without ensuring it exists.
-    /// This could by panic but can be used when certain a name would exist for an item.
+    /// Gets the item by name, the error holds the name that was missing
+    pub fn try_get_by_name(&self, name: &str) -> Result<&ItemInfo, LookupError> {
+        self.get_by_name(name).ok_or_else(|| LookupError::Name(name.to_string()))
+    }
+
+    /// Gets the item by name without ensuring it exists.
+    /// Only call this with names read from the database itself, anything typed by hand or read from other raws
+    /// should use `try_get_by_name` since the data can drift.
+    /// # Panics
+    /// When no item has the name
+    #[allow(dead_code)]
     pub fn get_by_name_unchecked(&self, name: &String) -> &ItemInfo {
         self.data.iter().find(|i| i.name.eq(name)).unwrap()
     }
@@ -42,6 +84,15 @@ impl ItemDatabase {
     pub fn get_by_id(&self, id: ItemID) -> Option<&ItemInfo> {
         self.data.iter().find(|i| i.identifier == id)
     }
+
+    /// Gets the item by id, the error holds the id that was missing
+    pub fn try_get_by_id(&self, id: ItemID) -> Result<&ItemInfo, LookupError> {
+        self.get_by_id(id).ok_or(LookupError::Id(id.value()))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ItemInfo> {
+        self.data.iter()
+    }
 }
 
 #[derive(Deserialize)]
@@ -50,12 +101,35 @@ pub struct RawItemInfo {
     pub identifier: ItemID,
     pub name: String,
     pub examine_text: String,
-    pub atlas_index: u8,
+    pub atlas_index: usize,
     pub fg: (u8, u8, u8),
     pub pickup_text: Option<String>,
     pub equipable: Option<String>,
     pub attack_bonus: Option<usize>,
+    /// Defense and speed added to whoever has the item equipped
+    pub stat_modifiers: Option<StatModifiers>,
+    pub mining_area: Option<u32>,
+    /// Turns between swings while the item is wielded
+    pub attack_cooldown: Option<u32>,
+    /// Tiles in a line hit by each swing while the item is wielded
+    pub reach: Option<u32>,
+    /// Uses before the item breaks, items without it never wear out
+    pub durability: Option<u32>,
+    /// Carrying the item lets its owner travel over water
+    #[serde(default)]
+    pub boat: bool,
+    /// Whether the item floats or sinks when it ends up on water, most things sink
+    #[serde(default)]
+    pub buoyancy: Buoyancy,
     pub consumable: Option<RawConsumable>,
+    pub target_use: Option<RawTargetUse>,
+    #[serde(default)]
+    pub rarity: Rarity,
+    /// Gold a shop asks for the item, items worth 0 can't be sold
+    #[serde(default)]
+    pub value: u32,
+    /// Labels other systems can find the item by, ie. "edible"
+    pub tags: Option<Vec<String>>,
 }
 
 #[derive(Deserialize, Clone)]
@@ -64,18 +138,105 @@ pub struct RawConsumable {
     pub amount: Option<usize>,
 }
 
+#[derive(Deserialize, Clone)]
+pub struct RawTargetUse {
+    pub effect: String,
+    /// The world object or item the use makes, explosives make nothing and leave it out
+    #[serde(default)]
+    pub result: String,
+    pub range: Option<u32>,
+    /// How far an explosive's blast reaches and how hard it hits
+    pub radius: Option<u32>,
+    pub damage: Option<u32>,
+}
+
+/// Targeted uses reach the tiles next to the user unless the raws say otherwise
+const DEFAULT_TARGET_RANGE: u32 = 1;
+
 impl ItemInfo {
-    fn from_raw(value: &RawItemInfo) -> Self {
-        Self {
+    fn from_raw(value: &RawItemInfo) -> Result<Self, String> {
+        let equipable = match &value.equipable {
+            Some(slot) => {
+                Some(Equipable::from_str(slot).ok_or_else(|| format!("{} is not a valid equipment slot", slot))?)
+            }
+            None => None,
+        };
+        if value.attack_cooldown == Some(0) {
+            return Err("attack_cooldown must be above 0".to_string());
+        }
+        if value.reach == Some(0) {
+            return Err("reach must be above 0".to_string());
+        }
+        if value.durability == Some(0) {
+            return Err("durability must be above 0".to_string());
+        }
+        let consumable = match &value.consumable {
+            Some(rc) => {
+                let amount = rc.amount.ok_or_else(|| format!("consumable {} has no amount", rc.effect))?;
+                Some(
+                    Consumable::from_str(&rc.effect, amount)
+                        .ok_or_else(|| format!("{} is not a valid consumable effect", rc.effect))?,
+                )
+            }
+            None => None,
+        };
+        let target_use = match &value.target_use {
+            Some(rt) => {
+                let effect = match rt.effect.as_str() {
+                    "explode" => match (rt.radius, rt.damage) {
+                        (Some(radius), Some(damage)) if radius > 0 => TargetEffect::Explode(Blast { radius, damage }),
+                        (Some(_), Some(_)) => return Err("explode radius must be above 0".to_string()),
+                        _ => return Err("explode needs a radius and damage".to_string()),
+                    },
+                    _ if rt.result.is_empty() => return Err(format!("{} needs a result", rt.effect)),
+                    _ => TargetEffect::from_str(&rt.effect, rt.result.clone())
+                        .ok_or_else(|| format!("{} is not a valid target effect", rt.effect))?,
+                };
+                let range = rt.range.unwrap_or(DEFAULT_TARGET_RANGE);
+                if range == 0 {
+                    return Err("target_use range must be above 0".to_string());
+                }
+                Some(TargetedUse { effect, range })
+            }
+            None => None,
+        };
+
+        Ok(Self {
             identifier: value.identifier,
             name: value.name.clone(),
             examine_text: value.examine_text.clone(),
             atlas_index: value.atlas_index,
             fg: value.fg,
             pickup_text: value.pickup_text.clone(),
-            equipable: value.equipable.clone().map(|e| Equipable::from_str(&e)),
+            equipable,
             attack_bonus: value.attack_bonus.map(|bonus| AttackBonus(bonus as i32)),
-            consumable: value.consumable.clone().map(|rc| Consumable::from_str(&rc.effect, rc.amount.unwrap())),
-        }
+            stat_modifiers: value.stat_modifiers,
+            mining_area: value.mining_area.map(MiningArea),
+            attack_cooldown: value.attack_cooldown.map(WeaponCooldown),
+            reach: value.reach.map(WeaponReach),
+            durability: value.durability.map(Durability::new),
+            boat: value.boat.then_some(Boat {}),
+            buoyancy: value.buoyancy,
+            consumable,
+            target_use,
+            rarity: value.rarity,
+            value: value.value,
+            tags: Tags::from_raw(&value.tags),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{components::Renderable, z_order::ITEM_Z};
+
+    #[test]
+    fn atlas_indices_past_a_byte_reach_the_sprite() {
+        let json = r#"{ "identifier": 900, "name": "Shell", "examine_text": "", "atlas_index": 300, "fg": [1, 2, 3] }"#;
+        let raw: RawItemInfo = serde_json::from_str(json).unwrap();
+        let info = ItemInfo::from_raw(&raw).unwrap();
+        assert_eq!(info.atlas_index, 300);
+        assert_eq!(Renderable::clear_bg(info.atlas_index, info.fg, ITEM_Z).atlas_index, 300);
     }
 }
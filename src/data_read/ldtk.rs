@@ -0,0 +1,178 @@
+/* ldtk.rs
+ *   Reads the entity and int grid layers of a level made in the LDtk editor so objects and terrain can be placed by
+ *   hand instead of by coordinates in the raws. Only the parts of the project json needed for those are read.
+ * */
+
+use std::{collections::HashMap, fmt, fs};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::components::Position;
+
+#[derive(Deserialize)]
+struct LdtkProject {
+    levels: Vec<LdtkLevel>,
+}
+
+#[derive(Deserialize)]
+struct LdtkLevel {
+    identifier: String,
+    /// Missing when the project saves levels to separate files
+    #[serde(rename = "layerInstances")]
+    layer_instances: Option<Vec<LdtkLayer>>,
+}
+
+#[derive(Deserialize)]
+struct LdtkLayer {
+    #[serde(rename = "__type")]
+    layer_type: String,
+    #[serde(rename = "entityInstances", default)]
+    entity_instances: Vec<RawLdtkEntity>,
+    /// Width of the layer in tiles
+    #[serde(rename = "__cWid", default)]
+    width: usize,
+    /// One value per tile going row by row, 0 is a tile left empty in the editor
+    #[serde(rename = "intGridCsv", default)]
+    int_grid: Vec<i64>,
+}
+
+#[derive(Deserialize)]
+struct RawLdtkEntity {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    /// Tile coordinates of the entity's pivot
+    #[serde(rename = "__grid")]
+    grid: (usize, usize),
+    #[serde(rename = "fieldInstances", default)]
+    field_instances: Vec<RawLdtkField>,
+}
+
+#[derive(Deserialize)]
+struct RawLdtkField {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    #[serde(rename = "__value")]
+    value: Value,
+}
+
+/// An entity placed in the editor
+#[derive(Debug, Clone)]
+pub struct LdtkEntity {
+    /// The editor can't have spaces in identifiers so `Fire_Wizard` is the being named `Fire Wizard`
+    pub identifier: String,
+    pub position: Position,
+    /// Custom fields set on the entity, fields left empty in the editor are not included
+    pub fields: HashMap<String, Value>,
+}
+
+impl LdtkEntity {
+    /// The name the entity goes by in the raws
+    pub fn name(&self) -> String {
+        self.identifier.replace('_', " ")
+    }
+
+    pub fn int_field(&self, field: &str) -> Option<i64> {
+        self.fields.get(field).and_then(Value::as_i64)
+    }
+
+    pub fn bool_field(&self, field: &str) -> Option<bool> {
+        self.fields.get(field).and_then(Value::as_bool)
+    }
+
+    pub fn str_field(&self, field: &str) -> Option<&str> {
+        self.fields.get(field).and_then(Value::as_str)
+    }
+}
+
+#[derive(Debug)]
+pub enum LdtkError {
+    Unreadable(String),
+    NoLevel(String),
+}
+
+impl fmt::Display for LdtkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LdtkError::Unreadable(reason) => write!(f, "the ldtk project could not be read, {}", reason),
+            LdtkError::NoLevel(level) => write!(f, "the ldtk project has no level named {}", level),
+        }
+    }
+}
+
+fn read_level(path: &str, level: &str) -> Result<LdtkLevel, LdtkError> {
+    let contents = fs::read_to_string(path).map_err(|e| LdtkError::Unreadable(e.to_string()))?;
+    let project: LdtkProject = serde_json::from_str(&contents).map_err(|e| LdtkError::Unreadable(e.to_string()))?;
+    project
+        .levels
+        .into_iter()
+        .find(|found| found.identifier == level)
+        .ok_or_else(|| LdtkError::NoLevel(level.to_string()))
+}
+
+/// Every entity on the entity layers of `level`, moved over by `offset` tiles
+pub fn read_level_entities(path: &str, level: &str, offset: Position) -> Result<Vec<LdtkEntity>, LdtkError> {
+    Ok(read_level(path, level)?
+        .layer_instances
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|layer| layer.layer_type == "Entities")
+        .flat_map(|layer| layer.entity_instances)
+        .map(|raw| LdtkEntity {
+            identifier: raw.identifier,
+            position: Position::new(raw.grid.0 + offset.x, raw.grid.1 + offset.y),
+            fields: raw
+                .field_instances
+                .into_iter()
+                .filter(|field| !field.value.is_null())
+                .map(|field| (field.identifier, field.value))
+                .collect(),
+        })
+        .collect())
+}
+
+/// The value of every tile set on the int grid layers of `level`, moved over by `offset` tiles. Empty tiles are left
+/// out and when layers overlap the value from the layer on top comes last
+pub fn read_level_int_grid(path: &str, level: &str, offset: Position) -> Result<Vec<(Position, i64)>, LdtkError> {
+    Ok(read_level(path, level)?
+        .layer_instances
+        .unwrap_or_default()
+        .into_iter()
+        .rev()
+        .filter(|layer| layer.layer_type == "IntGrid" && layer.width > 0)
+        .flat_map(|layer| {
+            let width = layer.width;
+            layer
+                .int_grid
+                .into_iter()
+                .enumerate()
+                .filter(|(_, value)| *value != 0)
+                .map(move |(idx, value)| (Position::new(idx % width + offset.x, idx / width + offset.y), value))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entity_layers_are_read_from_the_level() {
+        let path = "resources/ldtk/rpg_world_v2.ldtk";
+        let merchants = read_level_entities(path, "Level_2", Position::new(1, 2)).unwrap();
+        assert_eq!(merchants.len(), 5);
+        assert_eq!(merchants[0].name(), "Merchant");
+        assert_eq!(merchants[0].position, Position::new(79, 15));
+
+        assert!(matches!(read_level_entities(path, "Level_99", Position::new(0, 0)), Err(LdtkError::NoLevel(_))));
+    }
+
+    #[test]
+    fn int_grid_values_are_read_without_empty_tiles() {
+        let grid = read_level_int_grid("resources/ldtk/rpg_world_v1.ldtk", "Level_0", Position::new(1, 0)).unwrap();
+        assert_eq!(grid.len(), 1397);
+        assert!(grid.contains(&(Position::new(3, 0), 1)));
+        assert!(grid.contains(&(Position::new(45, 0), 2)));
+        assert!(!grid.iter().any(|(pos, _)| *pos == Position::new(1, 0)));
+    }
+}
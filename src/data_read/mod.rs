@@ -1,9 +1,16 @@
 mod animations;
 mod audio;
+mod autotile;
 mod beings;
+mod crops;
+mod debug_world;
+mod factions;
 mod items;
+mod ldtk;
+mod new_game;
 mod noise;
 mod recipes;
+mod tile_kinds;
 mod world_objs;
 
 /// A tight bunch of important data reading stuff such as the databases and json loading
@@ -13,9 +20,15 @@ mod world_objs;
 pub mod prelude {
     pub use crate::data_read::animations::ANIMATION_DB;
     pub use crate::data_read::audio::{AUDIOMAN, AUDIO_DB};
+    pub use crate::data_read::autotile::{AutotileRule, Neighbours, AUTOTILE_DB};
     pub use crate::data_read::beings::build_being;
+    pub use crate::data_read::crops::{build_crop, CROP_DB};
+    pub use crate::data_read::debug_world::DebugWorld;
+    pub use crate::data_read::factions::FACTION_DB;
+    pub use crate::data_read::new_game::{BeingStart, NewGameConfig, TerrainTile};
     pub use crate::data_read::noise::NOISE_DB;
-    pub use crate::data_read::recipes::RECIPE_DB;
+    pub use crate::data_read::recipes::{RecipeDatabase, RECIPE_DB};
+    pub use crate::data_read::tile_kinds::{TileKind, TILE_KIND_DB, VEIN_TAG};
     pub use crate::data_read::world_objs::build_world_obj;
     pub use crate::data_read::ENTITY_DB;
 }
@@ -24,9 +37,12 @@ use itertools::Itertools;
 use lazy_static::lazy_static;
 use log::{debug, error, warn};
 use noise::NOISE_DB;
-use prelude::{ANIMATION_DB, AUDIO_DB};
-use serde::{Deserialize, Serialize};
-use std::sync::Mutex;
+use prelude::{ANIMATION_DB, AUDIO_DB, AUTOTILE_DB, CROP_DB, FACTION_DB, TILE_KIND_DB};
+use serde::{
+    de::{self, DeserializeOwned},
+    Deserialize, Deserializer, Serialize,
+};
+use std::{collections::HashSet, fmt, fs, sync::Mutex};
 
 use crate::{
     droptables::{DropQty, Drops, Loot},
@@ -45,7 +61,131 @@ lazy_static! {
 }
 
 #[derive(Debug)]
-pub struct EntityBuildError;
+pub enum EntityBuildError {
+    /// There is no definition with the name in the database
+    NotFound(String),
+    /// The definition has a value the builder can't turn into a component
+    InvalidField { name: String, reason: String },
+}
+
+impl fmt::Display for EntityBuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EntityBuildError::NotFound(name) => write!(f, "no definition found named {}", name),
+            EntityBuildError::InvalidField { name, reason } => write!(f, "{} could not be built: {}", name, reason),
+        }
+    }
+}
+
+/// Reads the number inside an `ItemID` or `BeingID`, `kind` names which one in the error so a typo like `-1` in
+/// the raws or a save points at the id that was wrong
+pub(crate) fn deserialize_id<'de, D: Deserializer<'de>>(deserializer: D, kind: &str) -> Result<u32, D::Error> {
+    let raw = i64::deserialize(deserializer)?;
+    u32::try_from(raw).map_err(|_| de::Error::custom(format!("{} id {} is not between 0 and {}", kind, raw, u32::MAX)))
+}
+
+/// A lookup in one of the databases that found nothing
+#[derive(Debug, PartialEq)]
+pub enum LookupError {
+    Name(String),
+    Id(u32),
+}
+
+impl fmt::Display for LookupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LookupError::Name(name) => write!(f, "nothing is named {}", name),
+            LookupError::Id(id) => write!(f, "nothing has the id {}", id),
+        }
+    }
+}
+
+/// Problems found while reading the raws. A bad file leaves its database empty and a bad entry is skipped,
+/// so a typo in the data is reported instead of stopping the game
+#[derive(Debug)]
+pub enum RawsError {
+    File {
+        path: &'static str,
+        error: std::io::Error,
+    },
+    /// The message includes the line and column of the bad json when it is known
+    Json {
+        path: &'static str,
+        message: String,
+    },
+    Duplicates {
+        path: &'static str,
+        collisions: Vec<Collision>,
+    },
+    InvalidEntry {
+        path: &'static str,
+        entry: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for RawsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RawsError::File { path, error } => write!(f, "{}: could not be read, {}", path, error),
+            RawsError::Json { path, message } => write!(f, "{}: bad json, {}", path, message),
+            RawsError::Duplicates { path, collisions } => {
+                write!(f, "{}: duplicate entries found,", path)?;
+                for collision in collisions.iter() {
+                    match collision {
+                        Collision::Id(id) => write!(f, " id {}", id)?,
+                        Collision::Name(name) => write!(f, " name {}", name)?,
+                    }
+                }
+                Ok(())
+            }
+            RawsError::InvalidEntry { path, entry, reason } => write!(f, "{}: {} was skipped, {}", path, entry, reason),
+        }
+    }
+}
+
+/// An id or name used by more than one entry in a database
+#[derive(Debug, PartialEq)]
+pub enum Collision {
+    Id(String),
+    Name(String),
+}
+
+/// Finds every id and name that appears more than once in the (id, name) pairs, each is listed once
+pub(crate) fn find_collisions<'a>(entries: impl Iterator<Item = (String, &'a str)>) -> Vec<Collision> {
+    let mut ids = HashSet::new();
+    let mut names = HashSet::new();
+    let mut collisions = Vec::new();
+    for (id, name) in entries {
+        if !ids.insert(id.clone()) && !collisions.contains(&Collision::Id(id.clone())) {
+            collisions.push(Collision::Id(id));
+        }
+        if !names.insert(name) && !collisions.contains(&Collision::Name(name.to_string())) {
+            collisions.push(Collision::Name(name.to_string()));
+        }
+    }
+    collisions
+}
+
+/// Reads a json file from the raws into `T`
+pub(crate) fn read_json<T: DeserializeOwned>(path: &'static str) -> Result<T, RawsError> {
+    let contents = fs::read_to_string(path).map_err(|error| RawsError::File { path, error })?;
+    serde_json::from_str(&contents).map_err(|e| RawsError::Json { path, message: e.to_string() })
+}
+
+/// Reads a json5 file from the raws into `T`
+pub(crate) fn read_json5<T: DeserializeOwned>(path: &'static str) -> Result<T, RawsError> {
+    let contents = fs::read_to_string(path).map_err(|error| RawsError::File { path, error })?;
+    json5::from_str(&contents).map_err(|e| {
+        let message = match &e {
+            json5::Error::Message { msg, location: Some(loc) } => {
+                format!("{} at line {} column {}", msg, loc.line, loc.column)
+            }
+            json5::Error::Message { msg, location: None } => msg.clone(),
+        };
+        RawsError::Json { path, message }
+    })
+}
 
 pub struct GameData {
     pub items: ItemDatabase,
@@ -61,29 +201,80 @@ impl GameData {
     fn load(&mut self, data: GameData) {
         *self = data;
     }
+
+    /// Runs after loading to catch entries that would shadow each other in lookups
+    fn validate(&self) -> Vec<RawsError> {
+        [self.items.validate(), self.world_objs.validate(), self.beings.validate()]
+            .into_iter()
+            .filter_map(Result::err)
+            .collect()
+    }
 }
 
-/// Creates global instances of static data present in the `raws/` folder
-pub fn initialize_game_databases() {
-    debug!("startup: starting to load game databases");
+/// Reads the items, world objects and beings raws, anything wrong with them is added to `errors`
+fn load_entity_data(errors: &mut Vec<RawsError>) -> GameData {
     let mut game_db = GameData::new();
 
     // the item database must be loaded first since other tables rely on looking up item names to find their ids
-    game_db.items = ItemDatabase::load();
+    game_db.items = ItemDatabase::load(errors);
+
+    game_db.world_objs = WorldObjectDatabase::load(&game_db, errors);
+
+    game_db.beings = BeingDatabase::load(&game_db, errors);
+
+    errors.extend(game_db.validate());
+    game_db
+}
 
-    game_db.world_objs = WorldObjectDatabase::load(&game_db);
+/// Creates global instances of static data present in the `raws/` folder
+pub fn initialize_game_databases() {
+    debug!("startup: starting to load game databases");
+    let mut errors = Vec::new();
+    let game_db = load_entity_data(&mut errors);
 
-    game_db.beings = BeingDatabase::load(&game_db);
+    CROP_DB.lock().unwrap().load(&game_db);
+
+    for raws_error in errors.iter() {
+        error!("{}", raws_error);
+    }
+    if !errors.is_empty() {
+        warn!("{} problems found in the raws, the game may be missing some content", errors.len());
+    }
 
     ENTITY_DB.lock().unwrap().load(game_db);
     NOISE_DB.lock().unwrap().load();
     RECIPE_DB.lock().unwrap().load();
     ANIMATION_DB.lock().unwrap().load();
     AUDIO_DB.lock().unwrap().load();
+    AUTOTILE_DB.lock().unwrap().load();
+    TILE_KIND_DB.lock().unwrap().load();
+    FACTION_DB.lock().unwrap().load();
 
     debug!("startup: finished loading game databases");
 }
 
+/// Reads the items, world objects, beings and crops raws again while the game is running. The new data is only
+/// swapped in when it loads without problems so a half saved file can't empty a database, the problems are
+/// returned instead.
+///
+/// Changes to anything looked up when it is used take effect right away: item names, examine text, prices,
+/// drop tables, respawn times, crop stages and what items and world objects spawn as from then on.
+/// Anything copied onto an entity when it was built, such as sprites, stats, health, AI and quips, stays as
+/// it was until that entity is spawned again.
+#[cfg(feature = "dev")]
+pub fn reload_entity_databases() -> Result<(), Vec<RawsError>> {
+    let mut errors = Vec::new();
+    let game_db = load_entity_data(&mut errors);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    CROP_DB.lock().unwrap().load(&game_db);
+    ENTITY_DB.lock().unwrap().load(game_db);
+    debug!("entity databases were reloaded");
+    Ok(())
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub(crate) struct OptionalStats {
     pub intelligence: Option<usize>,
@@ -105,26 +296,34 @@ impl Stats {
             vitality: some_stats.vitality.map_or(0, |stat| stat),
         }
     }
+
+    /// Replaces only the stats that are given
+    pub(crate) fn override_with(&mut self, some_stats: &OptionalStats) {
+        self.intelligence = some_stats.intelligence.unwrap_or(self.intelligence);
+        self.charisma = some_stats.charisma.unwrap_or(self.charisma);
+        self.dexterity = some_stats.dexterity.unwrap_or(self.dexterity);
+        self.strength = some_stats.strength.unwrap_or(self.strength);
+        self.precision = some_stats.precision.unwrap_or(self.precision);
+        self.vitality = some_stats.vitality.unwrap_or(self.vitality);
+    }
 }
 
 impl Drops {
-    pub(crate) fn from_raw(raw: &RawDrops, game_db: &GameData) -> Self {
-        Self {
-            drop_chance: raw.drop_chance,
-            loot_table: raw
-                .loot_table
-                .iter()
-                .map(|raw_loot| Loot {
-                    id: game_db
-                        .items
-                        .get_by_name(&raw_loot.item)
-                        .unwrap_or_else(|| panic!("{} has no definition in items", raw_loot.item))
-                        .identifier,
+    /// Fails with the name of the first loot item that has no definition in items
+    pub(crate) fn from_raw(raw: &RawDrops, game_db: &GameData) -> Result<Self, String> {
+        let loot_table = raw
+            .loot_table
+            .iter()
+            .map(|raw_loot| match game_db.items.get_by_name(&raw_loot.item) {
+                Some(item) => Ok(Loot {
+                    id: item.identifier,
                     qty: DropQty::from_str(&raw_loot.item_qty),
                     weight: raw_loot.weight,
-                })
-                .collect(),
-        }
+                }),
+                None => Err(format!("loot item {} has no definition in items", raw_loot.item)),
+            })
+            .collect::<Result<Vec<Loot>, String>>()?;
+        Ok(Self { drop_chance: raw.drop_chance, loot_table })
     }
 }
 
@@ -154,3 +353,182 @@ impl DropQty {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::debug_world::DebugWorld;
+    use super::new_game::{NewGameConfig, TerrainTile};
+    use super::*;
+    use crate::{components::Position, items::ItemQty};
+
+    #[test]
+    fn duplicate_items_are_listed() {
+        let mut errors = Vec::new();
+        let items = ItemDatabase::load_from("tests/fixtures/duplicate_items.json", &mut errors);
+        assert!(errors.is_empty());
+
+        match items.validate() {
+            Err(RawsError::Duplicates { collisions, .. }) => {
+                assert_eq!(collisions, vec![Collision::Id("1".to_string()), Collision::Name("Flint".to_string())])
+            }
+            _ => panic!("duplicate items were not found"),
+        }
+    }
+
+    #[test]
+    fn duplicate_beings_are_listed() {
+        let mut errors = Vec::new();
+        let beings = BeingDatabase::load_from("tests/fixtures/duplicate_beings.json", &GameData::new(), &mut errors);
+        assert!(errors.is_empty());
+
+        match beings.validate() {
+            Err(RawsError::Duplicates { collisions, .. }) => {
+                assert_eq!(collisions, vec![Collision::Id("0".to_string()), Collision::Name("Greg Goat".to_string())])
+            }
+            _ => panic!("duplicate beings were not found"),
+        }
+    }
+
+    #[test]
+    fn shipped_raws_have_no_duplicates() {
+        let mut errors = Vec::new();
+        let mut game_db = GameData::new();
+        game_db.items = ItemDatabase::load(&mut errors);
+        game_db.world_objs = WorldObjectDatabase::load(&game_db, &mut errors);
+        game_db.beings = BeingDatabase::load(&game_db, &mut errors);
+        errors.extend(game_db.validate());
+
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert!(messages.is_empty(), "{:?}", messages);
+    }
+
+    #[test]
+    fn shipped_new_game_names_exist() {
+        let mut errors = Vec::new();
+        let mut game_db = GameData::new();
+        game_db.items = ItemDatabase::load(&mut errors);
+        game_db.world_objs = WorldObjectDatabase::load(&game_db, &mut errors);
+        game_db.beings = BeingDatabase::load(&game_db, &mut errors);
+        errors.clear();
+
+        NewGameConfig::load(&game_db, &mut errors);
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert!(messages.is_empty(), "{:?}", messages);
+    }
+
+    #[test]
+    fn shipped_debug_world_breaks_with_every_tool() {
+        let mut errors = Vec::new();
+        let mut game_db = GameData::new();
+        game_db.items = ItemDatabase::load(&mut errors);
+        game_db.world_objs = WorldObjectDatabase::load(&game_db, &mut errors);
+        game_db.beings = BeingDatabase::load(&game_db, &mut errors);
+        errors.clear();
+
+        let debug_world = DebugWorld::load(&game_db, &mut errors);
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert!(messages.is_empty(), "{:?}", messages);
+
+        let tools: Vec<String> = debug_world
+            .world_objs
+            .iter()
+            .filter_map(|(_, _, tool)| tool.as_ref())
+            .map(|tool| format!("{:?}", tool))
+            .collect();
+        for tool in ["Hand", "Pickaxe", "Axe", "Shovel"] {
+            assert!(tools.iter().any(|listed| listed == tool), "nothing is broken with {}", tool);
+        }
+        assert!(debug_world.terrain.iter().any(|(_, terrain)| terrain.tile == "Water"));
+        assert!(!debug_world.beings.is_empty());
+    }
+
+    #[test]
+    fn shipped_tile_kinds_load() {
+        let mut tile_kinds = tile_kinds::TileKindDatabase::empty();
+        tile_kinds.load();
+
+        let water = tile_kinds.get_by_name("Water").expect("water has a tile kind");
+        assert!(water.fishable && water.blocking);
+        assert!(tile_kinds.get_by_name("Grass").is_some_and(|grass| !grass.needs_entity()));
+        let veins = tile_kinds.get_by_name("Mountain").and_then(|mountain| mountain.veins.clone()).unwrap();
+        let mut errors = Vec::new();
+        let mut game_db = GameData::new();
+        game_db.items = ItemDatabase::load(&mut errors);
+        let world_objs = WorldObjectDatabase::load(&game_db, &mut errors);
+        for vein in veins.table {
+            let obj = world_objs.get_by_name(&vein.object).expect("veins are world objects");
+            assert!(
+                obj.tags.as_ref().is_some_and(|tags| tags.has(tile_kinds::VEIN_TAG)),
+                "{} is not tagged a vein",
+                vein.object
+            );
+        }
+    }
+
+    #[test]
+    fn shipped_crops_load() {
+        let mut errors = Vec::new();
+        let mut game_db = GameData::new();
+        game_db.items = ItemDatabase::load(&mut errors);
+        let mut crops = crops::CropDatabase::empty();
+        crops.load(&game_db);
+
+        let carrot = crops.get_by_name("Carrot Crop").expect("carrots can be planted");
+        assert!(!carrot.is_ripe(0));
+        assert!(carrot.is_ripe(carrot.stages.len() - 1));
+        assert!(!carrot.yields.is_empty());
+    }
+
+    #[test]
+    fn ldtk_entities_are_placed_by_their_raws_name() {
+        let mut errors = Vec::new();
+        let mut game_db = GameData::new();
+        game_db.items = ItemDatabase::load(&mut errors);
+        game_db.world_objs = WorldObjectDatabase::load(&game_db, &mut errors);
+        game_db.beings = BeingDatabase::load(&game_db, &mut errors);
+        errors.clear();
+
+        let config = NewGameConfig::load_from("tests/fixtures/ldtk_new_game.json", &game_db, &mut errors);
+        assert!(errors.is_empty());
+        assert_eq!(config.beings.iter().filter(|being| being.name == "Fire Wizard").count(), 2);
+        assert_eq!(config.world_objs.len(), 6);
+        let rod = game_db.items.get_by_name("Fishing Rod").unwrap().identifier;
+        assert!(config.ground_items.contains(&(rod, ItemQty(1), Position::new(4, 34))));
+        assert_eq!(config.player.position, Some(Position::new(10, 10)));
+        assert!(config.terrain.contains(&(Position::new(3, 0), TerrainTile::named("Mountain"))));
+        assert!(config.terrain.contains(&(Position::new(45, 0), TerrainTile::named("Water"))));
+    }
+
+    #[test]
+    fn ldtk_int_grid_values_follow_the_openings_mapping() {
+        let mut errors = Vec::new();
+        let config = NewGameConfig::load_from("tests/fixtures/ldtk_int_grid.json", &GameData::new(), &mut errors);
+        assert!(errors.is_empty());
+        let gravel = TerrainTile { tile: "Gravel".to_string(), blocking: Some(true), move_cost: Some(30) };
+        assert!(config.terrain.contains(&(Position::new(2, 0), gravel)));
+        // 2 is left out of the mapping so it falls back to floor
+        assert!(config.terrain.contains(&(Position::new(44, 0), TerrainTile::floor())));
+    }
+
+    #[test]
+    fn missing_new_game_falls_back_to_minimal() {
+        let mut errors = Vec::new();
+        let config = NewGameConfig::load_from("tests/fixtures/does_not_exist.json", &GameData::new(), &mut errors);
+        assert!(matches!(errors[..], [RawsError::File { .. }]));
+        assert!(config.inventory.is_empty() && config.beings.is_empty());
+        assert_eq!(config.player.position, None);
+    }
+
+    #[test]
+    fn ids_are_read_as_plain_numbers_that_fit_a_u32() {
+        use crate::{being::BeingID, items::ItemID};
+
+        assert_eq!(serde_json::from_str::<ItemID>("208").unwrap(), ItemID::new(208));
+        assert_eq!(serde_json::to_string(&ItemID::new(208)).unwrap(), "208");
+        assert_eq!(serde_json::from_str::<BeingID>("3").unwrap().value(), 3);
+
+        let negative = serde_json::from_str::<ItemID>("-1").unwrap_err().to_string();
+        assert!(negative.contains("item id -1"), "{}", negative);
+        assert!(serde_json::from_str::<BeingID>("4294967296").is_err());
+    }
+}
@@ -0,0 +1,337 @@
+use std::collections::{HashMap, HashSet};
+
+use log::warn;
+use serde::Deserialize;
+
+use crate::{
+    components::Position,
+    items::{ItemID, ItemQty},
+    stats::{get_random_stats, Stats},
+    triggers::{Trigger, TriggerAction},
+};
+
+use super::{
+    ldtk::{read_level_entities, read_level_int_grid, LdtkEntity},
+    read_json, GameData, OptionalStats, RawsError,
+};
+
+const NEW_GAME_PATH: &str = "raws/new_game.json";
+
+/// Everything placed in the world when a new game starts
+pub struct NewGameConfig {
+    pub player: PlayerStart,
+    pub inventory: Vec<(ItemID, ItemQty)>,
+    pub beings: Vec<BeingStart>,
+    pub world_objs: Vec<(String, Position)>,
+    /// Items lying on the ground, only placed from an ldtk level
+    pub ground_items: Vec<(ItemID, ItemQty, Position)>,
+    /// Tiles turned into water so there is somewhere to fish, only placed from an ldtk level
+    pub water: Vec<Position>,
+    /// Invisible tiles that fire when stepped on, only placed from an ldtk level
+    pub triggers: Vec<(Position, Trigger)>,
+    /// Tiles painted on the int grid layers of an ldtk level, placed over the generated map
+    pub terrain: Vec<(Position, TerrainTile)>,
+}
+
+/// What a tile is turned into when the map is made
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct TerrainTile {
+    /// Name of the tile's kind, ie. `Grass`, `Water` or `Mountain`
+    pub tile: String,
+    /// Overrides whether the tile kind blocks movement
+    pub blocking: Option<bool>,
+    /// Overrides the action points it takes to step onto the tile
+    pub move_cost: Option<u32>,
+}
+
+impl TerrainTile {
+    /// The tile kind with nothing overridden
+    pub fn named(tile: &str) -> Self {
+        Self { tile: tile.to_string(), blocking: None, move_cost: None }
+    }
+
+    /// Walkable grass, what int grid values without a tile become
+    pub fn floor() -> Self {
+        Self { blocking: Some(false), ..Self::named("Grass") }
+    }
+}
+
+pub struct PlayerStart {
+    /// Starts in the middle of the map when not given
+    pub position: Option<Position>,
+    /// Stats left out of the file are rolled randomly
+    pub stats: Stats,
+    pub gold: u32,
+}
+
+pub struct BeingStart {
+    pub name: String,
+    pub position: Position,
+    /// Gives the being a transform so it is drawn with the fancy sprites
+    pub fancy: bool,
+}
+
+#[derive(Deserialize)]
+struct RawNewGame {
+    player: RawPlayerStart,
+    #[serde(default)]
+    inventory: Vec<RawStartingItem>,
+    #[serde(default)]
+    beings: Vec<RawBeingStart>,
+    #[serde(default)]
+    world_objs: Vec<RawWorldObjStart>,
+    ldtk: Option<RawLdtkStart>,
+}
+
+/// An ldtk level whose entity layers are placed along with everything else
+#[derive(Deserialize)]
+struct RawLdtkStart {
+    file: String,
+    level: String,
+    /// Where the level's top left corner is on the map
+    #[serde(default)]
+    offset: (usize, usize),
+    /// What each int grid value turns its tile into, values are written as strings since they are json keys
+    #[serde(default = "default_int_grid")]
+    int_grid: HashMap<i64, TerrainTile>,
+}
+
+/// The int grid convention levels are painted with, 0 is left as generated since it is an empty tile in ldtk,
+/// 1 is blocked rock that can be mined and 2 is water. Any other value is walkable floor unless the opening maps it
+fn default_int_grid() -> HashMap<i64, TerrainTile> {
+    HashMap::from([(1, TerrainTile::named("Mountain")), (2, TerrainTile::named("Water"))])
+}
+
+#[derive(Deserialize)]
+struct RawPlayerStart {
+    position: Option<(usize, usize)>,
+    stats: Option<OptionalStats>,
+    #[serde(default)]
+    gold: u32,
+}
+
+#[derive(Deserialize)]
+struct RawStartingItem {
+    item: String,
+    #[serde(default = "one")]
+    qty: usize,
+}
+
+fn one() -> usize {
+    1
+}
+
+#[derive(Deserialize)]
+struct RawWorldObjStart {
+    name: String,
+    position: (usize, usize),
+}
+
+#[derive(Deserialize)]
+struct RawBeingStart {
+    name: String,
+    position: (usize, usize),
+    #[serde(default)]
+    fancy: bool,
+}
+
+impl NewGameConfig {
+    /// Reads the opening from the raws, entries naming unknown items or beings are skipped and added to `errors`.
+    /// Falls back to `minimal` when the file can't be read
+    pub fn load(game_db: &GameData, errors: &mut Vec<RawsError>) -> Self {
+        Self::load_from(NEW_GAME_PATH, game_db, errors)
+    }
+
+    pub(crate) fn load_from(path: &'static str, game_db: &GameData, errors: &mut Vec<RawsError>) -> Self {
+        let raw: RawNewGame = match read_json(path) {
+            Ok(raw) => raw,
+            Err(e) => {
+                errors.push(e);
+                return Self::minimal();
+            }
+        };
+
+        let inventory = raw
+            .inventory
+            .iter()
+            .filter_map(|start| match game_db.items.try_get_by_name(&start.item) {
+                Ok(item) => Some((item.identifier, ItemQty(start.qty))),
+                Err(e) => {
+                    errors.push(RawsError::InvalidEntry { path, entry: start.item.clone(), reason: e.to_string() });
+                    None
+                }
+            })
+            .collect();
+
+        let beings = raw
+            .beings
+            .iter()
+            .filter_map(|start| match game_db.beings.get_by_name(&start.name) {
+                Some(_) => Some(BeingStart {
+                    name: start.name.clone(),
+                    position: Position::new(start.position.0, start.position.1),
+                    fancy: start.fancy,
+                }),
+                None => {
+                    let reason = "no being has this name".to_string();
+                    errors.push(RawsError::InvalidEntry { path, entry: start.name.clone(), reason });
+                    None
+                }
+            })
+            .collect();
+
+        let world_objs = raw
+            .world_objs
+            .iter()
+            .filter_map(|start| match game_db.world_objs.get_by_name(&start.name) {
+                Some(_) => Some((start.name.clone(), Position::new(start.position.0, start.position.1))),
+                None => {
+                    let reason = "no world object has this name".to_string();
+                    errors.push(RawsError::InvalidEntry { path, entry: start.name.clone(), reason });
+                    None
+                }
+            })
+            .collect();
+
+        let mut stats = get_random_stats().set;
+        if let Some(some_stats) = &raw.player.stats {
+            stats.override_with(some_stats);
+        }
+        let player = PlayerStart {
+            position: raw.player.position.map(|(x, y)| Position::new(x, y)),
+            stats,
+            gold: raw.player.gold,
+        };
+
+        let mut config = Self {
+            player,
+            inventory,
+            beings,
+            world_objs,
+            ground_items: Vec::new(),
+            water: Vec::new(),
+            triggers: Vec::new(),
+            terrain: Vec::new(),
+        };
+        if let Some(ldtk) = &raw.ldtk {
+            let offset = Position::new(ldtk.offset.0, ldtk.offset.1);
+            let level = read_level_entities(&ldtk.file, &ldtk.level, offset)
+                .and_then(|entities| Ok((entities, read_level_int_grid(&ldtk.file, &ldtk.level, offset)?)));
+            match level {
+                Ok((entities, grid)) => {
+                    entities.iter().for_each(|entity| config.place_ldtk_entity(entity, game_db));
+                    config.terrain = ldtk_terrain(&grid, &ldtk.int_grid);
+                }
+                Err(e) => {
+                    errors.push(RawsError::InvalidEntry { path, entry: ldtk.file.clone(), reason: e.to_string() })
+                }
+            }
+        }
+        config
+    }
+
+    /// Adds an entity from an ldtk level by what its identifier names in the raws. `Player` sets where the player
+    /// starts when the opening doesn't already say, `Water` makes the tile water and `Trigger` places a trigger
+    fn place_ldtk_entity(&mut self, entity: &LdtkEntity, game_db: &GameData) {
+        let name = entity.name();
+        let known_fields: &[&str] = if name == "Player" {
+            self.player.position = self.player.position.or(Some(entity.position));
+            &[]
+        } else if name == "Water" {
+            self.water.push(entity.position);
+            &[]
+        } else if name == "Trigger" {
+            match ldtk_trigger(entity, game_db) {
+                Ok(trigger) => self.triggers.push((entity.position, trigger)),
+                Err(reason) => {
+                    warn!("ldtk trigger at {} was not placed, {}", entity.position, reason);
+                    return;
+                }
+            }
+            &["action", "message", "being", "count", "x", "y", "once"]
+        } else if game_db.beings.get_by_name(&name).is_some() {
+            let fancy = entity.bool_field("fancy").unwrap_or(false);
+            self.beings.push(BeingStart { name, position: entity.position, fancy });
+            &["fancy"]
+        } else if game_db.world_objs.get_by_name(&name).is_some() {
+            self.world_objs.push((name, entity.position));
+            &[]
+        } else if let Some(item) = game_db.items.get_by_name(&name) {
+            let qty = entity.int_field("qty").map_or(1, |qty| qty.max(1) as usize);
+            self.ground_items.push((item.identifier, ItemQty(qty), entity.position));
+            &["qty"]
+        } else {
+            warn!(
+                "ldtk entity {} at {} is not a being, world object or item, it was not placed",
+                name, entity.position
+            );
+            return;
+        };
+        for field in entity.fields.keys().filter(|field| !known_fields.contains(&field.as_str())) {
+            warn!("ldtk entity {} has a field {} which is not used", name, field);
+        }
+    }
+
+    /// Just the player with random stats, used when the opening can't be read
+    pub fn minimal() -> Self {
+        Self {
+            player: PlayerStart { position: None, stats: get_random_stats().set, gold: 0 },
+            inventory: Vec::new(),
+            beings: Vec::new(),
+            world_objs: Vec::new(),
+            ground_items: Vec::new(),
+            water: Vec::new(),
+            triggers: Vec::new(),
+            terrain: Vec::new(),
+        }
+    }
+}
+
+/// Turns int grid values into the tiles they stand for, a value missing from `mapping` is warned about once and
+/// becomes walkable floor
+fn ldtk_terrain(grid: &[(Position, i64)], mapping: &HashMap<i64, TerrainTile>) -> Vec<(Position, TerrainTile)> {
+    let mut unmapped = HashSet::new();
+    grid.iter()
+        .map(|(pos, value)| {
+            let tile = match mapping.get(value) {
+                Some(tile) => tile.clone(),
+                None => {
+                    if unmapped.insert(*value) {
+                        warn!(
+                            "ldtk int grid value {} has no tile in the opening, it is treated as walkable floor",
+                            value
+                        );
+                    }
+                    TerrainTile::floor()
+                }
+            };
+            (*pos, tile)
+        })
+        .collect()
+}
+
+/// Reads a trigger from its ldtk fields. `action` is one of message, ambush, open or teleport, an ambush names the
+/// `being` and `count` to spawn and open or teleport target the map tile at `x` and `y`. Triggers fire once unless
+/// `once` is unchecked
+fn ldtk_trigger(entity: &LdtkEntity, game_db: &GameData) -> Result<Trigger, String> {
+    let target = || match (entity.int_field("x"), entity.int_field("y")) {
+        (Some(x), Some(y)) if x >= 0 && y >= 0 => Ok(Position::new(x as usize, y as usize)),
+        _ => Err("it needs an x and y to target".to_string()),
+    };
+    let action = match entity.str_field("action").unwrap_or("message") {
+        "message" => TriggerAction::Message,
+        "ambush" => {
+            let being = entity.str_field("being").unwrap_or_default().to_string();
+            if game_db.beings.get_by_name(&being).is_none() {
+                return Err(format!("no being is named {}", being));
+            }
+            let count = entity.int_field("count").map_or(1, |count| count.max(1) as usize);
+            TriggerAction::Ambush { being, count }
+        }
+        "open" => TriggerAction::Open(target()?),
+        "teleport" => TriggerAction::Teleport(target()?),
+        other => return Err(format!("{} is not a trigger action", other)),
+    };
+    let message = entity.str_field("message").map(str::to_string);
+    Ok(Trigger { action, message, once: entity.bool_field("once").unwrap_or(true) })
+}
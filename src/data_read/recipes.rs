@@ -1,13 +1,17 @@
 use crate::{
-    crafting::{Ingredient, UseWithRecipe},
-    items::ItemQty,
+    crafting::{CookingRecipe, Ingredient, RepairRecipe, UseWithRecipe},
+    items::{ItemID, ItemQty},
 };
 use lazy_static::lazy_static;
+use log::error;
 use serde::{Deserialize, Serialize};
 use serde_json::from_str;
-use std::{fs, sync::Mutex};
+use std::{fs, sync::Mutex, time::Duration};
 
-use super::ENTITY_DB;
+use super::{read_json, LookupError, ENTITY_DB};
+
+const COOKING_PATH: &str = "raws/cooking.json";
+const REPAIRS_PATH: &str = "raws/repairs.json";
 
 lazy_static! {
     pub static ref RECIPE_DB: Mutex<RecipeDatabase> = Mutex::new(RecipeDatabase::new());
@@ -15,11 +19,24 @@ lazy_static! {
 
 pub struct RecipeDatabase {
     pub use_with_recipes: Vec<UseWithRecipe>,
+    pub cooking_recipes: Vec<CookingRecipe>,
+    pub repair_recipes: Vec<RepairRecipe>,
 }
 
 impl RecipeDatabase {
     pub fn new() -> Self {
-        Self { use_with_recipes: Vec::new() }
+        Self { use_with_recipes: Vec::new(), cooking_recipes: Vec::new(), repair_recipes: Vec::new() }
+    }
+
+    pub fn cooking_recipe_for(&self, input: ItemID) -> Option<&CookingRecipe> {
+        self.cooking_recipes.iter().find(|recipe| recipe.input == input)
+    }
+
+    /// The repair for `tool` that uses `material`, the order the two were picked in doesn't matter
+    pub fn repair_recipe_for(&self, first: ItemID, second: ItemID) -> Option<&RepairRecipe> {
+        self.repair_recipes.iter().find(|recipe| {
+            (recipe.tool == first && recipe.material == second) || (recipe.tool == second && recipe.material == first)
+        })
     }
 
     pub fn load(&mut self) {
@@ -29,23 +46,85 @@ impl RecipeDatabase {
         let edb = &ENTITY_DB.lock().unwrap();
         self.use_with_recipes = recipes
             .iter()
-            .map(|r| UseWithRecipe {
-                ingredients: vec![
-                    Ingredient {
-                        id: edb.items.get_by_name_unchecked(&r.first.name).identifier,
-                        consume: r.first.consume.map(ItemQty),
-                    },
-                    Ingredient {
-                        id: edb.items.get_by_name_unchecked(&r.second.name).identifier,
-                        consume: r.second.consume.map(ItemQty),
-                    },
-                ],
-                output: edb.items.get_by_name_unchecked(&r.output).identifier,
+            .filter_map(|r| {
+                let lookup = || -> Result<UseWithRecipe, LookupError> {
+                    Ok(UseWithRecipe {
+                        ingredients: vec![
+                            Ingredient {
+                                id: edb.items.try_get_by_name(&r.first.name)?.identifier,
+                                consume: r.first.consume.map(ItemQty),
+                            },
+                            Ingredient {
+                                id: edb.items.try_get_by_name(&r.second.name)?.identifier,
+                                consume: r.second.consume.map(ItemQty),
+                            },
+                        ],
+                        output: edb.items.try_get_by_name(&r.output)?.identifier,
+                    })
+                };
+                lookup().map_err(|e| error!("Recipe for {} was skipped, {}", r.output, e)).ok()
+            })
+            .collect();
+
+        let cooking: Vec<RawCookingRecipe> = read_json(COOKING_PATH).unwrap_or_else(|e| {
+            error!("{}", e);
+            Vec::new()
+        });
+        self.cooking_recipes = cooking
+            .iter()
+            .filter_map(|r| {
+                let lookup = || -> Result<CookingRecipe, LookupError> {
+                    Ok(CookingRecipe {
+                        input: edb.items.try_get_by_name(&r.input)?.identifier,
+                        output: edb.items.try_get_by_name(&r.output)?.identifier,
+                        burnt: edb.items.try_get_by_name(&r.burnt)?.identifier,
+                        burn_chance: r.burn_chance,
+                        cook_time: Duration::from_millis(r.cook_ms),
+                    })
+                };
+                lookup().map_err(|e| error!("Cooking recipe for {} was skipped, {}", r.output, e)).ok()
+            })
+            .collect();
+
+        let repairs: Vec<RawRepairRecipe> = read_json(REPAIRS_PATH).unwrap_or_else(|e| {
+            error!("{}", e);
+            Vec::new()
+        });
+        self.repair_recipes = repairs
+            .iter()
+            .filter_map(|r| {
+                let lookup = || -> Result<RepairRecipe, LookupError> {
+                    Ok(RepairRecipe {
+                        tool: edb.items.try_get_by_name(&r.tool)?.identifier,
+                        material: edb.items.try_get_by_name(&r.material)?.identifier,
+                        consume: ItemQty(r.consume),
+                        restores: r.restores,
+                    })
+                };
+                lookup().map_err(|e| error!("Repair recipe for {} was skipped, {}", r.tool, e)).ok()
             })
             .collect();
     }
 }
 
+#[derive(Deserialize)]
+struct RawCookingRecipe {
+    input: String,
+    output: String,
+    burnt: String,
+    /// Out of 100
+    burn_chance: u32,
+    cook_ms: u64,
+}
+
+#[derive(Deserialize)]
+struct RawRepairRecipe {
+    tool: String,
+    material: String,
+    consume: usize,
+    restores: u32,
+}
+
 #[derive(Deserialize, Serialize)]
 struct RawRecipe {
     first: RawIngredient,
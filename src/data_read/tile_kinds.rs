@@ -0,0 +1,176 @@
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+use log::error;
+use serde::Deserialize;
+
+use crate::{
+    components::{Breakable, Hazard, ParticleEmitter},
+    speed::MOVE_COST,
+};
+
+use super::read_json5;
+
+lazy_static! {
+    pub static ref TILE_KIND_DB: Mutex<TileKindDatabase> = Mutex::new(TileKindDatabase::empty());
+}
+
+const TILE_KINDS_PATH: &str = "raws/tile_kinds.json5";
+
+/// The behavior every map tile with the same name starts with
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct TileKind {
+    pub name: String,
+    #[serde(default)]
+    pub blocking: bool,
+    /// Fishing bubbles can appear in the tile
+    #[serde(default)]
+    pub fishable: bool,
+    /// Seeds can be planted in the tile
+    #[serde(default)]
+    pub tillable: bool,
+    /// The tool type needed to break the tile, see `Breakable`
+    pub breakable: Option<String>,
+    pub health_stats: Option<TileHealth>,
+    #[serde(default = "default_move_cost")]
+    pub move_cost: u32,
+    /// Given off by every tile of the kind the player can see, ie. bubbles from water
+    pub particles: Option<ParticleEmitter>,
+    /// Ore veins that can be made in place of the plain breakable tile
+    pub veins: Option<VeinTable>,
+    /// Hurts whatever ends its turn in the tile, ie. lava
+    pub hazard: Option<Hazard>,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct TileHealth {
+    pub max_hp: usize,
+    pub defense: usize,
+}
+
+/// World objects tagged with this are ore veins, breaking one leaves floor behind like the tile it replaced
+pub const VEIN_TAG: &str = "vein";
+
+/// Weighted picks between a plain breakable tile and the veins that can replace it
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct VeinTable {
+    /// Weight of the tile staying plain
+    pub plain_weight: u32,
+    pub table: Vec<VeinWeight>,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct VeinWeight {
+    /// Name of the world object built as the vein
+    pub object: String,
+    pub weight: u32,
+}
+
+impl VeinTable {
+    pub fn total_weight(&self) -> u32 {
+        self.plain_weight + self.table.iter().map(|vein| vein.weight).sum::<u32>()
+    }
+
+    /// The vein landed on by `roll`, which is below `total_weight`. `None` when the tile stays plain
+    pub fn pick(&self, roll: u32) -> Option<&str> {
+        let mut past = self.plain_weight;
+        if roll < past {
+            return None;
+        }
+        for vein in self.table.iter() {
+            past += vein.weight;
+            if roll < past {
+                return Some(&vein.object);
+            }
+        }
+        None
+    }
+}
+
+fn default_move_cost() -> u32 {
+    MOVE_COST
+}
+
+impl TileKind {
+    /// A tile with nothing special about it that can be walked over
+    pub fn floor(name: impl ToString) -> Self {
+        Self {
+            name: name.to_string(),
+            blocking: false,
+            fishable: false,
+            tillable: false,
+            breakable: None,
+            health_stats: None,
+            move_cost: MOVE_COST,
+            particles: None,
+            veins: None,
+            hazard: None,
+        }
+    }
+
+    /// True when the tile needs an entity to carry its behavior
+    pub fn needs_entity(&self) -> bool {
+        self.blocking || self.fishable || self.breakable.is_some() || self.hazard.is_some()
+    }
+}
+
+pub struct TileKindDatabase {
+    kinds: Vec<TileKind>,
+}
+
+impl TileKindDatabase {
+    pub fn empty() -> Self {
+        Self { kinds: Vec::new() }
+    }
+
+    pub fn load(&mut self) {
+        let kinds: Vec<TileKind> = match read_json5(TILE_KINDS_PATH) {
+            Ok(kinds) => kinds,
+            Err(e) => {
+                error!("{}", e);
+                *self = Self::empty();
+                return;
+            }
+        };
+
+        self.kinds = kinds
+            .into_iter()
+            .filter(|kind| match &kind.breakable {
+                Some(by) if by.parse::<Breakable>().is_err() => {
+                    error!("{}: {} was skipped, {} is not a valid breakable type", TILE_KINDS_PATH, kind.name, by);
+                    false
+                }
+                Some(_) if kind.health_stats.is_none() => {
+                    error!("{}: {} was skipped, breakable tiles need health_stats", TILE_KINDS_PATH, kind.name);
+                    false
+                }
+                None if kind.veins.is_some() => {
+                    error!("{}: {} was skipped, only breakable tiles can have veins", TILE_KINDS_PATH, kind.name);
+                    false
+                }
+                _ => true,
+            })
+            .collect();
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<&TileKind> {
+        self.kinds.iter().find(|kind| kind.name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn veins_are_picked_by_weight() {
+        let vein = |object: &str, weight| VeinWeight { object: object.to_string(), weight };
+        let veins = VeinTable { plain_weight: 10, table: vec![vein("Iron Vein", 3), vein("Gold Vein", 1)] };
+        assert_eq!(veins.total_weight(), 14);
+        assert_eq!(veins.pick(0), None);
+        assert_eq!(veins.pick(9), None);
+        assert_eq!(veins.pick(10), Some("Iron Vein"));
+        assert_eq!(veins.pick(12), Some("Iron Vein"));
+        assert_eq!(veins.pick(13), Some("Gold Vein"));
+    }
+}
@@ -1,7 +1,10 @@
-use std::{fs, str::FromStr};
+use std::str::FromStr;
 
 use crate::{
-    components::{Blocking, Breakable, Grass, HealthStats as HealthStatsComponent, Name, Position, Renderable},
+    components::{
+        Blocking, Breakable, Container, CookingStation, Grass, HealthStats as HealthStatsComponent, LightSource, Name,
+        ParticleEmitter, Position, Renderable, Tags,
+    },
     droptables::Drops,
     map::{ObjectID, WorldObject},
     saveload::SerializeMe,
@@ -13,7 +16,9 @@ use specs::{
     Builder, Entity, World, WorldExt,
 };
 
-use super::{beings::RawDrops, EntityBuildError, GameData, ENTITY_DB};
+use super::{beings::RawDrops, find_collisions, read_json5, EntityBuildError, GameData, RawsError, ENTITY_DB};
+
+const WORLD_OBJS_PATH: &str = "raws/world_objs.json5";
 
 pub struct WorldObjectDatabase {
     data: Vec<WorldObject>,
@@ -24,14 +29,27 @@ pub struct RawWorldObject {
     /// Unique id to find the world object's static data
     identifier: usize,
     name: String,
-    atlas_index: u8,
+    atlas_index: usize,
     is_blocking: bool,
     breakable: Option<String>,
     health_stats: Option<HealthStats>,
     grass: Option<String>,
+    cooking_station: Option<String>,
     foreground: Option<(u8, u8, u8)>,
+    /// Tint blended over the map under the object, the last value is its alpha
+    background: Option<(u8, u8, u8, u8)>,
+    /// Atlas indexes shown as the object is damaged, from lightly to heavily damaged
+    damage_stages: Option<Vec<usize>>,
+    /// Labels other systems can find the object by, ie. "flammable"
+    tags: Option<Vec<String>>,
     loot: Option<RawDrops>,
     impact_sound: Option<String>,
+    respawn_turns: Option<usize>,
+    light: Option<LightSource>,
+    /// Embers, pollen or anything else the object gives off
+    particles: Option<ParticleEmitter>,
+    /// Item stacks that can be stored in the object, ie. a chest
+    container: Option<usize>,
 }
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -45,28 +63,37 @@ impl WorldObjectDatabase {
         Self { data: Vec::new() }
     }
 
-    pub(crate) fn load(game_data: &GameData) -> Self {
-        let contents: String = fs::read_to_string("raws/world_objs.json5")
-            .expect("Unable to find world_objs.json at `raws/world_objs.json5`");
-        let world_objs: Vec<RawWorldObject> = json5::from_str(&contents).expect("Bad JSON in world_objs.json5 fix it");
-        let data = world_objs
-            .iter()
-            .map(|raw| WorldObject {
-                id: ObjectID(raw.identifier),
-                name: raw.name.clone(),
-                atlas_index: raw.atlas_index,
-                is_blocking: raw.is_blocking,
-                breakable: raw.breakable.clone(),
-                health_stats: raw.health_stats.clone().map(|hs| HealthStatsComponent::new(hs.max_hp, hs.defense)),
-                grass: raw.grass.clone(),
-                foreground: raw.foreground,
-                loot: raw.loot.as_ref().map(|raw| Drops::from_raw(raw, game_data)),
-                impact_sound: raw.impact_sound.clone().unwrap_or("".to_string()),
-            })
-            .collect();
+    /// Bad entries are skipped and added to `errors`
+    pub(crate) fn load(game_data: &GameData, errors: &mut Vec<RawsError>) -> Self {
+        let world_objs: Vec<RawWorldObject> = match read_json5(WORLD_OBJS_PATH) {
+            Ok(raw) => raw,
+            Err(e) => {
+                errors.push(e);
+                return Self::empty();
+            }
+        };
+
+        let mut data: Vec<WorldObject> = Vec::new();
+        for raw in world_objs.iter() {
+            match WorldObject::from_raw(raw, game_data) {
+                Ok(obj) => data.push(obj),
+                Err(reason) => {
+                    errors.push(RawsError::InvalidEntry { path: WORLD_OBJS_PATH, entry: raw.name.clone(), reason })
+                }
+            }
+        }
         WorldObjectDatabase { data }
     }
 
+    /// Checks that no two world objects share an id or a name since lookups only ever find the first
+    pub(crate) fn validate(&self) -> Result<(), RawsError> {
+        let collisions = find_collisions(self.data.iter().map(|obj| (obj.id.0.to_string(), obj.name.as_str())));
+        if collisions.is_empty() {
+            return Ok(());
+        }
+        Err(RawsError::Duplicates { path: WORLD_OBJS_PATH, collisions })
+    }
+
     pub fn get_by_name(&self, name: &String) -> Option<&WorldObject> {
         self.data.iter().find(|i| i.name.eq(name))
     }
@@ -75,6 +102,48 @@ impl WorldObjectDatabase {
     pub fn get_by_id(&self, id: usize) -> Option<&WorldObject> {
         self.data.iter().find(|i| i.id.0 == id)
     }
+
+    pub fn all(&self) -> impl Iterator<Item = &WorldObject> {
+        self.data.iter()
+    }
+}
+
+impl WorldObject {
+    fn from_raw(raw: &RawWorldObject, game_data: &GameData) -> Result<Self, String> {
+        if let Some(breakable) = &raw.breakable {
+            if Breakable::from_str(breakable).is_err() {
+                return Err(format!("{} is not a valid breakable type", breakable));
+            }
+        }
+        if raw.container == Some(0) {
+            return Err("container must hold at least 1 stack".to_string());
+        }
+        let loot = match &raw.loot {
+            Some(loot) => Some(Drops::from_raw(loot, game_data)?),
+            None => None,
+        };
+
+        Ok(WorldObject {
+            id: ObjectID(raw.identifier),
+            name: raw.name.clone(),
+            atlas_index: raw.atlas_index,
+            is_blocking: raw.is_blocking,
+            breakable: raw.breakable.clone(),
+            health_stats: raw.health_stats.clone().map(|hs| HealthStatsComponent::new(hs.max_hp, hs.defense)),
+            grass: raw.grass.clone(),
+            cooking_station: raw.cooking_station.clone(),
+            foreground: raw.foreground,
+            background: raw.background,
+            damage_stages: raw.damage_stages.clone().unwrap_or_default(),
+            tags: Tags::from_raw(&raw.tags),
+            loot,
+            impact_sound: raw.impact_sound.clone().unwrap_or("".to_string()),
+            respawn_turns: raw.respawn_turns,
+            light: raw.light,
+            particles: raw.particles,
+            container: raw.container,
+        })
+    }
 }
 
 /// Attempts to create the specified entity directly into the world
@@ -82,29 +151,36 @@ pub fn build_world_obj(name: impl ToString, pos: Position, world: &mut World) ->
     let edb = &ENTITY_DB.lock().unwrap();
     let raw = match edb.world_objs.get_by_name(&name.to_string()) {
         Some(raw) => raw,
-        None => {
-            eprintln!("No world object found named: {}", name.to_string());
-            return Err(EntityBuildError);
-        }
+        None => return Err(EntityBuildError::NotFound(name.to_string())),
     };
     let mut builder = world.create_entity().with(Name::new(&raw.name)).with(pos).marked::<SimpleMarker<SerializeMe>>();
 
     if let Some(foreground) = &raw.foreground {
-        builder = builder.with(Renderable::clear_bg(raw.atlas_index, *foreground, WORLD_OBJECT_Z));
+        let mut renderable = Renderable::clear_bg(raw.atlas_index, *foreground, WORLD_OBJECT_Z);
+        if let Some(background) = raw.background {
+            renderable = renderable.with_background(background);
+        }
+        builder = builder.with(renderable);
     }
 
     if raw.is_blocking {
         builder = builder.with(Blocking {});
     }
 
+    if let Some(tags) = &raw.tags {
+        builder = builder.with(tags.clone());
+    }
+
     if let Some(breakable) = &raw.breakable {
         match Breakable::from_str(breakable) {
             Ok(breakable_type) => {
                 builder = builder.with(breakable_type);
             }
             Err(_) => {
-                eprintln!("Invalid breakable string {} on world object {}", breakable, &raw.name);
-                return Err(EntityBuildError);
+                return Err(EntityBuildError::InvalidField {
+                    name: raw.name.clone(),
+                    reason: format!("{} is not a valid breakable type", breakable),
+                });
             }
         }
     }
@@ -113,9 +189,25 @@ pub fn build_world_obj(name: impl ToString, pos: Position, world: &mut World) ->
         builder = builder.with(Grass {});
     }
 
+    if raw.cooking_station.is_some() {
+        builder = builder.with(CookingStation {});
+    }
+
     if let Some(health_stats) = &raw.health_stats {
         builder = builder.with(HealthStatsComponent::new(health_stats.max_hp, health_stats.defense));
     }
 
+    if let Some(light) = raw.light {
+        builder = builder.with(light);
+    }
+
+    if let Some(particles) = raw.particles {
+        builder = builder.with(particles);
+    }
+
+    if let Some(slots) = raw.container {
+        builder = builder.with(Container { slots });
+    }
+
     Ok(builder.build())
 }
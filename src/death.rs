@@ -0,0 +1,183 @@
+use bracket_lib::terminal::{BTerm, VirtualKeyCode as VKC};
+use specs::{
+    shrev::{EventChannel, ReaderId},
+    Entity, Join, Read, ReadStorage, RunNow, System, World, WorldExt, Write, WriteStorage,
+};
+
+use crate::{
+    components::{Breakable, Equipped, HealthStats, InBag, Item, KillCount, Name, Position, SufferDamage, Viewshed},
+    data_read::prelude::{NewGameConfig, ENTITY_DB},
+    game_init::{player_start_position, PlayerEntity},
+    hunger::{Hunger, Metabolism},
+    items::{ItemQty, ItemSpawner, ItemSpawnerSystem, SpawnType},
+    layers::return_to_surface,
+    status::StatusEffects,
+    ui::message_log::MessageLog,
+};
+
+/// Named as the killer when the finishing blow had no source, like poison
+pub const ENVIRONMENT_CAUSE: &str = "the elements";
+
+/// What the player was last killed by, shown on the death screen
+#[derive(Default)]
+pub struct DeathCause(pub Option<String>);
+
+/// Published by `DamageSystem` when something's hp hits zero, each death is only published once
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DeathEvent {
+    pub victim: Entity,
+    /// Who dealt the finishing blow, `None` when it came from the environment
+    pub killer: Option<Entity>,
+}
+
+/// Every death not yet read, systems reacting to deaths each read it through their own reader instead of looking
+/// for zero hp. The dead are removed at the end of the frame so readers can still look them up
+pub type DeathEvents = EventChannel<DeathEvent>;
+
+/// Registers a reader for a system that reacts to deaths, the system has to run every frame so the channel is drained
+pub fn death_reader(ecs: &mut World) -> ReaderId<DeathEvent> {
+    ecs.write_resource::<DeathEvents>().register_reader()
+}
+
+/// Credits the kill to whoever dealt the finishing blow, breaking tiles and world objects does not count
+pub struct KillCounter {
+    reader: ReaderId<DeathEvent>,
+}
+
+impl KillCounter {
+    pub fn new(ecs: &mut World) -> Self {
+        Self { reader: death_reader(ecs) }
+    }
+}
+
+impl<'a> System<'a> for KillCounter {
+    type SystemData = (Read<'a, DeathEvents>, WriteStorage<'a, KillCount>, ReadStorage<'a, Breakable>);
+
+    fn run(&mut self, (death_events, mut kill_counts, breakables): Self::SystemData) {
+        for death in death_events.read(&mut self.reader) {
+            if breakables.contains(death.victim) {
+                continue;
+            }
+            if let Some(kills) = death.killer.and_then(|killer| kill_counts.get_mut(killer)) {
+                kills.0 += 1;
+            }
+        }
+    }
+}
+
+/// Tells the player when a being dies, their own death is told by `DamageSystem` along with what killed them
+pub struct DeathMessages {
+    reader: ReaderId<DeathEvent>,
+}
+
+impl DeathMessages {
+    pub fn new(ecs: &mut World) -> Self {
+        Self { reader: death_reader(ecs) }
+    }
+}
+
+impl<'a> System<'a> for DeathMessages {
+    type SystemData = (
+        Read<'a, DeathEvents>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Breakable>,
+        Read<'a, PlayerEntity>,
+        Write<'a, MessageLog>,
+    );
+
+    fn run(&mut self, (death_events, names, breakables, player_entity, mut log): Self::SystemData) {
+        for death in death_events.read(&mut self.reader) {
+            if death.victim == player_entity.0 || breakables.contains(death.victim) {
+                continue;
+            }
+            if let Some(name) = names.get(death.victim) {
+                log.log(format!("{} dies.", name));
+            }
+        }
+    }
+}
+
+pub enum DeathAction {
+    Respawn,
+    ReturnToMenu,
+    Waiting,
+}
+
+pub fn p_input_death(ctx: &mut BTerm) -> DeathAction {
+    match ctx.key {
+        Some(VKC::R) => DeathAction::Respawn,
+        Some(VKC::Q) => DeathAction::ReturnToMenu,
+        _ => DeathAction::Waiting,
+    }
+}
+
+/// Checked after damage is dealt, a dead player is kept around for the death screen instead of being deleted
+pub fn player_is_dead(ecs: &World) -> bool {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    ecs.read_storage::<HealthStats>().get(player_entity).is_some_and(|health| health.hp == 0)
+}
+
+/// Wakes the player back up at the start of the world with full health. Half of every unequipped stack they
+/// carried is left where they fell so there is a reason to go back
+pub fn respawn_player(ecs: &mut World) {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let death_pos = match ecs.read_storage::<Position>().get(player_entity) {
+        Some(pos) => *pos,
+        None => return,
+    };
+
+    let dropped = drop_half_of_bag(ecs, player_entity, death_pos);
+    // spawned now so the drops land on the layer the player died on
+    ItemSpawnerSystem.run_now(ecs);
+    ecs.maintain();
+
+    let start = {
+        let new_game = NewGameConfig::load(&ENTITY_DB.lock().unwrap(), &mut Vec::new());
+        player_start_position(ecs, &new_game)
+    };
+    return_to_surface(ecs, start);
+    if let Some(pos) = ecs.write_storage::<Position>().get_mut(player_entity) {
+        *pos = start;
+    }
+    if let Some(view) = ecs.write_storage::<Viewshed>().get_mut(player_entity) {
+        view.dirty = true;
+    }
+    if let Some(health) = ecs.write_storage::<HealthStats>().get_mut(player_entity) {
+        health.hp = health.max_hp;
+    }
+    ecs.write_storage::<SufferDamage>().remove(player_entity);
+    ecs.write_resource::<DeathCause>().0 = None;
+    if let Some(statuses) = ecs.write_storage::<StatusEffects>().get_mut(player_entity) {
+        statuses.effects.clear();
+    }
+    if let Some(hunger) = ecs.write_storage::<Hunger>().get_mut(player_entity) {
+        *hunger = ecs.read_resource::<Metabolism>().full();
+    }
+
+    let mut log = ecs.fetch_mut::<MessageLog>();
+    if dropped {
+        log.log("You wake up where your journey began, some of your things were left where you fell.");
+    } else {
+        log.log("You wake up where your journey began.");
+    }
+}
+
+/// True when anything was dropped
+fn drop_half_of_bag(ecs: &mut World, owner: Entity, pos: Position) -> bool {
+    let mut items = ecs.write_storage::<Item>();
+    let in_bags = ecs.read_storage::<InBag>();
+    let equipped = ecs.read_storage::<Equipped>();
+    let mut item_spawner = ecs.write_resource::<ItemSpawner>();
+
+    let mut dropped = false;
+    for (item, bag, _) in (&mut items, &in_bags, !&equipped).join() {
+        let lost = item.qty.0 / 2;
+        if bag.owner != owner || lost == 0 {
+            continue;
+        }
+        item.qty = item.qty - ItemQty(lost);
+        item_spawner.request_amt(item.id, SpawnType::OnGround(pos), ItemQty(lost));
+        dropped = true;
+    }
+    dropped
+}
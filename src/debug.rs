@@ -1,54 +1,364 @@
-use bracket_lib::terminal::{to_char, BTerm, TextAlign, VirtualKeyCode, RGB, RGBA, WHITESMOKE};
-use itertools::Itertools;
-use specs::{Join, ReadStorage, World, WorldExt};
+use bracket_lib::terminal::{to_char, to_cp437, BTerm, Point, TextAlign, VirtualKeyCode, RGB, RGBA, WHITESMOKE};
+use specs::{Entity, Join, World, WorldExt};
 
 use crate::{
-    camera::mouse_to_map_pos,
-    colors::{PL_CRITICAL_HP, PL_LOW_HP, PL_MAX_HP, PL_MED_HP, PL_MENU_TEXT, TEXASROSE},
-    components::{HealthStats, InBag, Interactor, Item, Name, Position, SelectedInventoryItem, Transform},
-    config::{InventoryConfig, SortMode},
+    activity::{Activity, ActivityKind},
+    camera::{get_camera_bounds, HoveredTile},
+    colors::{PL_MENU_TEXT, TEXASROSE},
+    components::{
+        ActionPoints, AttackCooldown, Blocking, Breakable, Companion, EffectiveStats, GoalMoverAI, HealthStats, InBag,
+        Interactor, Item, Monster, Name, Position, RandomWalkerAI, Renderable, SelectedInventoryItem, Transform,
+        Viewshed,
+    },
+    config::{ConfigMaster, InventoryConfig},
+    cooldowns::{turns_left, CooldownKind, Cooldowns},
+    data_read::{prelude::build_being, ENTITY_DB},
+    draw_sprites::SpriteDrawTime,
+    factions::Faction,
     game_init::PlayerEntity,
-    inventory::UseMenuResult,
+    hunger::Hunger,
+    indexing::TileIndexCheck,
+    inventory::{listed_items, UseMenuResult},
+    items::{ItemID, ItemSpawner, SpawnType},
     map::MapRes,
-    CL_INTERACTABLES, CL_TEXT, CL_WORLD,
+    profiling::SystemTimings,
+    theme::Theme,
+    time::TimeScale,
+    ui::message_log::MessageLog,
+    CL_INTERACTABLES, CL_TEXT, CL_WORLD, DISPLAY_WIDTH,
 };
 
 pub const CLEAR: RGBA = RGBA { r: 0.0, g: 0.0, b: 0.0, a: 0.0 };
 
-pub fn debug_info(ctx: &mut BTerm, ecs: &World, cfg: &InventoryConfig) {
+/// How many moves creative mode remembers for undoing
+const MAX_UNDO_HISTORY: usize = 32;
+
+/// A debug mode for testing maps where the player's moves can be undone
+#[derive(Default)]
+pub struct CreativeMode {
+    pub enabled: bool,
+    /// Where the player was before each move, the most recent is last
+    history: Vec<Position>,
+}
+
+impl CreativeMode {
+    /// Remembers the player's move, a turn spent without moving forgets everything since it can't be undone
+    pub fn record(&mut self, before: Position, after: Position) {
+        if !self.enabled {
+            return;
+        }
+        if before == after {
+            self.history.clear();
+            return;
+        }
+        if self.history.len() == MAX_UNDO_HISTORY {
+            self.history.remove(0);
+        }
+        self.history.push(before);
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+}
+
+/// Tile corners and coordinate labels drawn over the world for reading off positions, toggled with Ctrl+G
+#[derive(Default)]
+pub struct GridOverlay {
+    pub enabled: bool,
+}
+
+/// Faint enough that the world can still be played under the grid
+const GRID_COLOR: RGBA = RGBA { r: 1.0, g: 1.0, b: 1.0, a: 0.3 };
+const GRID_LABEL_COLOR: RGBA = RGBA { r: 1.0, g: 0.9, b: 0.4, a: 0.8 };
+
+/// The entity picked with Ctrl+click whose components are listed in a panel, the panel closes once it is deleted
+#[derive(Default)]
+pub struct Inspector {
+    selected: Option<Entity>,
+}
+
+/// Width in text cells of the inspector panel
+const INSPECTOR_WIDTH: usize = 30;
+
+/// What Ctrl+Shift+click spawns in dev builds, Ctrl+N and Ctrl+B cycle through every being and then every item
+#[derive(Default)]
+pub struct DebugSpawner {
+    selected: usize,
+}
+
+enum Spawnable {
+    Being(String),
+    Item(ItemID, String),
+}
+
+impl Spawnable {
+    fn label(&self) -> String {
+        match self {
+            Spawnable::Being(name) => format!("{} (being)", name),
+            Spawnable::Item(_, name) => format!("{} (item)", name),
+        }
+    }
+}
+
+fn spawnables() -> Vec<Spawnable> {
+    let edb = ENTITY_DB.lock().unwrap();
+    edb.beings
+        .iter()
+        .map(|being| Spawnable::Being(being.name.clone()))
+        .chain(edb.items.iter().map(|item| Spawnable::Item(item.identifier, item.name.clone())))
+        .collect()
+}
+
+pub fn debug_info(ctx: &mut BTerm, ecs: &World, cfg: &ConfigMaster) {
+    draw_grid_overlay(ctx, ecs, cfg.general.grid_label_spacing);
+    if cfg!(feature = "dev") {
+        draw_spawn_selection(ctx, ecs);
+    }
     draw_interaction_mode(ctx, ecs);
-    draw_inventory_state(ctx, ecs, cfg);
+    draw_inventory_state(ctx, ecs, &cfg.inventory);
     draw_health(ctx, ecs);
     draw_position(ctx, ecs);
+    draw_frame_time(ctx, ecs);
+    draw_system_timings(ctx, ecs);
+    draw_inspector(ctx, ecs);
+}
+
+/// Picks the entity drawn on top of the hovered tile, clicking a tile without anything on it closes the panel
+fn select_inspected(ecs: &World) {
+    let hovered = ecs.read_resource::<HoveredTile>().0;
+    let entities = ecs.entities();
+    let positions = ecs.read_storage::<Position>();
+    let renderables = ecs.read_storage::<Renderable>();
+    let selected = (&entities, &positions, (&renderables).maybe())
+        .join()
+        .filter(|(_, pos, _)| Some(**pos) == hovered)
+        .max_by_key(|(e, _, render)| (render.map_or(0, |render| render.z_priority), e.id()))
+        .map(|(e, ..)| e);
+    ecs.write_resource::<Inspector>().selected = selected;
+}
+
+/// Lists every component of `entity` the inspector knows about along with their key fields. There is no reflection
+/// in specs so each component has to be added here by hand
+fn inspect(ecs: &World, entity: Entity) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(name) = ecs.read_storage::<Name>().get(entity) {
+        lines.push(format!("Name: {}", name.0));
+    }
+    if let Some(pos) = ecs.read_storage::<Position>().get(entity) {
+        lines.push(format!("Position: {}", pos));
+    }
+    if let Some(stats) = ecs.read_storage::<HealthStats>().get(entity) {
+        lines.push(format!("HealthStats: {}/{} def {}", stats.hp, stats.max_hp, stats.defense));
+    }
+    if let Some(render) = ecs.read_storage::<Renderable>().get(entity) {
+        lines.push(format!("Renderable: atlas {} z {}", render.atlas_index, render.z_priority));
+    }
+    if ecs.read_storage::<Blocking>().contains(entity) {
+        lines.push("Blocking".to_string());
+    }
+    if let Some(breakable) = ecs.read_storage::<Breakable>().get(entity) {
+        lines.push(format!("Breakable: by {:?}", breakable.by));
+    }
+    if let Some(interactor) = ecs.read_storage::<Interactor>().get(entity) {
+        lines.push(format!("Interactor: {}", interactor.mode));
+    }
+    if let Some(faction) = ecs.read_storage::<Faction>().get(entity) {
+        lines.push(format!("Faction: {:?}", faction));
+    }
+    if let Some(monster) = ecs.read_storage::<Monster>().get(entity) {
+        let target = monster.target.map_or("none".to_string(), |target| target.id().to_string());
+        lines.push(format!("Monster: aggro {} target {}", monster.aggro_range, target));
+    }
+    if ecs.read_storage::<RandomWalkerAI>().contains(entity) {
+        lines.push("RandomWalkerAI".to_string());
+    }
+    if let Some(goals) = ecs.read_storage::<GoalMoverAI>().get(entity) {
+        lines.push(format!("GoalMoverAI: {} desires, range {}", goals.desires.len(), goals.goal_range));
+    }
+    if let Some(companion) = ecs.read_storage::<Companion>().get(entity) {
+        lines.push(format!("Companion: follows at {}", companion.follow_distance));
+    }
+    if let Some(points) = ecs.read_storage::<ActionPoints>().get(entity) {
+        lines.push(format!("ActionPoints: {} speed {}", points.points, points.speed));
+    }
+    if let Some(cooldown) = ecs.read_storage::<AttackCooldown>().get(entity) {
+        let remaining = turns_left(ecs.read_storage::<Cooldowns>().get(entity), CooldownKind::Attack);
+        lines.push(format!("AttackCooldown: {}/{}", remaining, cooldown.turns));
+    }
+    if let Some(view) = ecs.read_storage::<Viewshed>().get(entity) {
+        lines.push(format!("Viewshed: range {} sees {}", view.range, view.tiles.len()));
+    }
+    if let Some(hunger) = ecs.read_storage::<Hunger>().get(entity) {
+        lines.push(format!("Hunger: {}/{}", hunger.current, hunger.max));
+    }
+    if let Some(item) = ecs.read_storage::<Item>().get(entity) {
+        lines.push(format!("Item: id {} qty {}", item.id.value(), item.qty));
+    }
+    if let Some(bag) = ecs.read_storage::<InBag>().get(entity) {
+        lines.push(format!("InBag: owner {}", bag.owner.id()));
+    }
+    if let Some(activity) = ecs.read_storage::<Activity>().get(entity) {
+        let kind = match activity.kind {
+            ActivityKind::Fishing { attempts_left, hooked } => {
+                format!("fishing, {} left hooked {}", attempts_left, hooked)
+            }
+            ActivityKind::Cooking { .. } => "cooking".to_string(),
+        };
+        lines.push(format!("Activity: {}", kind));
+    }
+    lines
+}
+
+/// Draws the inspected entity's components in a panel on the right, read fresh every frame
+fn draw_inspector(ctx: &mut BTerm, ecs: &World) {
+    let selected = match ecs.read_resource::<Inspector>().selected {
+        Some(selected) => selected,
+        None => return,
+    };
+    if !ecs.is_alive(selected) {
+        ecs.write_resource::<Inspector>().selected = None;
+        ecs.fetch_mut::<MessageLog>().debug("The inspected entity was deleted");
+        return;
+    }
+
+    let lines = inspect(ecs, selected);
+    let x = DISPLAY_WIDTH * 2 - INSPECTOR_WIDTH - 1;
+    ctx.set_active_console(CL_TEXT);
+    ctx.draw_box(x, 10, INSPECTOR_WIDTH, lines.len() + 2, WHITESMOKE, RGB::from_u8(61, 84, 107));
+    ctx.print_color(x + 1, 11, WHITESMOKE, RGB::from_u8(61, 84, 107), format!("Entity {}", selected.id()));
+    for (idx, line) in lines.iter().enumerate() {
+        let line: String = line.chars().take(INSPECTOR_WIDTH - 1).collect();
+        ctx.print_color(x + 1, 12 + idx, WHITESMOKE, RGB::from_u8(61, 84, 107), line);
+    }
+}
+
+/// Marks the top left corner of every tile in view and labels the coordinates of every `label_spacing`th tile.
+/// Text cells are half a tile so each tile covers 2x2 of them
+fn draw_grid_overlay(ctx: &mut BTerm, ecs: &World, label_spacing: usize) {
+    if !ecs.read_resource::<GridOverlay>().enabled {
+        return;
+    }
+    let bounds = get_camera_bounds(ecs);
+    let map = ecs.read_resource::<MapRes>();
+    ctx.set_active_console(CL_TEXT);
+    for y in bounds.y1..bounds.y2 {
+        for x in bounds.x1..bounds.x2 {
+            if !map.0.in_bounds(Point::new(x, y)) {
+                continue;
+            }
+            let (cell_x, cell_y) = ((x - bounds.x1) * 2, (y - bounds.y1) * 2);
+            let labeled = label_spacing > 0 && x as usize % label_spacing == 0 && y as usize % label_spacing == 0;
+            if labeled {
+                ctx.print_color(cell_x, cell_y, GRID_LABEL_COLOR, CLEAR, format!("{},{}", x, y));
+            } else {
+                ctx.set(cell_x, cell_y, GRID_COLOR, CLEAR, to_cp437('+'));
+            }
+        }
+    }
+}
+
+fn toggle_grid_overlay(ecs: &World) {
+    let mut grid = ecs.write_resource::<GridOverlay>();
+    grid.enabled = !grid.enabled;
+    let state = if grid.enabled { "on" } else { "off" };
+    ecs.fetch_mut::<MessageLog>().debug(format!("Grid overlay is {}", state));
+}
+
+fn toggle_system_timings(ecs: &World) {
+    let mut timings = ecs.write_resource::<SystemTimings>();
+    timings.toggle();
+    let state = if timings.enabled() { "on" } else { "off" };
+    ecs.fetch_mut::<MessageLog>().debug(format!("System timings are {}", state));
+}
+
+fn toggle_tile_index_check(ecs: &World) {
+    let mut check = ecs.write_resource::<TileIndexCheck>();
+    check.enabled = !check.enabled;
+    check.mismatches.clear();
+    let state = if check.enabled { "on, mismatches are logged as warnings" } else { "off" };
+    ecs.fetch_mut::<MessageLog>().debug(format!("Tile index check is {}", state));
+}
+
+/// Systems listed by the system timing overlay
+const TIMED_SYSTEMS_SHOWN: usize = 8;
+
+/// The systems taking the longest on average under the frame time, only while timing is toggled on with Ctrl+T
+fn draw_system_timings(ctx: &mut BTerm, ecs: &World) {
+    let timings = ecs.read_resource::<SystemTimings>();
+    if !timings.enabled() {
+        return;
+    }
+    ctx.set_active_console(CL_TEXT);
+    ctx.printer(2, 11, "#[white]slowest systems, avg per frame:#[]", TextAlign::Left, None);
+    for (row, (system, time)) in timings.slowest(TIMED_SYSTEMS_SHOWN).iter().enumerate() {
+        ctx.printer(
+            2,
+            12 + row as i32,
+            format!("#[white]{:.3}ms {}#[]", time.as_secs_f32() * 1000.0, system),
+            TextAlign::Left,
+            None,
+        );
+    }
 }
 
 fn draw_health(ctx: &mut BTerm, ecs: &World) {
     let player_entity = ecs.read_resource::<PlayerEntity>();
     let health_stats = ecs.read_storage::<HealthStats>();
     if let Some(stats) = health_stats.get(player_entity.0) {
-        let percent = stats.hp as f32 / stats.max_hp as f32;
-        let color = if stats.hp == stats.max_hp {
-            PL_MAX_HP
-        } else if percent > 0.5 {
-            PL_MED_HP
-        } else if percent > 0.25 {
-            PL_LOW_HP
-        } else {
-            PL_CRITICAL_HP
-        };
+        let color = ecs.fetch::<Theme>().hp_palette(stats.hp, stats.max_hp);
 
-        ctx.printer(2, 7, format!("#[{}]hp: {}/{}#[]", color, stats.hp, stats.max_hp), TextAlign::Left, None);
+        // equipment changes the rest of the stats so they are shown alongside the health
+        let effective = match ecs.read_storage::<EffectiveStats>().get(player_entity.0) {
+            Some(effective) => {
+                format!(" atk: +{} def: {} spd: {}", effective.attack_bonus, effective.defense, effective.speed)
+            }
+            None => String::new(),
+        };
+        ctx.printer(
+            2,
+            7,
+            format!("#[{}]hp: {}/{}#[white]{}#[]", color, stats.hp, stats.max_hp, effective),
+            TextAlign::Left,
+            None,
+        );
     }
 }
 
 fn draw_position(ctx: &mut BTerm, ecs: &World) {
     let player_entity = ecs.read_resource::<PlayerEntity>();
     let positions = ecs.read_storage::<Position>();
+    let creative = ecs.read_resource::<CreativeMode>();
     if let Some(pos) = positions.get(player_entity.0) {
-        ctx.printer(2, 6, format!("#[white]pos: {}, {}#[]", pos.x, pos.y), TextAlign::Left, None);
+        let mode =
+            if creative.enabled { format!(" | creative, {} undos", creative.history.len()) } else { String::new() };
+        ctx.printer(2, 6, format!("#[white]pos: {}, {}{}#[]", pos.x, pos.y, mode), TextAlign::Left, None);
     }
 }
 
+fn draw_spawn_selection(ctx: &mut BTerm, ecs: &World) {
+    let selected = ecs.read_resource::<DebugSpawner>().selected;
+    let label = spawnables().get(selected).map_or("nothing".to_string(), Spawnable::label);
+    ctx.set_active_console(CL_TEXT);
+    ctx.printer(2, 9, format!("#[white]spawn: {}#[]", label), TextAlign::Left, None);
+}
+
+fn draw_frame_time(ctx: &mut BTerm, ecs: &World) {
+    let sprite_time = ecs.read_resource::<SpriteDrawTime>();
+    ctx.printer(
+        2,
+        8,
+        format!(
+            "#[white]frame: {:.1}ms ({:.0} fps) | sprites: {:.2}ms#[]",
+            ctx.frame_time_ms,
+            ctx.fps,
+            sprite_time.0.as_secs_f32() * 1000.0
+        ),
+        TextAlign::Left,
+        None,
+    );
+}
+
 // NOTE: This may be better in user interface once we figure out a cool way to display it, maybe an
 // icon?
 fn draw_interaction_mode(ctx: &mut BTerm, ecs: &World) {
@@ -86,20 +396,7 @@ fn draw_inventory_state(ctx: &mut BTerm, ecs: &World, cfg: &InventoryConfig) {
                 None => "none".to_string(),
             };
 
-            let items: ReadStorage<Item> = ecs.read_storage();
-            let inbags: ReadStorage<InBag> = ecs.read_storage();
-            let names: ReadStorage<Name> = ecs.read_storage();
-            let entities = ecs.entities();
-            match (&entities, &items, &inbags, &names)
-                .join()
-                .filter(|inv_item| inv_item.2.owner == player_entity.0)
-                .sorted_by(|a, b| match cfg.sort_mode {
-                    SortMode::NameABC => a.3.cmp(b.3),
-                    SortMode::IDAsc => a.1.id.cmp(&b.1.id),
-                    _ => a.1.id.cmp(&b.1.id),
-                })
-                .position(|inv_item| inv_item.0 == selection.first_item)
-            {
+            match listed_items(ecs, cfg).iter().position(|item| *item == selection.first_item) {
                 Some(idx_selected) => format!("Selected: {} | Action: {}", idx_selected, message),
                 None => "Inventory selection index mismatch".to_string(),
             }
@@ -113,7 +410,7 @@ fn draw_inventory_state(ctx: &mut BTerm, ecs: &World, cfg: &InventoryConfig) {
     ctx.print_color(41, 49, WHITESMOKE, RGB::from_u8(61, 84, 107), sort_mode);
 }
 
-pub fn debug_input(ctx: &mut BTerm, ecs: &World) {
+pub fn debug_input(ctx: &mut BTerm, ecs: &mut World) {
     if !ctx.control {
         return;
     }
@@ -121,11 +418,119 @@ pub fn debug_input(ctx: &mut BTerm, ecs: &World) {
     draw_cursor(ctx);
 
     if ctx.left_click {
-        print_tile_contents(ctx, ecs);
+        if ctx.shift && cfg!(feature = "dev") {
+            spawn_selected(ecs);
+        } else {
+            print_tile_contents(ctx, ecs);
+            select_inspected(ecs);
+        }
+    }
+
+    match ctx.key {
+        Some(VirtualKeyCode::V) => print_position(ecs),
+        Some(VirtualKeyCode::C) => toggle_creative_mode(ecs),
+        Some(VirtualKeyCode::G) => toggle_grid_overlay(ecs),
+        Some(VirtualKeyCode::T) => toggle_system_timings(ecs),
+        Some(VirtualKeyCode::I) if cfg!(feature = "dev") => toggle_tile_index_check(ecs),
+        Some(VirtualKeyCode::N) if cfg!(feature = "dev") => cycle_spawn_selection(ecs, true),
+        Some(VirtualKeyCode::B) if cfg!(feature = "dev") => cycle_spawn_selection(ecs, false),
+        Some(VirtualKeyCode::Z) => undo_player_move(ecs),
+        Some(VirtualKeyCode::Minus) => change_time_scale(ecs, TimeScale::slower),
+        Some(VirtualKeyCode::Equals) => change_time_scale(ecs, TimeScale::faster),
+        Some(VirtualKeyCode::Key0) => change_time_scale(ecs, TimeScale::reset),
+        #[cfg(feature = "dev")]
+        Some(VirtualKeyCode::R) => reload_raws(ecs),
+        _ => {}
     }
+}
+
+/// See `reload_entity_databases` for which changes show up without respawning anything
+#[cfg(feature = "dev")]
+fn reload_raws(ecs: &World) {
+    let mut log = ecs.fetch_mut::<MessageLog>();
+    match crate::data_read::reload_entity_databases() {
+        Ok(()) => log.debug("Raws were reloaded"),
+        Err(errors) => {
+            for raws_error in errors.iter() {
+                log::error!("{}", raws_error);
+            }
+            log.debug(format!("Raws were not reloaded, {} problems found", errors.len()));
+        }
+    }
+}
+
+/// Slows down or speeds up animations and waits, ie. to watch a tween closely or skip through fishing
+fn change_time_scale(ecs: &World, change: fn(&mut TimeScale)) {
+    let mut scale = ecs.write_resource::<TimeScale>();
+    change(&mut scale);
+    ecs.fetch_mut::<MessageLog>().debug(format!("Time scale is {}x", scale.get()));
+}
+
+fn cycle_spawn_selection(ecs: &World, forward: bool) {
+    let count = spawnables().len();
+    if count == 0 {
+        return;
+    }
+    let mut spawner = ecs.write_resource::<DebugSpawner>();
+    spawner.selected = if forward { (spawner.selected + 1) % count } else { (spawner.selected + count - 1) % count };
+}
+
+/// Spawns the selected being or item on the hovered tile as long as nothing is standing there
+fn spawn_selected(ecs: &mut World) {
+    let pos = match ecs.read_resource::<HoveredTile>().0 {
+        Some(pos) => pos,
+        None => return,
+    };
+    if ecs.read_resource::<MapRes>().0.is_blocked(&pos) {
+        ecs.fetch_mut::<MessageLog>().debug(format!("Can't spawn at {}, it is blocked", pos));
+        return;
+    }
+
+    let selected = ecs.read_resource::<DebugSpawner>().selected;
+    let message = match spawnables().into_iter().nth(selected) {
+        Some(Spawnable::Being(name)) => match build_being(&name, pos, ecs) {
+            Ok(_) => format!("Spawned {} at {}", name, pos),
+            Err(e) => format!("Could not spawn {}, {}", name, e),
+        },
+        Some(Spawnable::Item(id, name)) => {
+            ecs.write_resource::<ItemSpawner>().request(id, SpawnType::OnGround(pos));
+            format!("Spawned {} at {}", name, pos)
+        }
+        None => return,
+    };
+    ecs.fetch_mut::<MessageLog>().debug(message);
+}
+
+fn toggle_creative_mode(ecs: &World) {
+    let mut creative = ecs.write_resource::<CreativeMode>();
+    creative.enabled = !creative.enabled;
+    creative.clear();
+    let state = if creative.enabled { "on" } else { "off" };
+    ecs.fetch_mut::<MessageLog>().debug(format!("Creative mode is {}", state));
+}
 
-    if ctx.key.is_some() && ctx.key == Some(VirtualKeyCode::V) {
-        print_position(ecs);
+/// Puts the player back where they were before their last move, as long as nothing has moved in there since
+fn undo_player_move(ecs: &World) {
+    let mut creative = ecs.write_resource::<CreativeMode>();
+    if !creative.enabled {
+        return;
+    }
+    let previous = match creative.history.last() {
+        Some(previous) => *previous,
+        None => return,
+    };
+    if ecs.read_resource::<MapRes>().0.is_blocked(&previous) {
+        ecs.fetch_mut::<MessageLog>().debug(format!("Can't undo, {} is blocked", previous));
+        return;
+    }
+    creative.history.pop();
+
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    if let Some(pos) = ecs.write_storage::<Position>().get_mut(player_entity) {
+        *pos = previous;
+    }
+    if let Some(view) = ecs.write_storage::<Viewshed>().get_mut(player_entity) {
+        view.dirty = true;
     }
 }
 
@@ -154,10 +559,10 @@ fn print_tile_contents(ctx: &mut BTerm, ecs: &World) {
     ctx.set_active_console(CL_WORLD);
     print!("MousePos on CL_WORLD: {:?} | ", &ctx.mouse_pos());
 
-    let cursor_map_pos = mouse_to_map_pos(&ctx.mouse_pos(), ecs);
+    let cursor_map_pos = ecs.read_resource::<HoveredTile>().0;
 
-    let tile_idx = match cursor_map_pos {
-        Some(pos) => pos.to_idx(map.0.width),
+    let tile_idx = match cursor_map_pos.and_then(|pos| map.0.checked_xy_to_idx(pos.x, pos.y)) {
+        Some(idx) => idx,
         None => {
             println!("Cannot print tile entities at {:?}", &cursor_map_pos);
             return;
@@ -172,3 +577,22 @@ fn print_tile_contents(ctx: &mut BTerm, ecs: &World) {
         println!("There are no entities at {:?}", cursor_map_pos);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use specs::Builder;
+
+    use super::*;
+    use crate::test_harness::TestWorld;
+
+    #[test]
+    fn inspecting_lists_known_components() {
+        let mut world = TestWorld::new();
+        let rock = world.spawn("Rock", Position::new(3, 4)).with(Blocking {}).with(HealthStats::new(5, 0)).build();
+        let lines = inspect(&world.ecs, rock);
+        assert_eq!(lines[0], "Name: Rock");
+        assert!(lines.contains(&"Blocking".to_string()));
+        assert!(lines.iter().any(|line| line.starts_with("HealthStats: 5/5")));
+        assert!(!lines.iter().any(|line| line.starts_with("Item")));
+    }
+}
@@ -0,0 +1,143 @@
+/* difficulty.rs
+ *   The difficulty is picked when a world is made and stays with it in the save. Each level scales how hard
+ *   monsters hit the player, how many of them are around at the start and how quickly harvested resources grow
+ *   back, the multipliers for every level are read from the settings so they can be tuned.
+ * */
+
+use bracket_lib::random::RandomNumberGenerator;
+use serde::{Deserialize, Serialize};
+use specs::{Component, VecStorage};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum DifficultyLevel {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl DifficultyLevel {
+    pub fn next(&self) -> Self {
+        match self {
+            DifficultyLevel::Easy => DifficultyLevel::Normal,
+            DifficultyLevel::Normal => DifficultyLevel::Hard,
+            DifficultyLevel::Hard => DifficultyLevel::Easy,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            DifficultyLevel::Easy => "Easy",
+            DifficultyLevel::Normal => "Normal",
+            DifficultyLevel::Hard => "Hard",
+        }
+    }
+}
+
+/// Multipliers for one difficulty level, 1.0 leaves things as the raws have them
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct DifficultyScale {
+    /// Damage monsters deal to the player
+    pub monster_damage: f32,
+    /// How many of each monster a new world starts with, a fraction is the chance of one more
+    pub monster_spawns: f32,
+    /// How quickly harvested resources grow back, 2.0 takes half the turns
+    pub resource_regen: f32,
+}
+
+impl Default for DifficultyScale {
+    fn default() -> Self {
+        Self { monster_damage: 1.0, monster_spawns: 1.0, resource_regen: 1.0 }
+    }
+}
+
+/// The multipliers of each difficulty level, read from the settings when the game starts
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct DifficultyConfig {
+    pub easy: DifficultyScale,
+    pub normal: DifficultyScale,
+    pub hard: DifficultyScale,
+}
+
+impl Default for DifficultyConfig {
+    fn default() -> Self {
+        Self {
+            easy: DifficultyScale { monster_damage: 0.5, monster_spawns: 0.75, resource_regen: 1.5 },
+            normal: DifficultyScale::default(),
+            hard: DifficultyScale { monster_damage: 1.5, monster_spawns: 1.5, resource_regen: 0.75 },
+        }
+    }
+}
+
+/// The difficulty of the world being played
+#[derive(Default)]
+pub struct Difficulty {
+    cfg: DifficultyConfig,
+    pub level: DifficultyLevel,
+}
+
+impl Difficulty {
+    pub fn new(cfg: DifficultyConfig) -> Self {
+        Self { cfg, level: DifficultyLevel::default() }
+    }
+
+    pub fn scale(&self) -> &DifficultyScale {
+        match self.level {
+            DifficultyLevel::Easy => &self.cfg.easy,
+            DifficultyLevel::Normal => &self.cfg.normal,
+            DifficultyLevel::Hard => &self.cfg.hard,
+        }
+    }
+
+    /// A hit of `amount` from a monster on the player, negative amounts are damage. A hit never scales down to nothing
+    pub fn monster_hit(&self, amount: i32) -> i32 {
+        if amount >= 0 {
+            return amount;
+        }
+        (amount as f32 * self.scale().monster_damage).round().min(-1.0) as i32
+    }
+
+    /// Turns a harvested resource takes to grow back that would take `turns` on normal
+    pub fn regrow_turns(&self, turns: usize) -> usize {
+        (turns as f32 / self.scale().resource_regen.max(0.01)).round() as usize
+    }
+
+    /// How many of a starting monster to spawn in place of one
+    pub fn spawn_count(&self, rng: &mut RandomNumberGenerator) -> usize {
+        let spawns = self.scale().monster_spawns.max(0.0);
+        let extra_chance = spawns.fract();
+        spawns.trunc() as usize + usize::from(rng.rand::<f32>() < extra_chance)
+    }
+}
+
+/// Carries the difficulty into the save on the save helper, read back into `Difficulty` when loading
+#[derive(Component, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct SavedDifficulty(pub DifficultyLevel);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_scale_by_their_multipliers() {
+        let mut difficulty = Difficulty::new(DifficultyConfig::default());
+        assert_eq!(difficulty.monster_hit(-4), -4);
+        assert_eq!(difficulty.regrow_turns(100), 100);
+
+        difficulty.level = DifficultyLevel::Easy;
+        assert_eq!(difficulty.monster_hit(-4), -2);
+        assert_eq!(difficulty.monster_hit(-1), -1);
+        assert_eq!(difficulty.monster_hit(3), 3);
+        assert_eq!(difficulty.regrow_turns(150), 100);
+
+        difficulty.level = DifficultyLevel::Hard;
+        assert_eq!(difficulty.monster_hit(-4), -6);
+        let mut rng = RandomNumberGenerator::seeded(7);
+        let counts: Vec<usize> = (0..20).map(|_| difficulty.spawn_count(&mut rng)).collect();
+        assert!(counts.iter().all(|count| (1..=2).contains(count)));
+        assert!(counts.contains(&1) && counts.contains(&2));
+    }
+}
@@ -0,0 +1,258 @@
+//! The systems that run every frame or turn, grouped into specs `Dispatcher`s.
+//! Each system names the systems it must run after, systems that don't depend on each other are free to run in
+//! parallel. Systems that touch the same storages or resources never run at the same time and keep the order they
+//! are added in, so the dependencies are for ordering that matters to the game, ie. indexing before the systems
+//! reading the index. Barriers end a stage, nothing after one starts until everything before it is done.
+//! Systems are added with `with_timed` so their run time can be shown in the debug overlay, see `profiling.rs`.
+
+use specs::{Component, Dispatcher, DispatcherBuilder, Join, World, WorldExt};
+
+use crate::{
+    activity::CancelActivities,
+    being::{
+        CompanionAI, FleeAI, GoalFindEntities, GoalMoveToEntities, HandleMoveActions, MonsterChaseAI, QuipSystem,
+        RandomMonsterMovementSystem,
+    },
+    boating::StrandedCheck,
+    buoyancy::SinkingItems,
+    catch_release::CatchChoiceSystem,
+    combat::{AttackActionHandler, HealActionHandler, RangedAttackHandler},
+    components::{
+        AttackAction, BreakAction, ConsumeAction, CookAction, CraftAction, EquipAction, FishAction, GameAction,
+        HealAction, MoveAction, PickupAction, RangedAttackAction,
+    },
+    cooking::{CookingSystem, SetupCookingActions},
+    cooldowns::TickCooldowns,
+    currents::CurrentDrift,
+    death::{DeathMessages, KillCounter},
+    droptables::DeathLootDrop,
+    equipment::{EffectiveStatsSystem, EquipActionHandler},
+    explosives::DetonationSystem,
+    farming::CropGrowthSystem,
+    fish_schools::FishSchoolSystem,
+    fishing::{
+        AbandonedFishingCleanup, CatchFishSystem, CreateFishingBubbles, FishStruggleSystem, FishingMinigameCheck,
+        FishingMinigameUpdate, PollFishingTiles, SetupFishingActions, WaitingForFishSystem,
+    },
+    fov::UpdateViewsheds,
+    hazards::HazardSystem,
+    hunger::HungerSystem,
+    indexing::{
+        IndexBlockedTiles, IndexBreakableTiles, IndexFishableTiles, IndexItemTiles, IndexNamedEntities, IndexReset,
+        IndexStaticTiles, ValidateTileIndex,
+    },
+    item_despawn::ItemDespawnSystem,
+    items::{ConsumeHandler, ItemPickupHandler, ItemSpawnerSystem, ZeroQtyItemCleanup},
+    lighting::LightingSystem,
+    mining::{DamageSystem, RemoveDeadTiles, ShowDamageStages, TileDestructionSystem},
+    particles::ParticleSystem,
+    profiling::WithTimed,
+    special_attacks::{MonsterSpecialAttacks, TickSpecialAttacks},
+    speed::{GrantActionPoints, SpendActionPoints},
+    status::StatusEffectSystem,
+    tile_animation::{
+        AggroIndicatorFade, FadeSystem, FloatingTextUpdater, SlideUpdater, SpawnPopUpdater, TileAnimationCleanUpSystem,
+        TileAnimationSpawner, TileAnimationUpdater,
+    },
+    toasts::ToastFade,
+    weather::WeatherSystem,
+};
+
+/// Built once when the game starts and kept on `State`
+pub struct GameDispatchers {
+    ingame: Dispatcher<'static, 'static>,
+    activity_bound: Dispatcher<'static, 'static>,
+    turn_start: Dispatcher<'static, 'static>,
+    turn_round: Dispatcher<'static, 'static>,
+    turn_end: Dispatcher<'static, 'static>,
+}
+
+impl GameDispatchers {
+    /// Systems reacting to events register their readers with `ecs`
+    pub fn new(ecs: &mut World) -> Self {
+        Self {
+            ingame: ingame_dispatcher(ecs),
+            activity_bound: activity_bound_dispatcher(),
+            turn_start: turn_start_dispatcher(),
+            turn_round: turn_round_dispatcher(),
+            turn_end: turn_end_dispatcher(),
+        }
+    }
+
+    /// Ran every frame while in game
+    pub fn run_ingame(&mut self, ecs: &World) {
+        self.ingame.dispatch(ecs);
+    }
+
+    /// Ran every frame while the player is busy with an activity
+    pub fn run_activity_bound(&mut self, ecs: &World) {
+        self.activity_bound.dispatch(ecs);
+    }
+
+    /// Ran once when the player's action has taken time
+    pub fn run_turn_start(&mut self, ecs: &World) {
+        self.turn_start.dispatch(ecs);
+    }
+
+    /// Ran for each round beings get to act in during a turn
+    pub fn run_turn_round(&mut self, ecs: &World) {
+        self.turn_round.dispatch(ecs);
+    }
+
+    /// Ran once every being is done acting for the turn
+    pub fn run_turn_end(&mut self, ecs: &World) {
+        self.turn_end.dispatch(ecs);
+    }
+}
+
+/// The action components still attached to something. Every action is cleared by the system that handles it in the
+/// same frame it was queued, anything left over at the end of a frame would be performed a second time
+pub fn lingering_actions(ecs: &World) -> Vec<&'static str> {
+    fn any<T: Component>(ecs: &World) -> bool {
+        ecs.read_storage::<T>().join().next().is_some()
+    }
+
+    let actions = [
+        ("MoveAction", any::<MoveAction>(ecs)),
+        ("BreakAction", any::<BreakAction>(ecs)),
+        ("AttackAction", any::<AttackAction>(ecs)),
+        ("RangedAttackAction", any::<RangedAttackAction>(ecs)),
+        ("PickupAction", any::<PickupAction>(ecs)),
+        ("FishAction", any::<FishAction>(ecs)),
+        ("CookAction", any::<CookAction>(ecs)),
+        ("CraftAction", any::<CraftAction>(ecs)),
+        ("EquipAction", any::<EquipAction>(ecs)),
+        ("ConsumeAction", any::<ConsumeAction>(ecs)),
+        ("HealAction", any::<HealAction>(ecs)),
+        ("GameAction", any::<GameAction>(ecs)),
+    ];
+    actions.into_iter().filter(|(_, lingering)| *lingering).map(|(name, _)| name).collect()
+}
+
+fn ingame_dispatcher(ecs: &mut World) -> Dispatcher<'static, 'static> {
+    DispatcherBuilder::new()
+        // Indexing Systems ===============================>
+        .with_timed(IndexReset, "index_reset", &[])
+        .with_timed(IndexStaticTiles, "index_static", &["index_reset"])
+        .with_timed(IndexBlockedTiles, "index_blocking", &["index_static"])
+        .with_timed(IndexBreakableTiles, "index_breaking", &["index_static"])
+        .with_timed(IndexFishableTiles, "index_fishing", &["index_reset"])
+        .with_timed(IndexItemTiles, "index_items", &["index_reset"])
+        .with_timed(IndexNamedEntities::new(ecs), "index_names", &[])
+        .with_timed(
+            ValidateTileIndex,
+            "validate_tile_index",
+            &["index_blocking", "index_breaking", "index_fishing", "index_items"],
+        )
+        .with_barrier()
+        // Fishing Systems ==================================>
+        .with_timed(SetupFishingActions, "setup_fishing_actions", &[])
+        .with_timed(WaitingForFishSystem, "waiting_for_fish", &["setup_fishing_actions"])
+        .with_timed(CatchFishSystem, "catch_fish", &["waiting_for_fish"])
+        .with_timed(PollFishingTiles, "poll_fishing_tiles", &["catch_fish"])
+        .with_timed(AbandonedFishingCleanup, "abandoned_fishing", &["catch_fish"])
+        // Cooking Systems ==================================>
+        .with_timed(SetupCookingActions, "setup_cooking_actions", &[])
+        .with_timed(CookingSystem, "cooking", &["setup_cooking_actions"])
+        .with_barrier()
+        // Action Systems =================================>
+        .with_timed(StatusEffectSystem, "status_effects", &[])
+        .with_timed(EquipActionHandler, "equip_system", &[])
+        .with_timed(EffectiveStatsSystem, "effective_stats", &["equip_system"])
+        .with_timed(ConsumeHandler, "consume_handler", &["equip_system"])
+        .with_timed(HealActionHandler, "heal_handler", &["consume_handler"])
+        .with_timed(TileDestructionSystem, "destruction_sys", &[])
+        .with_timed(DamageSystem, "damage_sys", &["status_effects", "heal_handler", "destruction_sys"])
+        .with_timed(ShowDamageStages, "damage_stages", &["damage_sys"])
+        .with_timed(ItemPickupHandler, "item_pickup_handler", &[])
+        .with_barrier()
+        // Misc Systems ==================================>
+        .with_timed(DeathLootDrop::new(ecs), "death_loot_spawn", &[])
+        .with_timed(KillCounter::new(ecs), "kill_counter", &[])
+        .with_timed(DeathMessages::new(ecs), "death_messages", &[])
+        .with_timed(StrandedCheck, "stranded_check", &[])
+        .with_timed(UpdateViewsheds, "viewshed_update", &["stranded_check"])
+        .with_timed(LightingSystem, "lighting", &[])
+        .with_timed(DetonationSystem, "detonation", &[])
+        // Request Based Systems ================================>
+        .with_timed(ItemSpawnerSystem, "item_spawner", &["death_loot_spawn"])
+        // Animation Systems =========================================>
+        .with_timed(TileAnimationSpawner, "tile_anim_spawner", &["detonation"])
+        .with_timed(TileAnimationUpdater, "tile_anim_updater", &["tile_anim_spawner"])
+        .with_timed(FloatingTextUpdater, "floating_text_updater", &[])
+        .with_timed(AggroIndicatorFade, "aggro_indicator_fade", &[])
+        .with_timed(SpawnPopUpdater, "spawn_pop_updater", &[])
+        .with_timed(SlideUpdater, "slide_updater", &[])
+        .with_timed(ParticleSystem, "particles", &[])
+        .with_timed(ToastFade, "toast_fade", &[])
+        .with_timed(FadeSystem, "fade", &[])
+        .with_timed(
+            TileAnimationCleanUpSystem,
+            "tile_anim_cleanup_system",
+            &["tile_anim_updater", "floating_text_updater", "spawn_pop_updater", "detonation"],
+        )
+        // Cleanup Systems =======================================>
+        .with_barrier()
+        .with_timed(ZeroQtyItemCleanup, "zero_qty_item_cleanup", &[])
+        .with_timed(SinkingItems, "sinking_items", &[])
+        .with_timed(RemoveDeadTiles, "remove_dead_tiles", &[])
+        .build()
+}
+
+fn activity_bound_dispatcher() -> Dispatcher<'static, 'static> {
+    DispatcherBuilder::new()
+        .with_timed(CancelActivities, "cancel_activities", &[])
+        // Fishing Minigame Systems ====================>
+        .with_timed(WaitingForFishSystem, "waiting_for_fish", &["cancel_activities"])
+        .with_timed(FishingMinigameUpdate, "fish_mini_update", &["waiting_for_fish"])
+        .with_timed(FishStruggleSystem, "fish_struggle", &["fish_mini_update"])
+        .with_timed(FishingMinigameCheck, "fish_mini_check", &["fish_struggle"])
+        .with_timed(CatchChoiceSystem, "catch_choice", &["fish_struggle"])
+        .with_timed(CatchFishSystem, "catch_fish", &["fish_mini_check", "catch_choice"])
+        // Cooking Systems ==================================>
+        .with_timed(CookingSystem, "cooking", &["cancel_activities"])
+        .build()
+}
+
+fn turn_start_dispatcher() -> Dispatcher<'static, 'static> {
+    DispatcherBuilder::new()
+        .with_timed(GrantActionPoints, "grant_points", &[])
+        .with_timed(TickCooldowns, "tick_cooldowns", &[])
+        .with_timed(TickSpecialAttacks, "tick_special_attacks", &[])
+        .with_timed(CropGrowthSystem, "crop_growth", &[])
+        .build()
+}
+
+/// Every ai picks what it wants to do before any of it is carried out
+fn turn_round_dispatcher() -> Dispatcher<'static, 'static> {
+    DispatcherBuilder::new()
+        .with_timed(MonsterSpecialAttacks, "monster_specials", &[])
+        .with_timed(MonsterChaseAI, "monster_chase", &["monster_specials"])
+        .with_timed(FleeAI, "flee", &[])
+        .with_timed(RandomMonsterMovementSystem, "randomwalker", &["flee"])
+        .with_timed(GoalFindEntities, "find_goals", &[])
+        .with_timed(GoalMoveToEntities, "goalmover", &["find_goals"])
+        .with_timed(CompanionAI, "companions", &[])
+        .with_timed(
+            SpendActionPoints,
+            "spend_points",
+            &["monster_chase", "flee", "randomwalker", "goalmover", "companions"],
+        )
+        .with_timed(HandleMoveActions, "handle_moves", &["spend_points"])
+        .with_timed(RangedAttackHandler, "handle_ranged_attacks", &["spend_points"])
+        .with_timed(AttackActionHandler, "handle_attack_actions", &["handle_moves", "handle_ranged_attacks"])
+        .build()
+}
+
+fn turn_end_dispatcher() -> Dispatcher<'static, 'static> {
+    DispatcherBuilder::new()
+        .with_timed(QuipSystem, "quips", &[])
+        .with_timed(CreateFishingBubbles, "update_fishing_tiles", &[])
+        .with_timed(FishSchoolSystem, "fish_schools", &["update_fishing_tiles"])
+        .with_timed(WeatherSystem, "weather", &[])
+        .with_timed(CurrentDrift, "current_drift", &[])
+        .with_timed(HungerSystem, "hunger", &[])
+        .with_timed(HazardSystem, "hazards", &["current_drift"])
+        .with_timed(ItemDespawnSystem, "item_despawn", &["current_drift"])
+        .build()
+}
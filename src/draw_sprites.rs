@@ -1,14 +1,30 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
 use bracket_lib::terminal::*;
-use specs::{Join, World, WorldExt};
+use specs::{Entity, Join, LendJoin, World, WorldExt};
 
 use crate::{
+    being::BeingID,
     camera::get_camera_bounds,
-    components::{GlyphFlash, Renderable, SizeFlexor, Transform},
+    char_c::{CH_ALERT, CH_LOST},
+    colors::{blend_over, MIDDLERED, TEXASROSE},
+    components::{
+        AggroIndicator, Alert, DeleteCondition, FloatingText, GlyphFlash, IdleAnimation, Renderable, SizeFlexor,
+        Transform, Viewshed,
+    },
     debug::CLEAR,
-    map::render_map,
-    time::DeltaTime,
-    z_order::PLAYER_Z,
-    Position, CL_EFFECTS, CL_INTERACTABLES, CL_WORLD,
+    game_init::PlayerEntity,
+    idle_bob::IdleBob,
+    item_despawn::{DespawnTimer, ItemDespawn},
+    lighting::LightMap,
+    map::{map_background, render_map, Map, MapRes},
+    time::{DeltaTime, IdleClock},
+    weather::draw_rain,
+    z_order::{INDICATOR_Z, PLAYER_Z},
+    Position, CL_EFFECTS, CL_INTERACTABLES, CL_TEXT, CL_WORLD,
 };
 
 pub const SPRITE_SPEED: f32 = 8.0;
@@ -25,42 +41,143 @@ pub fn update_fancy_positions(ecs: &World) {
     }
 }
 
+/// How long the last call to `draw_sprite_layers` took, shown in the debug info
+#[derive(Default)]
+pub struct SpriteDrawTime(pub Duration);
+
 /// Draws the CL_INTERACTABLES and CL_WORLD sprites to the screen
-pub fn draw_sprite_layers(ecs: &World) {
+pub fn draw_sprite_layers(ecs: &World, flat: bool) {
+    let start = Instant::now();
     let mut draw_batch = DrawBatch::new();
 
     draw_batch.target(CL_INTERACTABLES);
     draw_batch.cls();
 
-    draw_sprites(ecs, &mut draw_batch);
-    draw_fancy_sprites(ecs, &mut draw_batch);
+    let tints = draw_sprites(ecs, &mut draw_batch, flat);
+    match flat {
+        true => draw_flat_sprites(ecs, &mut draw_batch),
+        false => draw_fancy_sprites(ecs, &mut draw_batch),
+    }
+    draw_aggro_indicators(ecs, &mut draw_batch);
+    draw_rain(ecs, &mut draw_batch);
     draw_batch.submit(CL_INTERACTABLES).expect("Batch error??");
 
     draw_batch.target(CL_WORLD);
     draw_batch.cls();
-    render_map(ecs, &mut draw_batch);
+    render_map(ecs, &mut draw_batch, &tints);
     draw_batch.submit(CL_WORLD).expect("Batch error??");
+
+    ecs.write_resource::<SpriteDrawTime>().0 = start.elapsed();
 }
 
-fn draw_sprites(ecs: &World, draw_batch: &mut DrawBatch) {
+/// Draws the sprites with a clear background, their background tints are returned blended together for each
+/// screen tile so the map can draw them under the sprites
+fn draw_sprites(ecs: &World, draw_batch: &mut DrawBatch, flat: bool) -> HashMap<Point, RGBA> {
+    let entities = ecs.entities();
     let positions = ecs.read_storage::<Position>();
     let renderables = ecs.read_storage::<Renderable>();
     let transforms = ecs.read_storage::<Transform>();
     let flashes = ecs.read_storage::<GlyphFlash>();
+    let beings = ecs.read_storage::<BeingID>();
+    let viewsheds = ecs.read_storage::<Viewshed>();
+    let idle_anims = ecs.read_storage::<IdleAnimation>();
+    let idle_clock = ecs.read_resource::<IdleClock>();
+    let despawn_timers = ecs.read_storage::<DespawnTimer>();
+    let item_despawn = ecs.read_resource::<ItemDespawn>();
+    let player_e = ecs.read_resource::<PlayerEntity>();
+    let player_view = viewsheds.get(player_e.0);
+    // The bob moves the player off the tile grid, which only the fancy console can draw
+    let idle_bob = if flat { 0.0 } else { ecs.read_resource::<IdleBob>().offset() };
+
+    let map = ecs.fetch::<MapRes>();
+    let light_map = ecs.fetch::<LightMap>();
 
     let bounding_box = get_camera_bounds(ecs);
 
-    let data = (&positions, &renderables, !&transforms, !&flashes)
+    // Cull everything off camera or on tiles never seen before anything else is done with them
+    let mut visible: Vec<(Entity, Point, &Renderable)> =
+        (&entities, &positions, &renderables, !&transforms, !&flashes, (&beings).maybe())
+            .join()
+            .filter(|(_, pos, .., being)| {
+                bounding_box.point_in_rect(pos.to_point())
+                    && can_see(&map.0, player_view, pos.to_point(), being.is_some())
+            })
+            // Items about to despawn blink as a warning
+            .filter(|(entity, ..)| !item_despawn.blinked_out(despawn_timers.get(*entity), idle_clock.0))
+            .map(|(entity, pos, render, ..)| {
+                (entity, Point::new(pos.x as i32 - bounding_box.x1, pos.y as i32 - bounding_box.y1), render)
+            })
+            .collect();
+    // Sorting once here means the batch is already in order when it is sorted on submit
+    sort_draw_order(&mut visible);
+    let tints = blend_tints(&visible);
+
+    for (entity, screen_pos, render) in visible {
+        let atlas_index = idle_frame(idle_anims.get(entity), &idle_clock).unwrap_or(render.atlas_index);
+        let map_pos = Point::new(screen_pos.x + bounding_box.x1, screen_pos.y + bounding_box.y1);
+        let color = ColorPair { fg: light_map.shade(render.color_pair.fg, map_pos), bg: CLEAR };
+        if entity == player_e.0 && idle_bob != 0.0 {
+            let rendered_pos = PointF::new(screen_pos.x as f32, screen_pos.y as f32 + 1.0 + idle_bob);
+            draw_batch.set_fancy(rendered_pos, render.z_priority, 0.0, PointF::new(1.0, 1.0), color, atlas_index);
+        } else {
+            draw_batch.set_with_z(screen_pos, color, atlas_index, render.z_priority);
+        }
+    }
+    tints
+}
+
+/// Whether a sprite on `map_pos` is drawn, the tile has to have been seen before and beings are only drawn while the
+/// player can currently see them
+fn can_see(map: &Map, player_view: Option<&Viewshed>, map_pos: Point, is_being: bool) -> bool {
+    map.is_revealed(map_pos) && (!is_being || player_view.map_or(true, |view| view.tiles.contains(&map_pos)))
+}
+
+/// Composites the background of every sprite onto the map's background in draw order, only tiles with a sprite
+/// that isn't fully transparent are in the result
+fn blend_tints(sprites: &[(Entity, Point, &Renderable)]) -> HashMap<Point, RGBA> {
+    let mut tints = HashMap::new();
+    for (_, screen_pos, render) in sprites.iter().filter(|(.., render)| render.color_pair.bg.a > 0.0) {
+        let tint = tints.entry(*screen_pos).or_insert_with(map_background);
+        *tint = blend_over(*tint, render.color_pair.bg);
+    }
+    tints
+}
+
+/// Orders sprites by z priority, using the entity id as a tiebreaker so sprites sharing a tile draw
+/// the same way every frame instead of depending on storage order
+fn sort_draw_order(sprites: &mut [(Entity, Point, &Renderable)]) {
+    sprites.sort_by_key(|(entity, _, render)| (render.z_priority, entity.id()));
+}
+
+/// The current frame of an entity's idle animation if it has one
+fn idle_frame(idle: Option<&IdleAnimation>, clock: &IdleClock) -> Option<usize> {
+    idle.and_then(|idle| idle.frame(clock.0))
+}
+
+/// Draws the "!" or "?" on the tile above monsters the player can see, fading with the time the indicator has left
+fn draw_aggro_indicators(ecs: &World, draw_batch: &mut DrawBatch) {
+    let positions = ecs.read_storage::<Position>();
+    let indicators = ecs.read_storage::<AggroIndicator>();
+    let viewsheds = ecs.read_storage::<Viewshed>();
+    let player_e = ecs.read_resource::<PlayerEntity>();
+    let player_view = viewsheds.get(player_e.0);
+
+    let bounding_box = get_camera_bounds(ecs);
+    for (pos, indicator) in (&positions, &indicators)
         .join()
-        .map(|(p, r, ..)| (p, r))
-        .filter(|(pos, _)| bounding_box.point_in_rect(pos.to_point()));
-    for (pos, render) in data {
-        draw_batch.set_with_z(
-            Point::new(pos.x as i32 - bounding_box.x1, pos.y as i32 - bounding_box.y1),
-            ColorPair { fg: render.color_pair.fg, bg: CLEAR },
-            render.atlas_index,
-            render.z_priority,
-        );
+        .filter(|(pos, _)| player_view.map_or(true, |view| view.tiles.contains(&pos.to_point())))
+    {
+        let above = Point::new(pos.x as i32, pos.y as i32 - 1);
+        if !bounding_box.point_in_rect(above) {
+            continue;
+        }
+        let (glyph, (r, g, b)) = match indicator.alert {
+            Alert::Noticed => (CH_ALERT, MIDDLERED),
+            Alert::Lost => (CH_LOST, TEXASROSE),
+        };
+        let fg = RGBA::from_u8(r, g, b, (indicator.alpha().clamp(0.0, 1.0) * 255.0) as u8);
+        let screen_pos = Point::new(above.x - bounding_box.x1, above.y - bounding_box.y1);
+        draw_batch.set_with_z(screen_pos, ColorPair { fg, bg: CLEAR }, glyph, INDICATOR_Z);
     }
 }
 
@@ -77,45 +194,160 @@ pub fn draw_flashes(ecs: &World, draw_batch: &mut DrawBatch) {
     }
 }
 
+/// Draws floating text above the world, fading it out as it reaches the end of its lifetime
+pub fn draw_floating_text(ecs: &World, draw_batch: &mut DrawBatch) {
+    draw_batch.target(CL_TEXT);
+    let transforms = ecs.read_storage::<Transform>();
+    let floating_texts = ecs.read_storage::<FloatingText>();
+    let delete_conditions = ecs.read_storage::<DeleteCondition>();
+
+    let bounding_box = get_camera_bounds(ecs);
+    for (ftrans, floating, condition) in (&transforms, &floating_texts, (&delete_conditions).maybe())
+        .join()
+        .filter(|(ftrans, ..)| bounding_box.point_in_rect(ftrans.sprite_pos.into()))
+    {
+        let alpha = match condition {
+            Some(DeleteCondition::Timed(time_left)) => time_left.as_secs_f32() / floating.lifetime.as_secs_f32(),
+            _ => 1.0,
+        };
+        // CL_TEXT is double the size of the world layers so the position needs to be scaled
+        let x = ((ftrans.sprite_pos.x - bounding_box.x1 as f32) * 2.0).round() as i32;
+        let y = ((ftrans.sprite_pos.y - bounding_box.y1 as f32) * 2.0).round() as i32 - 1;
+        let (r, g, b) = floating.color;
+        draw_batch.print_color(
+            Point::new(x, y),
+            &floating.text,
+            ColorPair { fg: RGBA::from_u8(r, g, b, (alpha.clamp(0.0, 1.0) * 255.0) as u8), bg: CLEAR },
+        );
+    }
+}
+
 fn draw_fancy_sprites(ecs: &World, draw_batch: &mut DrawBatch) {
+    let positions = ecs.read_storage::<Position>();
     let renderables = ecs.read_storage::<Renderable>();
     let transforms = ecs.read_storage::<Transform>();
+    let idle_anims = ecs.read_storage::<IdleAnimation>();
+    let beings = ecs.read_storage::<BeingID>();
+    let viewsheds = ecs.read_storage::<Viewshed>();
+    let idle_clock = ecs.read_resource::<IdleClock>();
+    let light_map = ecs.fetch::<LightMap>();
+    let map = ecs.fetch::<MapRes>();
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let player_view = viewsheds.get(player_entity);
+    let idle_bob = ecs.read_resource::<IdleBob>().offset();
 
     let bounding_box = get_camera_bounds(ecs);
-    for (ftrans, render) in
-        (&transforms, &renderables).join().filter(|(pos, _)| bounding_box.point_in_rect(pos.sprite_pos.into()))
+    for (entity, ftrans, render, idle) in (&ecs.entities(), &transforms, &renderables, (&idle_anims).maybe())
+        .join()
+        .filter(|(_, pos, ..)| bounding_box.point_in_rect(pos.sprite_pos.into()))
     {
+        let map_pos = transform_tile(ftrans, positions.get(entity));
+        if !can_see(&map.0, player_view, map_pos, beings.contains(entity)) {
+            continue;
+        }
         let fx = ftrans.sprite_pos.x - bounding_box.x1 as f32;
-        let fy = ftrans.sprite_pos.y - bounding_box.y1 as f32 + 1.0;
+        let mut fy = ftrans.sprite_pos.y - bounding_box.y1 as f32 + 1.0;
+        if entity == player_entity {
+            fy += idle_bob;
+        }
         let rendered_pos = PointF::new(fx, fy);
         draw_batch.set_fancy(
             rendered_pos,
             PLAYER_Z,
             ftrans.rotation,
             ftrans.scale,
-            render.color_pair,
-            render.atlas_index,
+            ColorPair::new(light_map.shade(render.color_pair.fg, ftrans.sprite_pos.into()), render.color_pair.bg),
+            idle_frame(idle, &idle_clock).unwrap_or(render.atlas_index),
+        );
+    }
+}
+
+/// Draws what would be drawn with a `Transform` at the whole tile it stands on, for when CL_INTERACTABLES is a plain
+/// console. Entities without a position, ie. particles, use the tile nearest to where their transform has them
+fn draw_flat_sprites(ecs: &World, draw_batch: &mut DrawBatch) {
+    let positions = ecs.read_storage::<Position>();
+    let renderables = ecs.read_storage::<Renderable>();
+    let transforms = ecs.read_storage::<Transform>();
+    let idle_anims = ecs.read_storage::<IdleAnimation>();
+    let beings = ecs.read_storage::<BeingID>();
+    let viewsheds = ecs.read_storage::<Viewshed>();
+    let idle_clock = ecs.read_resource::<IdleClock>();
+    let light_map = ecs.fetch::<LightMap>();
+    let map = ecs.fetch::<MapRes>();
+    let player_view = viewsheds.get(ecs.read_resource::<PlayerEntity>().0);
+
+    let bounding_box = get_camera_bounds(ecs);
+    for (entity, ftrans, render, idle) in (&ecs.entities(), &transforms, &renderables, (&idle_anims).maybe()).join() {
+        let map_pos = transform_tile(ftrans, positions.get(entity));
+        if !bounding_box.point_in_rect(map_pos) || !can_see(&map.0, player_view, map_pos, beings.contains(entity)) {
+            continue;
+        }
+        draw_batch.set_with_z(
+            Point::new(map_pos.x - bounding_box.x1, map_pos.y - bounding_box.y1),
+            ColorPair::new(light_map.shade(render.color_pair.fg, map_pos), render.color_pair.bg),
+            idle_frame(idle, &idle_clock).unwrap_or(render.atlas_index),
+            PLAYER_Z,
         );
     }
 }
 
+/// The tile a transform's entity is on, entities without a position use the tile nearest to their sprite
+fn transform_tile(ftrans: &Transform, pos: Option<&Position>) -> Point {
+    match pos {
+        Some(pos) => pos.to_point(),
+        None => Point::new(ftrans.sprite_pos.x.round() as i32, ftrans.sprite_pos.y.round() as i32),
+    }
+}
+
 pub fn lerp_point(curr: &PointF, x: f32, y: f32, scalar: f32) -> PointF {
     let fx = curr.x + (x - curr.x) * scalar;
     let fy = curr.y + (y - curr.y) * scalar;
     PointF { x: fx, y: fy }
 }
 
-#[allow(dead_code)]
-const COLORS: [&(u8, u8, u8); 11] = [
-    &ROSYBROWN,
-    &DARKSALMON,
-    &BURLYWOOD,
-    &CADETBLUE4,
-    &ANTIQUEWHITE,
-    &DARKGOLDENROD1,
-    &CORNFLOWER_BLUE,
-    &LIGHTPINK,
-    &LIGHTCYAN4,
-    &LIGHTGOLDENRODYELLOW,
-    &LIGHTSALMON,
-];
+#[cfg(test)]
+mod tests {
+    use specs::{Builder, World, WorldExt};
+
+    use super::*;
+    use crate::z_order::{BEING_Z, ITEM_Z};
+
+    #[test]
+    fn sprites_sort_by_z_then_entity_id() {
+        let mut world = World::new();
+        let entities: Vec<Entity> = (0..4).map(|_| world.create_entity().build()).collect();
+        let item = Renderable::clear_bg(1, (255, 255, 255), ITEM_Z);
+        let being = Renderable::clear_bg(2, (255, 255, 255), BEING_Z);
+        let tile = Point::new(3, 3);
+
+        let mut sprites = [
+            (entities[3], tile, &item),
+            (entities[1], tile, &being),
+            (entities[0], tile, &being),
+            (entities[2], tile, &item),
+        ];
+        sort_draw_order(&mut sprites);
+
+        let order: Vec<Entity> = sprites.iter().map(|(entity, ..)| *entity).collect();
+        // items are below beings, then each pair is in entity id order
+        let expected = [entities[2], entities[3], entities[0], entities[1]];
+        assert_eq!(order, expected);
+    }
+
+    #[test]
+    fn only_tinted_sprites_blend_over_the_map() {
+        let mut world = World::new();
+        let entities: Vec<Entity> = (0..2).map(|_| world.create_entity().build()).collect();
+        let clear = Renderable::clear_bg(1, (255, 255, 255), ITEM_Z);
+        let shade = Renderable::clear_bg(2, (255, 255, 255), BEING_Z).with_background((255, 255, 255, 128));
+        let (bare, shaded) = (Point::new(1, 1), Point::new(2, 2));
+
+        let tints = blend_tints(&[(entities[0], bare, &clear), (entities[1], shaded, &shade)]);
+
+        assert!(!tints.contains_key(&bare));
+        let tint = tints[&shaded];
+        let base = map_background();
+        assert!((tint.r - (base.r + (1.0 - base.r) * shade.color_pair.bg.a)).abs() < 0.001);
+        assert_eq!(tint.a, base.a);
+    }
+}
@@ -1,11 +1,12 @@
 use bracket_lib::random::RandomNumberGenerator;
 use log::{debug, error};
-use specs::{Join, ReadStorage, System, Write};
+use specs::{shrev::ReaderId, Read, ReadStorage, System, World, Write};
 use weighted_rand::builder::{NewBuilder, WalkerTableBuilder};
 
 use crate::{
-    components::{HealthStats, Name, Position},
+    components::{Name, Position},
     data_read::ENTITY_DB,
+    death::{death_reader, DeathEvent, DeathEvents},
     items::{ItemID, ItemQty, ItemSpawner, SpawnType},
 };
 
@@ -25,6 +26,16 @@ pub enum DropQty {
     Range { min: usize, max: usize },
 }
 
+impl DropQty {
+    /// Picks how many to drop, a range never reaches its max
+    pub fn roll(&self, rng: &mut RandomNumberGenerator) -> ItemQty {
+        ItemQty(match self {
+            DropQty::Single(n) => *n,
+            DropQty::Range { min, max } => rng.range(*min, *max),
+        })
+    }
+}
+
 const MAX_ITEM_DROPS: u32 = 10;
 
 fn generate_drops(drop_table: &Drops) -> Vec<(ItemID, ItemQty)> {
@@ -43,11 +54,7 @@ fn generate_drops(drop_table: &Drops) -> Vec<(ItemID, ItemQty)> {
                 unreachable!("idx out of bounds of drop table, mismatch between `weights` and `drop_table.loot_table`")
             }
         };
-        let qty = ItemQty(match drop.qty {
-            DropQty::Single(n) => n,
-            DropQty::Range { min, max } => rng.range(min, max),
-        });
-        drops.push((drop.id, qty));
+        drops.push((drop.id, drop.qty.roll(&mut rng)));
         total_drops += 1;
         roll = rng.range(0, 100);
     }
@@ -55,15 +62,27 @@ fn generate_drops(drop_table: &Drops) -> Vec<(ItemID, ItemQty)> {
     drops
 }
 
-pub struct DeathLootDrop;
+/// Rolls the loot table of everything that died and drops it where they fell
+pub struct DeathLootDrop {
+    reader: ReaderId<DeathEvent>,
+}
+
+impl DeathLootDrop {
+    pub fn new(ecs: &mut World) -> Self {
+        Self { reader: death_reader(ecs) }
+    }
+}
 
 impl<'a> System<'a> for DeathLootDrop {
-    type SystemData =
-        (ReadStorage<'a, HealthStats>, ReadStorage<'a, Position>, ReadStorage<'a, Name>, Write<'a, ItemSpawner>);
+    type SystemData = (Read<'a, DeathEvents>, ReadStorage<'a, Position>, ReadStorage<'a, Name>, Write<'a, ItemSpawner>);
 
-    fn run(&mut self, (healths, positions, names, mut item_spawner): Self::SystemData) {
+    fn run(&mut self, (death_events, positions, names, mut item_spawner): Self::SystemData) {
         let edb = &ENTITY_DB.lock().unwrap();
-        for (pos, _, name) in (&positions, &healths, &names).join().filter(|(_, health, _)| health.hp == 0) {
+        for death in death_events.read(&mut self.reader) {
+            let (pos, name) = match (positions.get(death.victim), names.get(death.victim)) {
+                (Some(pos), Some(name)) => (pos, name),
+                _ => continue,
+            };
             debug!("{} in deathloopdrop", name);
             let drop_table = match edb.beings.get_by_name(&name.0) {
                 Some(being) => match &being.loot {
@@ -0,0 +1,50 @@
+use specs::{Entities, Entity, Join, ReadStorage, WriteStorage};
+
+use crate::{
+    components::{Durability, Equipped, Item},
+    items::ItemQty,
+};
+
+/// Wears down every tool `owner` has equipped by one use. A tool that breaks loses one from its stack and the next
+/// one takes over, an emptied stack is removed by `ZeroQtyItemCleanup`. Returns the tools that broke
+pub fn wear_equipped_tools(
+    owner: Entity,
+    equipped: &ReadStorage<Equipped>,
+    durabilities: &mut WriteStorage<Durability>,
+    items: &mut WriteStorage<Item>,
+    entities: &Entities,
+) -> Vec<Entity> {
+    let mut broken = Vec::new();
+    for (tool, durability, equip) in (entities, durabilities, equipped).join() {
+        if equip.on != owner || !durability.wear() {
+            continue;
+        }
+        if let Some(item) = items.get_mut(tool) {
+            item.qty = item.qty - ItemQty(1);
+        }
+        broken.push(tool);
+    }
+    broken
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::components::Durability;
+
+    #[test]
+    fn tools_break_after_their_last_use() {
+        let mut durability = Durability::new(2);
+        assert!(!durability.wear());
+        assert_eq!(durability.current, 1);
+        assert!(durability.wear());
+        assert!(durability.is_full());
+    }
+
+    #[test]
+    fn repairs_stop_at_max() {
+        let mut durability = Durability { current: 3, max: 10 };
+        assert_eq!(durability.repair(5), 5);
+        assert_eq!(durability.repair(5), 2);
+        assert!(durability.is_full());
+    }
+}
@@ -7,9 +7,16 @@
 
 use std::mem::discriminant;
 
-use specs::{Entities, Join, ReadStorage, System, WriteStorage};
+use specs::{Entities, Entity, Join, ReadStorage, System, WriteStorage};
 
-use crate::components::{EquipAction, Equipable, EquipmentSlots, Equipped};
+use crate::{
+    components::{
+        ActionPoints, AttackBonus, EffectiveStats, EquipAction, Equipable, EquipmentSlots, Equipped, HealthStats, Item,
+        StatModifiers,
+    },
+    speed::NORMAL_SPEED,
+    status::{StatusEffects, StatusKind},
+};
 
 pub struct EquipActionHandler;
 
@@ -65,3 +72,149 @@ impl<'a> System<'a> for EquipActionHandler {
         equip_actions.clear();
     }
 }
+
+/// The stats `owner` ends up with once the bonuses of everything it has equipped and any speed status are added to its
+/// base stats. Tools that broke this turn no longer count even though they are only cleaned up at the end of the frame
+pub fn effective_stats(
+    owner: Entity,
+    health: Option<&HealthStats>,
+    action_points: Option<&ActionPoints>,
+    statuses: Option<&StatusEffects>,
+    equipped: &ReadStorage<Equipped>,
+    items: &ReadStorage<Item>,
+    attack_bonuses: &ReadStorage<AttackBonus>,
+    modifiers: &ReadStorage<StatModifiers>,
+) -> EffectiveStats {
+    let mut attack_bonus = 0;
+    let mut defense = health.map_or(0, |health| health.defense as i32);
+    let mut speed = action_points.map_or(NORMAL_SPEED, |ap| ap.speed) as i32;
+    speed += statuses.map_or(0, |statuses| statuses.magnitude(StatusKind::Speed)) as i32;
+    for (equip, item, bonus, modifier) in (equipped, items, attack_bonuses.maybe(), modifiers.maybe()).join() {
+        if equip.on != owner || item.qty.0 == 0 {
+            continue;
+        }
+        attack_bonus += bonus.map_or(0, |bonus| bonus.0);
+        if let Some(modifier) = modifier {
+            defense += modifier.defense;
+            speed += modifier.speed;
+        }
+    }
+    EffectiveStats { attack_bonus, defense: defense.max(0) as usize, speed: speed.max(1) as u32 }
+}
+
+/// Keeps the `EffectiveStats` of everything with equipment slots in step with what it has on and its speed status, ran
+/// after equipping and after weapons wear out so every change is counted
+pub struct EffectiveStatsSystem;
+
+impl<'a> System<'a> for EffectiveStatsSystem {
+    type SystemData = (
+        WriteStorage<'a, EffectiveStats>,
+        ReadStorage<'a, EquipmentSlots>,
+        ReadStorage<'a, HealthStats>,
+        ReadStorage<'a, ActionPoints>,
+        ReadStorage<'a, StatusEffects>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, Item>,
+        ReadStorage<'a, AttackBonus>,
+        ReadStorage<'a, StatModifiers>,
+        Entities<'a>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut effective,
+            equipment_slots,
+            healths,
+            action_points,
+            statuses,
+            equipped,
+            items,
+            attack_bonuses,
+            modifiers,
+            entities,
+        ): Self::SystemData,
+    ) {
+        for (owner, _) in (&entities, &equipment_slots).join() {
+            let stats = effective_stats(
+                owner,
+                healths.get(owner),
+                action_points.get(owner),
+                statuses.get(owner),
+                &equipped,
+                &items,
+                &attack_bonuses,
+                &modifiers,
+            );
+            if effective.get(owner) != Some(&stats) {
+                let _ = effective.insert(owner, stats);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::{Builder, WorldExt};
+
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        components::EquipmentSlot,
+        items::{ItemID, ItemQty},
+        status::StatusEffect,
+        test_harness::TestWorld,
+    };
+
+    fn gear(world: &mut TestWorld, owner: Entity, slot: EquipmentSlot, modifiers: StatModifiers) -> Entity {
+        world
+            .ecs
+            .create_entity()
+            .with(Item::new(ItemID::default(), ItemQty(1)))
+            .with(Equipable { slot })
+            .with(Equipped { on: owner })
+            .with(modifiers)
+            .build()
+    }
+
+    #[test]
+    fn equipment_adds_to_the_base_stats_until_it_comes_off_or_breaks() {
+        let mut world = TestWorld::new();
+        let player = world.player();
+        world.insert(player, EquipmentSlots::human());
+        world.insert(player, HealthStats::new(10, 1));
+        world.insert(player, ActionPoints::new(NORMAL_SPEED));
+        let vest = gear(&mut world, player, EquipmentSlot::Torso, StatModifiers { defense: 2, speed: -1 });
+        let boots = gear(&mut world, player, EquipmentSlot::Feet, StatModifiers { defense: 0, speed: 3 });
+        world.insert(boots, AttackBonus(1));
+
+        world.run(EffectiveStatsSystem);
+        let expected = EffectiveStats { attack_bonus: 1, defense: 3, speed: NORMAL_SPEED + 2 };
+        assert_eq!(world.get::<EffectiveStats>(player), Some(expected));
+
+        world.ecs.write_storage::<Equipped>().remove(vest);
+        world.ecs.write_storage::<Item>().get_mut(boots).unwrap().qty = ItemQty(0);
+        world.run(EffectiveStatsSystem);
+        let expected = EffectiveStats { attack_bonus: 0, defense: 1, speed: NORMAL_SPEED };
+        assert_eq!(world.get::<EffectiveStats>(player), Some(expected));
+    }
+
+    #[test]
+    fn speed_status_adds_to_the_speed_until_it_runs_out() {
+        let mut world = TestWorld::new();
+        let player = world.player();
+        world.insert(player, EquipmentSlots::human());
+        world.insert(player, ActionPoints::new(NORMAL_SPEED));
+        let mut statuses = StatusEffects::default();
+        statuses.apply(StatusEffect::new(StatusKind::Speed, 5, Duration::from_secs(1)));
+        world.insert(player, statuses);
+
+        world.run(EffectiveStatsSystem);
+        assert_eq!(world.get::<EffectiveStats>(player).map(|stats| stats.speed), Some(NORMAL_SPEED + 5));
+
+        world.ecs.write_storage::<StatusEffects>().remove(player);
+        world.run(EffectiveStatsSystem);
+        assert_eq!(world.get::<EffectiveStats>(player).map(|stats| stats.speed), Some(NORMAL_SPEED));
+    }
+}
@@ -0,0 +1,208 @@
+/* examine.rs
+ *   Describes whatever the player looks at, items in the inventory and anything out in the world are described
+ *   the same way. Looking moves a cursor over the map, by key or by clicking a tile, and lists what is on the tile
+ *   under it. One thing in the list is focused at a time and has its description shown.
+ * */
+
+use std::cmp::Reverse;
+
+use bracket_lib::terminal::{BTerm, Point, VirtualKeyCode as VKC};
+use specs::{World, WorldExt};
+
+use crate::{
+    being::BeingID,
+    camera::HoveredTile,
+    components::{Item, Name, Position, Renderable, Viewshed},
+    data_read::ENTITY_DB,
+    game_init::PlayerEntity,
+    map::{MapRes, TileEntity},
+};
+
+/// Said about something that has neither a description nor a name
+const UNKNOWN_THING: &str = "Something you can't quite make out.";
+
+/// One thing on an examined tile
+#[derive(Debug, PartialEq, Eq)]
+pub struct Examined {
+    pub name: String,
+    /// The same as the name when nothing more can be said about it
+    pub description: String,
+}
+
+pub enum LookResponse {
+    Waiting,
+    Moved(Position),
+    /// Another thing on the tile is focused, by its index in `examine_tile`
+    Focused(usize),
+    Cancel,
+}
+
+/// What examining an entity tells the player. Items and beings use the description from their raws, anything without
+/// one is described by its name
+pub fn describe(name: Option<&Name>, item: Option<&Item>, being: Option<&BeingID>) -> String {
+    let edb = ENTITY_DB.lock().unwrap();
+    let description = match (item, being) {
+        (Some(item), _) => edb.items.get_by_id(item.id).map(|info| info.examine_text.clone()),
+        (None, Some(being)) => edb.beings.get_by_id(*being).and_then(|info| info.examine_text.clone()),
+        _ => None,
+    };
+    match (description.filter(|text| !text.is_empty()), name) {
+        (Some(description), _) => description,
+        (None, Some(name)) => name.0.clone(),
+        (None, None) => UNKNOWN_THING.to_string(),
+    }
+}
+
+/// Everything the player can make out on the tile, whatever is drawn on top first and the tile itself last. Tiles that
+/// were never seen have nothing on them and beings are only there while the tile is in view
+pub fn examine_tile(ecs: &World, pos: Position) -> Vec<Examined> {
+    let map = ecs.read_resource::<MapRes>();
+    if !map.0.is_revealed(pos.to_point()) {
+        return vec![];
+    }
+    let names = ecs.read_storage::<Name>();
+    let items = ecs.read_storage::<Item>();
+    let beings = ecs.read_storage::<BeingID>();
+    let renderables = ecs.read_storage::<Renderable>();
+    let player_e = ecs.read_resource::<PlayerEntity>();
+    let in_view =
+        ecs.read_storage::<Viewshed>().get(player_e.0).map_or(true, |view| view.tiles.contains(&pos.to_point()));
+
+    let idx = pos.to_idx(map.0.width);
+    let mut examined: Vec<(u32, Examined)> = Vec::new();
+    for tile_entity in map.0.tile_entities[idx].iter() {
+        let entity = match tile_entity {
+            TileEntity::Fishable(_) => continue, // the tile is already water
+            TileEntity::Breakable(e) | TileEntity::Item(e) | TileEntity::Blocking(e) => *e,
+        };
+        if beings.contains(entity) && !in_view {
+            continue;
+        }
+        let name = match names.get(entity) {
+            Some(name) => name,
+            None => continue,
+        };
+        let thing =
+            Examined { name: name.0.clone(), description: describe(Some(name), items.get(entity), beings.get(entity)) };
+        if !examined.iter().any(|(_, seen)| *seen == thing) {
+            examined.push((renderables.get(entity).map_or(0, |render| render.z_priority), thing));
+        }
+    }
+    examined.sort_by_key(|(z, _)| Reverse(*z));
+
+    let tile_name = map.0.tiles[idx].name.clone();
+    let mut examined: Vec<Examined> = examined.into_iter().map(|(_, thing)| thing).collect();
+    examined.push(Examined { name: tile_name.clone(), description: tile_name });
+    examined
+}
+
+/// The part of a list of `len` things shown in `rows` rows that keeps `focus` in view. When it doesn't all fit the
+/// last row is left for saying how many are hidden
+pub fn visible_window(len: usize, focus: usize, rows: usize) -> (usize, usize) {
+    if len <= rows {
+        return (0, len);
+    }
+    let shown = rows.saturating_sub(1).max(1);
+    let start = (focus + 1).saturating_sub(shown).min(len - shown);
+    (start, start + shown)
+}
+
+/// The cursor starts on the player
+pub fn start_looking(ecs: &World) -> Option<Position> {
+    let player_e = ecs.read_resource::<PlayerEntity>();
+    ecs.read_storage::<Position>().get(player_e.0).copied()
+}
+
+pub fn p_input_looking(ecs: &World, ctx: &BTerm, target: Position, focus: usize) -> LookResponse {
+    if ctx.left_click {
+        return match ecs.read_resource::<HoveredTile>().0 {
+            Some(hovered) => LookResponse::Moved(hovered),
+            None => LookResponse::Waiting,
+        };
+    }
+    let (delta_x, delta_y) = match ctx.key {
+        Some(VKC::W | VKC::Up) => (0, -1),
+        Some(VKC::S | VKC::Down) => (0, 1),
+        Some(VKC::A | VKC::Left) => (-1, 0),
+        Some(VKC::D | VKC::Right) => (1, 0),
+        Some(VKC::Tab) => {
+            let count = examine_tile(ecs, target).len();
+            if count < 2 {
+                return LookResponse::Waiting;
+            }
+            let next = if ctx.shift { (focus + count - 1) % count } else { (focus + 1) % count };
+            return LookResponse::Focused(next);
+        }
+        Some(VKC::Escape | VKC::X) => return LookResponse::Cancel,
+        _ => return LookResponse::Waiting,
+    };
+    let next = target.to_point() + Point::new(delta_x, delta_y);
+    if !ecs.fetch::<MapRes>().0.in_bounds(next) {
+        return LookResponse::Waiting;
+    }
+    LookResponse::Moved(Position::from(next))
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::Builder;
+
+    use crate::{
+        components::Blocking,
+        indexing::{IndexBlockedTiles, IndexItemTiles, IndexReset},
+        items::{ItemID, ItemQty},
+        test_harness::TestWorld,
+        z_order::{BEING_Z, ITEM_Z},
+    };
+
+    use super::*;
+
+    #[test]
+    fn things_without_a_description_are_described_by_name() {
+        assert_eq!(describe(Some(&Name::new("Boulder")), None, None), "Boulder");
+        assert_eq!(describe(None, None, None), UNKNOWN_THING);
+    }
+
+    #[test]
+    fn looking_lists_what_is_on_the_tile_then_the_tile() {
+        let mut world = TestWorld::new().with_map(6, 6);
+        let pos = Position::new(2, 3);
+        world.spawn("Boulder", pos).with(Blocking {}).build();
+        world.ecs.write_resource::<MapRes>().0.revealed_tiles.fill(true);
+        world.run(IndexReset);
+        world.run(IndexBlockedTiles);
+
+        let examined = examine_tile(&world.ecs, pos);
+
+        assert_eq!(examined.len(), 2);
+        assert_eq!(examined[0], Examined { name: "Boulder".to_string(), description: "Boulder".to_string() });
+        assert!(examine_tile(&world.ecs, Position::new(9, 9)).is_empty());
+    }
+
+    #[test]
+    fn things_on_top_are_listed_first() {
+        let mut world = TestWorld::new().with_map(6, 6);
+        let pos = Position::new(2, 3);
+        world
+            .spawn("Stick", pos)
+            .with(Item::new(ItemID::new(2), ItemQty(1)))
+            .with(Renderable::clear_bg(0, (255, 255, 255), ITEM_Z))
+            .build();
+        world.spawn("Goat", pos).with(Blocking {}).with(Renderable::clear_bg(0, (255, 255, 255), BEING_Z)).build();
+        world.ecs.write_resource::<MapRes>().0.revealed_tiles.fill(true);
+        world.run(IndexReset);
+        world.run(IndexBlockedTiles);
+        world.run(IndexItemTiles);
+
+        let names: Vec<String> = examine_tile(&world.ecs, pos).into_iter().map(|thing| thing.name).collect();
+        assert_eq!(names, vec!["Goat", "Stick", "Grass"]);
+    }
+
+    #[test]
+    fn long_lists_scroll_to_keep_the_focus_shown() {
+        assert_eq!(visible_window(3, 2, 4), (0, 3));
+        assert_eq!(visible_window(10, 0, 4), (0, 3));
+        assert_eq!(visible_window(10, 5, 4), (3, 6));
+        assert_eq!(visible_window(10, 9, 4), (7, 10));
+    }
+}
@@ -0,0 +1,212 @@
+/* explosives.rs
+ *   Thrown explosives sit on the tile they land on until their fuse burns down, then hurt every breakable and being
+ *   the blast can reach. Walls, ie. anything solid that can be broken like rock and trees, take the hit but shelter
+ *   whatever is behind them.
+ * */
+
+use std::{collections::HashSet, time::Duration};
+
+use bracket_lib::{
+    geometry::{line2d, DistanceAlg, LineAlg},
+    terminal::{ColorPair, Point},
+};
+use specs::{Entities, Join, Read, ReadStorage, System, Write, WriteStorage};
+
+use crate::{
+    being::BeingID,
+    char_c::CH_EXPLOSION,
+    colors::TEXASROSE,
+    components::{
+        Blocking, Breakable, DeleteCondition, EffectiveStats, HealthStats, LitFuse, Name, Position, SufferDamage,
+    },
+    debug::CLEAR,
+    game_init::PlayerEntity,
+    map::{Map, MapRes},
+    screen_shake::{ScreenShake, HEAVY_HIT_SHAKE},
+    tile_animation::{AnimationRequest, TileAnimationBuilder},
+    time::DeltaTime,
+    ui::message_log::MessageLog,
+};
+
+/// How long an explosive takes to go off after it is thrown
+pub const FUSE_TIME: Duration = Duration::from_millis(1500);
+/// How long the fire of a blast stays on each tile it reached
+const BLAST_FLASH_TIME: Duration = Duration::from_millis(300);
+
+/// Sets off explosives whose fuse runs out this frame, before the animation cleanup deletes them
+pub struct DetonationSystem;
+
+impl<'a> System<'a> for DetonationSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, LitFuse>,
+        ReadStorage<'a, DeleteCondition>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, HealthStats>,
+        ReadStorage<'a, EffectiveStats>,
+        ReadStorage<'a, Breakable>,
+        ReadStorage<'a, BeingID>,
+        ReadStorage<'a, Blocking>,
+        WriteStorage<'a, SufferDamage>,
+        Write<'a, TileAnimationBuilder>,
+        Write<'a, ScreenShake>,
+        Write<'a, MessageLog>,
+        Read<'a, MapRes>,
+        Read<'a, DeltaTime>,
+        Read<'a, PlayerEntity>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            fuses,
+            delete_conditions,
+            positions,
+            names,
+            health_stats,
+            effective_stats,
+            breakables,
+            beings,
+            blockers,
+            mut suffer_damage,
+            mut anim_builder,
+            mut screen_shake,
+            mut log,
+            map,
+            dt,
+            player_e,
+        ): Self::SystemData,
+    ) {
+        let detonating: Vec<(LitFuse, Position, Option<&Name>)> =
+            (&fuses, &delete_conditions, &positions, names.maybe())
+                .join()
+                .filter(
+                    |(_, condition, ..)| matches!(condition, DeleteCondition::Timed(time_left) if *time_left <= dt.0),
+                )
+                .map(|(fuse, _, pos, name)| (*fuse, *pos, name))
+                .collect();
+        if detonating.is_empty() {
+            return;
+        }
+
+        let walls: HashSet<Point> =
+            (&positions, &blockers, &breakables).join().map(|(pos, ..)| pos.to_point()).collect();
+        for (fuse, at, name) in detonating {
+            let reached = blast_area(at, fuse.blast.radius, &map.0, &walls);
+            log.log(format!("The {} explodes!", name.map_or("bomb", |name| name.0.as_str())));
+            screen_shake.shake(HEAVY_HIT_SHAKE);
+
+            for point in reached.iter() {
+                anim_builder.request(AnimationRequest::StaticTile(
+                    CH_EXPLOSION as usize,
+                    Position::from(*point),
+                    ColorPair::new(TEXASROSE, CLEAR),
+                    DeleteCondition::Timed(BLAST_FLASH_TIME),
+                ));
+            }
+
+            for (victim, pos, stats, breakable, being) in
+                (&entities, &positions, &health_stats, breakables.maybe(), beings.maybe()).join()
+            {
+                if (breakable.is_none() && being.is_none()) || !reached.contains(&pos.to_point()) {
+                    continue;
+                }
+                let defense = effective_stats.get(victim).map_or(stats.defense, |effective| effective.defense);
+                let damage = fuse.blast.damage.saturating_sub(defense as u32);
+                if damage == 0 {
+                    continue;
+                }
+                // being caught in your own blast is the environment's fault so the thrower isn't named as a killer
+                let source = if victim == fuse.thrower { None } else { Some(fuse.thrower) };
+                SufferDamage::new_damage(&mut suffer_damage, victim, source, -(damage as i32));
+                if victim == player_e.0 {
+                    log.log("You are caught in the blast!");
+                }
+            }
+        }
+    }
+}
+
+/// Every tile within `radius` of `center` with a clear line to it, a wall is hit itself but shelters what is past it.
+/// Opaque tiles count as walls as well as the points in `walls`
+pub fn blast_area(center: Position, radius: u32, map: &Map, walls: &HashSet<Point>) -> HashSet<Point> {
+    let center = center.to_point();
+    let radius = radius as i32;
+    let shelters = |point: Point| {
+        walls.contains(&point) || !map.tiles[map.xy_to_idx(point.x as usize, point.y as usize)].transparent
+    };
+
+    let mut reached = HashSet::new();
+    for y in center.y - radius..=center.y + radius {
+        for x in center.x - radius..=center.x + radius {
+            let point = Point::new(x, y);
+            if !map.in_bounds(point) || DistanceAlg::Pythagoras.distance2d(center, point) > radius as f32 {
+                continue;
+            }
+            let sheltered = line2d(LineAlg::Bresenham, center, point)
+                .into_iter()
+                .filter(|step| *step != center && *step != point)
+                .any(shelters);
+            if !sheltered {
+                reached.insert(point);
+            }
+        }
+    }
+    reached
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::Builder;
+
+    use super::*;
+    use crate::{
+        components::{Blast, ToolType},
+        test_harness::TestWorld,
+    };
+
+    #[test]
+    fn walls_shelter_what_is_behind_them() {
+        let map = Map::new(10, 10, (0, 0));
+        let walls: HashSet<Point> = (0..10).map(|y| Point::new(6, y)).collect();
+        let reached = blast_area(Position::new(5, 5), 3, &map, &walls);
+
+        assert!(reached.contains(&Point::new(3, 5)));
+        assert!(reached.contains(&Point::new(6, 5)));
+        assert!(!reached.contains(&Point::new(7, 5)));
+        assert!(!reached.contains(&Point::new(7, 6)));
+    }
+
+    #[test]
+    fn bombs_go_off_when_the_fuse_runs_out() {
+        let mut world = TestWorld::new();
+        let thrower = world.player();
+        let near_rock = world
+            .spawn("Rock", Position::new(6, 5))
+            .with(Breakable::new(ToolType::Pickaxe))
+            .with(HealthStats::new(10, 1))
+            .build();
+        let far_rock = world
+            .spawn("Rock", Position::new(9, 5))
+            .with(Breakable::new(ToolType::Pickaxe))
+            .with(HealthStats::new(10, 1))
+            .build();
+        let bomb = world
+            .spawn("Bomb", Position::new(5, 5))
+            .with(LitFuse { blast: Blast { radius: 2, damage: 6 }, thrower })
+            .with(DeleteCondition::Timed(FUSE_TIME))
+            .build();
+
+        world.run(DetonationSystem);
+        assert!(!world.has::<SufferDamage>(near_rock));
+
+        world.insert(bomb, DeleteCondition::Timed(Duration::ZERO));
+        world.run(DetonationSystem);
+        let hits = world.ecs.read_storage::<SufferDamage>().get(near_rock).map(|hurt| hurt.amount.clone());
+        assert_eq!(hits, Some(vec![(Some(thrower), -5)]));
+        assert!(!world.has::<SufferDamage>(far_rock));
+        assert!(world.logged("The Bomb explodes!"));
+    }
+}
@@ -0,0 +1,67 @@
+/* factions.rs
+ *   Which side a being is on and how the sides feel about each other, the relations are read from the raws.
+ *   Aggressive beings go after the nearest being they are hostile toward and wildlife runs from what hunts it.
+ * */
+
+use std::ops::Deref;
+
+use serde::{Deserialize, Serialize};
+use specs::{storage::MaskedStorage, Component, Entity, ReadStorage, Storage, VecStorage};
+
+use crate::{components::Monster, data_read::prelude::FACTION_DB, player::Player};
+
+#[derive(Debug, Component, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[storage(VecStorage)]
+#[serde(rename_all = "lowercase")]
+pub enum Faction {
+    /// The player and their companions
+    Player,
+    Villager,
+    /// Animals that are prey to predators
+    Wildlife,
+    Predator,
+    Monster,
+}
+
+impl Faction {
+    /// The entity's faction, the player and monsters made before factions existed fall back to their own. Anything
+    /// else without one is on no side so it is never attacked
+    pub fn of<D>(
+        entity: Entity,
+        factions: &ReadStorage<Faction>,
+        players: &ReadStorage<Player>,
+        monsters: &Storage<'_, Monster, D>,
+    ) -> Option<Faction>
+    where
+        D: Deref<Target = MaskedStorage<Monster>>,
+    {
+        if let Some(faction) = factions.get(entity) {
+            Some(*faction)
+        } else if players.contains(entity) {
+            Some(Faction::Player)
+        } else if monsters.contains(entity) {
+            Some(Faction::Monster)
+        } else {
+            None
+        }
+    }
+}
+
+/// How one faction feels toward another, see `raws/factions.json5`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Relation {
+    Ally,
+    Neutral,
+    Hostile,
+}
+
+/// True when `a` is out to attack `b`, relations only go one way so `b` may not feel the same about `a`
+pub fn are_hostile(a: Faction, b: Faction) -> bool {
+    FACTION_DB.lock().unwrap().relation(a, b) == Relation::Hostile
+}
+
+/// True when `a` counts `b` as a friend, a being is always allied with its own faction
+pub fn are_allied(a: Faction, b: Faction) -> bool {
+    FACTION_DB.lock().unwrap().relation(a, b) == Relation::Ally
+}
@@ -0,0 +1,103 @@
+use bracket_lib::random::RandomNumberGenerator;
+use bracket_lib::terminal::ColorPair;
+use log::{error, warn};
+use specs::{Entity, Join, ReadStorage, System, World, WorldExt, WriteStorage};
+
+use crate::{
+    components::{Crop, Name, Renderable},
+    data_read::{prelude::CROP_DB, ENTITY_DB},
+    debug::CLEAR,
+    items::{ItemID, ItemQty, ItemSpawner, SpawnType},
+    ui::message_log::MessageLog,
+};
+
+pub fn is_crop(ecs: &World, entity: Entity) -> bool {
+    ecs.read_storage::<Crop>().contains(entity)
+}
+
+/// Grows every crop by a turn, moving it to the next stage once it has spent long enough in the current one
+pub struct CropGrowthSystem;
+
+impl<'a> System<'a> for CropGrowthSystem {
+    type SystemData = (WriteStorage<'a, Crop>, WriteStorage<'a, Renderable>, ReadStorage<'a, Name>);
+
+    fn run(&mut self, (mut crops, mut renderables, names): Self::SystemData) {
+        let cdb = CROP_DB.lock().unwrap();
+        for (crop, renderable, name) in (&mut crops, &mut renderables, &names).join() {
+            let info = match cdb.get_by_name(&name.0) {
+                Some(info) => info,
+                None => {
+                    warn!("{} is planted but has no crop definition", name);
+                    continue;
+                }
+            };
+            if info.is_ripe(crop.stage) {
+                continue;
+            }
+
+            crop.turns_in_stage += 1;
+            if crop.turns_in_stage < info.stages[crop.stage].turns {
+                continue;
+            }
+            crop.stage += 1;
+            crop.turns_in_stage = 0;
+
+            let stage = &info.stages[crop.stage];
+            renderable.atlas_index = stage.atlas_index;
+            renderable.color_pair = ColorPair::new(info.stage_fg(stage), CLEAR);
+        }
+    }
+}
+
+/// Picks the crop into the harvester's bag when it is ripe, false when there was nothing to harvest
+pub fn harvest_crop(ecs: &mut World, harvester: Entity, crop_entity: Entity) -> bool {
+    let name = match ecs.read_storage::<Name>().get(crop_entity) {
+        Some(name) => name.0.clone(),
+        None => return false,
+    };
+    let stage = match ecs.read_storage::<Crop>().get(crop_entity) {
+        Some(crop) => crop.stage,
+        None => return false,
+    };
+
+    let mut rng = RandomNumberGenerator::new();
+    let yields: Vec<(ItemID, ItemQty)> = {
+        let cdb = CROP_DB.lock().unwrap();
+        match cdb.get_by_name(&name) {
+            Some(info) if info.is_ripe(stage) => {
+                info.yields.iter().map(|(id, qty)| (*id, qty.roll(&mut rng))).collect()
+            }
+            Some(_) => {
+                ecs.fetch_mut::<MessageLog>().log(format!("The {} is not ready to harvest yet.", name));
+                return false;
+            }
+            None => {
+                error!("{} could not be harvested, it has no crop definition", name);
+                return false;
+            }
+        }
+    };
+
+    let mut harvested = Vec::new();
+    {
+        let mut spawner = ecs.fetch_mut::<ItemSpawner>();
+        let edb = ENTITY_DB.lock().unwrap();
+        for (id, qty) in yields.into_iter().filter(|(_, qty)| qty.0 > 0) {
+            spawner.request_amt(id, SpawnType::InBag(harvester), qty);
+            if let Some(info) = edb.items.get_by_id(id) {
+                harvested.push(format!("{} {}", qty, info.name));
+            }
+        }
+    }
+
+    if ecs.delete_entity(crop_entity).is_err() {
+        warn!("{} was harvested but could not be removed", name);
+    }
+    let mut log = ecs.fetch_mut::<MessageLog>();
+    if harvested.is_empty() {
+        log.log(format!("The {} had nothing worth picking.", name));
+    } else {
+        log.enhance(format!("You harvested {}.", harvested.join(", ")));
+    }
+    true
+}
@@ -0,0 +1,225 @@
+/* fish_schools.rs
+ *   Schools of fish gather over a cluster of water tiles. Every tile of a school can be fished and they share one
+ *   catch counter, once it runs out or the school has been around long enough the fish scatter.
+ * */
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    time::Duration,
+};
+
+use log::info;
+use serde::{Deserialize, Serialize};
+use specs::{Entities, Entity, Join, ReadStorage, System, Write, WriteStorage};
+
+use crate::{
+    colors::Color,
+    components::{Fishable, Position, Renderable, Water},
+    rng::GameRng,
+    z_order::EFFECT_Z,
+};
+
+/// Ripples drawn over every tile of a school
+const RIPPLE_ATLAS_INDEX: usize = 247;
+const RIPPLE_COLOR: Color = (170, 215, 255);
+
+/// How big schools are and how often they show up, read from the settings when the game starts
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct FishSchoolConfig {
+    /// No schools gather when off
+    pub enabled: bool,
+    /// Water tiles a school spreads over
+    pub size: usize,
+    /// Fish that can be caught from a school before it scatters
+    pub catches: u32,
+    /// Most schools in the water at once
+    pub max_schools: usize,
+    /// Turns after a school scatters before another can gather
+    pub respawn_turns: usize,
+    /// Seconds a school stays before scattering on its own
+    pub lifetime_secs: u64,
+    /// Added to every fishing roll made at a school, the same way bait potency is
+    pub bite_bonus: usize,
+}
+
+impl Default for FishSchoolConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            size: 5,
+            catches: 4,
+            max_schools: 1,
+            respawn_turns: 150,
+            lifetime_secs: 180,
+            bite_bonus: 20,
+        }
+    }
+}
+
+pub struct FishSchool {
+    pub tiles: Vec<(Entity, Position)>,
+    pub catches_left: u32,
+}
+
+/// Every school in the water and how long until the next one can gather
+pub struct FishSchools {
+    cfg: FishSchoolConfig,
+    schools: Vec<FishSchool>,
+    /// Turns until another school can gather
+    respawn_in: usize,
+}
+
+impl Default for FishSchools {
+    fn default() -> Self {
+        Self::new(FishSchoolConfig::default())
+    }
+}
+
+impl FishSchools {
+    /// The first school is free to gather right away
+    pub fn new(cfg: FishSchoolConfig) -> Self {
+        Self { cfg, schools: Vec::new(), respawn_in: 0 }
+    }
+
+    fn school_at(&self, pos: &Position) -> Option<usize> {
+        self.schools.iter().position(|school| school.tiles.iter().any(|(_, tile)| tile == pos))
+    }
+
+    /// Bonus to the bite roll when fishing at `pos`
+    pub fn bite_bonus(&self, pos: &Position) -> usize {
+        self.school_at(pos).map_or(0, |_| self.cfg.bite_bonus)
+    }
+
+    /// Takes a fish from the school at `pos`, the school's tiles are returned when that was its last fish
+    pub fn catch_at(&mut self, pos: &Position) -> Option<Vec<Entity>> {
+        let idx = self.school_at(pos)?;
+        let school = &mut self.schools[idx];
+        school.catches_left = school.catches_left.saturating_sub(1);
+        if school.catches_left > 0 {
+            return None;
+        }
+        self.respawn_in = self.cfg.respawn_turns;
+        Some(self.schools.remove(idx).tiles.into_iter().map(|(e, _)| e).collect())
+    }
+}
+
+/// Up to `size` tiles spreading out from `start` through the open water
+fn gather_tiles(start: Position, open_water: &HashMap<Position, Entity>, size: usize) -> Vec<(Entity, Position)> {
+    let mut tiles = Vec::new();
+    let mut seen = HashSet::from([start]);
+    let mut frontier = VecDeque::from([start]);
+    while let Some(pos) = frontier.pop_front() {
+        if tiles.len() >= size {
+            break;
+        }
+        let entity = match open_water.get(&pos) {
+            Some(entity) => *entity,
+            None => continue,
+        };
+        tiles.push((entity, pos));
+        let neighbors =
+            [(pos.x + 1, pos.y), (pos.x.wrapping_sub(1), pos.y), (pos.x, pos.y + 1), (pos.x, pos.y.wrapping_sub(1))];
+        for (x, y) in neighbors {
+            let next = Position::new(x, y);
+            if open_water.contains_key(&next) && seen.insert(next) {
+                frontier.push_back(next);
+            }
+        }
+    }
+    tiles
+}
+
+/// Forgets schools that timed out and gathers a new one over open water when there is room for it
+pub struct FishSchoolSystem;
+
+impl<'a> System<'a> for FishSchoolSystem {
+    type SystemData = (
+        Write<'a, FishSchools>,
+        WriteStorage<'a, Fishable>,
+        WriteStorage<'a, Renderable>,
+        ReadStorage<'a, Water>,
+        ReadStorage<'a, Position>,
+        Write<'a, GameRng>,
+        Entities<'a>,
+    );
+
+    fn run(
+        &mut self,
+        (mut schools, mut fishables, mut renderables, waters, positions, mut rng, entities): Self::SystemData,
+    ) {
+        // the fish leave on their own once their tiles stop being fishable
+        let before = schools.schools.len();
+        schools.schools.retain(|school| school.tiles.iter().any(|(tile, _)| fishables.contains(*tile)));
+        if schools.schools.len() < before {
+            schools.respawn_in = schools.cfg.respawn_turns;
+        }
+
+        if !schools.cfg.enabled || schools.schools.len() >= schools.cfg.max_schools || schools.cfg.size == 0 {
+            return;
+        }
+        if schools.respawn_in > 0 {
+            schools.respawn_in -= 1;
+            return;
+        }
+
+        let open_water: HashMap<Position, Entity> =
+            (&entities, &waters, &positions, !&fishables).join().map(|(e, _, pos, _)| (*pos, e)).collect();
+        if open_water.is_empty() {
+            return;
+        }
+        let mut starts: Vec<&Position> = open_water.keys().collect();
+        // sorted so the seeded rng picks the same spot every time
+        starts.sort_by_key(|pos| (pos.y, pos.x));
+        let start = *starts[rng.0.range(0, starts.len())];
+        let tiles = gather_tiles(start, &open_water, schools.cfg.size);
+
+        let lifetime = Duration::from_secs(schools.cfg.lifetime_secs);
+        for (tile, _) in tiles.iter() {
+            let _ = fishables.insert(*tile, Fishable { time_left: lifetime });
+            let _ = renderables.insert(*tile, Renderable::clear_bg(RIPPLE_ATLAS_INDEX, RIPPLE_COLOR, EFFECT_Z));
+        }
+        info!("A school of fish gathered over {} tiles around {:?}", tiles.len(), start);
+        let catches_left = schools.cfg.catches;
+        schools.schools.push(FishSchool { tiles, catches_left });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::{Builder, WorldExt};
+
+    use super::*;
+    use crate::test_harness::TestWorld;
+
+    fn lake(world: &mut TestWorld, width: usize) {
+        for x in 0..width {
+            world.spawn("Water", Position::new(x, 0)).with(Water {}).build();
+        }
+    }
+
+    #[test]
+    fn schools_gather_over_a_cluster_of_water() {
+        let mut world = TestWorld::new();
+        lake(&mut world, 8);
+        world.run(FishSchoolSystem);
+
+        let fishable = world.ecs.read_storage::<Fishable>().join().count();
+        assert_eq!(fishable, FishSchoolConfig::default().size);
+        assert_eq!(world.ecs.read_resource::<FishSchools>().schools.len(), 1);
+    }
+
+    #[test]
+    fn schools_scatter_once_fished_out() {
+        let mut schools = FishSchools::new(FishSchoolConfig { catches: 2, ..Default::default() });
+        let tile = TestWorld::new().ecs.create_entity().build();
+        schools.schools.push(FishSchool { tiles: vec![(tile, Position::new(3, 3))], catches_left: 2 });
+
+        assert_eq!(schools.bite_bonus(&Position::new(3, 3)), schools.cfg.bite_bonus);
+        assert_eq!(schools.bite_bonus(&Position::new(4, 3)), 0);
+        assert!(schools.catch_at(&Position::new(3, 3)).is_none());
+        assert_eq!(schools.catch_at(&Position::new(3, 3)), Some(vec![tile]));
+        assert!(schools.schools.is_empty());
+        assert_eq!(schools.respawn_in, schools.cfg.respawn_turns);
+    }
+}
@@ -1,32 +1,46 @@
 use std::time::Duration;
 
 use crate::{
+    activity::{Activity, ActivityKind},
+    catch_release::{CatchChoice, CatchDecision, CatchRelease},
     components::{
-        DeleteCondition, FinishedActivity, FishAction, FishOnTheLine, Fishable, FishingMinigame, GameAction, Name,
-        Renderable, WaitingForFish, Water,
+        BaitActive, Container, Currency, DeleteCondition, FinishedActivity, FishAction, FishOnTheLine, FishStruggle,
+        Fishable, FishingMinigame, FishingSpot, GameAction, InBag, Item, Name, Position, Renderable, Water,
     },
+    data_read::ENTITY_DB,
+    fish_schools::FishSchools,
     game_init::PlayerEntity,
-    items::{ItemID, ItemSpawner, SpawnType},
+    items::{ItemID, ItemQty, ItemSpawner, Rarity, SpawnType},
+    rng::GameRng,
+    shop::bag_has_room,
     tile_animation::{AnimationRequest, TileAnimationBuilder},
     time::DeltaTime,
+    toasts::Toasts,
     ui::message_log::MessageLog,
+    weather::Weather,
     z_order::EFFECT_Z,
 };
 
 pub const WHITE: (u8, u8, u8) = (255, 255, 255);
 
 use bracket_lib::{color::ColorPair, random::*, terminal::BLACK};
-use log::info;
+use log::{debug, info};
 use specs::{Entities, Join, Read, ReadStorage, System, Write, WriteExpect, WriteStorage};
 
 pub struct SetupFishingActions;
 
 impl<'a> System<'a> for SetupFishingActions {
-    type SystemData =
-        (Entities<'a>, WriteStorage<'a, FishAction>, WriteStorage<'a, WaitingForFish>, Write<'a, TileAnimationBuilder>);
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, FishAction>,
+        WriteStorage<'a, FishingSpot>,
+        WriteStorage<'a, Activity>,
+        Write<'a, TileAnimationBuilder>,
+    );
 
-    fn run(&mut self, (entities, mut fish_actions, mut fish_waiters, mut anim_builder): Self::SystemData) {
+    fn run(&mut self, (entities, mut fish_actions, mut spots, mut activities, mut anim_builder): Self::SystemData) {
         for (fisher, fish_action) in (&entities, &mut fish_actions).join() {
+            let _ = spots.insert(fisher, FishingSpot { pos: fish_action.target });
             let mut rng = RandomNumberGenerator::new();
             anim_builder.request(AnimationRequest::StaticTile(
                 112,
@@ -36,10 +50,11 @@ impl<'a> System<'a> for SetupFishingActions {
             ));
 
             let attempts = rng.range(2, 6); // this could be affected by a fishing skill level?
-            match fish_waiters.insert(fisher, WaitingForFish::new(attempts)) {
+            let fishing = ActivityKind::Fishing { attempts_left: attempts, hooked: false };
+            match activities.insert(fisher, Activity::new(fishing, FISH_DELAY_TIME)) {
                 Ok(fishy) => {
                     if fishy.is_some() {
-                        eprintln!("ERROR: entity: {} was already waiting for fish, they should not have performed the action again", fisher.id());
+                        eprintln!("ERROR: entity: {} was already in an activity, they should not have performed the action again", fisher.id());
                     }
                 }
                 Err(..) => {
@@ -55,63 +70,89 @@ impl<'a> System<'a> for SetupFishingActions {
 
 pub struct WaitingForFishSystem;
 const FISH_DELAY_TIME: Duration = Duration::new(1, 0);
+/// Each point of bait potency shortens the wait between attempts by this much
+const BAIT_DELAY_REDUCTION: Duration = Duration::from_millis(20);
 
 impl<'a> System<'a> for WaitingForFishSystem {
     type SystemData = (
         Entities<'a>,
-        WriteStorage<'a, WaitingForFish>,
-        WriteStorage<'a, FishOnTheLine>,
+        WriteStorage<'a, Activity>,
         WriteStorage<'a, FishingMinigame>,
+        WriteStorage<'a, FishOnTheLine>,
         WriteStorage<'a, FinishedActivity>,
         Read<'a, PlayerEntity>,
         Read<'a, DeltaTime>,
         WriteExpect<'a, MessageLog>,
         ReadStorage<'a, Name>,
+        ReadStorage<'a, BaitActive>,
+        Write<'a, GameRng>,
+        Read<'a, Weather>,
+        ReadStorage<'a, FishingSpot>,
+        Read<'a, FishSchools>,
     );
 
     fn run(
         &mut self,
         (
             entities,
-            mut waiters,
-            mut fishing_lines,
+            mut activities,
             mut minigames,
+            mut on_lines,
             mut finished_activities,
             p_entity,
             dt,
             mut log,
             names,
+            baits,
+            mut rng,
+            weather,
+            spots,
+            schools,
         ): Self::SystemData,
     ) {
-        let mut rng = RandomNumberGenerator::new();
         let mut finished_fishers = Vec::new();
 
-        for (e, waiter, name) in (&entities, &mut waiters, &names).join() {
-            if waiter.attempts == 0 {
+        for (e, activity, name, ()) in (&entities, &mut activities, &names, !&finished_activities).join() {
+            let (attempts_left, hooked) = match activity.kind {
+                ActivityKind::Fishing { attempts_left, hooked } => (attempts_left, hooked),
+                _ => continue,
+            };
+            // Once hooked the fish is reeled in by the minigame
+            if hooked {
+                continue;
+            }
+            if attempts_left == 0 {
                 finished_fishers.push(e);
                 log.enhance(format!("{} ran out of attempts to catch a fish", name));
                 continue;
             }
 
-            // Wait for FISH_DELAY_TIME before attempting to fish again
-            waiter.time_since_last_attempt += dt.0;
-            if waiter.time_since_last_attempt <= FISH_DELAY_TIME {
+            let potency = baits.get(e).map_or(0, |bait| bait.potency);
+
+            // Wait for FISH_DELAY_TIME before attempting to fish again, bait makes the wait shorter
+            activity.duration = FISH_DELAY_TIME.saturating_sub(BAIT_DELAY_REDUCTION * potency as u32);
+            if !activity.advance(dt.0) {
                 continue;
             }
-            waiter.time_since_last_attempt = Duration::ZERO;
-            waiter.attempts -= 1;
+            let attempts_left = attempts_left - 1;
 
-            let roll = rng.range(1, 100);
-            log.debug(format!("Attempts left: {} | Rolled: {} ", waiter.attempts, roll));
+            let roll = rng.0.range(1, 100);
+            log.debug(format!("Attempts left: {} | Rolled: {} ", attempts_left, roll));
 
             // if roll < 1 {
             // for testing fix with below
-            if roll < 80 {
+            // rain stirs up the fish like bait does, and a school has plenty of them to bite
+            let school_bonus = spots.get(e).map_or(0, |spot| schools.bite_bonus(&spot.pos));
+            let bite = roll >= 80usize.saturating_sub(potency + weather.bite_bonus() + school_bonus);
+            activity.kind = ActivityKind::Fishing { attempts_left, hooked: bite };
+            if !bite {
                 continue;
             }
 
             // Bite on the line, succeeding in fishing attempt
-            finished_fishers.push(e);
+            let fish = roll_fish(&mut rng.0);
+            debug!("{} hooked fish {}", name, fish.value());
+            let _ = on_lines.insert(e, FishOnTheLine { fish });
             if e == p_entity.0 {
                 let _ = minigames.insert(
                     e,
@@ -123,31 +164,16 @@ impl<'a> System<'a> for WaitingForFishSystem {
                         mode: FishingBehavior::BackNForth,
                     },
                 );
+                info!("Player entering minigame state");
             } else {
                 info!("{} caught a fish, o cool", name);
-                log.log(format!("{} caught a fish wow with {} attempts remaining", name, waiter.attempts));
-            }
-
-            match fishing_lines.insert(e, FishOnTheLine {}) {
-                Ok(existing_fish) => {
-                    if let Some(fish) = existing_fish {
-                        log.debug(format!("ERROR: entity {} {} already had a fish on their line, cannot add a second fish ABORTING fish", name, e.id()));
-                        let _ = fishing_lines.insert(e, fish);
-                    }
-                }
-                Err(err) => {
-                    log.debug(format!("ERROR: entity: {} {} failed to add fish on the line: {}", name, e.id(), err));
-                }
+                log.log(format!("{} caught a fish wow with {} attempts remaining", name, attempts_left));
+                finished_fishers.push(e);
             }
         }
 
-        for finished in finished_fishers.iter() {
-            waiters.remove(*finished);
-            if *finished == p_entity.0 && minigames.contains(p_entity.0) {
-                info!("Player entering minigame state");
-                continue;
-            }
-            let _ = finished_activities.insert(*finished, FinishedActivity {});
+        for finished in finished_fishers {
+            let _ = finished_activities.insert(finished, FinishedActivity {});
         }
     }
 }
@@ -202,14 +228,28 @@ pub struct FishingMinigameUpdate;
 impl<'a> System<'a> for FishingMinigameUpdate {
     type SystemData = (
         WriteStorage<'a, FishingMinigame>,
+        WriteStorage<'a, FishStruggle>,
+        ReadStorage<'a, FishOnTheLine>,
         WriteStorage<'a, FinishedActivity>,
-        WriteStorage<'a, FishOnTheLine>,
+        WriteStorage<'a, Activity>,
         Write<'a, MessageLog>,
         Entities<'a>,
         Read<'a, DeltaTime>,
     );
 
-    fn run(&mut self, (mut minigames, mut finished_activities, mut hooks, mut log, entities, dt): Self::SystemData) {
+    fn run(
+        &mut self,
+        (
+            mut minigames,
+            mut struggles,
+            on_lines,
+            mut finished_activities,
+            mut activities,
+            mut log,
+            entities,
+            dt,
+        ): Self::SystemData,
+    ) {
         let mut remove_mes = vec![];
         for (fisher, minigame) in (&entities, &mut minigames).join() {
             let seconds_past = dt.0.as_millis() as f32 / 1000.0;
@@ -235,8 +275,14 @@ impl<'a> System<'a> for FishingMinigameUpdate {
             }
 
             if minigame.reel.catch_percent <= 0.0 {
-                let _ = finished_activities.insert(fisher, FinishedActivity {});
-                log.log("#[bright_green]Success!#[]");
+                // the fish makes one last fight for it before it can be landed
+                let rarity = on_lines
+                    .get(fisher)
+                    .and_then(|line| fish_info(line.fish))
+                    .map_or(Rarity::Common, |(_, rarity)| rarity);
+                log.log("#[orange]The fish is fighting back, reel it in!#[]");
+                let _ = struggles.insert(fisher, FishStruggle::new(rarity));
+                remove_mes.push(fisher);
                 continue;
             }
 
@@ -244,7 +290,9 @@ impl<'a> System<'a> for FishingMinigameUpdate {
 
             if minigame.reel.catch_percent >= 100.0 || minigame.attempts_left == 0 {
                 log.log("#[red]Ahhh, the fish got away.#[]");
-                hooks.remove(fisher);
+                if let Some(ActivityKind::Fishing { hooked, .. }) = activities.get_mut(fisher).map(|a| &mut a.kind) {
+                    *hooked = false;
+                }
                 remove_mes.push(fisher);
                 let _ = finished_activities.insert(fisher, FinishedActivity {});
             }
@@ -263,18 +311,17 @@ impl<'a> System<'a> for FishingMinigameCheck {
         WriteStorage<'a, FishingMinigame>,
         Write<'a, MessageLog>,
         ReadStorage<'a, FinishedActivity>,
-        ReadStorage<'a, FishOnTheLine>,
         Read<'a, PlayerEntity>,
         Entities<'a>,
     );
 
     fn run(
         &mut self,
-        (mut game_actions, mut minigames, mut log, finished_activities, hooks, p_entity, entities): Self::SystemData,
+        (mut game_actions, mut minigames, mut log, finished_activities, p_entity, entities): Self::SystemData,
     ) {
-        if let Some((_, _, _, game, ())) = (&entities, &game_actions, &hooks, &mut minigames, !&finished_activities)
+        if let Some((_, _, game, ())) = (&entities, &game_actions, &mut minigames, !&finished_activities)
             .join()
-            .find(|(e, _, _, _, _)| *e == p_entity.0)
+            .find(|(e, _, _, _)| *e == p_entity.0)
         {
             info!("Game action read, checking if in_pos");
             let hit_idx = game.cursor.bar_position();
@@ -293,32 +340,317 @@ impl<'a> System<'a> for FishingMinigameCheck {
     }
 }
 
+/// Caught when no fish in the raws can be caught
+const CAUGHT_FISH: ItemID = ItemID::new(3);
+/// Items with this tag can bite on the line
+const CATCHABLE_TAG: &str = "catchable";
+/// Time the fisher has to land a fish once it starts struggling
+const STRUGGLE_TIME: Duration = Duration::from_secs(3);
+
+impl FishStruggle {
+    /// Rarer fish are bigger and need more reeling to land
+    pub fn new(rarity: Rarity) -> Self {
+        let reels_needed = match rarity {
+            Rarity::Common => 3,
+            Rarity::Uncommon => 5,
+            Rarity::Rare => 8,
+        };
+        Self { reels_needed, reels: 0, time_left: STRUGGLE_TIME }
+    }
+}
+
+/// Picks which fish bit, common fish bite far more often than rare ones
+fn roll_fish(rng: &mut RandomNumberGenerator) -> ItemID {
+    let edb = ENTITY_DB.lock().unwrap();
+    let catchable: Vec<(ItemID, u32)> = edb
+        .items
+        .iter()
+        .filter(|item| item.tags.as_ref().is_some_and(|tags| tags.has(CATCHABLE_TAG)))
+        .map(|item| {
+            let weight = match item.rarity {
+                Rarity::Common => 6,
+                Rarity::Uncommon => 3,
+                Rarity::Rare => 1,
+            };
+            (item.identifier, weight)
+        })
+        .collect();
+    let total: u32 = catchable.iter().map(|(_, weight)| weight).sum();
+    if total == 0 {
+        return CAUGHT_FISH;
+    }
+    let mut roll = rng.range(0, total);
+    for (fish, weight) in catchable {
+        if roll < weight {
+            return fish;
+        }
+        roll -= weight;
+    }
+    CAUGHT_FISH
+}
+
+/// The name and rarity of a fish in the raws
+fn fish_info(fish: ItemID) -> Option<(String, Rarity)> {
+    ENTITY_DB.lock().unwrap().items.get_by_id(fish).map(|info| (info.name.clone(), info.rarity))
+}
+
+/// Counts the reels made against a struggling fish, it is landed with enough of them and escapes when time runs out
+pub struct FishStruggleSystem;
+
+impl<'a> System<'a> for FishStruggleSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, FishStruggle>,
+        ReadStorage<'a, GameAction>,
+        WriteStorage<'a, FishOnTheLine>,
+        WriteStorage<'a, Activity>,
+        WriteStorage<'a, FinishedActivity>,
+        WriteStorage<'a, CatchChoice>,
+        Write<'a, MessageLog>,
+        Read<'a, DeltaTime>,
+        Read<'a, PlayerEntity>,
+        Read<'a, CatchRelease>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut struggles,
+            game_actions,
+            mut on_lines,
+            mut activities,
+            mut finished_activities,
+            mut choices,
+            mut log,
+            dt,
+            player_entity,
+            catch_release,
+        ): Self::SystemData,
+    ) {
+        let mut remove_mes = vec![];
+        let mut choosers = vec![];
+        for (fisher, struggle, ()) in (&entities, &mut struggles, !&finished_activities).join() {
+            let fish_name =
+                on_lines.get(fisher).and_then(|line| fish_info(line.fish)).map_or("fish".to_string(), |(name, _)| name);
+            if game_actions.contains(fisher) {
+                struggle.reels += 1;
+            }
+            if struggle.reels >= struggle.reels_needed {
+                info!("{} was landed with {} reels", fish_name, struggle.reels);
+                log.log(format!("#[bright_green]Success!#[] The {} stopped struggling.", fish_name));
+                // the player decides what happens to the fish before their fishing is done
+                if fisher == player_entity.0 {
+                    log.log(format!("Keep the {} or let it go?", fish_name));
+                    choosers.push(fisher);
+                } else {
+                    remove_mes.push(fisher);
+                }
+                continue;
+            }
+
+            struggle.time_left = struggle.time_left.saturating_sub(dt.0);
+            if struggle.time_left.is_zero() {
+                info!("{} escaped after {}/{} reels", fish_name, struggle.reels, struggle.reels_needed);
+                log.log(format!("#[red]The {} thrashed free and got away.#[]", fish_name));
+                if let Some(ActivityKind::Fishing { hooked, .. }) = activities.get_mut(fisher).map(|a| &mut a.kind) {
+                    *hooked = false;
+                }
+                on_lines.remove(fisher);
+                remove_mes.push(fisher);
+            }
+        }
+        for fisher in remove_mes {
+            struggles.remove(fisher);
+            let _ = finished_activities.insert(fisher, FinishedActivity {});
+        }
+        for fisher in choosers {
+            struggles.remove(fisher);
+            let _ = choices.insert(fisher, CatchChoice::new(catch_release.choice_time()));
+        }
+    }
+}
+
 pub struct CatchFishSystem;
 
 impl<'a> System<'a> for CatchFishSystem {
     type SystemData = (
         Entities<'a>,
-        WriteStorage<'a, FishOnTheLine>,
+        WriteStorage<'a, Activity>,
         WriteStorage<'a, FishingMinigame>,
+        WriteStorage<'a, FishOnTheLine>,
         WriteExpect<'a, ItemSpawner>,
         WriteExpect<'a, MessageLog>,
         ReadStorage<'a, FinishedActivity>,
         ReadStorage<'a, Name>,
+        WriteStorage<'a, BaitActive>,
+        Write<'a, Toasts>,
+        Read<'a, PlayerEntity>,
+        WriteStorage<'a, FishingSpot>,
+        Write<'a, FishSchools>,
+        WriteStorage<'a, Fishable>,
+        WriteStorage<'a, Renderable>,
+        WriteStorage<'a, FishStruggle>,
+        WriteStorage<'a, CatchChoice>,
+        Read<'a, CatchRelease>,
+        WriteStorage<'a, Currency>,
+        ReadStorage<'a, Container>,
+        ReadStorage<'a, Item>,
+        ReadStorage<'a, InBag>,
     );
 
     fn run(
         &mut self,
-        (entities, mut hooks, mut minigames, mut item_spawner, mut log, finished_activities, names): Self::SystemData,
+        (
+            entities,
+            mut activities,
+            mut minigames,
+            mut on_lines,
+            mut item_spawner,
+            mut log,
+            finished_activities,
+            names,
+            mut baits,
+            mut toasts,
+            player_entity,
+            mut spots,
+            mut schools,
+            mut fishables,
+            mut renderables,
+            mut struggles,
+            mut choices,
+            catch_release,
+            mut currencies,
+            containers,
+            items,
+            in_bags,
+        ): Self::SystemData,
     ) {
         let mut remove_mes = Vec::new();
-        for (e, _, name, _) in (&entities, &hooks, &names, &finished_activities).join() {
+        for (e, activity, name, _) in (&entities, &mut activities, &names, &finished_activities).join() {
+            let hooked = match &mut activity.kind {
+                ActivityKind::Fishing { hooked, .. } => hooked,
+                _ => continue,
+            };
+            let fish = on_lines.remove(e).map_or(CAUGHT_FISH, |line| line.fish);
+            let spot = spots.remove(e).map(|spot| spot.pos);
+            if !*hooked {
+                // a canceled activity can end in the middle of the minigame
+                minigames.remove(e);
+                struggles.remove(e);
+                choices.remove(e);
+                continue;
+            }
+            // Unhooking keeps the fish from being caught again while the activity is still finishing
+            *hooked = false;
             remove_mes.push((e, name));
-            log.enhance(format!("{} caught a really big fish!", name));
-            item_spawner.request(ItemID(3), SpawnType::InBag(e));
+            let fish_name = fish_info(fish).map_or("fish".to_string(), |(fish_name, _)| fish_name);
+            // only the player is asked, anyone else keeps what they catch
+            let mut decision = choices.remove(e).map_or(CatchDecision::Keep, |choice| choice.decided());
+            if decision == CatchDecision::Keep && !bag_has_room(e, fish, &containers, &items, &in_bags) {
+                log.log(format!("{}'s backpack is full so the {} has to go back.", name, fish_name));
+                decision = CatchDecision::Release;
+            }
+            match decision {
+                CatchDecision::Keep => {
+                    log.enhance(format!("{} caught a {}!", name, fish_name));
+                    item_spawner.request(fish, SpawnType::InBag(e));
+                    if e == player_entity.0 {
+                        toasts.gained(fish, &fish_name, ItemQty(1));
+                    }
+                }
+                CatchDecision::Release => {
+                    log.log(format!("{} let the {} go and it swims away.", name, fish_name));
+                    let reward = catch_release.release_gold();
+                    if reward > 0 {
+                        let gold = currencies.get(e).map_or(0, |purse| purse.gold);
+                        let _ = currencies.insert(e, Currency { gold: gold + reward });
+                        log.log(format!("{} earned {} gold for the release.", name, reward));
+                    }
+                }
+            }
+            // a school only has so many fish in it before the rest scatter
+            if let Some(tiles) = spot.and_then(|pos| schools.catch_at(&pos)) {
+                for tile in tiles {
+                    fishables.remove(tile);
+                    renderables.remove(tile);
+                }
+                log.log("The rest of the school of fish scatters.");
+            }
         }
-        for (entity, _) in remove_mes.iter() {
-            hooks.remove(*entity);
+        for (entity, name) in remove_mes.iter() {
             minigames.remove(*entity);
+            if baits.remove(*entity).is_some() {
+                log.log(format!("The fish ate {}'s bait.", name));
+            }
+        }
+    }
+}
+
+/// Clears the fishing state of anyone who stopped fishing without the catch systems cleaning up after them, ie. the
+/// activity was ended some other way or they walked away from their spot. A fish still on the line gets away
+pub struct AbandonedFishingCleanup;
+
+impl<'a> System<'a> for AbandonedFishingCleanup {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Position>,
+        WriteStorage<'a, Activity>,
+        WriteStorage<'a, FishingSpot>,
+        WriteStorage<'a, FishOnTheLine>,
+        WriteStorage<'a, FishingMinigame>,
+        WriteStorage<'a, FishStruggle>,
+        WriteStorage<'a, FinishedActivity>,
+        Read<'a, PlayerEntity>,
+        Write<'a, MessageLog>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            positions,
+            mut activities,
+            mut spots,
+            mut on_lines,
+            mut minigames,
+            mut struggles,
+            mut finished_activities,
+            player_entity,
+            mut log,
+        ): Self::SystemData,
+    ) {
+        let fishers: Vec<_> = (&entities, (&spots).maybe(), (&on_lines).maybe())
+            .join()
+            .filter(|(_, spot, on_line)| spot.is_some() || on_line.is_some())
+            .map(|(e, ..)| e)
+            .collect();
+        for fisher in fishers {
+            let at_spot = match (positions.get(fisher), spots.get(fisher)) {
+                (Some(pos), Some(spot)) => pos.x.abs_diff(spot.pos.x) <= 1 && pos.y.abs_diff(spot.pos.y) <= 1,
+                _ => false,
+            };
+            let fishing = activities.get_mut(fisher).and_then(|activity| match &mut activity.kind {
+                ActivityKind::Fishing { hooked, .. } => Some(hooked),
+                _ => None,
+            });
+            match fishing {
+                Some(_) if at_spot => continue,
+                Some(hooked) => {
+                    // unhooked so finishing the activity does not land the fish
+                    *hooked = false;
+                    let _ = finished_activities.insert(fisher, FinishedActivity {});
+                    if fisher == player_entity.0 {
+                        log.log("Your line goes slack as you walk away from the water.");
+                    }
+                }
+                None => {}
+            }
+            debug!("Cleared the abandoned fishing state of entity {}", fisher.id());
+            spots.remove(fisher);
+            on_lines.remove(fisher);
+            minigames.remove(fisher);
+            struggles.remove(fisher);
         }
     }
 }
@@ -366,3 +698,105 @@ impl<'a> System<'a> for PollFishingTiles {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use specs::{Builder, Entity};
+
+    use super::*;
+    use crate::test_harness::TestWorld;
+
+    fn hooked_fisher(world: &mut TestWorld, reels_needed: u32) -> Entity {
+        let fishing = ActivityKind::Fishing { attempts_left: 1, hooked: true };
+        world
+            .spawn("Fisher", Position::new(1, 1))
+            .with(Activity::new(fishing, FISH_DELAY_TIME))
+            .with(FishOnTheLine { fish: CAUGHT_FISH })
+            .with(FishStruggle { reels_needed, reels: 0, time_left: STRUGGLE_TIME })
+            .build()
+    }
+
+    #[test]
+    fn enough_reels_land_a_struggling_fish() {
+        let mut world = TestWorld::new();
+        let fisher = hooked_fisher(&mut world, 2);
+        for _ in 0..2 {
+            world.insert(fisher, GameAction {});
+            world.run(FishStruggleSystem);
+            world.ecs.write_storage::<GameAction>().clear();
+        }
+        assert!(world.has::<FinishedActivity>(fisher));
+        assert!(!world.has::<FishStruggle>(fisher));
+        assert!(world.has::<FishOnTheLine>(fisher));
+    }
+
+    #[test]
+    fn struggling_fish_escape_when_time_runs_out() {
+        let mut world = TestWorld::new().with_delta(STRUGGLE_TIME);
+        let fisher = hooked_fisher(&mut world, 2);
+        world.run(FishStruggleSystem);
+        assert!(world.has::<FinishedActivity>(fisher));
+        assert!(!world.has::<FishOnTheLine>(fisher));
+        let activities = world.ecs.read_storage::<Activity>();
+        assert!(matches!(activities.get(fisher).unwrap().kind, ActivityKind::Fishing { hooked: false, .. }));
+    }
+
+    #[test]
+    fn walking_away_mid_fish_clears_the_fishing_state() {
+        let mut world = TestWorld::new();
+        let player = world.player();
+        let fishing = ActivityKind::Fishing { attempts_left: 1, hooked: true };
+        world.insert(player, Position::new(1, 1));
+        world.insert(player, Activity::new(fishing, FISH_DELAY_TIME));
+        world.insert(player, FishingSpot { pos: Position::new(2, 1) });
+        world.insert(player, FishOnTheLine { fish: CAUGHT_FISH });
+        world.run(AbandonedFishingCleanup);
+        assert!(world.has::<FishOnTheLine>(player));
+
+        world.insert(player, Position::new(5, 1));
+        world.run(AbandonedFishingCleanup);
+        assert!(!world.has::<FishOnTheLine>(player));
+        assert!(!world.has::<FishingSpot>(player));
+        assert!(world.has::<FinishedActivity>(player));
+        assert!(world.logged("line goes slack"));
+        let activities = world.ecs.read_storage::<Activity>();
+        assert!(matches!(activities.get(player).unwrap().kind, ActivityKind::Fishing { hooked: false, .. }));
+    }
+
+    #[test]
+    fn fishing_state_left_without_an_activity_is_cleared() {
+        let mut world = TestWorld::new();
+        let fisher = world.spawn("Fisher", Position::new(1, 1)).with(FishOnTheLine { fish: CAUGHT_FISH }).build();
+        world.run(AbandonedFishingCleanup);
+        assert!(!world.has::<FishOnTheLine>(fisher));
+        assert!(!world.has::<FinishedActivity>(fisher));
+    }
+
+    #[test]
+    fn landed_fish_that_wont_fit_are_released_for_gold() {
+        let mut world = TestWorld::new();
+        let player = world.player();
+        let fishing = ActivityKind::Fishing { attempts_left: 1, hooked: true };
+        world.insert(player, Activity::new(fishing, FISH_DELAY_TIME));
+        world.insert(player, FishOnTheLine { fish: CAUGHT_FISH });
+        world.insert(player, FinishedActivity {});
+        world.insert(player, Container { slots: 0 });
+        world.insert(player, Currency { gold: 5 });
+        let mut choice = CatchChoice::new(STRUGGLE_TIME);
+        choice.decision = Some(CatchDecision::Keep);
+        world.insert(player, choice);
+
+        world.run(CatchFishSystem);
+        assert!(world.logged("backpack is full"));
+        assert!(world.logged("swims away"));
+        let reward = CatchRelease::default().release_gold();
+        assert_eq!(world.ecs.read_storage::<Currency>().get(player).map(|purse| purse.gold), Some(5 + reward));
+        assert!(!world.has::<CatchChoice>(player));
+    }
+
+    #[test]
+    fn rarer_fish_need_more_reels() {
+        assert!(FishStruggle::new(Rarity::Common).reels_needed < FishStruggle::new(Rarity::Uncommon).reels_needed);
+        assert!(FishStruggle::new(Rarity::Uncommon).reels_needed < FishStruggle::new(Rarity::Rare).reels_needed);
+    }
+}
@@ -1,9 +1,9 @@
 use bracket_lib::{
     pathfinding::{field_of_view_set, Algorithm2D, BaseMap},
     prelude::{Point, SmallVec},
-    terminal::{to_char, ColorPair, DistanceAlg, DrawBatch},
+    terminal::{to_char, ColorPair, DistanceAlg, DrawBatch, BLACK},
 };
-use specs::{Join, Read, ReadStorage, System, World, WorldExt, WriteStorage};
+use specs::{Entities, Join, Read, ReadStorage, System, World, WorldExt, Write, WriteStorage};
 
 use crate::{
     camera::get_camera_bounds,
@@ -11,22 +11,62 @@ use crate::{
     components::{Position, Viewshed},
     game_init::PlayerEntity,
     indexing::idx_to_point,
+    layers::MapLayers,
     map::{Map, MapRes},
+    weather::{is_outdoors, Weather},
     CL_EFFECTS,
 };
 
+pub const DEFAULT_VIEW_RANGE: usize = 16;
+
+/// Recalculates the viewsheds that have been marked dirty, the player's view also reveals tiles on the map
 pub struct UpdateViewsheds;
 
 impl<'a> System<'a> for UpdateViewsheds {
-    type SystemData = (WriteStorage<'a, Viewshed>, ReadStorage<'a, Position>, Read<'a, MapRes>);
-
-    fn run(&mut self, (mut viewsheds, positions, map): Self::SystemData) {
-        for (view, pos) in (&mut viewsheds, &positions).join() {
-            view.tiles = field_of_view_set(pos.to_point(), view.range as i32, &map.0);
+    type SystemData = (
+        WriteStorage<'a, Viewshed>,
+        ReadStorage<'a, Position>,
+        Write<'a, MapRes>,
+        Read<'a, PlayerEntity>,
+        Entities<'a>,
+        Read<'a, Weather>,
+        Read<'a, MapLayers>,
+    );
+
+    fn run(&mut self, (mut viewsheds, positions, mut map, player_e, entities, weather, layers): Self::SystemData) {
+        let outdoors = is_outdoors(&layers);
+        for (entity, view, pos) in (&entities, &mut viewsheds, &positions).join().filter(|(_, view, _)| view.dirty) {
+            let range = if outdoors { weather.view_range(view.range) } else { view.range };
+            view.tiles = field_of_view_set(pos.to_point(), range as i32, &map.0);
+            view.dirty = false;
+
+            if entity != player_e.0 {
+                continue;
+            }
+            let tile_count = map.0.width * map.0.height;
+            if map.0.revealed_tiles.len() != tile_count {
+                map.0.revealed_tiles.resize(tile_count, false);
+            }
+            for point in view.tiles.iter() {
+                let idx = map.0.point2d_to_index(*point);
+                map.0.revealed_tiles[idx] = true;
+            }
         }
     }
 }
 
+/// Sets the player's view range and marks every viewshed to be recalculated, used when a world is started or loaded
+pub fn refresh_viewsheds(ecs: &World, player_view_range: usize) {
+    let player_e = ecs.read_resource::<PlayerEntity>();
+    let mut viewsheds = ecs.write_storage::<Viewshed>();
+    if let Some(player_view) = viewsheds.get_mut(player_e.0) {
+        player_view.range = player_view_range;
+    }
+    for view in (&mut viewsheds).join() {
+        view.dirty = true;
+    }
+}
+
 pub fn draw_unseen_area(draw_batch: &mut DrawBatch, ecs: &World) {
     let player_e = ecs.read_resource::<PlayerEntity>();
     let viewsheds = ecs.read_storage::<Viewshed>();
@@ -35,6 +75,8 @@ pub fn draw_unseen_area(draw_batch: &mut DrawBatch, ecs: &World) {
         None => return,
     };
 
+    let map = ecs.fetch::<MapRes>();
+
     draw_batch.target(CL_EFFECTS);
     let bounding_box = get_camera_bounds(ecs);
     for x in bounding_box.x1..bounding_box.x2 {
@@ -43,9 +85,14 @@ pub fn draw_unseen_area(draw_batch: &mut DrawBatch, ecs: &World) {
                 continue;
             }
 
-            let screen_x = x - bounding_box.x1;
-            let screen_y = y - bounding_box.y1;
-            draw_batch.set(Point::new(screen_x, screen_y), ColorPair::new(WHITE, DARKBLUE), to_char(34));
+            let screen_pos = Point::new(x - bounding_box.x1, y - bounding_box.y1);
+            if map.0.is_revealed(Point { x, y }) {
+                // Remembered tiles are dimmed
+                draw_batch.set(screen_pos, ColorPair::new(WHITE, DARKBLUE), to_char(34));
+            } else if map.0.in_bounds(Point { x, y }) {
+                // Never seen tiles are completely hidden, including anything on them
+                draw_batch.set(screen_pos, ColorPair::new(BLACK, BLACK), to_char(219));
+            }
         }
     }
 }
@@ -58,9 +105,12 @@ impl BaseMap for Map {
     }
 
     fn get_available_exits(&self, starting_idx: usize) -> SmallVec<[(usize, f32); 10]> {
-        let Point { x, y } = idx_to_point(starting_idx, self.width);
-
         let mut exits = SmallVec::new();
+        let Point { x, y } = match self.checked_idx_to_point(starting_idx) {
+            Some(point) => point,
+            None => return exits,
+        };
+
         let valid_directions = [(0, 1), (1, 0), (0, -1), (-1, 0)];
         for &(dx, dy) in &valid_directions {
             let new_x = x + dx;
@@ -1,27 +1,36 @@
-use std::collections::HashSet;
-
-use bracket_lib::terminal::{BTerm, VirtualKeyCode};
-use log::debug;
+use bracket_lib::terminal::{BTerm, Point, VirtualKeyCode};
+use log::{debug, error, info};
 use specs::{
     saveload::{MarkedBuilder, SimpleMarker},
-    Builder, Entity, World, WorldExt,
+    Builder, Entity, Join, World, WorldExt,
 };
 
 pub const WHITE: (u8, u8, u8) = (255, 255, 255);
 
 use crate::{
+    combat::DEFAULT_ATTACK_COOLDOWN,
     components::{
-        EquipmentSlots, Interactor, InteractorMode, LevelPersistent, Name, Position, Renderable, Transform, Viewshed,
+        ActionPoints, AttackCooldown, Blocking, Breakable, Currency, EntityStats, EquipmentSlots, Facing, Hotbar,
+        Interactor, InteractorMode, KillCount, LevelPersistent, Monster, Name, Position, Renderable, Transform,
+        Viewshed,
     },
-    data_read::prelude::build_being,
+    data_read::prelude::{build_being, build_world_obj, BeingStart, DebugWorld, NewGameConfig, ENTITY_DB},
+    difficulty::Difficulty,
+    fov::DEFAULT_VIEW_RANGE,
     get_text,
-    items::{ItemID, ItemSpawner, SpawnType},
-    map::MapRes,
-    map_gen::{gen_world, WorldConfig},
+    hunger::Metabolism,
+    items::{ItemSpawner, SpawnType},
+    layers::MapLayers,
+    lighting::PLAYER_LIGHT,
+    map::{Map, MapRes},
+    map_gen::{gen_caves, gen_world, place_terrain, place_water, CaveConfig, MapGenerator, WorldConfig},
     player::Player,
+    rng::GameRng,
     saveload::{SerializeMe, SAVE_EXTENSION},
     saveload_menu::LoadedWorld,
-    stats::get_random_stats,
+    speed::NORMAL_SPEED,
+    tilesets::{tileset_font, TerrainFonts},
+    triggers::TriggerWatch,
     z_order::PLAYER_Z,
     CL_WORLD,
 };
@@ -36,23 +45,52 @@ impl Default for PlayerEntity {
 }
 
 pub fn initialize_new_game_world(ecs: &mut World, world_config: &WorldConfig) {
+    let (new_game, debug_world) = {
+        let edb = ENTITY_DB.lock().unwrap();
+        let mut errors = Vec::new();
+        let new_game = NewGameConfig::load(&edb, &mut errors);
+        let debug_world =
+            if cfg!(feature = "dev") { DebugWorld::load(&edb, &mut errors) } else { DebugWorld::default() };
+        for raws_error in errors.iter() {
+            error!("{}", raws_error);
+        }
+        (new_game, debug_world)
+    };
+
+    ecs.write_resource::<Difficulty>().level = world_config.difficulty;
+
     debug!("startup: map loading");
-    let new_chunk = gen_world(ecs, world_config);
+    let (mut new_chunk, seed, spawn) = generate_surface(ecs, world_config, &new_game);
+    for (pos, terrain) in new_game.terrain.iter().chain(debug_world.terrain.iter()) {
+        place_terrain(&mut new_chunk, ecs, *pos, terrain);
+    }
+    for pos in new_game.water.iter() {
+        place_water(&mut new_chunk, ecs, *pos);
+    }
     ecs.insert(MapRes(new_chunk));
+    ecs.insert(MapLayers::new(world_config.layers, seed));
     debug!("startup: map loaded");
 
-    let mut player_stats = get_random_stats();
-    player_stats.set.vitality = 25;
-    player_stats.set.strength = 2;
+    let player_pos = spawn.unwrap_or_else(|| player_start_position(ecs, &new_game));
+    let player_stats = EntityStats::from(new_game.player.stats);
+    let hunger = ecs.read_resource::<Metabolism>().full();
     let player_entity = ecs
         .create_entity()
-        .with(Position::new(67, 30))
+        .with(player_pos)
         .with(Interactor::new(InteractorMode::Reactive))
+        .with(Facing::default())
         .with(Player {})
-        .with(Viewshed { tiles: HashSet::new(), range: 16 })
+        .with(Viewshed::new(DEFAULT_VIEW_RANGE))
         .with(EquipmentSlots::human())
+        .with(Currency { gold: new_game.player.gold })
+        .with(KillCount::default())
+        .with(Hotbar::default())
+        .with(PLAYER_LIGHT)
+        .with(ActionPoints::new(NORMAL_SPEED))
+        .with(AttackCooldown::new(DEFAULT_ATTACK_COOLDOWN))
         .with(player_stats)
         .with(player_stats.set.get_health_stats())
+        .with(hunger)
         .with(Renderable::clear_bg(2, WHITE, PLAYER_Z))
         .with(Name("Player".to_string()))
         .with(LevelPersistent {})
@@ -63,25 +101,140 @@ pub fn initialize_new_game_world(ecs: &mut World, world_config: &WorldConfig) {
 
     {
         let mut item_spawner = ecs.write_resource::<ItemSpawner>();
-        item_spawner.request(ItemID(201), SpawnType::InBag(player_entity));
+        for (id, qty) in new_game.inventory.iter() {
+            item_spawner.request_amt(*id, SpawnType::InBag(player_entity), *qty);
+        }
+        for (id, qty, pos) in new_game.ground_items.iter() {
+            item_spawner.request_amt(*id, SpawnType::OnGround(*pos), *qty);
+        }
     }
 
-    build_being("Bahhhby", Position::new(5, 15), ecs).ok();
-    let greg = build_being("Greg Goat", Position::new(12, 19), ecs).unwrap();
-    let mut transforms = ecs.write_storage::<Transform>();
-    let _ = transforms.insert(greg, Transform::new(12.0, 19.0, 0.0, 1.0, 1.0));
-    debug!("startup: sample beings loaded");
+    for start in new_game.beings.iter() {
+        let being = match build_being(&start.name, start.position, ecs) {
+            Ok(being) => being,
+            Err(e) => {
+                error!("Starting being failed to build: {}", e);
+                continue;
+            }
+        };
+        if start.fancy {
+            let (x, y) = (start.position.x as f32, start.position.y as f32);
+            let mut transforms = ecs.write_storage::<Transform>();
+            let _ = transforms.insert(being, Transform::new(x, y, 0.0, 1.0, 1.0));
+        }
+        if ecs.read_storage::<Monster>().contains(being) {
+            scale_monster_spawn(ecs, being, start);
+        }
+    }
+    for (name, pos) in new_game.world_objs.iter() {
+        if let Err(e) = build_world_obj(name, *pos, ecs) {
+            error!("Starting world object failed to build: {}", e);
+        }
+    }
+    debug_world_setup(ecs, &debug_world);
+    for (pos, trigger) in new_game.triggers.iter() {
+        ecs.create_entity().with(*pos).with(trigger.clone()).marked::<SimpleMarker<SerializeMe>>().build();
+    }
+    ecs.write_resource::<TriggerWatch>().reset(Some(player_pos));
+    debug!("startup: starting beings loaded");
 
     let mut lw = ecs.write_resource::<LoadedWorld>();
     lw.file_name = Some(format!("{}.{}", world_config.world_name.clone(), SAVE_EXTENSION));
 }
 
-/// Updates the CL_WORLD layer's font to match the active map's tile atlas
+/// Builds the dev test scene's world objects and beings, its terrain is placed with the rest of the map
+fn debug_world_setup(ecs: &mut World, debug_world: &DebugWorld) {
+    for (name, pos, tool) in debug_world.world_objs.iter() {
+        let obj = match build_world_obj(name, *pos, ecs) {
+            Ok(obj) => obj,
+            Err(e) => {
+                error!("Debug world object failed to build: {}", e);
+                continue;
+            }
+        };
+        if let Some(tool) = tool {
+            let _ = ecs.write_storage::<Breakable>().insert(obj, Breakable::new(tool.clone()));
+        }
+    }
+    for (name, pos) in debug_world.beings.iter() {
+        if let Err(e) = build_being(name, *pos, ecs) {
+            error!("Debug world being failed to build: {}", e);
+        }
+    }
+}
+
+/// Spawns as many of a starting monster as the difficulty asks for, the copies go on open tiles around it. When the
+/// difficulty wants none of it the monster is taken back out
+fn scale_monster_spawn(ecs: &mut World, monster: Entity, start: &BeingStart) {
+    let count = {
+        let mut rng = ecs.write_resource::<GameRng>();
+        ecs.read_resource::<Difficulty>().spawn_count(&mut rng.0)
+    };
+    if count == 0 {
+        let _ = ecs.delete_entity(monster);
+        return;
+    }
+    for _ in 1..count {
+        let pos = match open_tile_around(ecs, start.position) {
+            Some(pos) => pos,
+            None => return,
+        };
+        if let Err(e) = build_being(&start.name, pos, ecs) {
+            error!("Extra {} failed to build: {}", start.name, e);
+            return;
+        }
+    }
+}
+
+/// A tile next to `pos` that is on the map and has nothing blocking on it
+pub fn open_tile_around(ecs: &World, pos: Position) -> Option<Position> {
+    let map = ecs.read_resource::<MapRes>();
+    let blocked: Vec<Position> =
+        (&ecs.read_storage::<Blocking>(), &ecs.read_storage::<Position>()).join().map(|(_, pos)| *pos).collect();
+    (-1..=1)
+        .flat_map(|dx| (-1..=1).map(move |dy| pos.to_point() + Point::new(dx, dy)))
+        .filter(|point| map.0.in_bounds(*point))
+        .map(Position::from)
+        .find(|spot| *spot != pos && !blocked.contains(spot))
+}
+
+/// Makes the surface with the world's generator, returning it with the seed it was made from and where the player
+/// should start when the generator picked somewhere
+fn generate_surface(ecs: &mut World, wc: &WorldConfig, new_game: &NewGameConfig) -> (Map, u64, Option<Position>) {
+    match wc.generator {
+        MapGenerator::Terrain => (gen_world(ecs, wc), wc.seed, None),
+        MapGenerator::Caves => {
+            // no seed was typed in so one is rolled, otherwise every unseeded cave world would be the same
+            let seed = if wc.seed == 0 { ecs.write_resource::<GameRng>().0.rand::<u64>() } else { wc.seed };
+            let spawn = new_game.player.position.unwrap_or(Position::new(wc.width / 2, wc.height / 2));
+            let keep_open: Vec<Position> = new_game
+                .beings
+                .iter()
+                .map(|being| being.position)
+                .chain(new_game.world_objs.iter().map(|(_, pos)| *pos))
+                .collect();
+            let caves = gen_caves(ecs, &CaveConfig::new(wc.width, wc.height, seed), spawn, &keep_open);
+            info!("Caves were generated from seed {}", caves.seed);
+            (caves.map, caves.seed, Some(caves.spawn))
+        }
+    }
+}
+
+/// Where the player starts a new game and wakes up after dying, the middle of the map when the opening doesn't say
+pub fn player_start_position(ecs: &World, new_game: &NewGameConfig) -> Position {
+    new_game.player.position.unwrap_or_else(|| {
+        let map = ecs.read_resource::<MapRes>();
+        Position::new(map.0.width / 2, map.0.height / 2)
+    })
+}
+
+/// Updates the CL_WORLD layer's font to match the active map's tileset, or the terrain skin replacing it
 pub fn set_level_font(ecs: &World, ctx: &mut BTerm) {
     let map = ecs.read_resource::<MapRes>();
+    let font = ecs.read_resource::<TerrainFonts>().font_for(tileset_font(&map.0.tileset));
     ctx.set_active_console(CL_WORLD);
-    ctx.set_active_font(map.0.tile_atlas_index, false);
-    debug!("Level font changed to index {}", map.0.tile_atlas_index);
+    ctx.set_active_font(font, false);
+    debug!("Level font changed to index {}", font);
 }
 
 #[derive(PartialEq, Eq, Clone)]
@@ -90,6 +243,9 @@ pub enum NewGameMenuSelection {
     Width,
     Height,
     Seed,
+    Layers,
+    Generator,
+    Difficulty,
     Finalize,
 }
 
@@ -99,7 +255,10 @@ impl NewGameMenuSelection {
             NewGameMenuSelection::WorldName => NewGameMenuSelection::Width,
             NewGameMenuSelection::Width => NewGameMenuSelection::Height,
             NewGameMenuSelection::Height => NewGameMenuSelection::Seed,
-            NewGameMenuSelection::Seed => NewGameMenuSelection::Finalize,
+            NewGameMenuSelection::Seed => NewGameMenuSelection::Layers,
+            NewGameMenuSelection::Layers => NewGameMenuSelection::Generator,
+            NewGameMenuSelection::Generator => NewGameMenuSelection::Difficulty,
+            NewGameMenuSelection::Difficulty => NewGameMenuSelection::Finalize,
             NewGameMenuSelection::Finalize => NewGameMenuSelection::WorldName,
         }
     }
@@ -110,7 +269,10 @@ impl NewGameMenuSelection {
             NewGameMenuSelection::Width => NewGameMenuSelection::WorldName,
             NewGameMenuSelection::Height => NewGameMenuSelection::Width,
             NewGameMenuSelection::Seed => NewGameMenuSelection::Height,
-            NewGameMenuSelection::Finalize => NewGameMenuSelection::Seed,
+            NewGameMenuSelection::Layers => NewGameMenuSelection::Seed,
+            NewGameMenuSelection::Generator => NewGameMenuSelection::Layers,
+            NewGameMenuSelection::Difficulty => NewGameMenuSelection::Generator,
+            NewGameMenuSelection::Finalize => NewGameMenuSelection::Difficulty,
         }
     }
 }
@@ -132,6 +294,9 @@ pub struct InputWorldConfig {
     pub height: String,
     pub sea_level: String,
     pub seed: String,
+    pub layers: String,
+    pub generator: MapGenerator,
+    pub difficulty: DifficultyLevel,
 }
 
 impl Default for InputWorldConfig {
@@ -142,6 +307,9 @@ impl Default for InputWorldConfig {
             height: "100".to_string(),
             sea_level: "33".to_string(),
             seed: String::new(),
+            layers: "1".to_string(),
+            generator: MapGenerator::default(),
+            difficulty: DifficultyLevel::default(),
         }
     }
 }
@@ -0,0 +1,122 @@
+/* hazards.rs
+ *   Tiles like lava or poisoned water that hurt any being ending its turn on them. The hazard comes from the tile's
+ *   kind in `tile_kinds.json5`, a being tagged with the hazard's immunity, or wearing something tagged with it, is
+ *   left unharmed.
+ * */
+
+use std::collections::HashMap;
+
+use specs::{Entities, Entity, Join, Read, ReadStorage, System, World, WorldExt, Write, WriteStorage};
+
+use crate::{
+    being::BeingID,
+    components::{Equipped, Hazard, Position, SufferDamage, Tags},
+    map::MapRes,
+    player::Player,
+    ui::message_log::MessageLog,
+};
+
+/// Whether `entity` or anything it has equipped carries the tag that shrugs off the hazard
+pub fn is_immune(entity: Entity, hazard: &Hazard, tags: &ReadStorage<Tags>, equipped: &ReadStorage<Equipped>) -> bool {
+    let immunity = match &hazard.immunity {
+        Some(immunity) => immunity,
+        None => return false,
+    };
+    tags.get(entity).is_some_and(|tags| tags.has(immunity))
+        || (equipped, tags).join().any(|(equipped, tags)| equipped.on == entity && tags.has(immunity))
+}
+
+/// The hazard at `pos` that would hurt `entity` there, `None` when the tile is safe for it
+pub fn hazard_for(ecs: &World, entity: Entity, pos: Position) -> Option<Hazard> {
+    let hazards = ecs.read_storage::<Hazard>();
+    let (tags, equipped) = (ecs.read_storage::<Tags>(), ecs.read_storage::<Equipped>());
+    (&hazards, &ecs.read_storage::<Position>())
+        .join()
+        .find(|(hazard, hazard_pos)| **hazard_pos == pos && !is_immune(entity, hazard, &tags, &equipped))
+        .map(|(hazard, _)| hazard.clone())
+}
+
+/// Hurts every being standing on a hazard at the end of the turn, the player is told what hurt them
+pub struct HazardSystem;
+
+impl<'a> System<'a> for HazardSystem {
+    type SystemData = (
+        ReadStorage<'a, Hazard>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, BeingID>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Tags>,
+        ReadStorage<'a, Equipped>,
+        WriteStorage<'a, SufferDamage>,
+        Read<'a, MapRes>,
+        Write<'a, MessageLog>,
+        Entities<'a>,
+    );
+
+    fn run(
+        &mut self,
+        (hazards, positions, beings, players, tags, equipped, mut damage, map, mut log, entities): Self::SystemData,
+    ) {
+        let hazard_tiles: HashMap<Position, &Hazard> =
+            (&hazards, &positions).join().map(|(hazard, pos)| (*pos, hazard)).collect();
+        if hazard_tiles.is_empty() {
+            return;
+        }
+
+        for (entity, pos) in (&entities, &positions).join() {
+            if !beings.contains(entity) && !players.contains(entity) {
+                continue;
+            }
+            let hazard = match hazard_tiles.get(pos) {
+                Some(hazard) if hazard.damage > 0 => hazard,
+                _ => continue,
+            };
+            if is_immune(entity, hazard, &tags, &equipped) {
+                continue;
+            }
+            SufferDamage::new_damage(&mut damage, entity, None, -(hazard.damage as i32));
+            if players.contains(entity) {
+                let tile = match map.0.checked_xy_to_idx(pos.x, pos.y) {
+                    Some(idx) => map.0.tiles[idx].name.to_lowercase(),
+                    None => "ground".to_string(),
+                };
+                log.log(format!("The {} hurts you for {}, get out of it!", tile, hazard.damage));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::{Builder, WorldExt};
+
+    use super::*;
+    use crate::test_harness::TestWorld;
+
+    fn lava() -> Hazard {
+        Hazard { damage: 3, immunity: Some("fireproof".to_string()) }
+    }
+
+    #[test]
+    fn hazards_hurt_beings_on_them_unless_they_are_immune() {
+        let mut world = TestWorld::new();
+        let player = world.player();
+        let pos = Position::new(4, 4);
+        world.insert(player, pos);
+        world.ecs.create_entity().with(pos).with(lava()).build();
+        let elsewhere = world.spawn("Slime", Position::new(8, 8)).with(BeingID::new(0)).build();
+
+        world.run(HazardSystem);
+        let hits = world.ecs.read_storage::<SufferDamage>().get(player).map(|hurt| hurt.amount.clone());
+        assert_eq!(hits, Some(vec![(None, -3)]));
+        assert!(!world.has::<SufferDamage>(elsewhere));
+        assert!(world.logged("hurts you for 3"));
+
+        world.ecs.write_storage::<SufferDamage>().clear();
+        let boots = Tags::from_raw(&Some(vec!["Fireproof".to_string()])).unwrap();
+        world.ecs.create_entity().with(boots).with(Equipped { on: player }).build();
+        world.run(HazardSystem);
+        assert!(!world.has::<SufferDamage>(player));
+        assert_eq!(hazard_for(&world.ecs, player, pos), None);
+    }
+}
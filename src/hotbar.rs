@@ -0,0 +1,89 @@
+use specs::{Entity, Join, World, WorldExt};
+
+use crate::{
+    components::{Consumable, ConsumeAction, EquipAction, Equipable, Hotbar, InBag, Item},
+    data_read::ENTITY_DB,
+    game_init::PlayerEntity,
+    items::ItemID,
+    ui::message_log::MessageLog,
+};
+
+/// Finds the stack of the item in the owner's bag, None once they have run out
+pub fn bagged_item(ecs: &World, owner: Entity, id: ItemID) -> Option<Entity> {
+    let entities = ecs.entities();
+    let items = ecs.read_storage::<Item>();
+    let in_bags = ecs.read_storage::<InBag>();
+    (&entities, &items, &in_bags)
+        .join()
+        .find(|(_, item, bag)| bag.owner == owner && item.id == id && item.qty.0 > 0)
+        .map(|(entity, ..)| entity)
+}
+
+fn item_name(id: ItemID) -> String {
+    ENTITY_DB.lock().unwrap().items.get_by_id(id).map_or("that item".to_string(), |info| info.name.clone())
+}
+
+/// Binds the bagged item to the player's hotbar slot, only items that can be consumed or equipped are allowed
+pub fn assign_hotbar_slot(ecs: &World, slot: usize, item: Entity) {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let id = match ecs.read_storage::<Item>().get(item) {
+        Some(item) => item.id,
+        None => return,
+    };
+    if !ecs.read_storage::<Consumable>().contains(item) && !ecs.read_storage::<Equipable>().contains(item) {
+        ecs.fetch_mut::<MessageLog>().log(format!("The {} can't be used from the hotbar.", item_name(id)));
+        return;
+    }
+    if let Some(hotbar) = ecs.write_storage::<Hotbar>().get_mut(player_entity) {
+        hotbar.assign(slot, id);
+        ecs.fetch_mut::<MessageLog>().log(format!("{} can now be used with {}.", item_name(id), slot + 1));
+    }
+}
+
+/// Consumes or equips the item in the player's hotbar slot the same way the inventory would
+pub fn use_hotbar_slot(ecs: &World, slot: usize) {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let id = match ecs.read_storage::<Hotbar>().get(player_entity).and_then(|hotbar| hotbar.slots[slot]) {
+        Some(id) => id,
+        None => {
+            ecs.fetch_mut::<MessageLog>()
+                .log(format!("Nothing is on {}, pick something from your bag for it.", slot + 1));
+            return;
+        }
+    };
+    let item = match bagged_item(ecs, player_entity, id) {
+        Some(item) => item,
+        None => {
+            ecs.fetch_mut::<MessageLog>().log(format!("You have run out of {}.", item_name(id)));
+            return;
+        }
+    };
+
+    if ecs.read_storage::<Consumable>().contains(item) {
+        let _ = ecs.write_storage::<ConsumeAction>().insert(player_entity, ConsumeAction::new(&item));
+    } else if ecs.read_storage::<Equipable>().contains(item) {
+        let _ = ecs.write_storage::<EquipAction>().insert(player_entity, EquipAction { item });
+    } else {
+        ecs.fetch_mut::<MessageLog>().log(format!("The {} can't be used from the hotbar.", item_name(id)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::components::HOTBAR_SLOTS;
+
+    use super::*;
+
+    #[test]
+    fn reassigning_moves_the_item() {
+        let mut hotbar = Hotbar::default();
+        hotbar.assign(0, ItemID::new(1));
+        hotbar.assign(1, ItemID::new(2));
+        hotbar.assign(3, ItemID::new(1));
+
+        assert_eq!(hotbar.slots, [None, Some(ItemID::new(2)), None, Some(ItemID::new(1)), None]);
+        hotbar.assign(1, ItemID::new(5));
+        assert_eq!(hotbar.slots[1], Some(ItemID::new(5)));
+        assert_eq!(hotbar.slots.len(), HOTBAR_SLOTS);
+    }
+}
@@ -0,0 +1,156 @@
+/* hunger.rs
+ *   The player gets hungrier as turns pass and has to eat to keep going. Once their hunger runs out they starve,
+ *   losing health every few turns until they find something to eat. Food fills hunger back up but never past full.
+ * */
+
+use serde::{Deserialize, Serialize};
+use specs::{Component, Entities, Join, Read, ReadStorage, System, VecStorage, Write, WriteStorage};
+
+use crate::{components::SufferDamage, player::Player, ui::message_log::MessageLog};
+
+/// How fast hunger runs out and how much starving hurts, read from the settings when the game starts
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct HungerConfig {
+    /// Hunger never goes down when off
+    pub enabled: bool,
+    /// Hunger of a player who is full
+    pub max: u32,
+    /// Turns between each point of hunger lost, or each hit of starvation once it is gone
+    pub turns_per_point: u32,
+    /// Damage taken each time a starving player would have lost hunger
+    pub starvation_damage: usize,
+}
+
+impl Default for HungerConfig {
+    fn default() -> Self {
+        Self { enabled: true, max: 100, turns_per_point: 10, starvation_damage: 1 }
+    }
+}
+
+#[derive(Default)]
+pub struct Metabolism {
+    cfg: HungerConfig,
+}
+
+impl Metabolism {
+    pub fn new(cfg: HungerConfig) -> Self {
+        Self { cfg }
+    }
+
+    /// Hunger for someone who has just eaten their fill
+    pub fn full(&self) -> Hunger {
+        Hunger { current: self.cfg.max, max: self.cfg.max, turns_until_hungrier: self.cfg.turns_per_point }
+    }
+}
+
+/// How much food an entity has left in them, they starve once it reaches 0
+#[derive(Component, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[storage(VecStorage)]
+pub struct Hunger {
+    pub current: u32,
+    pub max: u32,
+    turns_until_hungrier: u32,
+}
+
+impl Hunger {
+    /// Fills hunger back up by `amount`, eating more than is missing is wasted
+    pub fn eat(&mut self, amount: u32) {
+        self.current = self.current.saturating_add(amount).min(self.max);
+    }
+
+    pub fn is_starving(&self) -> bool {
+        self.current == 0
+    }
+}
+
+/// Counts down everyone's hunger at the end of each turn, those with none left take starvation damage instead
+pub struct HungerSystem;
+
+impl<'a> System<'a> for HungerSystem {
+    type SystemData = (
+        WriteStorage<'a, Hunger>,
+        WriteStorage<'a, SufferDamage>,
+        ReadStorage<'a, Player>,
+        Read<'a, Metabolism>,
+        Write<'a, MessageLog>,
+        Entities<'a>,
+    );
+
+    fn run(&mut self, (mut hungers, mut suffer_damage, players, metabolism, mut log, entities): Self::SystemData) {
+        if !metabolism.cfg.enabled {
+            return;
+        }
+        for (entity, hunger) in (&entities, &mut hungers).join() {
+            hunger.turns_until_hungrier = hunger.turns_until_hungrier.saturating_sub(1);
+            if hunger.turns_until_hungrier > 0 {
+                continue;
+            }
+            hunger.turns_until_hungrier = metabolism.cfg.turns_per_point;
+
+            if hunger.is_starving() {
+                let damage = metabolism.cfg.starvation_damage as i32;
+                SufferDamage::new_damage(&mut suffer_damage, entity, None, -damage);
+                continue;
+            }
+            hunger.current -= 1;
+            if hunger.is_starving() && players.contains(entity) {
+                log.log("You are starving, find something to eat!");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::{Builder, WorldExt};
+
+    use super::*;
+    use crate::{
+        components::{Consumable, ConsumeAction, Item},
+        items::{ConsumeHandler, ItemID, ItemQty},
+        test_harness::TestWorld,
+    };
+
+    fn hungry_player(current: u32) -> (TestWorld, specs::Entity) {
+        let mut world = TestWorld::new();
+        world.ecs.insert(Metabolism::new(HungerConfig {
+            turns_per_point: 2,
+            starvation_damage: 3,
+            ..Default::default()
+        }));
+        let player = world.player();
+        let mut hunger = world.ecs.read_resource::<Metabolism>().full();
+        hunger.current = current;
+        world.insert(player, hunger);
+        (world, player)
+    }
+
+    #[test]
+    fn hunger_runs_out_then_starving_hurts() {
+        let (mut world, player) = hungry_player(1);
+        world.run(HungerSystem);
+        assert_eq!(world.get::<Hunger>(player).map(|hunger| hunger.current), Some(1));
+        world.run(HungerSystem);
+        assert_eq!(world.get::<Hunger>(player).map(|hunger| hunger.current), Some(0));
+        assert!(world.logged("starving"));
+        assert!(!world.has::<SufferDamage>(player));
+
+        world.run(HungerSystem);
+        world.run(HungerSystem);
+        let hits = world.ecs.read_storage::<SufferDamage>().get(player).map(|hurt| hurt.amount.clone());
+        assert_eq!(hits, Some(vec![(None, -3)]));
+    }
+
+    #[test]
+    fn eating_fills_hunger_up_to_full() {
+        let (mut world, player) = hungry_player(90);
+        let food =
+            world.ecs.create_entity().with(Item::new(ItemID::new(5), ItemQty(2))).with(Consumable::Feed(25)).build();
+        world.insert(player, ConsumeAction::new(&food));
+        world.run(ConsumeHandler);
+
+        assert_eq!(world.get::<Hunger>(player).map(|hunger| hunger.current), Some(100));
+        assert_eq!(world.get::<Item>(food).map(|item| item.qty), Some(ItemQty(1)));
+    }
+}
@@ -0,0 +1,126 @@
+/* idle_bob.rs
+ *   A slow breathing bob for the player's sprite once they have stood still for a moment, so the character doesn't
+ *   look frozen. It is only an offset added when the sprite is drawn, the `Position` is never changed by it. Any key
+ *   press or movement settles the sprite back down.
+ * */
+
+use std::{f32::consts::TAU, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use specs::{World, WorldExt};
+
+use crate::{components::Transform, game_init::PlayerEntity, time::DeltaTime, Position};
+
+/// How the player's idle bob looks, read from the settings when the game starts
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct IdleBobConfig {
+    /// How far up the sprite rises in tiles, 0 turns the bob off
+    pub amplitude: f32,
+    /// Length of one breath
+    pub period_ms: u64,
+    /// How long the player has to stand still before the bob starts
+    pub delay_ms: u64,
+}
+
+impl Default for IdleBobConfig {
+    fn default() -> Self {
+        Self { amplitude: 0.08, period_ms: 1800, delay_ms: 1200 }
+    }
+}
+
+/// How long the player has been standing still
+#[derive(Default)]
+pub struct IdleBob {
+    cfg: IdleBobConfig,
+    still_for: Duration,
+    /// Where the player was last frame, they moved when it differs
+    last_pos: Option<Position>,
+}
+
+/// A sprite this close to its tile has finished its movement tween
+const SETTLED_DISTANCE: f32 = 0.01;
+
+impl IdleBob {
+    pub fn new(cfg: IdleBobConfig) -> Self {
+        Self { cfg, still_for: Duration::ZERO, last_pos: None }
+    }
+
+    /// Adds `dt` to the time stood still, or starts over when the player pressed something or is still moving
+    fn update(&mut self, dt: Duration, still: bool) {
+        self.still_for = if still { self.still_for + dt } else { Duration::ZERO };
+    }
+
+    /// Vertical offset of the player's sprite in tiles, negative is up. It eases in and out of each breath so the
+    /// sprite never jumps when the bob starts or stops
+    pub fn offset(&self) -> f32 {
+        let delay = Duration::from_millis(self.cfg.delay_ms);
+        if self.still_for < delay || self.cfg.period_ms == 0 {
+            return 0.0;
+        }
+        let breath = (self.still_for - delay).as_secs_f32() / Duration::from_millis(self.cfg.period_ms).as_secs_f32();
+        -self.cfg.amplitude * (1.0 - (breath * TAU).cos()) / 2.0
+    }
+}
+
+/// Ran every frame after the sprites are moved towards their tiles. The player is still when they didn't press
+/// anything, are on the same tile as last frame and aren't in the middle of a tween like a dash's slide
+pub fn update_idle_bob(ecs: &World, pressed_key: bool) {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let pos = ecs.read_storage::<Position>().get(player_entity).copied();
+    let tweening = match (ecs.read_storage::<Transform>().get(player_entity), pos) {
+        (Some(transform), Some(pos)) => {
+            (transform.sprite_pos.x - pos.x as f32).abs() >= SETTLED_DISTANCE
+                || (transform.sprite_pos.y - pos.y as f32).abs() >= SETTLED_DISTANCE
+        }
+        _ => false,
+    };
+    let dt = ecs.read_resource::<DeltaTime>().0;
+    let mut idle_bob = ecs.write_resource::<IdleBob>();
+    let moved = idle_bob.last_pos != pos;
+    idle_bob.last_pos = pos;
+    idle_bob.update(dt, !pressed_key && !moved && !tweening);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::TestWorld;
+
+    #[test]
+    fn the_bob_waits_for_the_player_to_stand_still() {
+        let mut bob = IdleBob::new(IdleBobConfig { amplitude: 0.1, period_ms: 1000, delay_ms: 500 });
+        bob.update(Duration::from_millis(400), true);
+        assert_eq!(bob.offset(), 0.0);
+
+        // half a breath past the delay is the top of it
+        bob.update(Duration::from_millis(600), true);
+        assert!((bob.offset() + 0.1).abs() < 0.001);
+
+        bob.update(Duration::from_millis(16), false);
+        assert_eq!(bob.offset(), 0.0);
+    }
+
+    #[test]
+    fn the_player_bobs_until_they_move_or_press_a_key() {
+        let mut world = TestWorld::new().with_delta(Duration::from_millis(250));
+        world.ecs.insert(IdleBob::new(IdleBobConfig { amplitude: 0.1, period_ms: 1000, delay_ms: 500 }));
+        let player = world.player();
+        world.insert(player, Position::new(3, 3));
+
+        for _ in 0..4 {
+            update_idle_bob(&world.ecs, false);
+        }
+        assert!(world.ecs.read_resource::<IdleBob>().offset() < 0.0);
+
+        world.insert(player, Position::new(4, 3));
+        update_idle_bob(&world.ecs, false);
+        assert_eq!(world.ecs.read_resource::<IdleBob>().offset(), 0.0);
+
+        for _ in 0..4 {
+            update_idle_bob(&world.ecs, false);
+        }
+        update_idle_bob(&world.ecs, true);
+        assert_eq!(world.ecs.read_resource::<IdleBob>().offset(), 0.0);
+    }
+}
@@ -3,24 +3,125 @@
  *   needed.
  * */
 
+use std::collections::HashMap;
+
 use bracket_lib::terminal::Point;
 use log::warn;
-use specs::{Entities, Join, ReadStorage, System, WriteExpect};
+use specs::{
+    shrev::ReaderId, storage::ComponentEvent, Entities, Entity, Join, ReadExpect, ReadStorage, System, World, WorldExt,
+    Write, WriteExpect, WriteStorage,
+};
 
 use crate::{
-    components::{Blocking, Breakable, Fishable, Item, Position},
+    being::BeingID,
+    components::{Blocking, Breakable, Fishable, Item, Name, Position, StaticIndexed},
     map::{MapRes, TileEntity},
+    player::Player,
 };
 
-/// Clears the entity contents of every tile in the map
+/// Clears the entity contents of every tile in the map, the static index is copied back in after dropping anything
+/// that has been destroyed or changed since it was indexed. Those entities lose `StaticIndexed` so they are indexed
+/// again like any other entity
 pub struct IndexReset;
 
 impl<'a> System<'a> for IndexReset {
-    type SystemData = (WriteExpect<'a, MapRes>,);
+    type SystemData = (
+        WriteExpect<'a, MapRes>,
+        WriteStorage<'a, StaticIndexed>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Blocking>,
+        ReadStorage<'a, Breakable>,
+        Entities<'a>,
+    );
+
+    fn run(&mut self, (mut map, mut static_indexed, positions, blocking, breakable, entities): Self::SystemData) {
+        let map = &mut map.0;
+        let width = map.width;
+        // maps that were loaded or just made don't have a static index yet, nothing can be in it
+        if map.static_entities.len() != map.tile_entities.len() {
+            map.static_entities = vec![Vec::new(); map.tile_entities.len()];
+            static_indexed.clear();
+        }
 
-    fn run(&mut self, (mut map,): Self::SystemData) {
-        for content in map.0.tile_entities.iter_mut() {
+        for (idx, (content, statics)) in map.tile_entities.iter_mut().zip(map.static_entities.iter_mut()).enumerate() {
             content.clear();
+            let stale: Vec<_> = statics
+                .iter()
+                .filter(|tile_entity| {
+                    let (e, still_has) = match tile_entity {
+                        TileEntity::Blocking(e) => (*e, blocking.contains(*e)),
+                        TileEntity::Breakable(e) => (*e, breakable.contains(*e)),
+                        TileEntity::Fishable(e) | TileEntity::Item(e) => (*e, false),
+                    };
+                    let in_place = positions.get(e).is_some_and(|pos| pos.to_idx(width) == idx);
+                    !(entities.is_alive(e) && static_indexed.contains(e) && in_place && still_has)
+                })
+                .map(|tile_entity| tile_entity.entity())
+                .collect();
+            for e in stale {
+                statics.retain(|tile_entity| tile_entity.entity() != e);
+                static_indexed.remove(e);
+            }
+            content.extend(statics.iter().cloned());
+        }
+    }
+}
+
+/// Adds blocking and breakable entities that never move to the static index, beings, items and the player move
+/// around so they are left for the other index systems
+pub struct IndexStaticTiles;
+
+impl<'a> System<'a> for IndexStaticTiles {
+    type SystemData = (
+        WriteExpect<'a, MapRes>,
+        WriteStorage<'a, StaticIndexed>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Blocking>,
+        ReadStorage<'a, Breakable>,
+        ReadStorage<'a, BeingID>,
+        ReadStorage<'a, Item>,
+        ReadStorage<'a, Player>,
+        Entities<'a>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut map,
+            mut static_indexed,
+            positions,
+            blocking,
+            breakable,
+            beings,
+            items,
+            players,
+            entities,
+        ): Self::SystemData,
+    ) {
+        let mut indexed = Vec::new();
+        for (e, pos, blocks, breaks, ..) in
+            (&entities, &positions, blocking.maybe(), breakable.maybe(), !&static_indexed, !&beings, !&items, !&players)
+                .join()
+        {
+            if blocks.is_none() && breaks.is_none() {
+                continue;
+            }
+            let idx = match map.0.checked_xy_to_idx(pos.x, pos.y) {
+                Some(idx) => idx,
+                None => {
+                    warn!("{:?} was out of bounds", pos);
+                    continue;
+                }
+            };
+            let indexes = [blocks.map(|_| TileEntity::Blocking(e)), breaks.map(|_| TileEntity::Breakable(e))];
+            for tile_entity in indexes.into_iter().flatten() {
+                map.0.static_entities[idx].push(tile_entity.clone());
+                map.0.tile_entities[idx].push(tile_entity);
+            }
+            indexed.push(e);
+        }
+        for e in indexed {
+            let _ = static_indexed.insert(e, StaticIndexed {});
         }
     }
 }
@@ -28,16 +129,19 @@ impl<'a> System<'a> for IndexReset {
 pub struct IndexBlockedTiles;
 
 impl<'a> System<'a> for IndexBlockedTiles {
-    type SystemData = (WriteExpect<'a, MapRes>, ReadStorage<'a, Position>, ReadStorage<'a, Blocking>, Entities<'a>);
-
-    fn run(&mut self, (mut map, pos, blocking, entities): Self::SystemData) {
-        for (pos, _, e) in (&pos, &blocking, &entities).join() {
-            let idx = map.0.xy_to_idx(pos.x, pos.y);
-            match map.0.tile_entities.get_mut(idx) {
-                Some(entities) => {
-                    entities.push(TileEntity::Blocking(e));
-                }
-                None => warn!("Idx: {} was out of bounds, {:?}", idx, pos),
+    type SystemData = (
+        WriteExpect<'a, MapRes>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Blocking>,
+        ReadStorage<'a, StaticIndexed>,
+        Entities<'a>,
+    );
+
+    fn run(&mut self, (mut map, pos, blocking, static_indexed, entities): Self::SystemData) {
+        for (pos, _, e, _) in (&pos, &blocking, &entities, !&static_indexed).join() {
+            match map.0.checked_xy_to_idx(pos.x, pos.y) {
+                Some(idx) => map.0.tile_entities[idx].push(TileEntity::Blocking(e)),
+                None => warn!("{:?} was out of bounds", pos),
             }
         }
     }
@@ -46,16 +150,19 @@ impl<'a> System<'a> for IndexBlockedTiles {
 pub struct IndexBreakableTiles;
 
 impl<'a> System<'a> for IndexBreakableTiles {
-    type SystemData = (WriteExpect<'a, MapRes>, ReadStorage<'a, Position>, ReadStorage<'a, Breakable>, Entities<'a>);
-
-    fn run(&mut self, (mut map, pos, breakable, entities): Self::SystemData) {
-        for (id, pos, _) in (&entities, &pos, &breakable).join() {
-            let idx = map.0.xy_to_idx(pos.x, pos.y);
-            match map.0.tile_entities.get_mut(idx) {
-                Some(entities) => {
-                    entities.push(TileEntity::Breakable(id));
-                }
-                None => warn!("Idx: {} was out of bounds, {:?}", idx, pos),
+    type SystemData = (
+        WriteExpect<'a, MapRes>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Breakable>,
+        ReadStorage<'a, StaticIndexed>,
+        Entities<'a>,
+    );
+
+    fn run(&mut self, (mut map, pos, breakable, static_indexed, entities): Self::SystemData) {
+        for (id, pos, _, _) in (&entities, &pos, &breakable, !&static_indexed).join() {
+            match map.0.checked_xy_to_idx(pos.x, pos.y) {
+                Some(idx) => map.0.tile_entities[idx].push(TileEntity::Breakable(id)),
+                None => warn!("{:?} was out of bounds", pos),
             }
         }
     }
@@ -68,12 +175,9 @@ impl<'a> System<'a> for IndexFishableTiles {
 
     fn run(&mut self, (mut map, pos, fishable, entities): Self::SystemData) {
         for (entity, pos, _) in (&entities, &pos, &fishable).join() {
-            let idx = map.0.xy_to_idx(pos.x, pos.y);
-            match map.0.tile_entities.get_mut(idx) {
-                Some(entities) => {
-                    entities.push(TileEntity::Fishable(entity));
-                }
-                None => warn!("Idx: {} was out of bounds, {:?}", idx, pos),
+            match map.0.checked_xy_to_idx(pos.x, pos.y) {
+                Some(idx) => map.0.tile_entities[idx].push(TileEntity::Fishable(entity)),
+                None => warn!("{:?} was out of bounds", pos),
             }
         }
     }
@@ -86,12 +190,178 @@ impl<'a> System<'a> for IndexItemTiles {
 
     fn run(&mut self, (mut map, pos, items, entities): Self::SystemData) {
         for (entity, pos, _) in (&entities, &pos, &items).join() {
-            let idx = map.0.xy_to_idx(pos.x, pos.y);
-            match map.0.tile_entities.get_mut(idx) {
-                Some(entities) => {
-                    entities.push(TileEntity::Item(entity));
+            match map.0.checked_xy_to_idx(pos.x, pos.y) {
+                Some(idx) => map.0.tile_entities[idx].push(TileEntity::Item(entity)),
+                None => warn!("{:?} was out of bounds", pos),
+            }
+        }
+    }
+}
+
+/// Cross checks `tile_entities` against every entity's `Position` after indexing. Only runs in dev builds while
+/// toggled on with Ctrl+I since it walks every tile and indexed entity each frame
+#[derive(Default)]
+pub struct TileIndexCheck {
+    pub enabled: bool,
+    /// What was wrong with the index the last time it was checked
+    pub mismatches: Vec<String>,
+}
+
+pub struct ValidateTileIndex;
+
+impl<'a> System<'a> for ValidateTileIndex {
+    type SystemData = (
+        Write<'a, TileIndexCheck>,
+        ReadExpect<'a, MapRes>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Blocking>,
+        ReadStorage<'a, Breakable>,
+        ReadStorage<'a, Fishable>,
+        ReadStorage<'a, Item>,
+        ReadStorage<'a, Name>,
+        Entities<'a>,
+    );
+
+    fn run(
+        &mut self,
+        (mut check, map, positions, blocking, breakable, fishable, items, names, entities): Self::SystemData,
+    ) {
+        if !cfg!(feature = "dev") || !check.enabled {
+            return;
+        }
+        let map = &map.0;
+        let describe = |e: Entity| match names.get(e) {
+            Some(name) => format!("{} ({}v{})", name.0, e.id(), e.gen().id()),
+            None => format!("unnamed ({}v{})", e.id(), e.gen().id()),
+        };
+
+        let mut mismatches = Vec::new();
+        for (idx, content) in map.tile_entities.iter().enumerate() {
+            let tile = idx_to_point(idx, map.width);
+            for tile_entity in content.iter() {
+                let (e, kind, still_has) = match tile_entity {
+                    TileEntity::Blocking(e) => (*e, "Blocking", blocking.contains(*e)),
+                    TileEntity::Breakable(e) => (*e, "Breakable", breakable.contains(*e)),
+                    TileEntity::Fishable(e) => (*e, "Fishable", fishable.contains(*e)),
+                    TileEntity::Item(e) => (*e, "Item", items.contains(*e)),
+                };
+                let listed = format!("listed as {} on tile ({}, {})", kind, tile.x, tile.y);
+                if !entities.is_alive(e) {
+                    mismatches.push(format!("dead entity {}v{} is {}", e.id(), e.gen().id(), listed));
+                    continue;
+                }
+                match positions.get(e) {
+                    None => mismatches.push(format!("{} has no position but is {}", describe(e), listed)),
+                    Some(pos) if pos.to_idx(map.width) != idx => {
+                        mismatches.push(format!("{} at ({}, {}) is {}", describe(e), pos.x, pos.y, listed))
+                    }
+                    Some(_) => {}
+                }
+                if !still_has {
+                    mismatches.push(format!("{} is not {} but is {}", describe(e), kind, listed));
+                }
+            }
+        }
+
+        for (e, pos, blocks, breaks, fishes, item) in
+            (&entities, &positions, blocking.maybe(), breakable.maybe(), fishable.maybe(), items.maybe()).join()
+        {
+            // out of bounds entities are warned about when indexing
+            let content = match map.checked_xy_to_idx(pos.x, pos.y) {
+                Some(idx) => &map.tile_entities[idx],
+                None => continue,
+            };
+            let expected = [
+                blocks.map(|_| TileEntity::Blocking(e)),
+                breaks.map(|_| TileEntity::Breakable(e)),
+                fishes.map(|_| TileEntity::Fishable(e)),
+                item.map(|_| TileEntity::Item(e)),
+            ];
+            for tile_entity in expected.into_iter().flatten() {
+                if !content.contains(&tile_entity) {
+                    mismatches.push(format!(
+                        "{} at ({}, {}) is missing from its tile as {:?}",
+                        describe(e),
+                        pos.x,
+                        pos.y,
+                        tile_entity
+                    ));
                 }
-                None => warn!("Idx: {} was out of bounds, {:?}", idx, pos),
+            }
+        }
+
+        for mismatch in mismatches.iter() {
+            warn!("Tile index mismatch: {}", mismatch);
+        }
+        check.mismatches = mismatches;
+    }
+}
+
+/// Every named entity by its name, so scripted content can find "the Merchant" without scanning. Kept up to date by
+/// `IndexNamedEntities` as names are added and entities are deleted
+#[derive(Default)]
+pub struct NamedEntities {
+    by_name: HashMap<String, Vec<Entity>>,
+    /// The name each entity was indexed under by its id, deleted entities are only known by their id
+    names: HashMap<u32, String>,
+}
+
+impl NamedEntities {
+    /// The entity with the name. When several share it the first one named is kept and a warning is logged, since the
+    /// caller likely expected it to be unique
+    pub fn get(&self, name: &str) -> Option<Entity> {
+        let named = self.by_name.get(name)?;
+        if named.len() > 1 {
+            warn!("{} entities are named {}, using the first one", named.len(), name);
+        }
+        named.first().copied()
+    }
+
+    fn insert(&mut self, entity: Entity, name: &str) {
+        self.remove(entity.id());
+        self.by_name.entry(name.to_string()).or_default().push(entity);
+        self.names.insert(entity.id(), name.to_string());
+    }
+
+    fn remove(&mut self, id: u32) {
+        let name = match self.names.remove(&id) {
+            Some(name) => name,
+            None => return,
+        };
+        if let Some(named) = self.by_name.get_mut(&name) {
+            named.retain(|e| e.id() != id);
+            if named.is_empty() {
+                self.by_name.remove(&name);
+            }
+        }
+    }
+}
+
+/// Follows `Name` being inserted, changed and removed, removals include entities deleted in `maintain`
+pub struct IndexNamedEntities {
+    reader: ReaderId<ComponentEvent>,
+}
+
+impl IndexNamedEntities {
+    pub fn new(ecs: &mut World) -> Self {
+        Self { reader: ecs.write_storage::<Name>().register_reader() }
+    }
+}
+
+impl<'a> System<'a> for IndexNamedEntities {
+    type SystemData = (Write<'a, NamedEntities>, ReadStorage<'a, Name>, Entities<'a>);
+
+    fn run(&mut self, (mut named, names, entities): Self::SystemData) {
+        for event in names.channel().read(&mut self.reader) {
+            match event {
+                ComponentEvent::Inserted(id) | ComponentEvent::Modified(id) => {
+                    let entity = entities.entity(*id);
+                    match names.get(entity) {
+                        Some(name) if entities.is_alive(entity) => named.insert(entity, &name.0),
+                        _ => named.remove(*id),
+                    }
+                }
+                ComponentEvent::Removed(id) => named.remove(*id),
             }
         }
     }
@@ -103,3 +373,99 @@ pub fn idx_to_point(idx: usize, width: usize) -> Point {
 
     Point::new(x, y)
 }
+
+#[cfg(test)]
+mod tests {
+    use specs::{Builder, RunNow, WorldExt};
+
+    use crate::{
+        components::{HealthStats, ToolType},
+        test_harness::TestWorld,
+    };
+
+    use super::*;
+
+    fn index(world: &mut TestWorld) {
+        world.run(IndexReset);
+        world.run(IndexStaticTiles);
+        world.run(IndexBlockedTiles);
+        world.run(IndexBreakableTiles);
+    }
+
+    #[test]
+    fn named_entities_follow_names_until_the_entity_is_deleted() {
+        let mut world = TestWorld::new();
+        let mut index = IndexNamedEntities::new(&mut world.ecs);
+        let merchant = world.spawn("Merchant", Position::new(1, 1)).build();
+        let second = world.spawn("Merchant", Position::new(2, 1)).build();
+        index.run_now(&world.ecs);
+        let player = world.player();
+        {
+            let named = world.ecs.read_resource::<NamedEntities>();
+            assert_eq!(named.get("Merchant"), Some(merchant));
+            assert_eq!(named.get("Player"), None);
+        }
+
+        world.ecs.delete_entity(merchant).unwrap();
+        world.ecs.maintain();
+        world.ecs.write_storage::<Name>().insert(player, Name::new("Hero")).unwrap();
+        index.run_now(&world.ecs);
+        let named = world.ecs.read_resource::<NamedEntities>();
+        assert_eq!(named.get("Merchant"), Some(second));
+        assert_eq!(named.get("Hero"), Some(player));
+        assert_eq!(named.get("Player"), None);
+    }
+
+    #[test]
+    fn tile_index_check_reports_entities_that_moved_since_indexing() {
+        let mut world = TestWorld::new().with_map(6, 6);
+        world.ecs.insert(TileIndexCheck { enabled: true, ..Default::default() });
+        let boulder = world.ecs.create_entity().with(Position::new(1, 1)).with(Blocking {}).build();
+
+        index(&mut world);
+        world.run(ValidateTileIndex);
+        assert!(world.ecs.read_resource::<TileIndexCheck>().mismatches.is_empty());
+
+        world.ecs.write_storage::<Position>().insert(boulder, Position::new(3, 4)).unwrap();
+        world.run(ValidateTileIndex);
+        let mismatches = world.ecs.read_resource::<TileIndexCheck>().mismatches.clone();
+        if cfg!(feature = "dev") {
+            assert_eq!(mismatches.len(), 2);
+            assert!(mismatches.iter().any(|m| m.contains("listed as Blocking on tile (1, 1)")));
+            assert!(mismatches.iter().any(|m| m.contains("at (3, 4) is missing from its tile")));
+        } else {
+            assert!(mismatches.is_empty());
+        }
+    }
+
+    #[test]
+    fn static_tiles_are_indexed_once_and_dropped_when_destroyed() {
+        let mut world = TestWorld::new().with_map(6, 6);
+        let pos = Position::new(2, 2);
+        let rock = world
+            .ecs
+            .create_entity()
+            .with(pos)
+            .with(Blocking {})
+            .with(Breakable::new(ToolType::Pickaxe))
+            .with(HealthStats::new(5, 0))
+            .build();
+
+        index(&mut world);
+        index(&mut world);
+        assert!(world.has::<StaticIndexed>(rock));
+        {
+            let map = world.ecs.read_resource::<MapRes>();
+            assert_eq!(
+                map.0.tile_entities[pos.to_idx(6)],
+                vec![TileEntity::Blocking(rock), TileEntity::Breakable(rock)]
+            );
+        }
+
+        world.ecs.delete_entity(rock).unwrap();
+        index(&mut world);
+        let map = world.ecs.read_resource::<MapRes>();
+        assert!(!map.0.is_blocked(&pos));
+        assert!(map.0.static_entities[pos.to_idx(6)].is_empty());
+    }
+}
@@ -0,0 +1,118 @@
+/* input_buffer.rs
+ *   Remembers a movement key pressed while the player couldn't act, like during an activity, so it is used on the
+ *   next frame that takes input instead of being dropped.
+ * */
+
+use std::time::Duration;
+
+use specs::{World, WorldExt};
+
+use crate::{
+    keybinds::{movement_delta, KeyPress, Modifier},
+    time::RealDeltaTime,
+};
+
+/// The last key pressed while input was blocked and how long ago it was pressed
+#[derive(Default)]
+pub struct InputBuffer {
+    /// How long a held key is kept for, zero turns the buffer off
+    window: Duration,
+    held: Option<(KeyPress, Duration)>,
+}
+
+impl InputBuffer {
+    pub fn new(window: Duration) -> Self {
+        Self { window, held: None }
+    }
+
+    /// Remembers the key, only called on frames where the player's input is not being used. Only movement is held
+    /// since opening a menu or using an item late would be surprising, its modifier is kept so a dash stays a dash
+    pub fn hold(&mut self, key: Option<KeyPress>) {
+        if self.window.is_zero() {
+            return;
+        }
+        if let Some(key) = key.filter(is_bufferable) {
+            self.held = Some((key, Duration::ZERO));
+        }
+    }
+
+    /// The key to use on a frame that takes input. A key pressed this frame always wins and throws away the held one,
+    /// so a press is only ever acted on once
+    pub fn take(&mut self, key: Option<KeyPress>) -> Option<KeyPress> {
+        let held = self.held.take().map(|(held, _)| held);
+        key.or(held)
+    }
+
+    /// Ages the held key and forgets it once it is older than the window
+    pub fn tick(&mut self, delta: Duration) {
+        if let Some((_, age)) = &mut self.held {
+            *age += delta;
+            if *age > self.window {
+                self.held = None;
+            }
+        }
+    }
+}
+
+/// Ctrl combos are debug controls and are never held
+fn is_bufferable(press: &KeyPress) -> bool {
+    press.modifier != Modifier::Ctrl && movement_delta(press.key).is_some()
+}
+
+/// Runs every frame time passes so held keys go stale in real time
+pub fn age_input_buffer(ecs: &World) {
+    let delta = ecs.read_resource::<RealDeltaTime>().0;
+    ecs.write_resource::<InputBuffer>().tick(delta);
+}
+
+#[cfg(test)]
+mod tests {
+    use bracket_lib::terminal::VirtualKeyCode as VKC;
+
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_millis(150);
+
+    fn plain(key: VKC) -> Option<KeyPress> {
+        Some(KeyPress::new(key, Modifier::Plain))
+    }
+
+    #[test]
+    fn held_key_is_used_once_unless_a_new_key_is_pressed() {
+        let mut buffer = InputBuffer::new(WINDOW);
+        buffer.hold(plain(VKC::I));
+        assert_eq!(buffer.take(None), None);
+
+        buffer.hold(plain(VKC::W));
+        assert_eq!(buffer.take(None), plain(VKC::W));
+        assert_eq!(buffer.take(None), None);
+
+        buffer.hold(plain(VKC::W));
+        assert_eq!(buffer.take(plain(VKC::D)), plain(VKC::D));
+        assert_eq!(buffer.take(None), None);
+    }
+
+    #[test]
+    fn held_key_expires_after_the_window() {
+        let mut buffer = InputBuffer::new(WINDOW);
+        buffer.hold(plain(VKC::Left));
+        buffer.tick(WINDOW / 2);
+        buffer.tick(WINDOW);
+        assert_eq!(buffer.take(None), None);
+
+        let mut off = InputBuffer::new(Duration::ZERO);
+        off.hold(plain(VKC::Left));
+        assert_eq!(off.take(None), None);
+    }
+
+    #[test]
+    fn modifier_is_held_with_the_key_except_for_debug_combos() {
+        let mut buffer = InputBuffer::new(WINDOW);
+        let dash = Some(KeyPress::new(VKC::D, Modifier::Shift));
+        buffer.hold(dash);
+        assert_eq!(buffer.take(None), dash);
+
+        buffer.hold(Some(KeyPress::new(VKC::D, Modifier::Ctrl)));
+        assert_eq!(buffer.take(None), None);
+    }
+}
@@ -1,18 +1,29 @@
 use bracket_lib::terminal::{BTerm, VirtualKeyCode as VKC};
-use itertools::Itertools;
-use specs::{Entity, Join, World, WorldExt};
-use specs::{LendJoin, ReadStorage};
+use specs::world::EntitiesRes;
+use specs::{Entity, Join, World, WorldExt, WriteStorage};
 
 use crate::components::{ConsumeAction, Equipped, Position};
-use crate::config::{InventoryConfig, SortMode};
+use crate::config::InventoryConfig;
 use crate::data_read::ENTITY_DB;
+use crate::examine::describe;
+use crate::hotbar::assign_hotbar_slot;
+use crate::items::{ItemID, ItemQty, MAX_STACK_SIZE};
+use crate::map::{Map, MapRes, TileEntity};
+use crate::targeting::start_targeting;
 use crate::{
     components::{CraftAction, EquipAction, InBag, Item, Name, SelectedInventoryItem},
     game_init::PlayerEntity,
-    ui::message_log::MessageLog,
+    get_text,
+    ui::{
+        message_log::MessageLog,
+        modal::{Modal, ModalResult},
+    },
     AppState,
 };
 
+/// Furthest from the player a batch drop spreads items, anything past the room there is piles onto the same tiles
+const MAX_DROP_RADIUS: usize = 3;
+
 #[derive(Clone)]
 pub enum UseMenuResult {
     Craft,
@@ -30,12 +41,55 @@ pub enum InventoryResponse {
     StateChange(AppState),
 }
 
+/// Dropping many items at once, it waits for the player to confirm since the items end up scattered on the ground.
+/// Equipped items are never dropped this way
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BatchDrop {
+    /// Every stack of one kind of item
+    AllOf(ItemID),
+    Everything,
+}
+
+impl BatchDrop {
+    fn includes(&self, id: ItemID) -> bool {
+        match self {
+            BatchDrop::AllOf(kind) => *kind == id,
+            BatchDrop::Everything => true,
+        }
+    }
+}
+
+/// Opens a modal asking the player whether they really want to make the drop
+fn ask_to_drop(cfg: &mut InventoryConfig, drop: BatchDrop) {
+    let question = match drop {
+        BatchDrop::AllOf(id) => {
+            let edb = ENTITY_DB.lock().unwrap();
+            let name = edb.items.get_by_id(id).map_or("item", |info| info.name.as_str());
+            format!("Drop every {}?", name)
+        }
+        BatchDrop::Everything => "Drop everything not equipped?".to_string(),
+    };
+    cfg.pending_drop = Some((drop, Modal::yes_no(question)));
+}
+
 pub fn p_input_inventory(ecs: &mut World, ctx: &BTerm, cfg: &mut InventoryConfig) -> InventoryResponse {
     let player_entity: Entity;
     {
         // dirty borrow checker hack to take the value of player entity
         player_entity = ecs.read_resource::<PlayerEntity>().0;
     }
+    if let Some((drop, modal)) = &mut cfg.pending_drop {
+        let drop = *drop;
+        match modal.handle_key(ctx.key) {
+            ModalResult::Waiting => return InventoryResponse::Waiting,
+            ModalResult::Selected(0) => drop_batch(ecs, drop),
+            ModalResult::Selected(_) | ModalResult::Cancelled => {
+                ecs.write_resource::<MessageLog>().log("You keep your things.")
+            }
+        }
+        cfg.pending_drop = None;
+        return InventoryResponse::Waiting;
+    }
     match ctx.key {
         None => InventoryResponse::Waiting,
         Some(key) if check_inventory_selection(ecs) == SelectionStatus::SelectionWithoutAction => {
@@ -51,6 +105,13 @@ pub fn p_input_inventory(ecs: &mut World, ctx: &BTerm, cfg: &mut InventoryConfig
                         selection.intended_action = Some(UseMenuResult::Examine);
                         InventoryResponse::ActionReady
                     }
+                    VKC::D if ctx.shift => {
+                        if let Some(item) = ecs.read_storage::<Item>().get(selection.first_item) {
+                            ask_to_drop(cfg, BatchDrop::AllOf(item.id));
+                        }
+                        selected_idxs.remove(player_entity);
+                        InventoryResponse::Waiting
+                    }
                     VKC::D => {
                         selection.intended_action = Some(UseMenuResult::Drop);
                         InventoryResponse::ActionReady
@@ -63,6 +124,28 @@ pub fn p_input_inventory(ecs: &mut World, ctx: &BTerm, cfg: &mut InventoryConfig
                         selection.intended_action = Some(UseMenuResult::Consume);
                         InventoryResponse::ActionReady
                     }
+                    VKC::T => {
+                        let item = selection.first_item;
+                        match start_targeting(ecs, player_entity, item) {
+                            Some(target) => {
+                                selected_idxs.remove(player_entity);
+                                InventoryResponse::StateChange(AppState::TargetingTile { item, target })
+                            }
+                            None => InventoryResponse::Waiting,
+                        }
+                    }
+                    VKC::Key1 | VKC::Key2 | VKC::Key3 | VKC::Key4 | VKC::Key5 => {
+                        let slot = match key {
+                            VKC::Key1 => 0,
+                            VKC::Key2 => 1,
+                            VKC::Key3 => 2,
+                            VKC::Key4 => 3,
+                            _ => 4,
+                        };
+                        assign_hotbar_slot(ecs, slot, selection.first_item);
+                        selected_idxs.remove(player_entity);
+                        InventoryResponse::Waiting
+                    }
                     VKC::Escape => {
                         selection.intended_action = Some(UseMenuResult::Cancel);
                         InventoryResponse::ActionReady
@@ -73,6 +156,21 @@ pub fn p_input_inventory(ecs: &mut World, ctx: &BTerm, cfg: &mut InventoryConfig
                 InventoryResponse::Waiting
             }
         }
+        Some(key) if cfg.typing_filter => {
+            match key {
+                VKC::Back => {
+                    cfg.filter.pop();
+                }
+                VKC::Escape => cfg.clear_filter(),
+                VKC::Return => cfg.typing_filter = false,
+                _ => {
+                    if let Some(letter) = get_text(key) {
+                        cfg.filter.push(letter);
+                    }
+                }
+            }
+            InventoryResponse::Waiting
+        }
         Some(key) => {
             match key {
                 VKC::Key1 => select_item(&player_entity, 0, ecs, cfg),
@@ -94,40 +192,69 @@ pub fn p_input_inventory(ecs: &mut World, ctx: &BTerm, cfg: &mut InventoryConfig
                 VKC::H => select_item(&player_entity, 16, ecs, cfg),
                 VKC::S => {
                     cfg.rotate_sort_mode();
+                    sort_now(ecs, cfg);
+                    InventoryResponse::Waiting
+                }
+                VKC::O => {
+                    sort_now(ecs, cfg);
+                    InventoryResponse::Waiting
+                }
+                VKC::X => {
+                    ask_to_drop(cfg, BatchDrop::Everything);
                     InventoryResponse::Waiting
                 }
-                VKC::Escape | VKC::I => clean_and_exit_inventory(&player_entity, ecs),
+                VKC::Slash => {
+                    cfg.typing_filter = true;
+                    InventoryResponse::Waiting
+                }
+                VKC::Escape if !cfg.filter.is_empty() => {
+                    cfg.clear_filter();
+                    InventoryResponse::Waiting
+                }
+                VKC::Escape | VKC::I => {
+                    cfg.clear_filter();
+                    clean_and_exit_inventory(&player_entity, ecs)
+                }
                 _ => InventoryResponse::Waiting, // Unbound keypress so just ignore it
             }
         }
     }
 }
 
-// This function depends on the ui code of getting the iterator
+/// The player's items in the order the inventory lists them, only items matching the filter are included.
+/// Items keep the order the bag was last sorted into and anything picked up since is listed after them
+pub fn listed_items(ecs: &World, cfg: &InventoryConfig) -> Vec<Entity> {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let items = ecs.read_storage::<Item>();
+    let in_bags = ecs.read_storage::<InBag>();
+    let names = ecs.read_storage::<Name>();
+    let entities = ecs.entities();
+
+    let (mut sorted, mut unsorted): (Vec<_>, Vec<_>) = (&entities, &items, &in_bags, &names)
+        .join()
+        .filter(|(_, _, bag, name)| bag.owner == player_entity && cfg.matches_filter(&name.0))
+        .map(|(e, item, _, name)| (e, item, name))
+        .partition(|(e, _, _)| cfg.order.contains(e));
+    sorted.sort_by_key(|(e, _, _)| cfg.order.iter().position(|ordered| ordered == e));
+    unsorted.sort_by(|a, b| cfg.sort_mode.compare((a.1, a.2), (b.1, b.2)));
+    sorted.into_iter().chain(unsorted).map(|(e, _, _)| e).collect()
+}
+
+/// Sorts the whole bag by the current sort mode, the list keeps this order until it is sorted again
+pub fn sort_now(ecs: &World, cfg: &mut InventoryConfig) {
+    let filter = std::mem::take(&mut cfg.filter);
+    cfg.order.clear();
+    cfg.order = listed_items(ecs, cfg);
+    cfg.filter = filter;
+}
+
 fn select_item(
     player_entity: &Entity,
     idx_selected: usize,
     ecs: &mut World,
     cfg: &InventoryConfig,
 ) -> InventoryResponse {
-    let items: ReadStorage<Item> = ecs.read_storage();
-    let inbags: ReadStorage<InBag> = ecs.read_storage();
-    let names: ReadStorage<Name> = ecs.read_storage();
-    let equipped: ReadStorage<Equipped> = ecs.read_storage();
-
-    let entities = ecs.entities();
-    let selected_entity = (&entities, &items, &inbags, &names, (&equipped).maybe())
-        // important: this must match in src/ui/inventory.rs until a better solution is found to share code
-        // up to the sorted_by
-        .join()
-        .filter(|(_, _, bag, _, _)| bag.owner == *player_entity)
-        .sorted_by(|a, b| match cfg.sort_mode {
-            SortMode::NameABC => a.3.cmp(b.3),
-            SortMode::IDAsc => a.1.id.cmp(&b.1.id),
-            _ => a.1.id.cmp(&b.1.id),
-        })
-        .nth(idx_selected)
-        .map(|(e, _, _, _, _)| e);
+    let selected_entity = listed_items(ecs, cfg).get(idx_selected).copied();
 
     match check_inventory_selection(ecs) {
         SelectionStatus::NoSelection => {
@@ -175,32 +302,21 @@ pub fn handle_one_item_actions(ecs: &mut World) {
     match selection.intended_action.as_ref().unwrap() {
         UseMenuResult::Drop => {
             // remove item from bag
-            if let Some((item_entity, dropped_item, _)) = items_in_player_bag {
+            if let Some((item_entity, _, _)) = items_in_player_bag {
                 in_bags.remove(item_entity);
                 log.log("Dropped it");
 
                 let mut positions = ecs.write_storage::<Position>();
-                if let Some(player_position) = positions.get(player_entity.0) {
-                    if let Some(ground_item) = (&entities, &items, &positions)
-                        .join()
-                        .find(|(_, i, p)| p.eq(&player_position) && i.id.eq(&dropped_item.id))
-                    {
-                        let _ = items
-                            .insert(ground_item.0, Item::new(ground_item.1.id, ground_item.1.qty + dropped_item.qty));
-                    } else {
-                        let _ = positions.insert(item_entity, *player_position);
-                    }
+                if let Some(player_position) = positions.get(player_entity.0).copied() {
+                    let map = ecs.read_resource::<MapRes>();
+                    place_on_ground(&map.0, &entities, &mut items, &mut positions, item_entity, player_position);
                 }
             }
         }
         UseMenuResult::Examine => {
             //log flavor text
-            if let Some((_, item, _)) = items_in_player_bag {
-                let examine_text = match &ENTITY_DB.lock().unwrap().items.get_by_id(item.id) {
-                    Some(info) => info.examine_text.clone(),
-                    None => format!("Could not find item with id: {}", item.id),
-                };
-                log.log(examine_text);
+            if let Some((item_entity, item, _)) = items_in_player_bag {
+                log.log(describe(ecs.read_storage::<Name>().get(item_entity), Some(item), None));
             } else {
                 log.log(format!("Couldn't examine entity: {:?}", selection.first_item));
             }
@@ -255,6 +371,103 @@ pub fn handle_two_item_actions(ecs: &mut World, second_item: &Entity) {
     selected_idxs.remove(player_entity.0);
 }
 
+/// Drops everything `drop` covers from the player's bag, each stack onto its own tile around the player while
+/// there is room
+fn drop_batch(ecs: &World, drop: BatchDrop) {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let entities = ecs.entities();
+    let mut items = ecs.write_storage::<Item>();
+    let mut in_bags = ecs.write_storage::<InBag>();
+    let mut positions = ecs.write_storage::<Position>();
+    let equipped = ecs.read_storage::<Equipped>();
+    let mut log = ecs.write_resource::<MessageLog>();
+
+    let dropping: Vec<Entity> = (&entities, &items, &in_bags, !&equipped)
+        .join()
+        .filter(|(_, item, bag, _)| bag.owner == player_entity && drop.includes(item.id))
+        .map(|(e, _, _, _)| e)
+        .collect();
+    let player_position = match positions.get(player_entity) {
+        Some(pos) if !dropping.is_empty() => *pos,
+        _ => {
+            log.log("There is nothing to drop.");
+            return;
+        }
+    };
+
+    let map = ecs.read_resource::<MapRes>();
+    let spots = drop_spots(&map.0, player_position, dropping.len());
+    for (item_entity, spot) in dropping.iter().zip(spots.iter().cycle()) {
+        in_bags.remove(*item_entity);
+        place_on_ground(&map.0, &entities, &mut items, &mut positions, *item_entity, *spot);
+    }
+    let stacks = if dropping.len() == 1 { "stack" } else { "stacks" };
+    log.log(format!("Dropped {} {} of items.", dropping.len(), stacks));
+}
+
+/// Up to `count` tiles to drop items on, the center first then the open tiles around it from nearest out
+fn drop_spots(map: &Map, center: Position, count: usize) -> Vec<Position> {
+    let mut spots = vec![center];
+    for radius in 1..=MAX_DROP_RADIUS {
+        for y in center.y.saturating_sub(radius)..=center.y + radius {
+            for x in center.x.saturating_sub(radius)..=center.x + radius {
+                let pos = Position::new(x, y);
+                let on_ring = x.abs_diff(center.x).max(y.abs_diff(center.y)) == radius;
+                let open = map.checked_xy_to_idx(x, y).is_some_and(|idx| !map.tiles[idx].is_blocked);
+                if spots.len() < count && on_ring && open && !map.is_blocked(&pos) {
+                    spots.push(pos);
+                }
+            }
+        }
+    }
+    spots
+}
+
+/// Puts the item on the ground, it joins stacks of the same item already lying there while they have room and
+/// whatever doesn't fit stays on the tile as a stack of its own
+fn place_on_ground(
+    map: &Map,
+    entities: &EntitiesRes,
+    items: &mut WriteStorage<Item>,
+    positions: &mut WriteStorage<Position>,
+    item_entity: Entity,
+    pos: Position,
+) {
+    let dropped = match items.get(item_entity) {
+        Some(item) => item.clone(),
+        None => return,
+    };
+    let on_tile: Vec<Entity> = map.checked_xy_to_idx(pos.x, pos.y).map_or(vec![], |idx| {
+        map.tile_entities[idx]
+            .iter()
+            .filter_map(|tile| match tile {
+                TileEntity::Item(e) => Some(*e),
+                _ => None,
+            })
+            .collect()
+    });
+
+    let mut left = dropped.qty.0;
+    for stack in on_tile {
+        // the index is from the start of the frame so anything picked up since then is skipped by its position
+        if left == 0 || stack == item_entity || positions.get(stack) != Some(&pos) {
+            continue;
+        }
+        if let Some(ground_item) = items.get_mut(stack).filter(|ground_item| ground_item.id == dropped.id) {
+            let moved = MAX_STACK_SIZE.0.saturating_sub(ground_item.qty.0).min(left);
+            ground_item.qty = ItemQty(ground_item.qty.0 + moved);
+            left -= moved;
+        }
+    }
+
+    if left == 0 {
+        let _ = entities.delete(item_entity);
+        return;
+    }
+    let _ = items.insert(item_entity, Item::new(dropped.id, ItemQty(left)));
+    let _ = positions.insert(item_entity, pos);
+}
+
 fn clean_and_exit_inventory(player_entity: &Entity, ecs: &mut World) -> InventoryResponse {
     // TODO: remove temporary inventory related components
     let mut selected_idxs = ecs.write_storage::<SelectedInventoryItem>();
@@ -282,3 +495,97 @@ pub fn check_inventory_selection(ecs: &World) -> SelectionStatus {
         None => SelectionStatus::NoSelection,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use specs::Builder;
+
+    use super::*;
+    use crate::{
+        components::Blocking,
+        indexing::{IndexBlockedTiles, IndexItemTiles, IndexReset},
+        test_harness::TestWorld,
+    };
+
+    fn bag_item(world: &mut TestWorld, name: &str, id: u32) -> Entity {
+        let owner = world.player();
+        world
+            .ecs
+            .create_entity()
+            .with(Name(name.to_string()))
+            .with(Item::new(ItemID::new(id), ItemQty(1)))
+            .with(InBag { owner })
+            .build()
+    }
+
+    #[test]
+    fn new_items_are_listed_last_until_sorted_again() {
+        let mut world = TestWorld::new();
+        let mut cfg = InventoryConfig::default();
+        let pear = bag_item(&mut world, "Pear", 1);
+        let apple = bag_item(&mut world, "Apple", 2);
+        assert_eq!(listed_items(&world.ecs, &cfg), vec![apple, pear]);
+
+        sort_now(&world.ecs, &mut cfg);
+        let banana = bag_item(&mut world, "Banana", 3);
+        assert_eq!(listed_items(&world.ecs, &cfg), vec![apple, pear, banana]);
+
+        cfg.rotate_sort_mode();
+        sort_now(&world.ecs, &mut cfg);
+        assert_eq!(listed_items(&world.ecs, &cfg), vec![pear, apple, banana]);
+    }
+
+    #[test]
+    fn dropping_everything_spreads_items_out_and_keeps_equipment() {
+        let mut world = TestWorld::new().with_map(8, 8);
+        let player = world.player();
+        world.insert(player, Position::new(4, 4));
+        world.spawn("Wall", Position::new(3, 3)).with(Blocking {}).build();
+        world.run(IndexReset);
+        world.run(IndexBlockedTiles);
+        let rocks: Vec<Entity> = (0..3).map(|_| bag_item(&mut world, "Rock", 5)).collect();
+        let sword = bag_item(&mut world, "Sword", 6);
+        world.insert(sword, Equipped { on: player });
+
+        drop_batch(&world.ecs, BatchDrop::Everything);
+
+        let spots: HashSet<Position> = rocks.iter().filter_map(|rock| world.get::<Position>(*rock)).collect();
+        assert_eq!(spots.len(), 3);
+        assert!(spots.contains(&Position::new(4, 4)) && !spots.contains(&Position::new(3, 3)));
+        assert!(rocks.iter().all(|rock| !world.has::<InBag>(*rock)));
+        assert!(world.has::<InBag>(sword));
+        assert!(world.logged("Dropped 3 stacks"));
+    }
+
+    #[test]
+    fn dropped_items_fill_up_ground_stacks_before_starting_a_new_one() {
+        let mut world = TestWorld::new().with_map(8, 8);
+        let player = world.player();
+        let pos = Position::new(4, 4);
+        world.insert(player, pos);
+        let pile = world.spawn("Rock", pos).with(Item::new(ItemID::new(5), ItemQty(95))).build();
+        let index = |world: &mut TestWorld| {
+            world.run(IndexReset);
+            world.run(IndexItemTiles);
+        };
+
+        index(&mut world);
+        let rocks = bag_item(&mut world, "Rock", 5);
+        world.insert(rocks, Item::new(ItemID::new(5), ItemQty(2)));
+        drop_batch(&world.ecs, BatchDrop::AllOf(ItemID::new(5)));
+        world.ecs.maintain();
+        assert_eq!(world.get::<Item>(pile).map(|item| item.qty), Some(ItemQty(97)));
+        assert!(!world.ecs.is_alive(rocks));
+
+        index(&mut world);
+        let rocks = bag_item(&mut world, "Rock", 5);
+        world.insert(rocks, Item::new(ItemID::new(5), ItemQty(10)));
+        drop_batch(&world.ecs, BatchDrop::AllOf(ItemID::new(5)));
+        world.ecs.maintain();
+        assert_eq!(world.get::<Item>(pile).map(|item| item.qty), Some(MAX_STACK_SIZE));
+        assert_eq!(world.get::<Item>(rocks).map(|item| item.qty), Some(ItemQty(8)));
+        assert_eq!(world.get::<Position>(rocks), Some(pos));
+    }
+}
@@ -0,0 +1,160 @@
+/* item_despawn.rs
+ *   Items left lying on the ground vanish after a while so dropped junk and loot don't pile up over a long game.
+ *   Each ground item gets a timer the turn it is first seen on the ground, it blinks for the last few turns as a
+ *   warning and then fades away. Items in a bag or a container never despawn and picking one up stops its timer.
+ * */
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use specs::{saveload::SimpleMarker, Component, Entities, Join, Read, ReadStorage, System, VecStorage, WriteStorage};
+
+use crate::{
+    components::{FadingOut, InBag, Item, Position},
+    saveload::SerializeMe,
+};
+
+/// How long a despawned item takes to fade out
+const ITEM_FADE_TIME: Duration = Duration::from_millis(400);
+/// Time the item is shown, then hidden, for each blink of the warning
+const BLINK_TIME: Duration = Duration::from_millis(250);
+
+/// How long items last on the ground, read from the settings when the game starts
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ItemDespawnConfig {
+    /// Items stay on the ground for good when off
+    pub enabled: bool,
+    /// Turns an item lies on the ground before it vanishes
+    pub despawn_turns: u32,
+    /// Turns before vanishing that the item starts blinking
+    pub warning_turns: u32,
+}
+
+impl Default for ItemDespawnConfig {
+    fn default() -> Self {
+        Self { enabled: true, despawn_turns: 300, warning_turns: 15 }
+    }
+}
+
+#[derive(Default)]
+pub struct ItemDespawn {
+    cfg: ItemDespawnConfig,
+}
+
+impl ItemDespawn {
+    pub fn new(cfg: ItemDespawnConfig) -> Self {
+        Self { cfg }
+    }
+
+    /// Whether the item's sprite is in the hidden half of a warning blink at `clock`
+    pub fn blinked_out(&self, timer: Option<&DespawnTimer>, clock: Duration) -> bool {
+        match timer {
+            Some(timer) if timer.turns_left <= self.cfg.warning_turns => {
+                (clock.as_millis() / BLINK_TIME.as_millis()) % 2 == 1
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Turns left before a ground item vanishes
+#[derive(Component, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[storage(VecStorage)]
+pub struct DespawnTimer {
+    pub turns_left: u32,
+}
+
+/// Counts down the timer of every item on the ground, starting one for items that just landed there. An item whose
+/// timer runs out stops being an item and fades away
+pub struct ItemDespawnSystem;
+
+impl<'a> System<'a> for ItemDespawnSystem {
+    type SystemData = (
+        WriteStorage<'a, DespawnTimer>,
+        WriteStorage<'a, Item>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, InBag>,
+        WriteStorage<'a, FadingOut>,
+        WriteStorage<'a, SimpleMarker<SerializeMe>>,
+        Read<'a, ItemDespawn>,
+        Entities<'a>,
+    );
+
+    fn run(
+        &mut self,
+        (mut timers, mut items, positions, inbags, mut fades, mut markers, despawn, entities): Self::SystemData,
+    ) {
+        if !despawn.cfg.enabled {
+            return;
+        }
+        let mut expired = Vec::new();
+        for (entity, _, _, _) in (&entities, &items, &positions, !&inbags).join() {
+            match timers.get_mut(entity) {
+                Some(timer) => {
+                    timer.turns_left = timer.turns_left.saturating_sub(1);
+                    if timer.turns_left == 0 {
+                        expired.push(entity);
+                    }
+                }
+                None => {
+                    let _ = timers.insert(entity, DespawnTimer { turns_left: despawn.cfg.despawn_turns });
+                }
+            }
+        }
+
+        for entity in expired {
+            // nothing can pick it up while it fades, the fade finishes the deletion
+            items.remove(entity);
+            timers.remove(entity);
+            // a save made mid fade would otherwise keep it around for good
+            markers.remove(entity);
+            let _ = fades.insert(entity, FadingOut::new(ITEM_FADE_TIME));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::{Builder, WorldExt};
+
+    use super::*;
+    use crate::{
+        components::PickupAction,
+        items::{ItemID, ItemPickupHandler, ItemQty},
+        test_harness::TestWorld,
+    };
+
+    #[test]
+    fn ground_items_blink_then_fade_unless_bagged_or_picked_up() {
+        let mut world = TestWorld::new();
+        world.ecs.insert(ItemDespawn::new(ItemDespawnConfig { enabled: true, despawn_turns: 3, warning_turns: 1 }));
+        let player = world.player();
+        let pos = Position::new(2, 2);
+        let ground = world.spawn("Stone", pos).with(Item::new(ItemID::new(1), ItemQty(1))).build();
+        let kept = world.spawn("Rope", pos).with(Item::new(ItemID::new(2), ItemQty(1))).build();
+        let bagged =
+            world.ecs.create_entity().with(Item::new(ItemID::new(3), ItemQty(1))).with(InBag { owner: player }).build();
+
+        world.run(ItemDespawnSystem);
+        assert_eq!(world.get::<DespawnTimer>(ground), Some(DespawnTimer { turns_left: 3 }));
+        assert!(!world.has::<DespawnTimer>(bagged));
+
+        world.insert(player, PickupAction::one(kept));
+        world.run(ItemPickupHandler);
+        assert!(!world.has::<DespawnTimer>(kept));
+
+        world.run(ItemDespawnSystem);
+        world.run(ItemDespawnSystem);
+        let despawn = world.ecs.read_resource::<ItemDespawn>();
+        let timer = world.get::<DespawnTimer>(ground);
+        assert!(despawn.blinked_out(timer.as_ref(), BLINK_TIME));
+        assert!(!despawn.blinked_out(timer.as_ref(), Duration::ZERO));
+        drop(despawn);
+
+        world.run(ItemDespawnSystem);
+        assert!(world.has::<FadingOut>(ground));
+        assert!(!world.has::<Item>(ground));
+        assert!(world.has::<Item>(kept) && world.has::<Item>(bagged));
+    }
+}
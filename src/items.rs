@@ -6,12 +6,13 @@
  */
 
 use std::{
+    collections::HashSet,
     fmt::Display,
     ops::{Add, Sub},
 };
 
 use log::{error, info, warn};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use specs::{
     saveload::{MarkerAllocator, SimpleMarker, SimpleMarkerAllocator},
     Entities, Entity, Join, Read, ReadStorage, System, World, WorldExt, Write, WriteStorage,
@@ -19,18 +20,30 @@ use specs::{
 
 use crate::{
     audio::play_sound_effect,
+    colors::{rarity_palette, PL_MENU_TEXT},
     components::{
-        AttackBonus, Consumable, ConsumeAction, Equipable, HealAction, InBag, Item, LevelPersistent, Name,
-        PickupAction, Position, Renderable,
+        AttackBonus, BaitActive, Boat, Buoyancy, Consumable, ConsumeAction, Container, Durability, Equipable,
+        HealAction, InBag, Item, LevelPersistent, MiningArea, Name, PickupAction, Position, Renderable, StatModifiers,
+        Tags, TargetedUse, WeaponCooldown, WeaponReach,
     },
-    data_read::prelude::*,
+    data_read::{deserialize_id, prelude::*},
     game_init::PlayerEntity,
+    hunger::Hunger,
+    item_despawn::DespawnTimer,
     saveload::SerializeMe,
+    shop::BACKPACK_SLOTS,
+    status::{apply_status, StatusEffect, StatusEffects, CONSUMABLE_STATUS_DURATION},
     storage_utils::MaybeInsert,
+    tile_animation::{AnimationRequest, TileAnimationBuilder},
+    toasts::Toasts,
     ui::message_log::MessageLog,
     z_order::ITEM_Z,
 };
 
+/// Most of one item a stack holds. Dropping more than fits on the ground starts another stack on the same tile,
+/// picking up more than fits in the backpack leaves the rest on the ground
+pub const MAX_STACK_SIZE: ItemQty = ItemQty(99);
+
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct ItemQty(pub usize);
 
@@ -40,19 +53,49 @@ impl Display for ItemQty {
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub enum QtyError {
+    /// The stack would hold more than a `usize` can count
+    Overflow,
+    /// More was taken than the stack holds
+    NotEnough { have: ItemQty, wanted: ItemQty },
+}
+
+impl Display for QtyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QtyError::Overflow => write!(f, "the stack can't hold any more"),
+            QtyError::NotEnough { have, wanted } => write!(f, "wanted {} but only {} are there", wanted, have),
+        }
+    }
+}
+
+impl ItemQty {
+    pub fn try_add(self, rhs: Self) -> Result<Self, QtyError> {
+        self.0.checked_add(rhs.0).map(Self).ok_or(QtyError::Overflow)
+    }
+
+    /// For removing items when taking less than asked for would be wrong, ie. paying for something
+    pub fn try_sub(self, rhs: Self) -> Result<Self, QtyError> {
+        self.0.checked_sub(rhs.0).map(Self).ok_or(QtyError::NotEnough { have: self, wanted: rhs })
+    }
+}
+
+/// Clamps at the largest quantity instead of wrapping around, use `try_add` to find out when that happens
 impl Add for ItemQty {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
-        Self(self.0 + rhs.0)
+        Self(self.0.saturating_add(rhs.0))
     }
 }
 
+/// Clamps at zero instead of wrapping around, use `try_sub` to find out when more was taken than there is
 impl Sub for ItemQty {
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        Self(self.0 - rhs.0)
+        Self(self.0.saturating_sub(rhs.0))
     }
 }
 
@@ -77,7 +120,6 @@ impl ItemSpawner {
         self.requests.push(ItemSpawnRequest { id, qty: ItemQty(1), spawn_type });
     }
 
-    #[allow(dead_code)]
     pub fn request_amt(&mut self, id: ItemID, spawn_type: SpawnType, qty: ItemQty) {
         self.requests.push(ItemSpawnRequest { id, qty, spawn_type });
     }
@@ -107,11 +149,21 @@ impl<'a> System<'a> for ItemSpawnerSystem {
         WriteStorage<'a, Name>,
         WriteStorage<'a, Equipable>,
         WriteStorage<'a, AttackBonus>,
+        WriteStorage<'a, StatModifiers>,
+        WriteStorage<'a, MiningArea>,
+        WriteStorage<'a, WeaponCooldown>,
+        WriteStorage<'a, WeaponReach>,
+        WriteStorage<'a, Durability>,
+        WriteStorage<'a, Boat>,
+        WriteStorage<'a, Buoyancy>,
         WriteStorage<'a, Consumable>,
+        WriteStorage<'a, TargetedUse>,
+        WriteStorage<'a, Tags>,
         WriteStorage<'a, LevelPersistent>,
         WriteStorage<'a, SimpleMarker<SerializeMe>>,
         Write<'a, SimpleMarkerAllocator<SerializeMe>>,
         Read<'a, PlayerEntity>,
+        Write<'a, TileAnimationBuilder>,
     );
 
     fn run(
@@ -126,20 +178,30 @@ impl<'a> System<'a> for ItemSpawnerSystem {
             mut names,
             mut equipables,
             mut attack_bonus,
+            mut stat_modifiers,
+            mut mining_areas,
+            mut weapon_cooldowns,
+            mut weapon_reaches,
+            mut durabilities,
+            mut boats,
+            mut buoyancies,
             mut consumables,
+            mut target_uses,
+            mut tags,
             mut persistents,
             mut serializables,
             mut mark_allocator,
             player_entity,
+            mut anim_builder,
         ): Self::SystemData,
     ) {
         let edb = &ENTITY_DB.lock().unwrap();
 
         for spawn in spawn_requests.requests.iter() {
-            let static_item = match edb.items.get_by_id(spawn.id) {
-                Some(val) => val,
-                None => {
-                    error!("Spawn request failed because {:?} item id does not exist in database", spawn.id);
+            let static_item = match edb.items.try_get_by_id(spawn.id) {
+                Ok(val) => val,
+                Err(e) => {
+                    error!("Spawn request failed because {}", e);
                     continue;
                 }
             };
@@ -149,6 +211,7 @@ impl<'a> System<'a> for ItemSpawnerSystem {
                 SpawnType::OnGround(pos) => {
                     let _ = positions.insert(new_item, pos);
                     let _ = items.insert(new_item, Item::new(spawn.id, spawn.qty));
+                    anim_builder.request(AnimationRequest::SpawnPop(new_item));
                 }
                 SpawnType::InBag(owner) => {
                     match (&entities, &items, &inbags)
@@ -183,6 +246,15 @@ impl<'a> System<'a> for ItemSpawnerSystem {
             equipables.maybe_insert(new_item, static_item.equipable.clone());
             consumables.maybe_insert(new_item, static_item.consumable.clone());
             attack_bonus.maybe_insert(new_item, static_item.attack_bonus.clone());
+            stat_modifiers.maybe_insert(new_item, static_item.stat_modifiers);
+            mining_areas.maybe_insert(new_item, static_item.mining_area.clone());
+            weapon_cooldowns.maybe_insert(new_item, static_item.attack_cooldown.clone());
+            weapon_reaches.maybe_insert(new_item, static_item.reach.clone());
+            durabilities.maybe_insert(new_item, static_item.durability.clone());
+            boats.maybe_insert(new_item, static_item.boat.clone());
+            let _ = buoyancies.insert(new_item, static_item.buoyancy);
+            target_uses.maybe_insert(new_item, static_item.target_use.clone());
+            tags.maybe_insert(new_item, static_item.tags.clone());
 
             let _ = renderables.insert(new_item, Renderable::clear_bg(static_item.atlas_index, static_item.fg, ITEM_Z));
             let _ = names.insert(new_item, Name(static_item.name.clone()));
@@ -192,6 +264,31 @@ impl<'a> System<'a> for ItemSpawnerSystem {
     }
 }
 
+/// Which items have had their pickup text shown this session. With `once` set an item's text is only shown the
+/// first time it is picked up, the usual pickup message is logged after that
+#[derive(Default)]
+pub struct PickupTexts {
+    once: bool,
+    shown: HashSet<ItemID>,
+}
+
+impl PickupTexts {
+    pub fn new(once: bool) -> Self {
+        Self { once, shown: HashSet::new() }
+    }
+
+    /// The pickup text to show for picking up `id`, `None` when it has none or it was already shown
+    pub fn take(&mut self, id: ItemID, text: Option<&String>) -> Option<String> {
+        let text = text?;
+        if self.once && !self.shown.insert(id) {
+            return None;
+        }
+        Some(text.clone())
+    }
+}
+
+/// Moves picked up items into the picker's backpack. Only as much as fits is taken, onto the stack they already
+/// carry up to `MAX_STACK_SIZE` or into a free slot, the rest stays on the ground
 pub struct ItemPickupHandler;
 
 impl<'a> System<'a> for ItemPickupHandler {
@@ -201,7 +298,12 @@ impl<'a> System<'a> for ItemPickupHandler {
         WriteStorage<'a, InBag>,
         WriteStorage<'a, Item>,
         WriteStorage<'a, LevelPersistent>,
+        WriteStorage<'a, DespawnTimer>,
+        ReadStorage<'a, Container>,
+        Write<'a, ItemSpawner>,
         Write<'a, MessageLog>,
+        Write<'a, Toasts>,
+        Write<'a, PickupTexts>,
         Read<'a, PlayerEntity>,
         ReadStorage<'a, Name>,
         Entities<'a>,
@@ -215,49 +317,124 @@ impl<'a> System<'a> for ItemPickupHandler {
             mut inbags,
             mut items,
             mut persistents,
+            mut despawn_timers,
+            containers,
+            mut item_spawner,
             mut log,
+            mut toasts,
+            mut pickup_texts,
             player_entity,
             names,
             entities,
         ): Self::SystemData,
     ) {
         for (picker, pickup, picker_name) in (&entities, &pickup_actions, &names).join() {
-            let ground_entity = pickup.item;
-            let item_name = match names.get(ground_entity) {
-                Some(name) => name.clone(),
-                None => Name::missing_item_name(),
-            };
-            let ground_item = match items.get(ground_entity) {
-                Some(item) => item,
-                None => {
-                    eprintln!("{:?} was not an item, it's name was {}", ground_entity, item_name);
+            // a gather reports one summary instead of a line for every item
+            let gathering = pickup.items.len() > 1;
+            let mut picked_up = 0;
+            let mut left_behind = 0;
+            // stacks requested from the spawner aren't in the backpack until it runs but still take up a slot
+            let mut spawned_stacks = 0;
+            for &ground_entity in pickup.items.iter() {
+                let item_name = match names.get(ground_entity) {
+                    Some(name) => name.clone(),
+                    None => Name::missing_item_name(),
+                };
+                let ground_item = match items.get(ground_entity) {
+                    Some(item) => item.clone(),
+                    None => {
+                        eprintln!("{:?} was not an item, it's name was {}", ground_entity, item_name);
+                        continue;
+                    }
+                };
+
+                let edb = &ENTITY_DB.lock().unwrap();
+                let bagged = (&entities, &items, &inbags)
+                    .join()
+                    .find(|(_, item, bag)| bag.owner == picker && item.id == ground_item.id)
+                    .map(|(bagged_entity, bagged_item, _)| (bagged_entity, bagged_item.qty));
+                let stacks = (&items, &inbags).join().filter(|(_, bag)| bag.owner == picker).count() + spawned_stacks;
+                let slots = containers.get(picker).map_or(BACKPACK_SLOTS, |container| container.slots);
+                let room = match bagged {
+                    Some((_, bagged_qty)) => MAX_STACK_SIZE - bagged_qty,
+                    None if stacks < slots => MAX_STACK_SIZE,
+                    None => ItemQty(0),
+                };
+                let taken = ItemQty(room.0.min(ground_item.qty.0));
+                if taken.0 == 0 {
+                    left_behind += 1;
+                    if player_entity.0 == picker && !gathering {
+                        log.log(format!("Backpack full, the {} is left on the ground.", item_name));
+                    }
                     continue;
                 }
-            };
 
-            let edb = &ENTITY_DB.lock().unwrap();
-            // TODO: check inventory capacity
-            match (&entities, &items, &inbags)
-                .join()
-                .find(|(_, item, bag)| bag.owner == picker && item.id == ground_item.id)
-            {
-                Some((bagged_entity, bagged_item, _)) => {
-                    let _ = items.insert(bagged_entity, Item::new(bagged_item.id, bagged_item.qty + ground_item.qty));
-                    let _ = entities.delete(ground_entity);
+                // anything that doesn't fit is left on the ground rather than lost
+                let left = ground_item.qty - taken;
+                match bagged {
+                    Some((bagged_entity, bagged_qty)) => {
+                        let _ = items.insert(bagged_entity, Item::new(ground_item.id, bagged_qty + taken));
+                        if left.0 == 0 {
+                            let _ = entities.delete(ground_entity);
+                        } else {
+                            let _ = items.insert(ground_entity, Item::new(ground_item.id, left));
+                        }
+                    }
+                    None if left.0 > 0 => {
+                        let _ = items.insert(ground_entity, Item::new(ground_item.id, left));
+                        item_spawner.request_amt(ground_item.id, SpawnType::InBag(picker), taken);
+                        spawned_stacks += 1;
+                    }
+                    None => {
+                        let _ = inbags.insert(ground_entity, InBag { owner: picker });
+                        if player_entity.0 == picker {
+                            let _ = persistents.insert(ground_entity, LevelPersistent {});
+                        }
+                        positions.remove(ground_entity);
+                        despawn_timers.remove(ground_entity);
+                    }
+                }
+                picked_up += 1;
+                if left.0 > 0 {
+                    left_behind += 1;
                 }
-                None => {
-                    let _ = inbags.insert(ground_entity, InBag { owner: picker });
-                    if player_entity.0 == picker {
-                        let _ = persistents.insert(ground_entity, LevelPersistent {});
+                let info = edb.items.get_by_id(ground_item.id);
+                // only the player's finds get flavor, others picking things up is just reported
+                let pickup_text = match info {
+                    Some(info) if player_entity.0 == picker => {
+                        pickup_texts.take(ground_item.id, info.pickup_text.as_ref())
                     }
-                    positions.remove(ground_entity);
-                    if let Some(text) = &edb.items.get_by_name_unchecked(&item_name.0).pickup_text {
-                        log.enhance(text);
+                    _ => None,
+                };
+                if !gathering {
+                    let colored_name = match info {
+                        Some(info) => info.colored_name(PL_MENU_TEXT),
+                        None => item_name.0.clone(),
+                    };
+                    if taken < ground_item.qty {
+                        log.log(format!("{} picked up {} {}, the rest does not fit", picker_name, taken, colored_name));
+                    } else if player_entity.0 != picker {
+                        log.log(format!("{} picked up a {}", picker_name, colored_name));
+                    } else if pickup_text.is_none() {
+                        log.log(format!("You pick up {}.", colored_name));
                     }
                 }
+                if let Some(text) = pickup_text {
+                    log.enhance(text);
+                }
+                if player_entity.0 == picker {
+                    toasts.gained(ground_item.id, &item_name.0, taken);
+                }
+            }
+
+            if gathering && player_entity.0 == picker {
+                let mut summary = format!("Picked up {} item{}", picked_up, if picked_up == 1 { "" } else { "s" });
+                if left_behind > 0 {
+                    summary.push_str(&format!(", {} left on the ground as the backpack is full", left_behind));
+                }
+                log.log(format!("{}.", summary));
             }
-            log.log(format!("{} picked up a {}", picker_name, item_name.0.to_lowercase()));
-            if player_entity.0 == picker {
+            if picked_up > 0 && player_entity.0 == picker {
                 play_sound_effect("pickup");
             }
         }
@@ -280,15 +457,21 @@ impl<'a> System<'a> for ZeroQtyItemCleanup {
     }
 }
 
-/// Checks to see if there is atleast one `target` item on the `owner`
+/// Checks to see if there is atleast one `target` item on the `owner`.
+/// A name missing from the item database is treated as not contained and warned about
 pub fn inventory_contains(target: &Name, owner: &Entity, ecs: &World) -> bool {
     // TODO: make this callable from systems
-    // pt2: check for id instead of name
+    let target_id = match ENTITY_DB.lock().unwrap().items.try_get_by_name(&target.0) {
+        Ok(info) => info.identifier,
+        Err(e) => {
+            warn!("Checked an inventory for an unknown item: {}", e);
+            return false;
+        }
+    };
     let items = ecs.read_storage::<Item>();
-    let names = ecs.read_storage::<Name>();
     let in_bags = ecs.read_storage::<InBag>();
 
-    (&items, &names, &in_bags).join().filter(|(_, name, bag)| name.eq(&target) && bag.owner.eq(owner)).count() >= 1
+    (&items, &in_bags).join().any(|(item, bag)| item.id == target_id && bag.owner.eq(owner))
 }
 
 pub struct ItemInfo {
@@ -296,16 +479,76 @@ pub struct ItemInfo {
     pub identifier: ItemID,
     pub name: String,
     pub examine_text: String,
-    pub atlas_index: u8,
+    pub atlas_index: usize,
     pub fg: (u8, u8, u8),
     pub pickup_text: Option<String>,
     pub equipable: Option<Equipable>,
     pub attack_bonus: Option<AttackBonus>,
+    pub stat_modifiers: Option<StatModifiers>,
+    pub mining_area: Option<MiningArea>,
+    pub attack_cooldown: Option<WeaponCooldown>,
+    pub reach: Option<WeaponReach>,
+    pub durability: Option<Durability>,
+    pub boat: Option<Boat>,
+    pub buoyancy: Buoyancy,
     pub consumable: Option<Consumable>,
+    pub target_use: Option<TargetedUse>,
+    pub rarity: Rarity,
+    pub value: u32,
+    pub tags: Option<Tags>,
 }
 
-#[derive(Serialize, Deserialize, Copy, Clone, Debug, Hash, Eq, PartialEq, Default, PartialOrd, Ord)]
-pub struct ItemID(pub u32);
+impl ItemInfo {
+    /// The item's name tinted by its rarity, `resume_palette` is the palette used for any text following the name
+    pub fn colored_name(&self, resume_palette: &str) -> String {
+        format!("#[{}]{}#[{}]", rarity_palette(self.rarity), self.name, resume_palette)
+    }
+
+    /// Anything rarer than common is worth telling the player about when it is lost
+    pub fn is_valuable(&self) -> bool {
+        self.rarity != Rarity::Common
+    }
+
+    /// Gold paid when buying the item from a shop
+    pub fn buy_price(&self) -> u32 {
+        self.value
+    }
+
+    /// Gold received when selling the item to a shop, shops only pay half
+    pub fn sell_price(&self) -> u32 {
+        self.value / 2
+    }
+}
+
+/// How hard an item is to come by, this changes the color of the item's name
+#[derive(Deserialize, Copy, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Rarity {
+    #[default]
+    Common,
+    Uncommon,
+    Rare,
+}
+
+/// Unique id of an item's static data in the raws, ordered by the number so sorting by id is stable
+#[derive(Serialize, Copy, Clone, Debug, Hash, Eq, PartialEq, Default, PartialOrd, Ord)]
+pub struct ItemID(u32);
+
+impl ItemID {
+    pub const fn new(id: u32) -> Self {
+        Self(id)
+    }
+
+    pub const fn value(self) -> u32 {
+        self.0
+    }
+}
+
+impl<'de> Deserialize<'de> for ItemID {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_id(deserializer, "item").map(Self)
+    }
+}
 
 impl Display for ItemID {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -325,12 +568,29 @@ impl<'a> System<'a> for ConsumeHandler {
     type SystemData = (
         WriteStorage<'a, ConsumeAction>,
         WriteStorage<'a, HealAction>,
+        WriteStorage<'a, BaitActive>,
+        WriteStorage<'a, StatusEffects>,
+        WriteStorage<'a, Hunger>,
         WriteStorage<'a, Item>,
         ReadStorage<'a, Consumable>,
+        Write<'a, MessageLog>,
         Entities<'a>,
     );
 
-    fn run(&mut self, (mut consume_actions, mut heal_actions, mut items, consumables, entities): Self::SystemData) {
+    fn run(
+        &mut self,
+        (
+            mut consume_actions,
+            mut heal_actions,
+            mut baits,
+            mut statuses,
+            mut hungers,
+            mut items,
+            consumables,
+            mut log,
+            entities,
+        ): Self::SystemData,
+    ) {
         for (consumer, consume) in (&entities, &consume_actions).join() {
             let (_, item, consumable) =
                 match (&entities, &mut items, &consumables).join().find(|(e, _, _)| *e == consume.consuming) {
@@ -342,9 +602,136 @@ impl<'a> System<'a> for ConsumeHandler {
                     let _ = heal_actions.insert(consumer, HealAction { amount: *amount });
                     item.qty.0 = item.qty.0.saturating_sub(1);
                 }
+                Consumable::Bait(potency) => {
+                    // Only one bait can be on the line at a time
+                    if baits.contains(consumer) {
+                        log.log("There is already bait on the line.");
+                        continue;
+                    }
+                    let _ = baits.insert(consumer, BaitActive { potency: *potency });
+                    item.qty.0 = item.qty.0.saturating_sub(1);
+                    log.log("You put some bait on the line.");
+                }
+                Consumable::Status(kind, magnitude) => {
+                    apply_status(
+                        &mut statuses,
+                        consumer,
+                        StatusEffect::new(*kind, *magnitude, CONSUMABLE_STATUS_DURATION),
+                    );
+                    item.qty.0 = item.qty.0.saturating_sub(1);
+                }
+                Consumable::Feed(amount) => {
+                    if let Some(hunger) = hungers.get_mut(consumer) {
+                        hunger.eat(*amount as u32);
+                    }
+                    item.qty.0 = item.qty.0.saturating_sub(1);
+                }
             }
         }
 
         consume_actions.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use specs::Builder;
+
+    use super::*;
+    use crate::test_harness::TestWorld;
+
+    fn bag_item(world: &mut TestWorld, owner: Entity, id: ItemID, qty: usize) -> Entity {
+        world.ecs.create_entity().with(Item::new(id, ItemQty(qty))).with(InBag { owner }).build()
+    }
+
+    fn carried(world: &TestWorld, owner: Entity, id: ItemID) -> usize {
+        let items = world.ecs.read_storage::<Item>();
+        let in_bags = world.ecs.read_storage::<InBag>();
+        (&items, &in_bags)
+            .join()
+            .filter(|(item, bag)| bag.owner == owner && item.id == id)
+            .map(|(item, _)| item.qty.0)
+            .sum()
+    }
+
+    #[test]
+    fn quantities_clamp_instead_of_wrapping() {
+        let most = ItemQty(usize::MAX);
+        assert_eq!(most + ItemQty(1), most);
+        assert_eq!(ItemQty(2) - ItemQty(5), ItemQty(0));
+        assert_eq!(ItemQty(5) - ItemQty(5), ItemQty(0));
+    }
+
+    #[test]
+    fn checked_quantities_report_the_boundaries() {
+        assert_eq!(ItemQty(usize::MAX - 1).try_add(ItemQty(1)), Ok(ItemQty(usize::MAX)));
+        assert_eq!(ItemQty(usize::MAX).try_add(ItemQty(1)), Err(QtyError::Overflow));
+        assert_eq!(ItemQty(3).try_sub(ItemQty(3)), Ok(ItemQty(0)));
+        assert_eq!(ItemQty(3).try_sub(ItemQty(4)), Err(QtyError::NotEnough { have: ItemQty(3), wanted: ItemQty(4) }));
+    }
+
+    #[test]
+    fn pickups_are_refused_when_the_backpack_is_full() {
+        let mut world = TestWorld::new();
+        let player = world.player();
+        world.insert(player, Container { slots: 1 });
+        bag_item(&mut world, player, ItemID::new(1), 1);
+        let rock = world.spawn("Rock", Position::new(2, 2)).with(Item::new(ItemID::new(2), ItemQty(1))).build();
+        world.insert(player, PickupAction::one(rock));
+
+        world.run(ItemPickupHandler);
+        assert!(world.logged("Backpack full"));
+        assert_eq!(world.get::<Position>(rock), Some(Position::new(2, 2)));
+        assert_eq!(carried(&world, player, ItemID::new(2)), 0);
+        assert!(!world.has::<PickupAction>(player));
+    }
+
+    #[test]
+    fn stacks_take_what_fits_and_leave_the_rest() {
+        let mut world = TestWorld::new();
+        let player = world.player();
+        let worms = ItemID::new(3);
+        bag_item(&mut world, player, worms, MAX_STACK_SIZE.0 - 5);
+        let pile = world.spawn("Worm", Position::new(2, 2)).with(Item::new(worms, ItemQty(8))).build();
+        world.insert(player, PickupAction::one(pile));
+
+        world.run(ItemPickupHandler);
+        assert!(world.logged("the rest does not fit"));
+        assert_eq!(carried(&world, player, worms), MAX_STACK_SIZE.0);
+        assert_eq!(world.get::<Item>(pile).map(|item| item.qty), Some(ItemQty(3)));
+        assert_eq!(world.get::<Position>(pile), Some(Position::new(2, 2)));
+    }
+
+    #[test]
+    fn pickup_text_is_shown_once_per_item_when_configured() {
+        let text = "Rare find!".to_string();
+        let (rod, hammer) = (ItemID::new(201), ItemID::new(202));
+        let mut once = PickupTexts::new(true);
+        assert_eq!(once.take(rod, Some(&text)), Some(text.clone()));
+        assert_eq!(once.take(rod, Some(&text)), None);
+        assert_eq!(once.take(hammer, None), None);
+        assert_eq!(once.take(hammer, Some(&text)), Some(text.clone()));
+
+        let mut always = PickupTexts::new(false);
+        assert_eq!(always.take(rod, Some(&text)), Some(text.clone()));
+        assert_eq!(always.take(rod, Some(&text)), Some(text));
+    }
+
+    #[test]
+    fn gathering_sums_up_what_was_picked_up() {
+        let mut world = TestWorld::new();
+        let player = world.player();
+        world.insert(player, Container { slots: 2 });
+        let rock = world.spawn("Rock", Position::new(2, 2)).with(Item::new(ItemID::new(2), ItemQty(1))).build();
+        let more_rocks = world.spawn("Rock", Position::new(3, 2)).with(Item::new(ItemID::new(2), ItemQty(2))).build();
+        let stick = world.spawn("Stick", Position::new(2, 3)).with(Item::new(ItemID::new(4), ItemQty(1))).build();
+        let leaf = world.spawn("Leaf", Position::new(1, 1)).with(Item::new(ItemID::new(5), ItemQty(1))).build();
+        world.insert(player, PickupAction { items: vec![rock, more_rocks, stick, leaf] });
+
+        world.run(ItemPickupHandler);
+        assert!(world.logged("Picked up 3 items, 1 left on the ground"));
+        assert_eq!(carried(&world, player, ItemID::new(2)), 3);
+        assert_eq!(carried(&world, player, ItemID::new(4)), 1);
+        assert_eq!(world.get::<Position>(leaf), Some(Position::new(1, 1)));
+    }
+}
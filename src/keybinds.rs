@@ -0,0 +1,203 @@
+//! Every control the player has, listed in one place so the help overlay can show them.
+//! When a key is bound or changed in one of the `p_input_*` functions its entry here should be updated to match.
+//!
+//! Modifiers are read along with the key as a `KeyPress`. When more than one is held only the strongest counts, Ctrl
+//! then Alt then Shift, so Ctrl+Shift+D is a Ctrl combo and never a Shift one. Ctrl combos belong to the debug
+//! controls in `debug_input` and are ignored while playing.
+
+use bracket_lib::terminal::{BTerm, VirtualKeyCode as VKC};
+
+use crate::colors::{PL_KEYBIND, PL_MENU_ACCENT_TEXT, PL_MENU_TEXT};
+use KeybindCategory::{Actions, Movement, Ui};
+
+/// The modifier a key was pressed with, ordered by precedence with the strongest last
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Modifier {
+    Plain,
+    Shift,
+    Alt,
+    Ctrl,
+}
+
+impl Modifier {
+    /// The strongest of the held modifiers
+    pub fn held(shift: bool, control: bool, alt: bool) -> Self {
+        if control {
+            Modifier::Ctrl
+        } else if alt {
+            Modifier::Alt
+        } else if shift {
+            Modifier::Shift
+        } else {
+            Modifier::Plain
+        }
+    }
+}
+
+/// A key along with the modifier held when it was pressed
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct KeyPress {
+    pub key: VKC,
+    pub modifier: Modifier,
+}
+
+impl KeyPress {
+    pub fn new(key: VKC, modifier: Modifier) -> Self {
+        Self { key, modifier }
+    }
+
+    /// The key pressed this frame, if any
+    pub fn read(ctx: &BTerm) -> Option<Self> {
+        ctx.key.map(|key| Self::new(key, Modifier::held(ctx.shift, ctx.control, ctx.alt)))
+    }
+}
+
+/// The step a movement key takes
+pub fn movement_delta(key: VKC) -> Option<(i32, i32)> {
+    match key {
+        VKC::W | VKC::Up => Some((0, -1)),
+        VKC::S | VKC::Down => Some((0, 1)),
+        VKC::A | VKC::Left => Some((-1, 0)),
+        VKC::D | VKC::Right => Some((1, 0)),
+        _ => None,
+    }
+}
+
+/// How many rows of the help list fit in the overlay at once
+pub const HELP_VISIBLE_ROWS: usize = 30;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum KeybindCategory {
+    Movement,
+    Actions,
+    Ui,
+}
+
+impl KeybindCategory {
+    const ALL: [KeybindCategory; 3] = [KeybindCategory::Movement, KeybindCategory::Actions, KeybindCategory::Ui];
+
+    fn title(&self) -> &str {
+        match self {
+            KeybindCategory::Movement => "Movement",
+            KeybindCategory::Actions => "Actions",
+            KeybindCategory::Ui => "Menus",
+        }
+    }
+}
+
+pub struct Keybind {
+    pub category: KeybindCategory,
+    pub keys: &'static str,
+    pub action: &'static str,
+}
+
+const fn bind(category: KeybindCategory, keys: &'static str, action: &'static str) -> Keybind {
+    Keybind { category, keys, action }
+}
+
+pub const KEYBINDS: &[Keybind] = &[
+    bind(Movement, "W A S D / Arrows", "Move or attack"),
+    bind(Movement, "Into water", "Paddle out when carrying a boat"),
+    bind(Movement, "Shift+Move", "Dash that way"),
+    bind(Movement, "Alt+Move", "Turn to face that way without moving"),
+    bind(Movement, "R", "Dash the way you are facing"),
+    bind(Movement, "Click a tile", "Travel there, any key stops"),
+    bind(Movement, "Space / .", "Wait a turn"),
+    bind(Movement, "Z", "Rest until healed or disturbed"),
+    bind(Movement, ">", "Dig down or take stairs down"),
+    bind(Movement, "<", "Take stairs up"),
+    bind(Actions, "E", "Interact with the faced tile"),
+    bind(Actions, "P", "Pick up items"),
+    bind(Actions, "G", "Gather every item on and around you"),
+    bind(Actions, "Q", "Slam the ground, hitting everything around you"),
+    bind(Actions, "F", "Target an enemy to attack"),
+    bind(Actions, "Tab / Shift+Tab", "Cycle targets or the examined thing"),
+    bind(Actions, "M", "Switch interaction mode"),
+    bind(Actions, "X", "Look around and examine what is there"),
+    bind(Actions, "1-5", "Use a hotbar slot"),
+    bind(Actions, "K / R", "Keep or release a landed fish"),
+    bind(Ui, "I", "Open the inventory"),
+    bind(Ui, "1-9 A-H", "Select an inventory item"),
+    bind(Ui, "U E D Q C T", "Use, examine, drop, equip, consume or target with an item"),
+    bind(Ui, "/", "Search the inventory"),
+    bind(Ui, "Shift+D", "Drop every item of the selected kind"),
+    bind(Ui, "X", "Drop everything not equipped"),
+    bind(Ui, "S", "Change how the inventory is sorted"),
+    bind(Ui, "O", "Sort again, new items are listed last until then"),
+    bind(Ui, "Enter", "Continue dialogue or confirm"),
+    bind(Ui, "Esc", "Pause, close or cancel, also stops fishing or cooking"),
+    bind(Ui, "? / F1", "Show this help"),
+    bind(Ui, "F8", "Toggle the minimal hud"),
+    bind(Ui, "F9", "Switch the terrain skin"),
+    bind(Ui, "F10", "Toggle crt effects"),
+];
+
+/// The help list as printable rows, each category has a title followed by its bindings
+pub fn help_rows() -> Vec<String> {
+    let mut rows = Vec::new();
+    for category in KeybindCategory::ALL {
+        if !rows.is_empty() {
+            rows.push(String::new());
+        }
+        rows.push(format!("#[{}]{}#[]", PL_MENU_ACCENT_TEXT, category.title()));
+        for keybind in KEYBINDS.iter().filter(|keybind| keybind.category == category) {
+            rows.push(format!("#[{}]{:>16} #[{}]{}#[]", PL_KEYBIND, keybind.keys, PL_MENU_TEXT, keybind.action));
+        }
+    }
+    rows
+}
+
+/// Furthest the help list can be scrolled before its end would leave the overlay
+pub fn max_help_scroll() -> usize {
+    help_rows().len().saturating_sub(HELP_VISIBLE_ROWS)
+}
+
+pub fn is_help_key(ctx: &BTerm) -> bool {
+    match ctx.key {
+        Some(VKC::F1) => true,
+        Some(VKC::Slash) => ctx.shift,
+        _ => false,
+    }
+}
+
+pub enum HelpAction {
+    Scroll(usize),
+    Close,
+    Waiting,
+}
+
+pub fn p_input_help(ctx: &BTerm, scroll: usize) -> HelpAction {
+    if is_help_key(ctx) {
+        return HelpAction::Close;
+    }
+    let scrolled = match ctx.key {
+        Some(VKC::Escape) => return HelpAction::Close,
+        Some(VKC::W | VKC::Up) => scroll.saturating_sub(1),
+        Some(VKC::S | VKC::Down) => scroll + 1,
+        Some(VKC::PageUp) => scroll.saturating_sub(HELP_VISIBLE_ROWS),
+        Some(VKC::PageDown) => scroll + HELP_VISIBLE_ROWS,
+        _ => return HelpAction::Waiting,
+    };
+    HelpAction::Scroll(scrolled.min(max_help_scroll()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_category_has_bindings() {
+        for category in KeybindCategory::ALL {
+            assert!(KEYBINDS.iter().any(|keybind| keybind.category == category));
+        }
+        assert_eq!(help_rows().len(), KEYBINDS.len() + KeybindCategory::ALL.len() * 2 - 1);
+    }
+
+    #[test]
+    fn strongest_modifier_wins() {
+        assert_eq!(Modifier::held(false, false, false), Modifier::Plain);
+        assert_eq!(Modifier::held(true, false, false), Modifier::Shift);
+        assert_eq!(Modifier::held(true, false, true), Modifier::Alt);
+        assert_eq!(Modifier::held(true, true, true), Modifier::Ctrl);
+    }
+}
@@ -0,0 +1,262 @@
+use log::error;
+use serde::{Deserialize, Serialize};
+use specs::{Entity, Join, RunNow, World, WorldExt};
+
+use crate::{
+    camera::Camera,
+    components::{Blocking, Boating, LayerStash, LevelPersistent, Name, Position, Transform, Viewshed},
+    currents::set_currents,
+    data_read::prelude::build_world_obj,
+    game_init::PlayerEntity,
+    indexing::{
+        IndexBlockedTiles, IndexBreakableTiles, IndexFishableTiles, IndexItemTiles, IndexReset, IndexStaticTiles,
+    },
+    map::{floor_tile, Map, MapRes},
+    map_gen::{autotile_around, gen_layer},
+    player::PlayerResponse,
+    respawn::RespawnQueue,
+    ui::message_log::MessageLog,
+};
+
+pub const MAX_LAYERS: usize = 10;
+pub const STAIRS_DOWN: &str = "Stairs Down";
+pub const STAIRS_UP: &str = "Stairs Up";
+
+/// Every layer of the world, the one being played on lives in `MapRes` and the rest wait here
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MapLayers {
+    /// 0 is the surface, higher is deeper
+    pub current: usize,
+    /// How many layers the world has, 1 keeps the world to just the surface
+    pub count: usize,
+    /// Layers below the surface are generated from this the first time they are visited
+    seed: u64,
+    /// `None` for the layer in `MapRes` and layers that have not been visited
+    stored: Vec<Option<Map>>,
+}
+
+impl Default for MapLayers {
+    fn default() -> Self {
+        Self::new(1, 0)
+    }
+}
+
+impl MapLayers {
+    pub fn new(count: usize, seed: u64) -> Self {
+        let count = count.clamp(1, MAX_LAYERS);
+        Self { current: 0, count, seed, stored: vec![None; count] }
+    }
+
+    pub fn is_deepest(&self) -> bool {
+        self.current + 1 >= self.count
+    }
+}
+
+/// Digs through the floor or takes the stairs under the player down a layer
+pub fn try_descend(ecs: &mut World) -> PlayerResponse {
+    let pos = match player_position(ecs) {
+        Some(pos) => pos,
+        None => return PlayerResponse::Waiting,
+    };
+    if ecs.read_resource::<MapLayers>().is_deepest() {
+        ecs.fetch_mut::<MessageLog>().log("The ground here is too hard to dig through.");
+        return PlayerResponse::Waiting;
+    }
+    if ecs.read_storage::<Boating>().contains(ecs.read_resource::<PlayerEntity>().0) {
+        ecs.fetch_mut::<MessageLog>().log("You can't dig down from a boat.");
+        return PlayerResponse::Waiting;
+    }
+
+    if !stairs_at(ecs, &pos, STAIRS_DOWN) {
+        if let Err(e) = build_world_obj(STAIRS_DOWN, pos, ecs) {
+            error!("Could not dig down: {}", e);
+            return PlayerResponse::Waiting;
+        }
+        ecs.fetch_mut::<MessageLog>().log("You dig a hole through the floor.");
+    }
+
+    let depth = ecs.read_resource::<MapLayers>().current + 1;
+    change_layer(ecs, depth, pos);
+    ensure_stairs(ecs, pos, STAIRS_UP);
+    ecs.fetch_mut::<MessageLog>().log(format!("You climb down to depth {}.", depth));
+    PlayerResponse::TurnAdvance
+}
+
+/// Takes the stairs under the player up a layer
+pub fn try_ascend(ecs: &mut World) -> PlayerResponse {
+    let pos = match player_position(ecs) {
+        Some(pos) => pos,
+        None => return PlayerResponse::Waiting,
+    };
+    let current = ecs.read_resource::<MapLayers>().current;
+    if current == 0 || !stairs_at(ecs, &pos, STAIRS_UP) {
+        ecs.fetch_mut::<MessageLog>().log("There are no stairs up here.");
+        return PlayerResponse::Waiting;
+    }
+
+    let depth = current - 1;
+    change_layer(ecs, depth, pos);
+    ensure_stairs(ecs, pos, STAIRS_DOWN);
+    let mut log = ecs.fetch_mut::<MessageLog>();
+    match depth {
+        0 => log.log("You climb back up to the surface."),
+        depth => log.log(format!("You climb up to depth {}.", depth)),
+    }
+    PlayerResponse::TurnAdvance
+}
+
+/// Brings the player straight back up to `arrival` on the surface from whatever depth they are at
+pub fn return_to_surface(ecs: &mut World, arrival: Position) {
+    if ecs.read_resource::<MapLayers>().current != 0 {
+        change_layer(ecs, 0, arrival);
+    }
+}
+
+/// Swaps the layer in `MapRes` for `to` and brings the player along to `arrival`
+fn change_layer(ecs: &mut World, to: usize, arrival: Position) {
+    let player = ecs.read_resource::<PlayerEntity>().0;
+    let from = ecs.read_resource::<MapLayers>().current;
+    stash_layer(ecs, from);
+    // respawns only know their tile and not the layer so whatever was waiting to grow back is dropped
+    ecs.write_resource::<RespawnQueue>().clear();
+
+    let stored = ecs.write_resource::<MapLayers>().stored[to].take();
+    let mut next = match stored {
+        Some(map) => map,
+        None => {
+            let size = {
+                let map = ecs.fetch::<MapRes>();
+                (map.0.width, map.0.height)
+            };
+            let seed = ecs.read_resource::<MapLayers>().seed;
+            gen_layer(ecs, size, seed, to, &arrival)
+        }
+    };
+    // stored layers do not keep their entity index so it is rebuilt below
+    next.tile_entities = vec![Vec::new(); next.width * next.height];
+    let prev = std::mem::replace(&mut ecs.write_resource::<MapRes>().0, next);
+    {
+        let mut layers = ecs.write_resource::<MapLayers>();
+        layers.stored[from] = Some(prev);
+        layers.current = to;
+    }
+
+    unstash_layer(ecs, to);
+    set_currents(&ecs.fetch::<MapRes>().0, ecs);
+    if let Some(pos) = ecs.write_storage::<Position>().get_mut(player) {
+        *pos = arrival;
+    }
+    clear_arrival(ecs, to, &arrival);
+    index_layer(ecs);
+    if let Some(view) = ecs.write_storage::<Viewshed>().get_mut(player) {
+        view.dirty = true;
+    }
+    ecs.write_resource::<Camera>().snap();
+}
+
+/// Takes everything on the layer out of the world, besides the player and what they carry
+fn stash_layer(ecs: &mut World, layer: usize) {
+    let leaving: Vec<(Entity, Position, bool)> = {
+        let entities = ecs.entities();
+        let positions = ecs.read_storage::<Position>();
+        let persistents = ecs.read_storage::<LevelPersistent>();
+        let transforms = ecs.read_storage::<Transform>();
+        (&entities, &positions, !&persistents).join().map(|(e, pos, _)| (e, *pos, transforms.contains(e))).collect()
+    };
+
+    let mut positions = ecs.write_storage::<Position>();
+    let mut transforms = ecs.write_storage::<Transform>();
+    let mut stashes = ecs.write_storage::<LayerStash>();
+    for (e, pos, fancy) in leaving {
+        positions.remove(e);
+        transforms.remove(e);
+        let _ = stashes.insert(e, LayerStash { layer, pos, fancy });
+    }
+}
+
+/// Puts everything that was left on the layer back where it was
+fn unstash_layer(ecs: &mut World, layer: usize) {
+    let returning: Vec<(Entity, LayerStash)> = {
+        let entities = ecs.entities();
+        let stashes = ecs.read_storage::<LayerStash>();
+        (&entities, &stashes).join().filter(|(_, stash)| stash.layer == layer).map(|(e, s)| (e, s.clone())).collect()
+    };
+
+    let mut positions = ecs.write_storage::<Position>();
+    let mut transforms = ecs.write_storage::<Transform>();
+    let mut stashes = ecs.write_storage::<LayerStash>();
+    for (e, stash) in returning {
+        stashes.remove(e);
+        let _ = positions.insert(e, stash.pos);
+        if stash.fancy {
+            let _ = transforms.insert(e, Transform::new(stash.pos.x as f32, stash.pos.y as f32, 0.0, 1.0, 1.0));
+        }
+    }
+}
+
+/// Anything blocking where the player lands is dug out so they are never stuck inside a wall
+fn clear_arrival(ecs: &mut World, depth: usize, arrival: &Position) {
+    let player = ecs.read_resource::<PlayerEntity>().0;
+    let blockers: Vec<Entity> = {
+        let entities = ecs.entities();
+        let positions = ecs.read_storage::<Position>();
+        let blocking = ecs.read_storage::<Blocking>();
+        (&entities, &positions, &blocking)
+            .join()
+            .filter(|(e, pos, _)| *e != player && *pos == arrival)
+            .map(|(e, ..)| e)
+            .collect()
+    };
+    for blocker in blockers {
+        let _ = ecs.entities().delete(blocker);
+    }
+    ecs.maintain();
+
+    let mut map = ecs.write_resource::<MapRes>();
+    if map.0.checked_xy_to_idx(arrival.x, arrival.y).is_some_and(|idx| map.0.tiles[idx].is_blocked) {
+        map.0.set_tile(&floor_tile(depth), arrival.x, arrival.y);
+        autotile_around(&mut map.0, arrival.x, arrival.y);
+    }
+}
+
+fn index_layer(ecs: &World) {
+    IndexReset.run_now(ecs);
+    IndexStaticTiles.run_now(ecs);
+    IndexBlockedTiles.run_now(ecs);
+    IndexBreakableTiles.run_now(ecs);
+    IndexFishableTiles.run_now(ecs);
+    IndexItemTiles.run_now(ecs);
+}
+
+fn ensure_stairs(ecs: &mut World, pos: Position, name: &str) {
+    if stairs_at(ecs, &pos, name) {
+        return;
+    }
+    if let Err(e) = build_world_obj(name, pos, ecs) {
+        error!("{} could not be placed: {}", name, e);
+    }
+}
+
+fn stairs_at(ecs: &World, pos: &Position, name: &str) -> bool {
+    let positions = ecs.read_storage::<Position>();
+    let names = ecs.read_storage::<Name>();
+    (&positions, &names).join().any(|(at, at_name)| at == pos && at_name.0 == name)
+}
+
+fn player_position(ecs: &World) -> Option<Position> {
+    let player = ecs.read_resource::<PlayerEntity>().0;
+    ecs.read_storage::<Position>().get(player).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layer_count_is_kept_in_range() {
+        assert_eq!(MapLayers::new(0, 0).count, 1);
+        assert_eq!(MapLayers::new(MAX_LAYERS + 5, 0).count, MAX_LAYERS);
+        assert!(MapLayers::default().is_deepest());
+        assert!(!MapLayers::new(3, 0).is_deepest());
+    }
+}
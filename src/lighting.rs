@@ -0,0 +1,182 @@
+/* lighting.rs
+ *   Light sources brighten the tiles around them while everything else is drawn at the ambient light of the layer.
+ *   The light on each tile is only worked out again when a light moves or the tiles it shines across change, so the
+ *   drawing only has to look it up.
+ * */
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+use bracket_lib::{
+    pathfinding::field_of_view_set,
+    terminal::{DistanceAlg, Point, RGBA},
+};
+use serde::{Deserialize, Serialize};
+use specs::{Join, Read, ReadStorage, System, Write};
+
+use crate::{
+    components::{LightSource, Position},
+    layers::MapLayers,
+    map::MapRes,
+    weather::is_outdoors,
+};
+
+/// The player always carries a little light so caves are never pitch black around them
+pub const PLAYER_LIGHT: LightSource = LightSource { radius: 4, color: (255, 225, 180) };
+
+/// Red, green and blue brightness, 1.0 draws a color as it is
+type Light = (f32, f32, f32);
+
+/// How dark the world is away from any light, read from the settings when the game starts
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LightingConfig {
+    /// From 0.0 for black to 1.0 where lights make no difference
+    pub surface_ambient: f32,
+    pub cave_ambient: f32,
+}
+
+impl Default for LightingConfig {
+    fn default() -> Self {
+        Self { surface_ambient: 1.0, cave_ambient: 0.35 }
+    }
+}
+
+/// The light falling on the map's tiles
+pub struct LightMap {
+    cfg: LightingConfig,
+    ambient: f32,
+    /// Light added up from every source that reaches the tile, tiles no light reaches are left out
+    lit: HashMap<Point, Light>,
+    /// Hash of the lights and tiles the map was last lit from
+    fingerprint: Option<u64>,
+}
+
+impl Default for LightMap {
+    fn default() -> Self {
+        Self::new(LightingConfig::default())
+    }
+}
+
+impl LightMap {
+    pub fn new(cfg: LightingConfig) -> Self {
+        Self { ambient: cfg.surface_ambient, cfg, lit: HashMap::new(), fingerprint: None }
+    }
+
+    /// Forgets the lighting of the last world
+    pub fn reset(&mut self) {
+        *self = Self::new(self.cfg.clone());
+    }
+
+    /// How bright the tile at the map position is
+    fn at(&self, pos: Point) -> Light {
+        let (r, g, b) = self.lit.get(&pos).copied().unwrap_or_default();
+        ((self.ambient + r).min(1.0), (self.ambient + g).min(1.0), (self.ambient + b).min(1.0))
+    }
+
+    /// The color as it looks under the light at the map position
+    pub fn shade(&self, color: RGBA, pos: Point) -> RGBA {
+        let (r, g, b) = self.at(pos);
+        RGBA::from_f32(color.r * r, color.g * g, color.b * b, color.a)
+    }
+}
+
+/// Full brightness on the source fading to nothing just past the radius
+fn falloff(distance: f32, radius: u32) -> f32 {
+    (1.0 - distance / (radius as f32 + 1.0)).max(0.0)
+}
+
+/// Relights the map when a light or the tiles around one have changed, overlapping lights add together
+pub struct LightingSystem;
+
+impl<'a> System<'a> for LightingSystem {
+    type SystemData = (
+        ReadStorage<'a, LightSource>,
+        ReadStorage<'a, Position>,
+        Read<'a, MapRes>,
+        Read<'a, MapLayers>,
+        Write<'a, LightMap>,
+    );
+
+    fn run(&mut self, (lights, positions, map, layers, mut light_map): Self::SystemData) {
+        let map = &map.0;
+        let ambient = if is_outdoors(&layers) { light_map.cfg.surface_ambient } else { light_map.cfg.cave_ambient };
+        let sources: Vec<(Position, LightSource)> = (&positions, &lights).join().map(|(p, l)| (*p, *l)).collect();
+
+        // only the tiles a light could reach matter, a wall mined out elsewhere doesn't change anything
+        let mut hasher = DefaultHasher::new();
+        (ambient.to_bits(), map.width, map.height, layers.current).hash(&mut hasher);
+        for (pos, light) in sources.iter() {
+            (pos, light.radius, light.color).hash(&mut hasher);
+            let radius = light.radius as usize;
+            for y in pos.y.saturating_sub(radius)..=pos.y + radius {
+                for x in pos.x.saturating_sub(radius)..=pos.x + radius {
+                    map.checked_xy_to_idx(x, y).map(|idx| map.tiles[idx].transparent).hash(&mut hasher);
+                }
+            }
+        }
+        let fingerprint = hasher.finish();
+        if light_map.fingerprint == Some(fingerprint) {
+            return;
+        }
+
+        light_map.fingerprint = Some(fingerprint);
+        light_map.ambient = ambient;
+        light_map.lit.clear();
+        for (pos, light) in sources {
+            let (r, g, b) = light.color;
+            let (r, g, b) = (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+            let source = pos.to_point();
+            for point in field_of_view_set(source, light.radius as i32, map) {
+                let strength = falloff(DistanceAlg::Pythagoras.distance2d(source, point), light.radius);
+                let tile = light_map.lit.entry(point).or_default();
+                *tile = (tile.0 + r * strength, tile.1 + g * strength, tile.2 + b * strength);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::{Builder, WorldExt};
+
+    use super::*;
+    use crate::test_harness::TestWorld;
+
+    const WHITE_LIGHT: LightSource = LightSource { radius: 3, color: (255, 255, 255) };
+
+    fn lit(world: &TestWorld, x: i32, y: i32) -> f32 {
+        world.ecs.read_resource::<LightMap>().lit.get(&Point::new(x, y)).map_or(0.0, |light| light.0)
+    }
+
+    #[test]
+    fn lights_fade_out_and_add_together() {
+        let mut world = TestWorld::new();
+        world.ecs.create_entity().with(Position::new(5, 5)).with(WHITE_LIGHT).build();
+        world.run(LightingSystem);
+        assert_eq!(lit(&world, 5, 5), 1.0);
+        assert_eq!(lit(&world, 7, 5), 0.5);
+        assert_eq!(lit(&world, 9, 5), 0.0);
+
+        world.ecs.create_entity().with(Position::new(9, 5)).with(WHITE_LIGHT).build();
+        world.run(LightingSystem);
+        assert_eq!(lit(&world, 7, 5), 1.0);
+    }
+
+    #[test]
+    fn only_relit_when_a_light_changes() {
+        let mut world = TestWorld::new();
+        let torch = world.ecs.create_entity().with(Position::new(5, 5)).with(WHITE_LIGHT).build();
+        world.run(LightingSystem);
+        world.ecs.write_resource::<LightMap>().lit.clear();
+
+        world.run(LightingSystem);
+        assert_eq!(lit(&world, 5, 5), 0.0);
+
+        world.insert(torch, Position::new(6, 5));
+        world.run(LightingSystem);
+        assert_eq!(lit(&world, 6, 5), 1.0);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::activity::{check_player_activity, Activity, CancelActivity, EndFinishedActivities};
 use crate::being::BeingID;
 use crate::colors::initialize_printer_palette;
 use crate::frame_animation::AnimationRenderer;
@@ -5,100 +6,197 @@ use crate::game_init::set_level_font;
 use crate::logger::create_logger;
 use crate::map::MapRes;
 use crate::saveload::{SerializationHelper, SerializeMe};
-use crate::ui::draw_ui;
 use crate::ui::message_log::MessageLog;
+use crate::ui::{
+    dialogue::{advance_dialogue, p_input_dialogue, ActiveDialogue, DialogueAction},
+    draw_ui,
+    tooltip::{update_tooltip, Tooltip},
+};
 use std::mem::discriminant;
 use std::process::exit;
 use std::time::Duration;
 
+use ability::GroundSlam;
 use audio::play_sound_effect;
-use being::{GoalFindEntities, GoalMoveToEntities, HandleMoveActions, RandomMonsterMovementSystem};
+use auto_attack::{auto_attack_turn, AutoAttack};
+use autosave::{auto_save, auto_save_if_due, AutoSave};
 use bracket_lib::geometry::Point;
 use bracket_lib::prelude::VirtualKeyCode;
 use bracket_lib::terminal::{main_loop, render_draw_buffer, BError, BTerm, BTermBuilder, GameState};
-use combat::{AttackActionHandler, HealActionHandler};
+use camera::{update_camera, update_hovered_tile, Camera, HoveredTile};
+use catch_release::{CatchChoice, CatchRelease};
+use combat::HealActionHandler;
 use config::ConfigMaster;
+use console::{p_input_console, ConsoleAction, DebugConsole};
+use cooldowns::Cooldowns;
 use crafting::HandleCraftingSystem;
-use debug::{debug_info, debug_input};
-use draw_sprites::{draw_sprite_layers, update_fancy_positions};
-use droptables::DeathLootDrop;
+use currents::Currents;
+use dash::Dash;
+use death::{p_input_death, player_is_dead, respawn_player, DeathAction, DeathCause, DeathEvents};
+use debug::{debug_info, debug_input, CreativeMode, DebugSpawner, GridOverlay, Inspector};
+use difficulty::{Difficulty, SavedDifficulty};
+use dispatch::{lingering_actions, GameDispatchers};
+use draw_sprites::{draw_sprite_layers, update_fancy_positions, SpriteDrawTime};
 use equipment::EquipActionHandler;
-use fov::UpdateViewsheds;
+use examine::{p_input_looking, LookResponse};
+use factions::Faction;
+use fish_schools::FishSchools;
+use fov::refresh_viewsheds;
 use frame_animation::{AnimationPlay, UpdateAnimationTimers};
 use game_init::{
     initialize_new_game_world, p_input_new_game_menu, InputWorldConfig, NewGameMenuAction, NewGameMenuSelection,
+    PlayerEntity,
 };
-use items::{ConsumeHandler, ItemPickupHandler, ItemSpawnerSystem, ZeroQtyItemCleanup};
+use hunger::{Hunger, Metabolism};
+use idle_bob::{update_idle_bob, IdleBob};
+use indexing::{NamedEntities, TileIndexCheck};
+use input_buffer::{age_input_buffer, InputBuffer};
+use item_despawn::{DespawnTimer, ItemDespawn};
+use items::{ConsumeHandler, ItemSpawnerSystem, PickupTexts, ZeroQtyItemCleanup};
+use keybinds::{p_input_help, HelpAction, KeyPress};
+use layers::MapLayers;
+use lighting::LightMap;
 use log::{debug, error, info, warn};
 use map_gen::WorldConfig;
-use mining::{DamageSystem, RemoveDeadTiles, TileDestructionSystem};
+use mining::DamageSystem;
+use particles::Particles;
+use profiling::{time_step, SystemTimings};
+use respawn::{respawn_harvested_tiles, RespawnQueue};
+use rest::{rest_stop, rest_turn, Resting};
+use rng::{startup_seed, GameRng};
 use saveload::{cleanup_game, load_game, save_game, SaveAction};
 use saveload_menu::{get_save_games, p_input_load_game_menu, GameSaves, LoadMenuAction, LoadedWorld};
-use settings::{handle_setting_selected, SettingsAction, SettingsSelection};
+use settings::{
+    apply_post_processing, handle_setting_selected, toggle_minimal_hud, toggle_post_processing, SettingsAction,
+    SettingsSelection,
+};
 use specs::prelude::*;
+use status::StatusEffects;
+use theme::Theme;
+use tilesets::{cycle_terrain_skin, TerrainFonts, TERRAIN_TILESETS};
+use toasts::Toasts;
+use travel::{travel_turn, Travel};
+use triggers::{fire_triggers, Trigger, TriggerWatch};
+use weather::Weather;
 
+mod ability;
+mod activity;
 mod audio;
+mod auto_attack;
+mod autosave;
+mod boating;
+mod buoyancy;
 mod camera;
+mod catch_release;
 mod char_c;
+mod chest;
+mod collision;
 mod colors;
 mod combat;
 mod config;
+mod console;
+mod cooking;
+mod cooldowns;
+mod currents;
+mod dash;
 mod data_read;
+mod death;
 mod debug;
+mod difficulty;
+mod dispatch;
 mod draw_sprites;
 mod droptables;
+mod durability;
 mod equipment;
+mod examine;
+mod explosives;
+mod factions;
+mod farming;
 mod fov;
 mod frame_animation;
 mod game_init;
+mod hazards;
+mod hotbar;
+mod hunger;
+mod idle_bob;
 mod indexing;
+mod input_buffer;
 mod inventory;
+mod item_despawn;
+mod layers;
+mod lighting;
 mod logger;
+mod respawn;
+mod rest;
+mod rng;
 mod saveload;
 mod saveload_menu;
+mod screen_shake;
 mod settings;
+mod shop;
+mod special_attacks;
+mod speed;
 mod storage_utils;
+mod targeting;
+#[cfg(test)]
+mod test_harness;
+mod theme;
 mod ui;
 use inventory::{handle_one_item_actions, handle_two_item_actions, p_input_inventory, InventoryResponse};
+use targeting::{p_input_enemy_targeting, p_input_targeting, EnemyTargetingResponse, TargetingResponse};
 mod being;
 mod items;
+mod keybinds;
 mod map_gen;
 mod mining;
 mod noise;
+mod particles;
 mod player;
+mod profiling;
+mod query;
 mod stats;
+mod status;
 mod tile_animation;
+mod tilesets;
+mod toasts;
+mod travel;
+mod triggers;
+mod weather;
 mod z_order;
 use specs::saveload::{SimpleMarker, SimpleMarkerAllocator};
-use tile_animation::{TileAnimationCleanUpSystem, TileAnimationUpdater};
 mod time;
 use player::{
-    check_player_finished, p_input_activity, p_input_game, p_input_main_menu, p_input_save_game, p_input_settings,
-    MenuAction, MenuSelection, PlayerResponse,
+    p_input_activity, p_input_game, p_input_main_menu, p_input_paused, p_input_save_game, p_input_settings, MenuAction,
+    MenuSelection, PauseAction, PlayerResponse,
 };
 mod map;
 use map::Map;
 mod components;
 use components::Position;
 mod crafting;
+mod fish_schools;
 mod fishing;
-use fishing::{
-    CatchFishSystem, CreateFishingBubbles, FishingMinigameCheck, FishingMinigameUpdate, PollFishingTiles,
-    SetupFishingActions, WaitingForFishSystem,
-};
-use indexing::{IndexBlockedTiles, IndexBreakableTiles, IndexFishableTiles, IndexItemTiles, IndexReset};
-use tile_animation::TileAnimationSpawner;
-use time::delta_time_update;
+use chest::{handle_chest_action, p_input_chest, OpenChest};
+use screen_shake::{update_screen_shake, ScreenShake};
+use shop::{handle_shop_action, p_input_shop, ActiveShop};
+use special_attacks::SpecialAttack;
+use speed::{any_ready, player_turn_time, TurnTime, ACTION_COST, MAX_ROUNDS_PER_TURN};
+use time::{delta_time_update, FrameTimeHistory, IdleClock, RealDeltaTime, TimeScale};
 
 use crate::components::{
-    AttackBonus, Consumable, ConsumeAction, CraftAction, EntityStats, EquipAction, Equipable, EquipmentSlots, Equipped,
-    FishingMinigame, GameAction, GlyphFlash, HealAction, InBag, LevelPersistent, SizeFlexor, Viewshed,
+    ActionPoints, AggroIndicator, AttackBonus, AttackCooldown, BaitActive, Boat, Boating, Consumable, ConsumeAction,
+    Container, CookAction, CookingStation, CraftAction, Crop, Currency, Current, Dialogue, Durability, EffectiveStats,
+    EntityStats, EquipAction, Equipable, EquipmentSlots, Equipped, FishOnTheLine, FishStruggle, FishingMinigame,
+    FishingSpot, FloatingText, Flying, GameAction, GlyphFlash, HealAction, Hotbar, IdleAnimation, InBag, KillCount,
+    LayerStash, LevelPersistent, LightSource, LitFuse, MiningArea, Monster, Particle, ParticleEmitter, Phasing, Quips,
+    Shop, Size, SizeFlexor, StatModifiers, StaticIndexed, Tags, TargetedUse, Viewshed, WeaponCooldown, WeaponReach,
 };
 use crate::{
     components::{
-        AttackAction, Blocking, BreakAction, Breakable, DeleteCondition, FinishedActivity, FishAction, FishOnTheLine,
-        Fishable, GoalMoverAI, Grass, HealthStats, Interactor, Item, MoveAction, Name, PickupAction, RandomWalkerAI,
-        Renderable, SelectedInventoryItem, SufferDamage, Transform, WaitingForFish, Water,
+        AttackAction, Blocking, BreakAction, Breakable, Buoyancy, Companion, DeleteCondition, Facing, FadingOut,
+        FinishedActivity, FishAction, Fishable, GoalMoverAI, Grass, Hazard, HealthStats, Interactor, Item, MoveAction,
+        Name, PickupAction, RandomWalkerAI, RangedAttackAction, Renderable, SelectedInventoryItem, Slide, SpawnPop,
+        SufferDamage, Transform, Water,
     },
     data_read::initialize_game_databases,
     items::ItemSpawner,
@@ -115,116 +213,131 @@ pub const DISPLAY_HEIGHT: usize = 30;
 pub struct State {
     ecs: World,
     cfg: ConfigMaster,
+    systems: GameDispatchers,
 }
 
 impl State {
-    fn run_response_systems(&mut self) {
-        let mut randomwalker = RandomMonsterMovementSystem;
-        randomwalker.run_now(&self.ecs);
-        let mut find_goals = GoalFindEntities;
-        find_goals.run_now(&self.ecs);
-        let mut goalmover = GoalMoveToEntities;
-        goalmover.run_now(&self.ecs);
-        let mut handle_moves = HandleMoveActions;
-        handle_moves.run_now(&self.ecs);
-        let mut handle_attack_actions = AttackActionHandler;
-        handle_attack_actions.run_now(&self.ecs);
-
-        let mut update_fishing_tiles = CreateFishingBubbles;
-        update_fishing_tiles.run_now(&self.ecs);
+    /// Lets everything else act for the `turn_time` the player's action took
+    fn run_response_systems(&mut self, turn_time: u32) {
+        self.ecs.insert(TurnTime(turn_time));
+        self.systems.run_turn_start(&self.ecs);
+
+        // Fast beings can act more than once in a turn, the first round always runs to resolve the player's action
+        for round in 0..MAX_ROUNDS_PER_TURN {
+            if round > 0 && !any_ready(&self.ecs) {
+                break;
+            }
+            self.systems.run_turn_round(&self.ecs);
+        }
+        self.systems.run_turn_end(&self.ecs);
+
+        time_step(&mut self.ecs, "respawn_harvested_tiles", respawn_harvested_tiles);
+        time_step(&mut self.ecs, "fire_triggers", fire_triggers);
+    }
+
+    /// Rests turn after turn within a single frame, the world is updated after each one so the player gets up as soon
+    /// as they are healed or something hostile comes near
+    fn rest_until_disturbed(&mut self) {
+        let mut turns = 0;
+        loop {
+            rest_turn(&self.ecs);
+            let turn_time = player_turn_time(&self.ecs, None);
+            turn_counter_incr(&mut self.ecs);
+            self.run_response_systems(turn_time);
+            self.run_ingame_systems();
+            self.ecs.maintain();
+            turns += 1;
+
+            if player_is_dead(&self.ecs) {
+                break;
+            }
+            if let Some(stop) = rest_stop(&self.ecs, turns) {
+                self.ecs.fetch_mut::<MessageLog>().log(stop);
+                break;
+            }
+        }
     }
 
     fn run_activity_bound_systems(&mut self) {
-        // Fishing Minigame Systems ====================>
-        let mut waiting_for_fish = WaitingForFishSystem;
-        waiting_for_fish.run_now(&self.ecs);
-        let mut fish_mini_update = FishingMinigameUpdate;
-        fish_mini_update.run_now(&self.ecs);
-        let mut fish_mini_check = FishingMinigameCheck;
-        fish_mini_check.run_now(&self.ecs);
-        let mut catch_fish = CatchFishSystem;
-        catch_fish.run_now(&self.ecs);
+        self.systems.run_activity_bound(&self.ecs);
     }
 
     fn run_ingame_systems(&mut self) {
-        // Indexing Systems ===============================>
-        let mut index_reset = IndexReset;
-        index_reset.run_now(&self.ecs);
-        let mut index_blocking = IndexBlockedTiles;
-        index_blocking.run_now(&self.ecs);
-        let mut index_breaking = IndexBreakableTiles;
-        index_breaking.run_now(&self.ecs);
-        let mut index_fishing = IndexFishableTiles;
-        index_fishing.run_now(&self.ecs);
-        let mut index_items = IndexItemTiles;
-        index_items.run_now(&self.ecs);
-
-        // Fishing Systems ==================================>
-        let mut setup_fishing_actions = SetupFishingActions;
-        setup_fishing_actions.run_now(&self.ecs);
-        let mut waiting_for_fish = WaitingForFishSystem;
-        waiting_for_fish.run_now(&self.ecs);
-        let mut catch_fish = CatchFishSystem;
-        catch_fish.run_now(&self.ecs);
-        let mut poll_fishing_tiles = PollFishingTiles;
-        poll_fishing_tiles.run_now(&self.ecs);
-
-        // Action Systems =================================>
-        let mut heal_handler = HealActionHandler;
-        heal_handler.run_now(&self.ecs);
-        let mut destruction_sys = TileDestructionSystem;
-        destruction_sys.run_now(&self.ecs);
-        let mut damage_sys = DamageSystem;
-        damage_sys.run_now(&self.ecs);
-        let mut item_pickup_handler = ItemPickupHandler;
-        item_pickup_handler.run_now(&self.ecs);
-
-        // Misc Systems ==================================>
-        let mut death_loot_spawn = DeathLootDrop;
-        death_loot_spawn.run_now(&self.ecs);
-        let mut viewshed_update = UpdateViewsheds;
-        viewshed_update.run_now(&self.ecs);
-
-        // Request Based Systems ================================>
-        let mut item_spawner = ItemSpawnerSystem;
-        item_spawner.run_now(&self.ecs);
-
-        // Animation Systems =========================================>
-        let mut tile_anim_spawner = TileAnimationSpawner;
-        tile_anim_spawner.run_now(&self.ecs);
-        let mut tile_anim_updater = TileAnimationUpdater;
-        tile_anim_updater.run_now(&self.ecs);
-        let mut tile_anim_cleanup_system = TileAnimationCleanUpSystem;
-        tile_anim_cleanup_system.run_now(&self.ecs);
-
-        // Cleanup Systems =======================================>
-        let mut zero_qty_item_cleanup = ZeroQtyItemCleanup;
-        zero_qty_item_cleanup.run_now(&self.ecs);
-        let mut remove_dead_tiles = RemoveDeadTiles;
-        remove_dead_tiles.run_now(&self.ecs);
+        self.systems.run_ingame(&self.ecs);
     }
 
     /// Systems that need to be ran after most other systems are finished EOF - end of frame
     fn run_eof_systems(&mut self) {
+        let mut end_activities = EndFinishedActivities;
+        end_activities.run_now(&self.ecs);
         self.ecs.write_storage::<FinishedActivity>().clear();
+
+        // Dev builds catch an action no system handled before it gets the chance to fire again
+        if cfg!(feature = "dev") {
+            let lingering = lingering_actions(&self.ecs);
+            assert!(lingering.is_empty(), "Actions survived past the end of the frame: {:?}", lingering);
+        }
     }
 }
 
 /// Defines the app's state for the game
 #[derive(Clone, PartialEq, Eq)]
 pub enum AppState {
-    MainMenu { hovering: MenuSelection },
-    SettingsMenu { hovering: SettingsSelection },
-    NewGameInitialize { hovering: NewGameMenuSelection, world_cfg: InputWorldConfig, form_errors: Vec<String> },
-    NewGameStart { world_cfg: WorldConfig },
-    LoadGameMenu { hovering: usize },
-    LoadGameStart { file_name: String },
-    MapChange { level_name: String, player_world_pos: Position },
+    MainMenu {
+        hovering: MenuSelection,
+    },
+    SettingsMenu {
+        hovering: SettingsSelection,
+    },
+    NewGameInitialize {
+        hovering: NewGameMenuSelection,
+        world_cfg: InputWorldConfig,
+        form_errors: Vec<String>,
+    },
+    NewGameStart {
+        world_cfg: WorldConfig,
+    },
+    LoadGameMenu {
+        hovering: usize,
+    },
+    LoadGameStart {
+        file_name: String,
+    },
+    MapChange {
+        level_name: String,
+        player_world_pos: Position,
+    },
     InGame,
-    ActivityBound { response_delay: Duration },
+    Paused,
+    ActivityBound {
+        response_delay: Duration,
+    },
     PlayerInInventory,
+    TargetingTile {
+        item: Entity,
+        target: Position,
+    },
+    TargetingEnemy {
+        target: Entity,
+    },
+    /// `focus` is the index of the thing on the tile whose description is shown
+    Looking {
+        target: Position,
+        focus: usize,
+    },
+    GameOver,
+    Help {
+        scroll: usize,
+    },
+    /// Dev builds only, commands typed here are run against the world
+    DebugConsole,
+    InDialogue,
+    InShop,
+    InChest,
     SaveGame,
-    PreRun { next_state: Box<AppState> },
+    PreRun {
+        next_state: Box<AppState>,
+    },
 }
 
 struct FrameState {
@@ -276,11 +389,26 @@ impl GameState for State {
             frame_state = FrameState::new(&current_state);
         }
 
+        // the hovered tile is found before anything else so input and the ui agree on it
+        ctx.set_active_console(CL_WORLD);
+        update_hovered_tile(&self.ecs, ctx.mouse_pos());
+
+        if ctx.key == Some(VirtualKeyCode::F10) {
+            toggle_post_processing(&mut self.cfg.general, ctx);
+        }
+        if ctx.key == Some(VirtualKeyCode::F8) {
+            toggle_minimal_hud(&mut self.cfg.general);
+        }
+        if ctx.key == Some(VirtualKeyCode::F9) {
+            cycle_terrain_skin(&self.ecs, &mut self.cfg.general, ctx);
+        }
+
         match frame_state.current.clone() {
             AppState::NewGameStart { world_cfg } => {
                 info!("Game startup occured");
                 initialize_new_game_world(&mut self.ecs, &world_cfg);
                 set_level_font(&self.ecs, ctx);
+                refresh_viewsheds(&self.ecs, self.cfg.general.view_range);
 
                 let mut item_spawner = ItemSpawnerSystem;
                 item_spawner.run_now(&self.ecs);
@@ -289,27 +417,119 @@ impl GameState for State {
             }
             AppState::LoadGameStart { file_name } => {
                 debug!("Attempting to load save file, {}", file_name);
-                load_game(&mut self.ecs, file_name);
-                set_level_font(&self.ecs, ctx);
+                self.ecs.write_resource::<GameSaves>().load_error = None;
+                match load_game(&mut self.ecs, file_name) {
+                    Ok(()) => {
+                        set_level_font(&self.ecs, ctx);
+                        refresh_viewsheds(&self.ecs, self.cfg.general.view_range);
 
-                frame_state.change_to(AppState::InGame);
-                debug!("Loaded save file");
+                        frame_state.change_to(AppState::InGame);
+                        debug!("Loaded save file");
+                    }
+                    Err(e) => {
+                        error!("Load game error: {}", e);
+                        self.ecs.write_resource::<GameSaves>().load_error = Some(e.to_string());
+                        frame_state.change_to(AppState::PreRun {
+                            next_state: Box::new(AppState::LoadGameMenu { hovering: 0 }),
+                        });
+                    }
+                }
             }
             AppState::InGame => {
-                match p_input_game(&mut self.ecs, ctx) {
+                let before = player_position(&self.ecs);
+                let response = match p_input_game(&mut self.ecs, ctx) {
+                    PlayerResponse::Waiting => match auto_attack_turn(&mut self.ecs) {
+                        PlayerResponse::Waiting => travel_turn(&mut self.ecs),
+                        response => response,
+                    },
+                    response => response,
+                };
+                match response {
                     PlayerResponse::Waiting => {
                         // Player hasn't done anything yet so only run essential systems
                     }
                     PlayerResponse::TurnAdvance => {
+                        let after = player_position(&self.ecs);
+                        if let (Some(before), Some(after)) = (before, after) {
+                            self.ecs.fetch_mut::<CreativeMode>().record(before, after);
+                        }
+                        let moved_to = after.filter(|after| before.is_some_and(|before| before != *after));
+                        let turn_time = player_turn_time(&self.ecs, moved_to);
                         turn_counter_incr(&mut self.ecs);
-                        self.run_response_systems();
+                        self.run_response_systems(turn_time);
+                    }
+                    PlayerResponse::Rest => {
+                        self.ecs.fetch_mut::<CreativeMode>().clear();
+                        self.rest_until_disturbed();
                     }
                     PlayerResponse::StateChange(delta_state) => {
+                        self.ecs.fetch_mut::<CreativeMode>().clear();
                         frame_state.change_to(delta_state);
                     }
                 }
                 self.run_ingame_systems();
                 self.run_eof_systems();
+                if player_is_dead(&self.ecs) {
+                    self.ecs.fetch_mut::<CreativeMode>().clear();
+                    frame_state.change_to(AppState::GameOver);
+                } else {
+                    auto_save_if_due(&mut self.ecs);
+                }
+            }
+            AppState::GameOver => match p_input_death(ctx) {
+                DeathAction::Respawn => {
+                    respawn_player(&mut self.ecs);
+                    frame_state.change_to(AppState::InGame);
+                }
+                DeathAction::ReturnToMenu => {
+                    cleanup_game(&mut self.ecs);
+                    frame_state.change_to(AppState::PreRun {
+                        next_state: Box::new(AppState::MainMenu { hovering: MenuSelection::NewGame }),
+                    });
+                }
+                DeathAction::Waiting => {}
+            },
+            AppState::Help { scroll } => match p_input_help(ctx, scroll) {
+                HelpAction::Scroll(scroll) => frame_state.change_to(AppState::Help { scroll }),
+                HelpAction::Close => frame_state.change_to(AppState::InGame),
+                HelpAction::Waiting => {}
+            },
+            AppState::DebugConsole => {
+                if let ConsoleAction::Close = p_input_console(&mut self.ecs, ctx) {
+                    frame_state.change_to(AppState::InGame);
+                }
+                let mut item_spawner = ItemSpawnerSystem;
+                item_spawner.run_now(&self.ecs);
+            }
+            AppState::Paused => match p_input_paused(ctx) {
+                PauseAction::Resume => frame_state.change_to(AppState::InGame),
+                PauseAction::OpenMenu => frame_state.change_to(AppState::SaveGame),
+                PauseAction::Waiting => {}
+            },
+            AppState::InDialogue => match p_input_dialogue(ctx) {
+                DialogueAction::Next => {
+                    if !advance_dialogue(&self.ecs) {
+                        frame_state.change_to(AppState::InGame);
+                    }
+                }
+                DialogueAction::Close => frame_state.change_to(AppState::InGame),
+                DialogueAction::Waiting => {}
+            },
+            AppState::InShop => {
+                if !handle_shop_action(&mut self.ecs, p_input_shop(ctx)) {
+                    frame_state.change_to(AppState::InGame);
+                }
+                let mut item_spawner = ItemSpawnerSystem;
+                item_spawner.run_now(&self.ecs);
+                let mut zero_qty_item_cleanup = ZeroQtyItemCleanup;
+                zero_qty_item_cleanup.run_now(&self.ecs);
+            }
+            AppState::InChest => {
+                if !handle_chest_action(&mut self.ecs, p_input_chest(ctx)) {
+                    frame_state.change_to(AppState::InGame);
+                }
+                let mut zero_qty_item_cleanup = ZeroQtyItemCleanup;
+                zero_qty_item_cleanup.run_now(&self.ecs);
             }
             AppState::PlayerInInventory => {
                 match p_input_inventory(&mut self.ecs, ctx, &mut self.cfg.inventory) {
@@ -341,13 +561,46 @@ impl GameState for State {
                 let mut zero_qty_item_cleanup = ZeroQtyItemCleanup;
                 zero_qty_item_cleanup.run_now(&self.ecs);
             }
+            AppState::TargetingTile { item, target } => {
+                match p_input_targeting(&mut self.ecs, ctx, item, target) {
+                    TargetingResponse::Waiting => {}
+                    TargetingResponse::Moved(target) => frame_state.change_to(AppState::TargetingTile { item, target }),
+                    TargetingResponse::Used => {
+                        turn_counter_incr(&mut self.ecs);
+                        self.run_response_systems(ACTION_COST);
+                        frame_state.change_to(AppState::InGame);
+                    }
+                    TargetingResponse::Cancel => frame_state.change_to(AppState::PlayerInInventory),
+                }
+                let mut item_spawner = ItemSpawnerSystem;
+                item_spawner.run_now(&self.ecs);
+                let mut zero_qty_item_cleanup = ZeroQtyItemCleanup;
+                zero_qty_item_cleanup.run_now(&self.ecs);
+            }
+            AppState::TargetingEnemy { target } => match p_input_enemy_targeting(&mut self.ecs, ctx, target) {
+                EnemyTargetingResponse::Waiting => {}
+                EnemyTargetingResponse::Switched(target) => frame_state.change_to(AppState::TargetingEnemy { target }),
+                EnemyTargetingResponse::Attacked => {
+                    turn_counter_incr(&mut self.ecs);
+                    self.run_response_systems(ACTION_COST);
+                    frame_state.change_to(AppState::InGame);
+                }
+                EnemyTargetingResponse::Cancel => frame_state.change_to(AppState::InGame),
+            },
+            AppState::Looking { target, focus } => match p_input_looking(&self.ecs, ctx, target, focus) {
+                LookResponse::Waiting => {}
+                LookResponse::Moved(target) => frame_state.change_to(AppState::Looking { target, focus: 0 }),
+                LookResponse::Focused(focus) => frame_state.change_to(AppState::Looking { target, focus }),
+                LookResponse::Cancel => frame_state.change_to(AppState::InGame),
+            },
             AppState::ActivityBound { response_delay } => {
+                self.ecs.write_resource::<InputBuffer>().hold(KeyPress::read(ctx));
                 p_input_activity(&mut self.ecs, ctx);
                 self.run_activity_bound_systems();
 
-                frame_state.change_to(if check_player_finished(&mut self.ecs) {
+                frame_state.change_to(if check_player_activity(&self.ecs) {
                     turn_counter_incr(&mut self.ecs);
-                    self.run_response_systems();
+                    self.run_response_systems(ACTION_COST);
                     AppState::InGame
                 } else {
                     AppState::ActivityBound { response_delay }
@@ -414,7 +667,14 @@ impl GameState for State {
                             NewGameMenuSelection::Seed => {
                                 cfg_input.seed.push(ch);
                             }
-                            NewGameMenuSelection::Finalize => {}
+                            NewGameMenuSelection::Layers => {
+                                if ch.is_ascii_digit() {
+                                    cfg_input.layers.push(ch);
+                                }
+                            }
+                            NewGameMenuSelection::Generator
+                            | NewGameMenuSelection::Difficulty
+                            | NewGameMenuSelection::Finalize => {}
                         }
                         if !matches!(hovering, NewGameMenuSelection::Finalize) {
                             frame_state.change_to(AppState::NewGameInitialize {
@@ -446,7 +706,14 @@ impl GameState for State {
                                     cfg_input.seed.remove(cfg_input.seed.len() - 1);
                                 }
                             }
-                            NewGameMenuSelection::Finalize => {}
+                            NewGameMenuSelection::Layers => {
+                                if !cfg_input.layers.is_empty() {
+                                    cfg_input.layers.remove(cfg_input.layers.len() - 1);
+                                }
+                            }
+                            NewGameMenuSelection::Generator
+                            | NewGameMenuSelection::Difficulty
+                            | NewGameMenuSelection::Finalize => {}
                         }
                         if !matches!(hovering, NewGameMenuSelection::Finalize) {
                             frame_state.change_to(AppState::NewGameInitialize {
@@ -457,6 +724,22 @@ impl GameState for State {
                         }
                     }
                     NewGameMenuAction::Select => match hovering {
+                        NewGameMenuSelection::Generator => {
+                            cfg_input.generator = cfg_input.generator.next();
+                            frame_state.change_to(AppState::NewGameInitialize {
+                                hovering,
+                                world_cfg: cfg_input,
+                                form_errors,
+                            });
+                        }
+                        NewGameMenuSelection::Difficulty => {
+                            cfg_input.difficulty = cfg_input.difficulty.next();
+                            frame_state.change_to(AppState::NewGameInitialize {
+                                hovering,
+                                world_cfg: cfg_input,
+                                form_errors,
+                            });
+                        }
                         NewGameMenuSelection::Finalize => match WorldConfig::try_from(&cfg_input) {
                             Ok(world_cfg) => frame_state.change_to(AppState::NewGameStart { world_cfg }),
                             Err(errs) => {
@@ -521,6 +804,7 @@ impl GameState for State {
             AppState::SaveGame => match p_input_save_game(ctx) {
                 SaveAction::Save => {
                     save_game(&mut self.ecs);
+                    auto_save(&mut self.ecs);
                     cleanup_game(&mut self.ecs);
                     frame_state.change_to(AppState::PreRun {
                         next_state: Box::new(AppState::MainMenu { hovering: MenuSelection::NewGame }),
@@ -548,24 +832,47 @@ impl GameState for State {
             }
         }
 
-        // Essential Systems run every frame
-        update_fancy_positions(&self.ecs);
-        delta_time_update(&mut self.ecs, ctx);
+        // Essential Systems run every frame, besides when the game is paused since they rely on time passing
+        if !matches!(frame_state.current, AppState::Paused | AppState::Help { .. } | AppState::DebugConsole) {
+            update_fancy_positions(&self.ecs);
+            update_idle_bob(&self.ecs, ctx.key.is_some());
+            update_camera(&self.ecs);
+            delta_time_update(&mut self.ecs, ctx, &self.cfg.general);
+            age_input_buffer(&self.ecs);
+            update_screen_shake(&self.ecs, ctx);
+        }
         self.ecs.maintain();
 
+        if frame_state.current == AppState::InGame {
+            ctx.set_active_console(CL_WORLD);
+            update_tooltip(&self.ecs, ctx.mouse_pos());
+        }
         draw_ui(&self.ecs, &frame_state.current, &self.cfg);
         // NOTE: for some unknown reason this must be called before the debug info so that
         // the debug info is drawn ontop of the ui
         render_draw_buffer(ctx).expect("Render error??");
+        self.ecs.write_resource::<SystemTimings>().end_frame();
 
         match frame_state.current {
             AppState::InGame
+            | AppState::Paused
             | AppState::PlayerInInventory
+            | AppState::TargetingTile { .. }
+            | AppState::TargetingEnemy { .. }
+            | AppState::Looking { .. }
+            | AppState::GameOver
+            | AppState::Help { .. }
+            | AppState::DebugConsole
+            | AppState::InDialogue
+            | AppState::InShop
+            | AppState::InChest
             | AppState::NewGameStart { .. }
             | AppState::MapChange { .. } => {
-                draw_sprite_layers(&self.ecs);
-                debug_info(ctx, &self.ecs, &self.cfg.inventory);
-                debug_input(ctx, &self.ecs);
+                draw_sprite_layers(&self.ecs, self.cfg.general.flat_sprites);
+                if !self.cfg.general.minimal_hud {
+                    debug_info(ctx, &self.ecs, &self.cfg);
+                }
+                debug_input(ctx, &mut self.ecs);
             }
             _ => {}
         }
@@ -636,7 +943,7 @@ fn run_pre_state_systems(state: &AppState, ecs: &mut World) {
             let mut saves = ecs.write_resource::<GameSaves>();
             let games = get_save_games();
             println!("{:#?}", games);
-            *saves = GameSaves { saves: games };
+            *saves = GameSaves { saves: games, load_error: saves.load_error.take() };
         }
         _ => {}
     }
@@ -648,6 +955,11 @@ fn run_exit_state_systems(state: &AppState, ecs: &mut World) {
         AppState::MainMenu { .. } => {
             ecs.write_resource::<AnimationRenderer>().clear();
         }
+        AppState::Paused | AppState::Help { .. } | AppState::DebugConsole => {
+            // time did not pass while paused so make sure the next frame doesn't jump ahead
+            ecs.write_resource::<DeltaTime>().0 = Duration::ZERO;
+            ecs.write_resource::<RealDeltaTime>().0 = Duration::ZERO;
+        }
         _ => {}
     }
 }
@@ -662,6 +974,12 @@ impl TurnCounter {
 fn turn_counter_incr(ecs: &mut World) {
     let mut tc = ecs.fetch_mut::<TurnCounter>();
     tc.0 += 1;
+    ecs.fetch_mut::<MessageLog>().set_turn(tc.0);
+}
+
+fn player_position(ecs: &World) -> Option<Position> {
+    let player_entity = ecs.read_resource::<PlayerEntity>();
+    ecs.read_storage::<Position>().get(player_entity.0).copied()
 }
 
 // CL - Console layer, represents the indices for each console
@@ -671,7 +989,8 @@ pub const CL_TEXT: usize = 4; // Used for UI
 pub const CL_WORLD: usize = 0; // Used for terrain tiles
 pub const CL_INTERACTABLES: usize = 1; // Used for the few or so moving items/entities on screen
 
-// FONTS - the indices are based on the order the fonts are added in the context init
+// FONTS - the indices are based on the order the fonts are added in the context init, terrain skins are added after
+// these starting at `tilesets::BASE_FONT_COUNT`
 pub const FONT_EFFECTS: usize = 0;
 pub const FONT_TEXT: usize = 1;
 pub const FONT_INTERACTABLES: usize = 2;
@@ -703,6 +1022,7 @@ fn main() -> BError {
     warn!("Warnings will be tracked in this file.");
 
     initialize_game_databases();
+    let seed = startup_seed();
 
     let cfg = ConfigMaster::load();
     let interactable_font = match cfg.general.sprite_mode {
@@ -711,31 +1031,75 @@ fn main() -> BError {
     };
     let text_font = "zaratustra.png";
 
+    let (window_width, window_height) = cfg.general.display.window_dimensions();
     // Setup Terminal (incl Window, Input, Font Loading)
-    let mut context = BTermBuilder::new()
+    let builder = BTermBuilder::new()
         .with_title("RPG")
-        .with_fps_cap(60.0)
+        .with_fps_cap(cfg.general.fps_cap)
         .with_font("effects_tiles.png", 8u32, 8u32)
         .with_font("zaratustra.png", 8u32, 8u32)
         .with_font("interactable_tiles.png", 8u32, 8u32)
-        .with_font("interactable_tiles_outline.png", 8u32, 8u32)
-        .with_font("terrain_forest.png", 8u32, 8u32)
-        .with_font("terrain_town_forest.png", 8u32, 8u32)
-        .with_dimensions(160, 120)
-        .with_simple_console(DISPLAY_WIDTH, DISPLAY_HEIGHT, "terrain_forest.png")
-        .with_fancy_console(DISPLAY_WIDTH, DISPLAY_HEIGHT, interactable_font)
+        .with_font("interactable_tiles_outline.png", 8u32, 8u32);
+    let builder = TERRAIN_TILESETS.iter().fold(builder, |builder, (file, _)| builder.with_font(*file, 8u32, 8u32));
+    let (builder, terrain_fonts) =
+        TerrainFonts::register(builder, &cfg.general.terrain_skins, cfg.general.terrain_skin.as_deref());
+    let builder = builder
+        .with_dimensions(window_width, window_height)
+        .with_fullscreen(cfg.general.display.fullscreen)
+        .with_simple_console(DISPLAY_WIDTH, DISPLAY_HEIGHT, "terrain_forest.png");
+    // A sparse console is still see through to the terrain but can only draw at whole tiles
+    let builder = match cfg.general.flat_sprites {
+        true => builder.with_sparse_console(DISPLAY_WIDTH, DISPLAY_HEIGHT, interactable_font),
+        false => builder.with_fancy_console(DISPLAY_WIDTH, DISPLAY_HEIGHT, interactable_font),
+    };
+    let mut context = builder
         .with_fancy_console(DISPLAY_WIDTH, DISPLAY_HEIGHT, "effects_tiles.png")
         .with_fancy_console(DISPLAY_WIDTH, DISPLAY_HEIGHT, "effects_tiles.png")
         .with_fancy_console(DISPLAY_WIDTH * 2, DISPLAY_HEIGHT * 2, text_font)
         .build()?;
+    apply_post_processing(&cfg.general, &mut context);
     context.cls();
 
-    initialize_printer_palette();
+    let theme = Theme::from_config(&cfg.general.theme);
+    initialize_printer_palette(&theme);
 
     // Setup ECS
     let mut world = World::new();
 
-    // Component Registration, the ECS needs to have every type of component registered
+    register_components(&mut world);
+    insert_resources(&mut world);
+    // worlds made without a typed in seed roll theirs from this, so a run can be repeated from its startup seed
+    world.insert(GameRng::seeded(seed));
+    world.insert(theme);
+    world.insert(InputBuffer::new(Duration::from_millis(cfg.general.input_buffer_ms)));
+    world.insert(AutoSave::new(cfg.general.autosave_turns, cfg.general.autosave_slots));
+    world.insert(PickupTexts::new(cfg.general.pickup_text_once));
+    world.insert(Weather::new(cfg.general.weather.clone()));
+    world.insert(FishSchools::new(cfg.general.fish_schools.clone()));
+    world.insert(CatchRelease::new(cfg.general.catch_release.clone()));
+    world.insert(Currents::new(cfg.general.currents.clone()));
+    world.insert(MessageLog::with_config(cfg.general.message_log.clone()));
+    world.insert(Dash::new(cfg.general.dash.clone()));
+    world.insert(GroundSlam::new(cfg.general.ability.clone()));
+    world.insert(IdleBob::new(cfg.general.idle_bob.clone()));
+    world.insert(Camera::new(cfg.general.camera.clone()));
+    world.insert(Resting::new(cfg.general.rest.clone()));
+    world.insert(AutoAttack::new(cfg.general.auto_attack.clone()));
+    world.insert(Travel::new(cfg.general.travel.clone()));
+    world.insert(Metabolism::new(cfg.general.hunger.clone()));
+    world.insert(ItemDespawn::new(cfg.general.item_despawn.clone()));
+    world.insert(Difficulty::new(cfg.general.difficulty.clone()));
+    world.insert(Particles::new(cfg.general.particles.clone()));
+    world.insert(LightMap::new(cfg.general.lighting.clone()));
+    world.insert(terrain_fonts);
+
+    let systems = GameDispatchers::new(&mut world);
+    let game_state = State { ecs: world, cfg, systems };
+    main_loop(context, game_state)
+}
+
+/// Component Registration, the ECS needs to have every type of component registered
+fn register_components(world: &mut World) {
     world.register::<Position>();
     world.register::<Player>();
     world.register::<BeingID>();
@@ -744,62 +1108,165 @@ fn main() -> BError {
     world.register::<HealthStats>();
     world.register::<BreakAction>();
     world.register::<AttackAction>();
+    world.register::<RangedAttackAction>();
     world.register::<PickupAction>();
     world.register::<FishAction>();
     world.register::<Breakable>();
     world.register::<SufferDamage>();
     world.register::<Fishable>();
-    world.register::<WaitingForFish>();
-    world.register::<FishOnTheLine>();
+    world.register::<Activity>();
+    world.register::<CancelActivity>();
     world.register::<DeleteCondition>();
+    world.register::<FadingOut>();
     world.register::<FinishedActivity>();
     world.register::<Name>();
     world.register::<RandomWalkerAI>();
     world.register::<GoalMoverAI>();
+    world.register::<Companion>();
+    world.register::<Monster>();
+    world.register::<SpecialAttack>();
     world.register::<Item>();
     world.register::<Water>();
+    world.register::<Hazard>();
+    world.register::<Buoyancy>();
     world.register::<Grass>();
     world.register::<InBag>();
     world.register::<MoveAction>();
+    world.register::<ActionPoints>();
     world.register::<CraftAction>();
     world.register::<EquipAction>();
     world.register::<Transform>();
     world.register::<Interactor>();
+    world.register::<Facing>();
     world.register::<EntityStats>();
     world.register::<SelectedInventoryItem>();
     world.register::<EquipmentSlots>();
     world.register::<Equipable>();
     world.register::<Equipped>();
     world.register::<AttackBonus>();
+    world.register::<StatModifiers>();
+    world.register::<EffectiveStats>();
+    world.register::<WeaponCooldown>();
+    world.register::<WeaponReach>();
+    world.register::<Durability>();
+    world.register::<Boat>();
+    world.register::<Boating>();
+    world.register::<Current>();
+    world.register::<Flying>();
+    world.register::<Phasing>();
+    world.register::<Size>();
+    world.register::<Tags>();
+    world.register::<AggroIndicator>();
+    world.register::<TargetedUse>();
+    world.register::<Crop>();
+    world.register::<Hotbar>();
+    world.register::<SpawnPop>();
+    world.register::<Slide>();
+    world.register::<Particle>();
+    world.register::<ParticleEmitter>();
+    world.register::<AttackCooldown>();
+    world.register::<MiningArea>();
     world.register::<Consumable>();
+    world.register::<BaitActive>();
+    world.register::<StatusEffects>();
+    world.register::<Hunger>();
+    world.register::<DespawnTimer>();
+    world.register::<SavedDifficulty>();
+    world.register::<Trigger>();
     world.register::<ConsumeAction>();
     world.register::<HealAction>();
     world.register::<GameAction>();
     world.register::<FishingMinigame>();
+    world.register::<FishOnTheLine>();
+    world.register::<FishingSpot>();
+    world.register::<FishStruggle>();
+    world.register::<CatchChoice>();
+    world.register::<Cooldowns>();
     world.register::<LevelPersistent>();
+    world.register::<LayerStash>();
+    world.register::<StaticIndexed>();
     world.register::<SizeFlexor>();
     world.register::<GlyphFlash>();
     world.register::<Viewshed>();
+    world.register::<FloatingText>();
+    world.register::<Quips>();
+    world.register::<IdleAnimation>();
+    world.register::<Dialogue>();
+    world.register::<Currency>();
+    world.register::<KillCount>();
+    world.register::<Shop>();
+    world.register::<CookingStation>();
+    world.register::<Container>();
+    world.register::<CookAction>();
+    world.register::<LightSource>();
+    world.register::<LitFuse>();
+    world.register::<Faction>();
 
     // Still components but used for saving the data in the ecs
     world.register::<SimpleMarker<SerializeMe>>();
     world.register::<SerializationHelper>();
     world.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+}
 
-    // Resource Initialization, the ECS needs a basic definition of every resource that will be in the game
+/// Resource Initialization, the ECS needs a basic definition of every resource that will be in the game
+fn insert_resources(world: &mut World) {
     world.insert(AppState::PreRun {
         next_state: Box::new(AppState::NewGameStart { world_cfg: WorldConfig::default() }),
     });
     world.insert(DeltaTime(Duration::ZERO));
+    world.insert(RealDeltaTime(Duration::ZERO));
+    world.insert(TimeScale::default());
+    world.insert(FrameTimeHistory::new());
+    world.insert(IdleClock::default());
+    world.insert(InputBuffer::default());
+    world.insert(Tooltip::default());
+    world.insert(HoveredTile::default());
+    world.insert(ActiveDialogue::default());
+    world.insert(ActiveShop::default());
+    world.insert(OpenChest::default());
+    world.insert(CreativeMode::default());
+    world.insert(GridOverlay::default());
+    world.insert(TileIndexCheck::default());
+    world.insert(NamedEntities::default());
+    world.insert(DebugSpawner::default());
+    world.insert(Inspector::default());
+    world.insert(DebugConsole::default());
+    world.insert(TurnTime::default());
+    world.insert(SpriteDrawTime::default());
+    world.insert(SystemTimings::default());
     world.insert(TileAnimationBuilder::new());
     world.insert(AnimationRenderer::new());
     world.insert(ItemSpawner::new());
+    world.insert(PickupTexts::default());
+    world.insert(ScreenShake::default());
+    world.insert(GameRng::default());
+    world.insert(RespawnQueue::default());
+    world.insert(TriggerWatch::default());
+    world.insert(DeathCause::default());
+    world.insert(DeathEvents::default());
+    world.insert(MapLayers::default());
     world.insert(MessageLog::new());
     world.insert(MapRes(Map::empty(0, 0)));
     world.insert(TurnCounter::zero());
     world.insert(GameSaves::default());
     world.insert(LoadedWorld::default());
-
-    let game_state = State { ecs: world, cfg };
-    main_loop(context, game_state)
+    world.insert(AutoSave::default());
+    world.insert(Weather::default());
+    world.insert(FishSchools::default());
+    world.insert(CatchRelease::default());
+    world.insert(Currents::default());
+    world.insert(Dash::default());
+    world.insert(GroundSlam::default());
+    world.insert(IdleBob::default());
+    world.insert(Camera::default());
+    world.insert(Resting::default());
+    world.insert(AutoAttack::default());
+    world.insert(Travel::default());
+    world.insert(Metabolism::default());
+    world.insert(ItemDespawn::default());
+    world.insert(Difficulty::default());
+    world.insert(Particles::default());
+    world.insert(LightMap::default());
+    world.insert(Toasts::default());
+    world.insert(TerrainFonts::default());
 }
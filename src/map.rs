@@ -1,10 +1,20 @@
+use std::{collections::HashMap, fmt::Display};
+
 use crate::{
     camera::get_camera_bounds,
-    char_c::{CH_SOLID, CH_WATER},
-    components::{HealthStats, Position},
+    char_c::{CH_CAVE_FLOOR, CH_MOUNTAIN, CH_SOLID, CH_WATER},
+    components::{HealthStats, LightSource, ParticleEmitter, Position, Tags},
     droptables::Drops,
+    indexing::idx_to_point,
+    layers::MapLayers,
+    lighting::LightMap,
+    speed::{MOVE_COST, WADING_MOVE_COST},
+    tilesets::DEFAULT_TILESET,
+    weather::{is_outdoors, Weather},
 };
-use bracket_lib::terminal::{ColorPair, DrawBatch, Point, BLACK};
+use bracket_lib::terminal::{ColorPair, DrawBatch, Point, BLACK, RGB, RGBA};
+use log::warn;
+use ron::ser::PrettyConfig;
 use serde::{Deserialize, Serialize};
 use specs::{Entity, World};
 
@@ -16,15 +26,60 @@ pub struct Map {
     pub width: usize,
     pub height: usize,
     pub chunk_coords: WorldCoords,
-    pub tile_atlas_index: usize,
+    /// The terrain tileset the map is drawn with, see `tilesets::tileset_font`
+    #[serde(default = "default_tileset")]
+    pub tileset: String,
+    /// Tiles the player has seen at some point, these stay drawn but dimmed when out of view
+    #[serde(default)]
+    pub revealed_tiles: Vec<bool>,
 
     #[serde(skip_serializing, skip_deserializing)]
     pub tile_entities: Vec<Vec<TileEntity>>,
+    /// Entities that never move, they are indexed once and copied into `tile_entities` each time it is reset
+    #[serde(skip_serializing, skip_deserializing)]
+    pub static_entities: Vec<Vec<TileEntity>>,
 }
 
 #[derive(Default, Clone)]
 pub struct MapRes(pub Map);
 
+/// The parts of a map that are stored in a map file, live entity references are left out since they
+/// are rebuilt by the indexing systems
+#[derive(Serialize, Deserialize)]
+struct MapFile {
+    width: usize,
+    height: usize,
+    chunk_coords: WorldCoords,
+    #[serde(default = "default_tileset")]
+    tileset: String,
+    tiles: Vec<WorldTile>,
+}
+
+fn default_tileset() -> String {
+    DEFAULT_TILESET.to_string()
+}
+
+#[derive(Debug)]
+pub enum MapFileError {
+    Ron(ron::error::SpannedError),
+    /// The amount of tiles does not match the dimensions of the map
+    TileCount {
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl Display for MapFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MapFileError::Ron(e) => write!(f, "Bad ron in map file: {}", e),
+            MapFileError::TileCount { expected, found } => {
+                write!(f, "Map file has {} tiles but its dimensions need {}", found, expected)
+            }
+        }
+    }
+}
+
 /// This is used over position when (de)serialization is needed.
 /// Position cannot impl Deserialize because it needs to impl ConvertSaveload
 #[derive(Deserialize, Serialize, Clone, Default)]
@@ -46,11 +101,25 @@ pub struct WorldTile {
     pub transparent: bool,
     pub is_blocked: bool,
     pub height: u8,
+    /// Action points it takes to step onto the tile
+    #[serde(default = "default_move_cost")]
+    pub move_cost: u32,
+}
+
+fn default_move_cost() -> u32 {
+    MOVE_COST
 }
 
 impl WorldTile {
     pub fn water(height: u8) -> WorldTile {
-        Self { name: "Water".to_string(), atlas_idx: CH_WATER as usize, transparent: true, height, is_blocked: false }
+        Self {
+            name: "Water".to_string(),
+            atlas_idx: CH_WATER as usize,
+            transparent: true,
+            height,
+            is_blocked: false,
+            move_cost: WADING_MOVE_COST,
+        }
     }
 
     pub fn grass() -> Self {
@@ -60,10 +129,44 @@ impl WorldTile {
             transparent: true,
             height: 0,
             is_blocked: false,
+            move_cost: MOVE_COST,
+        }
+    }
+
+    /// Solid rock, its tile kind makes it blocking and breakable
+    pub fn mountain() -> Self {
+        Self {
+            name: "Mountain".to_string(),
+            atlas_idx: CH_MOUNTAIN as usize,
+            transparent: true,
+            height: 0,
+            is_blocked: false,
+            move_cost: MOVE_COST,
+        }
+    }
+
+    /// Ground of the layers below the surface
+    pub fn cave_floor() -> Self {
+        Self {
+            name: "Cave Floor".to_string(),
+            atlas_idx: CH_CAVE_FLOOR as usize,
+            transparent: true,
+            height: 0,
+            is_blocked: false,
+            move_cost: MOVE_COST,
         }
     }
 }
 
+/// Plain floor for the layer at the depth, 0 being the surface
+pub fn floor_tile(depth: usize) -> WorldTile {
+    if depth == 0 {
+        WorldTile::grass()
+    } else {
+        WorldTile::cave_floor()
+    }
+}
+
 impl Default for WorldTile {
     fn default() -> Self {
         Self::grass()
@@ -77,14 +180,25 @@ pub struct WorldObject {
     /// Unique id to find the world object's static data
     pub id: ObjectID,
     pub name: String,
-    pub atlas_index: u8,
+    pub atlas_index: usize,
     pub is_blocking: bool,
     pub breakable: Option<String>,
     pub health_stats: Option<HealthStats>,
     pub grass: Option<String>,
+    pub cooking_station: Option<String>,
     pub foreground: Option<(u8, u8, u8)>,
+    pub background: Option<(u8, u8, u8, u8)>,
+    /// Swapped in for `atlas_index` as the object loses health, empty when it always looks intact
+    pub damage_stages: Vec<usize>,
+    pub tags: Option<Tags>,
     pub loot: Option<Drops>,
     pub impact_sound: String,
+    /// Turns until the object grows back after being broken, `None` never comes back
+    pub respawn_turns: Option<usize>,
+    pub light: Option<LightSource>,
+    pub particles: Option<ParticleEmitter>,
+    /// Item stacks that can be stored in the object
+    pub container: Option<usize>,
 }
 
 /// Defines the type of entity existing in a tile for quick lookup and action handling
@@ -99,6 +213,12 @@ pub enum TileEntity {
 }
 
 impl TileEntity {
+    pub fn entity(&self) -> Entity {
+        match self {
+            TileEntity::Fishable(e) | TileEntity::Breakable(e) | TileEntity::Item(e) | TileEntity::Blocking(e) => *e,
+        }
+    }
+
     /// Attempts to grab the inner entity if it is an item
     pub fn as_item_entity(&self) -> Option<&Entity> {
         match self {
@@ -115,7 +235,16 @@ impl TileEntity {
 
 impl Map {
     pub fn empty(width: usize, height: usize) -> Self {
-        Self { tiles: vec![], tile_entities: vec![], width, height, chunk_coords: (0, 0).into(), tile_atlas_index: 0 }
+        Self {
+            tiles: vec![],
+            tile_entities: vec![],
+            static_entities: vec![],
+            revealed_tiles: vec![],
+            width,
+            height,
+            chunk_coords: (0, 0).into(),
+            tileset: default_tileset(),
+        }
     }
 
     // Makes empty map of a size
@@ -123,17 +252,39 @@ impl Map {
         Map {
             tiles: vec![WorldTile::grass(); width * height],
             tile_entities: vec![vec![]; width * height],
+            static_entities: vec![vec![]; width * height],
+            revealed_tiles: vec![false; width * height],
             width,
             height,
             chunk_coords: world_coords.into(),
-            tile_atlas_index: 0,
+            tileset: default_tileset(),
         }
     }
 
+    /// Does no bounds check, an `x` past the width wraps onto the next row. Only for loops that already keep to the
+    /// map, anything else should use `checked_xy_to_idx`
     pub fn xy_to_idx(&self, x: usize, y: usize) -> usize {
         xy_to_idx_given_width(x, y, self.width)
     }
 
+    /// The index of the tile at `x`, `y` or `None` when it is off the map
+    pub fn checked_xy_to_idx(&self, x: usize, y: usize) -> Option<usize> {
+        (x < self.width && y < self.height).then(|| xy_to_idx_given_width(x, y, self.width))
+    }
+
+    /// The point of the tile at `idx` or `None` when it is past the last tile
+    pub fn checked_idx_to_point(&self, idx: usize) -> Option<Point> {
+        (idx < self.width * self.height).then(|| idx_to_point(idx, self.width))
+    }
+
+    /// The entities on the tile, off the map there are none
+    fn entities_at(&self, pos: &Position) -> &[TileEntity] {
+        match self.checked_xy_to_idx(pos.x, pos.y) {
+            Some(idx) => &self.tile_entities[idx],
+            None => &[],
+        }
+    }
+
     #[allow(unused)]
     pub fn chunk_x(&self) -> usize {
         self.chunk_coords.x
@@ -147,36 +298,91 @@ impl Map {
     /// Gets all the entities in the tile that are an item.
     /// It returns an iterator since often only the first value is used.
     pub fn all_items_at_pos(&self, pos: &Position) -> impl Iterator<Item = &TileEntity> {
-        self.tile_entities[self.xy_to_idx(pos.x, pos.y)].iter().filter(|te| te.as_item_entity().is_some())
+        self.entities_at(pos).iter().filter(|te| te.as_item_entity().is_some())
     }
 
     /// Attempts to get the first entity at the pos based on the contents of the tile
     /// Will return `None` if no entities are present in the tile
     pub fn first_entity_in_pos(&self, pos: &Position) -> Option<&TileEntity> {
-        self.tile_entities[self.xy_to_idx(pos.x, pos.y)].iter().min_by_key(|&tile_entity| match tile_entity {
-            TileEntity::Fishable(_) => 9,
-            TileEntity::Breakable(_) => 15,
-            TileEntity::Blocking(_) => 20,
-            TileEntity::Item(_) => 21,
-        })
+        self.first_entity_where(pos, |_| true)
     }
 
-    /// Checks a position on the map to see if it is blocked
+    /// The same as `first_entity_in_pos` but only considers the entities `keep` returns true for
+    pub fn first_entity_where(&self, pos: &Position, keep: impl Fn(&TileEntity) -> bool) -> Option<&TileEntity> {
+        self.entities_at(pos).iter().filter(|tile_entity| keep(tile_entity)).min_by_key(
+            |&tile_entity| match tile_entity {
+                TileEntity::Fishable(_) => 9,
+                TileEntity::Breakable(_) => 15,
+                TileEntity::Blocking(_) => 20,
+                TileEntity::Item(_) => 21,
+            },
+        )
+    }
+
+    /// Checks a position on the map to see if it is blocked, off the map counts as blocked
     pub fn is_blocked(&self, pos: &Position) -> bool {
-        self.tile_entities[self.xy_to_idx(pos.x, pos.y)].iter().any(|te| te.is_blocker())
+        match self.checked_xy_to_idx(pos.x, pos.y) {
+            Some(idx) => self.tile_entities[idx].iter().any(|te| te.is_blocker()),
+            None => true,
+        }
+    }
+
+    /// Action points it takes to step onto the position
+    pub fn move_cost(&self, pos: &Position) -> u32 {
+        self.checked_xy_to_idx(pos.x, pos.y).map_or(MOVE_COST, |idx| self.tiles[idx].move_cost)
     }
 
     pub fn in_bounds(&self, pos: Point) -> bool {
         pos.x >= 0 && pos.x < self.width as i32 && pos.y >= 0 && pos.y < self.height as i32
     }
 
+    /// Checks if the player has ever seen the tile at the point
+    pub fn is_revealed(&self, pos: Point) -> bool {
+        self.in_bounds(pos) && self.revealed_tiles.get(self.xy_to_idx(pos.x as usize, pos.y as usize)) == Some(&true)
+    }
+
+    /// Writes the tile grid of the map to ron, entities in the map are not included
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        let map_file = MapFile {
+            width: self.width,
+            height: self.height,
+            chunk_coords: self.chunk_coords.clone(),
+            tileset: self.tileset.clone(),
+            tiles: self.tiles.clone(),
+        };
+        ron::ser::to_string_pretty(&map_file, PrettyConfig::default())
+    }
+
+    /// Reads a map written by `to_ron`. The `tile_entities` are left empty for the indexing systems to fill
+    pub fn from_ron(contents: &str) -> Result<Self, MapFileError> {
+        let map_file: MapFile = ron::from_str(contents).map_err(MapFileError::Ron)?;
+        let tile_count = map_file.width * map_file.height;
+        if map_file.tiles.len() != tile_count {
+            return Err(MapFileError::TileCount { expected: tile_count, found: map_file.tiles.len() });
+        }
+
+        Ok(Map {
+            tiles: map_file.tiles,
+            tile_entities: vec![vec![]; tile_count],
+            static_entities: vec![vec![]; tile_count],
+            revealed_tiles: vec![false; tile_count],
+            width: map_file.width,
+            height: map_file.height,
+            chunk_coords: map_file.chunk_coords,
+            tileset: map_file.tileset,
+        })
+    }
+
     pub fn set_tile(&mut self, tile: &WorldTile, x: usize, y: usize) {
-        let idx = self.xy_to_idx(x, y);
-        self.tiles[idx] = tile.clone();
+        match self.checked_xy_to_idx(x, y) {
+            Some(idx) => self.tiles[idx] = tile.clone(),
+            None => warn!("Tried to set the tile at {}, {} which is off the map", x, y),
+        }
     }
 }
 
-pub fn successors(map: &Map, curr: &Position) -> Vec<(Position, u32)> {
+/// Neighbouring positions that are in bounds and not `blocked`, with the cost of stepping onto them
+pub fn successors(map: &Map, curr: &Position, blocked: impl Fn(&Position) -> bool) -> Vec<(Position, u32)> {
     let (x, y) = (curr.x as i32, curr.y as i32);
     let mut successors = Vec::new();
 
@@ -189,8 +395,8 @@ pub fn successors(map: &Map, curr: &Position) -> Vec<(Position, u32)> {
         // Check if the new position is within bounds and not blocked
         if map.in_bounds(Point::new(new_x, new_y)) {
             let new_pos = Position::new(new_x as usize, new_y as usize);
-            if !map.is_blocked(&new_pos) {
-                successors.push((new_pos, 1));
+            if !blocked(&new_pos) {
+                successors.push((new_pos, map.move_cost(&new_pos)));
             }
         }
     }
@@ -206,15 +412,31 @@ pub fn is_goal(curr_pos: &Position, dest_pos: &Position) -> bool {
     curr_pos == dest_pos
 }
 
-/// Renders the current map resource to the current console layer
-pub fn render_map(ecs: &World, batch: &mut DrawBatch) {
+/// Background of every map tile that nothing tints
+pub const MAP_BACKGROUND: (u8, u8, u8) = BLACK;
+
+pub fn map_background() -> RGBA {
+    RGB::from(MAP_BACKGROUND).to_rgba(1.0)
+}
+
+/// Renders the current map resource to the current console layer, `tints` replace the background of their tiles
+pub fn render_map(ecs: &World, batch: &mut DrawBatch, tints: &HashMap<Point, RGBA>) {
     let map = ecs.fetch::<MapRes>();
+    let light_map = ecs.fetch::<LightMap>();
 
     let bounding_box = get_camera_bounds(ecs);
+    let fg: RGBA = if is_outdoors(&ecs.fetch::<MapLayers>()) {
+        ecs.fetch::<Weather>().tint()
+    } else {
+        RGB::from(WHITE).to_rgba(1.0)
+    };
 
     for x in bounding_box.x1..bounding_box.x2 {
         for y in bounding_box.y1..bounding_box.y2 {
             let atlas_index = if x < map.0.width as i32 && y < map.0.height as i32 && x >= 0 && y >= 0 {
+                if !map.0.is_revealed(Point::new(x, y)) {
+                    continue;
+                }
                 map.0.tiles[map.0.xy_to_idx(x as usize, y as usize)].atlas_idx
             } else {
                 xy_to_idx_given_width(0, 2, 16)
@@ -223,7 +445,14 @@ pub fn render_map(ecs: &World, batch: &mut DrawBatch) {
             let screen_x = x - bounding_box.x1;
             let screen_y = y - bounding_box.y1;
 
-            batch.set(Point::new(screen_x, screen_y), ColorPair::new(WHITE, BLACK), atlas_index);
+            let screen_pos = Point::new(screen_x, screen_y);
+            let bg = tints.get(&screen_pos).copied().unwrap_or_else(map_background);
+            let map_pos = Point::new(x, y);
+            batch.set(
+                screen_pos,
+                ColorPair::new(light_map.shade(fg, map_pos), light_map.shade(bg, map_pos)),
+                atlas_index,
+            );
         }
     }
 }
@@ -231,3 +460,50 @@ pub fn render_map(ecs: &World, batch: &mut DrawBatch) {
 pub fn xy_to_idx_given_width(x: usize, y: usize, width: usize) -> usize {
     x + y * width
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ron_round_trip_keeps_tiles() {
+        let mut map = Map::new(3, 2, (1, 4));
+        map.set_tile(&WorldTile::water(3), 2, 1);
+        map.tileset = "terrain_town_forest".to_string();
+
+        let loaded = Map::from_ron(&map.to_ron().unwrap()).unwrap();
+
+        assert_eq!((loaded.width, loaded.height), (3, 2));
+        assert_eq!((loaded.chunk_x(), loaded.chunk_y()), (1, 4));
+        assert_eq!(loaded.tileset, "terrain_town_forest");
+        assert_eq!(loaded.tiles[loaded.xy_to_idx(2, 1)].name, "Water");
+        assert_eq!(loaded.tiles[loaded.xy_to_idx(2, 1)].height, 3);
+        assert!(loaded.tile_entities.iter().all(|tile| tile.is_empty()));
+        assert_eq!(loaded.tile_entities.len(), 6);
+    }
+
+    #[test]
+    fn ron_with_missing_tiles_is_rejected() {
+        let mut map = Map::new(2, 2, (0, 0));
+        map.tiles.pop();
+
+        assert!(matches!(
+            Map::from_ron(&map.to_ron().unwrap()),
+            Err(MapFileError::TileCount { expected: 4, found: 3 })
+        ));
+    }
+    #[test]
+    fn off_map_coordinates_have_no_index() {
+        let mut map = Map::new(3, 2, (0, 0));
+        assert_eq!(map.checked_xy_to_idx(2, 1), Some(5));
+        assert_eq!(map.checked_xy_to_idx(3, 0), None);
+        assert_eq!(map.checked_xy_to_idx(0, 2), None);
+        assert_eq!(map.checked_idx_to_point(4), Some(Point::new(1, 1)));
+        assert_eq!(map.checked_idx_to_point(6), None);
+
+        // x past the width would wrap onto (0, 1) without the check
+        map.set_tile(&WorldTile::water(3), 3, 0);
+        assert_ne!(map.tiles[map.xy_to_idx(0, 1)].name, "Water");
+        assert!(map.is_blocked(&Position::new(3, 0)));
+    }
+}
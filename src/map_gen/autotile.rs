@@ -0,0 +1,117 @@
+use crate::{
+    data_read::prelude::{AutotileRule, Neighbours, AUTOTILE_DB},
+    map::Map,
+    tilesets::tileset_font,
+};
+
+/// Offsets of each neighbour in the order of their bit in the mask
+const NEIGHBOUR_OFFSETS: [(i32, i32); 8] = [(0, -1), (1, 0), (0, 1), (-1, 0), (1, -1), (1, 1), (-1, 1), (-1, -1)];
+
+/// Picks the atlas index of every tile in the map based on its neighbours
+pub fn autotile_map(map: &mut Map) {
+    let rules = AUTOTILE_DB.lock().unwrap().rules_for(tileset_font(&map.tileset));
+    for y in 0..map.height {
+        for x in 0..map.width {
+            apply_rules(map, &rules, x, y);
+        }
+    }
+}
+
+/// Updates the tile and the tiles around it, call this after a single tile changes
+pub fn autotile_around(map: &mut Map, x: usize, y: usize) {
+    let rules = AUTOTILE_DB.lock().unwrap().rules_for(tileset_font(&map.tileset));
+    apply_rules(map, &rules, x, y);
+    for (dx, dy) in NEIGHBOUR_OFFSETS {
+        if let Some((nx, ny)) = offset(map, x, y, dx, dy) {
+            apply_rules(map, &rules, nx, ny);
+        }
+    }
+}
+
+fn apply_rules(map: &mut Map, rules: &[AutotileRule], x: usize, y: usize) {
+    let idx = map.xy_to_idx(x, y);
+    if let Some(rule) = rules.iter().find(|rule| rule.tile == map.tiles[idx].name) {
+        let mask = neighbour_mask(map, x, y, rule.neighbours);
+        map.tiles[idx].atlas_idx = rule.atlas_for(mask);
+    }
+}
+
+/// Builds the bitmask of neighbours sharing the tile's name, tiles off the map count as the same
+fn neighbour_mask(map: &Map, x: usize, y: usize, neighbours: Neighbours) -> u8 {
+    let name = &map.tiles[map.xy_to_idx(x, y)].name;
+    let checked = match neighbours {
+        Neighbours::Four => 4,
+        Neighbours::Eight => 8,
+    };
+
+    let mut mask = 0;
+    for (bit, (dx, dy)) in NEIGHBOUR_OFFSETS.iter().take(checked).enumerate() {
+        let same = match offset(map, x, y, *dx, *dy) {
+            Some((nx, ny)) => map.tiles[map.xy_to_idx(nx, ny)].name == *name,
+            None => true,
+        };
+        if same {
+            mask |= 1 << bit;
+        }
+    }
+    mask
+}
+
+fn offset(map: &Map, x: usize, y: usize, dx: i32, dy: i32) -> Option<(usize, usize)> {
+    let nx = x as i32 + dx;
+    let ny = y as i32 + dy;
+    if nx < 0 || ny < 0 || nx >= map.width as i32 || ny >= map.height as i32 {
+        return None;
+    }
+    Some((nx as usize, ny as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::WorldTile;
+
+    fn water_rule(neighbours: Neighbours) -> AutotileRule {
+        AutotileRule {
+            tileset: 0,
+            tile: "Water".to_string(),
+            neighbours,
+            base: 80,
+            masks: vec![(0b1011, 81), (0b1111, 82)],
+        }
+    }
+
+    #[test]
+    fn shoreline_picks_edge_variant() {
+        // Water fills the top row and the middle column, grass everywhere else
+        let mut map = Map::new(3, 3, (0, 0));
+        for x in 0..3 {
+            map.set_tile(&WorldTile::water(0), x, 0);
+        }
+        map.set_tile(&WorldTile::water(0), 1, 1);
+
+        assert_eq!(neighbour_mask(&map, 1, 0, Neighbours::Four), 0b1111);
+        // only the south neighbour is grass
+        assert_eq!(neighbour_mask(&map, 0, 0, Neighbours::Four), 0b1011);
+
+        let rules = [water_rule(Neighbours::Four)];
+        apply_rules(&mut map, &rules, 0, 0);
+        apply_rules(&mut map, &rules, 1, 0);
+        apply_rules(&mut map, &rules, 1, 1);
+        assert_eq!(map.tiles[map.xy_to_idx(0, 0)].atlas_idx, 81);
+        assert_eq!(map.tiles[map.xy_to_idx(1, 0)].atlas_idx, 82);
+        // only the north neighbour is water, that mask isn't in the table
+        assert_eq!(map.tiles[map.xy_to_idx(1, 1)].atlas_idx, 80);
+    }
+
+    #[test]
+    fn eight_neighbours_include_corners() {
+        let mut map = Map::new(3, 3, (0, 0));
+        map.set_tile(&WorldTile::water(0), 1, 1);
+        map.set_tile(&WorldTile::water(0), 2, 0);
+
+        // only the north-east corner matches inside the map
+        assert_eq!(neighbour_mask(&map, 1, 1, Neighbours::Eight), 0b0001_0000);
+        assert_eq!(neighbour_mask(&map, 1, 1, Neighbours::Four), 0);
+    }
+}
@@ -0,0 +1,273 @@
+//! Cellular automata caves, an alternative to the noise terrain that needs nothing but a seed.
+//! The same seed and size always make the same map and every floor tile can be walked to from the spawn.
+
+use std::collections::VecDeque;
+
+use bracket_lib::random::RandomNumberGenerator;
+use specs::World;
+
+use super::{apply_tile_kinds, autotile_map};
+use crate::{
+    components::Position,
+    map::{Map, WorldTile},
+    tilesets::DEFAULT_TILESET,
+};
+
+/// Smallest width or height caves can be made at, anything smaller is all wall
+pub const MIN_CAVE_SIZE: usize = 10;
+/// One pool of water is attempted for every this many tiles in the map
+const TILES_PER_POOL: usize = 400;
+const MAX_POOL_SIZE: usize = 8;
+
+pub struct CaveConfig {
+    pub width: usize,
+    pub height: usize,
+    pub seed: u64,
+    /// Percent of tiles that start out as rock before smoothing
+    pub fill_percent: i32,
+    /// Passes that round the noise out into caves, more passes make smoother walls
+    pub smoothing_steps: usize,
+}
+
+impl CaveConfig {
+    pub fn new(width: usize, height: usize, seed: u64) -> Self {
+        Self { width, height, seed, fill_percent: 45, smoothing_steps: 4 }
+    }
+}
+
+pub struct GeneratedCaves {
+    pub map: Map,
+    /// Floor the player can start on, every other floor tile is reachable from here
+    pub spawn: Position,
+    /// The seed the caves were made from, generating with it again gives the same map
+    pub seed: u64,
+}
+
+/// Makes a cave map where `spawn` and every position in `keep_open` are floor connected to each other.
+/// Positions too close to the edge are moved inside the outer wall
+pub fn gen_caves(ecs: &mut World, cfg: &CaveConfig, spawn: Position, keep_open: &[Position]) -> GeneratedCaves {
+    let layout = CaveLayout::generate(cfg, spawn, keep_open);
+
+    let mut map = Map::new(cfg.width, cfg.height, (0, 0));
+    map.tileset = DEFAULT_TILESET.to_string();
+    for (idx, cell) in layout.cells.iter().enumerate() {
+        let tile = match cell {
+            Cell::Rock => WorldTile::mountain(),
+            Cell::Floor => WorldTile::grass(),
+            Cell::Water => WorldTile::water(0),
+        };
+        map.tiles[idx] = tile;
+    }
+    apply_tile_kinds(&mut map, ecs);
+    autotile_map(&mut map);
+
+    GeneratedCaves { map, spawn: layout.spawn, seed: cfg.seed }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Cell {
+    Rock,
+    Floor,
+    Water,
+}
+
+/// What each tile will be before the tiles are made
+struct CaveLayout {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+    spawn: Position,
+}
+
+impl CaveLayout {
+    fn generate(cfg: &CaveConfig, spawn: Position, keep_open: &[Position]) -> Self {
+        let (width, height) = (cfg.width, cfg.height);
+        let mut layout = Self {
+            width,
+            height,
+            cells: vec![Cell::Rock; width * height],
+            spawn: Position::new(width / 2, height / 2),
+        };
+        if width < MIN_CAVE_SIZE || height < MIN_CAVE_SIZE {
+            return layout;
+        }
+
+        let mut rng = RandomNumberGenerator::seeded(cfg.seed);
+        for y in 1..height - 1 {
+            for x in 1..width - 1 {
+                if rng.range(0, 100) >= cfg.fill_percent {
+                    layout.cells[y * width + x] = Cell::Floor;
+                }
+            }
+        }
+        for _ in 0..cfg.smoothing_steps {
+            layout.smooth();
+        }
+
+        layout.spawn = layout.inside_walls(spawn);
+        for pos in keep_open.iter().chain([spawn].iter()) {
+            let pos = layout.inside_walls(*pos);
+            layout.open_around(pos);
+        }
+        layout.connect_regions();
+        layout.fill_pools(&mut rng, keep_open);
+        layout
+    }
+
+    /// Rock with mostly rock around it stays rock and floor with mostly floor around it stays floor, the map's
+    /// edge counts as rock so the outer wall is always solid
+    fn smooth(&mut self) {
+        let mut smoothed = self.cells.clone();
+        for y in 1..self.height - 1 {
+            for x in 1..self.width - 1 {
+                let rocks = self.rock_neighbours(x, y);
+                if rocks >= 5 {
+                    smoothed[y * self.width + x] = Cell::Rock;
+                } else if rocks <= 3 {
+                    smoothed[y * self.width + x] = Cell::Floor;
+                }
+            }
+        }
+        self.cells = smoothed;
+    }
+
+    fn rock_neighbours(&self, x: usize, y: usize) -> usize {
+        let mut rocks = 0;
+        for ny in y - 1..=y + 1 {
+            for nx in x - 1..=x + 1 {
+                if (nx, ny) != (x, y) && self.cells[ny * self.width + nx] == Cell::Rock {
+                    rocks += 1;
+                }
+            }
+        }
+        rocks
+    }
+
+    fn inside_walls(&self, pos: Position) -> Position {
+        Position::new(pos.x.clamp(1, self.width - 2), pos.y.clamp(1, self.height - 2))
+    }
+
+    /// Digs out the tile and the ones next to it so there is room to stand
+    fn open_around(&mut self, pos: Position) {
+        for y in pos.y.saturating_sub(1).max(1)..=(pos.y + 1).min(self.height - 2) {
+            for x in pos.x.saturating_sub(1).max(1)..=(pos.x + 1).min(self.width - 2) {
+                self.cells[y * self.width + x] = Cell::Floor;
+            }
+        }
+    }
+
+    /// Every floor tile that can be walked to from `start`
+    fn reachable_from(&self, start: usize) -> Vec<bool> {
+        let mut seen = vec![false; self.cells.len()];
+        if self.cells[start] != Cell::Floor {
+            return seen;
+        }
+        seen[start] = true;
+        let mut frontier = VecDeque::from([start]);
+        while let Some(idx) = frontier.pop_front() {
+            let (x, y) = (idx % self.width, idx / self.width);
+            for (nx, ny) in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+                let next = ny * self.width + nx;
+                if self.cells[next] == Cell::Floor && !seen[next] {
+                    seen[next] = true;
+                    frontier.push_back(next);
+                }
+            }
+        }
+        seen
+    }
+
+    /// Tunnels from every pocket of floor that can't be reached to the spawn
+    fn connect_regions(&mut self) {
+        let spawn = self.spawn.to_idx(self.width);
+        let mut connected = self.reachable_from(spawn);
+        for idx in 0..self.cells.len() {
+            if self.cells[idx] != Cell::Floor || connected[idx] {
+                continue;
+            }
+            self.tunnel(Position::from_idx(idx, self.width), self.spawn);
+            connected = self.reachable_from(spawn);
+        }
+    }
+
+    /// Digs across then down or up from `from` until reaching `to`
+    fn tunnel(&mut self, from: Position, to: Position) {
+        let (mut x, mut y) = (from.x, from.y);
+        while x != to.x {
+            self.cells[y * self.width + x] = Cell::Floor;
+            x = if x < to.x { x + 1 } else { x - 1 };
+        }
+        while y != to.y {
+            self.cells[y * self.width + x] = Cell::Floor;
+            y = if y < to.y { y + 1 } else { y - 1 };
+        }
+    }
+
+    /// Floods small pools of water across the floor, a tile only becomes water when every floor tile can still be
+    /// reached from the spawn afterwards
+    fn fill_pools(&mut self, rng: &mut RandomNumberGenerator, keep_open: &[Position]) {
+        let spawn = self.spawn.to_idx(self.width);
+        let kept: Vec<usize> = keep_open.iter().map(|pos| self.inside_walls(*pos).to_idx(self.width)).collect();
+        let pools = self.cells.len() / TILES_PER_POOL;
+        for _ in 0..pools {
+            let mut at = rng.range(0, self.cells.len());
+            for _ in 0..MAX_POOL_SIZE {
+                if at != spawn && !kept.contains(&at) && self.cells[at] == Cell::Floor && self.stays_connected(at) {
+                    self.cells[at] = Cell::Water;
+                }
+                let (x, y) = (at % self.width, at / self.width);
+                let (nx, ny) = match rng.range(0, 4) {
+                    0 => (x + 1, y),
+                    1 => (x.saturating_sub(1), y),
+                    2 => (x, y + 1),
+                    _ => (x, y.saturating_sub(1)),
+                };
+                at = ny.min(self.height - 1) * self.width + nx.min(self.width - 1);
+            }
+        }
+    }
+
+    fn stays_connected(&mut self, idx: usize) -> bool {
+        let floor_count = self.cells.iter().filter(|cell| **cell == Cell::Floor).count();
+        self.cells[idx] = Cell::Water;
+        let reached = self.reachable_from(self.spawn.to_idx(self.width)).iter().filter(|seen| **seen).count();
+        self.cells[idx] = Cell::Floor;
+        reached == floor_count - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(seed: u64) -> CaveLayout {
+        CaveLayout::generate(&CaveConfig::new(40, 30, seed), Position::new(20, 15), &[Position::new(2, 2)])
+    }
+
+    #[test]
+    fn same_seed_makes_the_same_caves() {
+        assert_eq!(layout(7).cells, layout(7).cells);
+        assert_ne!(layout(7).cells, layout(8).cells);
+    }
+
+    #[test]
+    fn every_floor_tile_is_reachable_from_the_spawn() {
+        for seed in 0..20 {
+            let caves = layout(seed);
+            let reached = caves.reachable_from(caves.spawn.to_idx(caves.width));
+            for (idx, cell) in caves.cells.iter().enumerate() {
+                assert!(*cell != Cell::Floor || reached[idx], "seed {} left tile {} cut off", seed, idx);
+            }
+            assert!(reached[Position::new(2, 2).to_idx(caves.width)]);
+        }
+    }
+
+    #[test]
+    fn outer_wall_is_solid() {
+        let caves = layout(3);
+        for x in 0..caves.width {
+            assert_eq!(caves.cells[x], Cell::Rock);
+            assert_eq!(caves.cells[(caves.height - 1) * caves.width + x], Cell::Rock);
+        }
+    }
+}
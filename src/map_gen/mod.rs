@@ -1,18 +1,58 @@
+mod autotile;
+mod caves;
+
+use std::collections::HashSet;
 use std::hash::{DefaultHasher, Hash, Hasher};
 
 use bracket_lib::random::RandomNumberGenerator;
-use log::{error, info};
-use specs::{Builder, World, WorldExt};
+use log::{error, info, warn};
+use specs::{
+    saveload::{MarkedBuilder, SimpleMarker},
+    Builder, Entity, Join, World, WorldExt,
+};
+
+pub use autotile::{autotile_around, autotile_map};
+pub use caves::{gen_caves, CaveConfig, MIN_CAVE_SIZE};
 
 use crate::{
-    components::{Blocking, Position, Water},
-    data_read::prelude::{build_world_obj, NOISE_DB},
+    components::{Blocking, Breakable, Hazard, HealthStats, Name, Position, Renderable, Tags, Water},
+    currents::set_currents,
+    data_read::prelude::{build_world_obj, TerrainTile, TileKind, NOISE_DB, TILE_KIND_DB, VEIN_TAG},
+    difficulty::DifficultyLevel,
     game_init::InputWorldConfig,
-    map::{Map, WorldTile},
-    saveload::{save_game_exists, SAVE_EXTENSION},
-    FONT_TERRAIN_FOREST,
+    layers::MAX_LAYERS,
+    map::{floor_tile, Map, WorldTile},
+    rng::GameRng,
+    saveload::{save_game_exists, SerializeMe, SAVE_EXTENSION},
+    tilesets::DEFAULT_TILESET,
 };
 
+/// How the surface of a new world is made
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum MapGenerator {
+    /// Hills, lakes and resources from the noise in `noise.json5`
+    #[default]
+    Terrain,
+    /// Rocky caves with pools of water, see `caves.rs`
+    Caves,
+}
+
+impl MapGenerator {
+    pub fn next(&self) -> Self {
+        match self {
+            MapGenerator::Terrain => MapGenerator::Caves,
+            MapGenerator::Caves => MapGenerator::Terrain,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            MapGenerator::Terrain => "Terrain",
+            MapGenerator::Caves => "Caves",
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Eq)]
 pub struct WorldConfig {
     pub world_name: String,
@@ -20,6 +60,10 @@ pub struct WorldConfig {
     pub height: usize,
     pub sea_level: u8,
     pub seed: u64,
+    /// How many layers can be dug down through, 1 is only the surface
+    pub layers: usize,
+    pub generator: MapGenerator,
+    pub difficulty: DifficultyLevel,
 }
 
 impl Default for WorldConfig {
@@ -30,6 +74,9 @@ impl Default for WorldConfig {
             height: 100,
             sea_level: (0.13f32 * 255.0).round() as u8,
             seed: 0,
+            layers: 1,
+            generator: MapGenerator::default(),
+            difficulty: DifficultyLevel::default(),
         }
     }
 }
@@ -66,6 +113,16 @@ impl WorldConfig {
                 0
             }
         };
+        let layers = match iwc.layers.parse::<usize>() {
+            Ok(l) if (1..=MAX_LAYERS).contains(&l) => l,
+            _ => {
+                errors.push(format!("Invalid layers must be 1 - {}", MAX_LAYERS));
+                0
+            }
+        };
+        if iwc.generator == MapGenerator::Caves && (width < MIN_CAVE_SIZE || height < MIN_CAVE_SIZE) {
+            errors.push(format!("Caves must be at least {} by {}", MIN_CAVE_SIZE, MIN_CAVE_SIZE));
+        }
         let seed = if iwc.seed.is_empty() {
             0
         } else {
@@ -77,7 +134,16 @@ impl WorldConfig {
         if !errors.is_empty() {
             return Err(errors);
         }
-        Ok(Self { world_name: iwc.world_name.clone(), width, height, seed, sea_level })
+        Ok(Self {
+            world_name: iwc.world_name.clone(),
+            width,
+            height,
+            seed,
+            sea_level,
+            layers,
+            generator: iwc.generator,
+            difficulty: iwc.difficulty,
+        })
     }
 }
 
@@ -91,7 +157,7 @@ pub fn gen_world(ecs: &mut World, wc: &WorldConfig) -> Map {
 
     let mut new_map = Map::new(wc.width, wc.height, (0, 0));
 
-    new_map.tile_atlas_index = FONT_TERRAIN_FOREST;
+    new_map.tileset = DEFAULT_TILESET.to_string();
     for x in 0..wc.width {
         for y in 0..wc.height {
             new_map.set_tile(&WorldTile::grass(), x, y);
@@ -102,8 +168,54 @@ pub fn gen_world(ecs: &mut World, wc: &WorldConfig) -> Map {
     // let _ = build_world_obj("Boulder".to_string(), Position::new(15, 20), ecs);
 
     generate_heights(&mut new_map);
-    fill_water_to_level(&mut new_map, wc.sea_level, ecs);
+    fill_water_to_level(&mut new_map, wc.sea_level);
+    apply_tile_kinds(&mut new_map, ecs);
+    set_currents(&new_map, ecs);
     generate_resources(&mut new_map, ecs, &mut rng);
+    autotile_map(&mut new_map);
+
+    new_map
+}
+
+/// Generates a cave layer below the surface, solid rock with tunnels that differ for every depth.
+/// The tiles around `arrival` are dug out so there is room to stand
+pub fn gen_layer(ecs: &mut World, size: (usize, usize), seed: u64, depth: usize, arrival: &Position) -> Map {
+    let (width, height) = size;
+    let mut new_map = Map::new(width, height, (0, 0));
+    new_map.tileset = DEFAULT_TILESET.to_string();
+
+    {
+        let mut noise_db = NOISE_DB.lock().unwrap();
+        // the noise may have been seeded by another world since this one was made
+        noise_db.reseed(seed);
+        match noise_db.get_by_name("caves") {
+            Some(noise) => {
+                // each layer reads a different part of the noise so no two layers are the same
+                let offset = depth * width;
+                for x in 0..width {
+                    for y in 0..height {
+                        let tile = noise.gen_tile(x + offset, y);
+                        new_map.set_tile(&tile, x, y);
+                    }
+                }
+            }
+            None => error!("No caves noise was found, depth {} will be an open floor", depth),
+        }
+    }
+
+    for x in arrival.x.saturating_sub(1)..=(arrival.x + 1).min(width - 1) {
+        for y in arrival.y.saturating_sub(1)..=(arrival.y + 1).min(height - 1) {
+            new_map.set_tile(&floor_tile(depth), x, y);
+        }
+    }
+    if depth > 0 {
+        for tile in new_map.tiles.iter_mut().filter(|tile| tile.name == WorldTile::grass().name) {
+            *tile = floor_tile(depth);
+        }
+    }
+
+    apply_tile_kinds(&mut new_map, ecs);
+    autotile_map(&mut new_map);
 
     new_map
 }
@@ -140,26 +252,162 @@ fn generate_resources(map: &mut Map, ecs: &mut World, rng: &mut RandomNumberGene
                 }
 
                 if let Err(e) = build_world_obj(name, Position::new(x, y), ecs) {
-                    error!("Resources failed to build: {:?}", e);
+                    error!("Resources failed to build: {}", e);
                 }
             }
         }
     }
 }
 
-fn fill_water_to_level(map: &mut Map, level: u8, ecs: &mut World) {
+fn fill_water_to_level(map: &mut Map, level: u8) {
     for x in 0..map.width {
         for y in 0..map.height {
             if let Some(tile) = map.tiles.get(map.xy_to_idx(x, y)) {
                 if tile.height < level {
                     map.set_tile(&WorldTile::water(tile.height), x, y);
-                    ecs.create_entity().with(Water {}).with(Position::new(x, y)).with(Blocking {}).build();
                 }
             }
         }
     }
 }
 
+/// Gives every tile the behavior of its `TileKind`, tiles that need components get an entity carrying them.
+/// Tiles without a kind are plain floor
+pub fn apply_tile_kinds(map: &mut Map, ecs: &mut World) {
+    let tile_kinds = TILE_KIND_DB.lock().unwrap();
+    let mut unknown = HashSet::new();
+    for idx in 0..map.tiles.len() {
+        let tile = &map.tiles[idx];
+        let kind = match tile_kinds.get_by_name(&tile.name) {
+            Some(kind) => kind.clone(),
+            None => {
+                if unknown.insert(tile.name.clone()) {
+                    warn!("{} has no tile kind, treating it as plain floor", tile.name);
+                }
+                TileKind::floor(&tile.name)
+            }
+        };
+        apply_tile_kind(map, idx, &kind, ecs);
+    }
+}
+
+/// Turns the tile at `pos` into water with its water entity, for water placed after the map was made
+pub fn place_water(map: &mut Map, ecs: &mut World, pos: Position) {
+    if map.checked_xy_to_idx(pos.x, pos.y).is_some_and(|idx| map.tiles[idx].name == "Water") {
+        return;
+    }
+    place_terrain(map, ecs, pos, &TerrainTile::named("Water"));
+}
+
+/// Replaces the tile at `pos` and whatever entity the old tile's kind needed, for terrain placed after the map was
+/// made. The tile keeps its height
+pub fn place_terrain(map: &mut Map, ecs: &mut World, pos: Position, terrain: &TerrainTile) {
+    let idx = match map.checked_xy_to_idx(pos.x, pos.y) {
+        Some(idx) => idx,
+        None => {
+            warn!("{} at {} is off the map", terrain.tile, pos);
+            return;
+        }
+    };
+    let mut kind = match TILE_KIND_DB.lock().unwrap().get_by_name(&terrain.tile) {
+        Some(kind) => kind.clone(),
+        None => {
+            warn!("{} has no tile kind, treating it as plain floor", terrain.tile);
+            TileKind::floor(&terrain.tile)
+        }
+    };
+    kind.blocking = terrain.blocking.unwrap_or(kind.blocking);
+    kind.move_cost = terrain.move_cost.unwrap_or(kind.move_cost);
+
+    clear_tile_entities(ecs, pos);
+    let height = map.tiles[idx].height;
+    map.tiles[idx] = match terrain.tile.as_str() {
+        "Water" => WorldTile::water(height),
+        // drawn as liquid, their particles tell them apart from water
+        "Lava" | "Poison Water" => WorldTile { name: terrain.tile.clone(), ..WorldTile::water(height) },
+        "Mountain" => WorldTile { height, ..WorldTile::mountain() },
+        "Cave Floor" => WorldTile { height, ..WorldTile::cave_floor() },
+        other => WorldTile { name: other.to_string(), height, ..WorldTile::grass() },
+    };
+    apply_tile_kind(map, idx, &kind, ecs);
+    autotile_around(map, pos.x, pos.y);
+}
+
+/// Deletes the entities given to the tile at `pos` by its kind, world objects standing there are left alone unless
+/// they are a vein of the tile
+fn clear_tile_entities(ecs: &mut World, pos: Position) {
+    let stale: Vec<Entity> = {
+        let blocking = ecs.read_storage::<Blocking>();
+        let water = ecs.read_storage::<Water>();
+        let breakable = ecs.read_storage::<Breakable>();
+        let hazards = ecs.read_storage::<Hazard>();
+        let tags = ecs.read_storage::<Tags>();
+        (&ecs.entities(), &ecs.read_storage::<Position>(), (&ecs.read_storage::<Renderable>()).maybe())
+            .join()
+            .filter(|(e, tile_pos, renderable)| {
+                let tile_entity = renderable.is_none()
+                    && (blocking.contains(*e) || water.contains(*e) || breakable.contains(*e) || hazards.contains(*e));
+                // veins are drawn like world objects but belong to the tile they were made in
+                let vein = tags.get(*e).is_some_and(|tags| tags.has(VEIN_TAG));
+                **tile_pos == pos && (tile_entity || vein)
+            })
+            .map(|(e, ..)| e)
+            .collect()
+    };
+    for tile_entity in stale {
+        let _ = ecs.entities().delete(tile_entity);
+    }
+}
+
+fn apply_tile_kind(map: &mut Map, idx: usize, kind: &TileKind, ecs: &mut World) {
+    let tile = &mut map.tiles[idx];
+    tile.is_blocked = kind.blocking;
+    tile.move_cost = kind.move_cost;
+    if !kind.needs_entity() {
+        return;
+    }
+    let pos = Position::from_idx(idx, map.width);
+    if let Some(vein) = roll_vein(kind, ecs) {
+        match build_world_obj(&vein, pos, ecs) {
+            Ok(_) => return,
+            Err(e) => error!("{} could not be made at {}, leaving plain {}: {}", vein, pos, kind.name, e),
+        }
+    }
+
+    let mut builder = ecs.create_entity().with(pos).marked::<SimpleMarker<SerializeMe>>();
+    if kind.blocking {
+        builder = builder.with(Blocking {});
+    }
+    if kind.fishable {
+        builder = builder.with(Water {});
+    }
+    if let Some(hazard) = &kind.hazard {
+        builder = builder.with(hazard.clone());
+    }
+    if let (Some(by), Some(health)) = (&kind.breakable, &kind.health_stats) {
+        // checked when the kinds are loaded so this always parses
+        if let Ok(breakable) = by.parse::<Breakable>() {
+            builder = builder
+                .with(breakable)
+                .with(HealthStats::new(health.max_hp, health.defense))
+                .with(Name::new(&kind.name));
+        }
+    }
+    builder.build();
+}
+
+/// Picks which vein, if any, replaces a breakable tile of `kind`. Rolled with `GameRng` so seeded worlds get the same
+/// veins
+fn roll_vein(kind: &TileKind, ecs: &World) -> Option<String> {
+    let veins = kind.veins.as_ref()?;
+    let total = veins.total_weight();
+    if total == 0 {
+        return None;
+    }
+    let roll = ecs.fetch_mut::<GameRng>().0.range(0, total);
+    veins.pick(roll).map(|vein| vein.to_string())
+}
+
 fn generate_heights(map: &mut Map) {
     let noise_db = NOISE_DB.lock().unwrap();
     let noise = noise_db.get_by_name("height").unwrap();
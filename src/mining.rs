@@ -1,20 +1,38 @@
-use std::time::Duration;
+use std::{collections::HashMap, time::Duration};
 
 use crate::{
     audio::play_sound_effect,
     char_c::CH_STRIKE,
+    colors::{self, Color, HEAL_GREEN, MIDDLERED, SALMON},
     components::{
-        BreakAction, Breakable, EntityStats, HealthStats, Name, Renderable, SizeFlexor, SufferDamage, ToolType,
+        ActionPoints, Blocking, BreakAction, Breakable, Durability, EntityStats, Equipped, FadingOut, Fishable,
+        HealthStats, Item, MiningArea, Monster, Name, Position, Renderable, SizeFlexor, SufferDamage, ToolType,
     },
-    data_read::ENTITY_DB,
+    data_read::{prelude::VEIN_TAG, ENTITY_DB},
+    death::{DeathCause, DeathEvent, DeathEvents, ENVIRONMENT_CAUSE},
+    difficulty::Difficulty,
+    durability::wear_equipped_tools,
+    factions::Faction,
     game_init::PlayerEntity,
+    layers::MapLayers,
+    map::{floor_tile, MapRes},
+    map_gen::autotile_around,
+    player::Player,
+    respawn::RespawnQueue,
+    saveload::SerializeMe,
+    screen_shake::{ScreenShake, HEAVY_HIT_PERCENT, HEAVY_HIT_SHAKE, TILE_BREAK_SHAKE},
+    speed::ACTION_COST,
     tile_animation::{AnimationRequest, TileAnimationBuilder},
     ui::message_log::MessageLog,
     z_order::EFFECT_Z,
+    TurnCounter,
 };
 use bracket_lib::color::WHITE;
 use log::{debug, error, info};
-use specs::{Entities, Entity, Join, Read, ReadStorage, System, Write, WriteStorage};
+use specs::{
+    saveload::SimpleMarker, Entities, Entity, Join, Read, ReadExpect, ReadStorage, System, World, WorldExt, Write,
+    WriteExpect, WriteStorage,
+};
 
 /// Allows tile to be breakable. The tile must contain a breakable and health stats component.
 /// The attacker must contain a strength and have breakactions queued up in their system.
@@ -31,6 +49,11 @@ impl<'a> System<'a> for TileDestructionSystem {
         ReadStorage<'a, Breakable>,
         ReadStorage<'a, HealthStats>,
         ReadStorage<'a, Name>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, MiningArea>,
+        ReadStorage<'a, Equipped>,
+        WriteStorage<'a, Durability>,
+        WriteStorage<'a, Item>,
         Read<'a, PlayerEntity>,
         Entities<'a>,
     );
@@ -46,50 +69,123 @@ impl<'a> System<'a> for TileDestructionSystem {
             breakable,
             health_stats,
             names,
+            positions,
+            mining_areas,
+            equipped,
+            mut durabilities,
+            mut items,
             player_e,
             entities,
         ): Self::SystemData,
     ) {
         let edb = &ENTITY_DB.lock().unwrap();
         for (breaker, stats, action, name) in (&entities, &stats, &break_actions, &names).join() {
-            if let Some((tile_entity, tile_name, target_breakable, target_stats)) =
-                (&entities, &names, &breakable, &health_stats).join().find(|(e, ..)| *e == action.target)
-            {
+            let targets =
+                break_targets(breaker, action.target, &positions, &breakable, &mining_areas, &equipped, &entities);
+            let mut tiles_hit = 0;
+            for target in targets {
+                let (tile_name, target_breakable, target_stats) =
+                    match (names.get(target), breakable.get(target), health_stats.get(target)) {
+                        (Some(tile_name), Some(target_breakable), Some(target_stats)) => {
+                            (tile_name, target_breakable, target_stats)
+                        }
+                        _ => continue,
+                    };
+                // Only the tile that was aimed at explains why it couldn't be broken, the rest are skipped quietly
+                let is_aimed_at = target == action.target;
                 if !inventory_contains_tool(&target_breakable.by) {
-                    log.log(format!("You do not own the correct tool for this {name}."));
+                    if is_aimed_at {
+                        log.log(format!("You do not own the correct tool for this {name}."));
+                    }
                     continue;
                 }
                 if target_stats.defense > stats.set.strength {
-                    log.log("Took no damage because defense is greater");
+                    if is_aimed_at {
+                        log.log("Took no damage because defense is greater");
+                    }
                     continue;
                 }
 
                 let damage = stats.set.strength - target_stats.defense;
                 log.log(format!("{} dealt {} damage to {}", name.0, damage, tile_name.0));
-                SufferDamage::new_damage(&mut suffer_damage, action.target, -(damage as i32));
+                SufferDamage::new_damage(&mut suffer_damage, target, Some(breaker), -(damage as i32));
+                tiles_hit += 1;
 
-                if breaker == player_e.0 {
+                let size_flex =
+                    AnimationRequest::StretchShrink(target, SizeFlexor::new(&[(0.75, 1.25), (1.0, 1.0)], 25.0));
+                let flash_white = AnimationRequest::GlyphFlash(
+                    target,
+                    Duration::from_secs_f32(0.15),
+                    Renderable::clear_bg(CH_STRIKE as usize, WHITE, EFFECT_Z),
+                );
+                anim_builder.request(size_flex);
+                anim_builder.request(flash_white);
+
+                // One impact sound is enough no matter how many tiles were hit
+                if breaker == player_e.0 && tiles_hit == 1 {
                     let sound_name = match edb.world_objs.get_by_name(&tile_name.0) {
                         Some(info) => &info.impact_sound,
                         None => "",
                     };
                     play_sound_effect(sound_name);
                 }
+            }
 
-                let size_flex =
-                    AnimationRequest::StretchShrink(tile_entity, SizeFlexor::new(&[(0.75, 1.25), (1.0, 1.0)], 25.0));
-                let flash_white = AnimationRequest::GlyphFlash(
-                    tile_entity,
-                    Duration::from_secs_f32(0.15),
-                    Renderable::clear_bg(CH_STRIKE, WHITE, EFFECT_Z),
-                );
-                anim_builder.request(size_flex);
-                anim_builder.request(flash_white);
+            if tiles_hit == 0 {
+                continue;
+            }
+            for tool in wear_equipped_tools(breaker, &equipped, &mut durabilities, &mut items, &entities) {
+                if breaker == player_e.0 {
+                    if let Some(tool_name) = names.get(tool) {
+                        log.log(format!("Your {} breaks!", tool_name));
+                    }
+                }
             }
         }
 
-        break_actions.clear()
+        break_actions.clear();
+    }
+}
+
+/// Every breakable hit by `breaker` striking `target`, an equipped `MiningArea` widens the hit to the breakables around
+/// the breaker. The aimed at target always comes first.
+pub fn break_targets(
+    breaker: Entity,
+    target: Entity,
+    positions: &ReadStorage<Position>,
+    breakables: &ReadStorage<Breakable>,
+    mining_areas: &ReadStorage<MiningArea>,
+    equipped: &ReadStorage<Equipped>,
+    entities: &Entities,
+) -> Vec<Entity> {
+    let mut targets = vec![target];
+    let area = (mining_areas, equipped).join().filter(|(_, equip)| equip.on == breaker).map(|(area, _)| area.0).max();
+    let (area, breaker_pos) = match (area, positions.get(breaker)) {
+        (Some(area), Some(pos)) if area > 0 => (area as usize, pos),
+        _ => return targets,
+    };
+
+    for (entity, pos, _) in (entities, positions, breakables).join() {
+        let reach = pos.x.abs_diff(breaker_pos.x).max(pos.y.abs_diff(breaker_pos.y));
+        if entity != target && reach > 0 && reach <= area {
+            targets.push(entity);
+        }
     }
+    targets
+}
+
+/// Breaking costs a normal action for every tile the break reaches
+pub fn break_cost(ecs: &World, breaker: Entity, target: Entity) -> u32 {
+    let targets = break_targets(
+        breaker,
+        target,
+        &ecs.read_storage::<Position>(),
+        &ecs.read_storage::<Breakable>(),
+        &ecs.read_storage::<MiningArea>(),
+        &ecs.read_storage::<Equipped>(),
+        &ecs.entities(),
+    );
+    ACTION_COST * targets.len() as u32
 }
 
 // TODO: when we get the inventory added check that it contains the tool
@@ -101,30 +197,97 @@ fn inventory_contains_tool(tool_type: &ToolType) -> bool {
 }
 
 impl SufferDamage {
-    pub fn new_damage(store: &mut WriteStorage<SufferDamage>, victim: Entity, amount: i32) {
+    pub fn new_damage(store: &mut WriteStorage<SufferDamage>, victim: Entity, source: Option<Entity>, amount: i32) {
         if let Some(suffering) = store.get_mut(victim) {
-            suffering.amount.push(amount);
+            suffering.amount.push((source, amount));
         } else {
-            let dmg = SufferDamage { amount: vec![amount] };
+            let dmg = SufferDamage { amount: vec![(source, amount)] };
             store.insert(victim, dmg).expect("Unable to insert damage");
         }
     }
 }
 
+/// Sums up every hit an entity took, anything brought to zero hp is published to `DeathEvents` once. When the player
+/// goes down whoever dealt the finishing blow is remembered
 pub struct DamageSystem;
 
 impl<'a> System<'a> for DamageSystem {
-    type SystemData = (WriteStorage<'a, SufferDamage>, WriteStorage<'a, HealthStats>);
+    type SystemData = (
+        WriteStorage<'a, SufferDamage>,
+        WriteStorage<'a, HealthStats>,
+        ReadStorage<'a, Name>,
+        Write<'a, TileAnimationBuilder>,
+        Write<'a, ScreenShake>,
+        Write<'a, DeathCause>,
+        Write<'a, DeathEvents>,
+        Write<'a, MessageLog>,
+        Read<'a, PlayerEntity>,
+        ReadStorage<'a, Monster>,
+        Read<'a, Difficulty>,
+        Entities<'a>,
+    );
 
-    fn run(&mut self, (mut damage, mut breakable): Self::SystemData) {
-        for (stats, damage) in (&mut breakable, &mut damage).join() {
+    fn run(
+        &mut self,
+        (
+            mut damage,
+            mut breakable,
+            names,
+            mut anim_builder,
+            mut screen_shake,
+            mut death_cause,
+            mut death_events,
+            mut log,
+            player_entity,
+            monsters,
+            difficulty,
+            entities,
+        ): Self::SystemData,
+    ) {
+        for (victim, stats, damage) in (&entities, &mut breakable, &mut damage).join() {
             let old_hp = stats.hp;
-            let damage_dealt = damage.amount.iter().sum::<i32>();
+            if victim == player_entity.0 {
+                for (source, amount) in damage.amount.iter_mut() {
+                    if source.is_some_and(|source| monsters.contains(source)) {
+                        *amount = difficulty.monster_hit(*amount);
+                    }
+                }
+            }
+            let damage_dealt = damage.amount.iter().map(|(_, amount)| amount).sum::<i32>();
+
+            // every hit gets its own number so they can stack on top of each other
+            for (_, amount) in damage.amount.iter().filter(|(_, amt)| *amt != 0) {
+                let text = if *amount > 0 { format!("+{}", amount) } else { amount.abs().to_string() };
+                anim_builder.request(AnimationRequest::FloatingText(
+                    victim,
+                    text,
+                    damage_severity_color(*amount, stats.max_hp),
+                ));
+            }
+
+            if victim == player_entity.0
+                && damage_dealt < 0
+                && damage_dealt.unsigned_abs() as f32 / stats.max_hp.max(1) as f32 >= HEAVY_HIT_PERCENT
+            {
+                screen_shake.shake(HEAVY_HIT_SHAKE);
+            }
 
             // Addition is used because damage dealt can be positive or negative
             let new_hp = stats.hp as i32 + damage_dealt;
             stats.hp = if new_hp >= 0 { new_hp as usize } else { 0 };
 
+            // something already at zero is not reported again when it keeps getting hit
+            if old_hp > 0 && stats.hp == 0 {
+                let killer = finishing_blow(old_hp, &damage.amount);
+                death_events.single_write(DeathEvent { victim, killer });
+                if victim == player_entity.0 {
+                    let killer_name = killer.and_then(|source| names.get(source));
+                    let cause = killer_name.map_or_else(|| ENVIRONMENT_CAUSE.to_string(), |name| name.0.clone());
+                    log.log(format!("You were killed by {}.", cause));
+                    death_cause.0 = Some(cause);
+                }
+            }
+
             debug!("Old HP: {} | Damage Dealt: {} | New HP: {}", old_hp, damage_dealt, stats.hp);
         }
 
@@ -132,23 +295,245 @@ impl<'a> System<'a> for DamageSystem {
     }
 }
 
+/// Swaps breakable objects to a more cracked looking sprite as they lose health, healing back up shows them intact
+/// again. The stages come from the object's raws so objects without any are left alone
+pub struct ShowDamageStages;
+
+impl<'a> System<'a> for ShowDamageStages {
+    type SystemData =
+        (WriteStorage<'a, Renderable>, ReadStorage<'a, HealthStats>, ReadStorage<'a, Breakable>, ReadStorage<'a, Name>);
+
+    fn run(&mut self, (mut renderables, health_stats, breakables, names): Self::SystemData) {
+        let edb = &ENTITY_DB.lock().unwrap();
+        let staged: HashMap<&str, (usize, &[usize])> = edb
+            .world_objs
+            .all()
+            .filter(|obj| !obj.damage_stages.is_empty())
+            .map(|obj| (obj.name.as_str(), (obj.atlas_index, obj.damage_stages.as_slice())))
+            .collect();
+        if staged.is_empty() {
+            return;
+        }
+
+        for (render, stats, name, _) in (&mut renderables, &health_stats, &names, &breakables).join() {
+            if let Some((intact, stages)) = staged.get(name.0.as_str()) {
+                render.atlas_index = match damage_stage(stats.hp, stats.max_hp, stages.len()) {
+                    0 => *intact,
+                    stage => stages[stage - 1],
+                };
+            }
+        }
+    }
+}
+
+/// 0 is intact and `stages` is the most damaged, the health lost is split evenly between intact and each stage
+fn damage_stage(hp: usize, max_hp: usize, stages: usize) -> usize {
+    if max_hp == 0 || hp >= max_hp {
+        return 0;
+    }
+    ((max_hp - hp) * (stages + 1) / max_hp).min(stages)
+}
+
+/// Who dealt the hit that brought `hp` to zero, `None` when it came from the environment. Hits are taken in order so
+/// a heal in between can undo an earlier hit that would have been the finishing blow
+fn finishing_blow(hp: usize, hits: &[(Option<Entity>, i32)]) -> Option<Entity> {
+    let mut remaining = hp as i32;
+    let mut finisher = None;
+    for (source, amount) in hits {
+        let before = remaining;
+        remaining += amount;
+        if before > 0 && remaining <= 0 {
+            finisher = *source;
+        }
+    }
+    finisher
+}
+
+/// Picks a color for a damage number based on how much of the victim's max hp it took away
+fn damage_severity_color(amount: i32, max_hp: usize) -> Color {
+    if amount > 0 {
+        return HEAL_GREEN;
+    }
+    let percent = amount.unsigned_abs() as f32 / max_hp.max(1) as f32;
+    if percent >= 0.25 {
+        MIDDLERED
+    } else if percent >= 0.1 {
+        SALMON
+    } else {
+        colors::WHITE
+    }
+}
+
+/// Deletes anything with no health left, world objects that grow back are queued to respawn where they were.
+/// The player is left alone so the death screen can bring them back
+/// How long a dead entity takes to fade away after it dies
+pub const DEATH_FADE_TIME: Duration = Duration::from_millis(350);
+
 pub struct RemoveDeadTiles;
 
 impl<'a> System<'a> for RemoveDeadTiles {
-    type SystemData = (ReadStorage<'a, HealthStats>, ReadStorage<'a, Name>, Entities<'a>);
+    type SystemData = (
+        WriteStorage<'a, HealthStats>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Position>,
+        WriteStorage<'a, Breakable>,
+        WriteStorage<'a, Blocking>,
+        WriteStorage<'a, Fishable>,
+        WriteStorage<'a, ActionPoints>,
+        WriteStorage<'a, Faction>,
+        ReadStorage<'a, Renderable>,
+        WriteStorage<'a, FadingOut>,
+        WriteStorage<'a, SimpleMarker<SerializeMe>>,
+        Write<'a, RespawnQueue>,
+        Read<'a, Difficulty>,
+        ReadExpect<'a, TurnCounter>,
+        WriteExpect<'a, MapRes>,
+        Write<'a, ScreenShake>,
+        Read<'a, MapLayers>,
+        ReadStorage<'a, Player>,
+        Entities<'a>,
+    );
 
-    fn run(&mut self, (breakable, names, entities): Self::SystemData) {
-        for (stats, e, name) in (&breakable, &entities, &names).join() {
-            if stats.hp == 0 {
+    fn run(
+        &mut self,
+        (
+            mut health_stats,
+            names,
+            positions,
+            mut breakables,
+            mut blockings,
+            mut fishables,
+            mut action_points,
+            mut factions,
+            renderables,
+            mut fades,
+            mut markers,
+            mut respawns,
+            difficulty,
+            turn_counter,
+            mut map,
+            mut screen_shake,
+            layers,
+            players,
+            entities,
+        ): Self::SystemData,
+    ) {
+        let edb = &ENTITY_DB.lock().unwrap();
+        let dead: Vec<(Entity, Name)> = (&health_stats, &entities, &names, !&players)
+            .join()
+            .filter(|(stats, ..)| stats.hp == 0)
+            .map(|(_, e, name, _)| (e, name.clone()))
+            .collect();
+        for (e, name) in dead {
+            let respawn_turns = edb.world_objs.get_by_name(&name.0).and_then(|obj| obj.respawn_turns);
+            let pos = positions.get(e).copied();
+            let is_tile = breakables.contains(e);
+            let respawn_pos = pos.filter(|_| is_tile);
+            if renderables.contains(e) {
+                // nothing can block, break, fish or fight it while it fades, the fade finishes the deletion
+                health_stats.remove(e);
+                breakables.remove(e);
+                blockings.remove(e);
+                fishables.remove(e);
+                action_points.remove(e);
+                factions.remove(e);
+                // a save made mid fade would otherwise keep it around for good
+                markers.remove(e);
+                let _ = fades.insert(e, FadingOut::new(DEATH_FADE_TIME));
+                info!("{} is dead and fading out, items should have spawned if any.", name);
+            } else {
                 match entities.delete(e) {
                     Ok(..) => {
                         info!("{} is dead and was deleted, items should have spawned if any.", name);
                     }
                     Err(err) => {
                         error!("Failed to clean up {} : {}", e.id(), err);
+                        continue;
                     }
                 }
             }
+
+            if is_tile {
+                screen_shake.shake(TILE_BREAK_SHAKE);
+            }
+            if let (Some(turns), Some(pos)) = (respawn_turns, respawn_pos) {
+                respawns.push(&name.0, pos, turn_counter.0 + difficulty.regrow_turns(turns));
+            }
+            // Broken terrain and the veins in it leave plain floor behind
+            let is_vein = edb
+                .world_objs
+                .get_by_name(&name.0)
+                .and_then(|obj| obj.tags.as_ref())
+                .is_some_and(|tags| tags.has(VEIN_TAG));
+            if let Some(pos) = pos {
+                if map.0.checked_xy_to_idx(pos.x, pos.y).is_some_and(|idx| map.0.tiles[idx].name == name.0 || is_vein) {
+                    map.0.set_tile(&floor_tile(layers.current), pos.x, pos.y);
+                    autotile_around(&mut map.0, pos.x, pos.y);
+                }
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use specs::Builder;
+
+    use super::*;
+    use crate::{
+        dispatch::lingering_actions, stats::get_random_stats, test_harness::TestWorld, tile_animation::FadeSystem,
+    };
+
+    #[test]
+    fn damage_stages_split_the_lost_health_evenly() {
+        let stages: Vec<usize> = (0..=9).rev().map(|hp| damage_stage(hp, 9, 2)).collect();
+        assert_eq!(stages, vec![0, 0, 0, 1, 1, 1, 2, 2, 2, 2]);
+        assert_eq!(damage_stage(10, 10, 0), 0);
+        assert_eq!(damage_stage(1, 10, 0), 0);
+    }
+
+    #[test]
+    fn break_actions_are_gone_once_handled() {
+        let mut world = TestWorld::new();
+        let mole = world.spawn("Mole", Position::new(1, 2)).with(get_random_stats()).build();
+        let rock = world
+            .spawn("Rock", Position::new(2, 2))
+            .with(HealthStats::new(10, 0))
+            .with(Breakable::new(ToolType::Hand))
+            .build();
+        world.insert(mole, BreakAction { target: rock });
+
+        world.run(TileDestructionSystem);
+        assert!(world.has::<SufferDamage>(rock));
+        assert!(!world.has::<BreakAction>(mole));
+        assert!(lingering_actions(&world.ecs).is_empty());
+
+        world.ecs.write_storage::<SufferDamage>().clear();
+        world.run(TileDestructionSystem);
+        assert!(!world.has::<SufferDamage>(rock));
+    }
+
+    #[test]
+    fn dead_tiles_fade_out_before_being_deleted() {
+        let mut world = TestWorld::new().with_delta(DEATH_FADE_TIME / 2);
+        let rock = world
+            .spawn("Rock", Position::new(2, 2))
+            .with(HealthStats::new(0, 0))
+            .with(Breakable::new(ToolType::Hand))
+            .with(Blocking {})
+            .with(Renderable::clear_bg(0, WHITE, 0))
+            .build();
+
+        world.run(RemoveDeadTiles);
+        assert!(world.ecs.is_alive(rock));
+        assert!(world.has::<FadingOut>(rock));
+        assert!(!world.has::<Blocking>(rock) && !world.has::<Breakable>(rock) && !world.has::<HealthStats>(rock));
+
+        world.run(FadeSystem);
+        let alpha = world.get::<Renderable>(rock).unwrap().color_pair.fg.a;
+        assert!((alpha - 0.5).abs() < 0.01);
+
+        world.run(FadeSystem);
+        assert!(!world.ecs.is_alive(rock));
+    }
+}
@@ -18,7 +18,6 @@ pub struct RawWorldTile {
     pub height: f32,
     pub name: String,
     pub atlas_idx: Option<usize>,
-    pub is_blocked: Option<String>,
     pub weight: Option<f32>,
 }
 
@@ -31,7 +30,6 @@ impl Noise {
                 name: tile.name,
                 atlas_idx: tile.atlas_idx.unwrap(),
                 height: (value * 255.0).round() as u8,
-                is_blocked: tile.is_blocked.is_some(),
                 ..Default::default()
             };
             return world_tile;
@@ -0,0 +1,197 @@
+/* particles.rs
+ *   Ambient particles like bubbles over water, embers from a fire or pollen off the grass. Tile kinds and world
+ *   objects set how often theirs are given off and how long they last in the raws. Particles are only sprites, they
+ *   drift with a `Transform` and fade out, nothing in the game can touch them.
+ * */
+
+use std::time::Duration;
+
+use bracket_lib::{random::RandomNumberGenerator, terminal::PointF};
+use serde::{Deserialize, Serialize};
+use specs::{Entities, Join, Read, ReadStorage, System, Write, WriteStorage};
+
+use crate::{
+    components::{Particle, ParticleEmitter, Position, Renderable, Transform, Viewshed},
+    data_read::prelude::TILE_KIND_DB,
+    game_init::PlayerEntity,
+    map::MapRes,
+    time::DeltaTime,
+    z_order::EFFECT_Z,
+};
+
+/// How far in tiles a particle can start from the middle of its tile and stray from its emitter's drift
+const PARTICLE_SPREAD: f32 = 0.3;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ParticleConfig {
+    /// No particles are given off when off
+    pub enabled: bool,
+    /// Most particles around at once, emitters wait for old ones to fade before giving off more
+    pub max_particles: usize,
+}
+
+impl Default for ParticleConfig {
+    fn default() -> Self {
+        Self { enabled: true, max_particles: 120 }
+    }
+}
+
+/// The particle settings along with an rng of their own, particles are only for show so they never use up rolls
+/// from the seeded `GameRng`
+pub struct Particles {
+    cfg: ParticleConfig,
+    rng: RandomNumberGenerator,
+}
+
+impl Default for Particles {
+    fn default() -> Self {
+        Self::new(ParticleConfig::default())
+    }
+}
+
+impl Particles {
+    pub fn new(cfg: ParticleConfig) -> Self {
+        Self { cfg, rng: RandomNumberGenerator::new() }
+    }
+
+    /// A number between -`PARTICLE_SPREAD` and `PARTICLE_SPREAD`
+    fn spread(&mut self) -> f32 {
+        (self.rng.rand::<f32>() * 2.0 - 1.0) * PARTICLE_SPREAD
+    }
+}
+
+/// Gives off particles from the emitters and tiles the player can see then drifts and fades the ones already around
+pub struct ParticleSystem;
+
+impl<'a> System<'a> for ParticleSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Particle>,
+        WriteStorage<'a, Transform>,
+        WriteStorage<'a, Renderable>,
+        ReadStorage<'a, ParticleEmitter>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Viewshed>,
+        Write<'a, Particles>,
+        Read<'a, MapRes>,
+        Read<'a, DeltaTime>,
+        Read<'a, PlayerEntity>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut particles,
+            mut transforms,
+            mut renderables,
+            emitters,
+            positions,
+            viewsheds,
+            mut settings,
+            map,
+            dt,
+            player_e,
+        ): Self::SystemData,
+    ) {
+        let secs = dt.0.as_secs_f32();
+        let mut active = 0;
+        for (e, particle, transform, renderable) in
+            (&entities, &mut particles, &mut transforms, &mut renderables).join()
+        {
+            particle.time_left = particle.time_left.saturating_sub(dt.0);
+            if particle.time_left.is_zero() {
+                let _ = entities.delete(e);
+                continue;
+            }
+            transform.sprite_pos.x += particle.velocity.x * secs;
+            transform.sprite_pos.y += particle.velocity.y * secs;
+            renderable.color_pair.fg.a = particle.time_left.as_secs_f32() / particle.lifetime.as_secs_f32();
+            active += 1;
+        }
+
+        let view = match viewsheds.get(player_e.0) {
+            Some(view) if settings.cfg.enabled => view,
+            _ => return,
+        };
+        let mut sources: Vec<(Position, ParticleEmitter)> = (&emitters, &positions)
+            .join()
+            .filter(|(_, pos)| view.tiles.contains(&pos.to_point()))
+            .map(|(emitter, pos)| (*pos, *emitter))
+            .collect();
+        {
+            let tile_kinds = TILE_KIND_DB.lock().unwrap();
+            for point in view.tiles.iter() {
+                let emitter = match map.0.checked_xy_to_idx(point.x as usize, point.y as usize) {
+                    Some(idx) => tile_kinds.get_by_name(&map.0.tiles[idx].name).and_then(|kind| kind.particles),
+                    None => None,
+                };
+                if let Some(emitter) = emitter {
+                    sources.push((Position::from(*point), emitter));
+                }
+            }
+        }
+
+        for (pos, emitter) in sources {
+            if active >= settings.cfg.max_particles {
+                break;
+            }
+            if settings.rng.rand::<f32>() >= emitter.per_second * secs {
+                continue;
+            }
+            let start = PointF::new(pos.x as f32 + settings.spread(), pos.y as f32 + settings.spread());
+            let velocity = PointF::new(emitter.drift.0 + settings.spread(), emitter.drift.1 + settings.spread());
+            let lifetime = Duration::from_millis(emitter.lifetime_ms);
+            entities
+                .build_entity()
+                .with(Transform::new(start.x, start.y, 0.0, 1.0, 1.0), &mut transforms)
+                .with(Renderable::clear_bg(emitter.atlas_index, emitter.color, EFFECT_Z), &mut renderables)
+                .with(Particle { velocity, lifetime, time_left: lifetime }, &mut particles)
+                .build();
+            active += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bracket_lib::terminal::Point;
+    use specs::{Builder, WorldExt};
+
+    use super::*;
+    use crate::test_harness::TestWorld;
+
+    fn bonfire() -> ParticleEmitter {
+        ParticleEmitter {
+            atlas_index: 250,
+            color: (255, 150, 60),
+            per_second: 1000.0,
+            lifetime_ms: 100,
+            drift: (0.0, -1.0),
+        }
+    }
+
+    #[test]
+    fn particles_are_capped_and_fade_away() {
+        let mut world = TestWorld::new();
+        world.ecs.insert(Particles::new(ParticleConfig { enabled: true, max_particles: 2 }));
+        let player = world.player();
+        let mut view = Viewshed::new(8);
+        view.tiles = (0..4).map(|x| Point::new(x, 0)).collect();
+        world.insert(player, view);
+        for x in 0..4 {
+            world.ecs.create_entity().with(Position::new(x, 0)).with(bonfire()).build();
+        }
+
+        world.run(ParticleSystem);
+        assert_eq!(world.ecs.read_storage::<Particle>().join().count(), 2);
+
+        // unseen emitters give off nothing once the first particles are gone
+        world.ecs.write_storage::<Viewshed>().get_mut(player).unwrap().tiles.clear();
+        for _ in 0..10 {
+            world.run(ParticleSystem);
+        }
+        assert_eq!(world.ecs.read_storage::<Particle>().join().count(), 0);
+    }
+}
@@ -1,15 +1,36 @@
 use crate::{
+    ability::try_ground_slam,
+    activity::{Activity, CancelActivity},
     audio::play_sound_effect,
+    auto_attack::{start_auto_attack, AutoAttack},
+    being::BeingID,
+    boating::{boat_step, carries_boat, BoatStep},
+    camera::HoveredTile,
+    catch_release::{CatchChoice, CatchDecision},
+    chest::try_open_chest,
+    collision::blocks_player,
     components::{
-        AttackAction, BreakAction, FinishedActivity, FishAction, GameAction, Interactor, InteractorMode, Name,
-        PickupAction,
+        AttackAction, Boating, BreakAction, Companion, Container, CookingStation, Crop, Facing, FishAction, GameAction,
+        Interactor, InteractorMode, MoveAction, Name, Phasing, PickupAction, Viewshed, Water,
     },
+    cooking::{is_cooking_station, try_start_cooking},
+    dash::try_dash,
+    examine::start_looking,
+    farming::{harvest_crop, is_crop},
     game_init::PlayerEntity,
+    hotbar::use_hotbar_slot,
+    input_buffer::InputBuffer,
     items::inventory_contains,
+    keybinds::{movement_delta, KeyPress, Modifier},
+    layers::{try_ascend, try_descend},
     map::{MapRes, TileEntity},
+    rest::try_rest,
     saveload::{any_save_game_exists, SaveAction},
     settings::SettingsAction,
-    ui::message_log::MessageLog,
+    shop::try_open_shop,
+    targeting::start_enemy_targeting,
+    travel::{start_travel, Travel},
+    ui::{dialogue::try_start_dialogue, message_log::MessageLog},
     AppState, Position,
 };
 use bracket_lib::terminal::{BTerm, Point, VirtualKeyCode as VKC};
@@ -24,19 +45,57 @@ pub struct Player {}
 pub enum PlayerResponse {
     StateChange(AppState),
     TurnAdvance,
+    /// Turns pass one after another until the player is healed or disturbed, see `rest.rs`
+    Rest,
     Waiting,
 }
 
+/// Modifier precedence is described in `keybinds.rs`, Ctrl combos are left to `debug_input`. Clicking a tile on the
+/// map travels there, see `travel.rs`
 pub fn p_input_game(ecs: &mut World, ctx: &BTerm) -> PlayerResponse {
-    match ctx.key {
+    let press = ecs.write_resource::<InputBuffer>().take(KeyPress::read(ctx));
+    match press {
+        None if ctx.left_click => {
+            ecs.write_resource::<AutoAttack>().stop();
+            if let Some(goal) = ecs.read_resource::<HoveredTile>().0 {
+                start_travel(ecs, goal);
+            }
+            PlayerResponse::Waiting
+        }
         None => PlayerResponse::Waiting,
-        Some(key) => {
+        Some(KeyPress { modifier: Modifier::Ctrl, .. }) => PlayerResponse::Waiting,
+        Some(KeyPress { key, modifier }) => {
+            // anything the player does takes back control from auto attacking or travelling
+            ecs.write_resource::<AutoAttack>().stop();
+            ecs.write_resource::<Travel>().stop();
+            if let Some((delta_x, delta_y)) = movement_delta(key) {
+                return match modifier {
+                    Modifier::Shift => {
+                        face_direction(delta_x, delta_y, ecs);
+                        try_dash(ecs)
+                    }
+                    Modifier::Alt => {
+                        face_direction(delta_x, delta_y, ecs);
+                        PlayerResponse::Waiting
+                    }
+                    _ => try_move_player(delta_x, delta_y, ecs),
+                };
+            }
+            if modifier == Modifier::Alt {
+                return PlayerResponse::Waiting; // nothing else is bound with alt yet
+            }
+            let shift = modifier == Modifier::Shift;
             match key {
-                VKC::W | VKC::Up => try_move_player(0, -1, ecs),
-                VKC::S | VKC::Down => try_move_player(0, 1, ecs),
-                VKC::A | VKC::Left => try_move_player(-1, 0, ecs),
-                VKC::D | VKC::Right => try_move_player(1, 0, ecs),
                 VKC::P => try_pickup(ecs), // p for pickup
+                VKC::G => try_gather(ecs),
+                VKC::E => try_interact_facing(ecs),
+                VKC::F => start_enemy_targeting(ecs), // f for fire
+                VKC::R => try_dash(ecs),              // r for rush
+                VKC::Q => try_ground_slam(ecs),
+                VKC::X => match start_looking(ecs) {
+                    Some(target) => PlayerResponse::StateChange(AppState::Looking { target, focus: 0 }),
+                    None => PlayerResponse::Waiting,
+                },
                 VKC::M => {
                     switch_interaction_mode(ecs);
                     PlayerResponse::Waiting
@@ -45,19 +104,34 @@ pub fn p_input_game(ecs: &mut World, ctx: &BTerm) -> PlayerResponse {
                     play_sound_effect("ui_inventory");
                     PlayerResponse::StateChange(AppState::PlayerInInventory)
                 }
-                VKC::Escape => PlayerResponse::StateChange(AppState::SaveGame),
-                VKC::Space => {
-                    let mut log = ecs.fetch_mut::<MessageLog>();
-                    log.log("The player stands around.");
-                    PlayerResponse::TurnAdvance
-                }
+                VKC::Escape => PlayerResponse::StateChange(AppState::Paused),
+                VKC::F1 => PlayerResponse::StateChange(AppState::Help { scroll: 0 }),
+                VKC::Grave if cfg!(feature = "dev") => PlayerResponse::StateChange(AppState::DebugConsole),
+                VKC::Slash if shift => PlayerResponse::StateChange(AppState::Help { scroll: 0 }), // '?'
+                VKC::Period if shift => try_descend(ecs),                                         // '>' like the stairs
+                VKC::Comma if shift => try_ascend(ecs),                                           // '<'
+                VKC::Space | VKC::Period => try_wait(ecs),
+                VKC::Z => try_rest(ecs), // z for zzz
+                VKC::Key1 => use_hotbar(ecs, 0),
+                VKC::Key2 => use_hotbar(ecs, 1),
+                VKC::Key3 => use_hotbar(ecs, 2),
+                VKC::Key4 => use_hotbar(ecs, 3),
+                VKC::Key5 => use_hotbar(ecs, 4),
                 _ => PlayerResponse::Waiting, // Unbound keypress so just ignore it
             }
         }
     }
 }
 
-fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> PlayerResponse {
+/// Hotbar items are used like they are from the inventory so it does not cost a turn
+fn use_hotbar(ecs: &World, slot: usize) -> PlayerResponse {
+    use_hotbar_slot(ecs, slot);
+    PlayerResponse::Waiting
+}
+
+pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> PlayerResponse {
+    face_direction(delta_x, delta_y, ecs);
+
     let mut positions = ecs.write_storage::<Position>();
     let players = ecs.read_storage::<Player>();
     let interactors = ecs.read_storage::<Interactor>();
@@ -70,7 +144,32 @@ fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> PlayerRespons
             return PlayerResponse::Waiting;
         }
 
-        match map.0.first_entity_in_pos(&Position::from(target_pos)) {
+        let boating = ecs.read_storage::<Boating>().contains(player_entity);
+        let has_boat = carries_boat(player_entity, &ecs.read_storage(), &ecs.read_storage());
+        let step = boat_step(&map.0, &ecs.read_storage::<Water>(), &Position::from(target_pos), boating, has_boat);
+        if step != BoatStep::Walk {
+            match step {
+                BoatStep::Embark => {
+                    let _ = ecs.write_storage::<Boating>().insert(player_entity, Boating {});
+                    ecs.fetch_mut::<MessageLog>().log("You push off from the shore.");
+                }
+                BoatStep::GoAshore => {
+                    ecs.write_storage::<Boating>().remove(player_entity);
+                    ecs.fetch_mut::<MessageLog>().log("You pull your boat up onto the shore.");
+                }
+                _ => {}
+            }
+            pos.x = target_pos.x as usize;
+            pos.y = target_pos.y as usize;
+            mark_view_dirty(ecs, player_entity);
+            return PlayerResponse::TurnAdvance;
+        }
+
+        // Phasing beings can be set to let the player walk straight through them
+        let phasing = ecs.read_storage::<Phasing>();
+        let in_the_way =
+            |tile_entity: &TileEntity| !tile_entity.is_blocker() || blocks_player(&phasing, tile_entity.entity());
+        match map.0.first_entity_where(&Position::from(target_pos), in_the_way) {
             Some(tile) => match tile {
                 TileEntity::Breakable(entity) => {
                     info!("Map is breakable at {}, {} : id: {}", target_pos.x, target_pos.y, entity.id());
@@ -79,6 +178,34 @@ fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> PlayerRespons
                         .expect("Break action could not be added to player entity");
                     return PlayerResponse::TurnAdvance;
                 }
+                TileEntity::Blocking(blocker) if ecs.read_storage::<Companion>().contains(*blocker) => {
+                    // Companions swap places with the player so they can never box the player in, but they can't
+                    // be swapped out onto the water
+                    if boating {
+                        return PlayerResponse::Waiting;
+                    }
+                    let _ = ecs.write_storage::<MoveAction>().insert(*blocker, MoveAction::new(*pos));
+                    pos.x = target_pos.x as usize;
+                    pos.y = target_pos.y as usize;
+                    mark_view_dirty(ecs, player_entity);
+                    return PlayerResponse::TurnAdvance;
+                }
+                TileEntity::Blocking(blocker) if is_cooking_station(ecs, *blocker) => {
+                    return cook_at(ecs, player_entity, *blocker);
+                }
+                TileEntity::Blocking(blocker) if try_open_chest(ecs, *blocker) => {
+                    return PlayerResponse::StateChange(AppState::InChest);
+                }
+                TileEntity::Blocking(blocker)
+                    if matches!(interactor.mode, InteractorMode::Reactive) && try_open_shop(ecs, *blocker) =>
+                {
+                    return PlayerResponse::StateChange(AppState::InShop);
+                }
+                TileEntity::Blocking(blocker)
+                    if matches!(interactor.mode, InteractorMode::Reactive) && try_start_dialogue(ecs, *blocker) =>
+                {
+                    return PlayerResponse::StateChange(AppState::InDialogue);
+                }
                 TileEntity::Blocking(blocker) => match interactor.mode {
                     InteractorMode::Reactive => {
                         return PlayerResponse::Waiting;
@@ -88,6 +215,7 @@ fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> PlayerRespons
                         ecs.write_storage::<AttackAction>()
                             .insert(player_entity, AttackAction { target: *blocker })
                             .expect("Attack action could not be added to player entity");
+                        start_auto_attack(ecs, *blocker);
                         return PlayerResponse::TurnAdvance;
                     }
                 },
@@ -103,12 +231,14 @@ fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> PlayerRespons
                 TileEntity::Item(_) => {
                     pos.x = target_pos.x as usize;
                     pos.y = target_pos.y as usize;
+                    mark_view_dirty(ecs, player_entity);
                     return PlayerResponse::TurnAdvance;
                 }
             },
             None => {
                 pos.x = target_pos.x as usize;
                 pos.y = target_pos.y as usize;
+                mark_view_dirty(ecs, player_entity);
                 return PlayerResponse::TurnAdvance;
             }
         }
@@ -117,6 +247,141 @@ fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> PlayerRespons
     PlayerResponse::Waiting
 }
 
+/// Starts cooking whatever the player can at `station`
+fn cook_at(ecs: &World, player_entity: Entity, station: Entity) -> PlayerResponse {
+    if try_start_cooking(ecs, player_entity, station) {
+        PlayerResponse::StateChange(AppState::activity_bound())
+    } else {
+        PlayerResponse::Waiting
+    }
+}
+
+/// Passes the turn without doing anything so everything else gets to act
+fn try_wait(ecs: &mut World) -> PlayerResponse {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+
+    // Waiting is not allowed to skip through an activity the player is in the middle of
+    if ecs.read_storage::<Activity>().contains(player_entity) {
+        return PlayerResponse::Waiting;
+    }
+
+    // Any action still queued up would otherwise be performed on the waited turn
+    ecs.write_storage::<MoveAction>().remove(player_entity);
+    ecs.write_storage::<BreakAction>().remove(player_entity);
+    ecs.write_storage::<AttackAction>().remove(player_entity);
+    ecs.write_storage::<PickupAction>().remove(player_entity);
+
+    ecs.fetch_mut::<MessageLog>().log("The player stands around.");
+    PlayerResponse::TurnAdvance
+}
+
+/// Turns the player towards the direction they tried to move, even if the move doesn't happen
+fn face_direction(delta_x: i32, delta_y: i32, ecs: &mut World) {
+    let player_entity = ecs.read_resource::<PlayerEntity>();
+    if let Some(facing) = Facing::from_delta(delta_x, delta_y) {
+        let _ = ecs.write_storage::<Facing>().insert(player_entity.0, facing);
+    }
+}
+
+/// Performs the contextual action on the tile the player is facing.
+/// When multiple things share the tile the priority is: cook at a station, open a chest, trade with, talk to, or attack
+/// a being, mine a breakable, fish, then pick up an item
+fn try_interact_facing(ecs: &mut World) -> PlayerResponse {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let target_pos = {
+        let positions = ecs.read_storage::<Position>();
+        let facings = ecs.read_storage::<Facing>();
+        match (positions.get(player_entity), facings.get(player_entity)) {
+            (Some(pos), Some(facing)) => pos.to_point() + facing.delta(),
+            _ => return PlayerResponse::Waiting,
+        }
+    };
+
+    let target = {
+        let map = ecs.fetch::<MapRes>();
+        if !map.0.in_bounds(target_pos) {
+            return PlayerResponse::Waiting;
+        }
+        let beings = ecs.read_storage::<BeingID>();
+        let companions = ecs.read_storage::<Companion>();
+        let stations = ecs.read_storage::<CookingStation>();
+        let containers = ecs.read_storage::<Container>();
+        let crops = ecs.read_storage::<Crop>();
+        let idx = map.0.xy_to_idx(target_pos.x as usize, target_pos.y as usize);
+        map.0.tile_entities[idx]
+            .iter()
+            .filter(|te| match te {
+                TileEntity::Blocking(entity) => {
+                    stations.contains(*entity)
+                        || containers.contains(*entity)
+                        || crops.contains(*entity)
+                        || (beings.contains(*entity) && !companions.contains(*entity))
+                }
+                _ => true,
+            })
+            .min_by_key(|te| match te {
+                TileEntity::Blocking(_) => 0,
+                TileEntity::Breakable(_) => 1,
+                TileEntity::Fishable(_) => 2,
+                TileEntity::Item(_) => 3,
+            })
+            .cloned()
+    };
+
+    match target {
+        Some(TileEntity::Blocking(station)) if is_cooking_station(ecs, station) => cook_at(ecs, player_entity, station),
+        Some(TileEntity::Blocking(chest)) if try_open_chest(ecs, chest) => {
+            PlayerResponse::StateChange(AppState::InChest)
+        }
+        Some(TileEntity::Blocking(crop)) if is_crop(ecs, crop) => {
+            if harvest_crop(ecs, player_entity, crop) {
+                PlayerResponse::TurnAdvance
+            } else {
+                PlayerResponse::Waiting
+            }
+        }
+        Some(TileEntity::Blocking(being)) if try_open_shop(ecs, being) => PlayerResponse::StateChange(AppState::InShop),
+        Some(TileEntity::Blocking(being)) if try_start_dialogue(ecs, being) => {
+            PlayerResponse::StateChange(AppState::InDialogue)
+        }
+        Some(TileEntity::Blocking(being)) => {
+            info!("Player attacked the position, {:?}", target_pos);
+            ecs.write_storage::<AttackAction>()
+                .insert(player_entity, AttackAction { target: being })
+                .expect("Attack action could not be added to player entity");
+            PlayerResponse::TurnAdvance
+        }
+        Some(TileEntity::Breakable(entity)) => {
+            ecs.write_storage::<BreakAction>()
+                .insert(player_entity, BreakAction { target: entity })
+                .expect("Break action could not be added to player entity");
+            PlayerResponse::TurnAdvance
+        }
+        Some(TileEntity::Fishable(_)) => {
+            if !inventory_contains(&Name::new("Fishing Rod"), &player_entity, ecs) {
+                ecs.fetch_mut::<MessageLog>().log("You need a fishing rod to fish here.");
+                return PlayerResponse::Waiting;
+            }
+            ecs.write_storage::<FishAction>()
+                .insert(player_entity, FishAction { target: target_pos.into() })
+                .expect("Fish action could not be added to player entity");
+            PlayerResponse::StateChange(AppState::activity_bound())
+        }
+        Some(TileEntity::Item(item)) => {
+            let _ = ecs.write_storage::<PickupAction>().insert(player_entity, PickupAction::one(item));
+            PlayerResponse::Waiting
+        }
+        None => PlayerResponse::Waiting,
+    }
+}
+
+/// Flags the entity's view to be recalculated since it has moved
+pub fn mark_view_dirty(ecs: &World, entity: Entity) {
+    if let Some(view) = ecs.write_storage::<Viewshed>().get_mut(entity) {
+        view.dirty = true;
+    }
+}
+
 fn try_pickup(ecs: &mut World) -> PlayerResponse {
     let mut pickups = ecs.write_storage::<PickupAction>();
 
@@ -127,13 +392,42 @@ fn try_pickup(ecs: &mut World) -> PlayerResponse {
     if let Some(pos) = positions.get(player_entity.0) {
         let mut item_iter = map.0.all_items_at_pos(pos);
         if let Some(item_entity) = item_iter.next() {
-            let _ = pickups.insert(player_entity.0, PickupAction { item: *item_entity.as_item_entity().unwrap() });
+            let _ = pickups.insert(player_entity.0, PickupAction::one(*item_entity.as_item_entity().unwrap()));
         }
     }
 
     PlayerResponse::Waiting
 }
 
+/// Picks up every item on and around the player's tile in one go
+fn try_gather(ecs: &mut World) -> PlayerResponse {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let center = match ecs.read_storage::<Position>().get(player_entity) {
+        Some(pos) => pos.to_point(),
+        None => return PlayerResponse::Waiting,
+    };
+
+    let items: Vec<Entity> = {
+        let map = ecs.fetch::<MapRes>();
+        (-1..=1)
+            .flat_map(|dx| (-1..=1).map(move |dy| center + Point::new(dx, dy)))
+            .filter(|point| map.0.in_bounds(*point))
+            .flat_map(|point| {
+                map.0
+                    .all_items_at_pos(&Position::from(point))
+                    .filter_map(|tile| tile.as_item_entity().copied())
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    };
+    if items.is_empty() {
+        ecs.fetch_mut::<MessageLog>().log("There is nothing nearby to pick up.");
+        return PlayerResponse::Waiting;
+    }
+    let _ = ecs.write_storage::<PickupAction>().insert(player_entity, PickupAction { items });
+    PlayerResponse::Waiting
+}
+
 fn switch_interaction_mode(ecs: &mut World) {
     let player_entity = ecs.read_resource::<PlayerEntity>();
     let mut interactors = ecs.write_storage::<Interactor>();
@@ -152,25 +446,29 @@ fn switch_interaction_mode(ecs: &mut World) {
     };
 }
 
-/// Checks if the main player entity has a FinishedActivity component on it so we can return to
-/// InGame state. Will not work nicely if we have multiple player entities, which we shouldn't ever
-pub fn check_player_finished(ecs: &mut World) -> bool {
-    let players = ecs.read_storage::<Player>();
-    let mut finished_activities = ecs.write_storage::<FinishedActivity>();
-    (&players, &mut finished_activities).join().next().is_some()
-}
-
 pub fn p_input_activity(ecs: &mut World, ctx: &mut BTerm) {
     if ctx.key.is_none() {
         return;
     }
 
+    // a landed fish is waiting on the player, escape lets it go instead of canceling
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    if let Some(choice) = ecs.write_storage::<CatchChoice>().get_mut(player_entity) {
+        choice.decision = match ctx.key.unwrap() {
+            VKC::K | VKC::Space => Some(CatchDecision::Keep),
+            VKC::R | VKC::Escape => Some(CatchDecision::Release),
+            _ => choice.decision,
+        };
+        return;
+    }
+
     match ctx.key.unwrap() {
         VKC::Space => {
             info!("Player pressed game action");
-            let mut game_actions = ecs.write_storage::<GameAction>();
-            let player_entity = ecs.read_resource::<PlayerEntity>();
-            let _ = game_actions.insert(player_entity.0, GameAction {});
+            let _ = ecs.write_storage::<GameAction>().insert(player_entity, GameAction {});
+        }
+        VKC::Escape => {
+            let _ = ecs.write_storage::<CancelActivity>().insert(player_entity, CancelActivity {});
         }
         _ => {}
     }
@@ -242,6 +540,24 @@ pub fn p_input_save_game(ctx: &mut BTerm) -> SaveAction {
     }
 }
 
+pub enum PauseAction {
+    Resume,
+    OpenMenu,
+    Waiting,
+}
+
+pub fn p_input_paused(ctx: &mut BTerm) -> PauseAction {
+    if let Some(key) = ctx.key {
+        match key {
+            VKC::Escape => PauseAction::Resume,
+            VKC::Q => PauseAction::OpenMenu,
+            _ => PauseAction::Waiting,
+        }
+    } else {
+        PauseAction::Waiting
+    }
+}
+
 pub fn p_input_settings(ctx: &mut BTerm) -> SettingsAction {
     if let Some(key) = ctx.key {
         match key {
@@ -0,0 +1,160 @@
+/* profiling.rs
+ *   Optional timing of every system the game runs, toggled with Ctrl+T. Each system's time is added up over a
+ *   frame and averaged over the last few frames so the debug overlay can list what is taking the longest. While
+ *   turned off the only cost is checking the flag before each system runs.
+ * */
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use specs::{DispatcherBuilder, Read, System, SystemData, World, WorldExt};
+
+/// Frames each system's time is averaged over
+const TIMING_FRAMES: usize = 30;
+
+#[derive(Default)]
+struct Samples {
+    /// Time spent in each system so far this frame, systems can run more than once a frame
+    current: HashMap<&'static str, Duration>,
+    /// Totals of the last `TIMING_FRAMES` frames, the most recent is last
+    history: HashMap<&'static str, VecDeque<Duration>>,
+}
+
+/// How long each system has been taking. Systems only read this resource so timing them doesn't stop them from
+/// running in parallel, the samples are kept behind a lock instead
+#[derive(Default)]
+pub struct SystemTimings {
+    enabled: bool,
+    samples: Mutex<Samples>,
+}
+
+impl SystemTimings {
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Every sample is forgotten so stale times aren't shown when timing is turned back on
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        *self.samples.get_mut().unwrap() = Samples::default();
+    }
+
+    pub fn record(&self, system: &'static str, time: Duration) {
+        if let Ok(mut samples) = self.samples.lock() {
+            *samples.current.entry(system).or_default() += time;
+        }
+    }
+
+    /// Moves this frame's totals into the history, systems that didn't run this frame are counted as taking no time
+    pub fn end_frame(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        let samples = self.samples.get_mut().unwrap();
+        let mut current = std::mem::take(&mut samples.current);
+        for (system, frames) in samples.history.iter_mut() {
+            frames.push_back(current.remove(system).unwrap_or_default());
+            if frames.len() > TIMING_FRAMES {
+                frames.pop_front();
+            }
+        }
+        for (system, time) in current {
+            samples.history.insert(system, VecDeque::from([time]));
+        }
+    }
+
+    /// The `count` systems taking the longest on average, slowest first
+    pub fn slowest(&self, count: usize) -> Vec<(&'static str, Duration)> {
+        let samples = match self.samples.lock() {
+            Ok(samples) => samples,
+            Err(_) => return Vec::new(),
+        };
+        let mut averages: Vec<(&'static str, Duration)> = samples
+            .history
+            .iter()
+            .map(|(system, frames)| (*system, frames.iter().sum::<Duration>() / frames.len().max(1) as u32))
+            .collect();
+        averages.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        averages.truncate(count);
+        averages
+    }
+}
+
+/// Runs the system and records how long it took when timing is on
+pub struct Timed<S> {
+    name: &'static str,
+    system: S,
+}
+
+impl<'a, S> System<'a> for Timed<S>
+where
+    S: System<'a>,
+    S::SystemData: SystemData<'a>,
+{
+    type SystemData = (S::SystemData, Read<'a, SystemTimings>);
+
+    fn run(&mut self, (data, timings): Self::SystemData) {
+        if !timings.enabled() {
+            self.system.run(data);
+            return;
+        }
+        let start = Instant::now();
+        self.system.run(data);
+        timings.record(self.name, start.elapsed());
+    }
+}
+
+/// Adds systems to a dispatcher wrapped in `Timed`, the system's name in the dispatcher is used for its timings
+pub trait WithTimed<'a> {
+    fn with_timed<S>(self, system: S, name: &'static str, deps: &[&str]) -> Self
+    where
+        S: for<'c> System<'c> + Send + 'a,
+        for<'c> <S as System<'c>>::SystemData: SystemData<'c>;
+}
+
+impl<'a, 'b> WithTimed<'a> for DispatcherBuilder<'a, 'b> {
+    fn with_timed<S>(self, system: S, name: &'static str, deps: &[&str]) -> Self
+    where
+        S: for<'c> System<'c> + Send + 'a,
+        for<'c> <S as System<'c>>::SystemData: SystemData<'c>,
+    {
+        self.with(Timed { name, system }, name, deps)
+    }
+}
+
+/// Times a step of the game that isn't a system, like firing triggers
+pub fn time_step(ecs: &mut World, name: &'static str, step: impl FnOnce(&mut World)) {
+    if !ecs.read_resource::<SystemTimings>().enabled() {
+        step(ecs);
+        return;
+    }
+    let start = Instant::now();
+    step(ecs);
+    ecs.read_resource::<SystemTimings>().record(name, start.elapsed());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timings_are_averaged_over_recent_frames() {
+        let mut timings = SystemTimings::default();
+        timings.end_frame();
+        assert!(timings.slowest(5).is_empty());
+
+        timings.toggle();
+        timings.record("fov", Duration::from_millis(3));
+        timings.record("fov", Duration::from_millis(3));
+        timings.record("ai", Duration::from_millis(4));
+        timings.end_frame();
+        timings.record("ai", Duration::from_millis(2));
+        timings.end_frame();
+
+        assert_eq!(timings.slowest(5), vec![("ai", Duration::from_millis(3)), ("fov", Duration::from_millis(3))]);
+        assert_eq!(timings.slowest(1).len(), 1);
+    }
+}
@@ -0,0 +1,93 @@
+/* query.rs
+ *   Finds the closest entity to a spot that matches what the caller is after, ie. the nearest hostile to aggro on or
+ *   the nearest item for a companion to fetch. Distances are counted in tile steps like the rest of the game and ties
+ *   always go to the lower entity id so the same world gives the same answer every time.
+ * */
+
+use specs::{Entity, Join, World, WorldExt};
+
+use crate::{
+    components::Position,
+    map::{distance, Map, TileEntity},
+};
+
+/// The entity a query found, where it is and how many steps away it is
+pub type Nearest = (Entity, Position, u32);
+
+/// The closest entity with a position that `pred` accepts, no further than `max_radius` steps from `from` when
+/// given. Every positioned entity is checked, things the map index keeps track of are found faster by
+/// `nearest_indexed`
+pub fn nearest<P>(ecs: &World, from: Position, max_radius: Option<u32>, pred: P) -> Option<Nearest>
+where
+    P: Fn(Entity, &Position) -> bool,
+{
+    let entities = ecs.entities();
+    let positions = ecs.read_storage::<Position>();
+    (&entities, &positions)
+        .join()
+        .map(|(e, pos)| (e, *pos, distance(&from, pos)))
+        .filter(|(e, pos, dist)| max_radius.map_or(true, |radius| *dist <= radius) && pred(*e, pos))
+        .min_by_key(|(e, _, dist)| (*dist, e.id()))
+}
+
+/// Like `nearest` but only looks through what the map index has on each tile, ie. blockers, breakables, fishing
+/// spots and items. Tiles are searched in rings going out from `from` so nothing past the first match is looked at
+pub fn nearest_indexed<P>(map: &Map, from: Position, max_radius: Option<u32>, pred: P) -> Option<Nearest>
+where
+    P: Fn(&TileEntity) -> bool,
+{
+    let furthest = (map.width + map.height) as u32;
+    let max_radius = max_radius.map_or(furthest, |radius| radius.min(furthest));
+    (0..=max_radius).find_map(|radius| {
+        ring(from, radius)
+            .filter_map(|pos| map.checked_xy_to_idx(pos.x, pos.y).map(|idx| (pos, idx)))
+            .flat_map(|(pos, idx)| {
+                map.tile_entities[idx].iter().filter(|tile| pred(tile)).map(move |tile| (tile.entity(), pos, radius))
+            })
+            .min_by_key(|(e, ..)| e.id())
+    })
+}
+
+/// Every tile exactly `radius` steps from `center`, tiles that would be off the top or left of the map are skipped
+fn ring(center: Position, radius: u32) -> impl Iterator<Item = Position> {
+    let (cx, cy, r) = (center.x as i64, center.y as i64, radius as i64);
+    (-r..=r)
+        .flat_map(move |dx| {
+            let dy = r - dx.abs();
+            // the left and right ends of the ring are a single tile
+            let ys = if dy == 0 { vec![cy] } else { vec![cy - dy, cy + dy] };
+            ys.into_iter().map(move |y| (cx + dx, y))
+        })
+        .filter(|(x, y)| *x >= 0 && *y >= 0)
+        .map(|(x, y)| Position::new(x as usize, y as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::Builder;
+
+    use super::*;
+    use crate::{components::Blocking, indexing::IndexBlockedTiles, map::MapRes, test_harness::TestWorld};
+
+    #[test]
+    fn nearest_breaks_ties_by_entity_id_and_stays_in_range() {
+        let mut world = TestWorld::new();
+        let first = world.spawn("Rock", Position::new(5, 3)).with(Blocking {}).build();
+        let second = world.spawn("Rock", Position::new(3, 5)).with(Blocking {}).build();
+        let far = world.spawn("Rock", Position::new(12, 12)).with(Blocking {}).build();
+        let from = Position::new(4, 4);
+        let is_rock = |e: Entity, _: &Position| e != world.player();
+
+        assert_eq!(nearest(&world.ecs, from, None, is_rock), Some((first, Position::new(5, 3), 2)));
+        assert_eq!(nearest(&world.ecs, from, Some(1), is_rock), None);
+        let not_close = |e: Entity, _: &Position| e != first && e != second && e != world.player();
+        assert_eq!(nearest(&world.ecs, from, None, not_close).map(|(e, ..)| e), Some(far));
+
+        world.run(IndexBlockedTiles);
+        let map = &world.ecs.fetch::<MapRes>().0;
+        let blocking = |tile: &TileEntity| matches!(tile, TileEntity::Blocking(_));
+        assert_eq!(nearest_indexed(map, from, None, blocking), Some((first, Position::new(5, 3), 2)));
+        assert_eq!(nearest_indexed(map, from, Some(1), blocking), None);
+        assert_eq!(nearest_indexed(map, Position::new(13, 13), Some(2), blocking).map(|(e, ..)| e), Some(far));
+    }
+}
@@ -0,0 +1,80 @@
+use log::{debug, error};
+use specs::{World, WorldExt};
+
+use crate::{components::Position, data_read::prelude::build_world_obj, map::MapRes, TurnCounter};
+
+/// A harvested world object waiting to grow back where it was broken
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingRespawn {
+    pub name: String,
+    pub pos: Position,
+    /// The turn the object is allowed to come back on
+    pub turn: usize,
+}
+
+/// World objects that were broken and will come back after their `respawn_turns` defined in the raws.
+/// The queue is not saved, a loaded game starts with nothing waiting to respawn
+#[derive(Default)]
+pub struct RespawnQueue {
+    pending: Vec<PendingRespawn>,
+}
+
+impl RespawnQueue {
+    pub fn push(&mut self, name: impl ToString, pos: Position, turn: usize) {
+        self.pending.push(PendingRespawn { name: name.to_string(), pos, turn });
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Removes and returns the respawns that are due by `turn`. Ones whose spot is not clear stay queued until it is
+    fn take_due(&mut self, turn: usize, is_clear: impl Fn(&Position) -> bool) -> Vec<PendingRespawn> {
+        let (due, waiting): (Vec<_>, Vec<_>) =
+            self.pending.drain(..).partition(|pending| pending.turn <= turn && is_clear(&pending.pos));
+        self.pending = waiting;
+        due
+    }
+}
+
+/// Rebuilds the harvested world objects whose time has come, anything standing on the spot delays it
+pub fn respawn_harvested_tiles(ecs: &mut World) {
+    let due = {
+        let turn = ecs.read_resource::<TurnCounter>().0;
+        let map = ecs.read_resource::<MapRes>();
+        let mut queue = ecs.write_resource::<RespawnQueue>();
+        queue.take_due(turn, |pos| !map.0.is_blocked(pos))
+    };
+
+    for respawn in due {
+        match build_world_obj(&respawn.name, respawn.pos, ecs) {
+            Ok(_) => debug!("{} respawned at {}", respawn.name, respawn.pos),
+            Err(e) => error!("Harvested tile failed to respawn: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn respawn_waits_for_its_turn_and_a_clear_tile() {
+        let mut queue = RespawnQueue::default();
+        queue.push("Boulder", Position::new(1, 1), 10);
+        queue.push("Sapling", Position::new(2, 2), 10);
+
+        assert!(queue.take_due(9, |_| true).is_empty());
+
+        let occupied = Position::new(2, 2);
+        let due = queue.take_due(10, |pos| *pos != occupied);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].name, "Boulder");
+
+        // the sapling comes back once whatever was standing there leaves
+        let due = queue.take_due(11, |_| true);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].name, "Sapling");
+        assert!(queue.take_due(100, |_| true).is_empty());
+    }
+}
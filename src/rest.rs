@@ -0,0 +1,173 @@
+/* rest.rs
+ *   Resting passes turn after turn in one go until the player is back to full health. Each turn rested heals the
+ *   player a little while everything else carries on around them, and they get up the moment something hostile
+ *   comes close enough to notice them.
+ * */
+
+use serde::{Deserialize, Serialize};
+use specs::{World, WorldExt};
+
+use crate::{
+    activity::Activity,
+    components::{
+        AttackAction, BreakAction, HealAction, HealthStats, Interactor, InteractorMode, Monster, MoveAction, Name,
+        PickupAction, Position,
+    },
+    factions::{are_hostile, Faction},
+    game_init::PlayerEntity,
+    map::distance,
+    player::{Player, PlayerResponse},
+    query::nearest,
+    ui::message_log::MessageLog,
+};
+
+/// How fast resting heals and how long the player can rest in one go, read from the settings when the game starts
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct RestConfig {
+    /// Health regained for each turn rested
+    pub hp_per_turn: usize,
+    /// Most turns passed by one rest, the player gets up after this even when they are not fully healed
+    pub max_turns: u32,
+}
+
+impl Default for RestConfig {
+    fn default() -> Self {
+        Self { hp_per_turn: 1, max_turns: 200 }
+    }
+}
+
+#[derive(Default)]
+pub struct Resting {
+    cfg: RestConfig,
+}
+
+impl Resting {
+    pub fn new(cfg: RestConfig) -> Self {
+        Self { cfg }
+    }
+}
+
+/// The name of the closest hostile being that would notice the player from where it is
+pub fn threat_nearby(ecs: &World) -> Option<String> {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let monsters = ecs.read_storage::<Monster>();
+    let interactors = ecs.read_storage::<Interactor>();
+    let factions = ecs.read_storage::<Faction>();
+    let players = ecs.read_storage::<Player>();
+    let names = ecs.read_storage::<Name>();
+
+    let player_pos = *ecs.read_storage::<Position>().get(player_entity)?;
+    let player_faction = Faction::of(player_entity, &factions, &players, &monsters)?;
+    let (threat, ..) = nearest(ecs, player_pos, None, |e, pos| {
+        let aggressive = interactors.get(e).is_some_and(|i| matches!(i.mode, InteractorMode::Agressive));
+        aggressive
+            && monsters.get(e).is_some_and(|monster| distance(pos, &player_pos) <= monster.aggro_range)
+            && Faction::of(e, &factions, &players, &monsters).is_some_and(|f| are_hostile(f, player_faction))
+    })?;
+    Some(names.get(threat).map_or("something".to_string(), |name| name.0.clone()))
+}
+
+/// Lies the player down to rest when they are hurt and nothing hostile is around
+pub fn try_rest(ecs: &mut World) -> PlayerResponse {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    if ecs.read_storage::<Activity>().contains(player_entity) {
+        return PlayerResponse::Waiting;
+    }
+    let hurt = ecs.read_storage::<HealthStats>().get(player_entity).is_some_and(|stats| stats.hp < stats.max_hp);
+    if !hurt {
+        ecs.fetch_mut::<MessageLog>().log("You are already fully rested.");
+        return PlayerResponse::Waiting;
+    }
+    if let Some(threat) = threat_nearby(ecs) {
+        ecs.fetch_mut::<MessageLog>().log(format!("You can't rest with the {} nearby.", threat));
+        return PlayerResponse::Waiting;
+    }
+
+    // Any action still queued up would otherwise be performed on the first rested turn
+    ecs.write_storage::<MoveAction>().remove(player_entity);
+    ecs.write_storage::<BreakAction>().remove(player_entity);
+    ecs.write_storage::<AttackAction>().remove(player_entity);
+    ecs.write_storage::<PickupAction>().remove(player_entity);
+
+    ecs.fetch_mut::<MessageLog>().log("You sit down to rest.");
+    PlayerResponse::Rest
+}
+
+/// Heals the player for the turn about to be rested
+pub fn rest_turn(ecs: &World) {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let amount = ecs.read_resource::<Resting>().cfg.hp_per_turn;
+    let mut heal_actions = ecs.write_storage::<HealAction>();
+    let amount = heal_actions.get(player_entity).map_or(0, |heal| heal.amount) + amount;
+    let _ = heal_actions.insert(player_entity, HealAction { amount });
+}
+
+/// Why the player gets up after resting `turns` turns, `None` when they keep resting
+pub fn rest_stop(ecs: &World, turns: u32) -> Option<String> {
+    if let Some(threat) = threat_nearby(ecs) {
+        return Some(format!("You stop resting, the {} is nearby!", threat));
+    }
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let healed = ecs.read_storage::<HealthStats>().get(player_entity).map_or(true, |stats| stats.hp >= stats.max_hp);
+    if healed {
+        return Some(format!("You rest for {} turns and feel fully healed.", turns));
+    }
+    if turns >= ecs.read_resource::<Resting>().cfg.max_turns {
+        return Some(format!("You get up after resting for {} turns.", turns));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::Builder;
+
+    use super::*;
+    use crate::{combat::HealActionHandler, data_read::prelude::FACTION_DB, test_harness::TestWorld};
+
+    fn hurt_player() -> (TestWorld, specs::Entity) {
+        let mut world = TestWorld::new().with_map(12, 12);
+        let player = world.player();
+        world.insert(player, Position::new(1, 1));
+        world.insert(player, HealthStats { hp: 5, max_hp: 8, defense: 0 });
+        (world, player)
+    }
+
+    #[test]
+    fn resting_heals_until_full() {
+        let (mut world, player) = hurt_player();
+        assert!(matches!(try_rest(&mut world.ecs), PlayerResponse::Rest));
+
+        let mut turns = 0;
+        let stop = loop {
+            rest_turn(&world.ecs);
+            world.run(HealActionHandler);
+            turns += 1;
+            if let Some(stop) = rest_stop(&world.ecs, turns) {
+                break stop;
+            }
+        };
+        assert_eq!(turns, 3);
+        assert!(stop.contains("fully healed"));
+        assert_eq!(world.get::<HealthStats>(player).map(|stats| stats.hp), Some(8));
+        assert!(matches!(try_rest(&mut world.ecs), PlayerResponse::Waiting));
+    }
+
+    #[test]
+    fn hostiles_in_aggro_range_stop_a_rest() {
+        FACTION_DB.lock().unwrap().load();
+        let (mut world, _) = hurt_player();
+        let orc = world
+            .spawn("Orc", Position::new(10, 10))
+            .with(Monster::new(3, 6))
+            .with(Interactor::new(InteractorMode::Agressive))
+            .build();
+        assert!(matches!(try_rest(&mut world.ecs), PlayerResponse::Rest));
+        assert_eq!(rest_stop(&world.ecs, 1), None);
+
+        world.insert(orc, Position::new(3, 2));
+        assert_eq!(rest_stop(&world.ecs, 2), Some("You stop resting, the Orc is nearby!".to_string()));
+        assert!(matches!(try_rest(&mut world.ecs), PlayerResponse::Waiting));
+    }
+}
@@ -0,0 +1,75 @@
+use bracket_lib::random::RandomNumberGenerator;
+use log::{info, warn};
+
+/// Checked for a seed when `--seed` is not passed on the command line
+pub const SEED_ENV_VAR: &str = "RPG_SEED";
+
+/// Shared rng for systems whose rolls should be repeatable when the world is seeded, such as in tests
+pub struct GameRng(pub RandomNumberGenerator);
+
+impl GameRng {
+    pub fn seeded(seed: u64) -> Self {
+        Self(RandomNumberGenerator::seeded(seed))
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self(RandomNumberGenerator::new())
+    }
+}
+
+/// The seed the game starts with, taken from `--seed <n>` then `RPG_SEED` and rolled when neither is given.
+/// It is printed so a player can report it along with a bug.
+pub fn startup_seed() -> u64 {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let given = seed_arg(&args)
+        .map(|arg| (arg.to_string(), "--seed"))
+        .or_else(|| std::env::var(SEED_ENV_VAR).ok().map(|var| (var, SEED_ENV_VAR)));
+    let seed = match given {
+        Some((value, from)) => match value.trim().parse::<u64>() {
+            Ok(seed) => {
+                info!("Seed {} was given by {}", seed, from);
+                seed
+            }
+            Err(_) => {
+                warn!("{} was given the seed '{}' which is not a number, rolling one instead", from, value);
+                RandomNumberGenerator::new().rand::<u64>()
+            }
+        },
+        None => RandomNumberGenerator::new().rand::<u64>(),
+    };
+    info!("Starting with seed {}", seed);
+    seed
+}
+
+/// The value passed to `--seed` as either `--seed <n>` or `--seed=<n>`
+fn seed_arg(args: &[String]) -> Option<&str> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--seed" {
+            return args.next().map(|value| value.as_str());
+        }
+        if let Some(value) = arg.strip_prefix("--seed=") {
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn seed_is_read_from_either_form_of_the_flag() {
+        assert_eq!(seed_arg(&args("--seed 42")), Some("42"));
+        assert_eq!(seed_arg(&args("--fullscreen --seed=7")), Some("7"));
+        assert_eq!(seed_arg(&args("--seed")), None);
+        assert_eq!(seed_arg(&args("")), None);
+    }
+}
@@ -1,4 +1,5 @@
 use std::convert::Infallible;
+use std::fmt::Display;
 use std::fs::{self, create_dir, File};
 use std::path::Path;
 
@@ -13,18 +14,37 @@ use specs::{
 };
 use specs::{Builder, Component, ConvertSaveload, Join, NullStorage, VecStorage, World, WorldExt};
 
-use crate::being::BeingID;
+use crate::autosave::AutoSave;
+use crate::being::{BeingID, DEFAULT_FOLLOW_DISTANCE};
+use crate::camera::Camera;
 use crate::components::{
-    AttackBonus, Blocking, Breakable, Consumable, DeleteCondition, EntityStats, Equipable, EquipmentSlots, Equipped,
-    Fishable, GoalMoverAI, Grass, HealthStats, InBag, Interactor, Item, LevelPersistent, Name, Position,
-    RandomWalkerAI, Renderable, Viewshed, Water,
+    ActionPoints, AttackBonus, AttackCooldown, BaitActive, Blocking, Boat, Boating, Breakable, Buoyancy, Companion,
+    Consumable, Container, CookingStation, Crop, Currency, DeleteCondition, Dialogue, Durability, EntityStats,
+    Equipable, EquipmentSlots, Equipped, Facing, Fishable, Flying, GoalMoverAI, Grass, Hazard, HealthStats, Hotbar,
+    IdleAnimation, InBag, Interactor, InteractorMode, Item, KillCount, LayerStash, LevelPersistent, LightSource,
+    MiningArea, Monster, Name, ParticleEmitter, Phasing, Position, Quips, RandomWalkerAI, Renderable, Shop, Size,
+    StatModifiers, Tags, TargetedUse, Viewshed, Water, WeaponCooldown, WeaponReach,
 };
+use crate::currents::set_currents;
 use crate::data_read::ENTITY_DB;
+use crate::difficulty::{Difficulty, SavedDifficulty};
+use crate::factions::Faction;
 use crate::game_init::PlayerEntity;
+use crate::hunger::{Hunger, Metabolism};
+use crate::item_despawn::DespawnTimer;
+use crate::layers::MapLayers;
+use crate::lighting::{LightMap, PLAYER_LIGHT};
 use crate::map::{Map, MapRes};
+use crate::map_gen::autotile_map;
 use crate::player::Player;
+use crate::respawn::RespawnQueue;
 use crate::saveload_menu::LoadedWorld;
+use crate::special_attacks::SpecialAttack;
+use crate::status::StatusEffects;
+use crate::toasts::Toasts;
+use crate::triggers::{Trigger, TriggerWatch};
 use crate::ui::message_log::MessageLog;
+use crate::weather::Weather;
 
 // ripped right from https://bfnightly.bracketproductions.com/chapter_11.html
 macro_rules! serialize_individually {
@@ -60,12 +80,21 @@ macro_rules! deserialize_individually {
 #[storage(NullStorage)]
 pub struct SerializeMe {}
 
+// Components that point at other entities and how each survives a save:
+//   Saved, the `Entity` is written as the target's marker and mapped back on load by `ConvertSaveload`:
+//     `InBag.owner`, `Equipped.on`, `DeleteCondition::ActivityFinish`
+//   Not saved, they only last a turn or are picked again by their systems after loading:
+//     every `*Action` (`BreakAction.target`, `AttackAction.target`, `PickupAction.items`, ...), `SufferDamage`,
+//     `Monster.target`, `GoalMoverAI.current`, `SelectedInventoryItem`, `LitFuse.thrower`
+// A component added to the save lists with an `Entity` in it has to derive `ConvertSaveload` and be listed here.
+
 /// Useful when trying to save things that aren't a normal component
 #[derive(Component, Clone, ConvertSaveload)]
 #[storage(VecStorage)]
 pub struct SerializationHelper {
     map: Map,
     message_log: MessageLog,
+    layers: MapLayers,
 }
 
 pub enum SaveAction {
@@ -79,11 +108,54 @@ pub enum SaveAction {
 pub const SAVE_PATH: &str = "./saves/";
 pub const SAVE_EXTENSION: &str = "edo";
 
+/// The format saves are written in. Components are saved one storage after another in the order of the lists in
+/// `save_game` and `load_game`, so adding a component to the end of the lists needs a new version whose migration
+/// gives older saves an empty storage for it.
+pub const SAVE_VERSION: u32 = 8;
+
+/// Written before the components, saves from before it existed are version 1
+#[derive(Serialize, Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SaveError {
+    Unreadable(String),
+    /// The save was made by a newer build of the game, it is refused rather than read wrong
+    TooNew {
+        version: u32,
+    },
+    Unsupported {
+        version: u32,
+    },
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::Unreadable(reason) => write!(f, "The save could not be read, {}", reason),
+            SaveError::TooNew { version } => write!(
+                f,
+                "The save is from a newer version of the game (format {}), this version reads up to format {}",
+                version, SAVE_VERSION
+            ),
+            SaveError::Unsupported { version } => write!(f, "Save format {} can no longer be loaded", version),
+        }
+    }
+}
+
 pub fn cleanup_game(ecs: &mut World) {
     info!("Cleaning up game world.");
     ecs.delete_all();
     let mut message_log = ecs.write_resource::<MessageLog>();
     message_log.clear();
+    ecs.write_resource::<RespawnQueue>().clear();
+    ecs.write_resource::<AutoSave>().reset();
+    ecs.write_resource::<Weather>().reset();
+    ecs.write_resource::<LightMap>().reset();
+    ecs.write_resource::<Toasts>().clear();
+    *ecs.write_resource::<MapLayers>() = MapLayers::default();
     let mut lw = ecs.write_resource::<LoadedWorld>();
     if let Some(file_name) = lw.file_name.as_ref() {
         info!("{}, Loaded World is now being deloaded.", file_name);
@@ -103,16 +175,24 @@ pub fn any_save_game_exists() -> bool {
 }
 
 pub fn save_game(ecs: &mut World) {
+    let lw = ecs.get_mut::<LoadedWorld>().unwrap();
+    let file_name = lw.file_name.clone().unwrap_or(format!("default.{SAVE_EXTENSION}"));
+    save_game_as(ecs, &file_name);
+}
+
+/// Writes the world to `file_name` in the saves folder without changing which save the world is loaded from
+pub fn save_game_as(ecs: &mut World, file_name: &str) {
     let MapRes(map) = ecs.get_mut::<MapRes>().unwrap().clone();
     let message_log = ecs.get_mut::<MessageLog>().unwrap().clone();
+    let layers = ecs.get_mut::<MapLayers>().unwrap().clone();
+    let difficulty = ecs.read_resource::<Difficulty>().level;
     let savehelper = ecs
         .create_entity()
-        .with(SerializationHelper { map, message_log })
+        .with(SerializationHelper { map, message_log, layers })
+        .with(SavedDifficulty(difficulty))
         .marked::<SimpleMarker<SerializeMe>>()
         .build();
 
-    let lw = ecs.get_mut::<LoadedWorld>().unwrap();
-    let file_name = lw.file_name.clone().unwrap_or(format!("default.{SAVE_EXTENSION}"));
     let full_file_path = format!("{}{}", SAVE_PATH, file_name);
 
     let writer = match File::create(&full_file_path) {
@@ -124,6 +204,7 @@ pub fn save_game(ecs: &mut World) {
                 Ok(w) => w,
                 Err(e) => {
                     error!("Could not save file successfully, {}", e);
+                    ecs.delete_entity(savehelper).expect("Crash in cleanup, save helper could not be deleted.");
                     return;
                 }
             }
@@ -133,45 +214,29 @@ pub fn save_game(ecs: &mut World) {
         let data = (ecs.entities(), ecs.read_storage::<SimpleMarker<SerializeMe>>());
 
         let mut serializer = serde_json::Serializer::new(writer);
+        SaveHeader { version: SAVE_VERSION }.serialize(&mut serializer).unwrap();
         #[rustfmt::skip]
         serialize_individually!(ecs, serializer, data, Position, Renderable, LevelPersistent, EntityStats, Blocking, Fishable,
                                 Name, HealthStats, Breakable, DeleteCondition, Item, InBag, Consumable, Equipped, Equipable,
                                 BeingID, Viewshed,
-                                Player, EquipmentSlots, Water, Grass, Interactor, AttackBonus, SerializationHelper);
+                                Player, EquipmentSlots, Water, Grass, Interactor, Facing, AttackBonus, MiningArea, BaitActive, StatusEffects, Currency, ActionPoints, CookingStation,
+                                AttackCooldown, WeaponCooldown, WeaponReach, Durability, Boat, Boating, TargetedUse, Crop, Hotbar, LayerStash, KillCount, SerializationHelper,
+                                Hunger, SavedDifficulty, Trigger, Hazard, DespawnTimer, Buoyancy);
     }
     info!("{} was saved", file_name);
 
     ecs.delete_entity(savehelper).expect("Crash in cleanup, hopefully we still saved.");
 }
-pub fn load_game(ecs: &mut World, file_name: String) {
-    // make sure everything is wiped out
-    cleanup_game(ecs);
 
-    // TODO: keep track of file_name for saving
+/// Loads the save, the current world is only cleaned up once the save is known to be readable
+pub fn load_game(ecs: &mut World, file_name: String) -> Result<(), SaveError> {
     let save_game_path = format!("{}{}", SAVE_PATH, file_name);
-    let save_data = match fs::read_to_string(&save_game_path) {
-        Ok(data) => data,
-        Err(e) => {
-            error!("Save game file cannot be loaded from `{}`", &save_game_path);
-            error!("Load game error: {}", e);
-            return;
-        }
-    };
+    let save_data = fs::read_to_string(&save_game_path).map_err(|e| SaveError::Unreadable(e.to_string()))?;
+    let components = read_save(&save_data)?;
 
-    let mut deserializer = serde_json::Deserializer::from_str(&save_data);
-
-    {
-        let mut d = (
-            &mut ecs.entities(),
-            &mut ecs.write_storage::<SimpleMarker<SerializeMe>>(),
-            &mut ecs.write_resource::<SimpleMarkerAllocator<SerializeMe>>(),
-        );
-        #[rustfmt::skip]
-        deserialize_individually!(ecs, deserializer, d, Position, Renderable, LevelPersistent, EntityStats, Blocking, Fishable,
-                                Name, HealthStats, Breakable, DeleteCondition, Item, InBag, Consumable, Equipped, Equipable,
-                                BeingID, Viewshed,
-                                Player, EquipmentSlots, Water, Grass, Interactor, AttackBonus, SerializationHelper);
-    }
+    // make sure everything is wiped out
+    cleanup_game(ecs);
+    deserialize_components(ecs, &components);
 
     // This is going to be replaced when it gets loaded below but it cannot be inserted in there
     // since some borrowing is going on
@@ -188,9 +253,16 @@ pub fn load_game(ecs: &mut World, file_name: String) {
             let mut map = ecs.write_resource::<MapRes>();
             *map = MapRes(helper_data.map.clone());
             map.0.tile_entities = vec![Vec::new(); map.0.width * map.0.height];
+            // The tables may have changed since the save was made
+            autotile_map(&mut map.0);
+            ecs.write_resource::<Camera>().snap();
 
             let mut msg_log = ecs.write_resource::<MessageLog>();
-            *msg_log = helper_data.message_log.clone();
+            msg_log.restore(&helper_data.message_log);
+            *ecs.write_resource::<MapLayers>() = helper_data.layers.clone();
+            // saves from before the difficulty was kept are played on normal
+            ecs.write_resource::<Difficulty>().level =
+                ecs.read_storage::<SavedDifficulty>().get(helper_e).map(|saved| saved.0).unwrap_or_default();
             debug!("Message and map loaded Successful");
 
             delete_me = Some(helper_e);
@@ -198,12 +270,64 @@ pub fn load_game(ecs: &mut World, file_name: String) {
             error!("No map found when loading the savegame.");
         }
 
-        // Recreate AI components for beings
+        // Recreate AI, special attack, size, quip, dialogue, shop, idle animation, movement, tag, faction and missing
+        // action point components for beings
         let beings = ecs.write_storage::<BeingID>();
         let edb = &ENTITY_DB.lock().unwrap();
         for (being_e, being_id) in (&entities, &beings).join() {
-            match edb.beings.get_by_id(being_id.0) {
+            match edb.beings.get_by_id(*being_id) {
                 Some(being_info) => {
+                    if let Some(lines) = &being_info.quips {
+                        let mut quips = ecs.write_storage::<Quips>();
+                        let _ = quips.insert(being_e, Quips::new(lines));
+                    }
+                    if let Some(lines) = being_info.dialogue_lines() {
+                        let mut dialogues = ecs.write_storage::<Dialogue>();
+                        let _ = dialogues.insert(being_e, Dialogue { lines: lines.clone() });
+                    }
+                    if let Some(stock) = &being_info.shop {
+                        let mut shops = ecs.write_storage::<Shop>();
+                        let _ = shops.insert(being_e, Shop { stock: stock.clone() });
+                    }
+                    if let Some(idle) = &being_info.idle {
+                        let mut idle_anims = ecs.write_storage::<IdleAnimation>();
+                        let _ = idle_anims.insert(being_e, idle.to_component());
+                    }
+                    if let Some(flying) = being_info.movement.flying() {
+                        let _ = ecs.write_storage::<Flying>().insert(being_e, flying);
+                    }
+                    if let Some(phasing) = being_info.movement.phasing() {
+                        let _ = ecs.write_storage::<Phasing>().insert(being_e, phasing);
+                    }
+                    if let Some(tags) = &being_info.tags {
+                        let _ = ecs.write_storage::<Tags>().insert(being_e, tags.clone());
+                    }
+                    if let Some(faction) = being_info.faction {
+                        let _ = ecs.write_storage::<Faction>().insert(being_e, faction);
+                    }
+                    if let Some(special) = &being_info.special {
+                        let _ =
+                            ecs.write_storage::<SpecialAttack>().insert(being_e, SpecialAttack::new(special.clone()));
+                    }
+                    if being_info.size != Size::Medium {
+                        let _ = ecs.write_storage::<Size>().insert(being_e, being_info.size);
+                    }
+                    // Saves from before beings had speed are missing their action points
+                    let mut action_points = ecs.write_storage::<ActionPoints>();
+                    match action_points.get_mut(being_e) {
+                        // and ones from before initiative have none
+                        Some(ap) if ap.initiative == 0 => ap.initiative = being_info.initiative,
+                        Some(_) => {}
+                        None => {
+                            let ap = ActionPoints::new(being_info.speed).with_initiative(being_info.initiative);
+                            let _ = action_points.insert(being_e, ap);
+                        }
+                    }
+                    // and ones from before attack cooldowns are missing those
+                    let mut cooldowns = ecs.write_storage::<AttackCooldown>();
+                    if !cooldowns.contains(being_e) {
+                        let _ = cooldowns.insert(being_e, AttackCooldown::new(being_info.attack_cooldown));
+                    }
                     if let Some(ai) = &being_info.ai {
                         match ai.start_mode.as_str() {
                             "random_walk" => {
@@ -224,6 +348,21 @@ pub fn load_game(ecs: &mut World, file_name: String) {
                                 let _ = goal_movers
                                     .insert(being_e, GoalMoverAI::with_desires(&goals, ai.goal_range.unwrap()));
                             }
+                            "companion" => {
+                                let follow_distance = ai.follow_distance.unwrap_or(DEFAULT_FOLLOW_DISTANCE);
+                                let mut companions = ecs.write_storage::<Companion>();
+                                let _ = companions.insert(being_e, Companion { follow_distance });
+                            }
+                            "hostile" => {
+                                let mut random_walk = ecs.write_storage::<RandomWalkerAI>();
+                                let _ = random_walk.insert(being_e, RandomWalkerAI {});
+                                let mut monsters = ecs.write_storage::<Monster>();
+                                let _ = monsters.insert(being_e, ai.monster());
+                                let mut interactors = ecs.write_storage::<Interactor>();
+                                if !interactors.contains(being_e) {
+                                    let _ = interactors.insert(being_e, Interactor::new(InteractorMode::Agressive));
+                                }
+                            }
                             _ => (),
                         }
                     }
@@ -232,16 +371,206 @@ pub fn load_game(ecs: &mut World, file_name: String) {
             }
         }
 
+        // Tags, lights, particles and containers come from the raws so items and world objects get theirs back too, as
+        // do the stat modifiers of equipment
+        let items = ecs.read_storage::<Item>();
+        let names = ecs.read_storage::<Name>();
+        let mut tags = ecs.write_storage::<Tags>();
+        let mut lights = ecs.write_storage::<LightSource>();
+        let mut emitters = ecs.write_storage::<ParticleEmitter>();
+        let mut containers = ecs.write_storage::<Container>();
+        let mut modifiers = ecs.write_storage::<StatModifiers>();
+        for (item_e, item) in (&entities, &items).join() {
+            let info = match edb.items.get_by_id(item.id) {
+                Some(info) => info,
+                None => continue,
+            };
+            if let Some(item_tags) = info.tags.clone() {
+                let _ = tags.insert(item_e, item_tags);
+            }
+            if let Some(modifier) = info.stat_modifiers {
+                let _ = modifiers.insert(item_e, modifier);
+            }
+        }
+        for (obj_e, name, _, _) in (&entities, &names, !&beings, !&items).join() {
+            let obj = match edb.world_objs.get_by_name(&name.0) {
+                Some(obj) => obj,
+                None => continue,
+            };
+            if let Some(obj_tags) = obj.tags.clone() {
+                let _ = tags.insert(obj_e, obj_tags);
+            }
+            if let Some(light) = obj.light {
+                let _ = lights.insert(obj_e, light);
+            }
+            if let Some(particles) = obj.particles {
+                let _ = emitters.insert(obj_e, particles);
+            }
+            if let Some(slots) = obj.container {
+                let _ = containers.insert(obj_e, Container { slots });
+            }
+        }
+
         if let Some((player_e, _)) = (&entities, &player).join().next() {
             let mut player_e_res = ecs.write_resource::<PlayerEntity>();
             *player_e_res = PlayerEntity(player_e);
+            // Saves from before the hotbar, kill count and hunger existed
+            let mut hotbars = ecs.write_storage::<Hotbar>();
+            if !hotbars.contains(player_e) {
+                let _ = hotbars.insert(player_e, Hotbar::default());
+            }
+            let mut kill_counts = ecs.write_storage::<KillCount>();
+            if !kill_counts.contains(player_e) {
+                let _ = kill_counts.insert(player_e, KillCount::default());
+            }
+            let mut hungers = ecs.write_storage::<Hunger>();
+            if !hungers.contains(player_e) {
+                let _ = hungers.insert(player_e, ecs.read_resource::<Metabolism>().full());
+            }
+            let _ = lights.insert(player_e, PLAYER_LIGHT);
+            // triggers wait for the player to step onto them, not for where the save left them standing
+            let player_pos = ecs.read_storage::<Position>().get(player_e).copied();
+            ecs.write_resource::<TriggerWatch>().reset(player_pos);
             debug!("Player res set Successful");
         } else {
             error!("No player found when loading the savegame. Resulting to temp player variable.");
         }
     }
 
+    // currents are worked out from the map rather than saved
+    set_currents(&ecs.fetch::<MapRes>().0, ecs);
     ecs.insert(LoadedWorld { file_name: Some(file_name) });
     debug!("Loading game complete");
     ecs.delete_entity(delete_me.unwrap()).expect("Unable to delete helper after loading.");
+    Ok(())
+}
+
+/// Reads a save into components of the current `SAVE_VERSION`, migrating it when it is older
+fn read_save(save_data: &str) -> Result<String, SaveError> {
+    let (version, components) = split_header(save_data)?;
+    if version < SAVE_VERSION {
+        info!("Migrating a save from format {} to {}", version, SAVE_VERSION);
+    }
+    migrate_save(version, components)
+}
+
+/// Splits the save into its version and the components after the header
+fn split_header(save_data: &str) -> Result<(u32, &str), SaveError> {
+    let mut values = serde_json::Deserializer::from_str(save_data).into_iter::<serde_json::Value>();
+    match values.next() {
+        Some(Ok(value)) if value.is_object() => {
+            let header: SaveHeader =
+                serde_json::from_value(value).map_err(|e| SaveError::Unreadable(format!("bad header, {}", e)))?;
+            Ok((header.version, &save_data[values.byte_offset()..]))
+        }
+        // version 1 saves start right away with the first component's storage
+        Some(Ok(_)) => Ok((1, save_data)),
+        Some(Err(e)) => Err(SaveError::Unreadable(e.to_string())),
+        None => Err(SaveError::Unreadable("the file is empty".to_string())),
+    }
+}
+
+/// Brings the components of a save made in `version` up to `SAVE_VERSION`, one version at a time
+fn migrate_save(version: u32, components: &str) -> Result<String, SaveError> {
+    if version > SAVE_VERSION {
+        return Err(SaveError::TooNew { version });
+    }
+    let mut components = components.to_string();
+    for from in version..SAVE_VERSION {
+        components = match from {
+            // version 2 added the header, version 1 viewsheds also didn't save if they needed recalculating
+            1 => mark_views_dirty(&components)?,
+            // version 3 added hunger at the end, nobody in an older save is hungry yet
+            2 => components + "[]",
+            // version 4 saved the difficulty, older saves are played on normal
+            3 => components + "[]",
+            // version 5 added triggers, older worlds have none
+            4 => components + "[]",
+            // version 6 added hazards, older maps have none
+            5 => components + "[]",
+            // version 7 added despawn timers, items on the ground start theirs on the next turn
+            6 => components + "[]",
+            // version 8 added buoyancy, items from older saves are left to neither float nor sink
+            7 => components + "[]",
+            _ => return Err(SaveError::Unsupported { version }),
+        };
+    }
+    Ok(components)
+}
+
+/// Where the `Viewshed` storage is in the save, it hasn't moved since version 1
+const VIEWSHED_STORAGE: usize = 16;
+
+/// Every viewshed in a version 1 save starts dirty so the view is worked out again once it is loaded
+fn mark_views_dirty(components: &str) -> Result<String, SaveError> {
+    let mut storages = serde_json::Deserializer::from_str(components)
+        .into_iter::<serde_json::Value>()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| SaveError::Unreadable(e.to_string()))?;
+    if let Some(serde_json::Value::Array(views)) = storages.get_mut(VIEWSHED_STORAGE) {
+        for view in views.iter_mut().filter_map(|entry| entry.pointer_mut("/components/0")) {
+            if let Some(view) = view.as_object_mut() {
+                view.entry("dirty").or_insert(serde_json::Value::Bool(true));
+            }
+        }
+    }
+    Ok(storages.iter().map(|storage| storage.to_string()).collect())
+}
+
+fn deserialize_components(ecs: &mut World, components: &str) {
+    let mut deserializer = serde_json::Deserializer::from_str(components);
+    let mut d = (
+        &mut ecs.entities(),
+        &mut ecs.write_storage::<SimpleMarker<SerializeMe>>(),
+        &mut ecs.write_resource::<SimpleMarkerAllocator<SerializeMe>>(),
+    );
+    #[rustfmt::skip]
+    deserialize_individually!(ecs, deserializer, d, Position, Renderable, LevelPersistent, EntityStats, Blocking, Fishable,
+                            Name, HealthStats, Breakable, DeleteCondition, Item, InBag, Consumable, Equipped, Equipable,
+                            BeingID, Viewshed,
+                            Player, EquipmentSlots, Water, Grass, Interactor, Facing, AttackBonus, MiningArea, BaitActive, StatusEffects, Currency, ActionPoints, CookingStation,
+                            AttackCooldown, WeaponCooldown, WeaponReach, Durability, Boat, Boating, TargetedUse, Crop, Hotbar, LayerStash, KillCount, SerializationHelper,
+                            Hunger, SavedDifficulty, Trigger, Hazard, DespawnTimer, Buoyancy);
+}
+
+#[cfg(test)]
+mod tests {
+    use bracket_lib::terminal::Point;
+
+    use crate::test_harness::TestWorld;
+
+    use super::*;
+
+    #[test]
+    fn version_one_saves_load_without_a_header() {
+        let save_data = fs::read_to_string("tests/fixtures/save_v1.edo").unwrap();
+        assert_eq!(split_header(&save_data).unwrap().0, 1);
+
+        let components = read_save(&save_data).unwrap();
+        let mut world = TestWorld::new();
+        deserialize_components(&mut world.ecs, &components);
+
+        let players = world.ecs.read_storage::<Player>();
+        let (player_e, _) = (&world.ecs.entities(), &players).join().next().expect("the save has a player");
+        assert_eq!(world.ecs.read_storage::<Position>().get(player_e), Some(&Position::new(3, 4)));
+        assert_eq!(world.ecs.read_storage::<Name>().get(player_e).map(|name| name.0.as_str()), Some("Player"));
+        let views = world.ecs.read_storage::<Viewshed>();
+        let view = views.get(player_e).expect("the player kept their viewshed");
+        assert_eq!(view.range, 8);
+        assert!(view.tiles.contains(&Point::new(4, 4)));
+        assert!(view.dirty);
+    }
+
+    #[test]
+    fn header_gives_the_version() {
+        let (version, components) = split_header(r#"{"version":2}[][]"#).unwrap();
+        assert_eq!(version, 2);
+        assert_eq!(components, "[][]");
+    }
+
+    #[test]
+    fn saves_from_newer_versions_are_refused() {
+        assert_eq!(read_save(r#"{"version":99}[]"#), Err(SaveError::TooNew { version: 99 }));
+        assert!(matches!(read_save(""), Err(SaveError::Unreadable(_))));
+    }
 }
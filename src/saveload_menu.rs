@@ -13,6 +13,8 @@ pub fn get_save_games() -> Vec<String> {
 #[derive(Default)]
 pub struct GameSaves {
     pub saves: Vec<String>,
+    /// Why the last save picked could not be loaded
+    pub load_error: Option<String>,
 }
 
 #[derive(Default)]
@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use bracket_lib::{random::RandomNumberGenerator, terminal::BTerm};
+use specs::{World, WorldExt};
+
+use crate::{time::DeltaTime, CL_EFFECTS, CL_EFFECTS2, CL_INTERACTABLES, CL_WORLD};
+
+/// Layers drawn in world space, CL_TEXT is left out so the ui stays still
+const SHAKEN_LAYERS: [usize; 4] = [CL_WORLD, CL_INTERACTABLES, CL_EFFECTS, CL_EFFECTS2];
+
+/// Shake from the player taking a hit of at least `HEAVY_HIT_PERCENT` of their max hp
+pub const HEAVY_HIT_SHAKE: (f32, Duration) = (0.4, Duration::from_millis(300));
+pub const HEAVY_HIT_PERCENT: f32 = 0.25;
+/// Shake from a tile being destroyed
+pub const TILE_BREAK_SHAKE: (f32, Duration) = (0.2, Duration::from_millis(150));
+
+/// Jolts the world layers by a random offset that shrinks until the shake runs out
+#[derive(Default)]
+pub struct ScreenShake {
+    /// Furthest the layers can be pushed in tiles when the shake starts
+    strength: f32,
+    duration: Duration,
+    remaining: Duration,
+}
+
+impl ScreenShake {
+    /// Starts a shake, a weaker shake does not cut short a stronger one that is still going
+    pub fn shake(&mut self, (strength, duration): (f32, Duration)) {
+        if strength < self.current_strength() {
+            return;
+        }
+        self.strength = strength;
+        self.duration = duration;
+        self.remaining = duration;
+    }
+
+    fn current_strength(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 0.0;
+        }
+        self.strength * (self.remaining.as_secs_f32() / self.duration.as_secs_f32())
+    }
+
+    /// Moves the shake forward by `dt` and picks the offset for this frame, (0, 0) once it has run out
+    fn advance(&mut self, dt: Duration, rng: &mut RandomNumberGenerator) -> (f32, f32) {
+        self.remaining = self.remaining.saturating_sub(dt);
+        let strength = self.current_strength();
+        if strength <= 0.0 {
+            *self = Self::default();
+            return (0.0, 0.0);
+        }
+        let mut jolt = || (rng.rand::<f32>() * 2.0 - 1.0) * strength;
+        (jolt(), jolt())
+    }
+}
+
+/// Offsets the world layers for the current frame of the shake
+pub fn update_screen_shake(ecs: &World, ctx: &mut BTerm) {
+    let dt = ecs.read_resource::<DeltaTime>().0;
+    let mut rng = RandomNumberGenerator::new();
+    let (x, y) = ecs.write_resource::<ScreenShake>().advance(dt, &mut rng);
+
+    let active = ctx.active_console;
+    for layer in SHAKEN_LAYERS {
+        ctx.set_active_console(layer);
+        ctx.set_offset(x, y);
+    }
+    ctx.set_active_console(active);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shake_settles_back_to_zero() {
+        let mut rng = RandomNumberGenerator::seeded(7);
+        let mut shake = ScreenShake::default();
+        shake.shake(HEAVY_HIT_SHAKE);
+
+        let (x, y) = shake.advance(Duration::from_millis(16), &mut rng);
+        assert!(x.abs() <= HEAVY_HIT_SHAKE.0 && y.abs() <= HEAVY_HIT_SHAKE.0);
+
+        assert_eq!(shake.advance(HEAVY_HIT_SHAKE.1, &mut rng), (0.0, 0.0));
+        assert_eq!(shake.advance(Duration::from_millis(16), &mut rng), (0.0, 0.0));
+    }
+
+    #[test]
+    fn weaker_shake_does_not_interrupt() {
+        let mut shake = ScreenShake::default();
+        shake.shake(HEAVY_HIT_SHAKE);
+        shake.shake(TILE_BREAK_SHAKE);
+        assert_eq!(shake.strength, HEAVY_HIT_SHAKE.0);
+    }
+}
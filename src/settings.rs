@@ -1,5 +1,12 @@
-use crate::{CL_INTERACTABLES, FONT_INTERACTABLES, FONT_INTERACTABLES_OUTLINE};
-use bracket_lib::terminal::BTerm;
+use crate::{
+    ability::AbilityConfig, auto_attack::AutoAttackConfig, camera::CameraConfig, catch_release::CatchReleaseConfig,
+    config::DisplayConfig, currents::CurrentConfig, dash::DashConfig, difficulty::DifficultyConfig,
+    fish_schools::FishSchoolConfig, fov::DEFAULT_VIEW_RANGE, hunger::HungerConfig, idle_bob::IdleBobConfig,
+    item_despawn::ItemDespawnConfig, lighting::LightingConfig, particles::ParticleConfig, rest::RestConfig,
+    theme::ThemeConfig, tilesets::TerrainSkinConfig, travel::TravelConfig, ui::message_log::MessageLogConfig,
+    weather::WeatherConfig, CL_INTERACTABLES, FONT_INTERACTABLES, FONT_INTERACTABLES_OUTLINE,
+};
+use bracket_lib::terminal::{BTerm, RGB};
 use std::fs;
 
 use log::{debug, error, warn};
@@ -11,10 +18,209 @@ const GENERAL_CONFIG_PATH: &str = "./config.json";
 // everything else unchanged
 
 /// The config for how the in game settings menu are configured
-#[derive(Serialize, Deserialize, Default)]
+#[derive(Serialize, Deserialize)]
 pub struct SettingsConfig {
     pub sprite_mode: SpriteMode,
     pub text_font: TextFonts,
+    #[serde(default = "default_fps_cap")]
+    pub fps_cap: f32,
+    /// The longest a single frame's delta time can be, this stops a laggy frame from making timers jump ahead
+    #[serde(default = "default_max_delta_ms")]
+    pub max_delta_ms: u64,
+    /// Averages the delta time over the last few frames for smoother animations on jittery systems
+    #[serde(default)]
+    pub delta_smoothing: bool,
+    /// How many tiles away the player can see, takes effect when a world is started or loaded
+    #[serde(default = "default_view_range")]
+    pub view_range: usize,
+    /// Draws moving sprites on a plain console snapped to whole tiles for low-end machines or anyone bothered by the
+    /// motion. Slides, hops, the idle bob, spins and scaling are all lost, everything jumps straight to its tile
+    /// instead. Only changes how things are drawn and takes effect the next time the game is started
+    #[serde(default)]
+    pub flat_sprites: bool,
+    /// Trims the hud down to the player's health and the last couple of messages and hides the debug info, prompts
+    /// like the interact hint still show. Can be flipped in game with F8
+    #[serde(default)]
+    pub minimal_hud: bool,
+    /// Retro crt look with scanlines and screen burn, can be flipped in game with F10
+    #[serde(default)]
+    pub crt_effects: bool,
+    /// Color that bleeds around bright tiles while the crt effects are on
+    #[serde(default = "default_burn_color")]
+    pub burn_color: (u8, u8, u8),
+    /// 0.0 to 1.0, bracket-lib darkens the scanlines by a fixed amount so this scales how much burn shows between them
+    #[serde(default = "default_scanline_intensity")]
+    pub scanline_intensity: f32,
+    /// How many tiles away the nearest breakable or fishable tile is pointed out, 0 turns the hint off
+    #[serde(default = "default_interact_hint_radius")]
+    pub interact_hint_radius: usize,
+    /// How long a movement key pressed while the player is busy is remembered for in ms, 0 turns it off
+    #[serde(default = "default_input_buffer_ms")]
+    pub input_buffer_ms: u64,
+    /// Turns between auto-saves, 0 turns them off though leaving the game still auto-saves
+    #[serde(default = "default_autosave_turns")]
+    pub autosave_turns: usize,
+    /// How many auto-saves are kept for each world before the oldest is written over
+    #[serde(default = "default_autosave_slots")]
+    pub autosave_slots: usize,
+    /// Shows an item's pickup text only the first time it is picked up each session instead of every time
+    #[serde(default = "default_pickup_text_once")]
+    pub pickup_text_once: bool,
+    /// How many messages the log keeps and whether they show the turn they were logged on
+    #[serde(default)]
+    pub message_log: MessageLogConfig,
+    /// How often the weather changes and how much it affects fishing and views
+    #[serde(default)]
+    pub weather: WeatherConfig,
+    /// How big schools of fish are and how often they gather
+    #[serde(default)]
+    pub fish_schools: FishSchoolConfig,
+    /// How long there is to keep or release a landed fish and what letting one go is worth
+    #[serde(default)]
+    pub catch_release: CatchReleaseConfig,
+    /// How often water currents push boats along and how steep the water must drop to flow
+    #[serde(default)]
+    pub currents: CurrentConfig,
+    /// How far the player dashes and how long they need to catch their breath after
+    #[serde(default)]
+    pub dash: DashConfig,
+    /// How hard the ground slam hits and how many turns it needs before it can be used again
+    #[serde(default)]
+    pub ability: AbilityConfig,
+    /// How quickly resting heals and how long one rest can last
+    #[serde(default)]
+    pub rest: RestConfig,
+    /// The breathing bob of the player's sprite while they stand still
+    #[serde(default)]
+    pub idle_bob: IdleBobConfig,
+    /// How quickly the camera glides after the player
+    #[serde(default)]
+    pub camera: CameraConfig,
+    /// Whether bumping into a monster keeps attacking it every turn until one of you is dead
+    #[serde(default)]
+    pub auto_attack: AutoAttackConfig,
+    /// Whether clicking a tile walks the player there and how quickly
+    #[serde(default)]
+    pub travel: TravelConfig,
+    /// How quickly the player gets hungry and how much starving hurts
+    #[serde(default)]
+    pub hunger: HungerConfig,
+    /// How long dropped items and loot lie on the ground before they vanish
+    #[serde(default)]
+    pub item_despawn: ItemDespawnConfig,
+    /// How much each difficulty scales monster damage, monster spawns and resource regrowth
+    #[serde(default)]
+    pub difficulty: DifficultyConfig,
+    /// How many ambient particles can be around at once
+    #[serde(default)]
+    pub particles: ParticleConfig,
+    /// How dark the surface and caves are away from lights
+    #[serde(default)]
+    pub lighting: LightingConfig,
+    /// Alternate looks for the terrain, see `tilesets.rs` for what a skin's fonts must be
+    #[serde(default)]
+    pub terrain_skins: Vec<TerrainSkinConfig>,
+    /// The skin the terrain is drawn with, none uses the usual tilesets. Switched in game with F9
+    #[serde(default)]
+    pub terrain_skin: Option<String>,
+    /// Window scale and fullscreen, changes take effect the next time the game is started
+    #[serde(default)]
+    pub display: DisplayConfig,
+    /// Overrides for the ui colors, read when the game starts
+    #[serde(default)]
+    pub theme: ThemeConfig,
+    /// Tiles between coordinate labels on the debug grid overlay, 0 draws the grid without labels
+    #[serde(default = "default_grid_label_spacing")]
+    pub grid_label_spacing: usize,
+}
+
+impl Default for SettingsConfig {
+    fn default() -> Self {
+        Self {
+            sprite_mode: SpriteMode::default(),
+            text_font: TextFonts::default(),
+            fps_cap: default_fps_cap(),
+            max_delta_ms: default_max_delta_ms(),
+            delta_smoothing: false,
+            flat_sprites: false,
+            minimal_hud: false,
+            view_range: default_view_range(),
+            crt_effects: false,
+            burn_color: default_burn_color(),
+            scanline_intensity: default_scanline_intensity(),
+            interact_hint_radius: default_interact_hint_radius(),
+            input_buffer_ms: default_input_buffer_ms(),
+            autosave_turns: default_autosave_turns(),
+            autosave_slots: default_autosave_slots(),
+            pickup_text_once: default_pickup_text_once(),
+            message_log: MessageLogConfig::default(),
+            weather: WeatherConfig::default(),
+            fish_schools: FishSchoolConfig::default(),
+            catch_release: CatchReleaseConfig::default(),
+            currents: CurrentConfig::default(),
+            dash: DashConfig::default(),
+            ability: AbilityConfig::default(),
+            rest: RestConfig::default(),
+            idle_bob: IdleBobConfig::default(),
+            camera: CameraConfig::default(),
+            auto_attack: AutoAttackConfig::default(),
+            travel: TravelConfig::default(),
+            hunger: HungerConfig::default(),
+            item_despawn: ItemDespawnConfig::default(),
+            difficulty: DifficultyConfig::default(),
+            particles: ParticleConfig::default(),
+            lighting: LightingConfig::default(),
+            terrain_skins: Vec::new(),
+            terrain_skin: None,
+            display: DisplayConfig::default(),
+            theme: ThemeConfig::default(),
+            grid_label_spacing: default_grid_label_spacing(),
+        }
+    }
+}
+
+fn default_fps_cap() -> f32 {
+    60.0
+}
+
+fn default_max_delta_ms() -> u64 {
+    100
+}
+
+fn default_view_range() -> usize {
+    DEFAULT_VIEW_RANGE
+}
+
+fn default_interact_hint_radius() -> usize {
+    3
+}
+
+fn default_input_buffer_ms() -> u64 {
+    150
+}
+
+fn default_autosave_turns() -> usize {
+    200
+}
+
+fn default_autosave_slots() -> usize {
+    3
+}
+
+fn default_pickup_text_once() -> bool {
+    true
+}
+
+fn default_grid_label_spacing() -> usize {
+    5
+}
+
+fn default_burn_color() -> (u8, u8, u8) {
+    (255, 240, 200)
+}
+
+fn default_scanline_intensity() -> f32 {
+    0.5
 }
 
 impl SettingsConfig {
@@ -99,3 +305,26 @@ fn toggle_sprite_mode(cfg: &mut SettingsConfig, ctx: &mut BTerm) {
     debug!("Active id is {}", active_id);
     ctx.set_active_font(active_id, false);
 }
+
+pub fn toggle_minimal_hud(cfg: &mut SettingsConfig) {
+    cfg.minimal_hud = !cfg.minimal_hud;
+    cfg.save();
+    debug!("Minimal hud is now {}", if cfg.minimal_hud { "on" } else { "off" });
+}
+
+/// Sets bracket-lib's post processing to match the config, the burn is skipped when the intensity is 0
+pub fn apply_post_processing(cfg: &SettingsConfig, ctx: &mut BTerm) {
+    let intensity = cfg.scanline_intensity.clamp(0.0, 1.0);
+    let (r, g, b) = cfg.burn_color;
+    ctx.post_scanlines = cfg.crt_effects;
+    ctx.post_screenburn = cfg.crt_effects && intensity > 0.0;
+    ctx.screen_burn_color = RGB::from_u8(r, g, b) * intensity;
+}
+
+/// Flips the crt effects and saves it so the choice sticks for the next time the game is opened
+pub fn toggle_post_processing(cfg: &mut SettingsConfig, ctx: &mut BTerm) {
+    cfg.crt_effects = !cfg.crt_effects;
+    apply_post_processing(cfg, ctx);
+    cfg.save();
+    debug!("Crt effects are now {}", if cfg.crt_effects { "on" } else { "off" });
+}
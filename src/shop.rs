@@ -0,0 +1,223 @@
+use bracket_lib::terminal::{BTerm, VirtualKeyCode as VKC};
+use log::error;
+use specs::{Entity, Join, ReadStorage, World, WorldExt};
+
+use crate::{
+    components::{Container, Currency, Dialogue, Equipped, InBag, Item, Name, Shop},
+    data_read::ENTITY_DB,
+    game_init::PlayerEntity,
+    items::{ItemID, ItemQty, ItemSpawner, SpawnType},
+    ui::{dialogue::is_hostile, message_log::MessageLog},
+};
+
+/// Distinct stacks a backpack can hold, matches the 1-F selection keys of the inventory
+pub const BACKPACK_SLOTS: usize = 15;
+
+#[derive(PartialEq, Clone, Copy, Default)]
+pub enum ShopMode {
+    #[default]
+    Buy,
+    Sell,
+}
+
+/// Resource holding the shop the player currently has open
+#[derive(Default)]
+pub struct ActiveShop {
+    pub keeper_name: String,
+    pub stock: Vec<ItemID>,
+    pub mode: ShopMode,
+    pub hovering: usize,
+}
+
+pub enum ShopAction {
+    Up,
+    Down,
+    SwitchMode,
+    Confirm,
+    Close,
+    Waiting,
+}
+
+/// Opens the being's shop if it is a shopkeeper that isn't hostile
+pub fn try_open_shop(ecs: &World, being: Entity) -> bool {
+    if is_hostile(ecs, being) {
+        return false;
+    }
+    let shops = ecs.read_storage::<Shop>();
+    let shop = match shops.get(being) {
+        Some(shop) => shop,
+        None => return false,
+    };
+    let keeper = ecs.read_storage::<Name>().get(being).map_or_else(Name::missing_being_name, |name| name.clone());
+    if let Some(greeting) = ecs.read_storage::<Dialogue>().get(being).and_then(|dialogue| dialogue.lines.first()) {
+        ecs.fetch_mut::<MessageLog>().speak(&keeper, greeting);
+    }
+
+    let mut active = ecs.write_resource::<ActiveShop>();
+    *active = ActiveShop { keeper_name: keeper.0, stock: shop.stock.clone(), mode: ShopMode::Buy, hovering: 0 };
+    true
+}
+
+pub fn p_input_shop(ctx: &BTerm) -> ShopAction {
+    match ctx.key {
+        Some(VKC::W | VKC::Up) => ShopAction::Up,
+        Some(VKC::S | VKC::Down) => ShopAction::Down,
+        Some(VKC::Tab) => ShopAction::SwitchMode,
+        Some(VKC::Return | VKC::E) => ShopAction::Confirm,
+        Some(VKC::Escape) => ShopAction::Close,
+        _ => ShopAction::Waiting,
+    }
+}
+
+/// Performs the action in the open shop, returns false once the shop is closed
+pub fn handle_shop_action(ecs: &mut World, action: ShopAction) -> bool {
+    let (mode, hovering, stock) = {
+        let active = ecs.read_resource::<ActiveShop>();
+        (active.mode, active.hovering, active.stock.clone())
+    };
+    let listed = match mode {
+        ShopMode::Buy => stock.len(),
+        ShopMode::Sell => sellable_items(ecs).len(),
+    };
+
+    match action {
+        ShopAction::Up => {
+            ecs.write_resource::<ActiveShop>().hovering =
+                if hovering == 0 { listed.saturating_sub(1) } else { hovering - 1 };
+        }
+        ShopAction::Down => {
+            ecs.write_resource::<ActiveShop>().hovering = if hovering + 1 >= listed { 0 } else { hovering + 1 };
+        }
+        ShopAction::SwitchMode => {
+            let mut active = ecs.write_resource::<ActiveShop>();
+            active.mode = match mode {
+                ShopMode::Buy => ShopMode::Sell,
+                ShopMode::Sell => ShopMode::Buy,
+            };
+            active.hovering = 0;
+        }
+        ShopAction::Confirm => match mode {
+            ShopMode::Buy => {
+                if let Some(id) = stock.get(hovering) {
+                    buy(ecs, *id);
+                }
+            }
+            ShopMode::Sell => {
+                if let Some((item_entity, ..)) = sellable_items(ecs).get(hovering) {
+                    sell(ecs, *item_entity);
+                }
+                // Selling the last of a stack shortens the list
+                let remaining = sellable_items(ecs).len();
+                let mut active = ecs.write_resource::<ActiveShop>();
+                active.hovering = active.hovering.min(remaining.saturating_sub(1));
+            }
+        },
+        ShopAction::Close => return false,
+        ShopAction::Waiting => {}
+    }
+    true
+}
+
+/// Items in the player's backpack that can be sold, equipped items have to be taken off first
+pub fn sellable_items(ecs: &World) -> Vec<(Entity, ItemID, ItemQty)> {
+    let player_entity = ecs.read_resource::<PlayerEntity>();
+    let items = ecs.read_storage::<Item>();
+    let in_bags = ecs.read_storage::<InBag>();
+    let equipped = ecs.read_storage::<Equipped>();
+    let entities = ecs.entities();
+
+    let mut sellable: Vec<(Entity, ItemID, ItemQty)> = (&entities, &items, &in_bags, !&equipped)
+        .join()
+        .filter(|(_, item, bag, _)| bag.owner == player_entity.0 && item.qty.0 > 0)
+        .map(|(entity, item, ..)| (entity, item.id, item.qty))
+        .collect();
+    sellable.sort_by_key(|(_, id, _)| *id);
+    sellable
+}
+
+/// Stacks the owner carries and how many slots they have for them
+pub fn backpack_usage(ecs: &World, owner: Entity) -> (usize, usize) {
+    let slots = ecs.read_storage::<Container>().get(owner).map_or(BACKPACK_SLOTS, |container| container.slots);
+    let items = ecs.read_storage::<Item>();
+    let in_bags = ecs.read_storage::<InBag>();
+    let used = (&items, &in_bags).join().filter(|(_, bag)| bag.owner == owner).count();
+    (used, slots)
+}
+
+/// Checks if the item can go into the owner's backpack, either onto an existing stack or into a free slot.
+/// Containers like chests have their own number of slots
+pub fn backpack_has_room(ecs: &World, owner: Entity, id: ItemID) -> bool {
+    bag_has_room(owner, id, &ecs.read_storage(), &ecs.read_storage(), &ecs.read_storage())
+}
+
+/// `backpack_has_room` for systems that already hold the storages
+pub fn bag_has_room(
+    owner: Entity,
+    id: ItemID,
+    containers: &ReadStorage<Container>,
+    items: &ReadStorage<Item>,
+    in_bags: &ReadStorage<InBag>,
+) -> bool {
+    let slots = containers.get(owner).map_or(BACKPACK_SLOTS, |container| container.slots);
+    let mut stacks = 0;
+    for (item, _) in (items, in_bags).join().filter(|(_, bag)| bag.owner == owner) {
+        if item.id == id {
+            return true;
+        }
+        stacks += 1;
+    }
+    stacks < slots
+}
+
+/// Every check is done before the gold is taken so a failed purchase costs nothing
+fn buy(ecs: &mut World, id: ItemID) {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let (name, price) = match ENTITY_DB.lock().unwrap().items.try_get_by_id(id) {
+        Ok(info) => (info.name.clone(), info.buy_price()),
+        Err(e) => {
+            error!("Shop has stock that can't be bought: {}", e);
+            return;
+        }
+    };
+
+    let gold = ecs.read_storage::<Currency>().get(player_entity).map_or(0, |purse| purse.gold);
+    if gold < price {
+        ecs.fetch_mut::<MessageLog>().log(format!("You can't afford the {}, it costs {} gold.", name, price));
+        return;
+    }
+    if !backpack_has_room(ecs, player_entity, id) {
+        ecs.fetch_mut::<MessageLog>().log(format!("Your backpack is too full to carry the {}.", name));
+        return;
+    }
+
+    let _ = ecs.write_storage::<Currency>().insert(player_entity, Currency { gold: gold - price });
+    ecs.write_resource::<ItemSpawner>().request(id, SpawnType::InBag(player_entity));
+    ecs.fetch_mut::<MessageLog>().log(format!("You bought a {} for {} gold.", name, price));
+}
+
+fn sell(ecs: &mut World, item_entity: Entity) {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let id = match ecs.read_storage::<Item>().get(item_entity) {
+        Some(item) => item.id,
+        None => return,
+    };
+    let (name, price) = match ENTITY_DB.lock().unwrap().items.try_get_by_id(id) {
+        Ok(info) => (info.name.clone(), info.sell_price()),
+        Err(e) => {
+            error!("Tried to sell an item that doesn't exist: {}", e);
+            return;
+        }
+    };
+    if price == 0 {
+        ecs.fetch_mut::<MessageLog>().log(format!("The {} is worthless to the shopkeeper.", name));
+        return;
+    }
+
+    // The zero quantity cleanup deletes the item once the last one is sold
+    if let Some(item) = ecs.write_storage::<Item>().get_mut(item_entity) {
+        item.qty = item.qty - ItemQty(1);
+    }
+    let gold = ecs.read_storage::<Currency>().get(player_entity).map_or(0, |purse| purse.gold);
+    let _ = ecs.write_storage::<Currency>().insert(player_entity, Currency { gold: gold + price });
+    ecs.fetch_mut::<MessageLog>().log(format!("You sold a {} for {} gold.", name, price));
+}
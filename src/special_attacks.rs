@@ -0,0 +1,305 @@
+/* special_attacks.rs
+ *   Monsters with a `special` in their raws have a charged attack on top of their normal one. When their target is
+ *   in range they wind up for a few turns with the tiles the attack will hit highlighted, then it goes off on those
+ *   tiles. The tiles are locked in when the wind up starts so anyone who steps off them in time takes no damage.
+ * */
+
+use std::time::Duration;
+
+use bracket_lib::{
+    color::ColorPair,
+    geometry::{line2d, LineAlg},
+    terminal::Point,
+};
+use serde::Deserialize;
+use specs::{
+    Component, Entities, Entity, Join, Read, ReadExpect, ReadStorage, System, VecStorage, Write, WriteStorage,
+};
+
+use crate::{
+    char_c::CH_STRIKE,
+    colors::TEXASROSE,
+    components::{ActionPoints, DeleteCondition, HealthStats, Monster, Name, Position, SufferDamage},
+    debug::CLEAR,
+    game_init::PlayerEntity,
+    map::{distance, Map, MapRes},
+    speed::is_ready,
+    tile_animation::{AnimationRequest, TileAnimationBuilder},
+    ui::message_log::MessageLog,
+};
+
+/// How long the strike is shown on the tiles the attack hit
+const STRIKE_FLASH_TIME: Duration = Duration::from_millis(250);
+
+/// Which tiles a special attack hits, `size` on the ability sets how far the shape reaches
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SpecialShape {
+    /// Every tile around the attacker
+    Around,
+    /// A straight line from the attacker towards its target
+    Line,
+    /// The target's tile and every tile around it
+    Burst,
+}
+
+/// A monster's charged attack as it is defined in the raws
+#[derive(Deserialize, Clone)]
+pub struct SpecialAbility {
+    pub(crate) name: String,
+    pub(crate) damage: u32,
+    /// The attack is started once the target is this many tiles away or closer
+    pub(crate) range: u32,
+    /// Turns the tiles are highlighted before the attack goes off, the player's chance to get out of the way
+    pub(crate) telegraph_turns: u32,
+    /// Turns after the attack before the monster can wind up again
+    pub(crate) cooldown_turns: u32,
+    pub(crate) shape: SpecialShape,
+    pub(crate) size: u32,
+}
+
+impl SpecialAbility {
+    /// Checked when the beings are loaded so a bad special never makes it into the game
+    pub fn validate(&self) -> Result<(), String> {
+        if self.telegraph_turns == 0 {
+            return Err(format!("special {} needs a telegraph_turns above 0", self.name));
+        }
+        if self.range == 0 || self.size == 0 {
+            return Err(format!("special {} needs a range and size above 0", self.name));
+        }
+        Ok(())
+    }
+
+    /// The tiles the attack would hit when used from `from` on a target standing at `target`
+    pub fn tiles(&self, map: &Map, from: Position, target: Position) -> Vec<Position> {
+        let size = self.size as i32;
+        let (from, target) = (from.to_point(), target.to_point());
+        let points: Vec<Point> = match self.shape {
+            SpecialShape::Around => square(from, size).filter(|point| *point != from).collect(),
+            SpecialShape::Line => {
+                // Stretched or cut to `size` tiles so the line always reaches the same distance
+                let (dx, dy) = (target.x - from.x, target.y - from.y);
+                let reach = dx.abs().max(dy.abs()).max(1) as f32;
+                let scale = |delta: i32| (delta as f32 * size as f32 / reach).round() as i32;
+                let end = from + Point::new(scale(dx), scale(dy));
+                line2d(LineAlg::Bresenham, from, end).into_iter().filter(|point| *point != from).collect()
+            }
+            SpecialShape::Burst => square(target, size).collect(),
+        };
+        points.into_iter().filter(|point| map.in_bounds(*point)).map(Position::from).collect()
+    }
+}
+
+/// Every point within `size` tiles of `center`, diagonals included
+fn square(center: Point, size: i32) -> impl Iterator<Item = Point> {
+    (-size..=size).flat_map(move |dx| (-size..=size).map(move |dy| center + Point::new(dx, dy)))
+}
+
+/// Where a monster is in using its special attack
+#[derive(Clone)]
+pub enum SpecialState {
+    /// Waiting for a target to come in range
+    Idle,
+    /// Winding up, the attack goes off on `tiles` once `turns_left` runs out
+    Telegraph { turns_left: u32, tiles: Vec<Position> },
+    /// Went off this turn, the monster recovers for the rest of it
+    Attack,
+}
+
+/// A monster's special attack and how far along it is, not saved since it is rebuilt from the raws on load like the
+/// rest of the ai
+#[derive(Component, Clone)]
+#[storage(VecStorage)]
+pub struct SpecialAttack {
+    pub ability: SpecialAbility,
+    pub state: SpecialState,
+    /// Turns left before the monster can start another wind up
+    pub cooldown_left: u32,
+}
+
+impl SpecialAttack {
+    pub fn new(ability: SpecialAbility) -> Self {
+        Self { ability, state: SpecialState::Idle, cooldown_left: 0 }
+    }
+
+    /// Whether the special is taking up the monster's turn so it neither moves nor makes a normal attack
+    pub fn busy(&self) -> bool {
+        !matches!(self.state, SpecialState::Idle)
+    }
+
+    /// The tiles that are about to be hit, empty unless the monster is winding up
+    pub fn telegraphed(&self) -> &[Position] {
+        match &self.state {
+            SpecialState::Telegraph { tiles, .. } => tiles,
+            _ => &[],
+        }
+    }
+}
+
+/// Counts down wind ups and cooldowns once a turn, a monster that attacked last turn goes back to idle
+pub struct TickSpecialAttacks;
+
+impl<'a> System<'a> for TickSpecialAttacks {
+    type SystemData = (WriteStorage<'a, SpecialAttack>,);
+
+    fn run(&mut self, (mut specials,): Self::SystemData) {
+        for special in (&mut specials).join() {
+            match &mut special.state {
+                SpecialState::Idle => special.cooldown_left = special.cooldown_left.saturating_sub(1),
+                SpecialState::Telegraph { turns_left, .. } => *turns_left = turns_left.saturating_sub(1),
+                SpecialState::Attack => {
+                    special.state = SpecialState::Idle;
+                    special.cooldown_left = special.ability.cooldown_turns;
+                }
+            }
+        }
+    }
+}
+
+/// Starts a wind up when a monster's target comes in range and fires it once the wind up is done. Whatever is still
+/// standing on the telegraphed tiles is hit, the monster itself aside
+pub struct MonsterSpecialAttacks;
+
+impl<'a> System<'a> for MonsterSpecialAttacks {
+    type SystemData = (
+        WriteStorage<'a, SpecialAttack>,
+        WriteStorage<'a, SufferDamage>,
+        ReadStorage<'a, Monster>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, HealthStats>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, ActionPoints>,
+        ReadExpect<'a, MapRes>,
+        Read<'a, PlayerEntity>,
+        Write<'a, TileAnimationBuilder>,
+        Write<'a, MessageLog>,
+        Entities<'a>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut specials,
+            mut suffer_damage,
+            monsters,
+            positions,
+            healths,
+            names,
+            action_points,
+            map,
+            player_e,
+            mut anim_builder,
+            mut log,
+            entities,
+        ): Self::SystemData,
+    ) {
+        for (entity, special, monster, pos, name) in (&entities, &mut specials, &monsters, &positions, &names).join() {
+            if !is_ready(action_points.get(entity)) {
+                continue;
+            }
+            match &special.state {
+                SpecialState::Idle => {
+                    let target = monster.target.and_then(|target| positions.get(target).map(|pos| (target, *pos)));
+                    let (target, target_pos) = match target {
+                        Some((target, target_pos))
+                            if special.cooldown_left == 0 && distance(pos, &target_pos) <= special.ability.range =>
+                        {
+                            (target, target_pos)
+                        }
+                        _ => continue,
+                    };
+                    let tiles = special.ability.tiles(&map.0, *pos, target_pos);
+                    if target == player_e.0 {
+                        log.log(format!("The {} readies its {}!", name, special.ability.name));
+                    }
+                    special.state = SpecialState::Telegraph { turns_left: special.ability.telegraph_turns, tiles };
+                }
+                SpecialState::Telegraph { turns_left: 0, tiles } => {
+                    let victims: Vec<Entity> = (&entities, &positions, &healths)
+                        .join()
+                        .filter(|(victim, victim_pos, _)| *victim != entity && tiles.contains(victim_pos))
+                        .map(|(victim, ..)| victim)
+                        .collect();
+                    for victim in victims.iter() {
+                        SufferDamage::new_damage(
+                            &mut suffer_damage,
+                            *victim,
+                            Some(entity),
+                            -(special.ability.damage as i32),
+                        );
+                    }
+                    for tile in tiles.iter() {
+                        anim_builder.request(AnimationRequest::StaticTile(
+                            CH_STRIKE as usize,
+                            *tile,
+                            ColorPair::new(TEXASROSE, CLEAR),
+                            DeleteCondition::Timed(STRIKE_FLASH_TIME),
+                        ));
+                    }
+                    if victims.contains(&player_e.0) {
+                        log.log(format!("The {}'s {} hits you!", name, special.ability.name));
+                    } else if monster.target == Some(player_e.0) {
+                        log.log(format!("You avoid the {}'s {}.", name, special.ability.name));
+                    }
+                    special.state = SpecialState::Attack;
+                }
+                // Still winding up or recovering, either way the turn is spent
+                SpecialState::Telegraph { .. } | SpecialState::Attack => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::{Builder, WorldExt};
+
+    use super::*;
+    use crate::{speed::NORMAL_SPEED, test_harness::TestWorld};
+
+    fn tail_sweep() -> SpecialAbility {
+        SpecialAbility {
+            name: "Tail Sweep".to_string(),
+            damage: 4,
+            range: 1,
+            telegraph_turns: 1,
+            cooldown_turns: 3,
+            shape: SpecialShape::Around,
+            size: 1,
+        }
+    }
+
+    #[test]
+    fn stepping_off_the_telegraphed_tiles_dodges_the_attack() {
+        let mut world = TestWorld::new().with_map(8, 8);
+        let player = world.player();
+        world.insert(player, Position::new(3, 2));
+        world.insert(player, HealthStats::new(10, 0));
+        let mut orc = Monster::new(3, 6);
+        orc.target = Some(player);
+        let orc = world
+            .spawn("Orc", Position::new(3, 3))
+            .with(orc)
+            .with(ActionPoints { points: 1, ..ActionPoints::new(NORMAL_SPEED) })
+            .with(SpecialAttack::new(tail_sweep()))
+            .build();
+        let sheep = world.spawn("Sheep", Position::new(4, 4)).with(HealthStats::new(5, 0)).build();
+
+        world.run(MonsterSpecialAttacks);
+        let special = world.get::<SpecialAttack>(orc).unwrap();
+        assert!(special.busy() && special.telegraphed().contains(&Position::new(3, 2)));
+        assert!(world.logged("readies its Tail Sweep"));
+
+        world.insert(player, Position::new(3, 1));
+        world.run(TickSpecialAttacks);
+        world.run(MonsterSpecialAttacks);
+        assert!(!world.has::<SufferDamage>(player));
+        assert!(world.has::<SufferDamage>(sheep) && !world.has::<SufferDamage>(orc));
+        assert!(world.logged("You avoid the Orc's Tail Sweep."));
+
+        world.run(TickSpecialAttacks);
+        let special = world.get::<SpecialAttack>(orc).unwrap();
+        assert!(!special.busy());
+        assert_eq!(special.cooldown_left, 3);
+    }
+}
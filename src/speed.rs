@@ -0,0 +1,172 @@
+use std::cmp::Reverse;
+
+use specs::{Entities, Entity, Join, Read, ReadExpect, ReadStorage, System, World, WorldExt, WriteStorage};
+
+use crate::{
+    components::{ActionPoints, BreakAction, EffectiveStats, MoveAction, Position},
+    game_init::PlayerEntity,
+    map::MapRes,
+    mining::break_cost,
+    player::Player,
+};
+
+/// Points gained each turn by an entity of normal speed, which is enough for one normal action
+pub const NORMAL_SPEED: u32 = 10;
+/// Points spent on anything that isn't a move, ie. attacking, breaking a tile, or waiting
+pub const ACTION_COST: u32 = 10;
+/// Points spent stepping onto an ordinary tile
+pub const MOVE_COST: u32 = 10;
+/// Points spent stepping into shallow water
+pub const WADING_MOVE_COST: u32 = 20;
+/// Limits how many times a very fast entity can act in a single turn
+pub const MAX_ROUNDS_PER_TURN: usize = 4;
+
+/// How long the player's last action took, everything else gains points for this much time
+#[derive(Default)]
+pub struct TurnTime(pub u32);
+
+/// An entity can act while it has points left, entities without action points never act on their own
+pub fn is_ready(action_points: Option<&ActionPoints>) -> bool {
+    action_points.is_some_and(|ap| ap.points > 0)
+}
+
+/// The time an action costing `cost` takes for an entity with `speed`, faster entities take less time
+fn time_taken(cost: u32, speed: u32) -> u32 {
+    cost * NORMAL_SPEED / speed.max(1)
+}
+
+/// How long the player's action took, `moved_to` is where they stepped when the action was a move
+pub fn player_turn_time(ecs: &World, moved_to: Option<Position>) -> u32 {
+    let cost = match moved_to {
+        Some(pos) => ecs.read_resource::<MapRes>().0.move_cost(&pos),
+        None => {
+            let player_entity = ecs.read_resource::<PlayerEntity>().0;
+            match ecs.read_storage::<BreakAction>().get(player_entity) {
+                Some(action) => break_cost(ecs, player_entity, action.target),
+                None => ACTION_COST,
+            }
+        }
+    };
+    let player_entity = ecs.read_resource::<PlayerEntity>();
+    let effective = ecs.read_storage::<EffectiveStats>();
+    let action_points = ecs.read_storage::<ActionPoints>();
+    time_taken(cost, speed_of(player_entity.0, &action_points, &effective))
+}
+
+/// An entity's speed with its equipment counted, entities without action points move at normal speed
+fn speed_of(entity: Entity, action_points: &ReadStorage<ActionPoints>, effective: &ReadStorage<EffectiveStats>) -> u32 {
+    match effective.get(entity) {
+        Some(stats) => stats.speed,
+        None => action_points.get(entity).map_or(NORMAL_SPEED, |ap| ap.speed),
+    }
+}
+
+/// Orders `acting` so the entities with the most initiative go first, ties go to the lower entity id so the order is
+/// the same every time. Entities without action points go last
+pub fn initiative_order(mut acting: Vec<Entity>, action_points: &ReadStorage<ActionPoints>) -> Vec<Entity> {
+    acting.sort_by_key(|entity| (Reverse(action_points.get(*entity).map_or(0, |ap| ap.initiative)), entity.id()));
+    acting
+}
+
+/// True when something other than the player still has points to act with this turn
+pub fn any_ready(ecs: &World) -> bool {
+    let players = ecs.read_storage::<Player>();
+    (&ecs.read_storage::<ActionPoints>(), !&players).join().any(|(ap, _)| is_ready(Some(ap)))
+}
+
+/// Gives everything besides the player the points they earned while the player acted
+pub struct GrantActionPoints;
+
+impl<'a> System<'a> for GrantActionPoints {
+    type SystemData =
+        (WriteStorage<'a, ActionPoints>, ReadStorage<'a, EffectiveStats>, ReadStorage<'a, Player>, Read<'a, TurnTime>);
+
+    fn run(&mut self, (mut action_points, effective, players, turn_time): Self::SystemData) {
+        for (ap, stats, _) in (&mut action_points, effective.maybe(), !&players).join() {
+            let speed = stats.map_or(ap.speed, |stats| stats.speed);
+            let gained = (turn_time.0 * speed / NORMAL_SPEED) as i32;
+            // Points left over from idling don't carry on past this turn so nothing can save up a burst of actions
+            ap.points = (ap.points + gained).min(gained);
+        }
+    }
+}
+
+/// Charges every entity that was ready for what it chose to do, doing nothing still spends a normal action
+pub struct SpendActionPoints;
+
+impl<'a> System<'a> for SpendActionPoints {
+    type SystemData = (
+        WriteStorage<'a, ActionPoints>,
+        ReadStorage<'a, MoveAction>,
+        ReadStorage<'a, Player>,
+        ReadExpect<'a, MapRes>,
+        Entities<'a>,
+    );
+
+    fn run(&mut self, (mut action_points, move_actions, players, map, entities): Self::SystemData) {
+        for (entity, ap, _) in (&entities, &mut action_points, !&players).join() {
+            if ap.points <= 0 {
+                continue;
+            }
+            let cost = match move_actions.get(entity) {
+                Some(want) => map.0.move_cost(&want.new_pos),
+                None => ACTION_COST,
+            };
+            ap.points -= cost as i32;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::{Builder, RunNow, World, WorldExt};
+
+    use crate::{being::HandleMoveActions, test_harness::TestWorld};
+
+    use super::*;
+
+    #[test]
+    fn double_speed_acts_twice_and_idle_points_do_not_pile_up() {
+        let mut world = World::new();
+        world.register::<ActionPoints>();
+        world.register::<Player>();
+        world.insert(TurnTime(time_taken(MOVE_COST, NORMAL_SPEED)));
+        let fast = world.create_entity().with(ActionPoints::new(NORMAL_SPEED * 2)).build();
+
+        GrantActionPoints.run_now(&world);
+        GrantActionPoints.run_now(&world);
+
+        // two turns of idling still only leaves enough for the two actions of one turn
+        let points = world.read_storage::<ActionPoints>().get(fast).unwrap().points;
+        assert_eq!(points, (MOVE_COST * 2) as i32);
+    }
+
+    #[test]
+    fn initiative_decides_who_takes_a_contested_tile() {
+        let mut world = TestWorld::new().with_map(6, 6);
+        let contested = Position::new(2, 2);
+        let slow = world.spawn("Slow", Position::new(1, 2)).with(ActionPoints::new(NORMAL_SPEED)).build();
+        let fast = world.spawn("Fast", Position::new(3, 2)).with(ActionPoints::new(NORMAL_SPEED * 2)).build();
+        let tied = world.spawn("Tied", Position::new(2, 1)).with(ActionPoints::new(NORMAL_SPEED)).build();
+        {
+            let action_points = world.ecs.read_storage::<ActionPoints>();
+            assert_eq!(initiative_order(vec![tied, slow, fast], &action_points), vec![fast, slow, tied]);
+        }
+        for mover in [slow, fast, tied] {
+            world.insert(mover, MoveAction::new(contested));
+        }
+
+        world.run(HandleMoveActions);
+
+        assert_eq!(world.get::<Position>(fast), Some(contested));
+        assert_eq!(world.get::<Position>(slow), Some(Position::new(1, 2)));
+        assert_eq!(world.get::<Position>(tied), Some(Position::new(2, 1)));
+    }
+
+    #[test]
+    fn faster_entities_take_less_time() {
+        assert_eq!(time_taken(MOVE_COST, NORMAL_SPEED), MOVE_COST);
+        assert_eq!(time_taken(WADING_MOVE_COST, NORMAL_SPEED * 2), WADING_MOVE_COST / 2);
+        assert_eq!(time_taken(ACTION_COST, 0), ACTION_COST * NORMAL_SPEED);
+    }
+}
@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use specs::{Component, Entities, Join, Read, System, VecStorage, WriteStorage};
+
+use crate::{
+    components::{HealAction, SufferDamage},
+    time::DeltaTime,
+};
+
+/// How often an effect with a per tick action (poison, regen) is applied
+const STATUS_TICK: Duration = Duration::from_secs(1);
+/// How long an effect lasts when applied from a consumable
+pub const CONSUMABLE_STATUS_DURATION: Duration = Duration::from_secs(10);
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum StatusKind {
+    /// Deals `magnitude` damage every tick
+    Poison,
+    /// Heals `magnitude` health every tick
+    Regen,
+    /// Has no tick action, adds `magnitude` to the effective speed while it lasts
+    Speed,
+}
+
+/// Decides what happens when an effect is applied to an entity that already has the same kind
+#[derive(PartialEq, Debug)]
+pub enum StackRule {
+    /// The timer is reset and the strongest magnitude is kept
+    Refresh,
+    /// The magnitudes are added together and the timer is reset
+    Add,
+}
+
+impl StatusKind {
+    pub fn from_str(str: &str) -> Option<Self> {
+        match str {
+            "poison" => Some(Self::Poison),
+            "regen" => Some(Self::Regen),
+            "speed" => Some(Self::Speed),
+            _ => None,
+        }
+    }
+
+    pub fn stack_rule(&self) -> StackRule {
+        match self {
+            StatusKind::Poison => StackRule::Add,
+            StatusKind::Regen | StatusKind::Speed => StackRule::Refresh,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub magnitude: usize,
+    pub remaining: Duration,
+    /// Time since the effect was last applied
+    since_tick: Duration,
+}
+
+impl StatusEffect {
+    pub fn new(kind: StatusKind, magnitude: usize, duration: Duration) -> Self {
+        Self { kind, magnitude, remaining: duration, since_tick: Duration::ZERO }
+    }
+}
+
+/// All the timed effects currently on an entity, at most one of each kind
+#[derive(Component, Serialize, Deserialize, Clone, Default)]
+#[storage(VecStorage)]
+pub struct StatusEffects {
+    pub effects: Vec<StatusEffect>,
+}
+
+impl StatusEffects {
+    /// Adds the effect following the stacking rule of its kind
+    pub fn apply(&mut self, effect: StatusEffect) {
+        let existing = match self.effects.iter_mut().find(|e| e.kind == effect.kind) {
+            Some(existing) => existing,
+            None => {
+                self.effects.push(effect);
+                return;
+            }
+        };
+
+        existing.remaining = existing.remaining.max(effect.remaining);
+        existing.magnitude = match effect.kind.stack_rule() {
+            StackRule::Refresh => existing.magnitude.max(effect.magnitude),
+            StackRule::Add => existing.magnitude + effect.magnitude,
+        };
+    }
+
+    /// Gets the magnitude of the kind of effect, 0 if the entity doesn't have it
+    pub fn magnitude(&self, kind: StatusKind) -> usize {
+        self.effects.iter().find(|e| e.kind == kind).map_or(0, |e| e.magnitude)
+    }
+}
+
+/// Adds an effect to the entity, creating their `StatusEffects` if they have none yet
+pub fn apply_status(store: &mut WriteStorage<StatusEffects>, target: specs::Entity, effect: StatusEffect) {
+    match store.get_mut(target) {
+        Some(statuses) => statuses.apply(effect),
+        None => {
+            let mut statuses = StatusEffects::default();
+            statuses.apply(effect);
+            let _ = store.insert(target, statuses);
+        }
+    }
+}
+
+/// Counts down every status effect and applies their tick actions, expired effects are removed
+pub struct StatusEffectSystem;
+
+impl<'a> System<'a> for StatusEffectSystem {
+    type SystemData = (
+        WriteStorage<'a, StatusEffects>,
+        WriteStorage<'a, SufferDamage>,
+        WriteStorage<'a, HealAction>,
+        Read<'a, DeltaTime>,
+        Entities<'a>,
+    );
+
+    fn run(&mut self, (mut statuses, mut suffer_damage, mut heal_actions, dt, entities): Self::SystemData) {
+        let mut cleared = Vec::new();
+        for (entity, status) in (&entities, &mut statuses).join() {
+            for effect in status.effects.iter_mut() {
+                // The final partial tick still counts so short effects are not wasted
+                let elapsed = dt.0.min(effect.remaining);
+                effect.remaining -= elapsed;
+                effect.since_tick += elapsed;
+                if effect.since_tick < STATUS_TICK && !effect.remaining.is_zero() {
+                    continue;
+                }
+                effect.since_tick = Duration::ZERO;
+
+                match effect.kind {
+                    StatusKind::Poison => {
+                        SufferDamage::new_damage(&mut suffer_damage, entity, None, -(effect.magnitude as i32))
+                    }
+                    StatusKind::Regen => {
+                        let amount = heal_actions.get(entity).map_or(0, |heal| heal.amount) + effect.magnitude;
+                        let _ = heal_actions.insert(entity, HealAction { amount });
+                    }
+                    StatusKind::Speed => {}
+                }
+            }
+
+            status.effects.retain(|effect| !effect.remaining.is_zero());
+            if status.effects.is_empty() {
+                cleared.push(entity);
+            }
+        }
+
+        for entity in cleared {
+            statuses.remove(entity);
+        }
+    }
+}
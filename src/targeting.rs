@@ -0,0 +1,467 @@
+use std::collections::HashSet;
+
+use bracket_lib::terminal::{BTerm, Point, VirtualKeyCode as VKC};
+use log::error;
+use specs::{Builder, Entity, Join, World, WorldExt};
+
+use crate::{
+    camera::HoveredTile,
+    components::{
+        Blast, Blocking, Breakable, DeleteCondition, Facing, InBag, Item, LitFuse, Monster, Name, Position,
+        RangedAttackAction, Renderable, TargetEffect, TargetedUse, Viewshed, Water,
+    },
+    data_read::{
+        prelude::{build_crop, build_world_obj, TILE_KIND_DB},
+        ENTITY_DB,
+    },
+    explosives::{blast_area, FUSE_TIME},
+    game_init::PlayerEntity,
+    items::{ItemQty, ItemSpawner, SpawnType},
+    map::{distance, MapRes},
+    player::PlayerResponse,
+    ui::message_log::MessageLog,
+    z_order::ITEM_Z,
+    AppState,
+};
+
+/// The tile name a growing use needs to be aimed at
+const GROWABLE_TILE: &str = "Grass";
+
+pub enum TargetingResponse {
+    Waiting,
+    /// The cursor moved onto a new tile
+    Moved(Position),
+    /// The item was used on the target, the turn should pass
+    Used,
+    Cancel,
+}
+
+pub enum EnemyTargetingResponse {
+    Waiting,
+    /// Tabbed over to another enemy
+    Switched(Entity),
+    /// The attack was queued on the target, the turn should pass
+    Attacked,
+    Cancel,
+}
+
+/// Picks the first tile the cursor starts on, the one the user is facing. None when the item has no targeted use
+pub fn start_targeting(ecs: &World, user: Entity, item: Entity) -> Option<Position> {
+    if !ecs.read_storage::<TargetedUse>().contains(item) {
+        ecs.fetch_mut::<MessageLog>().log("That can't be used on anything around you.");
+        return None;
+    }
+
+    let positions = ecs.read_storage::<Position>();
+    let facings = ecs.read_storage::<Facing>();
+    let user_pos = *positions.get(user)?;
+    let facing_pos = match facings.get(user) {
+        Some(facing) => user_pos.to_point() + facing.delta(),
+        None => user_pos.to_point(),
+    };
+    if ecs.fetch::<MapRes>().0.in_bounds(facing_pos) {
+        Some(Position::from(facing_pos))
+    } else {
+        Some(user_pos)
+    }
+}
+
+pub fn p_input_targeting(ecs: &mut World, ctx: &mut BTerm, item: Entity, target: Position) -> TargetingResponse {
+    if ctx.left_click {
+        let hovered = ecs.read_resource::<HoveredTile>().0;
+        return match hovered {
+            Some(clicked) => use_on_target(ecs, item, clicked),
+            None => TargetingResponse::Waiting,
+        };
+    }
+
+    match ctx.key {
+        None => TargetingResponse::Waiting,
+        Some(key) => match key {
+            VKC::W | VKC::Up => move_cursor(ecs, target, 0, -1),
+            VKC::S | VKC::Down => move_cursor(ecs, target, 0, 1),
+            VKC::A | VKC::Left => move_cursor(ecs, target, -1, 0),
+            VKC::D | VKC::Right => move_cursor(ecs, target, 1, 0),
+            VKC::E | VKC::Return | VKC::Space => use_on_target(ecs, item, target),
+            VKC::Escape => TargetingResponse::Cancel,
+            _ => TargetingResponse::Waiting,
+        },
+    }
+}
+
+fn move_cursor(ecs: &World, target: Position, delta_x: i32, delta_y: i32) -> TargetingResponse {
+    let next = target.to_point() + Point::new(delta_x, delta_y);
+    if !ecs.fetch::<MapRes>().0.in_bounds(next) {
+        return TargetingResponse::Waiting;
+    }
+    TargetingResponse::Moved(Position::from(next))
+}
+
+fn use_on_target(ecs: &mut World, item: Entity, target: Position) -> TargetingResponse {
+    if try_use_on_target(ecs, item, target) {
+        TargetingResponse::Used
+    } else {
+        TargetingResponse::Waiting
+    }
+}
+
+/// Uses the player's item on the target tile, logs why it couldn't be used when false
+pub fn try_use_on_target(ecs: &mut World, item: Entity, target: Position) -> bool {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let (targeted_use, item_name, user_pos) = {
+        let target_uses = ecs.read_storage::<TargetedUse>();
+        let in_bags = ecs.read_storage::<InBag>();
+        let names = ecs.read_storage::<Name>();
+        let positions = ecs.read_storage::<Position>();
+        match (target_uses.get(item), in_bags.get(item), positions.get(player_entity)) {
+            (Some(targeted_use), Some(bag), Some(pos)) if bag.owner == player_entity => {
+                let name = names.get(item).map_or("item".to_string(), |name| name.0.clone());
+                (targeted_use.clone(), name, *pos)
+            }
+            _ => {
+                ecs.fetch_mut::<MessageLog>().log("You no longer have anything to use there.");
+                return false;
+            }
+        }
+    };
+
+    if distance(&user_pos, &target) > targeted_use.range {
+        ecs.fetch_mut::<MessageLog>().log(format!("That is too far away to reach with the {}.", item_name));
+        return false;
+    }
+
+    match targeted_use.effect {
+        TargetEffect::Grow(world_obj) => grow_on(ecs, &item_name, &world_obj, target),
+        TargetEffect::Fill(filled) => fill_from(ecs, player_entity, item, &item_name, &filled, target),
+        TargetEffect::Plant(crop) => plant_in(ecs, item, &item_name, &crop, target),
+        TargetEffect::Explode(blast) => throw_at(ecs, player_entity, item, &item_name, blast, target),
+    }
+}
+
+fn grow_on(ecs: &mut World, item_name: &str, world_obj: &str, target: Position) -> bool {
+    let (tile_name, blocked) = {
+        let map = ecs.fetch::<MapRes>();
+        match map.0.checked_xy_to_idx(target.x, target.y) {
+            Some(idx) => (map.0.tiles[idx].name.clone(), map.0.is_blocked(&target)),
+            None => return false,
+        }
+    };
+    if tile_name != GROWABLE_TILE {
+        ecs.fetch_mut::<MessageLog>().log(format!("Nothing would grow on {} with the {}.", tile_name, item_name));
+        return false;
+    }
+    if blocked {
+        ecs.fetch_mut::<MessageLog>().log("Something is in the way of anything growing there.");
+        return false;
+    }
+
+    match build_world_obj(world_obj, target, ecs) {
+        Ok(_) => {
+            ecs.fetch_mut::<MessageLog>().enhance(format!("A {} sprouts up from the watered grass.", world_obj));
+            true
+        }
+        Err(e) => {
+            error!("{} could not grow a world object: {}", item_name, e);
+            ecs.fetch_mut::<MessageLog>().log("Nothing seems to grow.");
+            false
+        }
+    }
+}
+
+fn fill_from(
+    ecs: &mut World,
+    player_entity: Entity,
+    item: Entity,
+    item_name: &str,
+    filled: &str,
+    target: Position,
+) -> bool {
+    let is_water = {
+        let waters = ecs.read_storage::<Water>();
+        let positions = ecs.read_storage::<Position>();
+        (&waters, &positions).join().any(|(_, pos)| *pos == target)
+    };
+    if !is_water {
+        ecs.fetch_mut::<MessageLog>().log(format!("The {} can only be filled with water.", item_name));
+        return false;
+    }
+
+    let filled_id = match ENTITY_DB.lock().unwrap().items.try_get_by_name(filled) {
+        Ok(info) => info.identifier,
+        Err(e) => {
+            error!("{} could not be filled: {}", item_name, e);
+            return false;
+        }
+    };
+
+    if let Some(bucket) = ecs.write_storage::<Item>().get_mut(item) {
+        bucket.qty = bucket.qty - ItemQty(1);
+    }
+    ecs.fetch_mut::<ItemSpawner>().request(filled_id, SpawnType::InBag(player_entity));
+    ecs.fetch_mut::<MessageLog>().log(format!("You fill the {} with water.", item_name));
+    true
+}
+
+fn plant_in(ecs: &mut World, item: Entity, item_name: &str, crop: &str, target: Position) -> bool {
+    let (tile_name, blocked) = {
+        let map = ecs.fetch::<MapRes>();
+        match map.0.checked_xy_to_idx(target.x, target.y) {
+            Some(idx) => (map.0.tiles[idx].name.clone(), map.0.is_blocked(&target)),
+            None => return false,
+        }
+    };
+    let tillable = TILE_KIND_DB.lock().unwrap().get_by_name(&tile_name).is_some_and(|kind| kind.tillable);
+    if !tillable {
+        ecs.fetch_mut::<MessageLog>().log(format!("The {} won't take root in {}.", item_name, tile_name));
+        return false;
+    }
+    if blocked {
+        ecs.fetch_mut::<MessageLog>().log("Something is in the way of planting there.");
+        return false;
+    }
+
+    match build_crop(crop, target, ecs) {
+        Ok(_) => {
+            if let Some(seeds) = ecs.write_storage::<Item>().get_mut(item) {
+                seeds.qty = seeds.qty - ItemQty(1);
+            }
+            ecs.fetch_mut::<MessageLog>().log(format!("You plant the {}.", item_name));
+            true
+        }
+        Err(e) => {
+            error!("{} could not be planted: {}", item_name, e);
+            ecs.fetch_mut::<MessageLog>().log("Nothing seems to take root.");
+            false
+        }
+    }
+}
+
+/// Lights the explosive and leaves it on the target to go off once the fuse burns down
+fn throw_at(
+    ecs: &mut World,
+    player_entity: Entity,
+    item: Entity,
+    item_name: &str,
+    blast: Blast,
+    target: Position,
+) -> bool {
+    let in_sight =
+        ecs.read_storage::<Viewshed>().get(player_entity).map_or(true, |view| view.tiles.contains(&target.to_point()));
+    if !in_sight {
+        ecs.fetch_mut::<MessageLog>().log(format!("You can't see where the {} would land.", item_name));
+        return false;
+    }
+
+    let sprite = {
+        let items = ecs.read_storage::<Item>();
+        let edb = ENTITY_DB.lock().unwrap();
+        match items.get(item).and_then(|thrown| edb.items.get_by_id(thrown.id)) {
+            Some(info) => Renderable::clear_bg(info.atlas_index, info.fg, ITEM_Z),
+            None => return false,
+        }
+    };
+    if let Some(thrown) = ecs.write_storage::<Item>().get_mut(item) {
+        thrown.qty = thrown.qty - ItemQty(1);
+    }
+    ecs.create_entity()
+        .with(target)
+        .with(sprite)
+        .with(Name::new(item_name))
+        .with(LitFuse { blast, thrower: player_entity })
+        .with(DeleteCondition::Timed(FUSE_TIME))
+        .build();
+    ecs.fetch_mut::<MessageLog>().log(format!("You light the {} and throw it.", item_name));
+    true
+}
+
+/// Every tile the item would affect if used on `target`, an explosive reaches everything its blast would when it goes
+/// off there while the other uses only touch the target itself
+pub fn affected_tiles(ecs: &World, item: Entity, target: Position) -> Vec<Position> {
+    let blast = match ecs.read_storage::<TargetedUse>().get(item) {
+        Some(TargetedUse { effect: TargetEffect::Explode(blast), .. }) => *blast,
+        _ => return vec![target],
+    };
+    let positions = ecs.read_storage::<Position>();
+    let blockers = ecs.read_storage::<Blocking>();
+    let breakables = ecs.read_storage::<Breakable>();
+    let walls: HashSet<Point> = (&positions, &blockers, &breakables).join().map(|(pos, ..)| pos.to_point()).collect();
+
+    let mut tiles: Vec<Position> =
+        blast_area(target, blast.radius, &ecs.fetch::<MapRes>().0, &walls).into_iter().map(Position::from).collect();
+    tiles.sort_by_key(|pos| (pos.y, pos.x));
+    tiles
+}
+
+/// A point along the flight of a thrown item, drawn while aiming so the player can see where it goes
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ArcPoint {
+    pub point: Point,
+    /// False once the flight is past how far the item can be thrown
+    pub in_range: bool,
+}
+
+/// Tiles the arc rises on screen for each tile it travels
+const ARC_RISE: f32 = 0.25;
+
+/// The path a thrown item takes from `from` to `to`, bowed upwards on screen so it reads as a lob rather than a
+/// straight line. Points over ground further than `range` from the thrower are marked out of range. Neither end is
+/// part of the arc since the thrower and the target are already drawn there
+pub fn throw_arc(from: Position, to: Position, range: u32) -> Vec<ArcPoint> {
+    let (dx, dy) = (to.x as f32 - from.x as f32, to.y as f32 - from.y as f32);
+    let steps = dx.abs().max(dy.abs()) as u32;
+    let peak = steps as f32 * ARC_RISE;
+
+    let mut arc: Vec<ArcPoint> = Vec::new();
+    for step in 1..steps {
+        let t = step as f32 / steps as f32;
+        let (ground_x, ground_y) = (from.x as f32 + dx * t, from.y as f32 + dy * t);
+        let height = 4.0 * peak * t * (1.0 - t);
+        let point = Point::new(ground_x.round() as i32, (ground_y - height).round() as i32);
+        if arc.last().is_some_and(|last| last.point == point) {
+            continue;
+        }
+        let ground = Position::new(ground_x.round() as usize, ground_y.round() as usize);
+        arc.push(ArcPoint { point, in_range: distance(&from, &ground) <= range });
+    }
+    arc
+}
+
+/// The arc to preview for `item` when the player aims it at `target`, only thrown items have one
+pub fn item_throw_arc(ecs: &World, item: Entity, target: Position) -> Vec<ArcPoint> {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    match (ecs.read_storage::<TargetedUse>().get(item), ecs.read_storage::<Position>().get(player_entity)) {
+        (Some(TargetedUse { effect: TargetEffect::Explode(_), range }), Some(player_pos)) => {
+            throw_arc(*player_pos, target, *range)
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Monsters the player can see, nearest first
+pub fn visible_enemies(ecs: &World) -> Vec<Entity> {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let entities = ecs.entities();
+    let positions = ecs.read_storage::<Position>();
+    let monsters = ecs.read_storage::<Monster>();
+    let viewsheds = ecs.read_storage::<Viewshed>();
+    let (player_pos, view) = match (positions.get(player_entity), viewsheds.get(player_entity)) {
+        (Some(pos), Some(view)) => (*pos, view),
+        _ => return vec![],
+    };
+
+    let mut enemies: Vec<(Entity, u32)> = (&entities, &positions, &monsters)
+        .join()
+        .filter(|(_, pos, _)| view.tiles.contains(&pos.to_point()))
+        .map(|(e, pos, _)| (e, distance(&player_pos, pos)))
+        .collect();
+    // ties are broken by id so tabbing through enemies the same distance away is stable
+    enemies.sort_by_key(|(e, dist)| (*dist, e.id()));
+    enemies.into_iter().map(|(e, _)| e).collect()
+}
+
+/// Starts aiming at the nearest enemy in sight
+pub fn start_enemy_targeting(ecs: &mut World) -> PlayerResponse {
+    match visible_enemies(ecs).first() {
+        Some(target) => PlayerResponse::StateChange(AppState::TargetingEnemy { target: *target }),
+        None => {
+            ecs.fetch_mut::<MessageLog>().log("There is nothing in sight to attack.");
+            PlayerResponse::Waiting
+        }
+    }
+}
+
+pub fn p_input_enemy_targeting(ecs: &mut World, ctx: &BTerm, target: Entity) -> EnemyTargetingResponse {
+    let enemies = visible_enemies(ecs);
+    // the target may have died or gone out of sight since it was picked
+    if !enemies.contains(&target) {
+        return match enemies.first() {
+            Some(nearest) => EnemyTargetingResponse::Switched(*nearest),
+            None => EnemyTargetingResponse::Cancel,
+        };
+    }
+
+    match ctx.key {
+        None => EnemyTargetingResponse::Waiting,
+        Some(key) => match key {
+            VKC::Tab => match cycle_target(&enemies, target, !ctx.shift) {
+                Some(next) if next != target => EnemyTargetingResponse::Switched(next),
+                _ => EnemyTargetingResponse::Waiting,
+            },
+            VKC::Return | VKC::F => {
+                let player_entity = ecs.read_resource::<PlayerEntity>().0;
+                let _ = ecs.write_storage::<RangedAttackAction>().insert(player_entity, RangedAttackAction { target });
+                EnemyTargetingResponse::Attacked
+            }
+            VKC::Escape => EnemyTargetingResponse::Cancel,
+            _ => EnemyTargetingResponse::Waiting,
+        },
+    }
+}
+
+/// The enemy after `current`, wrapping back to the start after the last one. `forward` false goes the other way
+fn cycle_target(enemies: &[Entity], current: Entity, forward: bool) -> Option<Entity> {
+    if enemies.is_empty() {
+        return None;
+    }
+    let next = match enemies.iter().position(|e| *e == current) {
+        Some(idx) if forward => (idx + 1) % enemies.len(),
+        Some(idx) => (idx + enemies.len() - 1) % enemies.len(),
+        None => 0,
+    };
+    Some(enemies[next])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::TestWorld;
+
+    #[test]
+    fn tabbing_wraps_around_the_targets() {
+        let mut world = World::new();
+        let enemies: Vec<Entity> = (0..3).map(|_| world.create_entity().build()).collect();
+
+        assert_eq!(cycle_target(&enemies, enemies[0], true), Some(enemies[1]));
+        assert_eq!(cycle_target(&enemies, enemies[2], true), Some(enemies[0]));
+        assert_eq!(cycle_target(&enemies, enemies[0], false), Some(enemies[2]));
+        assert_eq!(cycle_target(&[], enemies[0], true), None);
+    }
+
+    #[test]
+    fn throw_arcs_bow_upwards_and_grey_out_past_the_range() {
+        let arc = throw_arc(Position::new(2, 10), Position::new(10, 10), 5);
+        assert_eq!(arc.len(), 7);
+        assert!(arc.iter().all(|point| point.point.y < 10));
+        assert_eq!(arc[0].point.x, 3);
+        assert_eq!(arc[6].point.x, 9);
+        // the peak is halfway along
+        let highest = arc.iter().map(|point| point.point.y).min().unwrap();
+        assert_eq!(arc[3].point.y, highest);
+        assert!(highest < arc[0].point.y);
+        assert_eq!(arc.iter().filter(|point| point.in_range).count(), 5);
+        assert!(arc[4].in_range && !arc[5].in_range);
+
+        assert!(throw_arc(Position::new(4, 4), Position::new(5, 4), 5).is_empty());
+    }
+
+    #[test]
+    fn bombs_preview_their_whole_blast() {
+        let mut world = TestWorld::new();
+        let player = world.player();
+        let bomb = world
+            .spawn("Bomb", Position::new(0, 0))
+            .with(TargetedUse { effect: TargetEffect::Explode(Blast { radius: 1, damage: 6 }), range: 5 })
+            .with(InBag { owner: player })
+            .build();
+        let seeds = world
+            .spawn("Seeds", Position::new(0, 0))
+            .with(TargetedUse { effect: TargetEffect::Plant("Wheat".to_string()), range: 1 })
+            .build();
+
+        let blast = affected_tiles(&world.ecs, bomb, Position::new(5, 5));
+        assert_eq!(blast.len(), 5);
+        assert!(blast.contains(&Position::new(5, 4)));
+        assert!(!blast.contains(&Position::new(6, 6)));
+        assert_eq!(affected_tiles(&world.ecs, seeds, Position::new(5, 5)), vec![Position::new(5, 5)]);
+    }
+}
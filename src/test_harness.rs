@@ -0,0 +1,263 @@
+/* test_harness.rs
+ *   Builds a headless world with every component and resource the game uses so systems can be ran
+ *   one at a time in tests without opening a window.
+ * */
+
+use std::time::Duration;
+
+use specs::{Builder, Component, Entity, EntityBuilder, RunNow, World, WorldExt};
+
+use crate::{
+    components::{Name, Position},
+    game_init::PlayerEntity,
+    insert_resources,
+    map::{Map, MapRes},
+    player::Player,
+    register_components,
+    rng::GameRng,
+    time::DeltaTime,
+    ui::message_log::MessageLog,
+};
+
+pub const TEST_SEED: u64 = 50;
+pub const TEST_MAP_SIZE: usize = 20;
+/// One frame at 60 fps
+pub const TEST_DELTA: Duration = Duration::from_millis(16);
+
+pub struct TestWorld {
+    pub ecs: World,
+}
+
+impl TestWorld {
+    /// A grass map of `TEST_MAP_SIZE`, a seeded rng and a player entity with no position
+    pub fn new() -> Self {
+        let mut ecs = World::new();
+        register_components(&mut ecs);
+        insert_resources(&mut ecs);
+        ecs.insert(MapRes(Map::new(TEST_MAP_SIZE, TEST_MAP_SIZE, (0, 0))));
+        ecs.insert(GameRng::seeded(TEST_SEED));
+        ecs.insert(DeltaTime(TEST_DELTA));
+
+        let player = ecs.create_entity().with(Player {}).with(Name("Player".to_string())).build();
+        ecs.insert(PlayerEntity(player));
+        Self { ecs }
+    }
+
+    pub fn with_map(mut self, width: usize, height: usize) -> Self {
+        self.ecs.insert(MapRes(Map::new(width, height, (0, 0))));
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.ecs.insert(GameRng::seeded(seed));
+        self
+    }
+
+    pub fn with_delta(mut self, dt: Duration) -> Self {
+        self.ecs.insert(DeltaTime(dt));
+        self
+    }
+
+    pub fn player(&self) -> Entity {
+        self.ecs.read_resource::<PlayerEntity>().0
+    }
+
+    /// Starts a named entity on the tile, add whatever else the test needs before building it
+    pub fn spawn(&mut self, name: &str, pos: Position) -> EntityBuilder {
+        self.ecs.create_entity().with(Name(name.to_string())).with(pos)
+    }
+
+    pub fn insert<C: Component>(&mut self, entity: Entity, component: C) {
+        let _ = self.ecs.write_storage::<C>().insert(entity, component);
+    }
+
+    /// Runs the system once then cleans up anything it created or deleted
+    pub fn run<S: for<'a> RunNow<'a>>(&mut self, mut system: S) {
+        system.run_now(&self.ecs);
+        self.ecs.maintain();
+    }
+
+    pub fn get<C: Component + Clone>(&self, entity: Entity) -> Option<C> {
+        self.ecs.read_storage::<C>().get(entity).cloned()
+    }
+
+    pub fn has<C: Component>(&self, entity: Entity) -> bool {
+        self.ecs.read_storage::<C>().contains(entity)
+    }
+
+    /// True if any message in the log contains the text
+    pub fn logged(&self, text: &str) -> bool {
+        self.ecs.read_resource::<MessageLog>().messages.iter().any(|message| message.contents.contains(text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        activity::{Activity, ActivityKind},
+        combat::AttackActionHandler,
+        components::{
+            AttackAction, Blocking, Equipped, FinishedActivity, HealthStats, KillCount, SufferDamage, WeaponReach,
+        },
+        death::{player_is_dead, respawn_player, DeathCause, DeathMessages, KillCounter},
+        fishing::{CatchFishSystem, WaitingForFishSystem},
+        indexing::{IndexBlockedTiles, IndexReset},
+        mining::{DamageSystem, RemoveDeadTiles},
+        stats::EntityStatsBuilder,
+    };
+
+    use super::*;
+
+    #[test]
+    fn damage_is_applied_and_cleared() {
+        let mut world = TestWorld::new();
+        let rock = world.spawn("Rock", Position::new(3, 3)).with(HealthStats::new(10, 0)).build();
+        world.insert(rock, SufferDamage { amount: vec![(None, -4), (None, -2)] });
+        let player = world.player();
+        world.insert(player, HealthStats::new(10, 0));
+        world.insert(player, SufferDamage { amount: vec![(None, 3), (Some(rock), -5)] });
+
+        world.run(DamageSystem);
+
+        assert_eq!(world.get::<HealthStats>(rock).unwrap().hp, 4);
+        assert_eq!(world.get::<HealthStats>(player).unwrap().hp, 8);
+        assert!(!world.has::<SufferDamage>(rock));
+    }
+
+    #[test]
+    fn the_finishing_blow_is_named_as_the_killer() {
+        let mut world = TestWorld::new();
+        let sheep = world.spawn("Bahhhby", Position::new(3, 3)).build();
+        let player = world.player();
+        world.insert(player, HealthStats::new(5, 0));
+        world.insert(player, SufferDamage { amount: vec![(None, -2), (Some(sheep), -4)] });
+
+        world.run(DamageSystem);
+
+        assert!(player_is_dead(&world.ecs));
+        assert_eq!(world.ecs.read_resource::<DeathCause>().0.as_deref(), Some("Bahhhby"));
+        assert!(world.logged("You were killed by Bahhhby."));
+    }
+
+    #[test]
+    fn a_death_is_reported_once_however_often_it_is_hit() {
+        let mut world = TestWorld::new();
+        let player = world.player();
+        world.insert(player, KillCount::default());
+        let slime = world.spawn("Slime", Position::new(3, 3)).with(HealthStats::new(3, 0)).build();
+        let mut kill_counter = KillCounter::new(&mut world.ecs);
+        let mut death_messages = DeathMessages::new(&mut world.ecs);
+
+        world.insert(slime, SufferDamage { amount: vec![(Some(player), -2), (Some(player), -2)] });
+        world.run(DamageSystem);
+        world.insert(slime, SufferDamage { amount: vec![(Some(player), -1)] });
+        world.run(DamageSystem);
+        for _ in 0..2 {
+            kill_counter.run_now(&world.ecs);
+            death_messages.run_now(&world.ecs);
+        }
+
+        assert_eq!(world.get::<KillCount>(player).map(|kills| kills.0), Some(1));
+        let messages = world.ecs.read_resource::<MessageLog>();
+        assert_eq!(messages.messages.iter().filter(|message| message.contents.contains("Slime dies.")).count(), 1);
+    }
+
+    #[test]
+    fn blockers_are_indexed_on_their_tile() {
+        let mut world = TestWorld::new().with_map(8, 8);
+        world.spawn("Wall", Position::new(2, 5)).with(Blocking {}).build();
+
+        world.run(IndexReset);
+        world.run(IndexBlockedTiles);
+
+        let map = world.ecs.read_resource::<MapRes>();
+        assert!(map.0.is_blocked(&Position::new(2, 5)));
+        assert!(!map.0.is_blocked(&Position::new(5, 2)));
+    }
+
+    #[test]
+    fn reach_hits_along_the_line_until_a_wall() {
+        let mut world = TestWorld::new().with_map(8, 8);
+        let player = world.player();
+        let (stats, _) = EntityStatsBuilder::new().with_strength(3).build();
+        world.insert(player, Position::new(1, 1));
+        world.insert(player, stats);
+        world.ecs.create_entity().with(WeaponReach(4)).with(Equipped { on: player }).build();
+        let near = world.spawn("Near", Position::new(2, 1)).with(Blocking {}).with(HealthStats::new(10, 0)).build();
+        let far = world.spawn("Far", Position::new(3, 1)).with(Blocking {}).with(HealthStats::new(10, 0)).build();
+        world.spawn("Wall", Position::new(4, 1)).with(Blocking {}).build();
+        let behind = world.spawn("Behind", Position::new(5, 1)).with(Blocking {}).with(HealthStats::new(10, 0)).build();
+        world.run(IndexReset);
+        world.run(IndexBlockedTiles);
+
+        world.insert(player, AttackAction { target: near });
+        world.run(AttackActionHandler);
+
+        assert!(world.has::<SufferDamage>(near));
+        assert!(world.has::<SufferDamage>(far));
+        assert!(!world.has::<SufferDamage>(behind));
+        assert!(world.logged("You hit Far for 3"));
+    }
+
+    #[test]
+    fn hooked_fish_is_caught_when_the_activity_finishes() {
+        let mut world = TestWorld::new();
+        let fisher = world
+            .spawn("Fisher", Position::new(1, 1))
+            .with(Activity::new(ActivityKind::Fishing { attempts_left: 2, hooked: true }, Duration::ZERO))
+            .with(FinishedActivity {})
+            .build();
+
+        world.run(CatchFishSystem);
+
+        assert!(world.logged("Fisher caught a really big fish!"));
+        let still_hooked = matches!(
+            world.ecs.read_storage::<Activity>().get(fisher).unwrap().kind,
+            ActivityKind::Fishing { hooked: true, .. }
+        );
+        assert!(!still_hooked);
+    }
+
+    #[test]
+    fn dead_player_is_kept_and_respawned() {
+        let mut world = TestWorld::new();
+        let player = world.player();
+        world.insert(player, Position::new(4, 4));
+        world.insert(player, HealthStats { hp: 0, max_hp: 10, defense: 0 });
+        let rock = world.spawn("Rock", Position::new(5, 5)).with(HealthStats { hp: 0, max_hp: 5, defense: 0 }).build();
+
+        world.run(RemoveDeadTiles);
+
+        assert!(player_is_dead(&world.ecs));
+        assert!(world.ecs.is_alive(player));
+        assert!(!world.ecs.is_alive(rock));
+
+        respawn_player(&mut world.ecs);
+
+        assert!(!player_is_dead(&world.ecs));
+        assert_eq!(world.get::<HealthStats>(player).unwrap().hp, 10);
+    }
+
+    /// Same seed, same bites, fishing is long enough that a few attempts are rolled
+    #[test]
+    fn seeded_rolls_repeat() {
+        let bites = |seed: u64| -> Vec<bool> {
+            let mut world = TestWorld::new().with_seed(seed).with_delta(Duration::from_secs(1));
+            let fisher = world
+                .spawn("Fisher", Position::new(1, 1))
+                .with(Activity::new(ActivityKind::Fishing { attempts_left: 10, hooked: false }, Duration::ZERO))
+                .build();
+            (0..5)
+                .map(|_| {
+                    world.run(WaitingForFishSystem);
+                    matches!(
+                        world.ecs.read_storage::<Activity>().get(fisher).unwrap().kind,
+                        ActivityKind::Fishing { hooked: true, .. }
+                    )
+                })
+                .collect()
+        };
+
+        assert_eq!(bites(TEST_SEED), bites(TEST_SEED));
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::BTreeMap;
+
+use bracket_lib::terminal::{ColorPair, RGBA};
+use log::{debug, error};
+use serde::{Deserialize, Serialize};
+
+use crate::colors::{
+    to_rgb, Color, DARKBROWN, DARKERBROWN, MAROON, PARCHMENT, PL_CRITICAL_HP, PL_LOW_HP, PL_MAX_HP, PL_MED_HP,
+    TEXASROSE,
+};
+
+/// Overrides for the ui colors, anything left out keeps the default theme's value.
+/// Colors are hex strings like `"#2a2942"` keyed by the `Theme` field they replace
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct ThemeConfig {
+    #[serde(default)]
+    pub colors: BTreeMap<String, String>,
+    /// Health above this fraction of max is drawn in the medium color
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub med_hp_above: Option<f32>,
+    /// Health above this fraction of max is drawn in the low color, anything less is critical
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub low_hp_above: Option<f32>,
+}
+
+/// Resource with the colors the ui is drawn with, built once at startup from the config
+#[derive(Clone)]
+pub struct Theme {
+    pub max_hp: Color,
+    pub med_hp: Color,
+    pub low_hp: Color,
+    pub critical_hp: Color,
+    pub med_hp_above: f32,
+    pub low_hp_above: f32,
+    pub menu_text: Color,
+    pub menu_accent_text: Color,
+    pub keybind: Color,
+    pub panel_background: Color,
+    pub panel_outline: Color,
+    /// Marks the tile the player is facing, drawn faintly over it
+    pub facing_highlight: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            max_hp: (103, 252, 58),
+            med_hp: (72, 170, 42),
+            low_hp: (234, 184, 56),
+            critical_hp: (252, 50, 27),
+            med_hp_above: 0.5,
+            low_hp_above: 0.25,
+            menu_text: DARKERBROWN,
+            menu_accent_text: DARKBROWN,
+            keybind: MAROON,
+            panel_background: PARCHMENT,
+            panel_outline: TEXASROSE,
+            facing_highlight: PARCHMENT,
+        }
+    }
+}
+
+impl Theme {
+    /// The default theme with the config's overrides applied, overrides that can't be used are logged and skipped
+    pub fn from_config(cfg: &ThemeConfig) -> Self {
+        let mut theme = Theme::default();
+        for problem in theme.apply_overrides(cfg) {
+            error!("Theme: {}", problem);
+        }
+        theme
+    }
+
+    /// Returns a description of every override that was not applied
+    fn apply_overrides(&mut self, cfg: &ThemeConfig) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (name, hex) in cfg.colors.iter() {
+            let color = match parse_hex_color(hex) {
+                Ok(color) => color,
+                Err(e) => {
+                    problems.push(format!("{} for {} is not a color, {}", hex, name, e));
+                    continue;
+                }
+            };
+            match self.color_mut(name) {
+                Some(slot) => {
+                    debug!("Theme {} set to {}", name, hex);
+                    *slot = color;
+                }
+                None => problems.push(format!("{} is not a themeable color", name)),
+            }
+        }
+
+        let med_hp_above = cfg.med_hp_above.unwrap_or(self.med_hp_above);
+        let low_hp_above = cfg.low_hp_above.unwrap_or(self.low_hp_above);
+        if (0.0..=1.0).contains(&low_hp_above) && (0.0..=1.0).contains(&med_hp_above) && low_hp_above <= med_hp_above {
+            self.med_hp_above = med_hp_above;
+            self.low_hp_above = low_hp_above;
+        } else if cfg.med_hp_above.is_some() || cfg.low_hp_above.is_some() {
+            problems.push(format!(
+                "hp thresholds must be between 0 and 1 with low ({}) no higher than medium ({})",
+                low_hp_above, med_hp_above
+            ));
+        }
+        problems
+    }
+
+    fn color_mut(&mut self, name: &str) -> Option<&mut Color> {
+        Some(match name {
+            "max_hp" => &mut self.max_hp,
+            "med_hp" => &mut self.med_hp,
+            "low_hp" => &mut self.low_hp,
+            "critical_hp" => &mut self.critical_hp,
+            "menu_text" => &mut self.menu_text,
+            "menu_accent_text" => &mut self.menu_accent_text,
+            "keybind" => &mut self.keybind,
+            "panel_background" => &mut self.panel_background,
+            "panel_outline" => &mut self.panel_outline,
+            "facing_highlight" => &mut self.facing_highlight,
+            _ => return None,
+        })
+    }
+
+    /// Outline and fill of the boxes the menus and log are drawn in
+    pub fn panel(&self) -> ColorPair {
+        ColorPair::new(self.panel_outline, self.panel_background)
+    }
+
+    pub fn panel_bg(&self) -> RGBA {
+        to_rgb(self.panel_background).into()
+    }
+
+    pub fn panel_border(&self) -> RGBA {
+        to_rgb(self.panel_outline).into()
+    }
+
+    /// The printer palette health is colored with, it goes from the full health color to critical as `hp` drops
+    pub fn hp_palette(&self, hp: usize, max_hp: usize) -> &'static str {
+        let percent = hp as f32 / max_hp.max(1) as f32;
+        if hp >= max_hp {
+            PL_MAX_HP
+        } else if percent > self.med_hp_above {
+            PL_MED_HP
+        } else if percent > self.low_hp_above {
+            PL_LOW_HP
+        } else {
+            PL_CRITICAL_HP
+        }
+    }
+}
+
+/// Reads a `#rrggbb` color, the `#` is optional
+pub fn parse_hex_color(hex: &str) -> Result<Color, String> {
+    let digits = hex.strip_prefix('#').unwrap_or(hex);
+    if digits.len() != 6 || !digits.is_ascii() {
+        return Err("expected 6 hex digits like #2a2942".to_string());
+    }
+    let channel = |at: usize| u8::from_str_radix(&digits[at..at + 2], 16).map_err(|e| e.to_string());
+    Ok((channel(0)?, channel(2)?, channel(4)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_colors_parse() {
+        assert_eq!(parse_hex_color("#67fc3a"), Ok((103, 252, 58)));
+        assert_eq!(parse_hex_color("FFFFFF"), Ok((255, 255, 255)));
+        assert!(parse_hex_color("#fff").is_err());
+        assert!(parse_hex_color("#gg0000").is_err());
+    }
+
+    #[test]
+    fn bad_overrides_keep_the_default() {
+        let mut cfg = ThemeConfig::default();
+        cfg.colors.insert("menu_text".to_string(), "#000000".to_string());
+        cfg.colors.insert("keybind".to_string(), "maroon".to_string());
+        cfg.colors.insert("sky".to_string(), "#0000ff".to_string());
+        cfg.low_hp_above = Some(0.75);
+
+        let mut theme = Theme::default();
+        let problems = theme.apply_overrides(&cfg);
+        assert_eq!(problems.len(), 3);
+        assert_eq!(theme.menu_text, (0, 0, 0));
+        assert_eq!(theme.keybind, Theme::default().keybind);
+        assert_eq!(theme.low_hp_above, 0.25);
+    }
+}
@@ -1,10 +1,14 @@
 use std::time::Duration;
 
-use bracket_lib::terminal::ColorPair;
+use bracket_lib::terminal::{ColorPair, PointF};
 use specs::{Entities, Entity, Join, Read, ReadStorage, System, Write, WriteStorage};
 
 use crate::{
-    components::{DeleteCondition, FinishedActivity, GlyphFlash, Position, Renderable, SizeFlexor, Transform},
+    colors::Color,
+    components::{
+        AggroIndicator, DeleteCondition, FadingOut, FinishedActivity, FloatingText, GlyphFlash, Position, Renderable,
+        SizeFlexor, Slide, SpawnPop, Transform,
+    },
     draw_sprites::lerp_point,
     time::DeltaTime,
     z_order::TILE_ANIM_Z,
@@ -26,11 +30,26 @@ impl TileAnimationBuilder {
 }
 
 pub enum AnimationRequest {
-    StaticTile(u8, Position, ColorPair, DeleteCondition),
+    StaticTile(usize, Position, ColorPair, DeleteCondition),
     StretchShrink(Entity, SizeFlexor),
     GlyphFlash(Entity, Duration, Renderable),
+    FloatingText(Entity, String, Color),
+    SpawnPop(Entity),
 }
 
+/// How long a freshly spawned entity takes to scale up to full size
+pub const SPAWN_POP_DURATION: Duration = Duration::from_millis(250);
+
+pub const FLOATING_TEXT_LIFETIME: Duration = Duration::from_millis(900);
+/// How many tiles per second floating text will rise
+const FLOATING_TEXT_RISE_SPEED: f32 = 1.5;
+/// How long the "!" or "?" above a monster stays up
+pub const AGGRO_INDICATOR_LIFETIME: Duration = Duration::from_millis(1200);
+/// How close in tiles a sliding sprite gets to its position before it is drawn on the tile again
+const SLIDE_DONE_DISTANCE: f32 = 0.05;
+/// Vertical gap in tiles between floating texts spawned on the same tile
+const FLOATING_TEXT_STACK_OFFSET: f32 = 0.5;
+
 pub struct TileAnimationSpawner;
 
 impl<'a> System<'a> for TileAnimationSpawner {
@@ -42,7 +61,9 @@ impl<'a> System<'a> for TileAnimationSpawner {
         WriteStorage<'a, Renderable>,
         WriteStorage<'a, SizeFlexor>,
         WriteStorage<'a, GlyphFlash>,
+        WriteStorage<'a, FloatingText>,
         WriteStorage<'a, DeleteCondition>,
+        WriteStorage<'a, SpawnPop>,
     );
 
     fn run(
@@ -55,7 +76,9 @@ impl<'a> System<'a> for TileAnimationSpawner {
             mut renderables,
             mut flexors,
             mut color_flashes,
+            mut floating_texts,
             mut delete_conditions,
+            mut spawn_pops,
         ): Self::SystemData,
     ) {
         for request in anim_builder.requests.iter() {
@@ -85,6 +108,48 @@ impl<'a> System<'a> for TileAnimationSpawner {
                         color_flashes.insert(new_anim, GlyphFlash { _time_left: *time_left, sprite: flash.clone() });
                     let _ = delete_conditions.insert(new_anim, DeleteCondition::Timed(*time_left));
                 }
+                AnimationRequest::FloatingText(who, text, color) => {
+                    let at = match positions.get(*who) {
+                        Some(pos) => *pos,
+                        None => continue,
+                    };
+                    // texts on the same tile are pushed up so they dont overlap each other
+                    let stacked = (&floating_texts).join().filter(|ft| ft.origin == at).count() as f32;
+
+                    let new_anim = entities.create();
+                    let start_y = at.y as f32 - stacked * FLOATING_TEXT_STACK_OFFSET;
+                    let _ = transforms.insert(new_anim, Transform::new(at.x as f32, start_y, 0.0, 1.0, 1.0));
+                    let _ = floating_texts.insert(
+                        new_anim,
+                        FloatingText {
+                            text: text.clone(),
+                            color: *color,
+                            origin: at,
+                            lifetime: FLOATING_TEXT_LIFETIME,
+                        },
+                    );
+                    let _ = delete_conditions.insert(new_anim, DeleteCondition::Timed(FLOATING_TEXT_LIFETIME));
+                }
+                AnimationRequest::SpawnPop(who) => {
+                    let at = match positions.get(*who) {
+                        Some(pos) => *pos,
+                        None => continue,
+                    };
+                    let owns_transform = match transforms.get_mut(*who) {
+                        Some(transform) => {
+                            transform.scale = PointF::new(0.0, 0.0);
+                            false
+                        }
+                        None => {
+                            let _ = transforms.insert(*who, Transform::new(at.x as f32, at.y as f32, 0.0, 0.0, 0.0));
+                            true
+                        }
+                    };
+                    let _ = spawn_pops.insert(
+                        *who,
+                        SpawnPop { elapsed: Duration::ZERO, duration: SPAWN_POP_DURATION, owns_transform },
+                    );
+                }
             }
         }
         anim_builder.requests.clear();
@@ -120,6 +185,99 @@ impl<'a> System<'a> for TileAnimationUpdater {
     }
 }
 
+/// Grows spawning entities to full size, the pop ends early if the entity leaves the world (e.g. picked up)
+pub struct SpawnPopUpdater;
+
+impl<'a> System<'a> for SpawnPopUpdater {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, SpawnPop>,
+        WriteStorage<'a, Transform>,
+        ReadStorage<'a, Position>,
+        Read<'a, DeltaTime>,
+    );
+
+    fn run(&mut self, (entities, mut spawn_pops, mut transforms, positions, dt): Self::SystemData) {
+        let mut finished = vec![];
+        for (e, pop, transform) in (&entities, &mut spawn_pops, &mut transforms).join() {
+            pop.elapsed += dt.0;
+            if pop.is_done() || !positions.contains(e) {
+                finished.push((e, pop.owns_transform));
+                continue;
+            }
+            let scale = pop.scale();
+            transform.scale = PointF::new(scale, scale);
+        }
+        for (e, owns_transform) in finished {
+            spawn_pops.remove(e);
+            if owns_transform {
+                transforms.remove(e);
+            } else if let Some(transform) = transforms.get_mut(e) {
+                transform.scale = PointF::new(1.0, 1.0);
+            }
+        }
+    }
+}
+
+/// Ends slides once the sprite has caught up to the entity's position
+pub struct SlideUpdater;
+
+impl<'a> System<'a> for SlideUpdater {
+    type SystemData = (Entities<'a>, WriteStorage<'a, Slide>, WriteStorage<'a, Transform>, ReadStorage<'a, Position>);
+
+    fn run(&mut self, (entities, mut slides, mut transforms, positions): Self::SystemData) {
+        let mut finished = vec![];
+        for (e, slide, transform) in (&entities, &slides, &transforms).join() {
+            let arrived = positions.get(e).map_or(true, |pos| {
+                (transform.sprite_pos.x - pos.x as f32).abs() < SLIDE_DONE_DISTANCE
+                    && (transform.sprite_pos.y - pos.y as f32).abs() < SLIDE_DONE_DISTANCE
+            });
+            if arrived {
+                finished.push((e, slide.owns_transform));
+            }
+        }
+        for (e, owns_transform) in finished {
+            slides.remove(e);
+            if owns_transform {
+                transforms.remove(e);
+            }
+        }
+    }
+}
+
+/// Moves floating text upwards, fading is handled when drawing based on the time left
+pub struct FloatingTextUpdater;
+
+impl<'a> System<'a> for FloatingTextUpdater {
+    type SystemData = (WriteStorage<'a, Transform>, ReadStorage<'a, FloatingText>, Read<'a, DeltaTime>);
+
+    fn run(&mut self, (mut transforms, floating_texts, dt): Self::SystemData) {
+        for (transform, _) in (&mut transforms, &floating_texts).join() {
+            transform.sprite_pos.y -= FLOATING_TEXT_RISE_SPEED * dt.0.as_secs_f32();
+        }
+    }
+}
+
+/// Runs down aggro indicators and takes them off the monster once faded
+pub struct AggroIndicatorFade;
+
+impl<'a> System<'a> for AggroIndicatorFade {
+    type SystemData = (Entities<'a>, WriteStorage<'a, AggroIndicator>, Read<'a, DeltaTime>);
+
+    fn run(&mut self, (entities, mut indicators, dt): Self::SystemData) {
+        let mut faded = Vec::new();
+        for (e, indicator) in (&entities, &mut indicators).join() {
+            indicator.time_left = indicator.time_left.saturating_sub(dt.0);
+            if indicator.time_left.is_zero() {
+                faded.push(e);
+            }
+        }
+        for e in faded {
+            indicators.remove(e);
+        }
+    }
+}
+
 // NOTE: Since some tile animations live on other entities that contain important component, they
 // will not always have a delte condition and could instead be cleaned up by other means for
 // example the dead tile cleanup which checks HealthStats.
@@ -147,3 +305,23 @@ impl<'a> System<'a> for TileAnimationCleanUpSystem {
         }
     }
 }
+
+/// Lowers the alpha of fading entities and deletes them once they can no longer be seen
+pub struct FadeSystem;
+
+impl<'a> System<'a> for FadeSystem {
+    type SystemData = (Entities<'a>, WriteStorage<'a, FadingOut>, WriteStorage<'a, Renderable>, Read<'a, DeltaTime>);
+
+    fn run(&mut self, (entities, mut fades, mut renderables, dt): Self::SystemData) {
+        for (e, fade, renderable) in (&entities, &mut fades, (&mut renderables).maybe()).join() {
+            fade.remaining = fade.remaining.saturating_sub(dt.0);
+            if fade.remaining.is_zero() {
+                let _ = entities.delete(e);
+                continue;
+            }
+            if let Some(renderable) = renderable {
+                renderable.color_pair.fg.a = fade.alpha();
+            }
+        }
+    }
+}
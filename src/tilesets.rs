@@ -0,0 +1,219 @@
+/* tilesets.rs
+ *   Alternate looks for the terrain tilesets that can be swapped in while playing, ie. a snowy forest for winter.
+ *   A skin only changes how the terrain looks, its glyphs have to be laid out like the tileset it replaces so the
+ *   autotiling still picks the right tiles.
+ * */
+
+use std::{collections::HashMap, fs::File, io::Read, path::Path};
+
+use bracket_lib::terminal::{BTerm, BTermBuilder};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use specs::{World, WorldExt};
+
+use crate::{
+    game_init::set_level_font, map::MapRes, settings::SettingsConfig, ui::message_log::MessageLog, FONT_TERRAIN_FOREST,
+    FONT_TERRAIN_TOWN_FOREST,
+};
+
+/// Where bracket-lib looks for fonts
+const RESOURCE_PATH: &str = "resources";
+/// The fonts added to the builder before any skin, skins are registered at the indices after them
+pub const BASE_FONT_COUNT: usize = 6;
+/// The terrain tilesets loaded at startup by their file name and the font index they are loaded at, in that order.
+/// Skins replace them by file name and maps pick one by the name without the extension
+pub const TERRAIN_TILESETS: [(&str, usize); 2] =
+    [("terrain_forest.png", FONT_TERRAIN_FOREST), ("terrain_town_forest.png", FONT_TERRAIN_TOWN_FOREST)];
+/// The tileset of maps that don't name one, and of maps naming a tileset that isn't loaded
+pub const DEFAULT_TILESET: &str = "terrain_forest";
+
+/// Glyphs are drawn at the size of the console's tiles, which are 8x8 for the world consoles. Fonts are a sheet of
+/// 16x16 glyphs so a skin has to be a 128x128 png, any other size would be stretched or cut off by the console
+pub const GLYPH_SIZE: u32 = 8;
+const GLYPHS_PER_ROW: u32 = 16;
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+/// A set of alternate terrain tilesets, read from the settings
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TerrainSkinConfig {
+    pub name: String,
+    /// The font file in resources/ that replaces each tileset, ie. `"terrain_forest.png": "winter_forest.png"`
+    pub replaces: HashMap<String, String>,
+}
+
+/// A skin with the font indices its files were registered at
+struct TerrainSkin {
+    name: String,
+    /// The font index of a terrain tileset to the index of the font replacing it
+    fonts: HashMap<usize, usize>,
+}
+
+/// Keeps track of which font each terrain tileset is drawn with
+#[derive(Default)]
+pub struct TerrainFonts {
+    skins: Vec<TerrainSkin>,
+    /// No skin uses the usual tilesets
+    active: Option<usize>,
+}
+
+impl TerrainFonts {
+    /// Adds the fonts of every skin to the builder, call it after the base fonts are added. A font that is missing or
+    /// the wrong size is left out so that tileset keeps its usual look, a skin with no usable fonts is left out too
+    pub fn register(
+        mut builder: BTermBuilder,
+        configs: &[TerrainSkinConfig],
+        active: Option<&str>,
+    ) -> (BTermBuilder, Self) {
+        let mut next_font = BASE_FONT_COUNT;
+        let mut skins = Vec::new();
+        for config in configs {
+            let mut fonts = HashMap::new();
+            for (tileset, file) in config.replaces.iter() {
+                let base = match TERRAIN_TILESETS.iter().find(|(name, _)| *name == tileset.as_str()) {
+                    Some((_, base)) => *base,
+                    None => {
+                        warn!("Skin {} replaces {} which is not a terrain tileset", config.name, tileset);
+                        continue;
+                    }
+                };
+                if let Err(reason) = check_font(file) {
+                    warn!("Skin {} can't use {}, {}. The usual {} is used instead", config.name, file, reason, tileset);
+                    continue;
+                }
+                builder = builder.with_font(file, GLYPH_SIZE, GLYPH_SIZE);
+                fonts.insert(base, next_font);
+                next_font += 1;
+            }
+            if fonts.is_empty() {
+                warn!("Skin {} has no fonts that could be used", config.name);
+                continue;
+            }
+            skins.push(TerrainSkin { name: config.name.clone(), fonts });
+        }
+
+        let active = active.and_then(|name| {
+            let found = skins.iter().position(|skin| skin.name == name);
+            if found.is_none() {
+                warn!("There is no skin named {}, using the usual tilesets", name);
+            }
+            found
+        });
+        (builder, Self { skins, active })
+    }
+
+    /// The font to draw the tileset with, the tileset itself unless the active skin replaces it
+    pub fn font_for(&self, tileset: usize) -> usize {
+        self.active.and_then(|skin| self.skins[skin].fonts.get(&tileset)).copied().unwrap_or(tileset)
+    }
+
+    /// Moves on to the next skin, after the last one the usual tilesets are used again
+    pub fn cycle(&mut self) {
+        self.active = match self.active {
+            None if !self.skins.is_empty() => Some(0),
+            Some(skin) if skin + 1 < self.skins.len() => Some(skin + 1),
+            _ => None,
+        };
+    }
+
+    pub fn active_name(&self) -> Option<&str> {
+        self.active.map(|skin| self.skins[skin].name.as_str())
+    }
+}
+
+/// The font index a map's tileset was loaded at, `tileset` is the file name without the extension. A tileset that
+/// was not loaded at startup falls back to the forest one
+pub fn tileset_font(tileset: &str) -> usize {
+    match TERRAIN_TILESETS.iter().find(|(file, _)| file.strip_suffix(".png") == Some(tileset)) {
+        Some((_, font)) => *font,
+        None => {
+            warn!("The map uses the {} tileset which was not loaded, drawing it as {}", tileset, DEFAULT_TILESET);
+            FONT_TERRAIN_FOREST
+        }
+    }
+}
+
+/// Checks the file is a png the size of a sheet of `GLYPH_SIZE` glyphs
+fn check_font(file: &str) -> Result<(), String> {
+    let path = Path::new(RESOURCE_PATH).join(file);
+    let mut header = [0u8; 24];
+    File::open(&path)
+        .and_then(|mut font| font.read_exact(&mut header))
+        .map_err(|e| format!("it could not be read ({})", e))?;
+    if header[..8] != PNG_SIGNATURE {
+        return Err("it is not a png".to_string());
+    }
+    let width = u32::from_be_bytes([header[16], header[17], header[18], header[19]]);
+    let height = u32::from_be_bytes([header[20], header[21], header[22], header[23]]);
+    let expected = GLYPH_SIZE * GLYPHS_PER_ROW;
+    if width != expected || height != expected {
+        return Err(format!(
+            "it is {}x{} but glyphs must be {}px so the sheet must be {}x{}",
+            width, height, GLYPH_SIZE, expected, expected
+        ));
+    }
+    Ok(())
+}
+
+/// Swaps the terrain to the next skin, the choice is saved so it is used the next time the game starts
+pub fn cycle_terrain_skin(ecs: &World, cfg: &mut SettingsConfig, ctx: &mut BTerm) {
+    let name = {
+        let mut terrain_fonts = ecs.write_resource::<TerrainFonts>();
+        if terrain_fonts.skins.is_empty() {
+            ecs.write_resource::<MessageLog>().log("There are no terrain skins to switch to.");
+            return;
+        }
+        terrain_fonts.cycle();
+        terrain_fonts.active_name().map(str::to_string)
+    };
+    info!("Terrain skin is now {}", name.as_deref().unwrap_or("the default"));
+    ecs.write_resource::<MessageLog>().log(format!("Terrain skin: {}", name.as_deref().unwrap_or("default")));
+    cfg.terrain_skin = name;
+    cfg.save();
+
+    // the menus have no level to redraw
+    if !ecs.read_resource::<MapRes>().0.tiles.is_empty() {
+        set_level_font(ecs, ctx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn winter() -> TerrainSkin {
+        TerrainSkin { name: "Winter".to_string(), fonts: HashMap::from([(FONT_TERRAIN_FOREST, BASE_FONT_COUNT)]) }
+    }
+
+    #[test]
+    fn skins_replace_only_their_tilesets_and_cycle_back_to_none() {
+        let mut terrain_fonts = TerrainFonts { skins: vec![winter()], active: None };
+        assert_eq!(terrain_fonts.font_for(FONT_TERRAIN_FOREST), FONT_TERRAIN_FOREST);
+
+        terrain_fonts.cycle();
+        assert_eq!(terrain_fonts.active_name(), Some("Winter"));
+        assert_eq!(terrain_fonts.font_for(FONT_TERRAIN_FOREST), BASE_FONT_COUNT);
+        assert_eq!(terrain_fonts.font_for(FONT_TERRAIN_TOWN_FOREST), FONT_TERRAIN_TOWN_FOREST);
+
+        terrain_fonts.cycle();
+        assert_eq!(terrain_fonts.active_name(), None);
+    }
+
+    #[test]
+    fn maps_pick_loaded_tilesets_and_fall_back_to_the_forest() {
+        assert_eq!(tileset_font("terrain_town_forest"), FONT_TERRAIN_TOWN_FOREST);
+        assert_eq!(tileset_font(DEFAULT_TILESET), FONT_TERRAIN_FOREST);
+        assert_eq!(tileset_font("terrain_desert"), FONT_TERRAIN_FOREST);
+    }
+
+    #[test]
+    fn missing_skins_are_left_out() {
+        assert!(check_font("no_such_skin.png").is_err());
+        let config = TerrainSkinConfig {
+            name: "Missing".to_string(),
+            replaces: HashMap::from([("terrain_forest.png".to_string(), "no_such_skin.png".to_string())]),
+        };
+        let (_, terrain_fonts) = TerrainFonts::register(BTermBuilder::new(), &[config], Some("Missing"));
+        assert!(terrain_fonts.skins.is_empty());
+        assert_eq!(terrain_fonts.font_for(FONT_TERRAIN_FOREST), FONT_TERRAIN_FOREST);
+    }
+}
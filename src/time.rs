@@ -1,14 +1,110 @@
-use std::time::Duration;
+use std::{collections::VecDeque, time::Duration};
 
 use bracket_lib::terminal::BTerm;
 use specs::{World, WorldExt};
 
+use crate::settings::SettingsConfig;
+
 /// The time since the last frame
 #[derive(Default)]
 pub struct DeltaTime(pub Duration);
 
+/// The time since the last frame before the time scale is applied, for anything tied to the player's input
+#[derive(Default)]
+pub struct RealDeltaTime(pub Duration);
+
+/// Time since the game started running, idle animations pick their frame from it
+#[derive(Default)]
+pub struct IdleClock(pub Duration);
+
+/// How many frames are averaged together when delta smoothing is enabled
+const SMOOTHING_SAMPLES: usize = 8;
+
+/// The most recent raw frame times, used to smooth out the delta time
+#[derive(Default)]
+pub struct FrameTimeHistory {
+    samples: VecDeque<Duration>,
+}
+
+impl FrameTimeHistory {
+    pub fn new() -> Self {
+        Self { samples: VecDeque::with_capacity(SMOOTHING_SAMPLES) }
+    }
+
+    /// Adds the sample and returns the rolling average of the stored samples
+    fn push_and_average(&mut self, sample: Duration) -> Duration {
+        if self.samples.len() >= SMOOTHING_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+        self.samples.iter().sum::<Duration>() / self.samples.len() as u32
+    }
+}
+
+const MIN_TIME_SCALE: f32 = 0.125;
+const MAX_TIME_SCALE: f32 = 8.0;
+
+/// Debug multiplier on how fast time passes for animations, tweens and anything else ran off of `DeltaTime`.
+/// Turns are unaffected since they only pass on the player's input
+pub struct TimeScale(f32);
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl TimeScale {
+    pub fn get(&self) -> f32 {
+        self.0
+    }
+
+    pub fn slower(&mut self) {
+        self.0 = (self.0 / 2.0).max(MIN_TIME_SCALE);
+    }
+
+    pub fn faster(&mut self) {
+        self.0 = (self.0 * 2.0).min(MAX_TIME_SCALE);
+    }
+
+    pub fn reset(&mut self) {
+        self.0 = 1.0;
+    }
+}
+
 /// Updates the DeltaTime resource in order to be used across systems which need said info
-pub fn delta_time_update(ecs: &mut World, ctx: &mut BTerm) {
-    let mut delta_timer = ecs.write_resource::<DeltaTime>();
-    delta_timer.0 = Duration::from_secs_f32(ctx.frame_time_ms / 1000f32);
+pub fn delta_time_update(ecs: &mut World, ctx: &mut BTerm, cfg: &SettingsConfig) {
+    let raw_delta = Duration::from_secs_f32(ctx.frame_time_ms / 1000f32);
+    let capped = raw_delta.min(Duration::from_millis(cfg.max_delta_ms));
+
+    let real_delta =
+        if cfg.delta_smoothing { ecs.write_resource::<FrameTimeHistory>().push_and_average(capped) } else { capped };
+    let delta = real_delta.mul_f32(ecs.read_resource::<TimeScale>().get());
+
+    ecs.write_resource::<RealDeltaTime>().0 = real_delta;
+    ecs.write_resource::<DeltaTime>().0 = delta;
+    ecs.write_resource::<IdleClock>().0 += delta;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_scale_stays_within_bounds() {
+        let mut scale = TimeScale::default();
+        scale.slower();
+        scale.slower();
+        assert_eq!(scale.get(), 0.25);
+        for _ in 0..10 {
+            scale.slower();
+        }
+        assert_eq!(scale.get(), MIN_TIME_SCALE);
+        for _ in 0..10 {
+            scale.faster();
+        }
+        assert_eq!(scale.get(), MAX_TIME_SCALE);
+        scale.reset();
+        assert_eq!(scale.get(), 1.0);
+    }
 }
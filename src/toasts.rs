@@ -0,0 +1,131 @@
+/* toasts.rs
+ *   Short notices in the corner of the screen for items the player gains, ie. "+3 Wood". Gaining more of the same item
+ *   soon after adds to the notice already showing instead of stacking up a new one.
+ * */
+
+use std::{collections::VecDeque, time::Duration};
+
+use specs::{Read, System, Write};
+
+use crate::{
+    items::{ItemID, ItemQty},
+    time::DeltaTime,
+};
+
+/// How long a toast stays up after the last time it changed
+const TOAST_LIFETIME: Duration = Duration::from_millis(2500);
+/// Toasts fade out over the end of their lifetime
+const TOAST_FADE: Duration = Duration::from_millis(700);
+/// More of an item gained within this long of its toast changing is added to that toast
+const COALESCE_WINDOW: Duration = Duration::from_millis(1500);
+/// The oldest toast is dropped to make room past this many
+const MAX_TOASTS: usize = 4;
+
+pub struct Toast {
+    item: ItemID,
+    name: String,
+    qty: usize,
+    /// Time since the toast was made or last added to
+    age: Duration,
+}
+
+impl Toast {
+    pub fn text(&self) -> String {
+        format!("+{} {}", self.qty, self.name)
+    }
+
+    /// 1.0 until the toast starts fading then down to 0.0 when it is gone
+    pub fn opacity(&self) -> f32 {
+        let left = TOAST_LIFETIME.saturating_sub(self.age);
+        (left.as_secs_f32() / TOAST_FADE.as_secs_f32()).min(1.0)
+    }
+}
+
+/// The toasts on screen, oldest first
+#[derive(Default)]
+pub struct Toasts {
+    toasts: VecDeque<Toast>,
+}
+
+impl Toasts {
+    /// Shows the player gaining the item, `name` is passed in since callers tend to hold the entity database already
+    pub fn gained(&mut self, item: ItemID, name: &str, qty: ItemQty) {
+        if let Some(toast) = self.toasts.iter_mut().find(|toast| toast.item == item && toast.age < COALESCE_WINDOW) {
+            toast.qty += qty.0;
+            toast.age = Duration::ZERO;
+            return;
+        }
+
+        self.toasts.push_back(Toast { item, name: name.to_string(), qty: qty.0, age: Duration::ZERO });
+        while self.toasts.len() > MAX_TOASTS {
+            self.toasts.pop_front();
+        }
+    }
+
+    fn age(&mut self, dt: Duration) {
+        for toast in self.toasts.iter_mut() {
+            toast.age += dt;
+        }
+        self.toasts.retain(|toast| toast.age < TOAST_LIFETIME);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Toast> {
+        self.toasts.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.toasts.clear();
+    }
+}
+
+/// Fades toasts out and removes the ones that have been up long enough
+pub struct ToastFade;
+
+impl<'a> System<'a> for ToastFade {
+    type SystemData = (Write<'a, Toasts>, Read<'a, DeltaTime>);
+
+    fn run(&mut self, (mut toasts, dt): Self::SystemData) {
+        toasts.age(dt.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WOOD: ItemID = ItemID::new(2);
+    const FISH: ItemID = ItemID::new(3);
+
+    fn texts(toasts: &Toasts) -> Vec<String> {
+        toasts.iter().map(Toast::text).collect()
+    }
+
+    #[test]
+    fn quick_repeats_add_to_the_same_toast() {
+        let mut toasts = Toasts::default();
+        toasts.gained(WOOD, "Wood", ItemQty(1));
+        toasts.age(COALESCE_WINDOW / 2);
+        toasts.gained(WOOD, "Wood", ItemQty(2));
+        assert_eq!(texts(&toasts), vec!["+3 Wood"]);
+
+        toasts.age(COALESCE_WINDOW);
+        toasts.gained(WOOD, "Wood", ItemQty(1));
+        assert_eq!(texts(&toasts), vec!["+3 Wood", "+1 Wood"]);
+    }
+
+    #[test]
+    fn toasts_are_capped_and_fade_away() {
+        let mut toasts = Toasts::default();
+        for qty in 1..=MAX_TOASTS + 1 {
+            toasts.gained(ItemID::new(qty as u32 + 10), "Thing", ItemQty(qty));
+        }
+        assert_eq!(toasts.iter().count(), MAX_TOASTS);
+        assert_eq!(texts(&toasts)[0], "+2 Thing");
+
+        toasts.gained(FISH, "Fish", ItemQty(1));
+        toasts.age(TOAST_LIFETIME - TOAST_FADE / 2);
+        assert!(toasts.iter().all(|toast| toast.opacity() > 0.4 && toast.opacity() < 0.6));
+        toasts.age(TOAST_FADE);
+        assert_eq!(toasts.iter().count(), 0);
+    }
+}
@@ -0,0 +1,244 @@
+/* travel.rs
+ *   Clicking a tile the player has seen walks them there one turn at a time along the shortest path. Travel stops
+ *   when a monster the player wasn't already watching comes into view or something steps into the way, and any
+ *   key the player presses takes back control. The player is left wherever they got to.
+ * */
+
+use std::{collections::VecDeque, time::Duration};
+
+use pathfinding::prelude::astar;
+use serde::{Deserialize, Serialize};
+use specs::{Entity, Join, World, WorldExt};
+
+use crate::{
+    components::{Monster, Name, Position, Viewshed},
+    game_init::PlayerEntity,
+    map::{distance, successors, MapRes},
+    player::{try_move_player, PlayerResponse},
+    time::RealDeltaTime,
+    ui::message_log::MessageLog,
+};
+
+/// Whether clicking a tile travels there and how quickly the steps come, read from the settings when the game starts
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct TravelConfig {
+    pub enabled: bool,
+    /// Time between each step so the walk can be followed
+    pub step_delay_ms: u64,
+}
+
+impl Default for TravelConfig {
+    fn default() -> Self {
+        Self { enabled: true, step_delay_ms: 80 }
+    }
+}
+
+/// Where the player is travelling to
+struct TravelTarget {
+    /// Steps left to take, the last one is the clicked tile
+    path: VecDeque<Position>,
+    /// Monsters in view on the last step, only newcomers stop the travel
+    in_view: Vec<Entity>,
+}
+
+/// The travel the player is on, if any, and how long until the next step
+#[derive(Default)]
+pub struct Travel {
+    cfg: TravelConfig,
+    target: Option<TravelTarget>,
+    wait: Duration,
+}
+
+impl Travel {
+    pub fn new(cfg: TravelConfig) -> Self {
+        Self { cfg, target: None, wait: Duration::ZERO }
+    }
+
+    /// Hands control back to the player
+    pub fn stop(&mut self) {
+        self.target = None;
+    }
+}
+
+/// Sets the player off towards `goal`, only revealed tiles are walked through
+pub fn start_travel(ecs: &World, goal: Position) {
+    if !ecs.read_resource::<Travel>().cfg.enabled {
+        return;
+    }
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let start = match ecs.read_storage::<Position>().get(player_entity) {
+        Some(pos) if *pos != goal => *pos,
+        _ => return,
+    };
+    let path = match find_path(ecs, start, goal) {
+        Some(path) => path,
+        None => {
+            ecs.fetch_mut::<MessageLog>().log("You can't find a way there.");
+            return;
+        }
+    };
+    let in_view = monsters_in_view(ecs);
+    let mut travel = ecs.write_resource::<Travel>();
+    travel.target = Some(TravelTarget { path: path.into_iter().skip(1).collect(), in_view });
+    travel.wait = Duration::ZERO;
+}
+
+/// The shortest walk from `start` to `goal` over tiles the player has seen, both ends included
+fn find_path(ecs: &World, start: Position, goal: Position) -> Option<Vec<Position>> {
+    let map = ecs.read_resource::<MapRes>();
+    if !map.0.is_revealed(goal.to_point()) || map.0.is_blocked(&goal) {
+        return None;
+    }
+    let blocked = |pos: &Position| map.0.is_blocked(pos) || !map.0.is_revealed(pos.to_point());
+    astar(&start, |p| successors(&map.0, p, blocked), |p| distance(p, &goal), |p| *p == goal).map(|(path, _)| path)
+}
+
+/// Monsters standing on a tile the player can currently see
+fn monsters_in_view(ecs: &World) -> Vec<Entity> {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let viewsheds = ecs.read_storage::<Viewshed>();
+    let view = match viewsheds.get(player_entity) {
+        Some(view) => view,
+        None => return Vec::new(),
+    };
+    (&ecs.entities(), &ecs.read_storage::<Monster>(), &ecs.read_storage::<Position>())
+        .join()
+        .filter(|(_, _, pos)| view.tiles.contains(&pos.to_point()))
+        .map(|(e, ..)| e)
+        .collect()
+}
+
+/// Takes the player's next step once the delay is up, stopping the travel when it is done or interrupted
+pub fn travel_turn(ecs: &mut World) -> PlayerResponse {
+    let next = match ecs.read_resource::<Travel>().target.as_ref() {
+        Some(target) => target.path.front().copied(),
+        None => return PlayerResponse::Waiting,
+    };
+    let next = match next {
+        Some(next) => next,
+        None => {
+            ecs.write_resource::<Travel>().stop();
+            return PlayerResponse::Waiting;
+        }
+    };
+    if let Some(reason) = interruption(ecs, next) {
+        ecs.fetch_mut::<MessageLog>().log(reason);
+        ecs.write_resource::<Travel>().stop();
+        return PlayerResponse::Waiting;
+    }
+
+    {
+        let dt = ecs.read_resource::<RealDeltaTime>().0;
+        let mut travel = ecs.write_resource::<Travel>();
+        travel.wait = travel.wait.saturating_sub(dt);
+        if !travel.wait.is_zero() {
+            return PlayerResponse::Waiting;
+        }
+        travel.wait = Duration::from_millis(travel.cfg.step_delay_ms);
+    }
+
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let pos = match ecs.read_storage::<Position>().get(player_entity) {
+        Some(pos) => *pos,
+        None => return PlayerResponse::Waiting,
+    };
+    let response = try_move_player(next.x as i32 - pos.x as i32, next.y as i32 - pos.y as i32, ecs);
+    let mut travel = ecs.write_resource::<Travel>();
+    match response {
+        PlayerResponse::TurnAdvance => {
+            if let Some(target) = travel.target.as_mut() {
+                target.path.pop_front();
+            }
+        }
+        _ => travel.stop(),
+    }
+    response
+}
+
+/// Why the player stops before stepping onto `next`, `None` when the way is clear
+fn interruption(ecs: &World, next: Position) -> Option<String> {
+    let in_view = monsters_in_view(ecs);
+    let newcomer = {
+        let mut travel = ecs.write_resource::<Travel>();
+        let target = travel.target.as_mut()?;
+        let newcomer = in_view.iter().find(|e| !target.in_view.contains(e)).copied();
+        target.in_view = in_view;
+        newcomer
+    };
+    if let Some(monster) = newcomer {
+        let name = ecs.read_storage::<Name>().get(monster).map_or("monster".to_string(), |name| name.0.clone());
+        return Some(format!("You stop travelling, the {} comes into view.", name));
+    }
+
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    // something could have pushed the player off the path, like a current
+    let on_path = ecs.read_storage::<Position>().get(player_entity).is_some_and(|pos| distance(pos, &next) == 1);
+    if !on_path || ecs.read_resource::<MapRes>().0.is_blocked(&next) {
+        return Some("You stop travelling, the way is blocked.".to_string());
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::Builder;
+
+    use super::*;
+    use crate::{
+        components::{Blocking, Interactor, InteractorMode},
+        indexing::{IndexBlockedTiles, IndexReset},
+        test_harness::TestWorld,
+    };
+
+    fn traveller() -> (TestWorld, Entity) {
+        let mut world = TestWorld::new().with_map(8, 8);
+        world.ecs.insert(Travel::new(TravelConfig { enabled: true, step_delay_ms: 0 }));
+        world.ecs.write_resource::<MapRes>().0.revealed_tiles.iter_mut().for_each(|tile| *tile = true);
+        let player = world.player();
+        world.insert(player, Position::new(1, 1));
+        world.insert(player, Interactor::new(InteractorMode::Reactive));
+        world.spawn("Wall", Position::new(2, 1)).with(Blocking {}).build();
+        world.run(IndexReset);
+        world.run(IndexBlockedTiles);
+        (world, player)
+    }
+
+    #[test]
+    fn travel_walks_around_walls_to_the_clicked_tile() {
+        let (mut world, player) = traveller();
+        start_travel(&world.ecs, Position::new(3, 1));
+        let mut turns = 0;
+        while let PlayerResponse::TurnAdvance = travel_turn(&mut world.ecs) {
+            world.run(IndexReset);
+            world.run(IndexBlockedTiles);
+            turns += 1;
+        }
+        assert_eq!(turns, 4);
+        assert_eq!(world.get::<Position>(player), Some(Position::new(3, 1)));
+        assert!(world.ecs.read_resource::<Travel>().target.is_none());
+    }
+
+    #[test]
+    fn travel_stops_when_a_monster_comes_into_view_or_the_way_is_blocked() {
+        let (mut world, player) = traveller();
+        let mut view = Viewshed::new(8);
+        view.tiles.insert(Position::new(6, 6).to_point());
+        world.insert(player, view);
+        start_travel(&world.ecs, Position::new(1, 5));
+        assert!(matches!(travel_turn(&mut world.ecs), PlayerResponse::TurnAdvance));
+
+        world.spawn("Orc", Position::new(6, 6)).with(Monster::new(3, 6)).build();
+        assert!(matches!(travel_turn(&mut world.ecs), PlayerResponse::Waiting));
+        assert!(world.logged("the Orc comes into view"));
+        assert_eq!(world.get::<Position>(player), Some(Position::new(1, 2)));
+
+        start_travel(&world.ecs, Position::new(1, 5));
+        world.spawn("Boulder", Position::new(1, 3)).with(Blocking {}).build();
+        world.run(IndexReset);
+        world.run(IndexBlockedTiles);
+        assert!(matches!(travel_turn(&mut world.ecs), PlayerResponse::Waiting));
+        assert!(world.logged("the way is blocked"));
+        assert!(world.ecs.read_resource::<Travel>().target.is_none());
+    }
+}
@@ -0,0 +1,195 @@
+/* triggers.rs
+ *   Invisible tiles that do something when the player steps onto them, placed by hand in an ldtk level. A trigger
+ *   can spring an ambush, show a message, clear the way through a blocked tile or send the player somewhere else.
+ *   One shot triggers are removed once they fire, the rest fire again each time the player steps back on.
+ * */
+
+use log::error;
+use serde::{Deserialize, Serialize};
+use specs::{Component, Entity, Join, VecStorage, World, WorldExt};
+
+use crate::{
+    components::{ActionPoints, Blocking, Position},
+    data_read::prelude::build_being,
+    game_init::{open_tile_around, PlayerEntity},
+    map::MapRes,
+    player::mark_view_dirty,
+    tile_animation::{AnimationRequest, TileAnimationBuilder},
+    ui::message_log::MessageLog,
+};
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub enum TriggerAction {
+    /// Only shows the trigger's message
+    Message,
+    /// Spawns `count` of the being around the trigger
+    Ambush { being: String, count: usize },
+    /// Clears the world object blocking the tile, ie. a gate or a boulder in the way
+    Open(Position),
+    /// Moves the player to the tile
+    Teleport(Position),
+}
+
+/// Fires its action when the player steps onto the tile it is on
+#[derive(Component, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[storage(VecStorage)]
+pub struct Trigger {
+    pub action: TriggerAction,
+    /// Logged when the trigger fires
+    pub message: Option<String>,
+    /// Removed after firing the first time
+    pub once: bool,
+}
+
+/// Where the player was when triggers were last checked, they only fire when the player moves onto them
+#[derive(Default)]
+pub struct TriggerWatch {
+    last_player_pos: Option<Position>,
+}
+
+impl TriggerWatch {
+    /// Forgets where the player was, ie. after a load so triggers are not fired by where the player already stands
+    pub fn reset(&mut self, pos: Option<Position>) {
+        self.last_player_pos = pos;
+    }
+}
+
+/// Fires the triggers on the player's tile when they have moved onto it since the last check
+pub fn fire_triggers(ecs: &mut World) {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let player_pos = match ecs.read_storage::<Position>().get(player_entity) {
+        Some(pos) => *pos,
+        None => return,
+    };
+    let moved = {
+        let mut watch = ecs.write_resource::<TriggerWatch>();
+        let moved = watch.last_player_pos != Some(player_pos);
+        watch.last_player_pos = Some(player_pos);
+        moved
+    };
+    if !moved {
+        return;
+    }
+
+    let fired: Vec<(Entity, Trigger)> =
+        (&ecs.entities(), &ecs.read_storage::<Trigger>(), &ecs.read_storage::<Position>())
+            .join()
+            .filter(|(_, _, pos)| **pos == player_pos)
+            .map(|(e, trigger, _)| (e, trigger.clone()))
+            .collect();
+    for (trigger_entity, trigger) in fired {
+        if let Some(message) = &trigger.message {
+            ecs.fetch_mut::<MessageLog>().log(message);
+        }
+        match &trigger.action {
+            TriggerAction::Message => {}
+            TriggerAction::Ambush { being, count } => spring_ambush(ecs, being, *count, player_pos),
+            TriggerAction::Open(target) => open_tile(ecs, *target),
+            TriggerAction::Teleport(target) => teleport_player(ecs, player_entity, *target),
+        }
+        if trigger.once {
+            let _ = ecs.delete_entity(trigger_entity);
+        }
+    }
+}
+
+fn spring_ambush(ecs: &mut World, being: &str, count: usize, around: Position) {
+    for _ in 0..count {
+        let pos = match open_tile_around(ecs, around) {
+            Some(pos) => pos,
+            None => return,
+        };
+        if let Err(e) = build_being(being, pos, ecs) {
+            error!("Ambushing {} failed to build: {}", being, e);
+            return;
+        }
+    }
+}
+
+/// Removes the world objects blocking `target`, beings standing there are left alone
+fn open_tile(ecs: &mut World, target: Position) {
+    let blockers: Vec<Entity> = (
+        &ecs.entities(),
+        &ecs.read_storage::<Position>(),
+        &ecs.read_storage::<Blocking>(),
+        !&ecs.read_storage::<ActionPoints>(),
+    )
+        .join()
+        .filter(|(_, pos, _, _)| **pos == target)
+        .map(|(e, _, _, _)| e)
+        .collect();
+    for blocker in blockers {
+        let _ = ecs.delete_entity(blocker);
+    }
+}
+
+/// Moves the player to `target` when there is room for them there
+fn teleport_player(ecs: &mut World, player_entity: Entity, target: Position) {
+    {
+        let map = ecs.read_resource::<MapRes>();
+        if !map.0.in_bounds(target.to_point()) || map.0.is_blocked(&target) {
+            error!("Trigger could not teleport the player to {}, it is blocked or off the map", target);
+            return;
+        }
+    }
+    let _ = ecs.write_storage::<Position>().insert(player_entity, target);
+    ecs.write_resource::<TriggerWatch>().last_player_pos = Some(target);
+    mark_view_dirty(ecs, player_entity);
+    ecs.fetch_mut::<TileAnimationBuilder>().request(AnimationRequest::SpawnPop(player_entity));
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::Builder;
+
+    use super::*;
+    use crate::test_harness::TestWorld;
+
+    fn trigger_at(world: &mut TestWorld, pos: Position, action: TriggerAction, once: bool) -> Entity {
+        let message = Some("Something clicks underfoot.".to_string());
+        world.ecs.create_entity().with(pos).with(Trigger { action, message, once }).build()
+    }
+
+    #[test]
+    fn triggers_fire_when_stepped_onto() {
+        let mut world = TestWorld::new();
+        let player = world.player();
+        world.insert(player, Position::new(1, 1));
+        let once = trigger_at(&mut world, Position::new(2, 1), TriggerAction::Message, true);
+        let repeat = trigger_at(&mut world, Position::new(3, 1), TriggerAction::Teleport(Position::new(2, 1)), false);
+        fire_triggers(&mut world.ecs);
+        assert!(!world.logged("clicks"));
+
+        world.insert(player, Position::new(2, 1));
+        fire_triggers(&mut world.ecs);
+        world.ecs.maintain();
+        assert!(world.logged("clicks"));
+        assert!(!world.ecs.is_alive(once));
+
+        // standing still does not fire it again, stepping back on does
+        world.insert(player, Position::new(3, 1));
+        fire_triggers(&mut world.ecs);
+        assert_eq!(world.get::<Position>(player), Some(Position::new(2, 1)));
+        fire_triggers(&mut world.ecs);
+        world.insert(player, Position::new(3, 1));
+        fire_triggers(&mut world.ecs);
+        assert_eq!(world.get::<Position>(player), Some(Position::new(2, 1)));
+        assert!(world.ecs.is_alive(repeat));
+    }
+
+    #[test]
+    fn opening_clears_blocking_objects_but_not_beings() {
+        let mut world = TestWorld::new();
+        let player = world.player();
+        world.insert(player, Position::new(1, 1));
+        let gate = world.ecs.create_entity().with(Position::new(5, 5)).with(Blocking {}).build();
+        let guard = world.spawn("Guard", Position::new(5, 5)).with(Blocking {}).with(ActionPoints::new(10)).build();
+        trigger_at(&mut world, Position::new(2, 1), TriggerAction::Open(Position::new(5, 5)), true);
+
+        world.insert(player, Position::new(2, 1));
+        fire_triggers(&mut world.ecs);
+        world.ecs.maintain();
+        assert!(!world.ecs.is_alive(gate));
+        assert!(world.ecs.is_alive(guard));
+    }
+}
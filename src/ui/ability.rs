@@ -0,0 +1,34 @@
+use bracket_lib::terminal::{DrawBatch, Point, Rect, TextAlign, RGBA};
+use specs::{World, WorldExt};
+
+use crate::{
+    ability::GroundSlam,
+    colors::{PL_MAX_HP, PL_MENU_TEXT, PL_ORANGE},
+    cooldowns::{turns_left, CooldownKind, Cooldowns},
+    game_init::PlayerEntity,
+    theme::Theme,
+    CL_TEXT,
+};
+
+use super::drawing::AccentBox;
+
+/// Box under the hunger bar showing whether the ground slam is ready or how many turns are left on it
+pub fn draw_ability_cooldown(draw_batch: &mut DrawBatch, ecs: &World) {
+    let slam = ecs.fetch::<GroundSlam>();
+    let theme = ecs.fetch::<Theme>();
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+
+    let status = match turns_left(ecs.read_storage::<Cooldowns>().get(player_entity), CooldownKind::GroundSlam) {
+        0 => format!("#[{}]ready", PL_MAX_HP),
+        turns_left => format!("#[{}]{}/{}", PL_ORANGE, turns_left, slam.cooldown_turns()),
+    };
+
+    draw_batch.target(CL_TEXT);
+    draw_batch.draw_accent_box(Rect::with_size(0, 7, 16, 2), theme.panel());
+    draw_batch.printer(
+        Point::new(1, 8),
+        format!("#[{}]Slam (Q) {}#[]", PL_MENU_TEXT, status),
+        TextAlign::Left,
+        Some(RGBA::new()),
+    );
+}
@@ -0,0 +1,50 @@
+use bracket_lib::terminal::{DrawBatch, Point, TextAlign, RGBA};
+use specs::{World, WorldExt};
+
+use crate::{
+    activity::Activity,
+    camera::get_camera_bounds,
+    components::{FinishedActivity, Position},
+    game_init::PlayerEntity,
+    time::IdleClock,
+    CL_TEXT,
+};
+
+const SPINNER_FRAMES: [char; 4] = ['|', '/', '-', '\\'];
+/// Spinner frames shown each second
+const SPINNER_RATE: f32 = 8.0;
+/// Cells in the progress bar drawn next to the spinner
+const PROGRESS_WIDTH: usize = 5;
+
+/// Shows a spinner just above the player while they are busy with an activity along with how far through the current
+/// step they are. Nothing is drawn once the activity is finished even though it is only removed at the end of the frame
+pub fn draw_activity_indicator(draw_batch: &mut DrawBatch, ecs: &World) {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let activities = ecs.read_storage::<Activity>();
+    let positions = ecs.read_storage::<Position>();
+    let (activity, pos) = match (activities.get(player_entity), positions.get(player_entity)) {
+        (Some(activity), Some(pos)) if !ecs.read_storage::<FinishedActivity>().contains(player_entity) => {
+            (activity, pos)
+        }
+        _ => return,
+    };
+    let bounding_box = get_camera_bounds(ecs);
+    if !bounding_box.point_in_rect(pos.to_point()) {
+        return;
+    }
+
+    let clock = ecs.read_resource::<IdleClock>().0.as_secs_f32();
+    let spinner = SPINNER_FRAMES[(clock * SPINNER_RATE) as usize % SPINNER_FRAMES.len()];
+    let progress = match activity.step_fraction() {
+        Some(fraction) => {
+            let filled = (fraction * PROGRESS_WIDTH as f32).round() as usize;
+            format!(" #[orange]{}#[lightgray]{}", "=".repeat(filled), "-".repeat(PROGRESS_WIDTH - filled))
+        }
+        None => String::new(),
+    };
+
+    // the text console has twice the resolution so the indicator sits just above the player's tile
+    let screen_pos = Point::new((pos.x as i32 - bounding_box.x1) * 2, (pos.y as i32 - bounding_box.y1) * 2 - 1);
+    draw_batch.target(CL_TEXT);
+    draw_batch.printer(screen_pos, format!("#[white]{}{}#[]", spinner, progress), TextAlign::Left, Some(RGBA::new()));
+}
@@ -0,0 +1,90 @@
+use bracket_lib::terminal::{DrawBatch, Point, Rect, TextAlign};
+use specs::{Entity, World, WorldExt};
+
+use crate::{
+    chest::{stored_items, ChestSide, OpenChest},
+    colors::{PL_KEYBIND, PL_MENU_ACCENT_TEXT, PL_MENU_TEXT},
+    components::Container,
+    data_read::ENTITY_DB,
+    game_init::PlayerEntity,
+    shop::BACKPACK_SLOTS,
+    theme::Theme,
+    CL_TEXT,
+};
+
+use super::drawing::AccentBox;
+
+const PANEL_WIDTH: usize = 35;
+
+/// Draws the open chest and the player's backpack side by side, the hovered item is in the focused panel
+pub fn draw_chest(draw_batch: &mut DrawBatch, ecs: &World) {
+    let open = ecs.read_resource::<OpenChest>();
+    let chest = match open.chest {
+        Some(chest) => chest,
+        None => return,
+    };
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let chest_slots = ecs.read_storage::<Container>().get(chest).map_or(0, |container| container.slots);
+
+    draw_batch.target(CL_TEXT);
+    let chest_hover = (open.side == ChestSide::Chest).then_some(open.hovering);
+    let backpack_hover = (open.side == ChestSide::Backpack).then_some(open.hovering);
+    draw_panel(draw_batch, ecs, 3, &open.chest_name, chest, chest_slots, chest_hover);
+    draw_panel(draw_batch, ecs, 41, "Backpack", player_entity, BACKPACK_SLOTS, backpack_hover);
+
+    draw_batch.printer(
+        Point::new(3, 19),
+        format!(
+            "#[{}]Move #[{}]<W/S> #[{}]Switch #[{}]<Tab> #[{}]Transfer #[{}]<Enter> #[{}]Close #[{}]<ESC>#[]",
+            PL_MENU_TEXT, PL_KEYBIND, PL_MENU_TEXT, PL_KEYBIND, PL_MENU_TEXT, PL_KEYBIND, PL_MENU_TEXT, PL_KEYBIND
+        ),
+        TextAlign::Left,
+        None,
+    );
+}
+
+/// Lists what the owner holds along with how many of its slots are used
+fn draw_panel(
+    draw_batch: &mut DrawBatch,
+    ecs: &World,
+    x: usize,
+    title: &str,
+    owner: Entity,
+    slots: usize,
+    hovering: Option<usize>,
+) {
+    let theme = ecs.fetch::<Theme>();
+    let edb = &ENTITY_DB.lock().unwrap();
+    let stored = stored_items(ecs, owner);
+
+    draw_batch.draw_accent_box(Rect::with_size(x - 1, 2, PANEL_WIDTH, stored.len().max(1) + 3), theme.panel());
+    let title_palette = if hovering.is_some() { PL_MENU_ACCENT_TEXT } else { PL_MENU_TEXT };
+    draw_batch.printer(
+        Point::new(x + 1, 3),
+        format!("#[{}]{} #[{}]{}/{}", title_palette, title, PL_MENU_TEXT, stored.len(), slots),
+        TextAlign::Left,
+        Some(theme.panel_bg()),
+    );
+
+    if stored.is_empty() {
+        draw_batch.printer(
+            Point::new(x + 1, 4),
+            format!("#[{}]Empty", PL_MENU_TEXT),
+            TextAlign::Left,
+            Some(theme.panel_bg()),
+        );
+    }
+    for (offset, (_, id, qty)) in stored.iter().enumerate() {
+        let name = edb.items.get_by_id(*id).map_or("???".to_string(), |info| info.colored_name(PL_MENU_ACCENT_TEXT));
+        let qty = if qty.0 > 1 { format!("{}x ", qty.0) } else { String::new() };
+        draw_batch.printer(
+            Point::new(x + 1, 4 + offset),
+            format!("#[{}]{}{}", PL_MENU_ACCENT_TEXT, qty, name),
+            TextAlign::Left,
+            Some(theme.panel_bg()),
+        );
+        if hovering == Some(offset) {
+            draw_batch.print(Point::new(x, 4 + offset), ">");
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use bracket_lib::terminal::{to_char, DrawBatch, Point, Rect, TextAlign};
+use specs::{World, WorldExt};
+
+use crate::{
+    colors::{PL_KEYBIND, PL_MENU_TEXT},
+    console::{DebugConsole, CONSOLE_OUTPUT_LINES},
+    theme::Theme,
+    CL_TEXT, DISPLAY_WIDTH,
+};
+
+use super::drawing::AccentBox;
+
+const CONSOLE_WIDTH: usize = DISPLAY_WIDTH * 2 - 2;
+const CONSOLE_X: usize = 1;
+const CONSOLE_Y: usize = 0;
+
+/// Drops the console down from the top of the screen with the last few results above the prompt
+pub fn draw_console(draw_batch: &mut DrawBatch, ecs: &World) {
+    let theme = ecs.fetch::<Theme>();
+    let console = ecs.fetch::<DebugConsole>();
+
+    draw_batch.target(CL_TEXT);
+    draw_batch
+        .draw_accent_box(Rect::with_size(CONSOLE_X, CONSOLE_Y, CONSOLE_WIDTH, CONSOLE_OUTPUT_LINES + 3), theme.panel());
+    draw_batch.printer(
+        Point::new(CONSOLE_X + 1, CONSOLE_Y),
+        format!("#[white]{}{} Console{}#[]", to_char(180), to_char(2), to_char(195)),
+        TextAlign::Left,
+        Some(theme.panel_outline.into()),
+    );
+    for (y_offset, line) in console.output.iter().enumerate() {
+        draw_batch.printer(Point::new(CONSOLE_X + 2, CONSOLE_Y + 1 + y_offset), line, TextAlign::Left, None);
+    }
+    draw_batch.printer(
+        Point::new(CONSOLE_X + 2, CONSOLE_Y + CONSOLE_OUTPUT_LINES + 2),
+        format!("#[{}]> {}_ #[{}]<ESC>#[]", PL_MENU_TEXT, console.input, PL_KEYBIND),
+        TextAlign::Left,
+        None,
+    );
+}
@@ -0,0 +1,24 @@
+use bracket_lib::terminal::DrawBatch;
+use specs::{World, WorldExt};
+
+use crate::{
+    activity::{Activity, ActivityKind},
+    game_init::PlayerEntity,
+    ui::fishing::MINIGAME_HEIGHT,
+    CL_TEXT,
+};
+
+/// Shows how far along the player's cooking is
+pub fn draw_cooking_progress(draw_batch: &mut DrawBatch, ecs: &World) {
+    let p_entity = ecs.read_resource::<PlayerEntity>();
+    let activities = ecs.read_storage::<Activity>();
+    let activity = match activities.get(p_entity.0) {
+        Some(activity @ Activity { kind: ActivityKind::Cooking { .. }, .. }) => activity,
+        _ => return,
+    };
+
+    let percent = activity.progress.as_millis() * 100 / activity.duration.as_millis().max(1);
+    draw_batch.target(CL_TEXT);
+    // CL_TEXT is double the resolution of the map
+    draw_batch.print_centered(MINIGAME_HEIGHT * 2, format!("Cooking... {}%", percent));
+}
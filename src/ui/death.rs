@@ -0,0 +1,43 @@
+use bracket_lib::terminal::{to_char, ColorPair, DrawBatch, Point, Rect, TextAlign};
+use specs::{World, WorldExt};
+
+use crate::{
+    colors::{MAROON, MIDDLERED, PL_KEYBIND},
+    components::KillCount,
+    death::{DeathCause, ENVIRONMENT_CAUSE},
+    game_init::PlayerEntity,
+    TurnCounter, CL_TEXT, DISPLAY_HEIGHT, DISPLAY_WIDTH,
+};
+
+use super::drawing::AccentBox;
+
+const MENU_WIDTH: usize = DISPLAY_WIDTH * 2 / 4;
+const MENU_X: usize = DISPLAY_WIDTH - MENU_WIDTH / 2;
+const MENU_HEIGHT: usize = 7;
+const MENU_Y: usize = DISPLAY_HEIGHT - MENU_HEIGHT / 2;
+
+/// Shown over the world once the player's health runs out
+pub fn draw_death_screen(draw_batch: &mut DrawBatch, ecs: &World) {
+    let turns = ecs.read_resource::<TurnCounter>().0;
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let kills = ecs.read_storage::<KillCount>().get(player_entity).map_or(0, |kills| kills.0);
+    let cause = ecs.read_resource::<DeathCause>();
+
+    draw_batch.target(CL_TEXT);
+    draw_batch
+        .draw_accent_box(Rect::with_size(MENU_X, MENU_Y, MENU_WIDTH, MENU_HEIGHT), ColorPair::new(MIDDLERED, MAROON));
+    let lines = [
+        format!("#[white]{}{} You Died{}#[]", to_char(180), to_char(2), to_char(195)),
+        format!("#[white]Killed by: {}", cause.0.as_deref().unwrap_or(ENVIRONMENT_CAUSE)),
+        format!("#[white]Turns survived: {}", turns),
+        format!("#[white]Kills: {}", kills),
+        String::new(),
+        format!("#[white]Respawn #[{}]<R>", PL_KEYBIND),
+        format!("#[white]Main Menu #[{}]<Q>", PL_KEYBIND),
+    ];
+    for (idx, line) in lines.iter().enumerate() {
+        // the title sits on the border like the other menus
+        let (x, bg) = if idx == 0 { (MENU_X + 1, MIDDLERED) } else { (MENU_X + 2, MAROON) };
+        draw_batch.printer(Point::new(x, MENU_Y + idx), line, TextAlign::Left, Some(bg.into()));
+    }
+}
@@ -0,0 +1,96 @@
+use bracket_lib::terminal::{BTerm, DrawBatch, Point, Rect, VirtualKeyCode as VKC};
+use specs::{Entity, World, WorldExt};
+
+use crate::{
+    colors::{speaker_palette, PL_KEYBIND, PL_MENU_TEXT},
+    components::{Dialogue, GoalMoverAI, Monster, Name},
+    theme::Theme,
+    CL_TEXT,
+};
+
+use super::{drawing::draw_panel, text::draw_wrapped_text};
+
+/// Widest a line of dialogue is drawn before it wraps onto the next row
+const DIALOGUE_WIDTH: usize = 66;
+/// Rows above the hint, longer lines are cut short
+const DIALOGUE_ROWS: usize = 6;
+
+/// Resource holding the conversation the player is currently in
+#[derive(Default)]
+pub struct ActiveDialogue {
+    speaker: String,
+    lines: Vec<String>,
+    line: usize,
+}
+
+pub enum DialogueAction {
+    Next,
+    Close,
+    Waiting,
+}
+
+/// Beings wanting to go after the player can't be talked to, bumping them attacks like before
+pub fn is_hostile(ecs: &World, entity: Entity) -> bool {
+    let player_name = Name::new("Player");
+    ecs.read_storage::<Monster>().contains(entity)
+        || ecs.read_storage::<GoalMoverAI>().get(entity).is_some_and(|ai| ai.desires.contains(&player_name))
+}
+
+/// Starts a conversation with the being if it has something to say and isn't hostile
+pub fn try_start_dialogue(ecs: &World, being: Entity) -> bool {
+    if is_hostile(ecs, being) {
+        return false;
+    }
+    let dialogues = ecs.read_storage::<Dialogue>();
+    let dialogue = match dialogues.get(being) {
+        Some(dialogue) if !dialogue.lines.is_empty() => dialogue,
+        _ => return false,
+    };
+    let speaker = ecs.read_storage::<Name>().get(being).map_or_else(Name::missing_being_name, |name| name.clone());
+
+    let mut active = ecs.write_resource::<ActiveDialogue>();
+    *active = ActiveDialogue { speaker: speaker.0, lines: dialogue.lines.clone(), line: 0 };
+    true
+}
+
+/// Moves to the next line, returns false once every line has been shown
+pub fn advance_dialogue(ecs: &World) -> bool {
+    let mut active = ecs.write_resource::<ActiveDialogue>();
+    active.line += 1;
+    active.line < active.lines.len()
+}
+
+pub fn p_input_dialogue(ctx: &BTerm) -> DialogueAction {
+    match ctx.key {
+        Some(VKC::Return | VKC::Space | VKC::E) => DialogueAction::Next,
+        Some(VKC::Escape) => DialogueAction::Close,
+        _ => DialogueAction::Waiting,
+    }
+}
+
+/// Draws the current line in a box over the message log
+pub fn draw_dialogue(draw_batch: &mut DrawBatch, ecs: &World) {
+    let theme = ecs.fetch::<Theme>();
+    let active = ecs.read_resource::<ActiveDialogue>();
+    let line = match active.lines.get(active.line) {
+        Some(line) => line,
+        None => return,
+    };
+
+    draw_batch.target(CL_TEXT);
+    let speaker = format!("#[{}]{}", speaker_palette(&active.speaker), active.speaker);
+    let panel =
+        draw_panel(draw_batch, Rect::with_size(-1, 50, 70, 10), Some(&speaker), theme.panel_bg(), theme.panel_border());
+    let pos = Point::new(panel.inner.x1 + 1, panel.inner.y1);
+    draw_wrapped_text(draw_batch, pos, DIALOGUE_WIDTH, Some(DIALOGUE_ROWS), line, PL_MENU_TEXT);
+
+    let next_hint = if active.line + 1 < active.lines.len() { "Next" } else { "Done" };
+    panel.print(
+        draw_batch,
+        panel.rows() - 1,
+        &format!(
+            "#[{}]{} #[{}]<Enter> #[{}]Leave #[{}]<ESC>#[]",
+            PL_MENU_TEXT, next_hint, PL_KEYBIND, PL_MENU_TEXT, PL_KEYBIND
+        ),
+    );
+}
@@ -1,6 +1,8 @@
-use bracket_lib::terminal::{to_cp437, ColorPair, DrawBatch, Rect};
+use bracket_lib::terminal::{to_cp437, ColorPair, DrawBatch, Point, Rect, TextAlign, RGBA};
 
-use crate::colors::white_fg;
+use crate::colors::{white_fg, PL_MENU_ACCENT_TEXT};
+
+use super::text::clip_to_width;
 
 pub trait AccentBox {
     fn draw_accent_box(&mut self, size: Rect, color: ColorPair) -> &mut Self;
@@ -17,3 +19,47 @@ impl AccentBox for DrawBatch {
         self
     }
 }
+
+/// A box drawn by `draw_panel`, its contents are printed through it so they never spill over the border
+pub struct Panel {
+    /// Where the contents go, inside the border with a column of space on either side
+    pub inner: Rect,
+    bg: RGBA,
+}
+
+impl Panel {
+    pub fn rows(&self) -> usize {
+        self.inner.height().max(0) as usize
+    }
+
+    pub fn width(&self) -> usize {
+        self.inner.width().max(0) as usize
+    }
+
+    /// Prints marked up `text` on `row` of the contents. Text past the right edge is cut off and rows past the
+    /// bottom are not drawn at all
+    pub fn print(&self, draw_batch: &mut DrawBatch, row: usize, text: &str) {
+        if row >= self.rows() {
+            return;
+        }
+        let pos = Point::new(self.inner.x1, self.inner.y1 + row as i32);
+        draw_batch.printer(pos, clip_to_width(text, self.width()), TextAlign::Left, Some(self.bg));
+    }
+}
+
+/// Draws a bordered box over `rect` with `title` set into its top border, the colors usually come from the `Theme`.
+/// Returns the panel so the caller can draw its contents inside
+pub fn draw_panel(draw_batch: &mut DrawBatch, rect: Rect, title: Option<&str>, bg: RGBA, border: RGBA) -> Panel {
+    draw_batch.draw_accent_box(rect, ColorPair::new(border, bg));
+    let panel = Panel { inner: Rect::with_exact(rect.x1 + 2, rect.y1 + 1, rect.x2 - 1, rect.y2), bg };
+    if let Some(title) = title {
+        let title = clip_to_width(title, panel.width().saturating_sub(2));
+        draw_batch.printer(
+            Point::new(panel.inner.x1, rect.y1),
+            format!("#[{}] {} #[]", PL_MENU_ACCENT_TEXT, title),
+            TextAlign::Left,
+            Some(bg),
+        );
+    }
+    panel
+}
@@ -0,0 +1,165 @@
+use bracket_lib::terminal::{to_char, DrawBatch, Point, Rect, TextAlign, RGBA};
+use specs::{World, WorldExt};
+
+use crate::{
+    colors::{PL_MENU_ACCENT_TEXT, PL_ORANGE},
+    components::{ActionPoints, BaitActive, EffectiveStats, HealthStats},
+    game_init::PlayerEntity,
+    status::{StatusEffects, StatusKind},
+    theme::Theme,
+    CL_TEXT,
+};
+
+use super::drawing::AccentBox;
+
+/// Cells each effect takes up, an icon followed by its timer or bonus
+const SLOT_WIDTH: usize = 6;
+/// Effects shown before the rest are summed up as `+N`
+const MAX_SLOTS: usize = 5;
+
+/// The order effects are shown in, timed effects first since those are the ones about to change
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum EffectKind {
+    Poison,
+    Regen,
+    Haste,
+    Bait,
+    Attack,
+    Defense,
+    Speed,
+}
+
+impl EffectKind {
+    fn icon(&self) -> char {
+        match self {
+            EffectKind::Poison => '!',
+            EffectKind::Regen => to_char(3),
+            EffectKind::Haste | EffectKind::Speed => to_char(175),
+            EffectKind::Bait => '~',
+            EffectKind::Attack => '/',
+            EffectKind::Defense => to_char(4),
+        }
+    }
+
+    fn color(&self) -> &'static str {
+        match self {
+            EffectKind::Poison => "bright_green",
+            EffectKind::Regen => "red",
+            EffectKind::Haste => PL_ORANGE,
+            EffectKind::Bait => "white",
+            EffectKind::Attack | EffectKind::Defense | EffectKind::Speed => PL_MENU_ACCENT_TEXT,
+        }
+    }
+}
+
+/// Something currently affecting the player and what is shown next to its icon
+struct ActiveEffect {
+    kind: EffectKind,
+    detail: String,
+}
+
+/// Every effect on the player in the order they are drawn. Status timers are counted down each frame from `DeltaTime`
+/// by the status system so reading them here keeps the row current
+fn active_effects(ecs: &World) -> Vec<ActiveEffect> {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let mut effects = Vec::new();
+
+    if let Some(statuses) = ecs.read_storage::<StatusEffects>().get(player_entity) {
+        for status in statuses.effects.iter() {
+            let kind = match status.kind {
+                StatusKind::Poison => EffectKind::Poison,
+                StatusKind::Regen => EffectKind::Regen,
+                StatusKind::Speed => EffectKind::Haste,
+            };
+            effects.push(ActiveEffect { kind, detail: format!("{}s", status.remaining.as_secs_f32().ceil()) });
+        }
+    }
+    if let Some(bait) = ecs.read_storage::<BaitActive>().get(player_entity) {
+        effects.push(ActiveEffect { kind: EffectKind::Bait, detail: bait.potency.to_string() });
+    }
+
+    // Equipment and haste bonuses are whatever the effective stats add on top of the player's own
+    if let Some(stats) = ecs.read_storage::<EffectiveStats>().get(player_entity) {
+        let base_defense = ecs.read_storage::<HealthStats>().get(player_entity).map_or(0, |health| health.defense);
+        let base_speed = ecs.read_storage::<ActionPoints>().get(player_entity).map_or(stats.speed, |ap| ap.speed);
+        let bonuses = [
+            (EffectKind::Attack, stats.attack_bonus),
+            (EffectKind::Defense, stats.defense as i32 - base_defense as i32),
+            (EffectKind::Speed, stats.speed as i32 - base_speed as i32),
+        ];
+        for (kind, bonus) in bonuses.into_iter().filter(|(_, bonus)| *bonus != 0) {
+            effects.push(ActiveEffect { kind, detail: format!("{:+}", bonus) });
+        }
+    }
+
+    // stable so effects of the same kind keep the order they were applied in
+    effects.sort_by_key(|effect| effect.kind);
+    effects
+}
+
+/// Row of small icons under the ability box showing everything affecting the player, effects that don't fit are
+/// counted at the end
+pub fn draw_effect_row(draw_batch: &mut DrawBatch, ecs: &World) {
+    let effects = active_effects(ecs);
+    if effects.is_empty() {
+        return;
+    }
+    let theme = ecs.fetch::<Theme>();
+
+    let shown = effects.len().min(MAX_SLOTS);
+    let overflow = effects.len() - shown;
+    let mut text: String = effects
+        .iter()
+        .take(shown)
+        .map(|effect| {
+            format!(
+                "#[{}]{}#[white]{:<width$}",
+                effect.kind.color(),
+                effect.kind.icon(),
+                effect.detail,
+                width = SLOT_WIDTH - 1
+            )
+        })
+        .collect();
+    if overflow > 0 {
+        text.push_str(&format!("#[lightgray]+{}", overflow));
+    }
+
+    let width = shown * SLOT_WIDTH + if overflow > 0 { 3 } else { 0 } + 1;
+    draw_batch.target(CL_TEXT);
+    draw_batch.draw_accent_box(Rect::with_size(0, 10, width, 2), theme.panel());
+    draw_batch.printer(Point::new(1, 11), format!("{}#[]", text), TextAlign::Left, Some(RGBA::new()));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{status::StatusEffect, test_harness::TestWorld};
+
+    #[test]
+    fn effects_are_listed_in_kind_order_with_their_timers_and_bonuses() {
+        let mut world = TestWorld::new();
+        let player = world.player();
+        let mut statuses = StatusEffects::default();
+        statuses.apply(StatusEffect::new(StatusKind::Regen, 1, Duration::from_millis(4200)));
+        statuses.apply(StatusEffect::new(StatusKind::Poison, 2, Duration::from_secs(3)));
+        world.insert(player, statuses);
+        world.insert(player, BaitActive { potency: 2 });
+        world.insert(player, HealthStats::new(10, 1));
+        world.insert(player, EffectiveStats { attack_bonus: 2, defense: 1, speed: 100 });
+
+        let effects: Vec<(EffectKind, String)> =
+            active_effects(&world.ecs).into_iter().map(|effect| (effect.kind, effect.detail)).collect();
+        assert_eq!(
+            effects,
+            vec![
+                (EffectKind::Poison, "3s".to_string()),
+                (EffectKind::Regen, "5s".to_string()),
+                (EffectKind::Bait, "2".to_string()),
+                (EffectKind::Attack, "+2".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use bracket_lib::terminal::{to_char, ColorPair, DrawBatch, Point, Rect, YELLOW};
+use specs::World;
+
+use crate::{
+    camera::get_camera_bounds,
+    char_c::CH_TARGET,
+    colors::{PL_KEYBIND, PL_MENU_ACCENT_TEXT, PL_MENU_TEXT},
+    components::Position,
+    debug::CLEAR,
+    examine::{examine_tile, visible_window},
+    theme::Theme,
+    CL_EFFECTS2, CL_TEXT,
+};
+
+use super::{drawing::draw_panel, text::draw_wrapped_text};
+
+/// Widest a description is drawn before it wraps onto the next row
+const EXAMINE_WIDTH: usize = 66;
+/// Rows inside the panel, anything past them is cut short
+const EXAMINE_ROWS: usize = 7;
+/// Rows the list of things on the tile can take, the rest are for the focused thing's description
+const LIST_ROWS: usize = 4;
+
+/// Marks the tile being looked at and lists what is on it in a box over the message log, the focused thing is
+/// described under the list
+pub fn draw_examine(draw_batch: &mut DrawBatch, ecs: &World, target: &Position, focus: usize) {
+    let bounding_box = get_camera_bounds(ecs);
+    if bounding_box.point_in_rect(target.to_point()) {
+        draw_batch.target(CL_EFFECTS2);
+        let point = Point::new(target.x as i32 - bounding_box.x1, target.y as i32 - bounding_box.y1);
+        draw_batch.print_color(point, to_char(CH_TARGET), ColorPair { fg: YELLOW.into(), bg: CLEAR });
+    }
+
+    let theme = ecs.fetch::<Theme>();
+    draw_batch.target(CL_TEXT);
+    let panel = draw_panel(draw_batch, Rect::with_size(-1, 50, 70, 10), None, theme.panel_bg(), theme.panel_border());
+
+    let examined = examine_tile(ecs, *target);
+    if examined.is_empty() {
+        panel.print(draw_batch, 0, &format!("#[{}]You haven't seen what is there.#[]", PL_MENU_TEXT));
+    }
+    // the tile may have changed under the cursor since the focus was picked
+    let focus = focus.min(examined.len().saturating_sub(1));
+    let (start, end) = visible_window(examined.len(), focus, LIST_ROWS);
+    let mut row = 0;
+    for (idx, thing) in examined.iter().enumerate().take(end).skip(start) {
+        let (marker, color) = if idx == focus { (">", PL_MENU_ACCENT_TEXT) } else { (" ", PL_MENU_TEXT) };
+        panel.print(draw_batch, row, &format!("#[{}]{} {}#[]", color, marker, thing.name));
+        row += 1;
+    }
+    let hidden = examined.len() - (end - start);
+    if hidden > 0 {
+        panel.print(draw_batch, row, &format!("#[{}]  ...and {} more#[]", PL_MENU_TEXT, hidden));
+        row += 1;
+    }
+
+    if let Some(thing) = examined.get(focus).filter(|thing| thing.description != thing.name) {
+        let pos = Point::new(panel.inner.x1 + 1, panel.inner.y1 + row as i32);
+        draw_wrapped_text(draw_batch, pos, EXAMINE_WIDTH, Some(EXAMINE_ROWS - row), &thing.description, PL_MENU_TEXT);
+    }
+
+    panel.print(
+        draw_batch,
+        panel.rows() - 1,
+        &format!(
+            "#[{}]Look around #[{}]<WASD> #[{}]Next #[{}]<Tab> #[{}]Stop #[{}]<ESC>#[]",
+            PL_MENU_TEXT, PL_KEYBIND, PL_MENU_TEXT, PL_KEYBIND, PL_MENU_TEXT, PL_KEYBIND
+        ),
+    );
+}
@@ -0,0 +1,50 @@
+use bracket_lib::terminal::{ColorPair, DrawBatch, Point};
+use specs::{World, WorldExt};
+
+use crate::{
+    camera::get_camera_bounds,
+    char_c::CH_HIGHLIGHT,
+    colors::to_rgb,
+    components::{Facing, Position},
+    debug::CLEAR,
+    game_init::PlayerEntity,
+    hazards::hazard_for,
+    map::MapRes,
+    theme::Theme,
+    CL_INTERACTABLES,
+};
+
+/// How strongly the faced tile is tinted when there is something on it
+const FACING_ALPHA: f32 = 0.35;
+/// The tint over a faced tile that is off the map or has nothing on it to act upon
+const FACING_DIM_ALPHA: f32 = 0.12;
+
+/// Faintly marks the tile in front of the player so it is clear what `E` will act upon. A hazard that would hurt the
+/// player is marked in the critical health color as a warning before they step in
+pub fn draw_facing_highlight(draw_batch: &mut DrawBatch, ecs: &World) {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let facing_point =
+        match (ecs.read_storage::<Position>().get(player_entity), ecs.read_storage::<Facing>().get(player_entity)) {
+            (Some(pos), Some(facing)) => pos.to_point() + facing.delta(),
+            _ => return,
+        };
+    let bounding_box = get_camera_bounds(ecs);
+    if !bounding_box.point_in_rect(facing_point) {
+        return;
+    }
+
+    let map = ecs.fetch::<MapRes>();
+    let blank = !map.0.in_bounds(facing_point)
+        || map.0.tile_entities[map.0.xy_to_idx(facing_point.x as usize, facing_point.y as usize)].is_empty();
+    let hazardous = map.0.in_bounds(facing_point) && hazard_for(ecs, player_entity, facing_point.into()).is_some();
+    let alpha = if blank && !hazardous { FACING_DIM_ALPHA } else { FACING_ALPHA };
+    let theme = ecs.fetch::<Theme>();
+    let color = to_rgb(if hazardous { theme.critical_hp } else { theme.facing_highlight });
+
+    draw_batch.target(CL_INTERACTABLES);
+    draw_batch.set(
+        Point::new(facing_point.x - bounding_box.x1, facing_point.y - bounding_box.y1),
+        ColorPair { fg: color.to_rgba(alpha), bg: CLEAR },
+        CH_HIGHLIGHT,
+    );
+}
@@ -1,19 +1,78 @@
-use bracket_lib::terminal::{to_char, ColorPair, DrawBatch, Point, WHITE};
+use bracket_lib::{
+    geometry::{line2d, LineAlg},
+    terminal::{to_char, ColorPair, DrawBatch, Point, RGBA, WHITE},
+};
 use specs::{World, WorldExt};
 
 use crate::{
+    activity::{Activity, ActivityKind},
+    camera::get_camera_bounds,
+    catch_release::CatchChoice,
     char_c::{
         CH_BAR_LEFT, CH_BAR_MID, CH_BAR_RIGHT, CH_CURSOR, CH_GOAL_LEFT, CH_GOAL_MID, CH_GOAL_RIGHT, CH_GOAL_SINGLE,
-        CH_LILFISH, CH_REELBAR_LEFT, CH_REELBAR_MID, CH_REELBAR_RIGHT, CH_REELLINE,
+        CH_LILFISH, CH_LINE_FALLING, CH_LINE_FLAT, CH_LINE_RISING, CH_LINE_UPRIGHT, CH_REELBAR_LEFT, CH_REELBAR_MID,
+        CH_REELBAR_RIGHT, CH_REELLINE,
     },
-    components::FishingMinigame,
+    components::{FishStruggle, FishingMinigame, FishingSpot, Position},
     debug::CLEAR,
     fishing::GoalBar,
     game_init::PlayerEntity,
-    CL_EFFECTS, CL_EFFECTS2, DISPLAY_WIDTH,
+    CL_EFFECTS, CL_EFFECTS2, CL_TEXT, DISPLAY_WIDTH,
 };
 
 pub const MINIGAME_HEIGHT: usize = 10;
+const FISHING_LINE_COLOR: RGBA = RGBA { r: 0.85, g: 0.85, b: 0.8, a: 0.8 };
+
+/// Draws the line from the player out to their bobber while they are fishing. It is drawn fresh every frame so it
+/// follows either end and is gone the frame fishing ends, the rows the minigame bars sit on are left alone
+pub fn draw_fishing_line(draw_batch: &mut DrawBatch, ecs: &World) {
+    let p_entity = ecs.read_resource::<PlayerEntity>().0;
+    let fishing = matches!(
+        ecs.read_storage::<Activity>().get(p_entity),
+        Some(Activity { kind: ActivityKind::Fishing { .. }, .. })
+    );
+    let positions = ecs.read_storage::<Position>();
+    let spots = ecs.read_storage::<FishingSpot>();
+    let (rod, bobber) = match (positions.get(p_entity), spots.get(p_entity)) {
+        (Some(rod), Some(spot)) if fishing => (rod.to_point(), spot.pos.to_point()),
+        _ => return,
+    };
+    let minigame_rows = if ecs.read_storage::<FishingMinigame>().contains(p_entity) {
+        (MINIGAME_HEIGHT - 2) as i32..=MINIGAME_HEIGHT as i32
+    } else {
+        1..=0
+    };
+
+    let bounding_box = get_camera_bounds(ecs);
+    draw_batch.target(CL_EFFECTS);
+    for (point, glyph) in line_glyphs(rod, bobber) {
+        let screen_pos = Point::new(point.x - bounding_box.x1, point.y - bounding_box.y1);
+        if bounding_box.point_in_rect(point) && !minigame_rows.contains(&screen_pos.y) {
+            draw_batch.set(screen_pos, ColorPair { fg: FISHING_LINE_COLOR, bg: CLEAR }, glyph);
+        }
+    }
+}
+
+/// The tiles strictly between `from` and `to` with the stroke that follows the line through each of them
+fn line_glyphs(from: Point, to: Point) -> Vec<(Point, u8)> {
+    let points = line2d(LineAlg::Bresenham, from, to);
+    points
+        .windows(3)
+        .map(|window| {
+            let (before, point, after) = (window[0], window[1], window[2]);
+            let (dx, dy) = ((after.x - before.x).signum(), (after.y - before.y).signum());
+            let glyph = match (dx, dy) {
+                (_, 0) => CH_LINE_FLAT,
+                (0, _) => CH_LINE_UPRIGHT,
+                // y grows downwards so a line going right and down falls
+                _ if dx == dy => CH_LINE_FALLING,
+                _ => CH_LINE_RISING,
+            };
+            (point, glyph)
+        })
+        .collect()
+}
+
 pub fn draw_fishing_bar(draw_batch: &mut DrawBatch, ecs: &World) {
     let p_entity = ecs.read_resource::<PlayerEntity>();
     let minigames = ecs.read_storage::<FishingMinigame>();
@@ -79,3 +138,50 @@ fn draw_goal_bar(goal_bar: &GoalBar, left_bar_x: usize, draw_batch: &mut DrawBat
     // right goal bumper
     draw_batch.print(Point::new(left_goal_x + goal_bar.goal_width - 1, MINIGAME_HEIGHT), to_char(CH_GOAL_RIGHT));
 }
+
+/// Shows how many more reels the player's struggling fish needs and how long is left to make them
+pub fn draw_fish_struggle(draw_batch: &mut DrawBatch, ecs: &World) {
+    let p_entity = ecs.read_resource::<PlayerEntity>();
+    let struggles = ecs.read_storage::<FishStruggle>();
+    if let Some(struggle) = struggles.get(p_entity.0) {
+        draw_batch.target(CL_TEXT);
+        // CL_TEXT is double the resolution of the map
+        draw_batch.print_centered(
+            MINIGAME_HEIGHT * 2,
+            format!(
+                "Reel it in! <Space> {}/{} ({:.1}s)",
+                struggle.reels,
+                struggle.reels_needed,
+                struggle.time_left.as_secs_f32()
+            ),
+        );
+    }
+}
+
+/// Asks the player whether to keep the fish they landed and counts down until it is kept for them
+pub fn draw_catch_choice(draw_batch: &mut DrawBatch, ecs: &World) {
+    let p_entity = ecs.read_resource::<PlayerEntity>();
+    let choices = ecs.read_storage::<CatchChoice>();
+    if let Some(choice) = choices.get(p_entity.0) {
+        draw_batch.target(CL_TEXT);
+        draw_batch.print_centered(
+            MINIGAME_HEIGHT * 2,
+            format!("Keep it <K> or let it go <R> ({:.1}s)", choice.time_left.as_secs_f32()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_line_only_fills_the_tiles_between_rod_and_bobber() {
+        assert!(line_glyphs(Point::new(3, 3), Point::new(4, 3)).is_empty());
+        let flat = line_glyphs(Point::new(3, 3), Point::new(6, 3));
+        assert_eq!(flat, vec![(Point::new(4, 3), CH_LINE_FLAT), (Point::new(5, 3), CH_LINE_FLAT)]);
+        let rising = line_glyphs(Point::new(3, 6), Point::new(6, 3));
+        assert!(rising.iter().all(|(_, glyph)| *glyph == CH_LINE_RISING));
+        assert_eq!(rising.len(), 2);
+    }
+}
@@ -0,0 +1,41 @@
+use bracket_lib::terminal::{DrawBatch, Point, Rect, TextAlign, RGBA};
+use specs::{World, WorldExt};
+
+use crate::{colors::PL_MENU_TEXT, components::HealthStats, game_init::PlayerEntity, theme::Theme, CL_TEXT};
+
+use super::drawing::AccentBox;
+
+/// Cells in the health bar
+const BAR_WIDTH: usize = 10;
+
+/// Bar in the top corner showing the player's health, the minimal hud's stand in for the debug info's hp readout
+pub fn draw_health_bar(draw_batch: &mut DrawBatch, ecs: &World) {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let healths = ecs.read_storage::<HealthStats>();
+    let stats = match healths.get(player_entity) {
+        Some(stats) => stats,
+        None => return,
+    };
+    let theme = ecs.fetch::<Theme>();
+
+    let fraction = stats.hp as f32 / stats.max_hp.max(1) as f32;
+    let filled = ((fraction * BAR_WIDTH as f32).ceil() as usize).min(BAR_WIDTH);
+    let label = format!("{}/{}", stats.hp, stats.max_hp);
+
+    draw_batch.target(CL_TEXT);
+    draw_batch.draw_accent_box(Rect::with_size(0, 1, 5 + BAR_WIDTH + label.len(), 2), theme.panel());
+    draw_batch.printer(
+        Point::new(1, 2),
+        format!(
+            "#[{}]HP #[{}]{}#[lightgray]{} #[{}]{}#[]",
+            PL_MENU_TEXT,
+            theme.hp_palette(stats.hp, stats.max_hp),
+            "=".repeat(filled),
+            "-".repeat(BAR_WIDTH - filled),
+            PL_MENU_TEXT,
+            label
+        ),
+        TextAlign::Left,
+        Some(RGBA::new()),
+    );
+}
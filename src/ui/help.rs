@@ -0,0 +1,49 @@
+use bracket_lib::terminal::{to_char, DrawBatch, Point, Rect, TextAlign};
+use specs::{World, WorldExt};
+
+use crate::{
+    colors::{PL_KEYBIND, PL_MENU_TEXT},
+    keybinds::{help_rows, HELP_VISIBLE_ROWS},
+    theme::Theme,
+    CL_TEXT, DISPLAY_WIDTH,
+};
+
+use super::drawing::AccentBox;
+
+const HELP_WIDTH: usize = 60;
+const HELP_X: usize = DISPLAY_WIDTH - HELP_WIDTH / 2;
+const HELP_Y: usize = 4;
+
+/// Lists every control starting from the `scroll`th row, the game is paused while it is open
+pub fn draw_help(draw_batch: &mut DrawBatch, ecs: &World, scroll: usize) {
+    let theme = ecs.fetch::<Theme>();
+    let rows = help_rows();
+
+    draw_batch.target(CL_TEXT);
+    draw_batch.draw_accent_box(Rect::with_size(HELP_X, HELP_Y, HELP_WIDTH, HELP_VISIBLE_ROWS + 3), theme.panel());
+    draw_batch.printer(
+        Point::new(HELP_X + 1, HELP_Y),
+        format!("#[white]{}{} Controls{}#[]", to_char(180), to_char(2), to_char(195)),
+        TextAlign::Left,
+        Some(theme.panel_outline.into()),
+    );
+    for (y_offset, row) in rows.iter().skip(scroll).take(HELP_VISIBLE_ROWS).enumerate() {
+        draw_batch.printer(Point::new(HELP_X + 2, HELP_Y + 2 + y_offset), row, TextAlign::Left, None);
+    }
+
+    let mut footer = format!("#[{}]Close #[{}]<ESC>", PL_MENU_TEXT, PL_KEYBIND);
+    if rows.len() > HELP_VISIBLE_ROWS {
+        let last = (scroll + HELP_VISIBLE_ROWS).min(rows.len());
+        footer = format!(
+            "#[{}]Scroll #[{}]<W/S> {} #[{}]{}-{} of {}#[]",
+            PL_MENU_TEXT,
+            PL_KEYBIND,
+            footer,
+            PL_MENU_TEXT,
+            scroll + 1,
+            last,
+            rows.len()
+        );
+    }
+    draw_batch.printer(Point::new(HELP_X + 2, HELP_Y + HELP_VISIBLE_ROWS + 2), footer, TextAlign::Left, None);
+}
@@ -0,0 +1,55 @@
+use bracket_lib::terminal::{DrawBatch, Point, Rect, TextAlign, RGBA};
+use specs::{Join, World, WorldExt};
+
+use crate::{
+    components::{Hotbar, InBag, Item},
+    data_read::ENTITY_DB,
+    game_init::PlayerEntity,
+    items::ItemID,
+    theme::Theme,
+    CL_TEXT,
+};
+
+use super::drawing::AccentBox;
+
+const SLOT_WIDTH: usize = 14;
+const NAME_WIDTH: usize = 8;
+
+/// Strip above the message log showing what each number key will use, slots the player has run out of are greyed
+pub fn draw_hotbar(draw_batch: &mut DrawBatch, ecs: &World) {
+    let theme = ecs.fetch::<Theme>();
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let hotbars = ecs.read_storage::<Hotbar>();
+    let hotbar = match hotbars.get(player_entity) {
+        Some(hotbar) => hotbar,
+        None => return,
+    };
+    let items = ecs.read_storage::<Item>();
+    let in_bags = ecs.read_storage::<InBag>();
+    let bag_qty = |id: ItemID| -> usize {
+        (&items, &in_bags)
+            .join()
+            .filter(|(item, bag)| bag.owner == player_entity && item.id == id)
+            .map(|(item, _)| item.qty.0)
+            .sum()
+    };
+
+    draw_batch.target(CL_TEXT);
+    draw_batch.draw_accent_box(Rect::with_size(-1, 47, 70, 2), theme.panel());
+
+    let edb = ENTITY_DB.lock().unwrap();
+    for (idx, slot) in hotbar.slots.iter().enumerate() {
+        let text = match slot {
+            Some(id) => {
+                let name = edb.items.get_by_id(*id).map_or("???".to_string(), |info| info.name.clone());
+                let name: String = name.chars().take(NAME_WIDTH).collect();
+                match bag_qty(*id) {
+                    0 => format!("#[lightgray]{} {} x0#[]", idx + 1, name),
+                    qty => format!("#[orange]{}#[] {} #[lightgray]x{}#[]", idx + 1, name, qty),
+                }
+            }
+            None => format!("#[lightgray]{} -#[]", idx + 1),
+        };
+        draw_batch.printer(Point::new(1 + idx * SLOT_WIDTH, 48), text, TextAlign::Left, Some(RGBA::new()));
+    }
+}
@@ -0,0 +1,47 @@
+use bracket_lib::terminal::{DrawBatch, Point, Rect, TextAlign, RGBA};
+use specs::{World, WorldExt};
+
+use crate::{
+    colors::{PL_CRITICAL_HP, PL_MENU_TEXT, PL_ORANGE},
+    game_init::PlayerEntity,
+    hunger::Hunger,
+    theme::Theme,
+    CL_TEXT,
+};
+
+use super::drawing::AccentBox;
+
+/// Cells in the hunger bar
+const BAR_WIDTH: usize = 10;
+/// Below this much of their hunger left the bar turns red to warn the player
+const LOW_HUNGER: f32 = 0.25;
+
+/// Bar under the turn counter showing how full the player is
+pub fn draw_hunger_bar(draw_batch: &mut DrawBatch, ecs: &World) {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let hungers = ecs.read_storage::<Hunger>();
+    let hunger = match hungers.get(player_entity) {
+        Some(hunger) => hunger,
+        None => return,
+    };
+    let theme = ecs.fetch::<Theme>();
+
+    let fraction = hunger.current as f32 / hunger.max.max(1) as f32;
+    let filled = (fraction * BAR_WIDTH as f32).ceil() as usize;
+    let color = if fraction < LOW_HUNGER { PL_CRITICAL_HP } else { PL_ORANGE };
+
+    draw_batch.target(CL_TEXT);
+    draw_batch.draw_accent_box(Rect::with_size(0, 4, 6 + BAR_WIDTH, 2), theme.panel());
+    draw_batch.printer(
+        Point::new(1, 5),
+        format!(
+            "#[{}]Food #[{}]{}#[lightgray]{}#[]",
+            PL_MENU_TEXT,
+            color,
+            "=".repeat(filled),
+            "-".repeat(BAR_WIDTH - filled)
+        ),
+        TextAlign::Left,
+        Some(RGBA::new()),
+    );
+}
@@ -0,0 +1,110 @@
+use bracket_lib::terminal::{to_char, ColorPair, DrawBatch, Point, TextAlign, RGBA};
+use specs::{World, WorldExt};
+
+use crate::{
+    camera::get_camera_bounds,
+    char_c::CH_TARGET,
+    colors::{to_rgb, TEXASROSE},
+    components::{Breakable, Facing, Position, ToolType, Viewshed},
+    debug::CLEAR,
+    game_init::PlayerEntity,
+    map::{distance, MapRes, TileEntity},
+    CL_INTERACTABLES, CL_TEXT,
+};
+
+/// What the hinted tile can be used for. When tiles are the same distance away the earlier action is hinted,
+/// matching the order `E` picks between them on a single tile
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum HintAction {
+    Mine,
+    Chop,
+    Dig,
+    Break,
+    Fish,
+}
+
+impl HintAction {
+    fn label(&self) -> &str {
+        match self {
+            HintAction::Mine => "Mine",
+            HintAction::Chop => "Chop",
+            HintAction::Dig => "Dig",
+            HintAction::Break => "Break",
+            HintAction::Fish => "Fish",
+        }
+    }
+}
+
+/// Marks the nearest breakable or fishable tile within `radius` of the player and what can be done there.
+/// The tile being faced wins ties since it is the one `E` would use. A radius of 0 hides the hint
+pub fn draw_interact_hint(draw_batch: &mut DrawBatch, ecs: &World, radius: usize) {
+    if radius == 0 {
+        return;
+    }
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let positions = ecs.read_storage::<Position>();
+    let viewsheds = ecs.read_storage::<Viewshed>();
+    let facings = ecs.read_storage::<Facing>();
+    let breakables = ecs.read_storage::<Breakable>();
+    let (player_pos, view) = match (positions.get(player_entity), viewsheds.get(player_entity)) {
+        (Some(pos), Some(view)) => (*pos, view),
+        _ => return,
+    };
+    let facing_pos = facings.get(player_entity).map(|facing| player_pos.to_point() + facing.delta());
+
+    let map = ecs.fetch::<MapRes>();
+    let mut nearest = None;
+    for x in player_pos.x.saturating_sub(radius)..=(player_pos.x + radius).min(map.0.width - 1) {
+        for y in player_pos.y.saturating_sub(radius)..=(player_pos.y + radius).min(map.0.height - 1) {
+            let pos = Position::new(x, y);
+            let dist = distance(&player_pos, &pos);
+            if dist == 0 || dist as usize > radius || !view.tiles.contains(&pos.to_point()) {
+                continue;
+            }
+            let action = map.0.tile_entities[map.0.xy_to_idx(x, y)]
+                .iter()
+                .filter_map(|te| match te {
+                    TileEntity::Breakable(entity) => breakables.get(*entity).map(|breakable| match breakable.by {
+                        ToolType::Pickaxe => HintAction::Mine,
+                        ToolType::Axe => HintAction::Chop,
+                        ToolType::Shovel => HintAction::Dig,
+                        ToolType::Hand => HintAction::Break,
+                    }),
+                    TileEntity::Fishable(_) => Some(HintAction::Fish),
+                    _ => None,
+                })
+                .min();
+            if let Some(action) = action {
+                let not_faced = facing_pos != Some(pos.to_point());
+                // reading order last so the same tile is picked every frame
+                let key = (dist, not_faced, action, y, x);
+                if nearest.as_ref().map_or(true, |(best, _)| key < *best) {
+                    nearest = Some((key, pos));
+                }
+            }
+        }
+    }
+
+    let ((.., action, _, _), pos) = match nearest {
+        Some(nearest) => nearest,
+        None => return,
+    };
+    let bounding_box = get_camera_bounds(ecs);
+    if !bounding_box.point_in_rect(pos.to_point()) {
+        return;
+    }
+    let screen_pos = Point::new(pos.x as i32 - bounding_box.x1, pos.y as i32 - bounding_box.y1);
+    let color = to_rgb(TEXASROSE);
+
+    draw_batch.target(CL_INTERACTABLES);
+    draw_batch.set(screen_pos, ColorPair { fg: color.into(), bg: CLEAR }, CH_TARGET);
+
+    // the text console has twice the resolution so the label sits just above the tile
+    draw_batch.target(CL_TEXT);
+    draw_batch.printer(
+        Point::new(screen_pos.x * 2, screen_pos.y * 2 - 1),
+        format!("#[orange]E#[] {}", action.label()),
+        TextAlign::Left,
+        Some(RGBA::new()),
+    );
+}
@@ -1,63 +1,104 @@
-use crate::colors::{PL_MENU_ACCENT_TEXT, PL_MENU_TEXT};
+use crate::colors::{PL_CRITICAL_HP, PL_MENU_ACCENT_TEXT, PL_MENU_TEXT};
 use crate::{
-    colors::{self, to_rgb, Color},
-    components::{Equipped, InBag, Item, Name},
-    config::{InventoryConfig, SortMode},
+    components::{Durability, Equipped, Item, Name},
+    config::InventoryConfig,
+    data_read::ENTITY_DB,
+    inventory::listed_items,
+    shop::backpack_usage,
+    theme::Theme,
 };
-use bracket_lib::terminal::{ColorPair, DrawBatch, TextAlign};
+use bracket_lib::terminal::{DrawBatch, TextAlign};
 use bracket_lib::terminal::{Point, Rect};
-use specs::{Join, LendJoin, ReadStorage, World, WorldExt};
+use specs::{Entity, ReadStorage, World, WorldExt};
 
 use crate::{components::SelectedInventoryItem, game_init::PlayerEntity};
 
-use super::drawing::AccentBox;
-//
-// Usage Definitions these should move into their own file
-pub const INVENTORY_BACKGROUND: Color = colors::PARCHMENT; // (44, 57, 71);
-pub const INVENTORY_OUTLINE: Color = colors::TEXASROSE; //(61, 84, 107);
+use super::{drawing::draw_panel, modal::draw_modal};
 
 pub(crate) fn draw_inventory(draw_batch: &mut DrawBatch, ecs: &World, cfg: &InventoryConfig) {
+    let theme = ecs.fetch::<Theme>();
     let player_entity = ecs.read_resource::<PlayerEntity>();
     let items: ReadStorage<Item> = ecs.read_storage();
-    let inbags: ReadStorage<InBag> = ecs.read_storage();
     let names: ReadStorage<Name> = ecs.read_storage();
     let equipped: ReadStorage<Equipped> = ecs.read_storage();
+    let durabilities: ReadStorage<Durability> = ecs.read_storage();
 
-    let entities = ecs.entities();
-    // important: this must match in src/inventory.rs until a better solution is found to share code
-    let mut data = (&entities, &items, &inbags, &names, (&equipped).maybe())
-        .join()
-        .filter(|(_, _, bag, _, _)| bag.owner == player_entity.0)
-        .collect::<Vec<(specs::Entity, &Item, &InBag, &Name, Option<&Equipped>)>>();
-    data.sort_by(|a, b| match cfg.sort_mode {
-        SortMode::NameABC => a.3.cmp(b.3),
-        SortMode::IDAsc => a.1.id.cmp(&b.1.id),
-        _ => a.1.id.cmp(&b.1.id),
-    });
+    let data: Vec<_> = listed_items(ecs, cfg)
+        .into_iter()
+        .filter_map(|e| Some((e, items.get(e)?, names.get(e)?, equipped.get(e))))
+        .collect();
 
     // TODO: show empty in inventory if inv_count == 0
     let inv_count = data.len();
-    draw_batch.draw_accent_box(
-        Rect::with_size(40, 2, 35, inv_count + 1),
-        ColorPair::new(INVENTORY_OUTLINE, INVENTORY_BACKGROUND),
-    );
+    let no_matches = inv_count == 0 && !cfg.filter.is_empty();
+    let rows = if no_matches { 1 } else { inv_count };
+    let panel =
+        draw_panel(draw_batch, Rect::with_size(40, 2, 35, rows + 1), None, theme.panel_bg(), theme.panel_border());
+    draw_filter(draw_batch, cfg, &theme);
+    draw_capacity(draw_batch, ecs, player_entity.0, &theme);
+    if no_matches {
+        panel.print(draw_batch, 0, &format!("#[{PL_MENU_TEXT}]No matches"));
+    }
 
     let selected_items = ecs.read_storage::<SelectedInventoryItem>();
     let selected_item = selected_items.get(player_entity.0).map(|SelectedInventoryItem { first_item, .. }| first_item);
 
     // Draw each item in inventory
-    for (offset, (item_entity, item, _, Name(name), equipped)) in data.iter().enumerate() {
+    let edb = &ENTITY_DB.lock().unwrap();
+    for (offset, (item_entity, item, Name(name), equipped)) in data.iter().enumerate() {
         let status = if equipped.is_some() { "(E)" } else { "" };
         let qty = if item.qty.0 > 1 { format!("{}x ", item.qty) } else { "".to_string() };
-        draw_batch.printer(
-            Point::new(42, 2 + offset + 1),
-            format!("#[{PL_MENU_TEXT}]{:X}| #[{PL_MENU_ACCENT_TEXT}]{status}{qty}{name}", offset + 1),
-            TextAlign::Left,
-            Some(to_rgb(INVENTORY_BACKGROUND).into()),
+        let name = match edb.items.get_by_id(item.id) {
+            Some(info) => info.colored_name(PL_MENU_ACCENT_TEXT),
+            None => name.clone(),
+        };
+        let wear = match durabilities.get(*item_entity) {
+            Some(durability) => format!(" #[{PL_MENU_TEXT}][{}/{}]", durability.current, durability.max),
+            None => "".to_string(),
+        };
+        panel.print(
+            draw_batch,
+            offset,
+            &format!("#[{PL_MENU_TEXT}]{:X}| #[{PL_MENU_ACCENT_TEXT}]{status}{qty}{name}{wear}", offset + 1),
         );
 
         if Some(item_entity) == selected_item {
-            draw_batch.print(Point::new(41, 3 + offset), ">");
+            draw_batch.print(Point::new(panel.inner.x1 - 1, panel.inner.y1 + offset as i32), ">");
         }
     }
+
+    if let Some((_, modal)) = &cfg.pending_drop {
+        draw_modal(draw_batch, modal, &theme);
+    }
+}
+
+/// Shows how many of the backpack's slots are taken above the inventory, once full nothing new can be picked up
+fn draw_capacity(draw_batch: &mut DrawBatch, ecs: &World, owner: Entity, theme: &Theme) {
+    let (used, slots) = backpack_usage(ecs, owner);
+    let text = if used >= slots {
+        format!("#[{PL_CRITICAL_HP}]Full {used}/{slots}#[]")
+    } else {
+        format!("#[{PL_MENU_TEXT}]Bag {used}/{slots}#[]")
+    };
+    draw_batch.printer(Point::new(74, 1), text, TextAlign::Right, Some(theme.panel_bg()));
+}
+
+/// Shows what the list is being filtered by above the inventory, the cursor shows while it is being typed
+fn draw_filter(draw_batch: &mut DrawBatch, cfg: &InventoryConfig, theme: &Theme) {
+    if !cfg.typing_filter && cfg.filter.is_empty() {
+        draw_batch.printer(
+            Point::new(41, 1),
+            "#[orange]/#[white] to search#[]",
+            TextAlign::Left,
+            Some(theme.panel_bg()),
+        );
+        return;
+    }
+    let cursor = if cfg.typing_filter { "_" } else { "" };
+    draw_batch.printer(
+        Point::new(41, 1),
+        format!("#[white]Search: {}{}#[]", cfg.filter, cursor),
+        TextAlign::Left,
+        Some(theme.panel_bg()),
+    );
 }
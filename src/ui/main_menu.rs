@@ -2,6 +2,7 @@ use bracket_lib::color::GREY4;
 use bracket_lib::terminal::{to_char, to_cp437, ColorPair, DrawBatch, Point, Rect, TextAlign, WHITESMOKE};
 
 use crate::game_init::{InputWorldConfig, NewGameMenuSelection};
+use crate::layers::MAX_LAYERS;
 use crate::saveload::any_save_game_exists;
 use crate::{
     colors::{Color, DARKBLUE, DARKBLUEPURPLE, MIDDLERED, PL_SETTINGS_HIGHLIGHT, PL_SETTINGS_TEXT, SALMON},
@@ -89,7 +90,7 @@ pub fn draw_settings(draw_batch: &mut DrawBatch, cfg: &SettingsConfig) {
     );
 }
 
-pub fn draw_load_game_menu(draw_batch: &mut DrawBatch, save_games: &[String], hovering: usize) {
+pub fn draw_load_game_menu(draw_batch: &mut DrawBatch, save_games: &[String], hovering: usize, error: Option<&String>) {
     // Background
     draw_batch.target(CL_TEXT);
     draw_batch.fill_region(
@@ -123,6 +124,14 @@ pub fn draw_load_game_menu(draw_batch: &mut DrawBatch, save_games: &[String], ho
         };
         draw_batch.print_color(Point::new(MENU_START_X + 1, (MENU_START_Y - menu_height) + 1 + idx), text, colors);
     }
+
+    if let Some(error) = error {
+        draw_batch.print_color(
+            Point::new(MENU_START_X, MENU_START_Y + 1),
+            error,
+            ColorPair::new(MAIN_MENU_TEXT_HL, MAIN_MENU_BG),
+        );
+    }
 }
 
 pub fn draw_new_game_menu(
@@ -153,12 +162,15 @@ pub fn draw_new_game_menu(
 
     let hl = ColorPair::new(MAIN_MENU_TEXT_HL, MAIN_MENU_HL);
     let no = ColorPair::new(MAIN_MENU_ACCENT, MAIN_MENU_BG);
-    let (name, width, height, seed, finish) = match hovering {
-        NewGameMenuSelection::WorldName => (hl, no, no, no, no),
-        NewGameMenuSelection::Width => (no, hl, no, no, no),
-        NewGameMenuSelection::Height => (no, no, hl, no, no),
-        NewGameMenuSelection::Seed => (no, no, no, hl, no),
-        NewGameMenuSelection::Finalize => (no, no, no, no, hl),
+    let (name, width, height, seed, layers, generator, difficulty, finish) = match hovering {
+        NewGameMenuSelection::WorldName => (hl, no, no, no, no, no, no, no),
+        NewGameMenuSelection::Width => (no, hl, no, no, no, no, no, no),
+        NewGameMenuSelection::Height => (no, no, hl, no, no, no, no, no),
+        NewGameMenuSelection::Seed => (no, no, no, hl, no, no, no, no),
+        NewGameMenuSelection::Layers => (no, no, no, no, hl, no, no, no),
+        NewGameMenuSelection::Generator => (no, no, no, no, no, hl, no, no),
+        NewGameMenuSelection::Difficulty => (no, no, no, no, no, no, hl, no),
+        NewGameMenuSelection::Finalize => (no, no, no, no, no, no, no, hl),
     };
 
     draw_batch.print_color(
@@ -188,6 +200,22 @@ pub fn draw_new_game_menu(
         format!("Seed: {}", world_cfg.seed),
         seed,
     );
+    draw_batch.print_color(
+        Point::new(menu_start_x + 1, MENU_START_Y - menu_height + 8),
+        format!("Layers (1-{}): {}", MAX_LAYERS, world_cfg.layers),
+        layers,
+    );
+    draw_batch.print_color(
+        Point::new(menu_start_x + 1, MENU_START_Y - menu_height + 9),
+        format!("Generator: <{}>", world_cfg.generator.name()),
+        generator,
+    );
+    draw_batch.print_color(Point::new(menu_start_x + 1, MENU_START_Y - menu_height + 10), "==Game==", no);
+    draw_batch.print_color(
+        Point::new(menu_start_x + 1, MENU_START_Y - menu_height + 11),
+        format!("Difficulty: <{}>", world_cfg.difficulty.name()),
+        difficulty,
+    );
 
     draw_batch.print_color(Point::new(menu_start_x + 29 / 2, MENU_START_Y), "Finish".to_string(), finish);
 
@@ -2,55 +2,104 @@
 // therefore, drawing the message log is defined in user_interface
 use std::fmt::Display;
 
-use bracket_lib::terminal::{ColorPair, DrawBatch, Point, Rect, TextAlign, RGBA};
+use bracket_lib::terminal::{ColorPair, DrawBatch, Point, Rect};
 use serde::{Deserialize, Serialize};
 use specs::{World, WorldExt};
 
 use crate::{
-    colors::{DARKERBROWN, PL_MENU_ACCENT_TEXT, PL_MENU_TEXT, PL_ORANGE},
+    colors::{speaker_palette, PL_MENU_ACCENT_TEXT, PL_MENU_TEXT, PL_ORANGE},
+    components::Name,
+    theme::Theme,
     TurnCounter, CL_TEXT,
 };
 
-use super::{
-    drawing::AccentBox,
-    inventory::{INVENTORY_BACKGROUND, INVENTORY_OUTLINE},
-};
+use super::drawing::{draw_panel, AccentBox};
+
+/// Messages shown at the bottom of the screen by the minimal hud
+const RECENT_LINES: usize = 2;
 
 pub(crate) fn draw_message_log(draw_batch: &mut DrawBatch, ecs: &World) {
+    draw_log_panel(draw_batch, ecs, Rect::with_size(-1, 50, 70, 10));
+}
+
+/// Just the last couple of messages along the bottom edge, used by the minimal hud
+pub(crate) fn draw_recent_messages(draw_batch: &mut DrawBatch, ecs: &World) {
+    draw_log_panel(draw_batch, ecs, Rect::with_size(-1, 59 - RECENT_LINES as i32, 70, RECENT_LINES + 1));
+}
+
+/// Fills a panel over `rect` with as many of the newest messages as fit
+fn draw_log_panel(draw_batch: &mut DrawBatch, ecs: &World, rect: Rect) {
+    let theme = ecs.fetch::<Theme>();
     let log = ecs.fetch::<MessageLog>();
 
     draw_batch.target(CL_TEXT);
-    draw_batch
-        .draw_accent_box(Rect::with_size(-1, 50, 70, 10), ColorPair::new(INVENTORY_OUTLINE, INVENTORY_BACKGROUND));
+    let panel = draw_panel(draw_batch, rect, None, theme.panel_bg(), theme.panel_border());
 
-    for (y_offset, message) in log.nth_recent(9).enumerate() {
-        draw_batch.printer(Point::new(1, 51 + y_offset), message.colored(), TextAlign::Left, Some(RGBA::new()));
+    for (row, message) in log.nth_recent(panel.rows()).enumerate() {
+        let line = if log.cfg.turn_prefix { message.stamped() } else { message.colored() };
+        panel.print(draw_batch, row, &line);
     }
 }
 
 pub fn draw_turn_counter(draw_batch: &mut DrawBatch, ecs: &World) {
+    let theme = ecs.fetch::<Theme>();
     let turn_counter = ecs.read_resource::<TurnCounter>();
     draw_batch.target(CL_TEXT);
-    draw_batch.draw_accent_box(
-        Rect::with_size(0, 1, 6 + turn_counter.0.to_string().len(), 2),
-        ColorPair::new(INVENTORY_OUTLINE, INVENTORY_BACKGROUND),
-    );
+    draw_batch.draw_accent_box(Rect::with_size(0, 1, 6 + turn_counter.0.to_string().len(), 2), theme.panel());
     draw_batch.print_color(
         Point { x: 1, y: 2 },
         format!("Turn:{}", turn_counter.0),
-        ColorPair { fg: DARKERBROWN.into(), bg: INVENTORY_BACKGROUND.into() },
+        ColorPair { fg: theme.menu_text.into(), bg: theme.panel_background.into() },
     );
 }
 
+/// How much of the log is kept and how it is shown, read from the settings when the game starts
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct MessageLogConfig {
+    /// Most messages kept, the oldest are dropped once there are more
+    pub max_messages: usize,
+    /// Shows the turn each message was logged on in front of it
+    pub turn_prefix: bool,
+}
+
+impl Default for MessageLogConfig {
+    fn default() -> Self {
+        Self { max_messages: 200, turn_prefix: false }
+    }
+}
+
 /// Resource used for logging to the message console on the screen to the player
 #[derive(Clone, Deserialize, Serialize)]
 pub struct MessageLog {
     pub messages: Vec<Message>,
+    /// Comes from the settings rather than the save
+    #[serde(skip)]
+    cfg: MessageLogConfig,
+    /// The turn new messages are stamped with
+    #[serde(skip)]
+    turn: usize,
 }
 
 impl MessageLog {
     pub fn new() -> Self {
-        Self { messages: vec![Message::new("Welcome to the world of rust_rpg!".to_string(), MessageType::Info)] }
+        Self::with_config(MessageLogConfig::default())
+    }
+
+    pub fn with_config(cfg: MessageLogConfig) -> Self {
+        let welcome = Message::new("Welcome to the world of rust_rpg!".to_string(), MessageType::Info, 0);
+        Self { messages: vec![welcome], cfg, turn: 0 }
+    }
+
+    /// Messages logged from now on are stamped with `turn`
+    pub fn set_turn(&mut self, turn: usize) {
+        self.turn = turn;
+    }
+
+    /// Swaps in the messages of a loaded save, keeping the settings and turn of this log
+    pub fn restore(&mut self, saved: &MessageLog) {
+        self.messages = saved.messages.clone();
+        self.trim();
     }
 
     /// Adds info to the log
@@ -63,6 +112,20 @@ impl MessageLog {
         self.add_to_log(contents.to_string(), MessageType::Flavor);
     }
 
+    /// Adds something a being said to the log as flavor, prefixed with who said it
+    pub fn speak(&mut self, speaker: &Name, contents: impl ToString) {
+        let contents = format!("\"{}\"", contents.to_string());
+        if let Some(last_msg) = self.messages.last_mut() {
+            if last_msg.contents.eq(&contents) && last_msg.speaker.as_ref().is_some_and(|s| s.eq(&speaker.0)) {
+                last_msg.repeated += 1;
+                return;
+            }
+        };
+        let mut message = Message::new(contents, MessageType::Flavor, self.turn);
+        message.speaker = Some(speaker.0.clone());
+        self.push(message);
+    }
+
     /// Adds debug info to the log
     pub fn debug(&mut self, contents: impl ToString) {
         self.add_to_log(contents.to_string(), MessageType::Debug);
@@ -82,12 +145,25 @@ impl MessageLog {
     /// increment the `repeated` variable
     fn add_to_log(&mut self, contents: String, msg_type: MessageType) {
         if let Some(last_msg) = self.messages.last_mut() {
-            if last_msg.contents.eq(&contents) && last_msg.kind.eq(&msg_type) {
+            if last_msg.contents.eq(&contents) && last_msg.kind.eq(&msg_type) && last_msg.speaker.is_none() {
                 last_msg.repeated += 1;
                 return;
             }
         };
-        self.messages.push(Message::new(contents, msg_type));
+        self.push(Message::new(contents, msg_type, self.turn));
+    }
+
+    fn push(&mut self, message: Message) {
+        self.messages.push(message);
+        self.trim();
+    }
+
+    /// Drops the oldest messages past the cap, the log is always read from the newest end so nothing shifts
+    fn trim(&mut self) {
+        let max = self.cfg.max_messages.max(1);
+        if self.messages.len() > max {
+            self.messages.drain(..self.messages.len() - max);
+        }
     }
 }
 
@@ -101,12 +177,18 @@ impl Default for MessageLog {
 pub struct Message {
     pub kind: MessageType,
     pub contents: String,
+    /// Who said this message, if anyone
+    #[serde(default)]
+    pub speaker: Option<String>,
     repeated: usize,
+    /// The turn the message was first logged on
+    #[serde(default)]
+    pub turn: usize,
 }
 
 impl Message {
-    fn new(contents: String, message_type: MessageType) -> Self {
-        Self { kind: message_type, contents, repeated: 1 }
+    fn new(contents: String, message_type: MessageType, turn: usize) -> Self {
+        Self { kind: message_type, contents, speaker: None, repeated: 1, turn }
     }
 
     /// Returns a colored output of the message based on type and amt
@@ -121,7 +203,16 @@ impl Message {
         } else {
             "".to_string()
         };
-        format!("#[{}]{}#[]{}", color, &self.contents, suffix_amt)
+        let prefix = match &self.speaker {
+            Some(speaker) => format!("#[{}]{}#[]: ", speaker_palette(speaker), speaker),
+            None => "".to_string(),
+        };
+        format!("{}#[{}]{}#[]{}", prefix, color, &self.contents, suffix_amt)
+    }
+
+    /// The colored message led by the turn it was logged on
+    pub fn stamped(&self) -> String {
+        format!("#[{}]{}#[] {}", PL_MENU_ACCENT_TEXT, self.turn, self.colored())
     }
 }
 
@@ -137,3 +228,23 @@ pub enum MessageType {
     Info,   // game info ie Fishing attempts remaining
     Debug,  // only shown if debug is enabled
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oldest_messages_are_dropped_past_the_cap() {
+        let mut log = MessageLog::with_config(MessageLogConfig { max_messages: 3, turn_prefix: true });
+        for turn in 1..=5 {
+            log.set_turn(turn);
+            log.log(format!("message {}", turn));
+        }
+        // repeats are folded into the newest message instead of taking up room
+        log.log("message 5");
+
+        let recent: Vec<String> = log.nth_recent(9).map(|message| message.contents.clone()).collect();
+        assert_eq!(recent, vec!["message 5", "message 4", "message 3"]);
+        assert_eq!(log.nth_recent(1).next().map(|message| message.turn), Some(5));
+    }
+}
@@ -4,7 +4,7 @@ use specs::{World, WorldExt};
 
 use crate::{
     config::ConfigMaster,
-    draw_sprites::draw_flashes,
+    draw_sprites::{draw_flashes, draw_floating_text},
     fov::draw_unseen_area,
     frame_animation::print_frame_animations,
     inventory::{check_inventory_selection, SelectionStatus},
@@ -13,20 +13,60 @@ use crate::{
 };
 
 use self::{
-    fishing::draw_fishing_bar,
+    ability::draw_ability_cooldown,
+    activity::draw_activity_indicator,
+    chest::draw_chest,
+    console::draw_console,
+    cooking::draw_cooking_progress,
+    death::draw_death_screen,
+    dialogue::draw_dialogue,
+    effects::draw_effect_row,
+    examine::draw_examine,
+    facing::draw_facing_highlight,
+    fishing::{draw_catch_choice, draw_fish_struggle, draw_fishing_bar, draw_fishing_line},
+    health::draw_health_bar,
+    help::draw_help,
+    hotbar::draw_hotbar,
+    hunger::draw_hunger_bar,
+    interact_hint::draw_interact_hint,
     inventory::draw_inventory,
     main_menu::{draw_main_menu, draw_new_game_menu, draw_settings},
-    message_log::{draw_message_log, draw_turn_counter},
-    save_menu::draw_save_menu,
+    message_log::{draw_message_log, draw_recent_messages, draw_turn_counter},
+    save_menu::{draw_pause_overlay, draw_save_menu},
+    shop::draw_shop,
+    targeting::{draw_enemy_targeting, draw_facing_forecast, draw_targeting, draw_telegraphs},
+    toasts::draw_toasts,
+    tooltip::draw_tooltip,
     use_menu::draw_use_menu,
 };
 
+mod ability;
+mod activity;
+mod chest;
+mod console;
+mod cooking;
+mod death;
+pub(crate) mod dialogue;
 mod drawing;
+mod effects;
+mod examine;
+mod facing;
 mod fishing;
+mod health;
+mod help;
+mod hotbar;
+mod hunger;
+mod interact_hint;
 mod inventory;
 mod main_menu;
 pub(crate) mod message_log;
+pub(crate) mod modal;
 mod save_menu;
+mod shop;
+mod targeting;
+mod text;
+mod toasts;
+pub(crate) mod tooltip;
 mod use_menu;
 
 pub fn draw_ui(ecs: &World, appstate: &AppState, cfg: &ConfigMaster) {
@@ -36,11 +76,73 @@ pub fn draw_ui(ecs: &World, appstate: &AppState, cfg: &ConfigMaster) {
     draw_batch.target(CL_TEXT).cls();
 
     match appstate {
+        AppState::InGame if cfg.general.minimal_hud => {
+            draw_health_bar(&mut draw_batch, ecs);
+            draw_recent_messages(&mut draw_batch, ecs);
+            draw_flashes(ecs, &mut draw_batch);
+            draw_telegraphs(&mut draw_batch, ecs);
+            draw_unseen_area(&mut draw_batch, ecs);
+            draw_floating_text(ecs, &mut draw_batch);
+            draw_facing_highlight(&mut draw_batch, ecs);
+            draw_interact_hint(&mut draw_batch, ecs, cfg.general.interact_hint_radius);
+            draw_toasts(&mut draw_batch, ecs);
+        }
         AppState::InGame => {
             draw_message_log(&mut draw_batch, ecs);
+            draw_hotbar(&mut draw_batch, ecs);
             draw_turn_counter(&mut draw_batch, ecs);
+            draw_hunger_bar(&mut draw_batch, ecs);
+            draw_ability_cooldown(&mut draw_batch, ecs);
+            draw_effect_row(&mut draw_batch, ecs);
             draw_flashes(ecs, &mut draw_batch);
+            draw_telegraphs(&mut draw_batch, ecs);
+            draw_unseen_area(&mut draw_batch, ecs);
+            draw_floating_text(ecs, &mut draw_batch);
+            draw_facing_highlight(&mut draw_batch, ecs);
+            draw_facing_forecast(&mut draw_batch, ecs);
+            draw_interact_hint(&mut draw_batch, ecs, cfg.general.interact_hint_radius);
+            draw_toasts(&mut draw_batch, ecs);
+            draw_tooltip(&mut draw_batch, ecs);
+        }
+        AppState::Paused => {
+            draw_message_log(&mut draw_batch, ecs);
+            draw_turn_counter(&mut draw_batch, ecs);
+            draw_unseen_area(&mut draw_batch, ecs);
+            draw_floating_text(ecs, &mut draw_batch);
+            draw_pause_overlay(&mut draw_batch);
+        }
+        AppState::Help { scroll } => {
+            draw_message_log(&mut draw_batch, ecs);
+            draw_turn_counter(&mut draw_batch, ecs);
+            draw_unseen_area(&mut draw_batch, ecs);
+            draw_help(&mut draw_batch, ecs, *scroll);
+        }
+        AppState::DebugConsole => {
+            draw_message_log(&mut draw_batch, ecs);
+            draw_turn_counter(&mut draw_batch, ecs);
+            draw_unseen_area(&mut draw_batch, ecs);
+            draw_console(&mut draw_batch, ecs);
+        }
+        AppState::GameOver => {
+            draw_message_log(&mut draw_batch, ecs);
+            draw_unseen_area(&mut draw_batch, ecs);
+            draw_death_screen(&mut draw_batch, ecs);
+        }
+        AppState::InDialogue => {
+            draw_turn_counter(&mut draw_batch, ecs);
             draw_unseen_area(&mut draw_batch, ecs);
+            draw_floating_text(ecs, &mut draw_batch);
+            draw_dialogue(&mut draw_batch, ecs);
+        }
+        AppState::InShop => {
+            draw_shop(&mut draw_batch, ecs);
+            draw_message_log(&mut draw_batch, ecs);
+            draw_turn_counter(&mut draw_batch, ecs);
+        }
+        AppState::InChest => {
+            draw_chest(&mut draw_batch, ecs);
+            draw_message_log(&mut draw_batch, ecs);
+            draw_turn_counter(&mut draw_batch, ecs);
         }
         AppState::PlayerInInventory => {
             draw_inventory(&mut draw_batch, ecs, &cfg.inventory);
@@ -49,12 +151,35 @@ pub fn draw_ui(ecs: &World, appstate: &AppState, cfg: &ConfigMaster) {
             }
 
             draw_message_log(&mut draw_batch, ecs);
+            draw_hotbar(&mut draw_batch, ecs);
             draw_turn_counter(&mut draw_batch, ecs);
         }
+        AppState::TargetingTile { item, target } => {
+            draw_message_log(&mut draw_batch, ecs);
+            draw_turn_counter(&mut draw_batch, ecs);
+            draw_unseen_area(&mut draw_batch, ecs);
+            draw_targeting(&mut draw_batch, ecs, *item, target);
+        }
+        AppState::TargetingEnemy { target } => {
+            draw_message_log(&mut draw_batch, ecs);
+            draw_turn_counter(&mut draw_batch, ecs);
+            draw_unseen_area(&mut draw_batch, ecs);
+            draw_enemy_targeting(&mut draw_batch, ecs, *target);
+        }
+        AppState::Looking { target, focus } => {
+            draw_turn_counter(&mut draw_batch, ecs);
+            draw_unseen_area(&mut draw_batch, ecs);
+            draw_examine(&mut draw_batch, ecs, target, *focus);
+        }
         AppState::ActivityBound { .. } => {
             draw_turn_counter(&mut draw_batch, ecs);
             draw_unseen_area(&mut draw_batch, ecs);
+            draw_fishing_line(&mut draw_batch, ecs);
             draw_fishing_bar(&mut draw_batch, ecs);
+            draw_fish_struggle(&mut draw_batch, ecs);
+            draw_catch_choice(&mut draw_batch, ecs);
+            draw_cooking_progress(&mut draw_batch, ecs);
+            draw_activity_indicator(&mut draw_batch, ecs);
         }
         AppState::MainMenu { hovering } => {
             draw_main_menu(&mut draw_batch, hovering);
@@ -68,7 +193,7 @@ pub fn draw_ui(ecs: &World, appstate: &AppState, cfg: &ConfigMaster) {
         }
         AppState::LoadGameMenu { hovering } => {
             let save_games = ecs.read_resource::<GameSaves>();
-            draw_load_game_menu(&mut draw_batch, &save_games.saves, *hovering);
+            draw_load_game_menu(&mut draw_batch, &save_games.saves, *hovering, save_games.load_error.as_ref());
         }
         AppState::NewGameInitialize { hovering, world_cfg, form_errors } => {
             draw_new_game_menu(&mut draw_batch, hovering, world_cfg, form_errors);
@@ -0,0 +1,102 @@
+use bracket_lib::terminal::{DrawBatch, Point, Rect, TextAlign, VirtualKeyCode as VKC};
+
+use crate::{
+    colors::{PL_KEYBIND, PL_MENU_ACCENT_TEXT, PL_MENU_TEXT},
+    theme::Theme,
+    CL_TEXT, DISPLAY_HEIGHT, DISPLAY_WIDTH,
+};
+
+use super::drawing::AccentBox;
+
+/// What the player did with a modal this frame
+#[derive(PartialEq, Eq, Debug)]
+pub enum ModalResult {
+    /// The index of the option they picked
+    Selected(usize),
+    Cancelled,
+    Waiting,
+}
+
+/// A box in the middle of the screen asking the player to pick one of a few options. Input is polled every frame so
+/// whatever asked the question keeps the modal around until it gives back something other than `Waiting`
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Modal {
+    title: String,
+    options: Vec<String>,
+    selected: usize,
+}
+
+impl Modal {
+    pub fn new(title: impl ToString, options: &[&str]) -> Self {
+        Self {
+            title: title.to_string(),
+            options: options.iter().map(|option| option.to_string()).collect(),
+            selected: 0,
+        }
+    }
+
+    /// Asks a yes or no question, yes is the option at index 0
+    pub fn yes_no(title: impl ToString) -> Self {
+        Self::new(title, &["Yes", "No"])
+    }
+
+    /// Moves the highlight with up and down, wrapping around at either end. Enter picks the highlighted option and
+    /// escape backs out without picking anything
+    pub fn handle_key(&mut self, key: Option<VKC>) -> ModalResult {
+        let count = self.options.len();
+        match key {
+            Some(VKC::Up | VKC::W) if count > 0 => self.selected = (self.selected + count - 1) % count,
+            Some(VKC::Down | VKC::S) if count > 0 => self.selected = (self.selected + 1) % count,
+            Some(VKC::Return | VKC::E) if count > 0 => return ModalResult::Selected(self.selected),
+            Some(VKC::Escape) => return ModalResult::Cancelled,
+            _ => {}
+        }
+        ModalResult::Waiting
+    }
+}
+
+/// Draws the modal centered on the text console with the highlighted option marked
+pub fn draw_modal(draw_batch: &mut DrawBatch, modal: &Modal, theme: &Theme) {
+    let longest_option = modal.options.iter().map(|option| option.len() + 2).max().unwrap_or(0);
+    let width = modal.title.len().max(longest_option) + 3;
+    let height = modal.options.len() + 3;
+    let x = (DISPLAY_WIDTH * 2).saturating_sub(width) / 2;
+    let y = (DISPLAY_HEIGHT * 2).saturating_sub(height) / 2;
+
+    draw_batch.target(CL_TEXT);
+    draw_batch.draw_accent_box(Rect::with_size(x, y, width, height), theme.panel());
+    draw_batch.printer(
+        Point::new(x + 2, y + 1),
+        format!("#[{}]{}#[]", PL_MENU_TEXT, modal.title),
+        TextAlign::Left,
+        Some(theme.panel_bg()),
+    );
+    for (idx, option) in modal.options.iter().enumerate() {
+        let line = if idx == modal.selected {
+            format!("#[{}]> #[{}]{}#[]", PL_KEYBIND, PL_MENU_ACCENT_TEXT, option)
+        } else {
+            format!("  #[{}]{}#[]", PL_MENU_TEXT, option)
+        };
+        draw_batch.printer(Point::new(x + 2, y + 3 + idx), line, TextAlign::Left, Some(theme.panel_bg()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modals_wrap_around_and_report_the_pick() {
+        let mut modal = Modal::new("Go where?", &["North", "South", "West"]);
+        assert_eq!(modal.handle_key(None), ModalResult::Waiting);
+        assert_eq!(modal.handle_key(Some(VKC::Up)), ModalResult::Waiting);
+        assert_eq!(modal.handle_key(Some(VKC::Return)), ModalResult::Selected(2));
+        assert_eq!(modal.handle_key(Some(VKC::Down)), ModalResult::Waiting);
+        assert_eq!(modal.handle_key(Some(VKC::Return)), ModalResult::Selected(0));
+        assert_eq!(modal.handle_key(Some(VKC::Escape)), ModalResult::Cancelled);
+
+        let mut empty = Modal::new("Nothing here", &[]);
+        assert_eq!(empty.handle_key(Some(VKC::Down)), ModalResult::Waiting);
+        assert_eq!(empty.handle_key(Some(VKC::Return)), ModalResult::Waiting);
+    }
+}
@@ -47,3 +47,27 @@ pub fn draw_save_menu(draw_batch: &mut DrawBatch) {
         Some(SALMON.into()),
     );
 }
+
+/// Draws a small overlay letting the player know the game is paused, the world is still drawn underneath
+pub fn draw_pause_overlay(draw_batch: &mut DrawBatch) {
+    draw_batch.target(CL_TEXT);
+    draw_batch.draw_accent_box(Rect::with_size(MENU_X, 2, MENU_WIDTH, 3), ColorPair::new(MIDDLERED, SALMON));
+    draw_batch.printer(
+        Point::new(MENU_X + 1, 2),
+        format!("#[white]{}{} PAUSED{}#[]", to_char(180), to_char(2), to_char(195)),
+        TextAlign::Left,
+        Some(MIDDLERED.into()),
+    );
+    draw_batch.printer(
+        Point::new(MENU_X + 2, 3),
+        format!("#[white]Resume #[{}]<ESC>", PL_KEYBIND),
+        TextAlign::Left,
+        Some(SALMON.into()),
+    );
+    draw_batch.printer(
+        Point::new(MENU_X + 2, 4),
+        format!("#[white]Menu #[{}]<Q>", PL_KEYBIND),
+        TextAlign::Left,
+        Some(SALMON.into()),
+    );
+}
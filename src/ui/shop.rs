@@ -0,0 +1,81 @@
+use bracket_lib::terminal::{DrawBatch, Point, Rect, TextAlign};
+use specs::{World, WorldExt};
+
+use crate::{
+    colors::{PL_KEYBIND, PL_MENU_ACCENT_TEXT, PL_MENU_TEXT},
+    components::Currency,
+    data_read::ENTITY_DB,
+    game_init::PlayerEntity,
+    shop::{sellable_items, ActiveShop, ShopMode},
+    theme::Theme,
+    CL_TEXT,
+};
+
+use super::drawing::AccentBox;
+
+/// Draws the open shop's listing for the current mode along with the player's gold
+pub fn draw_shop(draw_batch: &mut DrawBatch, ecs: &World) {
+    let theme = ecs.fetch::<Theme>();
+    let active = ecs.read_resource::<ActiveShop>();
+    let player_entity = ecs.read_resource::<PlayerEntity>();
+    let gold = ecs.read_storage::<Currency>().get(player_entity.0).map_or(0, |purse| purse.gold);
+    let edb = &ENTITY_DB.lock().unwrap();
+
+    // (name, qty, price) of every listed item
+    let listing: Vec<(String, Option<usize>, u32)> = match active.mode {
+        ShopMode::Buy => active
+            .stock
+            .iter()
+            .filter_map(|id| edb.items.get_by_id(*id))
+            .map(|info| (info.colored_name(PL_MENU_ACCENT_TEXT), None, info.buy_price()))
+            .collect(),
+        ShopMode::Sell => sellable_items(ecs)
+            .iter()
+            .filter_map(|(_, id, qty)| edb.items.get_by_id(*id).map(|info| (info, qty)))
+            .map(|(info, qty)| (info.colored_name(PL_MENU_ACCENT_TEXT), Some(qty.0), info.sell_price()))
+            .collect(),
+    };
+
+    draw_batch.target(CL_TEXT);
+    draw_batch.draw_accent_box(Rect::with_size(40, 2, 35, listing.len() + 4), theme.panel());
+    let (buy_palette, sell_palette) = match active.mode {
+        ShopMode::Buy => (PL_MENU_ACCENT_TEXT, PL_MENU_TEXT),
+        ShopMode::Sell => (PL_MENU_TEXT, PL_MENU_ACCENT_TEXT),
+    };
+    draw_batch.printer(
+        Point::new(42, 3),
+        format!(
+            "#[{}]{}'s shop #[{}]Buy #[{}]Sell #[{}]<TAB>",
+            PL_MENU_TEXT, active.keeper_name, buy_palette, sell_palette, PL_KEYBIND
+        ),
+        TextAlign::Left,
+        Some(theme.panel_bg()),
+    );
+    draw_batch.printer(
+        Point::new(42, 4),
+        format!("#[{}]Gold: {}", PL_MENU_TEXT, gold),
+        TextAlign::Left,
+        Some(theme.panel_bg()),
+    );
+
+    if listing.is_empty() {
+        draw_batch.printer(
+            Point::new(42, 5),
+            format!("#[{}]Nothing to {}", PL_MENU_TEXT, if active.mode == ShopMode::Buy { "buy" } else { "sell" }),
+            TextAlign::Left,
+            Some(theme.panel_bg()),
+        );
+    }
+    for (offset, (name, qty, price)) in listing.iter().enumerate() {
+        let qty = qty.filter(|qty| *qty > 1).map_or(String::new(), |qty| format!("{}x ", qty));
+        draw_batch.printer(
+            Point::new(42, 5 + offset),
+            format!("#[{}]{}g #[{}]{}{}", PL_MENU_TEXT, price, PL_MENU_ACCENT_TEXT, qty, name),
+            TextAlign::Left,
+            Some(theme.panel_bg()),
+        );
+        if offset == active.hovering {
+            draw_batch.print(Point::new(41, 5 + offset), ">");
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use bracket_lib::terminal::{to_char, ColorPair, DrawBatch, Point, TextAlign, GREY50, RGBA, YELLOW};
+use specs::{Entity, Join, World, WorldExt};
+
+use crate::{
+    auto_attack::AutoAttack,
+    camera::get_camera_bounds,
+    char_c::{CH_ARC, CH_HIGHLIGHT, CH_TARGET},
+    colors::{to_rgb, MIDDLERED},
+    combat::forecast_damage,
+    components::{Facing, HealthStats, Monster, Name, Position},
+    debug::CLEAR,
+    game_init::PlayerEntity,
+    special_attacks::SpecialAttack,
+    targeting::{affected_tiles, item_throw_arc, visible_enemies, ArcPoint},
+    CL_EFFECTS2, CL_INTERACTABLES, CL_TEXT,
+};
+
+/// Tints every on screen tile in `tiles`, shows what an action will reach before it is confirmed
+pub fn highlight_tiles(draw_batch: &mut DrawBatch, ecs: &World, tiles: &[Position], color: RGBA) {
+    let bounding_box = get_camera_bounds(ecs);
+    draw_batch.target(CL_INTERACTABLES);
+    for tile in tiles.iter().filter(|tile| bounding_box.point_in_rect(tile.to_point())) {
+        let point = Point::new(tile.x as i32 - bounding_box.x1, tile.y as i32 - bounding_box.y1);
+        draw_batch.set(point, ColorPair { fg: color, bg: CLEAR }, CH_HIGHLIGHT);
+    }
+}
+
+/// Warns of every special attack a monster is winding up by highlighting the tiles it will hit
+pub fn draw_telegraphs(draw_batch: &mut DrawBatch, ecs: &World) {
+    let specials = ecs.read_storage::<SpecialAttack>();
+    let tiles: Vec<Position> = specials.join().flat_map(|special| special.telegraphed().iter().copied()).collect();
+    highlight_tiles(draw_batch, ecs, &tiles, RGBA::from_u8(220, 40, 40, 130));
+}
+
+/// Draws the flight of a thrown item, the part past how far it can be thrown is greyed out
+fn draw_throw_arc(draw_batch: &mut DrawBatch, ecs: &World, arc: &[ArcPoint]) {
+    let bounding_box = get_camera_bounds(ecs);
+    draw_batch.target(CL_INTERACTABLES);
+    for arc_point in arc.iter().filter(|arc_point| bounding_box.point_in_rect(arc_point.point)) {
+        let fg = if arc_point.in_range { YELLOW.into() } else { GREY50.into() };
+        let point = Point::new(arc_point.point.x - bounding_box.x1, arc_point.point.y - bounding_box.y1);
+        draw_batch.set(point, ColorPair { fg, bg: CLEAR }, CH_ARC);
+    }
+}
+
+/// Marks the tile the item will be used on along with everything else it would affect and tells the player how to
+/// pick one. Thrown items also show the arc they will fly along
+pub fn draw_targeting(draw_batch: &mut DrawBatch, ecs: &World, item: Entity, target: &Position) {
+    highlight_tiles(draw_batch, ecs, &affected_tiles(ecs, item, *target), RGBA::from_u8(255, 165, 0, 110));
+    draw_throw_arc(draw_batch, ecs, &item_throw_arc(ecs, item, *target));
+
+    let bounding_box = get_camera_bounds(ecs);
+    if bounding_box.point_in_rect(target.to_point()) {
+        draw_batch.target(CL_EFFECTS2);
+        let point = Point::new(target.x as i32 - bounding_box.x1, target.y as i32 - bounding_box.y1);
+        draw_batch.print_color(point, to_char(CH_TARGET), ColorPair { fg: YELLOW.into(), bg: CLEAR });
+    }
+
+    draw_batch.target(CL_TEXT);
+    draw_batch.printer(
+        Point::new(1, 49),
+        "#[white]Use on which tile? #[orange]E#[white] to use, #[lightgray]<Esc>#[white] to go back#[]",
+        TextAlign::Left,
+        Some(RGBA::new()),
+    );
+}
+
+const ENEMY_TARGETING_KEYS: &str =
+    "#[orange]Tab#[white] next, #[orange]Enter#[white] to attack, #[lightgray]<Esc>#[white] to go back#[]";
+
+/// Marks every enemy in sight with the one being aimed at standing out, and tells the player how to switch between them
+pub fn draw_enemy_targeting(draw_batch: &mut DrawBatch, ecs: &World, target: Entity) {
+    let enemies = visible_enemies(ecs);
+    let bounding_box = get_camera_bounds(ecs);
+    let positions = ecs.read_storage::<Position>();
+
+    draw_batch.target(CL_EFFECTS2);
+    for enemy in enemies.iter() {
+        let pos = match positions.get(*enemy) {
+            Some(pos) if bounding_box.point_in_rect(pos.to_point()) => pos,
+            _ => continue,
+        };
+        let fg = if *enemy == target { to_rgb(MIDDLERED).into() } else { GREY50.into() };
+        let point = Point::new(pos.x as i32 - bounding_box.x1, pos.y as i32 - bounding_box.y1);
+        draw_batch.print_color(point, to_char(CH_TARGET), ColorPair { fg, bg: CLEAR });
+    }
+
+    draw_damage_forecast(draw_batch, ecs, target);
+
+    let name = ecs.read_storage::<Name>().get(target).map_or_else(|| Name::missing_being_name().0, |n| n.0.clone());
+    let number = enemies.iter().position(|e| *e == target).map_or(0, |idx| idx + 1);
+    draw_batch.target(CL_TEXT);
+    draw_batch.printer(
+        Point::new(1, 49),
+        format!("#[white]Attack {} ({}/{})? {}", name, number, enemies.len(), ENEMY_TARGETING_KEYS),
+        TextAlign::Left,
+        Some(RGBA::new()),
+    );
+}
+
+/// Forecasts a bump attack on the monster the player is facing, auto attack swings on its own so it goes without
+pub fn draw_facing_forecast(draw_batch: &mut DrawBatch, ecs: &World) {
+    if ecs.read_resource::<AutoAttack>().enabled() {
+        return;
+    }
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let positions = ecs.read_storage::<Position>();
+    let facing_point = match (positions.get(player_entity), ecs.read_storage::<Facing>().get(player_entity)) {
+        (Some(pos), Some(facing)) => pos.to_point() + facing.delta(),
+        _ => return,
+    };
+    let faced = (&ecs.entities(), &positions, &ecs.read_storage::<Monster>())
+        .join()
+        .find(|(_, pos, _)| pos.to_point() == facing_point)
+        .map(|(monster, ..)| monster);
+    if let Some(monster) = faced {
+        draw_damage_forecast(draw_batch, ecs, monster);
+    }
+}
+
+/// Shows what the player's attack would do to `target` just above it, worked out by the same formula the attack uses
+/// so it follows any change to their equipment or stats
+fn draw_damage_forecast(draw_batch: &mut DrawBatch, ecs: &World, target: Entity) {
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let damage = match forecast_damage(ecs, player_entity, target) {
+        Some(damage) => damage,
+        None => return,
+    };
+    let pos = match ecs.read_storage::<Position>().get(target) {
+        Some(pos) => *pos,
+        None => return,
+    };
+    let bounding_box = get_camera_bounds(ecs);
+    if !bounding_box.point_in_rect(pos.to_point()) {
+        return;
+    }
+    let hp = ecs.read_storage::<HealthStats>().get(target).map_or(0, |stats| stats.hp);
+    let text = match damage {
+        0 => "#[lightgray]no dmg#[]".to_string(),
+        lethal if lethal >= hp => format!("#[red]{} dmg, kills#[]", lethal),
+        damage => format!("#[orange]{}#[white] dmg#[]", damage),
+    };
+
+    // the text console has twice the resolution so the forecast sits just above the tile
+    draw_batch.target(CL_TEXT);
+    draw_batch.printer(
+        Point::new((pos.x as i32 - bounding_box.x1) * 2, (pos.y as i32 - bounding_box.y1) * 2 - 1),
+        text,
+        TextAlign::Left,
+        Some(RGBA::new()),
+    );
+}
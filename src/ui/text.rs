@@ -0,0 +1,168 @@
+use bracket_lib::terminal::{DrawBatch, Point, TextAlign};
+
+/// Ends a row that was cut short by the height limit
+const ELLIPSIS: &str = "...";
+
+/// A piece of marked up text, either a `#[palette]` tag or a character that gets printed
+enum Token<'a> {
+    Markup(&'a str),
+    Char(char),
+}
+
+fn tokens(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("#[") {
+            if let Some(end) = rest.find(']') {
+                tokens.push(Token::Markup(&rest[2..end]));
+                rest = &rest[end + 1..];
+                continue;
+            }
+        }
+        tokens.push(Token::Char(c));
+        rest = &rest[c.len_utf8()..];
+    }
+    tokens
+}
+
+/// Length of a line when printed, ignoring any `#[palette]` markup
+pub fn printed_len(line: &str) -> usize {
+    tokens(line).iter().filter(|token| matches!(token, Token::Char(_))).count()
+}
+
+/// Cuts the line short once `width` characters have been printed, markup in the text is kept
+pub fn clip_to_width(line: &str, width: usize) -> String {
+    let mut clipped = String::new();
+    let mut len = 0;
+    for token in tokens(line) {
+        match token {
+            Token::Markup(tag) => clipped.push_str(&format!("#[{}]", tag)),
+            Token::Char(_) if len == width => break,
+            Token::Char(c) => {
+                clipped.push(c);
+                len += 1;
+            }
+        }
+    }
+    clipped
+}
+
+/// Draws the text word wrapped to `width` starting at `pos` in `palette` color, markup in the text is kept.
+/// Rows past `max_lines` are clipped with the last shown row ending in an ellipsis. Returns how many rows were drawn
+pub fn draw_wrapped_text(
+    draw_batch: &mut DrawBatch,
+    pos: Point,
+    width: usize,
+    max_lines: Option<usize>,
+    text: &str,
+    palette: &str,
+) -> usize {
+    let mut rows = wrap_text(text, width);
+    if let Some(max_lines) = max_lines {
+        clip_rows(&mut rows, max_lines, width);
+    }
+    for (y_offset, row) in rows.iter().enumerate() {
+        draw_batch.printer(
+            Point::new(pos.x, pos.y + y_offset as i32),
+            format!("#[{}]{}#[]", palette, row),
+            TextAlign::Left,
+            None,
+        );
+    }
+    rows.len()
+}
+
+/// Splits the text into rows no wider than `width` when printed, breaking on spaces where possible and hard
+/// splitting words that could never fit. A color still open at the end of a row is opened again on the next
+pub fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut rows = Vec::new();
+    let mut row = String::new();
+    let mut row_len = 0;
+    let mut palette = "";
+
+    for word in text.split_whitespace() {
+        if row_len > 0 && row_len + 1 + printed_len(word) > width {
+            rows.push(std::mem::replace(&mut row, reopen(palette)));
+            row_len = 0;
+        }
+        if row_len > 0 {
+            row.push(' ');
+            row_len += 1;
+        }
+        for token in tokens(word) {
+            match token {
+                Token::Markup(tag) => {
+                    palette = tag;
+                    row.push_str(&format!("#[{}]", tag));
+                }
+                Token::Char(c) => {
+                    if row_len == width {
+                        rows.push(std::mem::replace(&mut row, reopen(palette)));
+                        row_len = 0;
+                    }
+                    row.push(c);
+                    row_len += 1;
+                }
+            }
+        }
+    }
+    if row_len > 0 {
+        rows.push(row);
+    }
+    rows
+}
+
+fn reopen(palette: &str) -> String {
+    if palette.is_empty() {
+        String::new()
+    } else {
+        format!("#[{}]", palette)
+    }
+}
+
+/// Keeps the first `max_lines` rows, the last kept row is shortened to make room for an ellipsis
+fn clip_rows(rows: &mut Vec<String>, max_lines: usize, width: usize) {
+    if rows.len() <= max_lines {
+        return;
+    }
+    rows.truncate(max_lines);
+    if let Some(last) = rows.last_mut() {
+        let clipped = clip_to_width(last, width.saturating_sub(ELLIPSIS.len()));
+        *last = format!("{}{}", clipped.trim_end(), ELLIPSIS);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_on_words_and_splits_long_ones() {
+        assert_eq!(wrap_text("the quick brown fox", 10), vec!["the quick", "brown fox"]);
+        assert_eq!(wrap_text("a abcdefghij", 4), vec!["a", "abcd", "efgh", "ij"]);
+        assert!(wrap_text("   ", 5).is_empty());
+    }
+
+    #[test]
+    fn markup_is_not_counted_and_carries_over() {
+        let rows = wrap_text("#[orange]bright orange#[] text", 7);
+        assert_eq!(rows, vec!["#[orange]bright", "#[orange]orange#[]", "text"]);
+        assert_eq!(printed_len(&rows[1]), 6);
+    }
+
+    #[test]
+    fn clipping_keeps_the_markup() {
+        assert_eq!(clip_to_width("#[orange]bright#[] orange", 4), "#[orange]brig");
+        assert_eq!(clip_to_width("#[orange]bright#[] orange", 8), "#[orange]bright#[] o");
+        assert_eq!(clip_to_width("short", 10), "short");
+    }
+
+    #[test]
+    fn clipped_rows_end_in_an_ellipsis() {
+        let mut rows = wrap_text("one two three four", 9);
+        clip_rows(&mut rows, 1, 9);
+        assert_eq!(rows, vec!["one tw..."]);
+    }
+}
@@ -0,0 +1,23 @@
+use bracket_lib::terminal::{ColorPair, DrawBatch, Point, RGBA};
+use specs::{World, WorldExt};
+
+use crate::{colors::to_rgb, theme::Theme, toasts::Toasts, CL_TEXT, DISPLAY_WIDTH};
+
+/// Stacks the toasts down the top right corner, the newest at the bottom
+pub fn draw_toasts(draw_batch: &mut DrawBatch, ecs: &World) {
+    let theme = ecs.fetch::<Theme>();
+    let toasts = ecs.fetch::<Toasts>();
+
+    draw_batch.target(CL_TEXT);
+    for (y_offset, toast) in toasts.iter().enumerate() {
+        let mut fg: RGBA = to_rgb(theme.menu_accent_text).into();
+        let mut bg = theme.panel_bg();
+        fg.a = toast.opacity();
+        bg.a = toast.opacity();
+        draw_batch.print_color_right(
+            Point::new(DISPLAY_WIDTH * 2 - 1, 2 + y_offset),
+            toast.text(),
+            ColorPair::new(fg, bg),
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use bracket_lib::terminal::{DrawBatch, Point, Rect, TextAlign};
+use specs::{World, WorldExt};
+
+use crate::{
+    being::BeingID,
+    camera::HoveredTile,
+    colors::{PL_MENU_ACCENT_TEXT, PL_MENU_TEXT},
+    components::{Durability, Item, Name, Position, Viewshed},
+    data_read::ENTITY_DB,
+    game_init::PlayerEntity,
+    map::{MapRes, TileEntity},
+    theme::Theme,
+    TurnCounter, CL_TEXT, DISPLAY_HEIGHT, DISPLAY_WIDTH,
+};
+
+use super::{drawing::AccentBox, text::printed_len};
+
+/// Widest a line in the tooltip can be before it is cut off, keeps long examine text from covering the screen
+const TOOLTIP_MAX_LINE: usize = 30;
+
+/// Resource holding what the mouse is hovering over in the world. The contents are only rebuilt when the
+/// hovered tile changes or a turn passes so the tooltip stays steady while the mouse sits still
+#[derive(Default)]
+pub struct Tooltip {
+    /// Mouse position in CL_WORLD console coordinates
+    mouse_pos: (i32, i32),
+    hovered: Option<Position>,
+    turn: usize,
+    lines: Vec<String>,
+}
+
+/// Updates the tooltip with whatever is on the hovered tile, `mouse_pos` should be relative to CL_WORLD
+pub fn update_tooltip(ecs: &World, mouse_pos: (i32, i32)) {
+    let map = ecs.read_resource::<MapRes>();
+    let turn = ecs.read_resource::<TurnCounter>().0;
+    let mut tooltip = ecs.write_resource::<Tooltip>();
+
+    let hovered = ecs.read_resource::<HoveredTile>().0.filter(|pos| map.0.is_revealed(pos.to_point()));
+
+    tooltip.mouse_pos = mouse_pos;
+    if tooltip.hovered == hovered && tooltip.turn == turn {
+        return;
+    }
+    tooltip.hovered = hovered;
+    tooltip.turn = turn;
+    tooltip.lines.clear();
+
+    let pos = match hovered {
+        Some(pos) => pos,
+        None => return,
+    };
+
+    let names = ecs.read_storage::<Name>();
+    let items = ecs.read_storage::<Item>();
+    let durabilities = ecs.read_storage::<Durability>();
+    let beings = ecs.read_storage::<BeingID>();
+    let viewsheds = ecs.read_storage::<Viewshed>();
+    let player_e = ecs.read_resource::<PlayerEntity>();
+    let in_view = viewsheds.get(player_e.0).map_or(true, |view| view.tiles.contains(&pos.to_point()));
+    let edb = &ENTITY_DB.lock().unwrap();
+
+    let idx = match map.0.checked_xy_to_idx(pos.x, pos.y) {
+        Some(idx) => idx,
+        None => return,
+    };
+    tooltip.lines.push(format!("#[{}]{}#[]", PL_MENU_TEXT, map.0.tiles[idx].name));
+    for tile_entity in map.0.tile_entities[idx].iter() {
+        let entity = match tile_entity {
+            TileEntity::Fishable(_) => continue, // the tile name already says it is water
+            TileEntity::Breakable(e) | TileEntity::Item(e) | TileEntity::Blocking(e) => *e,
+        };
+        // Beings can only be seen when the tile is in view, the rest of the tile is remembered
+        if beings.contains(entity) && !in_view {
+            continue;
+        }
+        let name = match names.get(entity) {
+            Some(name) => name,
+            None => continue,
+        };
+        let item_info = items.get(entity).and_then(|item| edb.items.get_by_id(item.id));
+        let line = match item_info {
+            Some(info) => format!("{}#[]", info.colored_name(PL_MENU_TEXT)),
+            None => format!("#[{}]{}#[]", PL_MENU_TEXT, name),
+        };
+        if tooltip.lines.contains(&line) {
+            continue;
+        }
+        tooltip.lines.push(line);
+
+        if let Some(info) = item_info {
+            let examine: String = info.examine_text.chars().take(TOOLTIP_MAX_LINE).collect();
+            tooltip.lines.push(format!("#[{}] {}#[]", PL_MENU_ACCENT_TEXT, examine));
+        }
+        if let Some(durability) = durabilities.get(entity) {
+            tooltip
+                .lines
+                .push(format!("#[{}] Durability {}/{}#[]", PL_MENU_ACCENT_TEXT, durability.current, durability.max));
+        }
+    }
+}
+
+/// Draws the hover tooltip next to the cursor, it is moved to stay fully on screen
+pub fn draw_tooltip(draw_batch: &mut DrawBatch, ecs: &World) {
+    let theme = ecs.fetch::<Theme>();
+    let tooltip = ecs.read_resource::<Tooltip>();
+    if tooltip.lines.is_empty() {
+        return;
+    }
+
+    // Markup is not drawn so it is not counted in the width
+    let width = tooltip.lines.iter().map(|line| printed_len(line)).max().unwrap_or(0) as i32 + 2;
+    let height = tooltip.lines.len() as i32 + 1;
+    let max_x = (DISPLAY_WIDTH * 2) as i32 - width - 1;
+    let max_y = (DISPLAY_HEIGHT * 2) as i32 - height - 1;
+
+    // CL_TEXT is double the size of CL_WORLD so the mouse position is scaled, then placed down-right of the cursor
+    let x = (tooltip.mouse_pos.0 * 2 + 2).clamp(0, max_x.max(0));
+    let y = (tooltip.mouse_pos.1 * 2 + 2).clamp(0, max_y.max(0));
+
+    draw_batch.target(CL_TEXT);
+    draw_batch.draw_accent_box(Rect::with_size(x, y, width, height), theme.panel());
+    for (offset, line) in tooltip.lines.iter().enumerate() {
+        draw_batch.printer(Point::new(x + 1, y + 1 + offset as i32), line, TextAlign::Left, None);
+    }
+}
@@ -1,23 +1,23 @@
-use bracket_lib::terminal::{ColorPair, DrawBatch, Point, Rect, TextAlign};
+use bracket_lib::terminal::{DrawBatch, Point, Rect, TextAlign};
 use specs::{World, WorldExt};
 
 use crate::{
-    colors::to_rgb,
-    components::{Consumable, Equipable, SelectedInventoryItem},
+    components::{Consumable, Equipable, SelectedInventoryItem, TargetedUse},
     game_init::PlayerEntity,
+    theme::Theme,
 };
 
-use super::{
-    drawing::AccentBox,
-    inventory::{INVENTORY_BACKGROUND, INVENTORY_OUTLINE},
-};
+use super::drawing::AccentBox;
 
 const BASE_ACTIONS: [&str; 4] =
     ["#[orange]U#[]se with", "#[orange]E#[]xamine", "#[orange]D#[]rop", "#[lightgray]<Esc>#[]"];
 const EQUIP_ACTION: &str = "#[]E#[orange]q#[]uip";
 const CONSUME_ACTION: &str = "#[orange]C#[]onsume";
+const TARGET_ACTION: &str = "#[orange]T#[]arget";
+const HOTBAR_ACTION: &str = "#[orange]1-5#[] Slot";
 
 pub fn draw_use_menu(draw_batch: &mut DrawBatch, ecs: &World) {
+    let theme = ecs.fetch::<Theme>();
     let selected_items = ecs.read_storage::<SelectedInventoryItem>();
     let player_entity = ecs.read_resource::<PlayerEntity>();
     let mut use_menu_actions = BASE_ACTIONS.to_vec();
@@ -28,17 +28,23 @@ pub fn draw_use_menu(draw_batch: &mut DrawBatch, ecs: &World) {
         use_menu_actions.insert(3, CONSUME_ACTION);
     }
 
+    let target_uses = ecs.read_storage::<TargetedUse>();
+    if target_uses.get(selected_inv.first_item).is_some() {
+        use_menu_actions.insert(3, TARGET_ACTION);
+    }
+
     let equipables = ecs.read_storage::<Equipable>();
     if equipables.get(selected_inv.first_item).is_some() {
         use_menu_actions.insert(3, EQUIP_ACTION);
     }
 
-    draw_batch.draw_accent_box(
-        Rect::with_size(28, 6, 10, use_menu_actions.len() + 1),
-        ColorPair::new(INVENTORY_OUTLINE, INVENTORY_BACKGROUND),
-    );
+    if consumables.contains(selected_inv.first_item) || equipables.contains(selected_inv.first_item) {
+        use_menu_actions.insert(use_menu_actions.len() - 1, HOTBAR_ACTION);
+    }
+
+    draw_batch.draw_accent_box(Rect::with_size(28, 6, 10, use_menu_actions.len() + 1), theme.panel());
 
     for (idx, action) in use_menu_actions.iter().enumerate() {
-        draw_batch.printer(Point::new(29, 7 + idx), action, TextAlign::Left, Some(to_rgb(INVENTORY_BACKGROUND).into()));
+        draw_batch.printer(Point::new(29, 7 + idx), action, TextAlign::Left, Some(theme.panel_bg()));
     }
 }
@@ -0,0 +1,284 @@
+/* weather.rs
+ *   The sky over the surface, it moves between clear, rain and fog over many turns. Rain makes fish bite more often
+ *   and fog closes in how far beings can see. Caves are sheltered from all of it.
+ * */
+
+use bracket_lib::{
+    random::RandomNumberGenerator,
+    terminal::{ColorPair, DrawBatch, Point, RGB, RGBA},
+};
+use serde::{Deserialize, Serialize};
+use specs::{Join, Read, System, World, WorldExt, Write, WriteExpect, WriteStorage};
+
+use crate::{
+    camera::get_camera_bounds,
+    char_c::CH_RAIN,
+    colors::{blend_over, Color},
+    components::Viewshed,
+    debug::CLEAR,
+    layers::MapLayers,
+    map::WHITE,
+    rng::GameRng,
+    time::IdleClock,
+    ui::message_log::MessageLog,
+    z_order::WEATHER_Z,
+};
+
+/// How far the world's colors move towards the weather's tint when it is at full strength
+const TINT_STRENGTH: f32 = 0.4;
+const RAIN_TINT: Color = (90, 110, 170);
+const FOG_TINT: Color = (150, 150, 150);
+const RAIN_COLOR: Color = (120, 150, 220);
+/// Tiles per second the slowest raindrop falls
+const RAIN_SPEED: f32 = 12.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeatherKind {
+    Clear,
+    Rain,
+    Fog,
+}
+
+impl WeatherKind {
+    fn arrives(&self) -> &str {
+        match self {
+            WeatherKind::Clear => "The sky clears up.",
+            WeatherKind::Rain => "It starts to rain.",
+            WeatherKind::Fog => "A fog rolls in.",
+        }
+    }
+}
+
+/// How the weather behaves and how strong its effects are, read from the settings when the game starts
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct WeatherConfig {
+    /// The sky stays clear when off
+    pub enabled: bool,
+    /// Fewest turns a kind of weather lasts once it has fully arrived
+    pub min_turns: usize,
+    /// Most turns a kind of weather lasts once it has fully arrived
+    pub max_turns: usize,
+    /// Turns it takes for one kind of weather to fade into the next
+    pub transition_turns: usize,
+    /// Added to every fishing roll in full rain, the same way bait potency is
+    pub rain_bite_bonus: usize,
+    /// Tiles taken off every view range in full fog
+    pub fog_view_reduction: usize,
+    /// Raindrops on screen in full rain
+    pub rain_drops: usize,
+}
+
+impl Default for WeatherConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_turns: 200,
+            max_turns: 500,
+            transition_turns: 20,
+            rain_bite_bonus: 15,
+            fog_view_reduction: 6,
+            rain_drops: 40,
+        }
+    }
+}
+
+/// The weather fading from `from` into `to`, once the fade is done `to` lasts for `turns_left`
+pub struct Weather {
+    cfg: WeatherConfig,
+    from: WeatherKind,
+    to: WeatherKind,
+    /// Turns since `to` started fading in
+    progress: usize,
+    turns_left: usize,
+}
+
+impl Default for Weather {
+    fn default() -> Self {
+        Self::new(WeatherConfig::default())
+    }
+}
+
+impl Weather {
+    /// Starts clear
+    pub fn new(cfg: WeatherConfig) -> Self {
+        Self {
+            from: WeatherKind::Clear,
+            to: WeatherKind::Clear,
+            progress: cfg.transition_turns,
+            turns_left: cfg.min_turns,
+            cfg,
+        }
+    }
+
+    /// A new world starts with clear skies again
+    pub fn reset(&mut self) {
+        *self = Self::new(self.cfg.clone());
+    }
+
+    /// How far the fade into `to` is, from 0.0 to 1.0
+    fn fade(&self) -> f32 {
+        if self.cfg.transition_turns == 0 {
+            return 1.0;
+        }
+        (self.progress as f32 / self.cfg.transition_turns as f32).min(1.0)
+    }
+
+    /// How much of `kind` is in the sky, from 0.0 to 1.0
+    pub fn strength(&self, kind: WeatherKind) -> f32 {
+        let fade = self.fade();
+        let mut strength = 0.0;
+        if self.from == kind {
+            strength += 1.0 - fade;
+        }
+        if self.to == kind {
+            strength += fade;
+        }
+        strength
+    }
+
+    /// Moves the weather on by a turn, returns the kind of weather that started to arrive if it changed
+    pub fn advance(&mut self, rng: &mut RandomNumberGenerator) -> Option<WeatherKind> {
+        if !self.cfg.enabled {
+            return None;
+        }
+        if self.progress < self.cfg.transition_turns {
+            self.progress += 1;
+            return None;
+        }
+        if self.turns_left > 0 {
+            self.turns_left -= 1;
+            return None;
+        }
+
+        self.from = self.to;
+        self.to = match self.from {
+            WeatherKind::Clear if rng.range(0, 2) == 0 => WeatherKind::Rain,
+            WeatherKind::Clear => WeatherKind::Fog,
+            WeatherKind::Rain | WeatherKind::Fog => WeatherKind::Clear,
+        };
+        self.progress = 0;
+        self.turns_left = rng.range(self.cfg.min_turns, self.cfg.max_turns.max(self.cfg.min_turns) + 1);
+        Some(self.to)
+    }
+
+    /// Added to fishing rolls, grows as the rain gets heavier
+    pub fn bite_bonus(&self) -> usize {
+        (self.cfg.rain_bite_bonus as f32 * self.strength(WeatherKind::Rain)).round() as usize
+    }
+
+    /// `range` shortened by the fog, nothing is ever blinded completely
+    pub fn view_range(&self, range: usize) -> usize {
+        let reduction = (self.cfg.fog_view_reduction as f32 * self.strength(WeatherKind::Fog)).round() as usize;
+        range.saturating_sub(reduction).max(1)
+    }
+
+    /// The color the world is drawn with, white on a clear day
+    pub fn tint(&self) -> RGBA {
+        let white = RGB::from(WHITE).to_rgba(1.0);
+        let rainy = RGB::from(RAIN_TINT).to_rgba(self.strength(WeatherKind::Rain) * TINT_STRENGTH);
+        let foggy = RGB::from(FOG_TINT).to_rgba(self.strength(WeatherKind::Fog) * TINT_STRENGTH);
+        blend_over(blend_over(white, rainy), foggy)
+    }
+
+    fn drops(&self) -> usize {
+        (self.cfg.rain_drops as f32 * self.strength(WeatherKind::Rain)).round() as usize
+    }
+}
+
+/// The weather only reaches the surface
+pub fn is_outdoors(layers: &MapLayers) -> bool {
+    layers.current == 0
+}
+
+/// Moves the weather along at the end of every turn, views are recalculated when the fog changes how far they reach
+pub struct WeatherSystem;
+
+impl<'a> System<'a> for WeatherSystem {
+    type SystemData = (
+        Write<'a, Weather>,
+        Write<'a, GameRng>,
+        WriteStorage<'a, Viewshed>,
+        WriteExpect<'a, MessageLog>,
+        Read<'a, MapLayers>,
+    );
+
+    fn run(&mut self, (mut weather, mut rng, mut viewsheds, mut log, layers): Self::SystemData) {
+        // any range works to see if the fog has thickened or thinned by a tile
+        let before = weather.view_range(usize::MAX);
+        let arrived = weather.advance(&mut rng.0);
+        if let Some(kind) = arrived.filter(|_| is_outdoors(&layers)) {
+            log.log(kind.arrives());
+        }
+        if weather.view_range(usize::MAX) != before {
+            for view in (&mut viewsheds).join() {
+                view.dirty = true;
+            }
+        }
+    }
+}
+
+/// Draws raindrops falling across the screen, each drop keeps to its own column and speed
+pub fn draw_rain(ecs: &World, draw_batch: &mut DrawBatch) {
+    if !is_outdoors(&ecs.read_resource::<MapLayers>()) {
+        return;
+    }
+    let drops = ecs.read_resource::<Weather>().drops();
+    if drops == 0 {
+        return;
+    }
+    let clock = ecs.read_resource::<IdleClock>().0.as_secs_f32();
+    let bounding_box = get_camera_bounds(ecs);
+    let (width, height) = (bounding_box.width().max(1) as u32, bounding_box.height().max(1) as f32);
+    let (r, g, b) = RAIN_COLOR;
+    let color = ColorPair { fg: RGBA::from_u8(r, g, b, 180), bg: CLEAR };
+
+    for drop in 0..drops as u32 {
+        let scatter = drop.wrapping_mul(2654435761);
+        let x = (scatter % width) as i32;
+        let speed = RAIN_SPEED + ((scatter >> 8) % 6) as f32;
+        let y = ((scatter >> 16) as f32 + clock * speed) % height;
+        draw_batch.set_with_z(Point::new(x, y as i32), color, CH_RAIN, WEATHER_Z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quick() -> WeatherConfig {
+        WeatherConfig { min_turns: 0, max_turns: 0, transition_turns: 4, ..Default::default() }
+    }
+
+    #[test]
+    fn weather_fades_in_over_the_transition() {
+        let mut weather = Weather::new(quick());
+        let mut rng = RandomNumberGenerator::seeded(3);
+        assert_eq!(weather.strength(WeatherKind::Clear), 1.0);
+
+        let arrived = weather.advance(&mut rng).expect("the clear sky lasts no turns");
+        assert_ne!(arrived, WeatherKind::Clear);
+        weather.advance(&mut rng);
+        weather.advance(&mut rng);
+        assert_eq!(weather.strength(arrived), 0.5);
+        assert_eq!(weather.strength(WeatherKind::Clear), 0.5);
+
+        weather.advance(&mut rng);
+        weather.advance(&mut rng);
+        assert_eq!(weather.strength(arrived), 1.0);
+    }
+
+    #[test]
+    fn fog_shortens_views_and_disabled_weather_stays_clear() {
+        let mut foggy = Weather::new(quick());
+        foggy.to = WeatherKind::Fog;
+        assert_eq!(foggy.view_range(16), 10);
+        assert_eq!(foggy.view_range(3), 1);
+        assert_eq!(foggy.bite_bonus(), 0);
+
+        let mut off = Weather::new(WeatherConfig { enabled: false, ..quick() });
+        let mut rng = RandomNumberGenerator::seeded(3);
+        assert_eq!(off.advance(&mut rng), None);
+        assert_eq!(off.strength(WeatherKind::Clear), 1.0);
+    }
+}
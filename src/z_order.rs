@@ -7,3 +7,7 @@ pub const ITEM_Z: u32 = 50;
 pub const WORLD_OBJECT_Z: u32 = 70;
 pub const BEING_Z: u32 = 90;
 pub const PLAYER_Z: u32 = 100;
+/// Rain falls in front of everything in the world
+pub const WEATHER_Z: u32 = 105;
+/// Indicators that pop up over beings
+pub const INDICATOR_Z: u32 = 110;